@@ -0,0 +1,115 @@
+//! provenance-exporter: Prometheus metrics for a set of matcher contexts.
+//!
+//! Polls every context in one RPC call per interval and serves the latest
+//! readings (see `provenance_cli::exporter`) in the Prometheus text format
+//! on `--listen`. A market that can't be read reports `provenance_up 0`
+//! instead of dropping out.
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use clap::Parser;
+use provenance_cli::{
+    exporter::{self, Poll, Reading},
+    keeper::{self, Market},
+    rpc::Rpc,
+    Result,
+};
+use provenance_client::MatcherContext;
+use solana_program::pubkey::Pubkey;
+
+#[derive(Parser)]
+#[command(name = "provenance-exporter", version, about = "Prometheus exporter for credibility matcher contexts")]
+struct Args {
+    /// RPC URL
+    #[arg(long, default_value = "https://api.devnet.solana.com")]
+    rpc: String,
+    /// Matcher program id; contexts owned by anything else are reported down
+    #[arg(long, env = "PROVENANCE_PROGRAM_ID")]
+    program: Option<Pubkey>,
+    /// Commitment level: processed, confirmed, finalized
+    #[arg(long, default_value = "confirmed")]
+    commitment: String,
+    /// JSON file listing the markets to export, as for provenance-keeper
+    #[arg(long)]
+    markets: PathBuf,
+    /// Address to serve metrics on
+    #[arg(long, default_value = "127.0.0.1:9101")]
+    listen: SocketAddr,
+    /// Seconds between polls
+    #[arg(long, default_value_t = 15)]
+    interval: u64,
+}
+
+fn main() {
+    if let Err(err) = run(Args::parse()) {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<()> {
+    let rpc = Rpc::new(&args.rpc, &args.commitment);
+    let markets = keeper::load_markets(&args.markets)?;
+    let page = Arc::new(Mutex::new(String::new()));
+    serve(args.listen, page.clone())?;
+    println!("exporting {} market(s) on http://{}/metrics", markets.len(), args.listen);
+
+    let mut poll = Poll::default();
+    let mut readings = Vec::new();
+    loop {
+        poll.polls += 1;
+        // A failed poll keeps the last readings; the error counter tells the story
+        match read(&rpc, args.program.as_ref(), &markets) {
+            Ok((slot, latest)) => (poll.slot, readings) = (slot, latest),
+            Err(err) => {
+                poll.errors += 1;
+                eprintln!("poll failed: {err}");
+            }
+        }
+        *page.lock().unwrap() = exporter::render(&markets, &readings, &poll);
+        thread::sleep(Duration::from_secs(args.interval));
+    }
+}
+
+/// The current slot and every market's context, in one call each.
+fn read(rpc: &Rpc, program: Option<&Pubkey>, markets: &[Market]) -> Result<(u64, Vec<Reading>)> {
+    let slot = rpc.slot()?;
+    let keys: Vec<Pubkey> = markets.iter().map(|m| m.context).collect();
+    let readings = rpc
+        .accounts(&keys)?
+        .into_iter()
+        .map(|account| {
+            let account = account.ok_or("context does not exist")?;
+            if program.is_some_and(|p| account.owner != *p) {
+                return Err(format!("context is owned by {}", account.owner));
+            }
+            MatcherContext::from_bytes(&account.data).map_err(|e| e.to_string())
+        })
+        .collect();
+    Ok((slot, readings))
+}
+
+/// Answer every request on `addr` with the latest metrics page.
+fn serve(addr: SocketAddr, page: Arc<Mutex<String>>) -> Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let _ = stream.set_read_timeout(Some(Duration::from_secs(1)));
+            let _ = stream.read(&mut [0u8; 1024]);
+            let body = page.lock().unwrap().clone();
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+        }
+    });
+    Ok(())
+}
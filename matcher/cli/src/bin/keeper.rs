@@ -0,0 +1,279 @@
+//! provenance-keeper: keep credibility snapshots fresh on a set of markets.
+//!
+//! Each round reads every context and slab in two RPC calls and sends
+//! UpdateCredibility for the markets whose snapshot is due (see
+//! `provenance_cli::keeper`), with a compute-unit price that follows recent
+//! fees on the written accounts and doubles on every retry. `--health` serves
+//! the keeper's state as JSON for liveness checks.
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use provenance_cli::{
+    keeper::{self, FeePolicy, Health, Market, SlabTotals, Thresholds},
+    load_wallet,
+    rpc::{Account, Rpc},
+    Result,
+};
+use provenance_client::{instruction as ix, MatcherContext};
+use solana_compute_budget_interface::ComputeBudgetInstruction;
+use solana_keypair::Keypair;
+use solana_program::pubkey::Pubkey;
+use solana_signer::Signer;
+
+#[derive(Parser)]
+#[command(name = "provenance-keeper", version, about = "Permissionless crank bot for the credibility matcher")]
+struct Args {
+    /// RPC URL
+    #[arg(long, default_value = "https://api.devnet.solana.com")]
+    rpc: String,
+    /// Keypair that pays for cranks
+    #[arg(long, default_value = "~/.config/solana/id.json")]
+    wallet: String,
+    /// Matcher program id
+    #[arg(long, env = "PROVENANCE_PROGRAM_ID")]
+    program: Pubkey,
+    /// Commitment level: processed, confirmed, finalized
+    #[arg(long, default_value = "confirmed")]
+    commitment: String,
+    /// JSON file listing the markets to watch
+    #[arg(long)]
+    markets: PathBuf,
+    /// Crank once the snapshot is this many slots old
+    #[arg(long, default_value_t = 150)]
+    max_age_slots: u64,
+    /// Crank early once insurance or open interest moves this many bps
+    #[arg(long, default_value_t = 500)]
+    move_bps: u64,
+    /// Seconds between rounds
+    #[arg(long, default_value_t = 10)]
+    interval: u64,
+    /// Priority fee in micro-lamports per compute unit (default: median of
+    /// recent fees on the context and slab)
+    #[arg(long)]
+    priority_fee: Option<u64>,
+    /// Highest priority fee to pay, retries included
+    #[arg(long, default_value_t = 100_000)]
+    max_priority_fee: u64,
+    /// Compute unit limit for each crank
+    #[arg(long, default_value_t = 50_000)]
+    compute_units: u32,
+    /// Sends per crank before the market counts as failing
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u32).range(1..))]
+    attempts: u32,
+    /// Serve health JSON over HTTP on this address, e.g. 127.0.0.1:9100
+    #[arg(long)]
+    health: Option<SocketAddr>,
+    /// Run a single round and exit, non-zero if any market failed
+    #[arg(long)]
+    once: bool,
+}
+
+struct Keeper {
+    rpc: Rpc,
+    program: Pubkey,
+    payer: Keypair,
+    markets: Vec<Market>,
+    thresholds: Thresholds,
+    fees: FeePolicy,
+    compute_units: u32,
+    attempts: u32,
+}
+
+fn main() {
+    if let Err(err) = run(Args::parse()) {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<()> {
+    let keeper = Keeper {
+        rpc: Rpc::new(&args.rpc, &args.commitment),
+        program: args.program,
+        payer: load_wallet(&args.wallet)?,
+        markets: keeper::load_markets(&args.markets)?,
+        thresholds: Thresholds { max_age_slots: args.max_age_slots, move_bps: args.move_bps },
+        fees: FeePolicy { fixed: args.priority_fee, max: args.max_priority_fee },
+        compute_units: args.compute_units,
+        attempts: args.attempts,
+    };
+    // A round can take a while when sends wait out their confirmation; the
+    // keeper only reports unhealthy once it has been quiet well past that.
+    let max_silence = args.interval * 3 + 60;
+    let health = Arc::new(Mutex::new(Health::new(&keeper.markets)));
+    if let Some(addr) = args.health {
+        serve_health(addr, health.clone(), max_silence)?;
+        println!("health on http://{addr}");
+    }
+    println!("watching {} market(s) with program {}", keeper.markets.len(), keeper.program);
+
+    loop {
+        if let Err(err) = keeper.round(&health) {
+            eprintln!("round failed: {err}");
+        }
+        if args.once {
+            let health = health.lock().unwrap();
+            return match health.markets.iter().filter(|m| m.failures > 0).count() {
+                0 if health.last_round.is_some() => Ok(()),
+                0 => Err("round did not complete".into()),
+                failing => Err(format!("{failing} market(s) failing").into()),
+            };
+        }
+        thread::sleep(Duration::from_secs(args.interval));
+    }
+}
+
+impl Keeper {
+    /// One pass over every market. An RPC failure that stops the whole round
+    /// is returned; a single market's failure lands in its health entry.
+    fn round(&self, health: &Mutex<Health>) -> Result<()> {
+        let slot = self.rpc.slot()?;
+        let keys: Vec<Pubkey> = self.markets.iter().map(|m| m.context).collect();
+        let contexts: Vec<Result<(MatcherContext, Pubkey)>> = self
+            .rpc
+            .accounts(&keys)?
+            .into_iter()
+            .zip(&self.markets)
+            .map(|(account, market)| self.resolve(market, account))
+            .collect();
+        let slab_keys: Vec<Pubkey> = contexts.iter().filter_map(|c| c.as_ref().ok().map(|(_, slab)| *slab)).collect();
+        let mut slabs = self.rpc.accounts(&slab_keys)?.into_iter();
+
+        let mut cranked = 0;
+        for (i, (market, context)) in self.markets.iter().zip(contexts).enumerate() {
+            let outcome = context.and_then(|(ctx, slab)| {
+                let account = slabs.next().flatten().ok_or_else(|| format!("slab {slab} does not exist"))?;
+                let totals = SlabTotals::read(&account.data).map_err(|e| format!("slab {slab}: {e}"))?;
+                match keeper::trigger(&ctx, &totals, slot, &self.thresholds) {
+                    Some(trigger) => Ok((ctx.snapshot_slot, Some((trigger, self.crank(market, &slab)?)))),
+                    None => Ok((ctx.snapshot_slot, None)),
+                }
+            });
+
+            let mut health = health.lock().unwrap();
+            let entry = &mut health.markets[i];
+            match outcome {
+                Ok((snapshot_slot, crank)) => {
+                    entry.snapshot_slot = snapshot_slot;
+                    entry.last_error = None;
+                    entry.failures = 0;
+                    if let Some((trigger, signature)) = crank {
+                        println!("slot {slot}: {}: {trigger}: {signature}", market.name);
+                        cranked += 1;
+                        entry.snapshot_slot = slot;
+                        entry.last_trigger = Some(trigger.to_string());
+                        entry.last_signature = Some(signature);
+                    }
+                }
+                Err(err) => {
+                    eprintln!("slot {slot}: {}: {err}", market.name);
+                    entry.last_error = Some(err.to_string());
+                    entry.failures += 1;
+                }
+            }
+        }
+
+        let mut health = health.lock().unwrap();
+        let failing = health.markets.iter().filter(|m| m.failures > 0).count();
+        println!("slot {slot}: {} market(s), {cranked} cranked, {failing} failing", self.markets.len());
+        health.rounds += 1;
+        health.last_round = Some(Instant::now());
+        health.last_slot = slot;
+        Ok(())
+    }
+
+    /// Decode a context and pick the slab to crank it against.
+    fn resolve(&self, market: &Market, account: Option<Account>) -> Result<(MatcherContext, Pubkey)> {
+        let account = account.ok_or_else(|| format!("context {} does not exist", market.context))?;
+        if account.owner != self.program {
+            return Err(format!("context {} is owned by {}, not {}", market.context, account.owner, self.program).into());
+        }
+        let ctx = MatcherContext::from_bytes(&account.data).map_err(|e| format!("context {}: {e}", market.context))?;
+        let bound = ctx.extension.map(|e| e.slab).filter(|k| *k != Pubkey::default());
+        if let (Some(configured), Some(bound)) = (market.slab, bound) {
+            if configured != bound {
+                return Err(format!("context is bound to slab {bound}, not {configured}").into());
+            }
+        }
+        let slab = market.slab.or(bound).ok_or("context has no bound slab; set \"slab\" in the markets file")?;
+        Ok((ctx, slab))
+    }
+
+    /// Send UpdateCredibility, raising the priority fee on each retry, with
+    /// the market's tip jar when it has one so the payer collects the tip.
+    fn crank(&self, market: &Market, slab: &Pubkey) -> Result<String> {
+        let ctx = &market.context;
+        let mut update = ix::update_credibility(&self.program, ctx, slab);
+        if let Some(tip_jar) = &market.tip_jar {
+            update = ix::with_tip_jar(update, tip_jar, &self.payer.pubkey())?;
+        }
+        let mut recent = match self.fees.fixed {
+            Some(_) => Vec::new(),
+            None => self.rpc.recent_prioritization_fees(&[*ctx, *slab]).unwrap_or_default(),
+        };
+        let mut last_err = None;
+        for attempt in 0..self.attempts {
+            let price = self.fees.price(&mut recent, attempt);
+            let instructions = [
+                ComputeBudgetInstruction::set_compute_unit_limit(self.compute_units),
+                ComputeBudgetInstruction::set_compute_unit_price(price),
+                update.clone(),
+            ];
+            match self.rpc.send(&instructions, &self.payer, &[]) {
+                Ok(signature) => return Ok(signature),
+                Err(err) => {
+                    eprintln!("  attempt {}/{} at {price} micro-lamports/CU: {err}", attempt + 1, self.attempts);
+                    last_err = Some(err);
+                }
+            }
+        }
+        Err(last_err.expect("at least one attempt"))
+    }
+}
+
+/// Answer every request on `addr` with the health JSON: 200 when healthy,
+/// 503 otherwise.
+fn serve_health(addr: SocketAddr, health: Arc<Mutex<Health>>, max_silence: u64) -> Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let _ = stream.set_read_timeout(Some(Duration::from_secs(1)));
+            let _ = stream.read(&mut [0u8; 1024]);
+            let (ok, body) = {
+                let health = health.lock().unwrap();
+                (health.is_ok(max_silence), health.to_json(max_silence).to_string())
+            };
+            let status = if ok { "200 OK" } else { "503 Service Unavailable" };
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_keeper_definition() {
+        Args::command().debug_assert();
+        let program = Pubkey::new_unique().to_string();
+        let args = ["provenance-keeper", "--program", &program, "--markets", "m.json"];
+        let parsed = Args::try_parse_from(args).unwrap();
+        assert_eq!((parsed.max_age_slots, parsed.attempts, parsed.priority_fee), (150, 3, None));
+        assert!(Args::try_parse_from(args.iter().chain(&["--attempts", "0"])).is_err());
+    }
+}
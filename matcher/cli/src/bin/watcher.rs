@@ -0,0 +1,88 @@
+//! provenance-watcher: stream change events for matcher contexts.
+//!
+//! Subscribes to each context over the RPC node's websocket and prints one
+//! line per event (see `provenance_cli::watcher`), or one JSON object per
+//! line with `--json`. Reconnects after a drop without re-reporting
+//! contexts it has already seen.
+
+use std::{path::PathBuf, time::Duration};
+
+use clap::Parser;
+use provenance_cli::{
+    keeper,
+    watcher::Watcher,
+    ws::ws_url,
+    Result,
+};
+use solana_program::pubkey::Pubkey;
+
+#[derive(Parser)]
+#[command(name = "provenance-watcher", version, about = "Stream change events for credibility matcher contexts")]
+struct Args {
+    /// Contexts to watch
+    contexts: Vec<Pubkey>,
+    /// RPC URL; the websocket endpoint is derived from it
+    #[arg(long, default_value = "https://api.devnet.solana.com")]
+    rpc: String,
+    /// Websocket URL, when it isn't the RPC URL's usual pubsub endpoint
+    #[arg(long)]
+    ws: Option<String>,
+    /// Commitment level: processed, confirmed, finalized
+    #[arg(long, default_value = "confirmed")]
+    commitment: String,
+    /// Also watch every context in this markets file, as for provenance-keeper
+    #[arg(long)]
+    markets: Option<PathBuf>,
+    /// Print events as JSON lines
+    #[arg(long)]
+    json: bool,
+    /// Seconds to wait before reconnecting
+    #[arg(long, default_value_t = 2)]
+    retry: u64,
+}
+
+fn main() {
+    if let Err(err) = run(Args::parse()) {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<()> {
+    let mut contexts = args.contexts;
+    if let Some(path) = &args.markets {
+        contexts.extend(keeper::load_markets(path)?.into_iter().map(|m| m.context));
+    }
+    if contexts.is_empty() {
+        return Err("no contexts to watch; pass them or --markets".into());
+    }
+    let url = match args.ws {
+        Some(url) => url,
+        None => ws_url(&args.rpc)?,
+    };
+    eprintln!("watching {} context(s) on {url}", contexts.len());
+
+    let events = Watcher::new(&url, &args.commitment, contexts).spawn(Duration::from_secs(args.retry));
+    for event in events {
+        match event {
+            Ok(event) if args.json => println!("{}", event.to_json()),
+            Ok(event) => println!("slot {}: {}: {}", event.slot, event.context, event.event),
+            Err(err) => eprintln!("{err}; reconnecting in {}s", args.retry),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_watcher_definition() {
+        Args::command().debug_assert();
+        let context = Pubkey::new_unique().to_string();
+        let parsed = Args::try_parse_from(["provenance-watcher", &context, "--json"]).unwrap();
+        assert_eq!((parsed.contexts.len(), parsed.json, parsed.ws), (1, true, None));
+    }
+}
@@ -0,0 +1,181 @@
+//! Prometheus metrics for `provenance-exporter`.
+//!
+//! Everything is read from the context account alone: the coverage ratio and
+//! tier the last snapshot prices at, how old that snapshot and the
+//! percolator crank behind it are, how much of the inventory cap is used, and
+//! what the last fill paid over the oracle. Alert on these and a market's
+//! credibility state can't degrade unnoticed.
+
+use std::fmt::Write;
+
+use provenance_client::{layout::CTX_FLAG_REDUCE_ONLY, MatcherContext};
+use provenance_core::{coverage_bps, coverage_tier, BPS};
+
+use crate::keeper::Market;
+
+/// One market's context as of a poll: decoded, or why it couldn't be.
+pub type Reading = std::result::Result<MatcherContext, String>;
+
+/// Poll-wide figures.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Poll {
+    pub slot: u64,
+    pub polls: u64,
+    pub errors: u64,
+}
+
+struct Metric {
+    name: &'static str,
+    help: &'static str,
+    value: fn(&MatcherContext, u64) -> Option<f64>,
+}
+
+const METRICS: &[Metric] = &[
+    Metric {
+        name: "provenance_coverage_bps",
+        help: "Insurance over open interest at the last snapshot, in bps",
+        value: |c, _| Some(coverage_bps(c.insurance_snapshot, c.total_oi_snapshot) as f64),
+    },
+    Metric {
+        name: "provenance_coverage_tier",
+        help: "Coverage tier the snapshot prices at: 0 CRITICAL .. 4 FORTIFIED",
+        value: |c, _| Some(coverage_tier(coverage_bps(c.insurance_snapshot, c.total_oi_snapshot)) as f64),
+    },
+    Metric {
+        name: "provenance_snapshot_age_slots",
+        help: "Slots since UpdateCredibility last ran; absent before the first snapshot",
+        value: |c, slot| (c.snapshot_slot > 0).then(|| slot.saturating_sub(c.snapshot_slot) as f64),
+    },
+    Metric {
+        name: "provenance_crank_age_slots",
+        help: "Slots since the percolator crank the last snapshot saw",
+        value: |c, slot| (c.last_crank_slot > 0).then(|| slot.saturating_sub(c.last_crank_slot) as f64),
+    },
+    Metric {
+        name: "provenance_inventory",
+        help: "Matcher inventory in base units",
+        value: |c, _| Some(c.inventory as f64),
+    },
+    Metric {
+        name: "provenance_inventory_utilization_bps",
+        help: "Absolute inventory over max_inventory, in bps; absent when uncapped",
+        value: |c, _| {
+            let cap = c.params.max_inventory;
+            (cap > 0).then(|| (c.inventory.unsigned_abs().saturating_mul(BPS as u128) / cap) as f64)
+        },
+    },
+    Metric {
+        name: "provenance_last_spread_bps",
+        help: "What the last fill paid over the oracle, fee and skew included, in bps",
+        value: |c, _| {
+            let oracle = c.last_oracle_price_e6;
+            (oracle > 0).then(|| c.last_exec_price_e6.abs_diff(oracle) as f64 * BPS as f64 / oracle as f64)
+        },
+    },
+    Metric {
+        name: "provenance_liq_heat",
+        help: "Liquidation heat at the last snapshot, 1000 per liquidation",
+        value: |c, _| Some(c.liq_heat as f64),
+    },
+    Metric {
+        name: "provenance_paused",
+        help: "1 while the LP has paused matching",
+        value: |c, _| Some(c.is_paused() as u8 as f64),
+    },
+    Metric {
+        name: "provenance_reduce_only",
+        help: "1 while the LP has set reduce-only",
+        value: |c, _| Some((c.flags & CTX_FLAG_REDUCE_ONLY != 0) as u8 as f64),
+    },
+    Metric {
+        name: "provenance_fills_total",
+        help: "Fills since init (v6 contexts)",
+        value: |c, _| c.extension.map(|x| x.fill_seq as f64),
+    },
+];
+
+/// Render every market in the Prometheus text format.
+pub fn render(markets: &[Market], readings: &[Reading], poll: &Poll) -> String {
+    let mut out = String::new();
+    let labels: Vec<String> = markets
+        .iter()
+        .map(|m| format!("market=\"{}\",context=\"{}\"", escape(&m.name), m.context))
+        .collect();
+
+    header(&mut out, "provenance_up", "1 when the context was read and decoded on the last poll");
+    for (labels, reading) in labels.iter().zip(readings) {
+        let _ = writeln!(out, "provenance_up{{{labels}}} {}", reading.is_ok() as u8);
+    }
+    for metric in METRICS {
+        header(&mut out, metric.name, metric.help);
+        for (labels, reading) in labels.iter().zip(readings) {
+            if let Some(value) = reading.as_ref().ok().and_then(|c| (metric.value)(c, poll.slot)) {
+                let _ = writeln!(out, "{}{{{labels}}} {value}", metric.name);
+            }
+        }
+    }
+
+    header(&mut out, "provenance_poll_slot", "Slot of the last successful poll");
+    let _ = writeln!(out, "provenance_poll_slot {}", poll.slot);
+    let _ = writeln!(out, "# HELP provenance_polls_total Polls attempted\n# TYPE provenance_polls_total counter");
+    let _ = writeln!(out, "provenance_polls_total {}", poll.polls);
+    let _ = writeln!(out, "# HELP provenance_poll_errors_total Polls that failed outright\n# TYPE provenance_poll_errors_total counter");
+    let _ = writeln!(out, "provenance_poll_errors_total {}", poll.errors);
+    out
+}
+
+fn header(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} gauge");
+}
+
+/// Label values escape backslash, double quote and newline.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use provenance_client::MatcherParams;
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_render() {
+        let context = Pubkey::new_unique();
+        let markets = [
+            Market { name: "SOL \"perp\"".into(), context, slab: None, tip_jar: None },
+            Market { name: "gone".into(), context, slab: None, tip_jar: None },
+        ];
+        let ctx = MatcherContext {
+            params: MatcherParams { max_inventory: 1_000, ..Default::default() },
+            inventory: -250,
+            insurance_snapshot: 30,
+            total_oi_snapshot: 100,
+            snapshot_slot: 900,
+            last_oracle_price_e6: 100_000_000,
+            last_exec_price_e6: 100_150_000,
+            ..Default::default()
+        };
+        let poll = Poll { slot: 1_000, polls: 3, errors: 1 };
+        let text = render(&markets, &[Ok(ctx), Err("missing".into())], &poll);
+
+        let labels = format!("market=\"SOL \\\"perp\\\"\",context=\"{context}\"");
+        for line in [
+            format!("provenance_up{{{labels}}} 1"),
+            format!("provenance_up{{market=\"gone\",context=\"{context}\"}} 0"),
+            format!("provenance_coverage_bps{{{labels}}} 3000"),
+            format!("provenance_coverage_tier{{{labels}}} 2"),
+            format!("provenance_snapshot_age_slots{{{labels}}} 100"),
+            format!("provenance_inventory{{{labels}}} -250"),
+            format!("provenance_inventory_utilization_bps{{{labels}}} 2500"),
+            format!("provenance_last_spread_bps{{{labels}}} 15"),
+            "provenance_poll_errors_total 1".into(),
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {line:?} in\n{text}");
+        }
+        // Never cranked, v5: no crank age, no fill count
+        assert!(!text.contains("provenance_crank_age_slots{"));
+        assert!(!text.contains("provenance_fills_total{"));
+        assert_eq!(text.matches("# TYPE provenance_coverage_bps gauge").count(), 1);
+    }
+}
@@ -0,0 +1,285 @@
+//! When `provenance-keeper` cranks, and what it reports.
+//!
+//! UpdateCredibility is permissionless, so any number of keepers can watch the
+//! same markets. Each one refreshes a snapshot when it is older than a slot
+//! budget or when the slab's insurance fund or open interest has moved far
+//! enough from it that quotes are priced off stale coverage.
+
+use std::{fmt, fs, path::Path, time::Instant};
+
+use provenance_client::{layout::*, MatcherContext};
+use serde_json::{json, Value};
+use solana_program::pubkey::Pubkey;
+
+use crate::Result;
+
+// Percolator slab fields, as UpdateCredibility reads them
+const SLAB_INSURANCE_OFF: usize = SLAB_ENGINE_OFF + ENGINE_INSURANCE_OFF;
+const SLAB_TOTAL_OI_OFF: usize = SLAB_ENGINE_OFF + ENGINE_TOTAL_OI_OFF;
+
+const BPS: u128 = 10_000;
+
+/// A context to keep fresh.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Market {
+    pub name: String,
+    pub context: Pubkey,
+    /// Slab to crank against; `None` uses the context's bound slab.
+    pub slab: Option<Pubkey>,
+    /// The context's tip jar, to collect the keeper tip from.
+    pub tip_jar: Option<Pubkey>,
+}
+
+/// Read a markets file: a JSON array (or `{ "markets": [...] }`) of
+/// `{ "name", "context", "slab", "tip_jar" }` objects, where only `context`
+/// is required.
+pub fn load_markets(path: &Path) -> Result<Vec<Market>> {
+    let json: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let list = json.get("markets").unwrap_or(&json);
+    let entries = list.as_array().ok_or_else(|| format!("{}: expected an array of markets", path.display()))?;
+    let mut markets = Vec::with_capacity(entries.len());
+    for (i, entry) in entries.iter().enumerate() {
+        let key = |field: &str| -> Result<Option<Pubkey>> {
+            match entry.get(field).and_then(Value::as_str) {
+                Some(key) => Ok(Some(key.parse().map_err(|_| format!("{}: market {i}: bad {field}", path.display()))?)),
+                None => Ok(None),
+            }
+        };
+        let context = key("context")?.ok_or_else(|| format!("{}: market {i}: no context", path.display()))?;
+        let name = entry.get("name").and_then(Value::as_str).map_or_else(|| context.to_string(), str::to_string);
+        markets.push(Market { name, context, slab: key("slab")?, tip_jar: key("tip_jar")? });
+    }
+    if markets.is_empty() {
+        return Err(format!("{}: no markets", path.display()).into());
+    }
+    Ok(markets)
+}
+
+/// The slab totals a snapshot records.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SlabTotals {
+    pub insurance: u128,
+    pub total_oi: u128,
+}
+
+impl SlabTotals {
+    pub fn read(data: &[u8]) -> Result<Self> {
+        if data.len() < SLAB_MIN_LEN {
+            return Err(format!("slab is {} bytes, expected at least {SLAB_MIN_LEN}", data.len()).into());
+        }
+        let u128_at = |off: usize| u128::from_le_bytes(data[off..off + 16].try_into().unwrap());
+        Ok(Self { insurance: u128_at(SLAB_INSURANCE_OFF), total_oi: u128_at(SLAB_TOTAL_OI_OFF) })
+    }
+}
+
+/// When a snapshot is due.
+#[derive(Clone, Copy, Debug)]
+pub struct Thresholds {
+    /// Refresh a snapshot at least this often.
+    pub max_age_slots: u64,
+    /// Refresh early when insurance or open interest moves this much.
+    pub move_bps: u64,
+}
+
+/// Why a snapshot is due.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Trigger {
+    /// The context has never been cranked.
+    First,
+    Stale { age: u64 },
+    Insurance { moved_bps: u64 },
+    OpenInterest { moved_bps: u64 },
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Trigger::First => write!(f, "first snapshot"),
+            Trigger::Stale { age } => write!(f, "snapshot {age} slots old"),
+            Trigger::Insurance { moved_bps } => write!(f, "insurance moved {moved_bps}bps"),
+            Trigger::OpenInterest { moved_bps } => write!(f, "open interest moved {moved_bps}bps"),
+        }
+    }
+}
+
+/// Whether `ctx` needs a fresh snapshot of `slab` at `slot`.
+pub fn trigger(ctx: &MatcherContext, slab: &SlabTotals, slot: u64, t: &Thresholds) -> Option<Trigger> {
+    if ctx.snapshot_slot == 0 {
+        return Some(Trigger::First);
+    }
+    let age = slot.saturating_sub(ctx.snapshot_slot);
+    if age >= t.max_age_slots {
+        return Some(Trigger::Stale { age });
+    }
+    let moved = moved_bps(ctx.insurance_snapshot, slab.insurance);
+    if moved >= t.move_bps {
+        return Some(Trigger::Insurance { moved_bps: moved });
+    }
+    let moved = moved_bps(ctx.total_oi_snapshot, slab.total_oi);
+    if moved >= t.move_bps {
+        return Some(Trigger::OpenInterest { moved_bps: moved });
+    }
+    None
+}
+
+/// Relative change from `old` to `new` in bps; anything from zero is `u64::MAX`.
+pub fn moved_bps(old: u128, new: u128) -> u64 {
+    let diff = old.abs_diff(new);
+    if diff == 0 {
+        return 0;
+    }
+    diff.saturating_mul(BPS).checked_div(old).map_or(u64::MAX, |bps| bps.min(u64::MAX as u128) as u64)
+}
+
+/// How a keeper bids for block space.
+#[derive(Clone, Copy, Debug)]
+pub struct FeePolicy {
+    /// Fixed price in micro-lamports per compute unit, or `None` to follow
+    /// recent fees on the accounts being written.
+    pub fixed: Option<u64>,
+    /// Never bid more than this, retries included.
+    pub max: u64,
+}
+
+impl FeePolicy {
+    /// Price for attempt `attempt` (0-based): the base price — fixed, or the
+    /// median of `recent` — doubled on every retry, capped at `max`.
+    pub fn price(&self, recent: &mut [u64], attempt: u32) -> u64 {
+        let base = self.fixed.unwrap_or_else(|| {
+            recent.sort_unstable();
+            recent.get(recent.len() / 2).copied().unwrap_or(0)
+        });
+        // A zero base still escalates, so a retry outbids an idle fee market
+        let base = if attempt > 0 { base.max(1) } else { base };
+        base.saturating_mul(1u64.checked_shl(attempt).unwrap_or(u64::MAX)).min(self.max)
+    }
+}
+
+/// What the keeper last did for one market.
+#[derive(Clone, Debug, Default)]
+pub struct MarketHealth {
+    pub name: String,
+    pub context: Pubkey,
+    pub snapshot_slot: u64,
+    pub last_trigger: Option<String>,
+    pub last_signature: Option<String>,
+    pub last_error: Option<String>,
+    /// Rounds in a row that ended in an error for this market.
+    pub failures: u32,
+}
+
+/// The keeper's state, as its health endpoint reports it.
+#[derive(Debug)]
+pub struct Health {
+    started: Instant,
+    pub rounds: u64,
+    pub last_round: Option<Instant>,
+    pub last_slot: u64,
+    pub markets: Vec<MarketHealth>,
+}
+
+impl Health {
+    pub fn new(markets: &[Market]) -> Self {
+        Self {
+            started: Instant::now(),
+            rounds: 0,
+            last_round: None,
+            last_slot: 0,
+            markets: markets
+                .iter()
+                .map(|m| MarketHealth { name: m.name.clone(), context: m.context, ..Default::default() })
+                .collect(),
+        }
+    }
+
+    /// Healthy when a round finished within `max_silence_secs` and no market's
+    /// last round failed.
+    pub fn is_ok(&self, max_silence_secs: u64) -> bool {
+        let recent = self.last_round.is_some_and(|t| t.elapsed().as_secs() <= max_silence_secs);
+        recent && self.markets.iter().all(|m| m.failures == 0)
+    }
+
+    pub fn to_json(&self, max_silence_secs: u64) -> Value {
+        json!({
+            "ok": self.is_ok(max_silence_secs),
+            "uptime_secs": self.started.elapsed().as_secs(),
+            "rounds": self.rounds,
+            "last_round_secs_ago": self.last_round.map(|t| t.elapsed().as_secs()),
+            "last_slot": self.last_slot,
+            "markets": self.markets.iter().map(|m| json!({
+                "name": m.name,
+                "context": m.context.to_string(),
+                "snapshot_slot": m.snapshot_slot,
+                "last_trigger": m.last_trigger,
+                "last_signature": m.last_signature,
+                "last_error": m.last_error,
+                "failures": m.failures,
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(snapshot_slot: u64, insurance: u128, total_oi: u128) -> MatcherContext {
+        MatcherContext {
+            snapshot_slot,
+            insurance_snapshot: insurance,
+            total_oi_snapshot: total_oi,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_trigger() {
+        let t = Thresholds { max_age_slots: 150, move_bps: 500 };
+        let slab = SlabTotals { insurance: 1_000_000, total_oi: 2_000_000 };
+
+        assert_eq!(trigger(&context(0, 0, 0), &slab, 10, &t), Some(Trigger::First));
+        assert_eq!(trigger(&context(100, 1_000_000, 2_000_000), &slab, 249, &t), None);
+        assert_eq!(trigger(&context(100, 1_000_000, 2_000_000), &slab, 250, &t), Some(Trigger::Stale { age: 150 }));
+        // 1.06M -> 1.0M is 566bps of the snapshot; OI within 4.8%
+        assert_eq!(
+            trigger(&context(100, 1_060_000, 2_000_000), &slab, 101, &t),
+            Some(Trigger::Insurance { moved_bps: 566 })
+        );
+        assert_eq!(trigger(&context(100, 1_000_000, 2_096_000), &slab, 101, &t), None);
+        assert_eq!(
+            trigger(&context(100, 1_000_000, 0), &slab, 101, &t),
+            Some(Trigger::OpenInterest { moved_bps: u64::MAX })
+        );
+    }
+
+    #[test]
+    fn test_fee_policy() {
+        let auto = FeePolicy { fixed: None, max: 50_000 };
+        assert_eq!(auto.price(&mut [300, 100, 200], 0), 200);
+        assert_eq!(auto.price(&mut [300, 100, 200], 2), 800);
+        assert_eq!(auto.price(&mut [], 0), 0);
+        assert_eq!(auto.price(&mut [], 3), 8);
+        assert_eq!(auto.price(&mut [40_000], 1), 50_000);
+
+        let fixed = FeePolicy { fixed: Some(1_000), max: u64::MAX };
+        assert_eq!(fixed.price(&mut [1, 2, 3], 1), 2_000);
+        assert_eq!(fixed.price(&mut [], 80), u64::MAX);
+    }
+
+    #[test]
+    fn test_load_markets() {
+        let path = std::env::temp_dir().join(format!("provenance-keeper-markets-{}.json", std::process::id()));
+        let (ctx, slab) = (Pubkey::new_unique(), Pubkey::new_unique());
+        fs::write(&path, format!(r#"{{ "markets": [{{ "name": "SOL-PERP", "context": "{ctx}", "slab": "{slab}", "tip_jar": "{ctx}" }}, {{ "context": "{ctx}" }}] }}"#))
+            .unwrap();
+        let markets = load_markets(&path);
+        fs::write(&path, r#"[{ "name": "no context" }]"#).unwrap();
+        let missing = load_markets(&path);
+        fs::remove_file(&path).unwrap();
+
+        let markets = markets.unwrap();
+        assert_eq!(markets[0], Market { name: "SOL-PERP".into(), context: ctx, slab: Some(slab), tip_jar: Some(ctx) });
+        assert_eq!(markets[1], Market { name: ctx.to_string(), context: ctx, slab: None, tip_jar: None });
+        assert!(missing.is_err());
+    }
+}
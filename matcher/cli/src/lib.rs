@@ -0,0 +1,28 @@
+//! Shared plumbing for the matcher's operator binaries: `provenance-cli`, the
+//! `provenance-keeper` crank bot, the `provenance-exporter` metrics daemon,
+//! the `provenance-watcher` event stream, the `provenance-sim` quote
+//! simulator and the `provenance-backtest` replay harness.
+
+pub mod backtest;
+pub mod exporter;
+pub mod keeper;
+pub mod params;
+pub mod rpc;
+pub mod sim;
+pub mod watcher;
+pub mod ws;
+
+use std::{error::Error, path::PathBuf};
+
+use solana_keypair::{read_keypair_file, Keypair};
+
+pub type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+/// Read a keypair file, expanding a leading `~/`.
+pub fn load_wallet(path: &str) -> Result<Keypair> {
+    let path = match path.strip_prefix("~/") {
+        Some(rest) => PathBuf::from(std::env::var("HOME")?).join(rest),
+        None => PathBuf::from(path),
+    };
+    read_keypair_file(&path).map_err(|e| format!("wallet {}: {e}", path.display()).into())
+}
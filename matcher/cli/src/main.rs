@@ -0,0 +1,512 @@
+//! provenance-cli: operate a credibility matcher context from the command line.
+//!
+//! Every command builds its instructions with `provenance-client`, so it
+//! tracks the program's layout without hand-rolled bytes. Administrative
+//! commands sign with `--wallet` as the context authority.
+
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use provenance_cli::{load_wallet, params, rpc::Rpc, Result};
+use provenance_client::{instruction as ix, layout::*, MatcherContext, MatcherParams};
+use solana_keypair::Keypair;
+use solana_program::pubkey::Pubkey;
+use solana_signer::Signer;
+use solana_system_interface::instruction::create_account;
+
+#[derive(Parser)]
+#[command(name = "provenance-cli", version, about = "Operator CLI for the credibility matcher")]
+struct Cli {
+    /// RPC URL
+    #[arg(long, global = true, default_value = "https://api.devnet.solana.com")]
+    rpc: String,
+    /// Keypair that pays and, for admin commands, signs as authority
+    #[arg(long, global = true, default_value = "~/.config/solana/id.json")]
+    wallet: String,
+    /// Matcher program id
+    #[arg(long, global = true, env = "PROVENANCE_PROGRAM_ID")]
+    program: Option<Pubkey>,
+    /// Commitment level: processed, confirmed, finalized
+    #[arg(long, global = true, default_value = "confirmed")]
+    commitment: String,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a context account and initialize it, with the wallet as its
+    /// authority: Init needs that signature, as the LP PDA signs only through
+    /// its own program
+    Init {
+        /// LP PDA that will sign matches
+        #[arg(long)]
+        lp: Pubkey,
+        /// Bind the context to this slab
+        #[arg(long)]
+        slab: Pubkey,
+        /// JSON object of MatcherParams fields
+        #[arg(long)]
+        params: Option<PathBuf>,
+        /// Override a parameter, e.g. --set max_spread_bps=200
+        #[arg(long = "set", value_name = "FIELD=VALUE")]
+        sets: Vec<String>,
+        /// Price imbalance-only (the plain kind) instead of by credibility
+        #[arg(long)]
+        plain: bool,
+        /// Price by the blended signals (the multi kind); set signal_weights
+        #[arg(long, conflicts_with = "plain")]
+        multi: bool,
+        /// Price around a 1.0 peg (the stable kind); set peg_band_e6
+        #[arg(long, conflicts_with_all = ["plain", "multi"])]
+        stable: bool,
+        /// Create a shared context with a market table; --slab becomes its
+        /// first market, SetMarket lists more
+        #[arg(long)]
+        shared: bool,
+    },
+    /// Change parameters: instantly before the first fill, through the
+    /// timelock (ProposeParams) after it
+    UpdateParams {
+        ctx: Pubkey,
+        /// JSON object of MatcherParams fields to change
+        #[arg(long)]
+        params: Option<PathBuf>,
+        /// Override a parameter, e.g. --set max_spread_bps=200
+        #[arg(long = "set", value_name = "FIELD=VALUE")]
+        sets: Vec<String>,
+        /// Cancel the pending proposal instead
+        #[arg(long, conflicts_with_all = ["params", "sets"])]
+        cancel: bool,
+    },
+    /// Refresh the credibility snapshot, and commit a proposal that is due
+    Crank {
+        ctx: Pubkey,
+        /// Slab to read (default: the bound slab)
+        #[arg(long)]
+        slab: Option<Pubkey>,
+    },
+    /// Decode and print a context account
+    Inspect { ctx: Pubkey },
+    /// Simulate QuotePreview for a signed size (positive = taker buys)
+    Quote {
+        #[arg(allow_negative_numbers = true)]
+        size: i128,
+        #[arg(long)]
+        ctx: Pubkey,
+        /// Oracle price, 1e6 fixed point
+        #[arg(long)]
+        oracle: u64,
+        /// Market-table entry to quote, on a shared context
+        #[arg(long)]
+        market: Option<u16>,
+        /// Signal account to price in (repeatable)
+        #[arg(long = "signal")]
+        signals: Vec<Pubkey>,
+        /// The context's spread schedule (required once it has one)
+        #[arg(long)]
+        schedule: Option<Pubkey>,
+    },
+    /// List a slab in a shared context's market table, or change its caps
+    SetMarket {
+        ctx: Pubkey,
+        slab: Pubkey,
+        /// Per-market max_fill (0 = the context's)
+        #[arg(long, default_value_t = 0)]
+        max_fill: u128,
+        /// Per-market max_inventory (0 = the context's)
+        #[arg(long, default_value_t = 0)]
+        max_inventory: u128,
+    },
+    /// Set the key whose signed quotes PostRfq accepts; without one, turn
+    /// RFQ off. Either way the posted quote is withdrawn
+    RfqSigner {
+        ctx: Pubkey,
+        signer: Option<Pubkey>,
+    },
+    /// Sign a quote with the RFQ signer's keypair and post it
+    PostRfq {
+        ctx: Pubkey,
+        /// Keypair of the context's RFQ signer
+        #[arg(long)]
+        signer: String,
+        /// Price, 1e6 fixed point
+        #[arg(long)]
+        price: u64,
+        /// Signed size (positive = takers may buy)
+        #[arg(long, allow_hyphen_values = true)]
+        size: i128,
+        /// Slots from now the quote stays fillable
+        #[arg(long, default_value_t = 150)]
+        slots: u64,
+    },
+    /// Close the context (and its schedule, feed, stats and ladder accounts),
+    /// returning the rent
+    Close {
+        ctx: Pubkey,
+        /// Rent destination (default: the wallet)
+        #[arg(long)]
+        destination: Option<Pubkey>,
+        /// The context's spread schedule (required once it has one)
+        #[arg(long)]
+        schedule: Option<Pubkey>,
+        /// The context's oracle feed account (required once it has one)
+        #[arg(long)]
+        feed: Option<Pubkey>,
+        /// A ladder account bound to the context, to reclaim with it
+        #[arg(long)]
+        ladder: Option<Pubkey>,
+    },
+}
+
+fn main() {
+    if let Err(err) = run(Cli::parse()) {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let rpc = Rpc::new(&cli.rpc, &cli.commitment);
+    let program = cli.program.ok_or("pass --program or set PROVENANCE_PROGRAM_ID")?;
+    let wallet = || load_wallet(&cli.wallet);
+    match cli.command {
+        Command::Init { lp, slab, params, sets, plain, multi, stable, shared } => {
+            let mut block = MatcherParams::default();
+            params::apply(&mut block, params.as_deref(), &sets)?;
+            let payer = wallet()?;
+            let ctx = Keypair::new();
+            let len = if shared { CTX_SHARED_LEN } else { CTX_EXT_LEN };
+            let create = create_account(
+                &payer.pubkey(),
+                &ctx.pubkey(),
+                rpc.rent_exempt_minimum(len)?,
+                len as u64,
+                &program,
+            );
+            let kind = match (plain, multi, stable) {
+                (true, _, _) => KIND_PLAIN,
+                (_, true, _) => KIND_MULTI,
+                (_, _, true) => KIND_STABLE,
+                _ => KIND_CREDIBILITY,
+            };
+            let init = ix::init_with_kind(&program, &lp, &ctx.pubkey(), Some(&slab), Some(&payer.pubkey()), kind, &block)?;
+            let signature = rpc.send(&[create, init], &payer, &[&ctx])?;
+            println!("context: {}", ctx.pubkey());
+            println!("signature: {signature}");
+        }
+        Command::UpdateParams { ctx, params, sets, cancel } => {
+            let state = load_context(&rpc, &program, &ctx)?;
+            let payer = wallet()?;
+            check_authority(&state, &payer)?;
+            let instruction = if cancel {
+                ix::cancel_params(&program, &payer.pubkey(), &ctx)
+            } else {
+                let mut block = state.params;
+                params::apply(&mut block, params.as_deref(), &sets)?;
+                // The program refuses instant changes once the market has traded
+                if state.last_oracle_price_e6 == 0 {
+                    ix::update_params(&program, &payer.pubkey(), &ctx, &block)
+                } else {
+                    println!("market is live: proposing behind the timelock");
+                    ix::propose_params(&program, &payer.pubkey(), &ctx, &block)
+                }
+            };
+            println!("signature: {}", rpc.send(&[instruction], &payer, &[])?);
+            if let Some(ext) = load_context(&rpc, &program, &ctx)?.extension {
+                if ext.pending_activation_slot != 0 {
+                    println!("committable from slot {}", ext.pending_activation_slot);
+                }
+            }
+        }
+        Command::Crank { ctx, slab } => {
+            let state = load_context(&rpc, &program, &ctx)?;
+            let ext = state.extension.unwrap_or_default();
+            let slab = slab
+                .or(Some(ext.slab).filter(|k| *k != Pubkey::default()))
+                .ok_or("context has no bound slab; pass --slab")?;
+            let mut instructions = vec![ix::update_credibility(&program, &ctx, &slab)];
+            // CommitParams is permissionless; a due proposal rides along
+            if ext.pending_activation_slot != 0 && rpc.slot()? >= ext.pending_activation_slot {
+                println!("committing the pending proposal");
+                instructions.push(ix::commit_params(&program, &ctx));
+            }
+            println!("signature: {}", rpc.send(&instructions, &wallet()?, &[])?);
+        }
+        Command::Inspect { ctx } => {
+            let state = load_context(&rpc, &program, &ctx)?;
+            print_context(&ctx, &state, rpc.slot()?);
+        }
+        Command::Quote { size, ctx, oracle, market, signals, schedule } => {
+            let state = load_context(&rpc, &program, &ctx)?;
+            let stats = state.extension.map(|e| e.stats).filter(|k| *k != Pubkey::default());
+            let mut preview = ix::quote_preview(&program, &ctx, stats.as_ref(), oracle, size);
+            if let Some(index) = market {
+                preview = ix::on_market(preview, index);
+            }
+            if let Some(schedule) = scheduled(&state, schedule)? {
+                preview = ix::with_schedule(preview, &schedule)?;
+            }
+            let preview = ix::with_signals(preview, &signals)?;
+            let sim = rpc.simulate(&[preview], &wallet()?.pubkey())?;
+            if let Some(err) = sim.err {
+                let logs: String = sim.logs.iter().map(|l| format!("\n  {l}")).collect();
+                return Err(format!("no quote: {err}{logs}").into());
+            }
+            let data = sim.return_data.ok_or("QuotePreview returned no data")?;
+            print_quote(&data)?;
+        }
+        Command::RfqSigner { ctx, signer } => {
+            let state = load_context(&rpc, &program, &ctx)?;
+            let payer = wallet()?;
+            check_authority(&state, &payer)?;
+            let set = ix::set_rfq_signer(&program, &payer.pubkey(), &ctx, &signer.unwrap_or_default());
+            println!("signature: {}", rpc.send(&[set], &payer, &[])?);
+        }
+        Command::SetMarket { ctx, slab, max_fill, max_inventory } => {
+            let state = load_context(&rpc, &program, &ctx)?;
+            if state.markets.is_none() {
+                return Err(format!("context {ctx} has no market table; init it with --shared").into());
+            }
+            let payer = wallet()?;
+            check_authority(&state, &payer)?;
+            let set = ix::set_market(&program, &payer.pubkey(), &ctx, &slab, max_fill, max_inventory);
+            println!("signature: {}", rpc.send(&[set], &payer, &[])?);
+        }
+        Command::PostRfq { ctx, signer, price, size, slots } => {
+            let ext = load_context(&rpc, &program, &ctx)?.extension.ok_or("RFQ needs a v6 context")?;
+            let signer = load_wallet(&signer)?;
+            if signer.pubkey() != ext.rfq_signer {
+                return Err(format!("{} is not the context's RFQ signer {}", signer.pubkey(), ext.rfq_signer).into());
+            }
+            let quote = ix::RfqQuote {
+                ctx,
+                price_e6: price,
+                size,
+                expiry_slot: rpc.slot()? + slots,
+                nonce: ext.rfq_nonce + 1,
+            };
+            let signature: [u8; 64] = signer.sign_message(&quote.message()).as_ref().try_into()?;
+            let verify = ix::rfq_signature(&signer.pubkey(), &signature, &quote);
+            let post = ix::post_rfq(&program, &quote);
+            println!("signature: {}", rpc.send(&[verify, post], &wallet()?, &[])?);
+            println!("nonce {} fillable through slot {}", quote.nonce, quote.expiry_slot);
+        }
+        Command::Close { ctx, destination, schedule, feed, ladder } => {
+            let state = load_context(&rpc, &program, &ctx)?;
+            let payer = wallet()?;
+            check_authority(&state, &payer)?;
+            let stats = state.extension.map(|e| e.stats).filter(|k| *k != Pubkey::default());
+            let destination = destination.unwrap_or(payer.pubkey());
+            let mut close = ix::close(&program, &payer.pubkey(), &ctx, &destination, stats.as_ref());
+            match (state.has_feed(), feed) {
+                (true, Some(feed)) => close = ix::with_feed(close, &feed, &[])?,
+                (true, None) => return Err("the context has an oracle feed; pass it with --feed".into()),
+                (false, Some(feed)) => return Err(format!("the context has no oracle feed, not {feed}").into()),
+                (false, None) => {}
+            }
+            if let Some(schedule) = scheduled(&state, schedule)? {
+                close = ix::with_schedule(close, &schedule)?;
+            }
+            if let Some(ladder) = ladder {
+                close = ix::with_ladder(close, &ladder)?;
+            }
+            println!("signature: {}", rpc.send(&[close], &payer, &[])?);
+        }
+    }
+    Ok(())
+}
+
+/// The schedule account to pass: the program refuses a scheduled context's
+/// quotes and close without it.
+fn scheduled(state: &MatcherContext, schedule: Option<Pubkey>) -> Result<Option<Pubkey>> {
+    match (state.is_scheduled(), schedule) {
+        (true, None) => Err("the context has a spread schedule; pass it with --schedule".into()),
+        (false, Some(schedule)) => Err(format!("the context has no spread schedule, not {schedule}").into()),
+        (_, schedule) => Ok(schedule),
+    }
+}
+
+fn load_context(rpc: &Rpc, program: &Pubkey, ctx: &Pubkey) -> Result<MatcherContext> {
+    let account = rpc.account(ctx)?.ok_or_else(|| format!("context {ctx} does not exist"))?;
+    if account.owner != *program {
+        return Err(format!("context {ctx} is owned by {}, not {program}", account.owner).into());
+    }
+    MatcherContext::from_bytes(&account.data).map_err(|e| format!("context {ctx}: {e}").into())
+}
+
+/// Fail before sending rather than on chain with AuthorityMismatch.
+fn check_authority(state: &MatcherContext, wallet: &Keypair) -> Result<()> {
+    if state.authority() != wallet.pubkey() {
+        return Err(format!("wallet {} is not the context authority {}", wallet.pubkey(), state.authority()).into());
+    }
+    Ok(())
+}
+
+fn print_context(key: &Pubkey, c: &MatcherContext, slot: u64) {
+    let p = &c.params;
+    let flags: Vec<&str> = [
+        (CTX_FLAG_PAUSED, "paused"),
+        (CTX_FLAG_REDUCE_ONLY, "reduce-only"),
+        (CTX_FLAG_RETURN_DATA_ONLY, "return-data-only"),
+        (CTX_FLAG_SOFT_REJECT, "soft-reject"),
+        (CTX_FLAG_NO_BIDS, "no-bids"),
+        (CTX_FLAG_NO_ASKS, "no-asks"),
+        (CTX_FLAG_SCHEDULED, "scheduled"),
+        (CTX_FLAG_FEED, "feed"),
+    ]
+    .into_iter()
+    .filter(|(bit, _)| c.flags & bit != 0)
+    .map(|(_, name)| name)
+    .collect();
+    let row = |name: &str, value: String| println!("{name:<26} {value}");
+
+    row("context", key.to_string());
+    row("version", c.version.to_string());
+    row("lp_pda", c.lp_pda.to_string());
+    row("authority", c.authority().to_string());
+    row("flags", if flags.is_empty() { "-".into() } else { flags.join(", ") });
+    row("oracle_decimals", if c.oracle_decimals == 0 { "6 (default)".into() } else { c.oracle_decimals.to_string() });
+    row("inventory", c.inventory.to_string());
+    row("last_oracle_price_e6", c.last_oracle_price_e6.to_string());
+    row("last_exec_price_e6", c.last_exec_price_e6.to_string());
+    row("insurance_snapshot", c.insurance_snapshot.to_string());
+    row("total_oi_snapshot", c.total_oi_snapshot.to_string());
+    row("snapshot_slot", format!("{} ({} slots ago)", c.snapshot_slot, slot.saturating_sub(c.snapshot_slot)));
+    row("last_crank_slot", c.last_crank_slot.to_string());
+    row("liq_heat", c.liq_heat.to_string());
+
+    println!();
+    row("base_fee_bps", p.base_fee_bps.to_string());
+    row("spread_bps", format!("{}..{}", p.min_spread_bps, p.max_spread_bps));
+    row("imbalance_k_bps", p.imbalance_k_bps.to_string());
+    row("liquidity_e6", p.liquidity_e6.to_string());
+    row("max_fill", p.max_fill.to_string());
+    row("max_inventory", p.max_inventory.to_string());
+    row("insurance_weight_bps", p.insurance_weight_bps.to_string());
+    row("impact", format!("mode {} k {}bps", p.impact_mode, p.impact_k_bps));
+    row("skew_k_bps", p.skew_k_bps.to_string());
+    row("coverage_curve", format!("{} {:?}", p.coverage_curve, p.curve_points));
+    row("liq_halflife", p.liq_halflife.to_string());
+    row("peg_band_e6", p.peg_band_e6.to_string());
+
+    let Some(ext) = &c.extension else {
+        return;
+    };
+    row("funding_horizon", p.funding_horizon.to_string());
+    row("oracle_jump", format!("{}bps / {} slots", p.oracle_jump_bps, p.oracle_jump_window));
+    row("flow_k_bps", p.flow_k_bps.to_string());
+    row("twap_window_slots", p.twap_window_slots.to_string());
+    row("spot_weight_bps", p.spot_weight_bps.to_string());
+    row("signal_weights", format!("{:?}", p.signal_weights));
+    row("deficit_halflife", p.deficit_halflife.to_string());
+    row("liq_auction", format!("{}bps / {} slots", p.liq_auction_bps, p.liq_auction_slots));
+
+    println!();
+    row("slab", ext.slab.to_string());
+    row("percolator_program", ext.percolator_program.to_string());
+    row("stats", ext.stats.to_string());
+    row("funding_rate_bps_per_slot", ext.funding_rate_bps_per_slot.to_string());
+    row("fill_seq", ext.fill_seq.to_string());
+    row("cum_notional", ext.cum_notional.to_string());
+    row("cum_fees", ext.cum_fees.to_string());
+    row("gross_long/short", format!("{} / {}", ext.gross_long, ext.gross_short));
+    if ext.last_liq_slot != 0 {
+        row("last_liq_slot", format!("{} ({} slots ago)", ext.last_liq_slot, slot.saturating_sub(ext.last_liq_slot)));
+    }
+    if ext.rfq_signer != Pubkey::default() {
+        row("rfq_signer", format!("{} (nonce {})", ext.rfq_signer, ext.rfq_nonce));
+    }
+    if ext.rfq_price_e6 != 0 {
+        let state = if slot > ext.rfq_expiry_slot { "expired" } else { "live" };
+        let quote = format!("{} at {} through slot {} ({state})", ext.rfq_size_left, ext.rfq_price_e6, ext.rfq_expiry_slot);
+        row("rfq_quote", quote);
+    }
+    if ext.pending_authority != Pubkey::default() {
+        row("pending_authority", ext.pending_authority.to_string());
+    }
+    if ext.pending_activation_slot != 0 {
+        let due = if slot >= ext.pending_activation_slot { "due" } else { "timelocked" };
+        row("pending_params", format!("from slot {} ({due})", ext.pending_activation_slot));
+        println!("{:?}", ext.pending_params);
+    }
+
+    let Some(markets) = &c.markets else {
+        return;
+    };
+    for (i, m) in markets.iter().enumerate().filter(|(_, m)| m.slab != Pubkey::default()) {
+        let cap = |v: u128| if v == 0 { "-".to_string() } else { v.to_string() };
+        println!();
+        row(&format!("market {i}"), m.slab.to_string());
+        row("caps", format!("max_fill {} max_inventory {}", cap(m.max_fill), cap(m.max_inventory)));
+        row("inventory", m.inventory.to_string());
+        row("last_oracle/exec_e6", format!("{} / {}", m.last_oracle_price_e6, m.last_exec_price_e6));
+        row("insurance/oi_snapshot", format!("{} / {}", m.insurance_snapshot, m.total_oi_snapshot));
+        row("snapshot_slot", format!("{} ({} slots ago)", m.snapshot_slot, slot.saturating_sub(m.snapshot_slot)));
+        row("funding_rate_bps_per_slot", m.funding_rate_bps_per_slot.to_string());
+        row("gross_long/short", format!("{} / {}", m.gross_long, m.gross_short));
+    }
+}
+
+fn print_quote(data: &[u8]) -> Result<()> {
+    if data.len() < QUOTE_LEN {
+        return Err(format!("QuotePreview returned {} bytes, expected {QUOTE_LEN}", data.len()).into());
+    }
+    let u64_at = |off: usize| u64::from_le_bytes(data[off..off + 8].try_into().unwrap());
+    let i64_at = |off: usize| i64::from_le_bytes(data[off..off + 8].try_into().unwrap());
+    let i128_at = |off: usize| i128::from_le_bytes(data[off..off + 16].try_into().unwrap());
+    const TIERS: [&str; 5] = ["CRITICAL", "FRAGILE", "NORMAL", "STRONG", "FORTIFIED"];
+    let tier = TIERS.get(u64_at(QUOTE_TIER_OFF) as usize).copied().unwrap_or("?");
+    let row = |name: &str, value: String| println!("{name:<16} {value}");
+
+    row("exec_price_e6", u64_at(QUOTE_EXEC_PRICE_OFF).to_string());
+    row("fill_size", i128_at(QUOTE_FILL_SIZE_OFF).to_string());
+    row("anchor_price_e6", u64_at(QUOTE_ANCHOR_OFF).to_string());
+    row("tier", format!("{tier} (coverage {}bps, fill cap {}%)", u64_at(QUOTE_COVERAGE_OFF), u64_at(QUOTE_FILL_PCT_OFF)));
+    row("tier_spread", format!("{}bps (discount {}bps)", u64_at(QUOTE_TIER_SPREAD_OFF), u64_at(QUOTE_DISCOUNT_OFF)));
+    for (name, off) in [
+        ("imbalance", QUOTE_IMBALANCE_OFF),
+        ("impact", QUOTE_IMPACT_OFF),
+        ("stale", QUOTE_STALE_OFF),
+        ("crank", QUOTE_CRANK_OFF),
+        ("liq", QUOTE_LIQ_OFF),
+        ("flow", QUOTE_FLOW_OFF),
+        ("signal", QUOTE_SIGNAL_OFF),
+        ("schedule", QUOTE_SCHEDULE_OFF),
+        ("oracle", QUOTE_ORACLE_OFF),
+    ] {
+        row(name, format!("+{}bps", u64_at(off)));
+    }
+    row("spread", format!("{}bps", u64_at(QUOTE_SPREAD_OFF)));
+    row("skew", format!("{}bps (funding {}bps)", i64_at(QUOTE_SKEW_OFF), i64_at(QUOTE_FUNDING_SKEW_OFF)));
+    row("fee", format!("{}bps (rebate {}bps)", u64_at(QUOTE_FEE_OFF), u64_at(QUOTE_REBATE_OFF)));
+    row("new_inventory", i128_at(QUOTE_NEW_INVENTORY_OFF).to_string());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+        let cli = Cli::try_parse_from(["provenance-cli", "quote", "-5", "--ctx", "11111111111111111111111111111111", "--oracle", "1"])
+            .unwrap();
+        assert!(matches!(cli.command, Command::Quote { size: -5, oracle: 1, .. }));
+        let signal = Pubkey::new_unique().to_string();
+        let cli = Cli::try_parse_from(["provenance-cli", "quote", "1", "--ctx", &signal, "--oracle", "1", "--signal", &signal, "--signal", &signal])
+            .unwrap();
+        assert!(matches!(cli.command, Command::Quote { ref signals, .. } if signals.len() == 2));
+        // --authority only binds alongside a slab
+        let lp = Pubkey::new_unique().to_string();
+        assert!(Cli::try_parse_from(["provenance-cli", "init", "--lp", &lp, "--authority", &lp]).is_err());
+        let cli = Cli::try_parse_from(["provenance-cli", "post-rfq", &lp, "--signer", "k.json", "--price", "1", "--size", "-3"])
+            .unwrap();
+        assert!(matches!(cli.command, Command::PostRfq { size: -3, slots: 150, .. }));
+        assert!(Cli::try_parse_from(["provenance-cli", "init", "--lp", &lp, "--shared", "--v5"]).is_err());
+        let cli = Cli::try_parse_from(["provenance-cli", "set-market", &lp, &lp, "--max-fill", "9"]).unwrap();
+        assert!(matches!(cli.command, Command::SetMarket { max_fill: 9, max_inventory: 0, .. }));
+    }
+}
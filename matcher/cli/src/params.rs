@@ -0,0 +1,182 @@
+//! Parameter edits from a JSON file and `--set FIELD=VALUE` flags.
+//!
+//! Field names are `MatcherParams`' own. Edits apply on top of a base block —
+//! all zeros for `init`, the context's current parameters for `update-params`
+//! — so a file or flag only has to name what it changes.
+
+use std::{fs, path::Path};
+
+use provenance_client::MatcherParams;
+use serde_json::Value;
+
+use crate::Result;
+
+/// Apply every field of a JSON object, then every `FIELD=VALUE` pair.
+pub fn apply(params: &mut MatcherParams, file: Option<&Path>, sets: &[String]) -> Result<()> {
+    if let Some(file) = file {
+        let json: Value = serde_json::from_str(&fs::read_to_string(file)?)?;
+        let fields = json.as_object().ok_or_else(|| format!("{}: expected a JSON object", file.display()))?;
+        for (field, value) in fields {
+            let value = match value {
+                Value::String(s) => s.clone(),
+                Value::Array(points) => points.iter().map(Value::to_string).collect::<Vec<_>>().join(","),
+                other => other.to_string(),
+            };
+            set_field(params, field, &value).map_err(|e| format!("{}: {e}", file.display()))?;
+        }
+    }
+    for set in sets {
+        let (field, value) = set.split_once('=').ok_or_else(|| format!("--set {set}: expected FIELD=VALUE"))?;
+        set_field(params, field.trim(), value.trim())?;
+    }
+    Ok(())
+}
+
+/// Every field with its value, in wire order, as `set_field` names them.
+pub fn fields(p: &MatcherParams) -> Vec<(&'static str, String)> {
+    let list = |values: &[u16]| values.iter().map(u16::to_string).collect::<Vec<_>>().join(",");
+    vec![
+        ("base_fee_bps", p.base_fee_bps.to_string()),
+        ("min_spread_bps", p.min_spread_bps.to_string()),
+        ("max_spread_bps", p.max_spread_bps.to_string()),
+        ("imbalance_k_bps", p.imbalance_k_bps.to_string()),
+        ("liquidity_e6", p.liquidity_e6.to_string()),
+        ("max_fill", p.max_fill.to_string()),
+        ("max_inventory", p.max_inventory.to_string()),
+        ("age_halflife", p.age_halflife.to_string()),
+        ("insurance_weight_bps", p.insurance_weight_bps.to_string()),
+        ("impact_mode", p.impact_mode.to_string()),
+        ("impact_k_bps", p.impact_k_bps.to_string()),
+        ("skew_k_bps", p.skew_k_bps.to_string()),
+        ("coverage_curve", p.coverage_curve.to_string()),
+        ("curve_points", list(&p.curve_points)),
+        ("liq_halflife", p.liq_halflife.to_string()),
+        ("funding_horizon", p.funding_horizon.to_string()),
+        ("oracle_jump_bps", p.oracle_jump_bps.to_string()),
+        ("oracle_jump_window", p.oracle_jump_window.to_string()),
+        ("flow_k_bps", p.flow_k_bps.to_string()),
+        ("twap_window_slots", p.twap_window_slots.to_string()),
+        ("signal_weights", list(&p.signal_weights)),
+        ("deficit_halflife", p.deficit_halflife.to_string()),
+        ("liq_auction_bps", p.liq_auction_bps.to_string()),
+        ("liq_auction_slots", p.liq_auction_slots.to_string()),
+        ("peg_band_e6", p.peg_band_e6.to_string()),
+        ("spot_weight_bps", p.spot_weight_bps.to_string()),
+    ]
+}
+
+/// Refuse a block the pricing path can't run offline: Init rejects the first,
+/// and the spread clamp needs a ceiling of at least 1 bps.
+pub fn check_spreads(p: &MatcherParams) -> Result<()> {
+    if p.min_spread_bps > p.max_spread_bps {
+        return Err(format!("min_spread_bps {} > max_spread_bps {}", p.min_spread_bps, p.max_spread_bps).into());
+    }
+    if p.max_spread_bps == 0 {
+        return Err("max_spread_bps must be at least 1".into());
+    }
+    Ok(())
+}
+
+fn set_field(p: &mut MatcherParams, field: &str, value: &str) -> Result<()> {
+    fn num<T: std::str::FromStr>(field: &str, value: &str) -> Result<T> {
+        value.replace('_', "").parse().map_err(|_| format!("{field}: invalid value {value:?}").into())
+    }
+    match field {
+        "base_fee_bps" => p.base_fee_bps = num(field, value)?,
+        "min_spread_bps" => p.min_spread_bps = num(field, value)?,
+        "max_spread_bps" => p.max_spread_bps = num(field, value)?,
+        "imbalance_k_bps" => p.imbalance_k_bps = num(field, value)?,
+        "liquidity_e6" => p.liquidity_e6 = num(field, value)?,
+        "max_fill" => p.max_fill = num(field, value)?,
+        "max_inventory" => p.max_inventory = num(field, value)?,
+        "age_halflife" => p.age_halflife = num(field, value)?,
+        "insurance_weight_bps" => p.insurance_weight_bps = num(field, value)?,
+        "impact_mode" => p.impact_mode = num(field, value)?,
+        "impact_k_bps" => p.impact_k_bps = num(field, value)?,
+        "skew_k_bps" => p.skew_k_bps = num(field, value)?,
+        "coverage_curve" => p.coverage_curve = num(field, value)?,
+        "curve_points" => list(&mut p.curve_points, field, value)?,
+        "liq_halflife" => p.liq_halflife = num(field, value)?,
+        "funding_horizon" => p.funding_horizon = num(field, value)?,
+        "oracle_jump_bps" => p.oracle_jump_bps = num(field, value)?,
+        "oracle_jump_window" => p.oracle_jump_window = num(field, value)?,
+        "flow_k_bps" => p.flow_k_bps = num(field, value)?,
+        "twap_window_slots" => p.twap_window_slots = num(field, value)?,
+        "signal_weights" => list(&mut p.signal_weights, field, value)?,
+        "deficit_halflife" => p.deficit_halflife = num(field, value)?,
+        "liq_auction_bps" => p.liq_auction_bps = num(field, value)?,
+        "liq_auction_slots" => p.liq_auction_slots = num(field, value)?,
+        "peg_band_e6" => p.peg_band_e6 = num(field, value)?,
+        "spot_weight_bps" => p.spot_weight_bps = num(field, value)?,
+        _ => return Err(format!("unknown parameter {field:?}").into()),
+    }
+    Ok(())
+}
+
+/// Fill a fixed-length list from comma-separated values.
+fn list(slots: &mut [u16], field: &str, value: &str) -> Result<()> {
+    let values = value
+        .split(',')
+        .map(|v| v.trim().replace('_', "").parse().map_err(|_| format!("{field}: invalid value {v:?}")))
+        .collect::<std::result::Result<Vec<u16>, _>>()?;
+    if values.len() != slots.len() {
+        return Err(format!("{field}: expected {} values", slots.len()).into());
+    }
+    slots.copy_from_slice(&values);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_then_flags() {
+        let path = std::env::temp_dir().join(format!("provenance-cli-params-{}.json", std::process::id()));
+        fs::write(
+            &path,
+            r#"{ "max_spread_bps": 200, "liquidity_e6": "10_000_000_000_000", "curve_points": [1, 2, 3, 4] }"#,
+        )
+        .unwrap();
+        let mut params = MatcherParams { base_fee_bps: 5, ..Default::default() };
+        apply(&mut params, Some(&path), &["max_spread_bps=300".into(), "twap_window_slots = 50".into()]).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(params.base_fee_bps, 5); // untouched fields keep the base
+        assert_eq!(params.max_spread_bps, 300); // flags win over the file
+        assert_eq!(params.liquidity_e6, 10_000_000_000_000);
+        assert_eq!(params.curve_points, [1, 2, 3, 4]);
+        assert_eq!(params.twap_window_slots, 50);
+    }
+
+    #[test]
+    fn test_fields_round_trip() {
+        let params = MatcherParams {
+            max_fill: 7,
+            curve_points: [1, 2, 3, 4],
+            twap_window_slots: 9,
+            signal_weights: [5_000, 0, 5_000, 0],
+            deficit_halflife: 11,
+            liq_auction_bps: 150,
+            liq_auction_slots: 20,
+            peg_band_e6: 500,
+            spot_weight_bps: 3_000,
+            ..Default::default()
+        };
+        let sets: Vec<String> = fields(&params).into_iter().map(|(f, v)| format!("{f}={v}")).collect();
+        let mut parsed = MatcherParams::default();
+        apply(&mut parsed, None, &sets).unwrap();
+        assert_eq!(parsed, params);
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        let mut params = MatcherParams::default();
+        assert!(apply(&mut params, None, &["max_spread".into()]).is_err());
+        assert!(apply(&mut params, None, &["spread=1".into()]).is_err());
+        assert!(apply(&mut params, None, &["impact_mode=256".into()]).is_err());
+        assert!(apply(&mut params, None, &["curve_points=1,2,3".into()]).is_err());
+        assert!(apply(&mut params, None, &["signal_weights=1,2,3,x".into()]).is_err());
+        assert_eq!(params, MatcherParams::default());
+    }
+}
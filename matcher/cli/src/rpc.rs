@@ -0,0 +1,167 @@
+//! Just enough JSON-RPC to operate a matcher: read accounts, send and
+//! simulate transactions.
+
+use std::{thread, time::Duration};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::{json, Value};
+use solana_keypair::Keypair;
+use solana_program::{hash::Hash, instruction::Instruction, message::Message, pubkey::Pubkey};
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use crate::Result;
+
+/// How long `send` waits for confirmation: 60 polls, 500ms apart.
+const CONFIRM_POLLS: usize = 60;
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct Account {
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+}
+
+/// What a simulated transaction did.
+pub struct Simulation {
+    pub err: Option<Value>,
+    pub logs: Vec<String>,
+    pub return_data: Option<Vec<u8>>,
+}
+
+pub struct Rpc {
+    url: String,
+    commitment: String,
+}
+
+impl Rpc {
+    pub fn new(url: &str, commitment: &str) -> Self {
+        Self { url: url.to_string(), commitment: commitment.to_string() }
+    }
+
+    fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let mut response: Value = ureq::post(&self.url).send_json(body)?.into_json()?;
+        if let Some(err) = response.get("error") {
+            let message = err["message"].as_str().unwrap_or("unknown error");
+            let logs = err["data"]["logs"].as_array().map(|l| format_logs(l)).unwrap_or_default();
+            return Err(format!("{method}: {message}{logs}").into());
+        }
+        Ok(response["result"].take())
+    }
+
+    pub fn account(&self, key: &Pubkey) -> Result<Option<Account>> {
+        let result = self.call(
+            "getAccountInfo",
+            json!([key.to_string(), { "encoding": "base64", "commitment": self.commitment }]),
+        )?;
+        parse_account(&result["value"])
+    }
+
+    /// Fetch several accounts in one round trip, in order.
+    pub fn accounts(&self, keys: &[Pubkey]) -> Result<Vec<Option<Account>>> {
+        let keys: Vec<String> = keys.iter().map(Pubkey::to_string).collect();
+        let result = self.call(
+            "getMultipleAccounts",
+            json!([keys, { "encoding": "base64", "commitment": self.commitment }]),
+        )?;
+        let values = result["value"].as_array().ok_or("getMultipleAccounts: no value")?;
+        values.iter().map(parse_account).collect()
+    }
+
+    pub fn slot(&self) -> Result<u64> {
+        let result = self.call("getSlot", json!([{ "commitment": self.commitment }]))?;
+        result.as_u64().ok_or_else(|| "getSlot: not a number".into())
+    }
+
+    pub fn rent_exempt_minimum(&self, len: usize) -> Result<u64> {
+        let result = self.call("getMinimumBalanceForRentExemption", json!([len]))?;
+        result.as_u64().ok_or_else(|| "getMinimumBalanceForRentExemption: not a number".into())
+    }
+
+    /// Priority fees (micro-lamports per compute unit) recently paid by
+    /// transactions that wrote `keys`, one per slot.
+    pub fn recent_prioritization_fees(&self, keys: &[Pubkey]) -> Result<Vec<u64>> {
+        let keys: Vec<String> = keys.iter().map(Pubkey::to_string).collect();
+        let result = self.call("getRecentPrioritizationFees", json!([keys]))?;
+        let fees = result.as_array().ok_or("getRecentPrioritizationFees: not an array")?;
+        Ok(fees.iter().filter_map(|f| f["prioritizationFee"].as_u64()).collect())
+    }
+
+    fn latest_blockhash(&self) -> Result<Hash> {
+        let result = self.call("getLatestBlockhash", json!([{ "commitment": self.commitment }]))?;
+        Ok(result["value"]["blockhash"].as_str().ok_or("getLatestBlockhash: no blockhash")?.parse()?)
+    }
+
+    /// Sign with `payer` (and `signers`), send, and wait for the configured
+    /// commitment. Returns the signature.
+    pub fn send(&self, ixs: &[Instruction], payer: &Keypair, signers: &[&Keypair]) -> Result<String> {
+        let blockhash = self.latest_blockhash()?;
+        let mut all: Vec<&Keypair> = vec![payer];
+        all.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(ixs, Some(&payer.pubkey()), &all, blockhash);
+        let encoded = BASE64.encode(bincode::serialize(&tx)?);
+        let signature = self.call(
+            "sendTransaction",
+            json!([encoded, { "encoding": "base64", "preflightCommitment": self.commitment }]),
+        )?;
+        let signature = signature.as_str().ok_or("sendTransaction: no signature")?.to_string();
+
+        for _ in 0..CONFIRM_POLLS {
+            let result = self.call("getSignatureStatuses", json!([[signature]]))?;
+            let status = &result["value"][0];
+            if !status.is_null() {
+                if !status["err"].is_null() {
+                    return Err(format!("transaction {signature} failed: {}", status["err"]).into());
+                }
+                let reached = status["confirmationStatus"].as_str().unwrap_or("processed");
+                if self.commitment == "processed" || reached == "finalized" || reached == self.commitment {
+                    return Ok(signature);
+                }
+            }
+            thread::sleep(CONFIRM_POLL_INTERVAL);
+        }
+        Err(format!("transaction {signature} not confirmed in time").into())
+    }
+
+    /// Simulate without signatures; `payer` only has to exist.
+    pub fn simulate(&self, ixs: &[Instruction], payer: &Pubkey) -> Result<Simulation> {
+        let tx = Transaction::new_unsigned(Message::new(ixs, Some(payer)));
+        let encoded = BASE64.encode(bincode::serialize(&tx)?);
+        let result = self.call(
+            "simulateTransaction",
+            json!([encoded, {
+                "encoding": "base64",
+                "sigVerify": false,
+                "replaceRecentBlockhash": true,
+                "commitment": self.commitment,
+            }]),
+        )?;
+        let value = &result["value"];
+        let return_data = match value["returnData"]["data"][0].as_str() {
+            Some(data) => Some(BASE64.decode(data)?),
+            None => None,
+        };
+        Ok(Simulation {
+            err: Some(value["err"].clone()).filter(|e| !e.is_null()),
+            logs: value["logs"]
+                .as_array()
+                .map(|logs| logs.iter().filter_map(|l| l.as_str().map(str::to_string)).collect())
+                .unwrap_or_default(),
+            return_data,
+        })
+    }
+}
+
+fn parse_account(value: &Value) -> Result<Option<Account>> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    Ok(Some(Account {
+        owner: value["owner"].as_str().ok_or("account: no owner")?.parse()?,
+        data: BASE64.decode(value["data"][0].as_str().ok_or("account: no data")?)?,
+    }))
+}
+
+fn format_logs(logs: &[Value]) -> String {
+    logs.iter().filter_map(Value::as_str).map(|l| format!("\n  {l}")).collect()
+}
@@ -0,0 +1,265 @@
+//! Quote sweeps for `provenance-sim`.
+//!
+//! A sweep prices every combination of coverage ratio, LP inventory, trade
+//! size and oracle move through `provenance_core::quote`, the same function
+//! Match runs, so a parameter block can be tuned off-chain before it is
+//! deployed. Everything else about the market is held at its calmest: the
+//! snapshot and the percolator crank are current, there is no liquidation
+//! heat, no funding and no one-way flow.
+
+use std::io::{self, Write};
+
+use provenance_client::MatcherParams;
+use provenance_core::{quote, twap, Params, Quote, QuoteError, State, BPS, TIER_NAMES};
+
+use crate::Result;
+
+/// Open interest the coverage axis is applied to. Only the ratio reaches the
+/// pricing path, so the figure itself is arbitrary.
+pub(crate) const OPEN_INTEREST: u128 = 1_000_000_000_000;
+
+/// Slot the simulated quotes are priced at.
+const SLOT: u64 = 300_000_000;
+
+pub const CSV_HEADER: &str = "coverage_bps,inventory,trade_size,volatility_bps,oracle_price_e6,tier,fill_size,\
+tier_spread_bps,imbalance_bps,impact_bps,spread_bps,skew_bps,fee_bps,anchor_price_e6,exec_price_e6,\
+exec_vs_oracle_bps,error";
+
+/// The subset of `MatcherParams` the pricing path reads, as the program
+/// decodes it.
+pub fn pricing(p: &MatcherParams) -> Params {
+    Params {
+        base_fee_bps: p.base_fee_bps,
+        min_spread_bps: p.min_spread_bps,
+        max_spread_bps: p.max_spread_bps,
+        imbalance_k_bps: p.imbalance_k_bps,
+        liquidity_e6: p.liquidity_e6,
+        max_fill: p.max_fill,
+        max_inventory: p.max_inventory,
+        age_halflife: p.age_halflife,
+        insurance_weight_bps: p.insurance_weight_bps,
+        impact_mode: p.impact_mode,
+        impact_k_bps: p.impact_k_bps,
+        skew_k_bps: p.skew_k_bps,
+        coverage_curve: p.coverage_curve,
+        curve_points: p.curve_points,
+        liq_halflife: p.liq_halflife,
+        funding_horizon: p.funding_horizon,
+        oracle_jump_bps: p.oracle_jump_bps,
+        oracle_jump_window: p.oracle_jump_window,
+        flow_k_bps: p.flow_k_bps,
+        twap_window_slots: p.twap_window_slots,
+        signal_weights: p.signal_weights,
+        deficit_halflife: p.deficit_halflife,
+        liq_auction_bps: p.liq_auction_bps,
+        liq_auction_slots: p.liq_auction_slots,
+        peg_band_e6: p.peg_band_e6,
+        spot_weight_bps: p.spot_weight_bps,
+    }
+}
+
+/// Parse one axis of a sweep: a single value, a comma-separated list, or an
+/// inclusive `start:end:step` range. Underscores are ignored in numbers.
+pub fn axis<T: TryFrom<i128>>(spec: &str) -> Result<Vec<T>> {
+    let num = |s: &str| -> Result<i128> {
+        s.trim().replace('_', "").parse().map_err(|_| format!("{spec:?}: invalid number {s:?}").into())
+    };
+    let values = match spec.split(':').collect::<Vec<_>>()[..] {
+        [start, end, step] => {
+            let (start, end, step) = (num(start)?, num(end)?, num(step)?);
+            if step <= 0 || end < start {
+                return Err(format!("{spec:?}: expected start <= end and a positive step").into());
+            }
+            let count = (end - start) / step + 1;
+            if count > 10_000 {
+                return Err(format!("{spec:?}: {count} points, at most 10000 per axis").into());
+            }
+            (0..count).map(|i| start + i * step).collect()
+        }
+        [_] => spec.split(',').map(num).collect::<Result<Vec<_>>>()?,
+        _ => return Err(format!("{spec:?}: expected VALUE, V1,V2,... or START:END:STEP").into()),
+    };
+    values
+        .into_iter()
+        .map(|v| T::try_from(v).map_err(|_| format!("{spec:?}: {v} is out of range").into()))
+        .collect()
+}
+
+/// The grid to price, plus the market it is priced in.
+#[derive(Clone, Debug)]
+pub struct Sweep {
+    /// Oracle print of the LP's last fill; the oracle moves from here.
+    pub oracle_price_e6: u64,
+    /// Slots between the last fill and the quote.
+    pub move_slots: u64,
+    /// Insurance over open interest.
+    pub coverage_bps: Vec<u64>,
+    /// The LP's signed inventory before the trade.
+    pub inventory: Vec<i128>,
+    /// Signed trade request; negative sells.
+    pub trade_size: Vec<i128>,
+    /// How far the oracle moved since the last fill, negative for down. It is
+    /// what the circuit breaker measures and what a TWAP anchor lags behind.
+    pub volatility_bps: Vec<i64>,
+}
+
+/// One point of a sweep.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Scenario {
+    pub coverage_bps: u64,
+    pub inventory: i128,
+    pub trade_size: i128,
+    pub volatility_bps: i64,
+}
+
+impl Sweep {
+    /// Every point of the grid, coverage outermost.
+    pub fn scenarios(&self) -> impl Iterator<Item = Scenario> + '_ {
+        self.coverage_bps.iter().flat_map(move |&coverage_bps| {
+            self.inventory.iter().flat_map(move |&inventory| {
+                self.trade_size.iter().flat_map(move |&trade_size| {
+                    self.volatility_bps.iter().map(move |&volatility_bps| Scenario {
+                        coverage_bps,
+                        inventory,
+                        trade_size,
+                        volatility_bps,
+                    })
+                })
+            })
+        })
+    }
+
+    /// The oracle price a scenario is quoted at.
+    pub fn oracle_price(&self, s: &Scenario) -> u64 {
+        let moved = self.oracle_price_e6 as i128 * (BPS as i128 + s.volatility_bps as i128) / BPS as i128;
+        moved.clamp(0, u64::MAX as i128) as u64
+    }
+
+    /// Matcher state for a scenario: fresh snapshot and crank, and a last
+    /// fill at `oracle_price_e6` `move_slots` ago, which is also the only
+    /// sample the TWAP sees.
+    pub fn state(&self, params: &Params, s: &Scenario) -> State {
+        let last_fill_slot = SLOT.saturating_sub(self.move_slots);
+        let window = params.twap_window_slots as u64;
+        State {
+            current_slot: SLOT,
+            inventory: s.inventory,
+            insurance_snapshot: OPEN_INTEREST * s.coverage_bps as u128 / BPS as u128,
+            total_oi_snapshot: OPEN_INTEREST,
+            snapshot_slot: SLOT,
+            last_crank_slot: SLOT,
+            last_oracle_price_e6: self.oracle_price_e6,
+            last_oracle_slot: last_fill_slot,
+            twap_price_e6: (window > 0)
+                .then(|| twap([(last_fill_slot, self.oracle_price_e6)], window, SLOT, self.oracle_price(s))),
+            ..Default::default()
+        }
+    }
+
+    pub fn quote(&self, params: &Params, s: &Scenario) -> std::result::Result<Quote, QuoteError> {
+        quote(params, &self.state(params, s), self.oracle_price(s), s.trade_size)
+    }
+
+    /// Price the whole grid as CSV, header first. Returns the number of rows.
+    pub fn write_csv(&self, params: &Params, out: &mut impl Write) -> io::Result<usize> {
+        writeln!(out, "{CSV_HEADER}")?;
+        let mut rows = 0;
+        for s in self.scenarios() {
+            let oracle = self.oracle_price(&s);
+            write!(out, "{},{},{},{},{oracle},", s.coverage_bps, s.inventory, s.trade_size, s.volatility_bps)?;
+            match self.quote(params, &s) {
+                Ok(q) => writeln!(
+                    out,
+                    "{},{},{},{},{},{},{},{},{},{},{},",
+                    TIER_NAMES[q.tier as usize],
+                    q.fill_size,
+                    q.tier_spread_bps,
+                    q.imbalance_bps,
+                    q.impact_bps,
+                    q.spread_bps,
+                    q.skew_bps,
+                    q.fee_bps,
+                    q.anchor_price_e6,
+                    q.exec_price_e6,
+                    exec_vs_oracle_bps(q.exec_price_e6, oracle),
+                )?,
+                Err(err) => writeln!(out, ",,,,,,,,,,,{}", error_name(&err))?,
+            }
+            rows += 1;
+        }
+        Ok(rows)
+    }
+}
+
+/// What the trader pays over the current oracle, in bps of it. Negative
+/// means a buy below or a sell above the oracle: a quote worth arbitraging.
+pub fn exec_vs_oracle_bps(exec_price_e6: u64, oracle_price_e6: u64) -> i64 {
+    if oracle_price_e6 == 0 {
+        return 0;
+    }
+    ((exec_price_e6 as i128 - oracle_price_e6 as i128) * BPS as i128 / oracle_price_e6 as i128) as i64
+}
+
+pub(crate) fn error_name(err: &QuoteError) -> &'static str {
+    match err {
+        QuoteError::ZeroOraclePrice => "zero_oracle_price",
+        QuoteError::OracleJump { .. } => "oracle_jump",
+        QuoteError::StatsRequired => "stats_required",
+        QuoteError::InventoryOverflow { .. } => "inventory_overflow",
+        QuoteError::ReduceOnly { .. } => "reduce_only",
+        QuoteError::InventoryLimit => "inventory_limit",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_axis() {
+        assert_eq!(axis::<u64>("0:10_000:2500").unwrap(), [0, 2_500, 5_000, 7_500, 10_000]);
+        assert_eq!(axis::<i128>("-5, 0,5").unwrap(), [-5, 0, 5]);
+        assert_eq!(axis::<i64>("7").unwrap(), [7]);
+        assert!(axis::<u64>("-1").is_err());
+        assert!(axis::<u64>("10:0:1").is_err());
+        assert!(axis::<u64>("0:10:0").is_err());
+        assert!(axis::<u64>("0:10").is_err());
+    }
+
+    #[test]
+    fn test_sweep_csv() {
+        let params = Params {
+            base_fee_bps: 5,
+            min_spread_bps: 10,
+            max_spread_bps: 200,
+            liquidity_e6: 10_000_000_000_000,
+            oracle_jump_bps: 500,
+            oracle_jump_window: 10,
+            ..Default::default()
+        };
+        let sweep = Sweep {
+            oracle_price_e6: 100_000_000,
+            move_slots: 1,
+            coverage_bps: vec![500, 30_000],
+            inventory: vec![0],
+            trade_size: vec![1_000],
+            volatility_bps: vec![0, 1_000],
+        };
+        let mut out = Vec::new();
+        assert_eq!(sweep.write_csv(&params, &mut out).unwrap(), 4);
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(lines[1], "500,0,1000,0,100000000,CRITICAL,1000,200,0,0,200,0,5,100000000,102050000,205,");
+        assert!(lines[2].ends_with(",,,,,,,,,,,oracle_jump"));
+        assert_eq!(lines[3], "30000,0,1000,0,100000000,FORTIFIED,1000,10,0,0,10,0,5,100000000,100150000,15,");
+        assert!(lines.iter().all(|l| l.split(',').count() == CSV_HEADER.split(',').count()));
+
+        // A TWAP anchor lags the move: the buyer pays under the new oracle
+        let anchored = Params { twap_window_slots: 100, oracle_jump_bps: 0, ..params };
+        let s = Scenario { coverage_bps: 30_000, inventory: 0, trade_size: 1_000, volatility_bps: 1_000 };
+        let q = sweep.quote(&anchored, &s).unwrap();
+        assert_eq!(q.anchor_price_e6, 100_000_000);
+        assert!(exec_vs_oracle_bps(q.exec_price_e6, sweep.oracle_price(&s)) < 0);
+    }
+}
@@ -0,0 +1,308 @@
+//! Context change events for `provenance-watcher`.
+//!
+//! A [`Watcher`] subscribes to each context over the RPC node's pubsub
+//! websocket, decodes every update with `MatcherContext::from_bytes`, and
+//! diffs it against the last one seen. Each difference becomes an [`Event`]:
+//! an inventory move, a snapshot refresh, a parameter change and so on.
+//! Events go to a callback ([`Watcher::run`]) or a channel
+//! ([`Watcher::spawn`]).
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use provenance_client::MatcherContext;
+use provenance_core::coverage_bps;
+use serde_json::{json, Value};
+use solana_program::pubkey::Pubkey;
+
+use crate::{params, ws::Socket, Result};
+
+/// What changed in a context.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// First update seen for the context.
+    Loaded { inventory: i128, snapshot_slot: u64 },
+    /// Fills or SettleInventory moved the inventory. `fills` counts the
+    /// fills in between on v6 contexts.
+    Inventory { delta: i128, inventory: i128, fills: Option<u64> },
+    /// UpdateCredibility took a new snapshot.
+    Snapshot { snapshot_slot: u64, insurance: u128, total_oi: u128, coverage_bps: u64 },
+    /// The parameter block changed: UpdateParams, or a committed proposal.
+    /// Each change is (field, old, new).
+    Params { changes: Vec<(&'static str, String, String)> },
+    /// A proposal was queued (its activation slot) or withdrawn (0).
+    Proposal { activation_slot: u64 },
+    /// Pause, reduce-only or another context flag changed.
+    Flags { old: u8, new: u8 },
+    /// The account was closed or no longer decodes as a context.
+    Gone { reason: String },
+}
+
+/// An event, with the context and slot it was seen at.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContextEvent {
+    pub context: Pubkey,
+    pub slot: u64,
+    pub event: Event,
+}
+
+/// Every event between two readings of a context; `old` is `None` the first
+/// time it is seen.
+pub fn diff(old: Option<&MatcherContext>, new: &MatcherContext) -> Vec<Event> {
+    let Some(old) = old else {
+        return vec![Event::Loaded { inventory: new.inventory, snapshot_slot: new.snapshot_slot }];
+    };
+    let mut events = Vec::new();
+    if new.inventory != old.inventory {
+        let fills = match (&old.extension, &new.extension) {
+            (Some(o), Some(n)) => Some(n.fill_seq.wrapping_sub(o.fill_seq)),
+            _ => None,
+        };
+        events.push(Event::Inventory { delta: new.inventory.wrapping_sub(old.inventory), inventory: new.inventory, fills });
+    }
+    if new.snapshot_slot != old.snapshot_slot {
+        events.push(Event::Snapshot {
+            snapshot_slot: new.snapshot_slot,
+            insurance: new.insurance_snapshot,
+            total_oi: new.total_oi_snapshot,
+            coverage_bps: coverage_bps(new.insurance_snapshot, new.total_oi_snapshot),
+        });
+    }
+    if new.params != old.params {
+        let changes = params::fields(&old.params)
+            .into_iter()
+            .zip(params::fields(&new.params))
+            .filter(|((_, old), (_, new))| old != new)
+            .map(|((field, old), (_, new))| (field, old, new))
+            .collect();
+        events.push(Event::Params { changes });
+    }
+    let pending = |c: &MatcherContext| c.extension.map_or(0, |x| x.pending_activation_slot);
+    if pending(new) != pending(old) {
+        events.push(Event::Proposal { activation_slot: pending(new) });
+    }
+    if new.flags != old.flags {
+        events.push(Event::Flags { old: old.flags, new: new.flags });
+    }
+    events
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::Loaded { inventory, snapshot_slot } => {
+                write!(f, "loaded: inventory {inventory}, snapshot at slot {snapshot_slot}")
+            }
+            Event::Inventory { delta, inventory, fills } => {
+                write!(f, "inventory {delta:+} -> {inventory}")?;
+                match fills {
+                    Some(fills) => write!(f, " ({fills} fill(s))"),
+                    None => Ok(()),
+                }
+            }
+            Event::Snapshot { snapshot_slot, insurance, total_oi, coverage_bps } => write!(
+                f,
+                "snapshot at slot {snapshot_slot}: insurance {insurance}, open interest {total_oi}, coverage {coverage_bps}bps"
+            ),
+            Event::Params { changes } => {
+                let changes: Vec<String> = changes.iter().map(|(field, old, new)| format!("{field} {old} -> {new}")).collect();
+                write!(f, "params: {}", changes.join(", "))
+            }
+            Event::Proposal { activation_slot: 0 } => write!(f, "proposal withdrawn or committed"),
+            Event::Proposal { activation_slot } => write!(f, "proposal queued, committable at slot {activation_slot}"),
+            Event::Flags { old, new } => write!(f, "flags {old:#04x} -> {new:#04x}"),
+            Event::Gone { reason } => write!(f, "gone: {reason}"),
+        }
+    }
+}
+
+impl ContextEvent {
+    pub fn to_json(&self) -> Value {
+        let event = match &self.event {
+            Event::Loaded { inventory, snapshot_slot } => {
+                json!({ "type": "loaded", "inventory": inventory.to_string(), "snapshot_slot": snapshot_slot })
+            }
+            Event::Inventory { delta, inventory, fills } => json!({
+                "type": "inventory", "delta": delta.to_string(), "inventory": inventory.to_string(), "fills": fills,
+            }),
+            Event::Snapshot { snapshot_slot, insurance, total_oi, coverage_bps } => json!({
+                "type": "snapshot", "snapshot_slot": snapshot_slot, "insurance": insurance.to_string(),
+                "total_oi": total_oi.to_string(), "coverage_bps": coverage_bps,
+            }),
+            Event::Params { changes } => json!({
+                "type": "params",
+                "changes": changes.iter().map(|(field, old, new)| json!({ "field": field, "old": old, "new": new })).collect::<Vec<_>>(),
+            }),
+            Event::Proposal { activation_slot } => json!({ "type": "proposal", "activation_slot": activation_slot }),
+            Event::Flags { old, new } => json!({ "type": "flags", "old": old, "new": new }),
+            Event::Gone { reason } => json!({ "type": "gone", "reason": reason }),
+        };
+        json!({ "context": self.context.to_string(), "slot": self.slot, "event": event })
+    }
+}
+
+/// Subscriptions to a set of contexts, and the last reading of each.
+pub struct Watcher {
+    url: String,
+    commitment: String,
+    contexts: Vec<Pubkey>,
+    last: HashMap<Pubkey, MatcherContext>,
+}
+
+impl Watcher {
+    /// `url` is the pubsub endpoint; see `ws::ws_url` for deriving it.
+    pub fn new(url: &str, commitment: &str, contexts: Vec<Pubkey>) -> Self {
+        Self { url: url.to_string(), commitment: commitment.to_string(), contexts, last: HashMap::new() }
+    }
+
+    /// Subscribe and deliver events until the connection ends. The last
+    /// readings survive a return, so calling again after a drop resumes the
+    /// diff instead of reporting every context as loaded.
+    pub fn run(&mut self, mut on_event: impl FnMut(ContextEvent)) -> Result<()> {
+        let mut socket = Socket::connect(&self.url)?;
+        for (i, context) in self.contexts.iter().enumerate() {
+            let request = json!({
+                "jsonrpc": "2.0",
+                "id": i,
+                "method": "accountSubscribe",
+                "params": [context.to_string(), { "encoding": "base64", "commitment": self.commitment }],
+            });
+            socket.send_text(&request.to_string())?;
+        }
+
+        let mut subscriptions = HashMap::new();
+        while let Some(text) = socket.recv_text()? {
+            let message: Value = serde_json::from_str(&text)?;
+            if let Some(err) = message.get("error") {
+                return Err(format!("accountSubscribe: {}", err["message"].as_str().unwrap_or("unknown error")).into());
+            }
+            if let (Some(id), Some(subscription)) = (message["id"].as_u64(), message["result"].as_u64()) {
+                let context = self.contexts.get(id as usize).ok_or("subscription for an unknown request")?;
+                subscriptions.insert(subscription, *context);
+                continue;
+            }
+            if message["method"] != "accountNotification" {
+                continue;
+            }
+            let params = &message["params"];
+            let Some(context) = params["subscription"].as_u64().and_then(|s| subscriptions.get(&s)).copied() else {
+                continue;
+            };
+            let slot = params["result"]["context"]["slot"].as_u64().unwrap_or_default();
+            for event in self.update(&context, &params["result"]["value"]) {
+                on_event(ContextEvent { context, slot, event });
+            }
+        }
+        Ok(())
+    }
+
+    /// Run on a thread, reconnecting `retry` after every drop, and stream the
+    /// events. Connection errors arrive on the channel too; the thread ends
+    /// when the receiver is dropped.
+    pub fn spawn(mut self, retry: Duration) -> Receiver<std::result::Result<ContextEvent, String>> {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || loop {
+            let mut open = true;
+            let result = self.run(|event| open &= tx.send(Ok(event)).is_ok());
+            let reason = match result {
+                Ok(()) => "connection closed".to_string(),
+                Err(err) => err.to_string(),
+            };
+            if !open || tx.send(Err(reason)).is_err() {
+                return;
+            }
+            thread::sleep(retry);
+        });
+        rx
+    }
+
+    /// Apply one account notification and return what changed.
+    fn update(&mut self, context: &Pubkey, value: &Value) -> Vec<Event> {
+        let data = value["data"][0].as_str().map(|d| BASE64.decode(d));
+        let decoded = match data {
+            _ if value["lamports"].as_u64() == Some(0) => Err("account closed".to_string()),
+            Some(Ok(data)) => MatcherContext::from_bytes(&data).map_err(|e| e.to_string()),
+            Some(Err(err)) => Err(format!("bad account data: {err}")),
+            None => Err("account closed".to_string()),
+        };
+        match decoded {
+            Ok(ctx) => {
+                let events = diff(self.last.get(context), &ctx);
+                self.last.insert(*context, ctx);
+                events
+            }
+            // Only report the first time; a context that stays gone is quiet
+            Err(reason) => match self.last.remove(context) {
+                Some(_) => vec![Event::Gone { reason }],
+                None => Vec::new(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use provenance_client::{layout::CTX_FLAG_PAUSED, ContextExtension, MatcherParams};
+
+    fn context() -> MatcherContext {
+        MatcherContext {
+            params: MatcherParams { max_spread_bps: 200, ..Default::default() },
+            inventory: 100,
+            snapshot_slot: 10,
+            extension: Some(ContextExtension { fill_seq: 4, ..Default::default() }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = context();
+        assert_eq!(diff(None, &old), [Event::Loaded { inventory: 100, snapshot_slot: 10 }]);
+        assert!(diff(Some(&old), &old).is_empty());
+
+        let mut new = old;
+        new.inventory = 40;
+        new.extension.as_mut().unwrap().fill_seq = 6;
+        new.snapshot_slot = 20;
+        new.insurance_snapshot = 50;
+        new.total_oi_snapshot = 100;
+        new.params.max_spread_bps = 300;
+        new.extension.as_mut().unwrap().pending_activation_slot = 99;
+        new.flags = CTX_FLAG_PAUSED;
+        assert_eq!(
+            diff(Some(&old), &new),
+            [
+                Event::Inventory { delta: -60, inventory: 40, fills: Some(2) },
+                Event::Snapshot { snapshot_slot: 20, insurance: 50, total_oi: 100, coverage_bps: 5_000 },
+                Event::Params { changes: vec![("max_spread_bps", "200".into(), "300".into())] },
+                Event::Proposal { activation_slot: 99 },
+                Event::Flags { old: 0, new: CTX_FLAG_PAUSED },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_update_from_notifications() {
+        let key = Pubkey::new_unique();
+        let mut watcher = Watcher::new("ws://localhost:8900", "confirmed", vec![key]);
+        let value = |ctx: &MatcherContext| json!({ "lamports": 1, "data": [BASE64.encode(ctx.to_bytes()), "base64"] });
+
+        let mut ctx = context();
+        assert!(matches!(watcher.update(&key, &value(&ctx))[..], [Event::Loaded { .. }]));
+        ctx.inventory = 150;
+        assert!(matches!(watcher.update(&key, &value(&ctx))[..], [Event::Inventory { delta: 50, .. }]));
+        let closed = json!({ "lamports": 0, "data": ["", "base64"] });
+        assert_eq!(watcher.update(&key, &closed), [Event::Gone { reason: "account closed".into() }]);
+        assert!(watcher.update(&key, &closed).is_empty());
+
+        let event = ContextEvent { context: key, slot: 5, event: Event::Flags { old: 0, new: 1 } };
+        assert_eq!(event.to_json()["event"]["type"], "flags");
+    }
+}
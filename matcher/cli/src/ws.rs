@@ -0,0 +1,276 @@
+//! Just enough WebSocket (RFC 6455) for JSON-RPC subscriptions: a client
+//! handshake over TCP or TLS, masked text frames out, text frames in, and
+//! the ping/pong and close handling a long-lived connection needs.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    io::{Read, Write},
+    net::TcpStream,
+    sync::Arc,
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use rustls::{ClientConfig, ClientConnection, OwnedTrustAnchor, RootCertStore, ServerName, StreamOwned};
+
+use crate::Result;
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+/// Largest response header or message accepted from the server.
+const MAX_HEADER_LEN: usize = 16 * 1024;
+const MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+
+trait Stream: Read + Write + Send {}
+impl<T: Read + Write + Send> Stream for T {}
+
+pub struct Socket {
+    stream: Box<dyn Stream>,
+}
+
+/// The pubsub endpoint for an RPC URL: `ws`/`wss` for `http`/`https`, and
+/// an explicit port one higher, as solana-test-validator and most nodes
+/// serve it (8899 -> 8900).
+pub fn ws_url(rpc_url: &str) -> Result<String> {
+    let mut url = url::Url::parse(rpc_url)?;
+    let scheme = match url.scheme() {
+        "http" | "ws" => "ws",
+        "https" | "wss" => "wss",
+        other => return Err(format!("{rpc_url}: unsupported scheme {other}").into()),
+    };
+    url.set_scheme(scheme).map_err(|_| format!("{rpc_url}: can't use {scheme}"))?;
+    if let Some(port) = url.port() {
+        url.set_port(Some(port + 1)).map_err(|_| format!("{rpc_url}: bad port"))?;
+    }
+    Ok(url.into())
+}
+
+impl Socket {
+    /// Open a `ws://` or `wss://` URL and complete the upgrade handshake.
+    pub fn connect(url: &str) -> Result<Self> {
+        let url = url::Url::parse(url)?;
+        let host = url.host_str().ok_or_else(|| format!("{url}: no host"))?.to_string();
+        let tls = match url.scheme() {
+            "ws" => false,
+            "wss" => true,
+            other => return Err(format!("{url}: expected ws or wss, not {other}").into()),
+        };
+        let port = url.port().unwrap_or(if tls { 443 } else { 80 });
+        let tcp = TcpStream::connect((host.as_str(), port))?;
+        tcp.set_nodelay(true)?;
+        let stream: Box<dyn Stream> = if tls {
+            let name = ServerName::try_from(host.as_str()).map_err(|_| format!("{host}: invalid server name"))?;
+            Box::new(StreamOwned::new(ClientConnection::new(tls_config(), name)?, tcp))
+        } else {
+            Box::new(tcp)
+        };
+        let mut socket = Self { stream };
+
+        let path = match url.query() {
+            Some(query) => format!("{}?{query}", url.path()),
+            None => url.path().to_string(),
+        };
+        let host_header = match url.port() {
+            Some(port) => format!("{host}:{port}"),
+            None => host,
+        };
+        let key = BASE64.encode(random::<16>());
+        write!(
+            socket.stream,
+            "GET {path} HTTP/1.1\r\nHost: {host_header}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: {key}\r\nSec-WebSocket-Version: 13\r\n\r\n"
+        )?;
+        socket.stream.flush()?;
+
+        let header = socket.read_header()?;
+        let status = header.lines().next().unwrap_or_default();
+        if status.split_whitespace().nth(1) != Some("101") {
+            return Err(format!("{url}: upgrade refused: {status}").into());
+        }
+        Ok(socket)
+    }
+
+    pub fn send_text(&mut self, text: &str) -> Result<()> {
+        self.write_frame(OP_TEXT, text.as_bytes())
+    }
+
+    /// The next text message, answering pings on the way. `None` once the
+    /// server has closed the connection.
+    pub fn recv_text(&mut self) -> Result<Option<String>> {
+        let mut message = Vec::new();
+        loop {
+            let (fin, opcode, payload) = self.read_frame()?;
+            match opcode {
+                OP_TEXT | OP_CONTINUATION => {
+                    message.extend_from_slice(&payload);
+                    if message.len() > MAX_MESSAGE_LEN {
+                        return Err("websocket message too large".into());
+                    }
+                    if fin {
+                        return Ok(Some(String::from_utf8(message)?));
+                    }
+                }
+                OP_PING => self.write_frame(OP_PONG, &payload)?,
+                OP_PONG => {}
+                OP_CLOSE => {
+                    let _ = self.write_frame(OP_CLOSE, &payload);
+                    return Ok(None);
+                }
+                other => return Err(format!("unexpected websocket opcode {other:#x}").into()),
+            }
+        }
+    }
+
+    fn read_header(&mut self) -> Result<String> {
+        let mut header = Vec::new();
+        let mut byte = [0u8; 1];
+        while !header.ends_with(b"\r\n\r\n") {
+            if header.len() > MAX_HEADER_LEN {
+                return Err("websocket handshake response too large".into());
+            }
+            self.stream.read_exact(&mut byte)?;
+            header.push(byte[0]);
+        }
+        Ok(String::from_utf8_lossy(&header).into_owned())
+    }
+
+    fn read_frame(&mut self) -> Result<(bool, u8, Vec<u8>)> {
+        let mut head = [0u8; 2];
+        self.stream.read_exact(&mut head)?;
+        let fin = head[0] & 0x80 != 0;
+        let opcode = head[0] & 0x0F;
+        let masked = head[1] & 0x80 != 0;
+        let len = match head[1] & 0x7F {
+            126 => {
+                let mut len = [0u8; 2];
+                self.stream.read_exact(&mut len)?;
+                u16::from_be_bytes(len) as u64
+            }
+            127 => {
+                let mut len = [0u8; 8];
+                self.stream.read_exact(&mut len)?;
+                u64::from_be_bytes(len)
+            }
+            len => len as u64,
+        };
+        if len > MAX_MESSAGE_LEN as u64 {
+            return Err("websocket frame too large".into());
+        }
+        let mut mask = [0u8; 4];
+        if masked {
+            self.stream.read_exact(&mut mask)?;
+        }
+        let mut payload = vec![0u8; len as usize];
+        self.stream.read_exact(&mut payload)?;
+        if masked {
+            apply_mask(&mut payload, mask);
+        }
+        Ok((fin, opcode, payload))
+    }
+
+    fn write_frame(&mut self, opcode: u8, payload: &[u8]) -> Result<()> {
+        let mut frame = Vec::with_capacity(payload.len() + 14);
+        frame.push(0x80 | opcode);
+        // Client frames are always masked
+        match payload.len() {
+            len @ 0..=125 => frame.push(0x80 | len as u8),
+            len @ 126..=0xFFFF => {
+                frame.push(0x80 | 126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+            len => {
+                frame.push(0x80 | 127);
+                frame.extend_from_slice(&(len as u64).to_be_bytes());
+            }
+        }
+        let mask = random::<4>();
+        frame.extend_from_slice(&mask);
+        let start = frame.len();
+        frame.extend_from_slice(payload);
+        apply_mask(&mut frame[start..], mask);
+        self.stream.write_all(&frame)?;
+        self.stream.flush()?;
+        Ok(())
+    }
+}
+
+fn apply_mask(data: &mut [u8], mask: [u8; 4]) {
+    for (i, byte) in data.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+}
+
+/// Unpredictable enough for handshake keys and frame masks.
+fn random<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    for chunk in bytes.chunks_mut(8) {
+        let word = RandomState::new().build_hasher().finish().to_le_bytes();
+        chunk.copy_from_slice(&word[..chunk.len()]);
+    }
+    bytes
+}
+
+fn tls_config() -> Arc<ClientConfig> {
+    let mut roots = RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(ta.subject, ta.spki, ta.name_constraints)
+    }));
+    Arc::new(ClientConfig::builder().with_safe_defaults().with_root_certificates(roots).with_no_client_auth())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{io::BufRead, net::TcpListener, thread};
+
+    #[test]
+    fn test_ws_url() {
+        assert_eq!(ws_url("https://api.devnet.solana.com").unwrap(), "wss://api.devnet.solana.com/");
+        assert_eq!(ws_url("http://127.0.0.1:8899").unwrap(), "ws://127.0.0.1:8900/");
+        assert!(ws_url("ftp://example.com").is_err());
+    }
+
+    #[test]
+    fn test_roundtrip_against_a_local_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while line != "\r\n" {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+            }
+            stream.write_all(b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\r\n").unwrap();
+
+            // One masked text frame from the client
+            let mut head = [0u8; 6];
+            reader.read_exact(&mut head).unwrap();
+            assert_eq!((head[0], head[1] & 0x80), (0x81, 0x80));
+            let mut payload = vec![0u8; (head[1] & 0x7F) as usize];
+            reader.read_exact(&mut payload).unwrap();
+            apply_mask(&mut payload, head[2..6].try_into().unwrap());
+            assert_eq!(payload, b"subscribe");
+
+            // A ping, a message split over two frames, then close
+            stream.write_all(&[0x89, 2, b'h', b'i']).unwrap();
+            stream.write_all(&[0x01, 3, b'a', b'b', b'c']).unwrap();
+            stream.write_all(&[0x80, 2, b'd', b'e']).unwrap();
+            stream.write_all(&[0x88, 0]).unwrap();
+            let mut pong = [0u8; 8];
+            reader.read_exact(&mut pong).unwrap();
+            assert_eq!(pong[0], 0x8A);
+        });
+
+        let mut socket = Socket::connect(&format!("ws://{addr}/")).unwrap();
+        socket.send_text("subscribe").unwrap();
+        assert_eq!(socket.recv_text().unwrap().as_deref(), Some("abcde"));
+        assert_eq!(socket.recv_text().unwrap(), None);
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,1452 @@
+//! Typed view of a matcher context account, for off-chain readers.
+//!
+//! [`MatcherContext::from_bytes`] takes the whole account (v5 or v6) and
+//! [`MatcherContext::to_bytes`] writes it back byte for byte; padding and the
+//! reserved tail are always zero on accounts the program wrote.
+
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::layout::*;
+use crate::params::MatcherParams;
+
+/// The 64-byte MatcherReturn at the front of the account (and of Match's
+/// return data).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MatcherReturn {
+    pub abi_version: u32,
+    pub flags: u32,
+    pub exec_price_e6: u64,
+    pub exec_size: i128,
+    pub req_id: u64,
+    pub lp_account_id: u64,
+    pub oracle_price_e6: u64,
+    /// MatcherError code of a soft reject, 0 on a fill.
+    pub reject_reason: u64,
+}
+
+impl MatcherReturn {
+    /// Decode the first RET_LEN bytes of `data`.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < RET_LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        Ok(Self {
+            abi_version: read_u32(data, RET_ABI_VERSION_OFF),
+            flags: read_u32(data, RET_FLAGS_OFF),
+            exec_price_e6: read_u64(data, RET_EXEC_PRICE_OFF),
+            exec_size: read_i128(data, RET_EXEC_SIZE_OFF),
+            req_id: read_u64(data, RET_REQ_ID_OFF),
+            lp_account_id: read_u64(data, RET_LP_ACCOUNT_ID_OFF),
+            oracle_price_e6: read_u64(data, RET_ORACLE_ECHO_OFF),
+            reject_reason: read_u64(data, RET_REJECT_REASON_OFF),
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; RET_LEN] {
+        let mut data = [0u8; RET_LEN];
+        write(&mut data, RET_ABI_VERSION_OFF, &self.abi_version.to_le_bytes());
+        write(&mut data, RET_FLAGS_OFF, &self.flags.to_le_bytes());
+        write(&mut data, RET_EXEC_PRICE_OFF, &self.exec_price_e6.to_le_bytes());
+        write(&mut data, RET_EXEC_SIZE_OFF, &self.exec_size.to_le_bytes());
+        write(&mut data, RET_REQ_ID_OFF, &self.req_id.to_le_bytes());
+        write(&mut data, RET_LP_ACCOUNT_ID_OFF, &self.lp_account_id.to_le_bytes());
+        write(&mut data, RET_ORACLE_ECHO_OFF, &self.oracle_price_e6.to_le_bytes());
+        write(&mut data, RET_REJECT_REASON_OFF, &self.reject_reason.to_le_bytes());
+        data
+    }
+}
+
+/// A signal account: an outside party's spread surcharge. A context's
+/// schedule registers the ones it prices off with SetSignals, and Match and
+/// the quotes take exactly those after the stats account.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SignalAccount {
+    pub value_bps: u32,
+    /// Share of `value_bps` charged, at most 10000.
+    pub weight_bps: u16,
+    /// Last slot the signal counts in.
+    pub stale_after_slot: u64,
+}
+
+impl SignalAccount {
+    /// Decode a signal account; anything without SIGNAL_MAGIC is rejected.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < SIGNAL_LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        if read_u64(data, SIGNAL_MAGIC_OFF) != SIGNAL_MAGIC {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self {
+            value_bps: read_u32(data, SIGNAL_VALUE_OFF),
+            weight_bps: read_u16(data, SIGNAL_WEIGHT_OFF),
+            stale_after_slot: read_u64(data, SIGNAL_STALE_AFTER_OFF),
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; SIGNAL_LEN] {
+        let mut data = [0u8; SIGNAL_LEN];
+        write(&mut data, SIGNAL_MAGIC_OFF, &SIGNAL_MAGIC.to_le_bytes());
+        write(&mut data, SIGNAL_VALUE_OFF, &self.value_bps.to_le_bytes());
+        write(&mut data, SIGNAL_WEIGHT_OFF, &self.weight_bps.to_le_bytes());
+        write(&mut data, SIGNAL_STALE_AFTER_OFF, &self.stale_after_slot.to_le_bytes());
+        data
+    }
+
+    /// What the signal adds to the spread at `slot`: 0 once it is stale.
+    pub fn surcharge_bps(&self, slot: u64) -> u64 {
+        if slot > self.stale_after_slot {
+            return 0;
+        }
+        self.value_bps as u64 * self.weight_bps as u64 / 10_000
+    }
+}
+
+/// The fee ledger at the end of a stats account of STATS_LEDGER_LEN bytes:
+/// the withdrawals RecordWithdrawal recorded against the context's cum_fees.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeeLedger {
+    pub withdrawn: u128,
+    pub withdrawals: u64,
+    /// Slot of the last withdrawal; 0 before the first.
+    pub last_withdrawal_slot: u64,
+}
+
+impl FeeLedger {
+    /// Decode the ledger of a stats account; a shorter one has none.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < STATS_LEDGER_LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        if read_u64(data, STATS_MAGIC_OFF) != STATS_MAGIC {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self {
+            withdrawn: read_u128(data, STATS_WITHDRAWN_OFF),
+            withdrawals: read_u64(data, STATS_WITHDRAWALS_OFF),
+            last_withdrawal_slot: read_u64(data, STATS_LAST_WITHDRAWAL_OFF),
+        })
+    }
+
+    /// What is left to withdraw of the fees a context accrued.
+    pub fn available(&self, cum_fees: u128) -> u128 {
+        cum_fees.saturating_sub(self.withdrawn)
+    }
+}
+
+/// The insurance skim at the end of a stats account of STATS_SKIM_LEN bytes:
+/// the share of each fill's spread Match earmarks for the insurance fund, and
+/// what ClaimSkim has paid in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InsuranceSkim {
+    pub skim_bps: u16,
+    pub accrued: u128,
+    pub claimed: u128,
+    pub claims: u64,
+}
+
+impl InsuranceSkim {
+    /// Decode the skim of a stats account; a shorter one has none.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < STATS_SKIM_LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        if read_u64(data, STATS_MAGIC_OFF) != STATS_MAGIC {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self {
+            skim_bps: read_u16(data, STATS_SKIM_BPS_OFF),
+            accrued: read_u128(data, STATS_SKIM_ACCRUED_OFF),
+            claimed: read_u128(data, STATS_SKIM_CLAIMED_OFF),
+            claims: read_u64(data, STATS_SKIM_CLAIMS_OFF),
+        })
+    }
+
+    /// What ClaimSkim can still pay in.
+    pub fn claimable(&self) -> u128 {
+        self.accrued.saturating_sub(self.claimed)
+    }
+}
+
+/// The protocol fee at the end of a stats account of STATS_PROTOCOL_LEN
+/// bytes: the treasury InitStats named, its cut of each fill's fee, and what
+/// SweepProtocolFee has recorded it taking.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProtocolFee {
+    pub treasury: Pubkey,
+    pub fee_bps: u16,
+    pub accrued: u128,
+    pub swept: u128,
+    pub sweeps: u64,
+}
+
+impl ProtocolFee {
+    /// Decode the protocol fee of a stats account; a shorter one has none.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < STATS_PROTOCOL_LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        if read_u64(data, STATS_MAGIC_OFF) != STATS_MAGIC {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self {
+            treasury: read_pubkey(data, STATS_TREASURY_OFF),
+            fee_bps: read_u16(data, STATS_PROTOCOL_FEE_BPS_OFF),
+            accrued: read_u128(data, STATS_PROTOCOL_ACCRUED_OFF),
+            swept: read_u128(data, STATS_PROTOCOL_SWEPT_OFF),
+            sweeps: read_u64(data, STATS_PROTOCOL_SWEEPS_OFF),
+        })
+    }
+
+    /// What SweepProtocolFee can still record.
+    pub fn sweepable(&self) -> u128 {
+        self.accrued.saturating_sub(self.swept)
+    }
+}
+
+/// The book marked to the oracle at the end of a stats account of
+/// STATS_PNL_LEN bytes, as of the last fill that passed it; PnL is in quote
+/// units.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InventoryPnl {
+    /// Average execution price of the inventory held; 0 when flat.
+    pub entry_price_e6: u64,
+    /// The oracle the last fill was marked at.
+    pub mark_price_e6: u64,
+    pub realized: i128,
+    pub unrealized: i128,
+}
+
+impl InventoryPnl {
+    /// Decode the PnL of a stats account; a shorter one tracks none.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < STATS_PNL_LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        if read_u64(data, STATS_MAGIC_OFF) != STATS_MAGIC {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self {
+            entry_price_e6: read_u64(data, STATS_PNL_ENTRY_OFF),
+            mark_price_e6: read_u64(data, STATS_PNL_MARK_OFF),
+            realized: read_i128(data, STATS_PNL_REALIZED_OFF),
+            unrealized: read_i128(data, STATS_PNL_UNREALIZED_OFF),
+        })
+    }
+
+    /// Realized and unrealized together.
+    pub fn total(&self) -> i128 {
+        self.realized.saturating_add(self.unrealized)
+    }
+}
+
+/// A tip jar: what UpdateCredibility pays a keeper for refreshing a stale
+/// snapshot, out of the jar's lamports above rent exemption.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TipJar {
+    /// The context InitTipJar bound it to.
+    pub ctx: Pubkey,
+    /// Paid per slot the snapshot was stale past SNAPSHOT_GRACE_SLOTS.
+    pub lamports_per_slot: u64,
+    pub max_tip: u64,
+    /// Lamports paid out so far, over `tips` tips.
+    pub paid: u64,
+    pub tips: u64,
+}
+
+impl TipJar {
+    /// Decode a tip jar; anything without TIP_JAR_MAGIC is rejected.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < TIP_JAR_LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        if read_u64(data, TIP_JAR_MAGIC_OFF) != TIP_JAR_MAGIC {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self {
+            ctx: read_pubkey(data, TIP_JAR_CTX_OFF),
+            lamports_per_slot: read_u64(data, TIP_JAR_PER_SLOT_OFF),
+            max_tip: read_u64(data, TIP_JAR_MAX_OFF),
+            paid: read_u64(data, TIP_JAR_PAID_OFF),
+            tips: read_u64(data, TIP_JAR_COUNT_OFF),
+        })
+    }
+
+    /// Encode as account data, TIP_JAR_LEN bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = vec![0u8; TIP_JAR_LEN];
+        write(&mut data, TIP_JAR_MAGIC_OFF, &TIP_JAR_MAGIC.to_le_bytes());
+        write(&mut data, TIP_JAR_VERSION_OFF, &TIP_JAR_VERSION.to_le_bytes());
+        write(&mut data, TIP_JAR_CTX_OFF, self.ctx.as_ref());
+        write(&mut data, TIP_JAR_PER_SLOT_OFF, &self.lamports_per_slot.to_le_bytes());
+        write(&mut data, TIP_JAR_MAX_OFF, &self.max_tip.to_le_bytes());
+        write(&mut data, TIP_JAR_PAID_OFF, &self.paid.to_le_bytes());
+        write(&mut data, TIP_JAR_COUNT_OFF, &self.tips.to_le_bytes());
+        data
+    }
+}
+
+/// What a referral account has credited one referrer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Referrer {
+    pub referrer: Pubkey,
+    /// Its share of the fees on the fills it referred, in the notional units
+    /// of the context's cum_fees.
+    pub accrued_fees: u128,
+    pub fills: u64,
+}
+
+/// A referral account: the share of each fill's fee Match credits to the
+/// referrer named in its data, and what each referrer has been credited.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Referrals {
+    /// The context InitReferral bound it to.
+    pub ctx: Pubkey,
+    pub share_bps: u16,
+    /// In the order the referrers first showed up.
+    pub referrers: Vec<Referrer>,
+}
+
+impl Referrals {
+    /// Decode a referral account; anything without REFERRAL_MAGIC is rejected.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < REFERRAL_LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        if read_u64(data, REFERRAL_MAGIC_OFF) != REFERRAL_MAGIC {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let count = (read_u16(data, REFERRAL_COUNT_OFF) as usize).min(MAX_REFERRERS);
+        let referrers = data[REFERRAL_HEADER_LEN..REFERRAL_LEN]
+            .chunks_exact(REFERRER_LEN)
+            .take(count)
+            .map(|entry| Referrer {
+                referrer: read_pubkey(entry, REFERRER_KEY_OFF),
+                accrued_fees: read_u128(entry, REFERRER_ACCRUED_OFF),
+                fills: read_u64(entry, REFERRER_FILLS_OFF),
+            })
+            .collect();
+        Ok(Self { ctx: read_pubkey(data, REFERRAL_CTX_OFF), share_bps: read_u16(data, REFERRAL_SHARE_OFF), referrers })
+    }
+
+    /// Encode as account data, REFERRAL_LEN bytes; referrers past
+    /// MAX_REFERRERS are dropped.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = vec![0u8; REFERRAL_LEN];
+        let referrers = &self.referrers[..self.referrers.len().min(MAX_REFERRERS)];
+        write(&mut data, REFERRAL_MAGIC_OFF, &REFERRAL_MAGIC.to_le_bytes());
+        write(&mut data, REFERRAL_VERSION_OFF, &REFERRAL_VERSION.to_le_bytes());
+        write(&mut data, REFERRAL_CTX_OFF, self.ctx.as_ref());
+        write(&mut data, REFERRAL_SHARE_OFF, &self.share_bps.to_le_bytes());
+        write(&mut data, REFERRAL_COUNT_OFF, &(referrers.len() as u16).to_le_bytes());
+        for (referrer, out) in referrers.iter().zip(data[REFERRAL_HEADER_LEN..].chunks_exact_mut(REFERRER_LEN)) {
+            write(out, REFERRER_KEY_OFF, referrer.referrer.as_ref());
+            write(out, REFERRER_ACCRUED_OFF, &referrer.accrued_fees.to_le_bytes());
+            write(out, REFERRER_FILLS_OFF, &referrer.fills.to_le_bytes());
+        }
+        data
+    }
+
+    /// What `referrer` has been credited, None if it referred nothing yet.
+    pub fn get(&self, referrer: &Pubkey) -> Option<&Referrer> {
+        self.referrers.iter().find(|r| r.referrer == *referrer)
+    }
+}
+
+/// A trader account: the volume Match recorded for one trader's fills with
+/// one context, which the schedule's loyalty tiers discount the fee by.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TraderAccount {
+    /// The context InitTrader bound it to.
+    pub ctx: Pubkey,
+    /// The trader, who signs every Match it is passed to.
+    pub trader: Pubkey,
+    /// Notional of the fills recorded, at their exec prices.
+    pub volume: u128,
+    pub fills: u64,
+    /// Slot of the last fill; 0 before the first.
+    pub last_slot: u64,
+}
+
+impl TraderAccount {
+    /// Decode a trader account; anything without TRADER_MAGIC is rejected.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < TRADER_LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        if read_u64(data, TRADER_MAGIC_OFF) != TRADER_MAGIC {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self {
+            ctx: read_pubkey(data, TRADER_CTX_OFF),
+            trader: read_pubkey(data, TRADER_KEY_OFF),
+            volume: read_u128(data, TRADER_VOLUME_OFF),
+            fills: read_u64(data, TRADER_FILLS_OFF),
+            last_slot: read_u64(data, TRADER_LAST_SLOT_OFF),
+        })
+    }
+}
+
+/// One slot range of a spread schedule, both ends included.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScheduleEntry {
+    pub start_slot: u64,
+    pub end_slot: u64,
+    /// What the spread is multiplied by, in bps: 10000 = 1x, never less.
+    pub multiplier_bps: u32,
+}
+
+/// One fee tier: trades of at least `min_notional` at the oracle pay
+/// `fee_bps` in place of base_fee_bps.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeeTier {
+    pub min_notional: u128,
+    pub fee_bps: u32,
+}
+
+/// One loyalty tier: a trader whose account has traded at least `min_volume`
+/// with the context gets `discount_bps` off the fee.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LoyaltyTier {
+    pub min_volume: u128,
+    pub discount_bps: u32,
+}
+
+/// A signal account a schedule registers, and the program that owned it when
+/// SetSignals did.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RegisteredSignal {
+    pub signal: Pubkey,
+    pub owner: Pubkey,
+}
+
+/// A schedule account: the slot ranges over which its context's spread is
+/// multiplied, set by the authority ahead of known events, and on an account
+/// of SCHEDULE_FEES_LEN bytes the notional fee tiers (SCHEDULE_REBATE_LEN, the
+/// rebate and fee discount too; SCHEDULE_MIN_NOTIONAL_LEN, the minimum
+/// notional as well; SCHEDULE_LOYALTY_LEN, the loyalty tiers as well).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SpreadSchedule {
+    /// The context InitSchedule bound it to.
+    pub ctx: Pubkey,
+    pub entries: Vec<ScheduleEntry>,
+    /// Rising in min_notional; empty on an account too short for them.
+    pub fee_tiers: Vec<FeeTier>,
+    /// Taken off the cost of fills that shrink |inventory|; 0 on an account
+    /// too short for it.
+    pub rebate_bps: u16,
+    /// Share of the fee waived at the full credibility discount; 0 on an
+    /// account too short for it.
+    pub fee_discount_bps: u16,
+    /// Trades whose notional at the oracle falls below it are refused; 0 on
+    /// an account too short for it.
+    pub min_notional_e6: u128,
+    /// Rising in min_volume; empty on an account too short for them.
+    pub loyalty_tiers: Vec<LoyaltyTier>,
+    /// Slots over which the inventory the imbalance and skew penalties see
+    /// halves without a fill; 0 on an account too short for it.
+    pub inventory_halflife: u32,
+    /// Past it, fills that take the inventory deeper pay up to band_k_bps
+    /// more; 0 (no band) on an account too short for it.
+    pub soft_inventory: u128,
+    pub band_k_bps: u32,
+    /// Caps on the LP's long book (negative inventory) and short book
+    /// (positive); 0 (max_inventory) on an account too short for them.
+    pub max_long_inventory: u128,
+    pub max_short_inventory: u128,
+    /// Share of the side's cap, in bps, past which the book goes reduce-only
+    /// on its own; 0 (AUTO_REDUCE_ONLY_BPS) on an account too short for it.
+    pub reduce_only_bps: u16,
+    /// max_inventory in bps of liquidity_notional_e6, at the oracle; 0
+    /// (max_inventory as set) on an account too short for it.
+    pub max_inventory_bps: u32,
+    /// The signal accounts every trade must pass, in order; empty on an
+    /// account too short for them.
+    pub signals: Vec<RegisteredSignal>,
+}
+
+impl SpreadSchedule {
+    /// Decode a schedule account; anything without SCHEDULE_MAGIC is rejected.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < SCHEDULE_LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        if read_u64(data, SCHEDULE_MAGIC_OFF) != SCHEDULE_MAGIC {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let count = (read_u32(data, SCHEDULE_COUNT_OFF) as usize).min(MAX_SCHEDULE_ENTRIES);
+        let entries = data[SCHEDULE_HEADER_LEN..SCHEDULE_LEN]
+            .chunks_exact(SCHED_ENTRY_LEN)
+            .take(count)
+            .map(|entry| ScheduleEntry {
+                start_slot: read_u64(entry, SCHED_START_OFF),
+                end_slot: read_u64(entry, SCHED_END_OFF),
+                multiplier_bps: read_u32(entry, SCHED_MULT_OFF),
+            })
+            .collect();
+        let fee_tiers = data
+            .get(FEE_TIERS_OFF..SCHEDULE_FEES_LEN)
+            .unwrap_or_default()
+            .chunks_exact(FEE_TIER_LEN)
+            .map(|tier| FeeTier {
+                min_notional: read_u128(tier, FEE_TIER_NOTIONAL_OFF),
+                fee_bps: read_u32(tier, FEE_TIER_FEE_OFF),
+            })
+            .filter(|tier| tier.min_notional != 0)
+            .collect();
+        let (rebate_bps, fee_discount_bps) = if data.len() < SCHEDULE_REBATE_LEN {
+            (0, 0)
+        } else {
+            (read_u16(data, SCHEDULE_REBATE_OFF), read_u16(data, SCHEDULE_FEE_DISCOUNT_OFF))
+        };
+        let min_notional_e6 = if data.len() < SCHEDULE_MIN_NOTIONAL_LEN { 0 } else { read_u128(data, SCHEDULE_MIN_NOTIONAL_OFF) };
+        let loyalty_tiers = data
+            .get(LOYALTY_TIERS_OFF..SCHEDULE_LOYALTY_LEN)
+            .unwrap_or_default()
+            .chunks_exact(LOYALTY_TIER_LEN)
+            .map(|tier| LoyaltyTier {
+                min_volume: read_u128(tier, LOYALTY_TIER_VOLUME_OFF),
+                discount_bps: read_u32(tier, LOYALTY_TIER_DISCOUNT_OFF),
+            })
+            .filter(|tier| tier.min_volume != 0)
+            .collect();
+        let inventory_halflife = if data.len() < SCHEDULE_DECAY_LEN { 0 } else { read_u32(data, SCHEDULE_INVENTORY_HALFLIFE_OFF) };
+        let (soft_inventory, band_k_bps) = if data.len() < SCHEDULE_BAND_LEN {
+            (0, 0)
+        } else {
+            (read_u128(data, SCHEDULE_SOFT_INVENTORY_OFF), read_u32(data, SCHEDULE_BAND_K_OFF))
+        };
+        let (max_long_inventory, max_short_inventory) = if data.len() < SCHEDULE_CAPS_LEN {
+            (0, 0)
+        } else {
+            (read_u128(data, SCHEDULE_MAX_LONG_OFF), read_u128(data, SCHEDULE_MAX_SHORT_OFF))
+        };
+        let reduce_only_bps = if data.len() < SCHEDULE_REDUCE_ONLY_LEN { 0 } else { read_u16(data, SCHEDULE_REDUCE_ONLY_BPS_OFF) };
+        let max_inventory_bps = if data.len() < SCHEDULE_DEPTH_CAP_LEN { 0 } else { read_u32(data, SCHEDULE_MAX_INVENTORY_BPS_OFF) };
+        let signal_count = data.get(SCHEDULE_SIGNAL_COUNT_OFF).filter(|_| data.len() >= SCHEDULE_SIGNALS_LEN).copied().unwrap_or(0);
+        let signals = data
+            .get(SCHEDULE_SIGNALS_OFF..SCHEDULE_SIGNALS_LEN)
+            .unwrap_or_default()
+            .chunks_exact(SIGNAL_ENTRY_LEN)
+            .take(signal_count as usize)
+            .map(|entry| RegisteredSignal {
+                signal: read_pubkey(entry, SIGNAL_ENTRY_KEY_OFF),
+                owner: read_pubkey(entry, SIGNAL_ENTRY_OWNER_OFF),
+            })
+            .collect();
+        Ok(Self {
+            ctx: read_pubkey(data, SCHEDULE_CTX_OFF),
+            entries,
+            fee_tiers,
+            rebate_bps,
+            fee_discount_bps,
+            min_notional_e6,
+            loyalty_tiers,
+            inventory_halflife,
+            soft_inventory,
+            band_k_bps,
+            max_long_inventory,
+            max_short_inventory,
+            reduce_only_bps,
+            max_inventory_bps,
+            signals,
+        })
+    }
+
+    /// Encode as account data: SCHEDULE_LEN bytes, SCHEDULE_FEES_LEN with fee
+    /// tiers, SCHEDULE_REBATE_LEN with a rebate or fee discount,
+    /// SCHEDULE_MIN_NOTIONAL_LEN with a minimum notional, SCHEDULE_LOYALTY_LEN
+    /// with loyalty tiers, SCHEDULE_DECAY_LEN with an inventory halflife,
+    /// SCHEDULE_BAND_LEN with an inventory band, SCHEDULE_CAPS_LEN with
+    /// inventory caps, SCHEDULE_REDUCE_ONLY_LEN with a reduce-only threshold,
+    /// SCHEDULE_DEPTH_CAP_LEN with a depth-relative cap, SCHEDULE_SIGNALS_LEN
+    /// with registered signals; entries past MAX_SCHEDULE_ENTRIES, tiers past MAX_FEE_TIERS or
+    /// MAX_LOYALTY_TIERS and signals past MAX_SIGNAL_ACCOUNTS are dropped.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let len = match (self.fee_tiers.is_empty(), self.rebate_bps | self.fee_discount_bps, self.min_notional_e6) {
+            _ if !self.signals.is_empty() => SCHEDULE_SIGNALS_LEN,
+            _ if self.max_inventory_bps != 0 => SCHEDULE_DEPTH_CAP_LEN,
+            _ if self.reduce_only_bps != 0 => SCHEDULE_REDUCE_ONLY_LEN,
+            _ if self.max_long_inventory != 0 || self.max_short_inventory != 0 => SCHEDULE_CAPS_LEN,
+            _ if self.soft_inventory != 0 || self.band_k_bps != 0 => SCHEDULE_BAND_LEN,
+            _ if self.inventory_halflife != 0 => SCHEDULE_DECAY_LEN,
+            _ if !self.loyalty_tiers.is_empty() => SCHEDULE_LOYALTY_LEN,
+            (_, _, 1..) => SCHEDULE_MIN_NOTIONAL_LEN,
+            (_, 1.., 0) => SCHEDULE_REBATE_LEN,
+            (false, 0, 0) => SCHEDULE_FEES_LEN,
+            (true, 0, 0) => SCHEDULE_LEN,
+        };
+        let mut data = vec![0u8; len];
+        let entries = &self.entries[..self.entries.len().min(MAX_SCHEDULE_ENTRIES)];
+        write(&mut data, SCHEDULE_MAGIC_OFF, &SCHEDULE_MAGIC.to_le_bytes());
+        write(&mut data, SCHEDULE_VERSION_OFF, &SCHEDULE_VERSION.to_le_bytes());
+        write(&mut data, SCHEDULE_COUNT_OFF, &(entries.len() as u32).to_le_bytes());
+        write(&mut data, SCHEDULE_CTX_OFF, self.ctx.as_ref());
+        for (entry, out) in entries.iter().zip(data[SCHEDULE_HEADER_LEN..].chunks_exact_mut(SCHED_ENTRY_LEN)) {
+            write(out, SCHED_START_OFF, &entry.start_slot.to_le_bytes());
+            write(out, SCHED_END_OFF, &entry.end_slot.to_le_bytes());
+            write(out, SCHED_MULT_OFF, &entry.multiplier_bps.to_le_bytes());
+        }
+        for (tier, out) in self.fee_tiers.iter().zip(data[FEE_TIERS_OFF..].chunks_exact_mut(FEE_TIER_LEN)) {
+            write(out, FEE_TIER_NOTIONAL_OFF, &tier.min_notional.to_le_bytes());
+            write(out, FEE_TIER_FEE_OFF, &tier.fee_bps.to_le_bytes());
+        }
+        if self.rebate_bps != 0 {
+            write(&mut data, SCHEDULE_REBATE_OFF, &self.rebate_bps.to_le_bytes());
+        }
+        if self.fee_discount_bps != 0 {
+            write(&mut data, SCHEDULE_FEE_DISCOUNT_OFF, &self.fee_discount_bps.to_le_bytes());
+        }
+        if self.min_notional_e6 != 0 {
+            write(&mut data, SCHEDULE_MIN_NOTIONAL_OFF, &self.min_notional_e6.to_le_bytes());
+        }
+        let table = data.get_mut(LOYALTY_TIERS_OFF..).unwrap_or_default();
+        for (tier, out) in self.loyalty_tiers.iter().zip(table.chunks_exact_mut(LOYALTY_TIER_LEN)) {
+            write(out, LOYALTY_TIER_VOLUME_OFF, &tier.min_volume.to_le_bytes());
+            write(out, LOYALTY_TIER_DISCOUNT_OFF, &tier.discount_bps.to_le_bytes());
+        }
+        if self.inventory_halflife != 0 {
+            write(&mut data, SCHEDULE_INVENTORY_HALFLIFE_OFF, &self.inventory_halflife.to_le_bytes());
+        }
+        if data.len() >= SCHEDULE_BAND_LEN {
+            write(&mut data, SCHEDULE_SOFT_INVENTORY_OFF, &self.soft_inventory.to_le_bytes());
+            write(&mut data, SCHEDULE_BAND_K_OFF, &self.band_k_bps.to_le_bytes());
+        }
+        if data.len() >= SCHEDULE_CAPS_LEN {
+            write(&mut data, SCHEDULE_MAX_LONG_OFF, &self.max_long_inventory.to_le_bytes());
+            write(&mut data, SCHEDULE_MAX_SHORT_OFF, &self.max_short_inventory.to_le_bytes());
+        }
+        if self.reduce_only_bps != 0 {
+            write(&mut data, SCHEDULE_REDUCE_ONLY_BPS_OFF, &self.reduce_only_bps.to_le_bytes());
+        }
+        if self.max_inventory_bps != 0 {
+            write(&mut data, SCHEDULE_MAX_INVENTORY_BPS_OFF, &self.max_inventory_bps.to_le_bytes());
+        }
+        let signals = &self.signals[..self.signals.len().min(MAX_SIGNAL_ACCOUNTS)];
+        if !signals.is_empty() {
+            data[SCHEDULE_SIGNAL_COUNT_OFF] = signals.len() as u8;
+        }
+        for (signal, out) in signals.iter().zip(data.get_mut(SCHEDULE_SIGNALS_OFF..).unwrap_or_default().chunks_exact_mut(SIGNAL_ENTRY_LEN)) {
+            write(out, SIGNAL_ENTRY_KEY_OFF, signal.signal.as_ref());
+            write(out, SIGNAL_ENTRY_OWNER_OFF, signal.owner.as_ref());
+        }
+        data
+    }
+
+    /// The fee a trade of `notional` pays: the largest tier it reaches, None
+    /// below every tier (base_fee_bps applies).
+    pub fn fee_bps(&self, notional: u128) -> Option<u32> {
+        self.fee_tiers.iter().filter(|t| t.min_notional <= notional).max_by_key(|t| t.min_notional).map(|t| t.fee_bps)
+    }
+
+    /// What a trader with `volume` on their trader account gets off the fee:
+    /// the largest loyalty tier it reaches, 0 below every tier.
+    pub fn loyalty_discount_bps(&self, volume: u128) -> u32 {
+        self.loyalty_tiers.iter().filter(|t| t.min_volume <= volume).max_by_key(|t| t.min_volume).map_or(0, |t| t.discount_bps)
+    }
+
+    /// The multiplier in force at `slot`, in bps: the widest entry covering
+    /// it, 0 when none does.
+    pub fn multiplier_bps(&self, slot: u64) -> u64 {
+        self.entries
+            .iter()
+            .filter(|e| (e.start_slot..=e.end_slot).contains(&slot))
+            .map(|e| e.multiplier_bps as u64)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// One level of a ladder: a single trade of `size` fills at `exec_price_e6`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LadderLevel {
+    /// Base size, after clipping.
+    pub size: u128,
+    pub exec_price_e6: u64,
+    pub spread_bps: u64,
+}
+
+/// A ladder account: the LP's depth each way as PublishLadder last priced it,
+/// for routers to read like a resting book.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Ladder {
+    /// The context InitLadder bound it to.
+    pub ctx: Pubkey,
+    /// Market-table entry it was priced on.
+    pub market: u16,
+    /// Slot it was published in; 0 if never.
+    pub slot: u64,
+    pub oracle_price_e6: u64,
+    pub step_size: u128,
+    /// Levels a taker's sell fills at, smallest first.
+    pub bids: Vec<LadderLevel>,
+    /// Levels a taker's buy fills at, smallest first.
+    pub asks: Vec<LadderLevel>,
+    /// Why the bids stop short, as a MatcherError code; 0 when they don't.
+    pub bid_reason: u32,
+    pub ask_reason: u32,
+}
+
+impl Ladder {
+    /// Decode a ladder account; anything without LADDER_MAGIC is rejected.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < LADDER_LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        if read_u64(data, LADDER_MAGIC_OFF) != LADDER_MAGIC {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let levels = |off: usize, count_off: usize| -> Vec<LadderLevel> {
+            let count = (read_u32(data, count_off) as usize).min(MAX_LADDER_LEVELS);
+            data[off..off + MAX_LADDER_LEVELS * LEVEL_LEN]
+                .chunks_exact(LEVEL_LEN)
+                .take(count)
+                .map(|level| LadderLevel {
+                    size: read_u128(level, LEVEL_SIZE_OFF),
+                    exec_price_e6: read_u64(level, LEVEL_PRICE_OFF),
+                    spread_bps: read_u64(level, LEVEL_SPREAD_OFF),
+                })
+                .collect()
+        };
+        Ok(Self {
+            ctx: read_pubkey(data, LADDER_CTX_OFF),
+            market: read_u16(data, LADDER_MARKET_OFF),
+            slot: read_u64(data, LADDER_SLOT_OFF),
+            oracle_price_e6: read_u64(data, LADDER_ORACLE_OFF),
+            step_size: read_u128(data, LADDER_STEP_OFF),
+            bids: levels(LADDER_BIDS_OFF, LADDER_BID_COUNT_OFF),
+            asks: levels(LADDER_ASKS_OFF, LADDER_ASK_COUNT_OFF),
+            bid_reason: read_u32(data, LADDER_BID_REASON_OFF),
+            ask_reason: read_u32(data, LADDER_ASK_REASON_OFF),
+        })
+    }
+
+    /// Encode as account data, LADDER_LEN bytes; levels past
+    /// MAX_LADDER_LEVELS are dropped.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = vec![0u8; LADDER_LEN];
+        write(&mut data, LADDER_MAGIC_OFF, &LADDER_MAGIC.to_le_bytes());
+        write(&mut data, LADDER_VERSION_OFF, &LADDER_VERSION.to_le_bytes());
+        write(&mut data, LADDER_MARKET_OFF, &self.market.to_le_bytes());
+        write(&mut data, LADDER_CTX_OFF, self.ctx.as_ref());
+        write(&mut data, LADDER_SLOT_OFF, &self.slot.to_le_bytes());
+        write(&mut data, LADDER_ORACLE_OFF, &self.oracle_price_e6.to_le_bytes());
+        write(&mut data, LADDER_STEP_OFF, &self.step_size.to_le_bytes());
+        write(&mut data, LADDER_BID_REASON_OFF, &self.bid_reason.to_le_bytes());
+        write(&mut data, LADDER_ASK_REASON_OFF, &self.ask_reason.to_le_bytes());
+        for (levels, off, count_off) in
+            [(&self.bids, LADDER_BIDS_OFF, LADDER_BID_COUNT_OFF), (&self.asks, LADDER_ASKS_OFF, LADDER_ASK_COUNT_OFF)]
+        {
+            let levels = &levels[..levels.len().min(MAX_LADDER_LEVELS)];
+            write(&mut data, count_off, &(levels.len() as u32).to_le_bytes());
+            for (level, out) in levels.iter().zip(data[off..].chunks_exact_mut(LEVEL_LEN)) {
+                write(out, LEVEL_SIZE_OFF, &level.size.to_le_bytes());
+                write(out, LEVEL_PRICE_OFF, &level.exec_price_e6.to_le_bytes());
+                write(out, LEVEL_SPREAD_OFF, &level.spread_bps.to_le_bytes());
+            }
+        }
+        data
+    }
+}
+
+/// The v6 extension region.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ContextExtension {
+    pub funding_rate_bps_per_slot: i64,
+    pub slab: Pubkey,
+    /// Slot a queued ProposeParams becomes committable; 0 when none is queued.
+    pub pending_activation_slot: u64,
+    /// The queued parameter block, as proposed.
+    pub pending_params: MatcherParams,
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+    pub percolator_program: Pubkey,
+    pub last_oracle_slot: u64,
+    pub fill_seq: u64,
+    pub cum_notional: u128,
+    pub cum_fees: u128,
+    pub gross_long: u128,
+    pub gross_short: u128,
+    pub stats: Pubkey,
+    /// Key whose quotes PostRfq accepts; the default key when RFQ is off.
+    pub rfq_signer: Pubkey,
+    /// The posted RFQ quote; a zero price when none is posted.
+    pub rfq_price_e6: u64,
+    pub rfq_expiry_slot: u64,
+    /// Nonce of the last quote posted, which the next one has to exceed.
+    pub rfq_nonce: u64,
+    /// Size the posted quote has left, signed like a trade.
+    pub rfq_size_left: i128,
+    /// Snapshot slot that last saw new liquidations; the auction runs from it.
+    pub last_liq_slot: u64,
+}
+
+/// One entry of a shared context's market table.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MarketBook {
+    /// The market's slab; the default key on a free entry.
+    pub slab: Pubkey,
+    /// Overrides the context's max_fill when non-zero.
+    pub max_fill: u128,
+    /// Overrides the context's max_inventory when non-zero.
+    pub max_inventory: u128,
+    pub inventory: i128,
+    pub insurance_snapshot: u128,
+    pub total_oi_snapshot: u128,
+    pub gross_long: u128,
+    pub gross_short: u128,
+    pub snapshot_slot: u64,
+    pub last_crank_slot: u64,
+    pub last_liquidations: u64,
+    pub funding_rate_bps_per_slot: i64,
+    pub market_age: u64,
+    pub last_deficit: u64,
+    pub last_liq_slot: u64,
+    pub last_oracle_price_e6: u64,
+    pub last_oracle_slot: u64,
+    pub last_exec_price_e6: u64,
+    pub liq_heat: u32,
+}
+
+impl MarketBook {
+    fn from_bytes(data: &[u8]) -> Self {
+        Self {
+            slab: read_pubkey(data, MKT_SLAB_OFF),
+            max_fill: read_u128(data, MKT_MAX_FILL_OFF),
+            max_inventory: read_u128(data, MKT_MAX_INVENTORY_OFF),
+            inventory: read_i128(data, MKT_INVENTORY_OFF),
+            insurance_snapshot: read_u128(data, MKT_INSURANCE_OFF),
+            total_oi_snapshot: read_u128(data, MKT_TOTAL_OI_OFF),
+            gross_long: read_u128(data, MKT_GROSS_LONG_OFF),
+            gross_short: read_u128(data, MKT_GROSS_SHORT_OFF),
+            snapshot_slot: read_u64(data, MKT_SNAPSHOT_SLOT_OFF),
+            last_crank_slot: read_u64(data, MKT_LAST_CRANK_OFF),
+            last_liquidations: read_u64(data, MKT_LAST_LIQS_OFF),
+            funding_rate_bps_per_slot: read_u64(data, MKT_FUNDING_RATE_OFF) as i64,
+            market_age: read_u64(data, MKT_MARKET_AGE_OFF),
+            last_deficit: read_u64(data, MKT_LAST_DEFICIT_OFF),
+            last_liq_slot: read_u64(data, MKT_LAST_LIQ_SLOT_OFF),
+            last_oracle_price_e6: read_u64(data, MKT_LAST_ORACLE_OFF),
+            last_oracle_slot: read_u64(data, MKT_LAST_ORACLE_SLOT_OFF),
+            last_exec_price_e6: read_u64(data, MKT_LAST_EXEC_OFF),
+            liq_heat: read_u32(data, MKT_LIQ_HEAT_OFF),
+        }
+    }
+
+    fn write_to(&self, data: &mut [u8]) {
+        write(data, MKT_SLAB_OFF, self.slab.as_ref());
+        write(data, MKT_MAX_FILL_OFF, &self.max_fill.to_le_bytes());
+        write(data, MKT_MAX_INVENTORY_OFF, &self.max_inventory.to_le_bytes());
+        write(data, MKT_INVENTORY_OFF, &self.inventory.to_le_bytes());
+        write(data, MKT_INSURANCE_OFF, &self.insurance_snapshot.to_le_bytes());
+        write(data, MKT_TOTAL_OI_OFF, &self.total_oi_snapshot.to_le_bytes());
+        write(data, MKT_GROSS_LONG_OFF, &self.gross_long.to_le_bytes());
+        write(data, MKT_GROSS_SHORT_OFF, &self.gross_short.to_le_bytes());
+        write(data, MKT_SNAPSHOT_SLOT_OFF, &self.snapshot_slot.to_le_bytes());
+        write(data, MKT_LAST_CRANK_OFF, &self.last_crank_slot.to_le_bytes());
+        write(data, MKT_LAST_LIQS_OFF, &self.last_liquidations.to_le_bytes());
+        write(data, MKT_FUNDING_RATE_OFF, &self.funding_rate_bps_per_slot.to_le_bytes());
+        write(data, MKT_MARKET_AGE_OFF, &self.market_age.to_le_bytes());
+        write(data, MKT_LAST_DEFICIT_OFF, &self.last_deficit.to_le_bytes());
+        write(data, MKT_LAST_LIQ_SLOT_OFF, &self.last_liq_slot.to_le_bytes());
+        write(data, MKT_LAST_ORACLE_OFF, &self.last_oracle_price_e6.to_le_bytes());
+        write(data, MKT_LAST_ORACLE_SLOT_OFF, &self.last_oracle_slot.to_le_bytes());
+        write(data, MKT_LAST_EXEC_OFF, &self.last_exec_price_e6.to_le_bytes());
+        write(data, MKT_LIQ_HEAT_OFF, &self.liq_heat.to_le_bytes());
+    }
+}
+
+/// When and by whom a v6 context was initialized, from the record after its
+/// layout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Creation {
+    pub slot: u64,
+    /// The key that signed Init: the authority it set, or the LP PDA.
+    pub creator: Pubkey,
+    /// CreateContext made the account, at the address derived from the LP
+    /// PDA and slab with `MatcherContext::bump`.
+    pub derived: bool,
+}
+
+/// A decoded context account. Parameters that live in the extension are zero
+/// on v5, exactly as the program treats them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MatcherContext {
+    pub last_return: MatcherReturn,
+    pub version: u32,
+    pub kind: u8,
+    /// Decimals of the oracle price the program is called with (0 = e6).
+    pub oracle_decimals: u8,
+    pub lp_pda: Pubkey,
+    pub params: MatcherParams,
+    pub inventory: i128,
+    pub last_oracle_price_e6: u64,
+    pub last_exec_price_e6: u64,
+    pub insurance_snapshot: u128,
+    pub total_oi_snapshot: u128,
+    pub market_age: u64,
+    pub last_deficit: u64,
+    pub snapshot_slot: u64,
+    pub flags: u8,
+    /// CreateContext's bump, meaningful when `creation` says `derived`.
+    pub bump: u8,
+    pub last_crank_slot: u64,
+    pub last_liquidations: u64,
+    pub liq_heat: u32,
+    pub extension: Option<ContextExtension>,
+    /// The market table of a shared context, which keeps each market's
+    /// inventory and snapshots there instead of in the fields above.
+    pub markets: Option<[MarketBook; MAX_MARKETS]>,
+    /// None when the account was created without room for the record.
+    pub creation: Option<Creation>,
+}
+
+impl MatcherContext {
+    /// Decode an initialized context account.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < CTX_LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        if read_u64(data, CTX_BASE + CTX_MAGIC_OFF) != MAGIC {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        let c = |off: usize| CTX_BASE + off;
+        let e = |off: usize| EXT_BASE + off;
+        let version = read_u32(data, c(CTX_VERSION_OFF));
+        let extended = version >= VERSION && data.len() >= CTX_EXT_LEN;
+
+        let mut params = MatcherParams {
+            base_fee_bps: read_u32(data, c(CTX_BASE_FEE_OFF)),
+            min_spread_bps: read_u32(data, c(CTX_MIN_SPREAD_OFF)),
+            max_spread_bps: read_u32(data, c(CTX_MAX_SPREAD_OFF)),
+            imbalance_k_bps: read_u32(data, c(CTX_IMBALANCE_K_OFF)),
+            liquidity_e6: read_u128(data, c(CTX_LIQUIDITY_OFF)),
+            max_fill: read_u128(data, c(CTX_MAX_FILL_OFF)),
+            max_inventory: read_u128(data, c(CTX_MAX_INVENTORY_OFF)),
+            age_halflife: read_u32(data, c(CTX_AGE_HALFLIFE_OFF)),
+            insurance_weight_bps: read_u32(data, c(CTX_INSURANCE_WEIGHT_OFF)),
+            impact_mode: data[c(CTX_IMPACT_MODE_OFF)],
+            impact_k_bps: read_u32(data, c(CTX_IMPACT_K_OFF)),
+            skew_k_bps: read_u32(data, c(CTX_SKEW_K_OFF)),
+            coverage_curve: data[c(CTX_COVERAGE_CURVE_OFF)],
+            curve_points: [0, 1, 2, 3].map(|i| read_u16(data, c(CTX_CURVE_POINTS_OFF) + i * 2)),
+            liq_halflife: read_u32(data, c(CTX_LIQ_HALFLIFE_OFF)),
+            peg_band_e6: read_u32(data, c(CTX_PEG_BAND_OFF)),
+            spot_weight_bps: read_u16(data, c(CTX_SPOT_WEIGHT_OFF)),
+            ..Default::default()
+        };
+
+        let extension = if extended {
+            params.funding_horizon = read_u32(data, e(EXT_FUNDING_HORIZON_OFF));
+            params.oracle_jump_bps = read_u16(data, e(EXT_ORACLE_JUMP_BPS_OFF));
+            params.oracle_jump_window = read_u32(data, e(EXT_ORACLE_JUMP_WINDOW_OFF));
+            params.flow_k_bps = read_u32(data, e(EXT_FLOW_K_OFF));
+            params.twap_window_slots = read_u32(data, e(EXT_TWAP_WINDOW_OFF));
+            params.signal_weights = [0, 1, 2, 3].map(|i| read_u16(data, e(EXT_SIGNAL_WEIGHTS_OFF) + i * 2));
+            params.deficit_halflife = read_u32(data, e(EXT_DEFICIT_HALFLIFE_OFF));
+            params.liq_auction_bps = read_u16(data, e(EXT_LIQ_AUCTION_BPS_OFF));
+            params.liq_auction_slots = read_u32(data, e(EXT_LIQ_AUCTION_SLOTS_OFF));
+
+            let mut pending = [0u8; PARAMS_MAX_LEN];
+            let (head, tail) = pending.split_at_mut(PENDING_HEAD_LEN);
+            head.copy_from_slice(&data[e(EXT_PENDING_PARAMS_OFF)..e(EXT_PENDING_PARAMS_OFF) + PENDING_HEAD_LEN]);
+            tail.copy_from_slice(&data[e(EXT_PENDING_PARAMS_TAIL_OFF)..e(EXT_PENDING_PARAMS_TAIL_OFF) + tail.len()]);
+
+            Some(ContextExtension {
+                funding_rate_bps_per_slot: read_u64(data, e(EXT_FUNDING_RATE_OFF)) as i64,
+                slab: read_pubkey(data, e(EXT_SLAB_OFF)),
+                pending_activation_slot: read_u64(data, e(EXT_PENDING_SLOT_OFF)),
+                pending_params: MatcherParams::unpack(&pending).unwrap_or_default(),
+                authority: read_pubkey(data, e(EXT_AUTHORITY_OFF)),
+                pending_authority: read_pubkey(data, e(EXT_PENDING_AUTHORITY_OFF)),
+                percolator_program: read_pubkey(data, e(EXT_PERCOLATOR_OFF)),
+                last_oracle_slot: read_u64(data, e(EXT_LAST_ORACLE_SLOT_OFF)),
+                fill_seq: read_u64(data, e(EXT_FILL_SEQ_OFF)),
+                cum_notional: read_u128(data, e(EXT_CUM_NOTIONAL_OFF)),
+                cum_fees: read_u128(data, e(EXT_CUM_FEES_OFF)),
+                gross_long: read_u128(data, e(EXT_GROSS_LONG_OFF)),
+                gross_short: read_u128(data, e(EXT_GROSS_SHORT_OFF)),
+                stats: read_pubkey(data, e(EXT_STATS_OFF)),
+                rfq_signer: read_pubkey(data, e(EXT_RFQ_SIGNER_OFF)),
+                rfq_price_e6: read_u64(data, e(EXT_RFQ_PRICE_OFF)),
+                rfq_expiry_slot: read_u64(data, e(EXT_RFQ_EXPIRY_OFF)),
+                rfq_nonce: read_u64(data, e(EXT_RFQ_NONCE_OFF)),
+                rfq_size_left: read_i128(data, e(EXT_RFQ_SIZE_OFF)),
+                last_liq_slot: read_u64(data, e(EXT_LAST_LIQ_SLOT_OFF)),
+            })
+        } else {
+            None
+        };
+
+        let shared = extended && data.len() >= CTX_SHARED_LEN && read_u32(data, e(EXT_MARKET_SLOTS_OFF)) != 0;
+        let markets = shared.then(|| {
+            std::array::from_fn(|i| MarketBook::from_bytes(&data[MARKETS_BASE + i * MARKET_LEN..][..MARKET_LEN]))
+        });
+        let creation_off = if shared { CTX_SHARED_LEN } else { CTX_EXT_LEN };
+        let creation = (extended && data.len() >= creation_off + CREATION_LEN).then(|| Creation {
+            slot: read_u64(data, creation_off + CREATION_SLOT_OFF),
+            creator: read_pubkey(data, creation_off + CREATION_CREATOR_OFF),
+            derived: data[creation_off + CREATION_DERIVED_OFF] != 0,
+        });
+
+        Ok(Self {
+            last_return: MatcherReturn::from_bytes(data)?,
+            version,
+            kind: data[c(CTX_KIND_OFF)],
+            oracle_decimals: data[c(CTX_ORACLE_DECIMALS_OFF)],
+            lp_pda: read_pubkey(data, c(CTX_LP_PDA_OFF)),
+            params,
+            inventory: read_i128(data, c(CTX_INVENTORY_OFF)),
+            last_oracle_price_e6: read_u64(data, c(CTX_LAST_ORACLE_OFF)),
+            last_exec_price_e6: read_u64(data, c(CTX_LAST_EXEC_OFF)),
+            insurance_snapshot: read_u128(data, c(CTX_INSURANCE_OFF)),
+            total_oi_snapshot: read_u128(data, c(CTX_TOTAL_OI_OFF)),
+            market_age: read_u64(data, c(CTX_MARKET_AGE_OFF)),
+            last_deficit: read_u64(data, c(CTX_LAST_DEFICIT_OFF)),
+            snapshot_slot: read_u64(data, c(CTX_SNAPSHOT_SLOT_OFF)),
+            flags: data[c(CTX_FLAGS_OFF)],
+            bump: data[c(CTX_BUMP_OFF)],
+            last_crank_slot: read_u64(data, c(CTX_LAST_CRANK_OFF)),
+            last_liquidations: read_u64(data, c(CTX_LAST_LIQS_OFF)),
+            liq_heat: read_u32(data, c(CTX_LIQ_HEAT_OFF)),
+            extension,
+            markets,
+            creation,
+        })
+    }
+
+    /// Encode as account data: CTX_SHARED_LEN bytes with a market table,
+    /// CTX_EXT_LEN with an extension, CTX_LEN without; CREATION_LEN more
+    /// with a creation record on an extended context.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let layout_len = match (&self.extension, &self.markets) {
+            (Some(_), Some(_)) => CTX_SHARED_LEN,
+            (Some(_), None) => CTX_EXT_LEN,
+            (None, _) => CTX_LEN,
+        };
+        let creation = self.creation.filter(|_| self.extension.is_some());
+        let mut data = vec![0u8; layout_len + if creation.is_some() { CREATION_LEN } else { 0 }];
+        let c = |off: usize| CTX_BASE + off;
+        let e = |off: usize| EXT_BASE + off;
+        let p = &self.params;
+
+        data[..RET_LEN].copy_from_slice(&self.last_return.to_bytes());
+        write(&mut data, c(CTX_MAGIC_OFF), &MAGIC.to_le_bytes());
+        write(&mut data, c(CTX_VERSION_OFF), &self.version.to_le_bytes());
+        data[c(CTX_KIND_OFF)] = self.kind;
+        data[c(CTX_ORACLE_DECIMALS_OFF)] = self.oracle_decimals;
+        write(&mut data, c(CTX_LP_PDA_OFF), self.lp_pda.as_ref());
+        write(&mut data, c(CTX_BASE_FEE_OFF), &p.base_fee_bps.to_le_bytes());
+        write(&mut data, c(CTX_MIN_SPREAD_OFF), &p.min_spread_bps.to_le_bytes());
+        write(&mut data, c(CTX_MAX_SPREAD_OFF), &p.max_spread_bps.to_le_bytes());
+        write(&mut data, c(CTX_IMBALANCE_K_OFF), &p.imbalance_k_bps.to_le_bytes());
+        write(&mut data, c(CTX_LIQUIDITY_OFF), &p.liquidity_e6.to_le_bytes());
+        write(&mut data, c(CTX_MAX_FILL_OFF), &p.max_fill.to_le_bytes());
+        write(&mut data, c(CTX_INVENTORY_OFF), &self.inventory.to_le_bytes());
+        write(&mut data, c(CTX_LAST_ORACLE_OFF), &self.last_oracle_price_e6.to_le_bytes());
+        write(&mut data, c(CTX_LAST_EXEC_OFF), &self.last_exec_price_e6.to_le_bytes());
+        write(&mut data, c(CTX_MAX_INVENTORY_OFF), &p.max_inventory.to_le_bytes());
+        write(&mut data, c(CTX_INSURANCE_OFF), &self.insurance_snapshot.to_le_bytes());
+        write(&mut data, c(CTX_TOTAL_OI_OFF), &self.total_oi_snapshot.to_le_bytes());
+        write(&mut data, c(CTX_MARKET_AGE_OFF), &self.market_age.to_le_bytes());
+        write(&mut data, c(CTX_LAST_DEFICIT_OFF), &self.last_deficit.to_le_bytes());
+        write(&mut data, c(CTX_SNAPSHOT_SLOT_OFF), &self.snapshot_slot.to_le_bytes());
+        write(&mut data, c(CTX_AGE_HALFLIFE_OFF), &p.age_halflife.to_le_bytes());
+        write(&mut data, c(CTX_INSURANCE_WEIGHT_OFF), &p.insurance_weight_bps.to_le_bytes());
+        data[c(CTX_IMPACT_MODE_OFF)] = p.impact_mode;
+        data[c(CTX_COVERAGE_CURVE_OFF)] = p.coverage_curve;
+        data[c(CTX_FLAGS_OFF)] = self.flags;
+        data[c(CTX_BUMP_OFF)] = self.bump;
+        write(&mut data, c(CTX_IMPACT_K_OFF), &p.impact_k_bps.to_le_bytes());
+        write(&mut data, c(CTX_SKEW_K_OFF), &p.skew_k_bps.to_le_bytes());
+        for (i, point) in p.curve_points.iter().enumerate() {
+            write(&mut data, c(CTX_CURVE_POINTS_OFF) + i * 2, &point.to_le_bytes());
+        }
+        write(&mut data, c(CTX_PEG_BAND_OFF), &p.peg_band_e6.to_le_bytes());
+        write(&mut data, c(CTX_SPOT_WEIGHT_OFF), &p.spot_weight_bps.to_le_bytes());
+        write(&mut data, c(CTX_LAST_CRANK_OFF), &self.last_crank_slot.to_le_bytes());
+        write(&mut data, c(CTX_LAST_LIQS_OFF), &self.last_liquidations.to_le_bytes());
+        write(&mut data, c(CTX_LIQ_HEAT_OFF), &self.liq_heat.to_le_bytes());
+        write(&mut data, c(CTX_LIQ_HALFLIFE_OFF), &p.liq_halflife.to_le_bytes());
+
+        if let Some(x) = &self.extension {
+            write(&mut data, e(EXT_FUNDING_RATE_OFF), &x.funding_rate_bps_per_slot.to_le_bytes());
+            write(&mut data, e(EXT_FUNDING_HORIZON_OFF), &p.funding_horizon.to_le_bytes());
+            write(&mut data, e(EXT_SLAB_OFF), x.slab.as_ref());
+            write(&mut data, e(EXT_PENDING_SLOT_OFF), &x.pending_activation_slot.to_le_bytes());
+            // A cleared proposal is all zeros on chain, not a packed default
+            if x.pending_activation_slot != 0 {
+                let pending = x.pending_params.pack();
+                write(&mut data, e(EXT_PENDING_PARAMS_OFF), &pending[..PENDING_HEAD_LEN]);
+                write(&mut data, e(EXT_PENDING_PARAMS_TAIL_OFF), &pending[PENDING_HEAD_LEN..]);
+            }
+            write(&mut data, e(EXT_AUTHORITY_OFF), x.authority.as_ref());
+            write(&mut data, e(EXT_PENDING_AUTHORITY_OFF), x.pending_authority.as_ref());
+            write(&mut data, e(EXT_PERCOLATOR_OFF), x.percolator_program.as_ref());
+            write(&mut data, e(EXT_ORACLE_JUMP_BPS_OFF), &p.oracle_jump_bps.to_le_bytes());
+            write(&mut data, e(EXT_ORACLE_JUMP_WINDOW_OFF), &p.oracle_jump_window.to_le_bytes());
+            write(&mut data, e(EXT_LAST_ORACLE_SLOT_OFF), &x.last_oracle_slot.to_le_bytes());
+            write(&mut data, e(EXT_FILL_SEQ_OFF), &x.fill_seq.to_le_bytes());
+            write(&mut data, e(EXT_CUM_NOTIONAL_OFF), &x.cum_notional.to_le_bytes());
+            write(&mut data, e(EXT_CUM_FEES_OFF), &x.cum_fees.to_le_bytes());
+            write(&mut data, e(EXT_GROSS_LONG_OFF), &x.gross_long.to_le_bytes());
+            write(&mut data, e(EXT_GROSS_SHORT_OFF), &x.gross_short.to_le_bytes());
+            write(&mut data, e(EXT_FLOW_K_OFF), &p.flow_k_bps.to_le_bytes());
+            write(&mut data, e(EXT_TWAP_WINDOW_OFF), &p.twap_window_slots.to_le_bytes());
+            for (i, weight) in p.signal_weights.iter().enumerate() {
+                write(&mut data, e(EXT_SIGNAL_WEIGHTS_OFF) + i * 2, &weight.to_le_bytes());
+            }
+            write(&mut data, e(EXT_DEFICIT_HALFLIFE_OFF), &p.deficit_halflife.to_le_bytes());
+            write(&mut data, e(EXT_STATS_OFF), x.stats.as_ref());
+            write(&mut data, e(EXT_RFQ_SIGNER_OFF), x.rfq_signer.as_ref());
+            write(&mut data, e(EXT_RFQ_PRICE_OFF), &x.rfq_price_e6.to_le_bytes());
+            write(&mut data, e(EXT_RFQ_EXPIRY_OFF), &x.rfq_expiry_slot.to_le_bytes());
+            write(&mut data, e(EXT_RFQ_NONCE_OFF), &x.rfq_nonce.to_le_bytes());
+            write(&mut data, e(EXT_RFQ_SIZE_OFF), &x.rfq_size_left.to_le_bytes());
+            write(&mut data, e(EXT_LIQ_AUCTION_BPS_OFF), &p.liq_auction_bps.to_le_bytes());
+            write(&mut data, e(EXT_LIQ_AUCTION_SLOTS_OFF), &p.liq_auction_slots.to_le_bytes());
+            write(&mut data, e(EXT_LAST_LIQ_SLOT_OFF), &x.last_liq_slot.to_le_bytes());
+            if let Some(markets) = &self.markets {
+                write(&mut data, e(EXT_MARKET_SLOTS_OFF), &(MAX_MARKETS as u32).to_le_bytes());
+                for (i, market) in markets.iter().enumerate() {
+                    market.write_to(&mut data[MARKETS_BASE + i * MARKET_LEN..][..MARKET_LEN]);
+                }
+            }
+        }
+        if let Some(creation) = creation {
+            write(&mut data, layout_len + CREATION_SLOT_OFF, &creation.slot.to_le_bytes());
+            write(&mut data, layout_len + CREATION_CREATOR_OFF, creation.creator.as_ref());
+            data[layout_len + CREATION_DERIVED_OFF] = creation.derived as u8;
+        }
+        data
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.flags & CTX_FLAG_PAUSED != 0
+    }
+
+    /// Whether a schedule account is bound, and so has to be passed to
+    /// Match, QuotePreview, QuoteTwoSided and Close.
+    pub fn is_scheduled(&self) -> bool {
+        self.flags & CTX_FLAG_SCHEDULED != 0
+    }
+
+    /// Whether a feed account is bound, and so has to be passed, with a price
+    /// account per feed for Match, to Match and Close.
+    pub fn has_feed(&self) -> bool {
+        self.flags & CTX_FLAG_FEED != 0
+    }
+
+    /// The key that may administer the context: the rotated authority once
+    /// set, the LP PDA otherwise.
+    pub fn authority(&self) -> Pubkey {
+        match &self.extension {
+            Some(x) if x.authority != Pubkey::default() => x.authority,
+            _ => self.lp_pda,
+        }
+    }
+
+    /// The market-table entry listing `slab`, on a shared context.
+    pub fn market(&self, slab: &Pubkey) -> Option<(usize, &MarketBook)> {
+        if *slab == Pubkey::default() {
+            return None;
+        }
+        self.markets.as_ref()?.iter().enumerate().find(|(_, m)| m.slab == *slab)
+    }
+}
+
+fn write(data: &mut [u8], off: usize, bytes: &[u8]) {
+    data[off..off + bytes.len()].copy_from_slice(bytes);
+}
+
+fn read_u16(data: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes(data[off..off + 2].try_into().unwrap())
+}
+
+fn read_u32(data: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes(data[off..off + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], off: usize) -> u64 {
+    u64::from_le_bytes(data[off..off + 8].try_into().unwrap())
+}
+
+fn read_u128(data: &[u8], off: usize) -> u128 {
+    u128::from_le_bytes(data[off..off + 16].try_into().unwrap())
+}
+
+fn read_i128(data: &[u8], off: usize) -> i128 {
+    i128::from_le_bytes(data[off..off + 16].try_into().unwrap())
+}
+
+fn read_pubkey(data: &[u8], off: usize) -> Pubkey {
+    Pubkey::new_from_array(data[off..off + 32].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_short_or_uninitialized() {
+        assert_eq!(MatcherContext::from_bytes(&[0u8; CTX_LEN - 1]), Err(ProgramError::AccountDataTooSmall));
+        assert_eq!(MatcherContext::from_bytes(&[0u8; CTX_LEN]), Err(ProgramError::UninitializedAccount));
+    }
+
+    #[test]
+    fn test_v5_roundtrip_has_no_extension() {
+        let ctx = MatcherContext {
+            version: VERSION_V5,
+            kind: KIND_CREDIBILITY,
+            lp_pda: Pubkey::new_unique(),
+            inventory: -42,
+            flags: CTX_FLAG_PAUSED,
+            params: MatcherParams { max_spread_bps: 200, curve_points: [1, 2, 3, 4], ..Default::default() },
+            ..Default::default()
+        };
+        let bytes = ctx.to_bytes();
+        assert_eq!(bytes.len(), CTX_LEN);
+        let decoded = MatcherContext::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, ctx);
+        assert!(decoded.is_paused());
+        assert_eq!(decoded.authority(), ctx.lp_pda);
+    }
+
+    #[test]
+    fn test_shared_roundtrip_keeps_the_market_table() {
+        let slab = Pubkey::new_unique();
+        let mut markets = [MarketBook::default(); MAX_MARKETS];
+        markets[1] = MarketBook { slab, max_fill: 7, inventory: -3, last_liq_slot: 9, liq_heat: 2, ..Default::default() };
+        let ctx = MatcherContext {
+            version: VERSION,
+            kind: KIND_CREDIBILITY,
+            extension: Some(ContextExtension::default()),
+            markets: Some(markets),
+            ..Default::default()
+        };
+        let bytes = ctx.to_bytes();
+        assert_eq!(bytes.len(), CTX_SHARED_LEN);
+        let decoded = MatcherContext::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, ctx);
+        assert_eq!(decoded.market(&slab), Some((1, &markets[1])));
+        assert_eq!(decoded.market(&Pubkey::default()), None);
+    }
+
+    #[test]
+    fn test_creation_record_follows_the_layout() {
+        let creation = Creation { slot: 77, creator: Pubkey::new_unique(), derived: true };
+        let single = MatcherContext {
+            version: VERSION,
+            kind: KIND_CREDIBILITY,
+            extension: Some(ContextExtension::default()),
+            creation: Some(creation),
+            ..Default::default()
+        };
+        let bytes = single.to_bytes();
+        assert_eq!(bytes.len(), CTX_EXT_LEN + CREATION_LEN);
+        assert_eq!(MatcherContext::from_bytes(&bytes), Ok(single));
+        assert_eq!(MatcherContext::from_bytes(&bytes[..CTX_EXT_LEN]).unwrap().creation, None);
+
+        let shared = MatcherContext { markets: Some([MarketBook::default(); MAX_MARKETS]), ..single };
+        let bytes = shared.to_bytes();
+        assert_eq!(bytes.len(), CTX_SHARED_LEN + CREATION_LEN);
+        assert_eq!(MatcherContext::from_bytes(&bytes), Ok(shared));
+    }
+
+    #[test]
+    fn test_schedule_roundtrip() {
+        let entries = vec![
+            ScheduleEntry { start_slot: 100, end_slot: 200, multiplier_bps: 15_000 },
+            ScheduleEntry { start_slot: 150, end_slot: 150, multiplier_bps: 30_000 },
+        ];
+        let schedule = SpreadSchedule {
+            ctx: Pubkey::new_unique(),
+            entries,
+            fee_tiers: vec![],
+            rebate_bps: 0,
+            fee_discount_bps: 0,
+            min_notional_e6: 0,
+            loyalty_tiers: vec![],
+            inventory_halflife: 0,
+            soft_inventory: 0,
+            band_k_bps: 0,
+            max_long_inventory: 0,
+            max_short_inventory: 0,
+            reduce_only_bps: 0,
+            max_inventory_bps: 0,
+            signals: vec![],
+        };
+        let bytes = schedule.to_bytes();
+        assert_eq!(bytes.len(), SCHEDULE_LEN);
+        assert_eq!(SpreadSchedule::from_bytes(&bytes), Ok(schedule.clone()));
+        assert_eq!((schedule.multiplier_bps(99), schedule.multiplier_bps(100), schedule.multiplier_bps(150)), (0, 15_000, 30_000));
+        assert_eq!(SpreadSchedule::from_bytes(&bytes[..SCHEDULE_LEN - 1]), Err(ProgramError::AccountDataTooSmall));
+        assert_eq!(SpreadSchedule::from_bytes(&[0u8; SCHEDULE_LEN]), Err(ProgramError::InvalidAccountData));
+
+        let fee_tiers = vec![FeeTier { min_notional: 1_000, fee_bps: 8 }, FeeTier { min_notional: 50_000, fee_bps: 3 }];
+        let tiered = SpreadSchedule { fee_tiers, ..schedule };
+        let bytes = tiered.to_bytes();
+        assert_eq!(bytes.len(), SCHEDULE_FEES_LEN);
+        assert_eq!(SpreadSchedule::from_bytes(&bytes), Ok(tiered.clone()));
+        assert_eq!((tiered.fee_bps(999), tiered.fee_bps(1_000), tiered.fee_bps(60_000)), (None, Some(8), Some(3)));
+        let rebated = SpreadSchedule { rebate_bps: 4, ..tiered };
+        let bytes = rebated.to_bytes();
+        assert_eq!((bytes.len(), SpreadSchedule::from_bytes(&bytes)), (SCHEDULE_REBATE_LEN, Ok(rebated.clone())));
+        let discounted = SpreadSchedule { rebate_bps: 0, fee_discount_bps: 5_000, ..rebated };
+        let bytes = discounted.to_bytes();
+        assert_eq!((bytes.len(), SpreadSchedule::from_bytes(&bytes)), (SCHEDULE_REBATE_LEN, Ok(discounted.clone())));
+        let floored = SpreadSchedule { min_notional_e6: 1_000, ..discounted };
+        let bytes = floored.to_bytes();
+        assert_eq!((bytes.len(), SpreadSchedule::from_bytes(&bytes)), (SCHEDULE_MIN_NOTIONAL_LEN, Ok(floored.clone())));
+        let loyalty_tiers = vec![LoyaltyTier { min_volume: 10_000, discount_bps: 1 }, LoyaltyTier { min_volume: 90_000, discount_bps: 2 }];
+        let loyal = SpreadSchedule { loyalty_tiers, ..floored };
+        let bytes = loyal.to_bytes();
+        assert_eq!((bytes.len(), SpreadSchedule::from_bytes(&bytes)), (SCHEDULE_LOYALTY_LEN, Ok(loyal.clone())));
+        assert_eq!((loyal.loyalty_discount_bps(9_999), loyal.loyalty_discount_bps(10_000), loyal.loyalty_discount_bps(90_000)), (0, 1, 2));
+        let decaying = SpreadSchedule { inventory_halflife: 300, ..loyal };
+        let bytes = decaying.to_bytes();
+        assert_eq!((bytes.len(), SpreadSchedule::from_bytes(&bytes)), (SCHEDULE_DECAY_LEN, Ok(decaying.clone())));
+        let banded = SpreadSchedule { soft_inventory: 2_000_000, band_k_bps: 100, ..decaying };
+        let bytes = banded.to_bytes();
+        assert_eq!((bytes.len(), SpreadSchedule::from_bytes(&bytes)), (SCHEDULE_BAND_LEN, Ok(banded.clone())));
+        let capped = SpreadSchedule { max_short_inventory: 500_000, ..banded };
+        let bytes = capped.to_bytes();
+        assert_eq!((bytes.len(), SpreadSchedule::from_bytes(&bytes)), (SCHEDULE_CAPS_LEN, Ok(capped.clone())));
+        let cautious = SpreadSchedule { reduce_only_bps: 8_000, ..capped };
+        let bytes = cautious.to_bytes();
+        assert_eq!((bytes.len(), SpreadSchedule::from_bytes(&bytes)), (SCHEDULE_REDUCE_ONLY_LEN, Ok(cautious.clone())));
+        let scaled = SpreadSchedule { max_inventory_bps: 30_000, ..cautious };
+        let bytes = scaled.to_bytes();
+        assert_eq!((bytes.len(), SpreadSchedule::from_bytes(&bytes)), (SCHEDULE_DEPTH_CAP_LEN, Ok(scaled.clone())));
+        let signals = vec![RegisteredSignal { signal: Pubkey::new_unique(), owner: Pubkey::new_unique() }];
+        let signalled = SpreadSchedule { signals, ..scaled };
+        let bytes = signalled.to_bytes();
+        assert_eq!((bytes.len(), SpreadSchedule::from_bytes(&bytes)), (SCHEDULE_SIGNALS_LEN, Ok(signalled)));
+    }
+
+    #[test]
+    fn test_trader_account_decodes() {
+        let (ctx, trader) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = vec![0u8; TRADER_LEN];
+        write(&mut data, TRADER_MAGIC_OFF, &TRADER_MAGIC.to_le_bytes());
+        write(&mut data, TRADER_CTX_OFF, ctx.as_ref());
+        write(&mut data, TRADER_KEY_OFF, trader.as_ref());
+        write(&mut data, TRADER_VOLUME_OFF, &5_000u128.to_le_bytes());
+        write(&mut data, TRADER_FILLS_OFF, &4u64.to_le_bytes());
+        write(&mut data, TRADER_LAST_SLOT_OFF, &99u64.to_le_bytes());
+        let account = TraderAccount::from_bytes(&data).unwrap();
+        assert_eq!(account, TraderAccount { ctx, trader, volume: 5_000, fills: 4, last_slot: 99 });
+        assert_eq!(TraderAccount::from_bytes(&data[..TRADER_LEN - 1]), Err(ProgramError::AccountDataTooSmall));
+        assert_eq!(TraderAccount::from_bytes(&[0u8; TRADER_LEN]), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn test_ladder_roundtrip() {
+        let level = |size, exec_price_e6| LadderLevel { size, exec_price_e6, spread_bps: 20 };
+        let ladder = Ladder {
+            ctx: Pubkey::new_unique(),
+            market: 2,
+            slot: 300,
+            oracle_price_e6: 100_000_000,
+            step_size: 1_000,
+            bids: vec![level(1_000, 99_800_000), level(1_500, 99_700_000)],
+            asks: vec![],
+            bid_reason: 0,
+            ask_reason: 44,
+        };
+        let bytes = ladder.to_bytes();
+        assert_eq!(bytes.len(), LADDER_LEN);
+        assert_eq!(Ladder::from_bytes(&bytes), Ok(ladder));
+        assert_eq!(Ladder::from_bytes(&bytes[..LADDER_LEN - 1]), Err(ProgramError::AccountDataTooSmall));
+        assert_eq!(Ladder::from_bytes(&[0u8; LADDER_LEN]), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn test_tip_jar_roundtrip() {
+        let jar = TipJar { ctx: Pubkey::new_unique(), lamports_per_slot: 10, max_tip: 5_000, paid: 3_000, tips: 2 };
+        let bytes = jar.to_bytes();
+        assert_eq!(TipJar::from_bytes(&bytes), Ok(jar));
+        assert_eq!(TipJar::from_bytes(&bytes[..TIP_JAR_LEN - 1]), Err(ProgramError::AccountDataTooSmall));
+        assert_eq!(TipJar::from_bytes(&[0u8; TIP_JAR_LEN]), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn test_referrals_roundtrip() {
+        let referrer = Referrer { referrer: Pubkey::new_unique(), accrued_fees: 1_234, fills: 3 };
+        let referrals = Referrals { ctx: Pubkey::new_unique(), share_bps: 2_000, referrers: vec![referrer] };
+        let bytes = referrals.to_bytes();
+        assert_eq!(bytes.len(), REFERRAL_LEN);
+        assert_eq!(Referrals::from_bytes(&bytes), Ok(referrals.clone()));
+        assert_eq!(referrals.get(&referrer.referrer), Some(&referrer));
+        assert_eq!(referrals.get(&Pubkey::new_unique()), None);
+        assert_eq!(Referrals::from_bytes(&bytes[..REFERRAL_LEN - 1]), Err(ProgramError::AccountDataTooSmall));
+        assert_eq!(Referrals::from_bytes(&[0u8; REFERRAL_LEN]), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn test_signal_account_roundtrip() {
+        let signal = SignalAccount { value_bps: 40, weight_bps: 5_000, stale_after_slot: 100 };
+        let bytes = signal.to_bytes();
+        assert_eq!(SignalAccount::from_bytes(&bytes), Ok(signal));
+        assert_eq!((signal.surcharge_bps(100), signal.surcharge_bps(101)), (20, 0));
+        assert_eq!(SignalAccount::from_bytes(&bytes[..SIGNAL_LEN - 1]), Err(ProgramError::AccountDataTooSmall));
+        assert_eq!(SignalAccount::from_bytes(&[0u8; SIGNAL_LEN]), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn test_fee_ledger_decodes_the_stats_tail() {
+        let mut data = vec![0u8; STATS_LEDGER_LEN];
+        write(&mut data, STATS_MAGIC_OFF, &STATS_MAGIC.to_le_bytes());
+        write(&mut data, STATS_WITHDRAWN_OFF, &900u128.to_le_bytes());
+        write(&mut data, STATS_WITHDRAWALS_OFF, &2u64.to_le_bytes());
+        write(&mut data, STATS_LAST_WITHDRAWAL_OFF, &77u64.to_le_bytes());
+        let ledger = FeeLedger::from_bytes(&data).unwrap();
+        assert_eq!(ledger, FeeLedger { withdrawn: 900, withdrawals: 2, last_withdrawal_slot: 77 });
+        assert_eq!((ledger.available(1_000), ledger.available(0)), (100, 0));
+        assert_eq!(FeeLedger::from_bytes(&data[..STATS_LEN]), Err(ProgramError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn test_insurance_skim_decodes_the_stats_tail() {
+        let mut data = vec![0u8; STATS_SKIM_LEN];
+        write(&mut data, STATS_MAGIC_OFF, &STATS_MAGIC.to_le_bytes());
+        write(&mut data, STATS_SKIM_BPS_OFF, &2_500u16.to_le_bytes());
+        write(&mut data, STATS_SKIM_ACCRUED_OFF, &900u128.to_le_bytes());
+        write(&mut data, STATS_SKIM_CLAIMED_OFF, &600u128.to_le_bytes());
+        write(&mut data, STATS_SKIM_CLAIMS_OFF, &3u64.to_le_bytes());
+        let skim = InsuranceSkim::from_bytes(&data).unwrap();
+        assert_eq!(skim, InsuranceSkim { skim_bps: 2_500, accrued: 900, claimed: 600, claims: 3 });
+        assert_eq!(skim.claimable(), 300);
+        assert_eq!(InsuranceSkim::from_bytes(&data[..STATS_LEDGER_LEN]), Err(ProgramError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn test_protocol_fee_decodes_the_stats_tail() {
+        let treasury = Pubkey::new_unique();
+        let mut data = vec![0u8; STATS_PROTOCOL_LEN];
+        write(&mut data, STATS_MAGIC_OFF, &STATS_MAGIC.to_le_bytes());
+        write(&mut data, STATS_TREASURY_OFF, treasury.as_ref());
+        write(&mut data, STATS_PROTOCOL_FEE_BPS_OFF, &2_000u16.to_le_bytes());
+        write(&mut data, STATS_PROTOCOL_ACCRUED_OFF, &500u128.to_le_bytes());
+        write(&mut data, STATS_PROTOCOL_SWEPT_OFF, &200u128.to_le_bytes());
+        write(&mut data, STATS_PROTOCOL_SWEEPS_OFF, &1u64.to_le_bytes());
+        let fee = ProtocolFee::from_bytes(&data).unwrap();
+        assert_eq!(fee, ProtocolFee { treasury, fee_bps: 2_000, accrued: 500, swept: 200, sweeps: 1 });
+        assert_eq!(fee.sweepable(), 300);
+        assert_eq!(ProtocolFee::from_bytes(&data[..STATS_SKIM_LEN]), Err(ProgramError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn test_inventory_pnl_decodes_the_stats_tail() {
+        let mut data = vec![0u8; STATS_PNL_LEN];
+        write(&mut data, STATS_MAGIC_OFF, &STATS_MAGIC.to_le_bytes());
+        write(&mut data, STATS_PNL_ENTRY_OFF, &100_150_000u64.to_le_bytes());
+        write(&mut data, STATS_PNL_MARK_OFF, &101_000_000u64.to_le_bytes());
+        write(&mut data, STATS_PNL_REALIZED_OFF, &300i128.to_le_bytes());
+        write(&mut data, STATS_PNL_UNREALIZED_OFF, &(-850i128).to_le_bytes());
+        let pnl = InventoryPnl::from_bytes(&data).unwrap();
+        let expected = InventoryPnl { entry_price_e6: 100_150_000, mark_price_e6: 101_000_000, realized: 300, unrealized: -850 };
+        assert_eq!((pnl, pnl.total()), (expected, -550));
+        assert_eq!(InventoryPnl::from_bytes(&data[..STATS_PROTOCOL_LEN]), Err(ProgramError::AccountDataTooSmall));
+    }
+}
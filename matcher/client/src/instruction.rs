@@ -0,0 +1,1655 @@
+//! Instruction builders, one per program instruction, with the account metas
+//! in the order and with the flags the program expects.
+//!
+//! Builders whose optional accounts are positional (a stats account goes after
+//! the slab, an authority after the slab) return `InvalidArgument` when a later
+//! optional account is given without the earlier one.
+
+use solana_program::{
+    ed25519_program,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar,
+};
+
+use crate::context::{FeeTier, LoyaltyTier, ScheduleEntry};
+use crate::layout::*;
+use crate::params::MatcherParams;
+
+/// The call percolator makes into the matcher for one trade.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MatchCall {
+    pub req_id: u64,
+    pub lp_idx: u16,
+    pub lp_account_id: u64,
+    pub oracle_price_e6: u64,
+    /// Signed base size: positive when the taker buys.
+    pub trade_size: i128,
+}
+
+impl MatchCall {
+    /// Encode as Match instruction data.
+    pub fn pack(&self) -> [u8; CALL_LEN] {
+        let mut data = [0u8; CALL_LEN];
+        data[0] = TAG_MATCH;
+        data[CALL_REQ_ID_OFF..CALL_REQ_ID_OFF + 8].copy_from_slice(&self.req_id.to_le_bytes());
+        data[CALL_LP_IDX_OFF..CALL_LP_IDX_OFF + 2].copy_from_slice(&self.lp_idx.to_le_bytes());
+        data[CALL_LP_ACCOUNT_ID_OFF..CALL_LP_ACCOUNT_ID_OFF + 8].copy_from_slice(&self.lp_account_id.to_le_bytes());
+        data[CALL_ORACLE_PRICE_OFF..CALL_ORACLE_PRICE_OFF + 8].copy_from_slice(&self.oracle_price_e6.to_le_bytes());
+        data[CALL_TRADE_SIZE_OFF..CALL_TRADE_SIZE_OFF + 16].copy_from_slice(&self.trade_size.to_le_bytes());
+        data
+    }
+
+    /// Decode Match instruction data, as the matcher (or a stand-in for
+    /// percolator relaying it) reads it.
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() != CALL_LEN || data[0] != TAG_MATCH {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let field = |off: usize, len: usize| &data[off..off + len];
+        Ok(Self {
+            req_id: u64::from_le_bytes(field(CALL_REQ_ID_OFF, 8).try_into().unwrap()),
+            lp_idx: u16::from_le_bytes(field(CALL_LP_IDX_OFF, 2).try_into().unwrap()),
+            lp_account_id: u64::from_le_bytes(field(CALL_LP_ACCOUNT_ID_OFF, 8).try_into().unwrap()),
+            oracle_price_e6: u64::from_le_bytes(field(CALL_ORACLE_PRICE_OFF, 8).try_into().unwrap()),
+            trade_size: i128::from_le_bytes(field(CALL_TRADE_SIZE_OFF, 16).try_into().unwrap()),
+        })
+    }
+}
+
+/// A quote the LP's RFQ signer signs off-chain for PostRfq to post.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RfqQuote {
+    /// The context the quote is for.
+    pub ctx: Pubkey,
+    pub price_e6: u64,
+    /// Signed like a trade: positive when takers may buy.
+    pub size: i128,
+    /// Last slot the quote fills in.
+    pub expiry_slot: u64,
+    /// Above the nonce of every quote posted to the context before.
+    pub nonce: u64,
+}
+
+impl RfqQuote {
+    /// The bytes the RFQ signer signs, as PostRfq carries them.
+    pub fn message(&self) -> [u8; RFQ_MSG_LEN] {
+        let mut data = [0u8; RFQ_MSG_LEN];
+        data[RFQ_CTX_OFF..RFQ_CTX_OFF + 32].copy_from_slice(self.ctx.as_ref());
+        data[RFQ_PRICE_OFF..RFQ_PRICE_OFF + 8].copy_from_slice(&self.price_e6.to_le_bytes());
+        data[RFQ_SIZE_OFF..RFQ_SIZE_OFF + 16].copy_from_slice(&self.size.to_le_bytes());
+        data[RFQ_EXPIRY_OFF..RFQ_EXPIRY_OFF + 8].copy_from_slice(&self.expiry_slot.to_le_bytes());
+        data[RFQ_NONCE_OFF..RFQ_NONCE_OFF + 8].copy_from_slice(&self.nonce.to_le_bytes());
+        data
+    }
+
+    /// Decode a signed message.
+    pub fn from_message(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() != RFQ_MSG_LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let field = |off: usize, len: usize| &data[off..off + len];
+        Ok(Self {
+            ctx: Pubkey::new_from_array(field(RFQ_CTX_OFF, 32).try_into().unwrap()),
+            price_e6: u64::from_le_bytes(field(RFQ_PRICE_OFF, 8).try_into().unwrap()),
+            size: i128::from_le_bytes(field(RFQ_SIZE_OFF, 16).try_into().unwrap()),
+            expiry_slot: u64::from_le_bytes(field(RFQ_EXPIRY_OFF, 8).try_into().unwrap()),
+            nonce: u64::from_le_bytes(field(RFQ_NONCE_OFF, 8).try_into().unwrap()),
+        })
+    }
+}
+
+/// The accounts ClaimSkim passes on to percolator's TopUpInsurance, and how
+/// much of the accrued skim to pay in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SkimClaim {
+    pub authority: Pubkey,
+    pub ctx: Pubkey,
+    pub stats: Pubkey,
+    /// The slab the context was bound to at init.
+    pub slab: Pubkey,
+    /// The authority's token account the tokens come out of.
+    pub source: Pubkey,
+    /// The slab's vault.
+    pub vault: Pubkey,
+    pub token_program: Pubkey,
+    /// The percolator program that owns the slab.
+    pub percolator: Pubkey,
+    pub amount: u64,
+}
+
+/// Positional optional accounts: `later` may only be passed after `earlier`.
+fn optional_pair(earlier: Option<AccountMeta>, later: Option<AccountMeta>) -> Result<Vec<AccountMeta>, ProgramError> {
+    match (earlier, later) {
+        (None, Some(_)) => Err(ProgramError::InvalidArgument),
+        (a, b) => Ok(a.into_iter().chain(b).collect()),
+    }
+}
+
+fn with_params(tag: u8, params: &MatcherParams) -> Vec<u8> {
+    let mut data = vec![tag];
+    data.extend_from_slice(&params.pack());
+    data
+}
+
+/// Match (0x00): `[lp_pda (signer), ctx (writable), slab?, stats? (writable)]`.
+pub fn match_trade(
+    program_id: &Pubkey,
+    lp_pda: &Pubkey,
+    ctx: &Pubkey,
+    slab: Option<&Pubkey>,
+    stats: Option<&Pubkey>,
+    call: &MatchCall,
+) -> Result<Instruction, ProgramError> {
+    let mut accounts = vec![AccountMeta::new_readonly(*lp_pda, true), AccountMeta::new(*ctx, false)];
+    accounts.extend(optional_pair(
+        slab.map(|k| AccountMeta::new_readonly(*k, false)),
+        stats.map(|k| AccountMeta::new(*k, false)),
+    )?);
+    Ok(Instruction::new_with_bytes(*program_id, &call.pack(), accounts))
+}
+
+/// Init (0x02): `[lp_pda, ctx (writable), slab?, authority?]`, signed by the
+/// authority when one is named and by the LP PDA otherwise. Binding a slab
+/// or a separate authority needs a v6 (CTX_EXT_LEN) context account. A
+/// context [`create_context`] made takes the LP PDA's signature either way.
+pub fn init(
+    program_id: &Pubkey,
+    lp_pda: &Pubkey,
+    ctx: &Pubkey,
+    slab: Option<&Pubkey>,
+    authority: Option<&Pubkey>,
+    params: &MatcherParams,
+) -> Result<Instruction, ProgramError> {
+    init_with_kind(program_id, lp_pda, ctx, slab, authority, KIND_CREDIBILITY, params)
+}
+
+/// [`init`] for a given matcher kind: `KIND_CREDIBILITY`, `KIND_PLAIN` for
+/// imbalance-only pricing on the same context layout, `KIND_MULTI` for the
+/// blended signals (v6 only), or `KIND_STABLE` for pegged markets.
+pub fn init_with_kind(
+    program_id: &Pubkey,
+    lp_pda: &Pubkey,
+    ctx: &Pubkey,
+    slab: Option<&Pubkey>,
+    authority: Option<&Pubkey>,
+    kind: u8,
+    params: &MatcherParams,
+) -> Result<Instruction, ProgramError> {
+    let mut accounts = vec![AccountMeta::new_readonly(*lp_pda, authority.is_none()), AccountMeta::new(*ctx, false)];
+    accounts.extend(optional_pair(
+        slab.map(|k| AccountMeta::new_readonly(*k, false)),
+        authority.map(|k| AccountMeta::new_readonly(*k, true)),
+    )?);
+    let mut data = vec![TAG_INIT, kind];
+    data.extend_from_slice(&params.pack());
+    Ok(Instruction::new_with_bytes(*program_id, &data, accounts))
+}
+
+/// UpdateCredibility (0x03): `[ctx (writable), slab, clock sysvar]`. Permissionless;
+/// see [`with_tip_jar`] to collect a keeper tip.
+pub fn update_credibility(program_id: &Pubkey, ctx: &Pubkey, slab: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &[TAG_UPDATE_CREDIBILITY],
+        vec![
+            AccountMeta::new(*ctx, false),
+            AccountMeta::new_readonly(*slab, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+fn authority_ix(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, data: &[u8]) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        data,
+        vec![AccountMeta::new_readonly(*authority, true), AccountMeta::new(*ctx, false)],
+    )
+}
+
+/// UpdateParams (0x04): `[authority (signer), ctx (writable)]`. Before the first fill only.
+pub fn update_params(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, params: &MatcherParams) -> Instruction {
+    authority_ix(program_id, authority, ctx, &with_params(TAG_UPDATE_PARAMS, params))
+}
+
+/// Close (0x05): `[authority (signer), ctx (writable), destination (writable), stats? (writable)]`.
+pub fn close(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    ctx: &Pubkey,
+    destination: &Pubkey,
+    stats: Option<&Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*ctx, false),
+        AccountMeta::new(*destination, false),
+    ];
+    accounts.extend(stats.map(|k| AccountMeta::new(*k, false)));
+    Instruction::new_with_bytes(*program_id, &[TAG_CLOSE], accounts)
+}
+
+/// SetPaused (0x06).
+pub fn set_paused(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, paused: bool) -> Instruction {
+    authority_ix(program_id, authority, ctx, &[TAG_SET_PAUSED, paused as u8])
+}
+
+/// The context CreateContext makes for `lp_pda` on `slab`, and its bump.
+pub fn context_address(program_id: &Pubkey, lp_pda: &Pubkey, slab: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONTEXT_SEED, lp_pda.as_ref(), slab.as_ref()], program_id)
+}
+
+/// CreateContext (0x38): `[lp_pda, ctx (writable), slab, payer (signer,
+/// writable), system_program]`. Creates the [`context_address`] account with
+/// `len` bytes, at least CTX_EXT_LEN + CREATION_LEN (the record marks it
+/// derived), for an [`init`] with the same LP PDA and slab to set up.
+pub fn create_context(program_id: &Pubkey, lp_pda: &Pubkey, slab: &Pubkey, payer: &Pubkey, len: u32) -> Instruction {
+    let (ctx, _) = context_address(program_id, lp_pda, slab);
+    let mut data = vec![TAG_CREATE_CONTEXT];
+    data.extend_from_slice(&len.to_le_bytes());
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*lp_pda, false),
+            AccountMeta::new(ctx, false),
+            AccountMeta::new_readonly(*slab, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+    )
+}
+
+/// Migrate (0x07): `[ctx (writable), payer (signer, writable), system_program]`.
+pub fn migrate(program_id: &Pubkey, ctx: &Pubkey, payer: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &[TAG_MIGRATE],
+        vec![
+            AccountMeta::new(*ctx, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+    )
+}
+
+fn quote_ix(program_id: &Pubkey, ctx: &Pubkey, stats: Option<&Pubkey>, tag: u8, oracle_price_e6: u64, size: [u8; 16]) -> Instruction {
+    let mut data = vec![tag];
+    data.extend_from_slice(&oracle_price_e6.to_le_bytes());
+    data.extend_from_slice(&size);
+    let mut accounts = vec![AccountMeta::new_readonly(*ctx, false)];
+    accounts.extend(stats.map(|k| AccountMeta::new_readonly(*k, false)));
+    Instruction::new_with_bytes(*program_id, &data, accounts)
+}
+
+/// QuotePreview (0x08): `[ctx, stats?]`. Read-only; simulate it and decode the
+/// QUOTE_LEN return data.
+pub fn quote_preview(program_id: &Pubkey, ctx: &Pubkey, stats: Option<&Pubkey>, oracle_price_e6: u64, trade_size: i128) -> Instruction {
+    quote_ix(program_id, ctx, stats, TAG_QUOTE_PREVIEW, oracle_price_e6, trade_size.to_le_bytes())
+}
+
+/// ProposeParams (0x09): queue `params` behind the timelock.
+pub fn propose_params(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, params: &MatcherParams) -> Instruction {
+    authority_ix(program_id, authority, ctx, &with_params(TAG_PROPOSE_PARAMS, params))
+}
+
+/// ProposeParams (0x09) with no block: cancel the pending proposal.
+pub fn cancel_params(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey) -> Instruction {
+    authority_ix(program_id, authority, ctx, &[TAG_PROPOSE_PARAMS])
+}
+
+/// CommitParams (0x0A): `[ctx (writable)]`. Permissionless.
+pub fn commit_params(program_id: &Pubkey, ctx: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(*program_id, &[TAG_COMMIT_PARAMS], vec![AccountMeta::new(*ctx, false)])
+}
+
+/// SetReduceOnly (0x0B).
+pub fn set_reduce_only(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, on: bool) -> Instruction {
+    authority_ix(program_id, authority, ctx, &[TAG_SET_REDUCE_ONLY, on as u8])
+}
+
+/// SettleInventory (0x0C): `mode` is SETTLE_SET or SETTLE_ADJUST.
+pub fn settle_inventory(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, mode: u8, value: i128) -> Instruction {
+    let mut data = vec![TAG_SETTLE_INVENTORY, mode];
+    data.extend_from_slice(&value.to_le_bytes());
+    authority_ix(program_id, authority, ctx, &data)
+}
+
+/// ProposeAuthority (0x0D): the default key cancels a pending rotation.
+pub fn propose_authority(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, new_authority: &Pubkey) -> Instruction {
+    let mut data = vec![TAG_PROPOSE_AUTHORITY];
+    data.extend_from_slice(new_authority.as_ref());
+    authority_ix(program_id, authority, ctx, &data)
+}
+
+/// AcceptAuthority (0x0E): signed by the proposed authority.
+pub fn accept_authority(program_id: &Pubkey, new_authority: &Pubkey, ctx: &Pubkey) -> Instruction {
+    authority_ix(program_id, new_authority, ctx, &[TAG_ACCEPT_AUTHORITY])
+}
+
+/// SetReturnDataOnly (0x0F).
+pub fn set_return_data_only(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, on: bool) -> Instruction {
+    authority_ix(program_id, authority, ctx, &[TAG_SET_RETURN_DATA_ONLY, on as u8])
+}
+
+/// SetSoftReject (0x10).
+pub fn set_soft_reject(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, on: bool) -> Instruction {
+    authority_ix(program_id, authority, ctx, &[TAG_SET_SOFT_REJECT, on as u8])
+}
+
+/// QuoteTwoSided (0x11): `[ctx, stats?]`. Read-only; decode TWO_SIDED_LEN
+/// return data. Sizes above `i128::MAX` are rejected by the program.
+pub fn quote_two_sided(program_id: &Pubkey, ctx: &Pubkey, stats: Option<&Pubkey>, oracle_price_e6: u64, size: u128) -> Instruction {
+    quote_ix(program_id, ctx, stats, TAG_QUOTE_TWO_SIDED, oracle_price_e6, size.to_le_bytes())
+}
+
+/// InitStats (0x12): `[authority (signer), ctx (writable), stats (writable)]`.
+/// The stats account must already exist: STATS_LEN bytes, rent-exempt, owned
+/// by the program.
+pub fn init_stats(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, stats: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &[TAG_INIT_STATS],
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*ctx, false),
+            AccountMeta::new(*stats, false),
+        ],
+    )
+}
+
+/// InitStats (0x12) with a protocol fee: `[authority (signer), ctx
+/// (writable), stats (writable), treasury]`. Every Match that passes the
+/// stats account then accrues `protocol_fee_bps` of the fill's fee to
+/// `treasury`, for the life of the account, which must have been created with
+/// STATS_PROTOCOL_LEN bytes.
+pub fn init_stats_with_protocol_fee(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    ctx: &Pubkey,
+    stats: &Pubkey,
+    treasury: &Pubkey,
+    protocol_fee_bps: u16,
+) -> Instruction {
+    let mut ix = init_stats(program_id, authority, ctx, stats);
+    ix.data.extend_from_slice(&protocol_fee_bps.to_le_bytes());
+    ix.accounts.push(AccountMeta::new_readonly(*treasury, false));
+    ix
+}
+
+/// SetRfqSigner (0x13): the default key turns RFQ off. Either way any posted
+/// quote is withdrawn.
+pub fn set_rfq_signer(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, signer: &Pubkey) -> Instruction {
+    let mut data = vec![TAG_SET_RFQ_SIGNER];
+    data.extend_from_slice(signer.as_ref());
+    authority_ix(program_id, authority, ctx, &data)
+}
+
+/// PostRfq (0x14): `[ctx (writable), instructions sysvar]`. Permissionless;
+/// send it right after [`rfq_signature`] for the same quote.
+pub fn post_rfq(program_id: &Pubkey, quote: &RfqQuote) -> Instruction {
+    let mut data = vec![TAG_POST_RFQ];
+    data.extend_from_slice(&quote.message());
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![AccountMeta::new(quote.ctx, false), AccountMeta::new_readonly(solana_instructions_sysvar::ID, false)],
+    )
+}
+
+/// SetMarket (0x15): `[authority (signer), ctx (writable), slab]`. Lists
+/// `slab` in a shared context's market table, or updates its caps; a zero cap
+/// keeps the context's.
+pub fn set_market(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, slab: &Pubkey, max_fill: u128, max_inventory: u128) -> Instruction {
+    let mut data = vec![TAG_SET_MARKET];
+    data.extend_from_slice(&max_fill.to_le_bytes());
+    data.extend_from_slice(&max_inventory.to_le_bytes());
+    let mut ix = authority_ix(program_id, authority, ctx, &data);
+    ix.accounts.push(AccountMeta::new_readonly(*slab, false));
+    ix
+}
+
+/// SetNoBids (0x16): with bids off, Matches selling to the LP come back as
+/// zero fills with reason SideDisabled.
+pub fn set_no_bids(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, on: bool) -> Instruction {
+    authority_ix(program_id, authority, ctx, &[TAG_SET_NO_BIDS, on as u8])
+}
+
+/// SetNoAsks (0x17): the same for Matches buying from the LP.
+pub fn set_no_asks(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, on: bool) -> Instruction {
+    authority_ix(program_id, authority, ctx, &[TAG_SET_NO_ASKS, on as u8])
+}
+
+/// InitSchedule (0x18): `[authority (signer), ctx (writable), schedule
+/// (writable)]`. The schedule account must already exist: SCHEDULE_LEN bytes,
+/// rent-exempt, owned by the program.
+pub fn init_schedule(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, schedule: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &[TAG_INIT_SCHEDULE],
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*ctx, false),
+            AccountMeta::new(*schedule, false),
+        ],
+    )
+}
+
+/// SetSchedule (0x19): `[authority (signer), ctx, schedule (writable)]`.
+/// Replaces the schedule's entries; more than MAX_SCHEDULE_ENTRIES is
+/// InvalidArgument.
+pub fn set_schedule(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    ctx: &Pubkey,
+    schedule: &Pubkey,
+    entries: &[ScheduleEntry],
+) -> Result<Instruction, ProgramError> {
+    if entries.len() > MAX_SCHEDULE_ENTRIES {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mut data = vec![TAG_SET_SCHEDULE, entries.len() as u8];
+    for entry in entries {
+        data.extend_from_slice(&entry.start_slot.to_le_bytes());
+        data.extend_from_slice(&entry.end_slot.to_le_bytes());
+        data.extend_from_slice(&entry.multiplier_bps.to_le_bytes());
+    }
+    Ok(Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*schedule, false),
+        ],
+    ))
+}
+
+/// SetFeeTiers (0x22): `[authority (signer), ctx, schedule (writable)]`.
+/// Replaces the fee tiers of a schedule account created with
+/// SCHEDULE_FEES_LEN bytes, before the market's first fill; more than
+/// MAX_FEE_TIERS is InvalidArgument.
+pub fn set_fee_tiers(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    ctx: &Pubkey,
+    schedule: &Pubkey,
+    tiers: &[FeeTier],
+) -> Result<Instruction, ProgramError> {
+    if tiers.len() > MAX_FEE_TIERS {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mut data = vec![TAG_SET_FEE_TIERS, tiers.len() as u8];
+    for tier in tiers {
+        data.extend_from_slice(&tier.min_notional.to_le_bytes());
+        data.extend_from_slice(&tier.fee_bps.to_le_bytes());
+    }
+    Ok(Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*schedule, false),
+        ],
+    ))
+}
+
+/// SetRebate (0x23): `[authority (signer), ctx, schedule (writable)]`. Fills
+/// that shrink |inventory| get `rebate_bps` off their spread and fee; the
+/// schedule account must have been created with SCHEDULE_REBATE_LEN bytes.
+pub fn set_rebate(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, schedule: &Pubkey, rebate_bps: u16) -> Instruction {
+    let mut data = vec![TAG_SET_REBATE];
+    data.extend_from_slice(&rebate_bps.to_le_bytes());
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*schedule, false),
+        ],
+    )
+}
+
+/// SetFeeDiscount (0x25): `[authority (signer), ctx, schedule (writable)]`.
+/// A quote that earned the whole credibility discount waives
+/// `fee_discount_bps` of its fee, one part way that share of it; the schedule
+/// account must have been created with SCHEDULE_REBATE_LEN bytes.
+pub fn set_fee_discount(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, schedule: &Pubkey, fee_discount_bps: u16) -> Instruction {
+    let mut data = vec![TAG_SET_FEE_DISCOUNT];
+    data.extend_from_slice(&fee_discount_bps.to_le_bytes());
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*schedule, false),
+        ],
+    )
+}
+
+/// SetMinNotional (0x2B): `[authority (signer), ctx, schedule (writable)]`.
+/// Trades whose notional at the oracle falls below `min_notional_e6` are
+/// refused with BelowMinNotional (soft-rejected in soft-reject mode); the
+/// schedule account must have been created with SCHEDULE_MIN_NOTIONAL_LEN
+/// bytes.
+pub fn set_min_notional(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, schedule: &Pubkey, min_notional_e6: u128) -> Instruction {
+    let mut data = vec![TAG_SET_MIN_NOTIONAL];
+    data.extend_from_slice(&min_notional_e6.to_le_bytes());
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*schedule, false),
+        ],
+    )
+}
+
+/// SetInventoryDecay (0x31): `[authority (signer), ctx, schedule
+/// (writable)]`. The imbalance and skew penalties then price the inventory as
+/// halving every `halflife_slots` without a fill (0 turns it off); before the
+/// first fill only, on a schedule account created with SCHEDULE_DECAY_LEN
+/// bytes.
+pub fn set_inventory_decay(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, schedule: &Pubkey, halflife_slots: u32) -> Instruction {
+    let mut data = vec![TAG_SET_INVENTORY_DECAY];
+    data.extend_from_slice(&halflife_slots.to_le_bytes());
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*schedule, false),
+        ],
+    )
+}
+
+/// SetInventoryBand (0x32): `[authority (signer), ctx, schedule (writable)]`.
+/// Past `soft_inventory`, a fill that takes the inventory deeper pays up to
+/// `band_k_bps` more, growing with the square of how far toward max_inventory
+/// it gets (0 turns it off); before the first fill only, on a schedule
+/// account created with SCHEDULE_BAND_LEN bytes.
+pub fn set_inventory_band(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, schedule: &Pubkey, soft_inventory: u128, band_k_bps: u32) -> Instruction {
+    let mut data = vec![TAG_SET_INVENTORY_BAND];
+    data.extend_from_slice(&soft_inventory.to_le_bytes());
+    data.extend_from_slice(&band_k_bps.to_le_bytes());
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*schedule, false),
+        ],
+    )
+}
+
+/// SetInventoryCaps (0x33): `[authority (signer), ctx, schedule
+/// (writable)]`. Caps the LP's long book (negative inventory) and short book
+/// (positive) separately, a zero cap leaving
+/// that side at max_inventory; takes effect at once, on a schedule account
+/// created with SCHEDULE_CAPS_LEN bytes.
+pub fn set_inventory_caps(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    ctx: &Pubkey,
+    schedule: &Pubkey,
+    max_long_inventory: u128,
+    max_short_inventory: u128,
+) -> Instruction {
+    let mut data = vec![TAG_SET_INVENTORY_CAPS];
+    data.extend_from_slice(&max_long_inventory.to_le_bytes());
+    data.extend_from_slice(&max_short_inventory.to_le_bytes());
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*schedule, false),
+        ],
+    )
+}
+
+/// SetReduceOnlyThreshold (0x34): `[authority (signer), ctx, schedule
+/// (writable)]`. Sets the share of the cap, in bps, past which the book goes
+/// reduce-only on its own (0 = AUTO_REDUCE_ONLY_BPS); takes effect at once, on
+/// a schedule account created with SCHEDULE_REDUCE_ONLY_LEN bytes.
+pub fn set_reduce_only_threshold(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    ctx: &Pubkey,
+    schedule: &Pubkey,
+    reduce_only_bps: u16,
+) -> Instruction {
+    let mut data = vec![TAG_SET_REDUCE_ONLY_THRESHOLD];
+    data.extend_from_slice(&reduce_only_bps.to_le_bytes());
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*schedule, false),
+        ],
+    )
+}
+
+/// SetInventoryDepthCap (0x36): `[authority (signer), ctx, schedule
+/// (writable)]`. Sets max_inventory to `max_inventory_bps` of
+/// liquidity_notional_e6 at the oracle (0 = max_inventory as set); takes
+/// effect at once, on a schedule account created with SCHEDULE_DEPTH_CAP_LEN
+/// bytes.
+pub fn set_inventory_depth_cap(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    ctx: &Pubkey,
+    schedule: &Pubkey,
+    max_inventory_bps: u32,
+) -> Instruction {
+    let mut data = vec![TAG_SET_INVENTORY_DEPTH_CAP];
+    data.extend_from_slice(&max_inventory_bps.to_le_bytes());
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*schedule, false),
+        ],
+    )
+}
+
+/// TransferInventory (0x35): `[from_authority (signer), from_ctx (writable),
+/// to_authority (signer), to_ctx (writable)]`. Moves `amount` of inventory
+/// from the source book to the destination's; both must quote the same slab.
+/// On shared contexts, [`on_market`] once names the source's entry and twice
+/// the destination's after it.
+pub fn transfer_inventory(
+    program_id: &Pubkey,
+    from_authority: &Pubkey,
+    from_ctx: &Pubkey,
+    to_authority: &Pubkey,
+    to_ctx: &Pubkey,
+    amount: i128,
+) -> Instruction {
+    let mut data = vec![TAG_TRANSFER_INVENTORY];
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*from_authority, true),
+            AccountMeta::new(*from_ctx, false),
+            AccountMeta::new_readonly(*to_authority, true),
+            AccountMeta::new(*to_ctx, false),
+        ],
+    )
+}
+
+/// SyncInventory (0x37): `[ctx (writable), slab]`. Sets the book's inventory
+/// from the position of the LP at `lp_idx` in the slab's account table, which
+/// must be matched through `ctx`. Permissionless.
+pub fn sync_inventory(program_id: &Pubkey, ctx: &Pubkey, slab: &Pubkey, lp_idx: u16) -> Instruction {
+    let mut data = vec![TAG_SYNC_INVENTORY];
+    data.extend_from_slice(&lp_idx.to_le_bytes());
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![AccountMeta::new(*ctx, false), AccountMeta::new_readonly(*slab, false)],
+    )
+}
+
+/// SetSignals (0x3A): `[authority (signer), ctx, schedule (writable),
+/// signals...]`. Registers `signals`, up to MAX_SIGNAL_ACCOUNTS, as the ones
+/// every trade and quote must pass, in this order, on a schedule account
+/// created with SCHEDULE_SIGNALS_LEN bytes; none clears the list.
+pub fn set_signals(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, schedule: &Pubkey, signals: &[Pubkey]) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new_readonly(*ctx, false),
+        AccountMeta::new(*schedule, false),
+    ];
+    accounts.extend(signals.iter().map(|k| AccountMeta::new_readonly(*k, false)));
+    Instruction::new_with_bytes(*program_id, &[TAG_SET_SIGNALS], accounts)
+}
+
+/// BindSlab (0x39): `[authority (signer), ctx (writable), slab]`. Binds an
+/// unbound v6 context to `slab` and pins its owner as the percolator program;
+/// without it UpdateCredibility and SyncInventory take any slab the
+/// percolator program owns.
+pub fn bind_slab(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, slab: &Pubkey) -> Instruction {
+    let mut ix = authority_ix(program_id, authority, ctx, &[TAG_BIND_SLAB]);
+    ix.accounts.push(AccountMeta::new_readonly(*slab, false));
+    ix
+}
+
+/// RecordWithdrawal (0x24): `[authority (signer), ctx, stats (writable)]`.
+/// Records that the LP withdrew `amount` of the context's accrued fees in the
+/// fee ledger of a stats account created with STATS_LEDGER_LEN bytes.
+pub fn record_withdrawal(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, stats: &Pubkey, amount: u128) -> Instruction {
+    let mut data = vec![TAG_RECORD_WITHDRAWAL];
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*stats, false),
+        ],
+    )
+}
+
+/// SetSkim (0x2C): `[authority (signer), ctx, stats (writable)]`. Every Match
+/// that passes the stats account then earmarks `skim_bps` of the fill's spread
+/// for the insurance fund; the account must have been created with
+/// STATS_SKIM_LEN bytes.
+pub fn set_skim(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, stats: &Pubkey, skim_bps: u16) -> Instruction {
+    let mut data = vec![TAG_SET_SKIM];
+    data.extend_from_slice(&skim_bps.to_le_bytes());
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*stats, false),
+        ],
+    )
+}
+
+/// ClaimSkim (0x2D): `[authority (signer), ctx, stats (writable), slab
+/// (writable), source (writable), vault (writable), token program,
+/// percolator]`. Pays `amount` of the accrued skim into the slab's insurance
+/// fund through percolator's TopUpInsurance.
+pub fn claim_skim(program_id: &Pubkey, claim: &SkimClaim) -> Instruction {
+    let mut data = vec![TAG_CLAIM_SKIM];
+    data.extend_from_slice(&claim.amount.to_le_bytes());
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(claim.authority, true),
+            AccountMeta::new_readonly(claim.ctx, false),
+            AccountMeta::new(claim.stats, false),
+            AccountMeta::new(claim.slab, false),
+            AccountMeta::new(claim.source, false),
+            AccountMeta::new(claim.vault, false),
+            AccountMeta::new_readonly(claim.token_program, false),
+            AccountMeta::new_readonly(claim.percolator, false),
+        ],
+    )
+}
+
+/// SweepProtocolFee (0x30): `[treasury (signer), ctx, stats (writable)]`.
+/// Records that the treasury took `amount` of the protocol fee accrued in the
+/// stats account.
+pub fn sweep_protocol_fee(program_id: &Pubkey, treasury: &Pubkey, ctx: &Pubkey, stats: &Pubkey, amount: u128) -> Instruction {
+    let mut data = vec![TAG_SWEEP_PROTOCOL_FEE];
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*treasury, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*stats, false),
+        ],
+    )
+}
+
+fn tip_data(tag: u8, lamports_per_slot: u64, max_tip: u64) -> Vec<u8> {
+    let mut data = vec![tag];
+    data.extend_from_slice(&lamports_per_slot.to_le_bytes());
+    data.extend_from_slice(&max_tip.to_le_bytes());
+    data
+}
+
+/// InitTipJar (0x26): `[authority (signer), ctx, tip_jar (writable)]`.
+/// UpdateCredibility then pays its keeper `lamports_per_slot` per slot the
+/// snapshot was stale past the grace period, up to `max_tip`. The tip jar must
+/// already exist: TIP_JAR_LEN bytes, rent-exempt, owned by the program, with
+/// the escrow on top of the rent.
+pub fn init_tip_jar(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    ctx: &Pubkey,
+    tip_jar: &Pubkey,
+    lamports_per_slot: u64,
+    max_tip: u64,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &tip_data(TAG_INIT_TIP_JAR, lamports_per_slot, max_tip),
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*tip_jar, false),
+        ],
+    )
+}
+
+/// SetTip (0x27): `[authority (signer), ctx, tip_jar (writable)]`. Takes
+/// effect at once; a zero `max_tip` stops the tips.
+pub fn set_tip(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, tip_jar: &Pubkey, lamports_per_slot: u64, max_tip: u64) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &tip_data(TAG_SET_TIP, lamports_per_slot, max_tip),
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*tip_jar, false),
+        ],
+    )
+}
+
+/// CloseTipJar (0x28): `[authority (signer), ctx, tip_jar (writable),
+/// destination (writable)]`. Sweeps the escrow and the rent to `destination`.
+pub fn close_tip_jar(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, tip_jar: &Pubkey, destination: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &[TAG_CLOSE_TIP_JAR],
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*tip_jar, false),
+            AccountMeta::new(*destination, false),
+        ],
+    )
+}
+
+/// InitReferral (0x29): `[authority (signer), ctx, referral (writable)]`. A
+/// Match that passes the referral account (see [`with_referral`]) credits its
+/// referrer with `share_bps` of the fill's fee. The account must already
+/// exist: REFERRAL_LEN bytes, rent-exempt, owned by the program.
+pub fn init_referral(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, referral: &Pubkey, share_bps: u16) -> Instruction {
+    referral_ix(program_id, TAG_INIT_REFERRAL, authority, ctx, referral, share_bps)
+}
+
+/// SetReferralShare (0x2A): `[authority (signer), ctx, referral (writable)]`.
+/// Applies to fills from then on.
+pub fn set_referral_share(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, referral: &Pubkey, share_bps: u16) -> Instruction {
+    referral_ix(program_id, TAG_SET_REFERRAL_SHARE, authority, ctx, referral, share_bps)
+}
+
+/// InitTrader (0x2E): `[trader (signer), ctx, trader account (writable)]`.
+/// Binds the trader account to the context and the trader, who then passes it
+/// to Match (see [`with_trader`]) for the schedule's loyalty discount. The
+/// account must already exist: TRADER_LEN bytes, rent-exempt, owned by the
+/// program.
+pub fn init_trader(program_id: &Pubkey, trader: &Pubkey, ctx: &Pubkey, trader_account: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &[TAG_INIT_TRADER],
+        vec![
+            AccountMeta::new_readonly(*trader, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*trader_account, false),
+        ],
+    )
+}
+
+/// SetLoyaltyTiers (0x2F): `[authority (signer), ctx, schedule (writable)]`.
+/// Replaces the loyalty tiers of a schedule account created with
+/// SCHEDULE_LOYALTY_LEN bytes; more than MAX_LOYALTY_TIERS is
+/// InvalidArgument.
+pub fn set_loyalty_tiers(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    ctx: &Pubkey,
+    schedule: &Pubkey,
+    tiers: &[LoyaltyTier],
+) -> Result<Instruction, ProgramError> {
+    if tiers.len() > MAX_LOYALTY_TIERS {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mut data = vec![TAG_SET_LOYALTY_TIERS, tiers.len() as u8];
+    for tier in tiers {
+        data.extend_from_slice(&tier.min_volume.to_le_bytes());
+        data.extend_from_slice(&tier.discount_bps.to_le_bytes());
+    }
+    Ok(Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*schedule, false),
+        ],
+    ))
+}
+
+fn referral_ix(program_id: &Pubkey, tag: u8, authority: &Pubkey, ctx: &Pubkey, referral: &Pubkey, share_bps: u16) -> Instruction {
+    let mut data = vec![tag];
+    data.extend_from_slice(&share_bps.to_le_bytes());
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*referral, false),
+        ],
+    )
+}
+
+/// InitLadder (0x1A): `[authority (signer), ctx, ladder (writable)]`. The
+/// ladder account must already exist: LADDER_LEN bytes, rent-exempt, owned by
+/// the program.
+pub fn init_ladder(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, ladder: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &[TAG_INIT_LADDER],
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*ladder, false),
+        ],
+    )
+}
+
+/// PublishLadder (0x1B): `[authority (signer), ctx, ladder (writable), stats
+/// (optional)]`. Prices MAX_LADDER_LEVELS trades of `step_size` multiples each
+/// way at `oracle_price_e6` into the ladder.
+pub fn publish_ladder(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    ctx: &Pubkey,
+    ladder: &Pubkey,
+    stats: Option<&Pubkey>,
+    oracle_price_e6: u64,
+    step_size: u128,
+) -> Instruction {
+    let mut data = vec![TAG_PUBLISH_LADDER];
+    data.extend_from_slice(&oracle_price_e6.to_le_bytes());
+    data.extend_from_slice(&step_size.to_le_bytes());
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new_readonly(*ctx, false),
+        AccountMeta::new(*ladder, false),
+    ];
+    if let Some(stats) = stats {
+        accounts.push(AccountMeta::new_readonly(*stats, false));
+    }
+    Instruction::new_with_bytes(*program_id, &data, accounts)
+}
+
+/// InitFeed (0x1C): `[authority (signer), ctx (writable), feed (writable),
+/// price]`. Binds the feed account to the feed of `price`, a price account
+/// from `source` (a FEED_SOURCE_* value); Match then refuses prices published
+/// more than `max_age_secs` ago. The feed account must already exist:
+/// FEED_LEN bytes, rent-exempt, owned by the program.
+pub fn init_feed(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    ctx: &Pubkey,
+    feed: &Pubkey,
+    price: &Pubkey,
+    max_age_secs: u32,
+    source: u8,
+) -> Instruction {
+    let mut data = vec![TAG_INIT_FEED];
+    data.extend_from_slice(&max_age_secs.to_le_bytes());
+    data.push(source);
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*ctx, false),
+            AccountMeta::new(*feed, false),
+            AccountMeta::new_readonly(*price, false),
+        ],
+    )
+}
+
+/// AddFeed (0x1D): `[authority (signer), ctx, feed (writable), price]`. Binds
+/// the feed of `price`, from `source`, as well; Match then takes a price
+/// account per feed, in the order they were bound, and prices off their
+/// median. At most MAX_FEEDS feeds.
+pub fn add_feed(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, feed: &Pubkey, price: &Pubkey, source: u8) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &[TAG_ADD_FEED, source],
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*feed, false),
+            AccountMeta::new_readonly(*price, false),
+        ],
+    )
+}
+
+/// Pin an InitFeed or AddFeed: the feed is bound to its price account's key,
+/// so Match takes that account and no other update for the same Pyth feed
+/// id. Any other instruction is InvalidArgument.
+pub fn pin_feed(mut ix: Instruction) -> Result<Instruction, ProgramError> {
+    if !matches!(ix.data.first(), Some(&TAG_INIT_FEED | &TAG_ADD_FEED)) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    ix.data.push(1);
+    Ok(ix)
+}
+
+/// SetFeedDivergence (0x1E): `[authority (signer), ctx, feed (writable)]`.
+/// Feeds more than `max_divergence_bps` apart (of their median; 0 = no limit)
+/// fail the Match, or with `widen` add the divergence to its spread.
+pub fn set_feed_divergence(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    ctx: &Pubkey,
+    feed: &Pubkey,
+    max_divergence_bps: u16,
+    widen: bool,
+) -> Instruction {
+    let mut data = vec![TAG_SET_FEED_DIVERGENCE];
+    data.extend_from_slice(&max_divergence_bps.to_le_bytes());
+    data.push(widen as u8);
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*feed, false),
+        ],
+    )
+}
+
+/// SetFeedSlotAge (0x20): `[authority (signer), ctx, feed (writable)]`.
+/// A feed price posted more than `max_age_slots` slots ago (0 = no limit)
+/// fails the Match, or with `widen` quotes it at max_spread_bps.
+pub fn set_feed_slot_age(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    ctx: &Pubkey,
+    feed: &Pubkey,
+    max_age_slots: u16,
+    widen: bool,
+) -> Instruction {
+    let mut data = vec![TAG_SET_FEED_SLOT_AGE];
+    data.extend_from_slice(&max_age_slots.to_le_bytes());
+    data.push(widen as u8);
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*feed, false),
+        ],
+    )
+}
+
+/// SetFeedFallback (0x21): `[authority (signer), ctx, feed (writable)]`.
+/// With `fallback`, Match prices off the first feed, in the order they were
+/// bound, with a fresh positive price, adding its `penalties_bps` entry to
+/// the spread, instead of off their median.
+pub fn set_feed_fallback(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    ctx: &Pubkey,
+    feed: &Pubkey,
+    fallback: bool,
+    penalties_bps: [u16; MAX_FEEDS],
+) -> Instruction {
+    let mut data = vec![TAG_SET_FEED_FALLBACK, fallback as u8];
+    for penalty in penalties_bps {
+        data.extend_from_slice(&penalty.to_le_bytes());
+    }
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*ctx, false),
+            AccountMeta::new(*feed, false),
+        ],
+    )
+}
+
+/// SetOracleDecimals (0x1F): the decimals of the oracle price the context is
+/// called with, `ORACLE_DECIMALS_E6..=MAX_ORACLE_DECIMALS` (or 0 for e6).
+/// Only before the first fill.
+pub fn set_oracle_decimals(program_id: &Pubkey, authority: &Pubkey, ctx: &Pubkey, decimals: u8) -> Instruction {
+    authority_ix(program_id, authority, ctx, &[TAG_SET_ORACLE_DECIMALS, decimals])
+}
+
+/// Point a QuotePreview, QuoteTwoSided, PublishLadder or SettleInventory at
+/// entry `index` of a shared context's market table; a TransferInventory's
+/// source book, then its destination's.
+pub fn on_market(mut ix: Instruction, index: u16) -> Instruction {
+    ix.data.extend_from_slice(&index.to_le_bytes());
+    ix
+}
+
+/// Append the signal accounts the schedule registers, all of them in the
+/// order SetSignals took them, to a Match, QuotePreview, QuoteTwoSided or
+/// PublishLadder. Match takes them after the slab (and the stats account, if any), so one without
+/// the slab, or more than MAX_SIGNAL_ACCOUNTS signals, is InvalidArgument.
+pub fn with_signals(mut ix: Instruction, signals: &[Pubkey]) -> Result<Instruction, ProgramError> {
+    if signals.len() > MAX_SIGNAL_ACCOUNTS || (ix.data.first() == Some(&TAG_MATCH) && ix.accounts.len() < 3) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    ix.accounts.extend(signals.iter().map(|k| AccountMeta::new_readonly(*k, false)));
+    Ok(ix)
+}
+
+/// Insert a scheduled context's schedule account into a Match, QuotePreview,
+/// QuoteTwoSided, PublishLadder or Close, right after the fixed accounts where
+/// the program looks for it. Match takes it after the slab, so one without the slab, or
+/// any other instruction, is InvalidArgument.
+pub fn with_schedule(mut ix: Instruction, schedule: &Pubkey) -> Result<Instruction, ProgramError> {
+    let (at, meta) = match ix.data.first() {
+        Some(&TAG_MATCH) if ix.accounts.len() >= 3 => (3, AccountMeta::new_readonly(*schedule, false)),
+        Some(&TAG_QUOTE_PREVIEW | &TAG_QUOTE_TWO_SIDED) => (1, AccountMeta::new_readonly(*schedule, false)),
+        Some(&TAG_PUBLISH_LADDER) => (3, AccountMeta::new_readonly(*schedule, false)),
+        Some(&TAG_CLOSE) => (3, AccountMeta::new(*schedule, false)),
+        _ => return Err(ProgramError::InvalidArgument),
+    };
+    ix.accounts.insert(at, meta);
+    Ok(ix)
+}
+
+/// Insert a context's feed account into a Match, with a price account per
+/// feed after it (`prices`, in the order the feeds were bound), or into a
+/// Close, with none. Both take it after the schedule, so apply this before
+/// [`with_schedule`]. A Match without the slab or with no prices or more
+/// than MAX_FEEDS, a Close with prices, or any other instruction is
+/// InvalidArgument.
+pub fn with_feed(mut ix: Instruction, feed: &Pubkey, prices: &[Pubkey]) -> Result<Instruction, ProgramError> {
+    match ix.data.first() {
+        Some(&TAG_MATCH) if ix.accounts.len() >= 3 && (1..=MAX_FEEDS).contains(&prices.len()) => {
+            let metas = std::iter::once(AccountMeta::new_readonly(*feed, false))
+                .chain(prices.iter().map(|price| AccountMeta::new_readonly(*price, false)));
+            ix.accounts.splice(3..3, metas);
+        }
+        Some(&TAG_CLOSE) if prices.is_empty() => ix.accounts.insert(3, AccountMeta::new(*feed, false)),
+        _ => return Err(ProgramError::InvalidArgument),
+    }
+    Ok(ix)
+}
+
+/// Append a bound ladder account to a Close, which reclaims it with the
+/// context; any other instruction is InvalidArgument.
+pub fn with_ladder(mut ix: Instruction, ladder: &Pubkey) -> Result<Instruction, ProgramError> {
+    if ix.data.first() != Some(&TAG_CLOSE) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    ix.accounts.push(AccountMeta::new(*ladder, false));
+    Ok(ix)
+}
+
+/// Name `referrer` in a Match and insert the context's referral account right
+/// after the slab, where the program looks for it, so apply this after
+/// [`with_schedule`] and [`with_feed`]. A Match without the slab or already
+/// naming a referrer, or any other instruction, is InvalidArgument.
+pub fn with_referral(mut ix: Instruction, referral: &Pubkey, referrer: &Pubkey) -> Result<Instruction, ProgramError> {
+    if ix.data.first() != Some(&TAG_MATCH) || ix.data.len() != CALL_LEN || ix.accounts.len() < 3 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    ix.data.extend_from_slice(referrer.as_ref());
+    ix.accounts.insert(3, AccountMeta::new(*referral, false));
+    Ok(ix)
+}
+
+/// Insert a trader account and its trader, who signs, into a Match after the
+/// slab and any referral account, where the program looks for them; order
+/// against [`with_referral`] doesn't matter. A Match without the slab or
+/// already passing a trader, or any other instruction, is InvalidArgument.
+pub fn with_trader(mut ix: Instruction, trader_account: &Pubkey, trader: &Pubkey) -> Result<Instruction, ProgramError> {
+    if ix.data.first() != Some(&TAG_MATCH) || ix.accounts.len() < 3 || ix.accounts[1..].iter().any(|m| m.is_signer) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let at = if ix.data.len() == REFERRED_CALL_LEN { 4 } else { 3 };
+    ix.accounts.insert(at, AccountMeta::new(*trader_account, false));
+    ix.accounts.insert(at + 1, AccountMeta::new_readonly(*trader, true));
+    Ok(ix)
+}
+
+/// Append a bound tip jar and the keeper it pays to an UpdateCredibility; the
+/// keeper signs. Any other instruction is InvalidArgument.
+pub fn with_tip_jar(mut ix: Instruction, tip_jar: &Pubkey, keeper: &Pubkey) -> Result<Instruction, ProgramError> {
+    if ix.data.first() != Some(&TAG_UPDATE_CREDIBILITY) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    ix.accounts.push(AccountMeta::new(*tip_jar, false));
+    ix.accounts.push(AccountMeta::new(*keeper, true));
+    Ok(ix)
+}
+
+/// The ed25519 program instruction PostRfq looks for before it: `signature`
+/// by `signer` over `quote`'s message, with everything in its own data.
+pub fn rfq_signature(signer: &Pubkey, signature: &[u8; 64], quote: &RfqQuote) -> Instruction {
+    let key_off = ED25519_OFFSETS_OFF + ED25519_OFFSETS_LEN;
+    let sig_off = key_off + 32;
+    let msg_off = sig_off + 64;
+    let mut data = vec![0u8; msg_off];
+    data[0] = 1;
+    let offsets = &mut data[ED25519_OFFSETS_OFF..key_off];
+    for (off, value) in [
+        (ED25519_SIG_OFF, sig_off),
+        (ED25519_SIG_IX_OFF, u16::MAX as usize),
+        (ED25519_KEY_OFF, key_off),
+        (ED25519_KEY_IX_OFF, u16::MAX as usize),
+        (ED25519_MSG_OFF, msg_off),
+        (ED25519_MSG_SIZE_OFF, RFQ_MSG_LEN),
+        (ED25519_MSG_IX_OFF, u16::MAX as usize),
+    ] {
+        offsets[off..off + 2].copy_from_slice(&(value as u16).to_le_bytes());
+    }
+    data[key_off..sig_off].copy_from_slice(signer.as_ref());
+    data[sig_off..msg_off].copy_from_slice(signature);
+    data.extend_from_slice(&quote.message());
+    Instruction::new_with_bytes(ed25519_program::ID, &data, vec![])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_accounts_and_data() {
+        let (program, lp, ctx, slab, stats) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let call = MatchCall { req_id: 7, oracle_price_e6: 100_000_000, trade_size: -5, ..Default::default() };
+
+        let ix = match_trade(&program, &lp, &ctx, Some(&slab), Some(&stats), &call).unwrap();
+        assert_eq!(ix.program_id, program);
+        assert_eq!(ix.data.len(), CALL_LEN);
+        assert_eq!(ix.data[0], TAG_MATCH);
+        assert_eq!(&ix.data[CALL_TRADE_SIZE_OFF..CALL_TRADE_SIZE_OFF + 16], &(-5i128).to_le_bytes());
+        assert_eq!(
+            ix.accounts,
+            vec![
+                AccountMeta::new_readonly(lp, true),
+                AccountMeta::new(ctx, false),
+                AccountMeta::new_readonly(slab, false),
+                AccountMeta::new(stats, false),
+            ]
+        );
+
+        assert_eq!(MatchCall::unpack(&ix.data), Ok(call));
+        assert_eq!(MatchCall::unpack(&ix.data[..CALL_LEN - 1]), Err(ProgramError::InvalidInstructionData));
+
+        assert_eq!(match_trade(&program, &lp, &ctx, None, None, &call).unwrap().accounts.len(), 2);
+        assert_eq!(
+            match_trade(&program, &lp, &ctx, None, Some(&stats), &call),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_init_carries_kind_and_full_params() {
+        let (program, lp, ctx, auth) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let params = MatcherParams { base_fee_bps: 5, max_spread_bps: 200, ..Default::default() };
+        assert_eq!(init(&program, &lp, &ctx, None, Some(&auth), &params), Err(ProgramError::InvalidArgument));
+
+        let ix = init(&program, &lp, &ctx, None, None, &params).unwrap();
+        assert_eq!(ix.data[..2], [TAG_INIT, KIND_CREDIBILITY]);
+        let plain = init_with_kind(&program, &lp, &ctx, None, None, KIND_PLAIN, &params).unwrap();
+        assert_eq!((plain.data[..2].to_vec(), &plain.data[2..]), (vec![TAG_INIT, KIND_PLAIN], &ix.data[2..]));
+        assert_eq!(ix.data.len(), 2 + PARAMS_MAX_LEN);
+        assert!(ix.accounts[0].is_signer);
+        assert!(ix.accounts[1].is_writable);
+        // A named authority signs in the LP PDA's place
+        let slab = Pubkey::new_unique();
+        let ix = init(&program, &lp, &ctx, Some(&slab), Some(&auth), &params).unwrap();
+        assert_eq!(ix.accounts.iter().map(|a| a.is_signer).collect::<Vec<_>>(), [false, false, false, true]);
+    }
+
+    #[test]
+    fn test_rfq_signature_locates_the_quote() {
+        let (program, signer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let quote = RfqQuote { ctx: Pubkey::new_unique(), price_e6: 7, size: -3, expiry_slot: 9, nonce: 1 };
+        assert_eq!(RfqQuote::from_message(&quote.message()), Ok(quote));
+        assert_eq!(RfqQuote::from_message(&[0; RFQ_MSG_LEN - 1]), Err(ProgramError::InvalidInstructionData));
+
+        let verify = rfq_signature(&signer, &[5; 64], &quote);
+        assert_eq!(verify.program_id, ed25519_program::ID);
+        let offset = |off: usize| {
+            let at = ED25519_OFFSETS_OFF + off;
+            u16::from_le_bytes([verify.data[at], verify.data[at + 1]]) as usize
+        };
+        assert_eq!(verify.data[0], 1);
+        assert_eq!(&verify.data[offset(ED25519_KEY_OFF)..][..32], signer.as_ref());
+        assert_eq!(&verify.data[offset(ED25519_SIG_OFF)..][..64], &[5; 64]);
+        assert_eq!(&verify.data[offset(ED25519_MSG_OFF)..][..offset(ED25519_MSG_SIZE_OFF)], &quote.message());
+        assert_eq!(offset(ED25519_MSG_IX_OFF), u16::MAX as usize);
+
+        let post = post_rfq(&program, &quote);
+        assert_eq!((post.data[0], &post.data[1..]), (TAG_POST_RFQ, &quote.message()[..]));
+        assert!(post.accounts[0].is_writable && post.accounts[0].pubkey == quote.ctx);
+        assert_eq!(post.accounts[1].pubkey, solana_instructions_sysvar::ID);
+    }
+
+    #[test]
+    fn test_quote_instructions_are_read_only() {
+        let (program, ctx) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = quote_two_sided(&program, &ctx, None, 1, u128::MAX);
+        assert_eq!(ix.data.len(), PREVIEW_CALL_LEN);
+        assert!(ix.accounts.iter().all(|a| !a.is_writable && !a.is_signer));
+        assert_eq!(quote_preview(&program, &ctx, None, 1, -1).data[0], TAG_QUOTE_PREVIEW);
+        assert_eq!(on_market(quote_two_sided(&program, &ctx, None, 1, 1), 3).data[PREVIEW_CALL_LEN..], [3, 0]);
+    }
+
+    #[test]
+    fn test_signals_follow_the_optional_accounts() {
+        let (program, lp, ctx, slab, signal) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let preview = with_signals(quote_preview(&program, &ctx, None, 1, 1), &[signal]).unwrap();
+        assert_eq!(preview.accounts[1], AccountMeta::new_readonly(signal, false));
+
+        let call = MatchCall::default();
+        let ix = with_signals(match_trade(&program, &lp, &ctx, Some(&slab), None, &call).unwrap(), &[signal]).unwrap();
+        assert_eq!(ix.accounts[3], AccountMeta::new_readonly(signal, false));
+        let bare = match_trade(&program, &lp, &ctx, None, None, &call).unwrap();
+        assert_eq!(with_signals(bare, &[signal]), Err(ProgramError::InvalidArgument));
+        let five = [signal; MAX_SIGNAL_ACCOUNTS + 1];
+        assert_eq!(with_signals(quote_preview(&program, &ctx, None, 1, 1), &five), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_schedule_goes_after_the_fixed_accounts() {
+        let (program, lp, ctx, slab, stats, schedule) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let call = MatchCall::default();
+        let ix = with_schedule(match_trade(&program, &lp, &ctx, Some(&slab), Some(&stats), &call).unwrap(), &schedule).unwrap();
+        assert_eq!(ix.accounts[3], AccountMeta::new_readonly(schedule, false));
+        assert_eq!(ix.accounts[4].pubkey, stats);
+        let bare = match_trade(&program, &lp, &ctx, None, None, &call).unwrap();
+        assert_eq!(with_schedule(bare, &schedule), Err(ProgramError::InvalidArgument));
+
+        let preview = with_schedule(quote_preview(&program, &ctx, Some(&stats), 1, 1), &schedule).unwrap();
+        assert_eq!((preview.accounts[1].pubkey, preview.accounts[2].pubkey), (schedule, stats));
+        let close = with_schedule(close(&program, &lp, &ctx, &lp, Some(&stats)), &schedule).unwrap();
+        assert_eq!(close.accounts[3], AccountMeta::new(schedule, false));
+        assert_eq!(with_schedule(set_paused(&program, &lp, &ctx, true), &schedule), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_publish_ladder_accounts_and_data() {
+        let [program, authority, ctx, ladder, stats, schedule] = [(); 6].map(|_| Pubkey::new_unique());
+        let ix = publish_ladder(&program, &authority, &ctx, &ladder, Some(&stats), 7, 1_000);
+        assert_eq!(ix.data.len(), PREVIEW_CALL_LEN);
+        assert_eq!(ix.data[0], TAG_PUBLISH_LADDER);
+        assert_eq!(ix.data[9..25], 1_000u128.to_le_bytes());
+        assert!(ix.accounts[0].is_signer && !ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+        // The schedule goes between the ladder and the stats account
+        let ix = with_schedule(on_market(ix, 3), &schedule).unwrap();
+        assert_eq!((ix.accounts[3].pubkey, ix.accounts[4].pubkey), (schedule, stats));
+        assert_eq!(ix.data[PREVIEW_CALL_LEN..], 3u16.to_le_bytes());
+        assert_eq!(init_ladder(&program, &authority, &ctx, &ladder).data, [TAG_INIT_LADDER]);
+
+        // Close takes it last
+        let close = with_ladder(with_schedule(close(&program, &authority, &ctx, &authority, Some(&stats)), &schedule).unwrap(), &ladder).unwrap();
+        assert_eq!(close.accounts[5], AccountMeta::new(ladder, false));
+        assert_eq!(with_ladder(ix, &ladder), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_feed_goes_after_the_schedule() {
+        let [program, lp, ctx, slab, stats, schedule, feed, price, other] = [(); 9].map(|_| Pubkey::new_unique());
+        let call = MatchCall::default();
+        let ix = match_trade(&program, &lp, &ctx, Some(&slab), Some(&stats), &call).unwrap();
+        let ix = with_schedule(with_feed(ix, &feed, &[price, other]).unwrap(), &schedule).unwrap();
+        let keys: Vec<Pubkey> = ix.accounts[3..].iter().map(|a| a.pubkey).collect();
+        assert_eq!(keys, [schedule, feed, price, other, stats]);
+        let bare = match_trade(&program, &lp, &ctx, None, None, &call).unwrap();
+        assert_eq!(with_feed(bare, &feed, &[price]), Err(ProgramError::InvalidArgument));
+        let ix = match_trade(&program, &lp, &ctx, Some(&slab), None, &call).unwrap();
+        assert_eq!(with_feed(ix.clone(), &feed, &[]), Err(ProgramError::InvalidArgument));
+        assert_eq!(with_feed(ix, &feed, &[price; MAX_FEEDS + 1]), Err(ProgramError::InvalidArgument));
+
+        let close = with_feed(close(&program, &lp, &ctx, &lp, Some(&stats)), &feed, &[]).unwrap();
+        assert_eq!((close.accounts[3].clone(), close.accounts[4].pubkey), (AccountMeta::new(feed, false), stats));
+        let ix = init_feed(&program, &lp, &ctx, &feed, &price, 30, FEED_SOURCE_SWITCHBOARD);
+        assert_eq!(ix.data, [&[TAG_INIT_FEED][..], &30u32.to_le_bytes(), &[FEED_SOURCE_SWITCHBOARD]].concat());
+        assert!(ix.accounts[0].is_signer && ix.accounts[1].is_writable && ix.accounts[2].is_writable && !ix.accounts[3].is_writable);
+        let ix = add_feed(&program, &lp, &ctx, &feed, &price, FEED_SOURCE_PYTH);
+        assert_eq!(ix.data, [TAG_ADD_FEED, FEED_SOURCE_PYTH]);
+        assert_eq!(pin_feed(ix.clone()).unwrap().data, [TAG_ADD_FEED, FEED_SOURCE_PYTH, 1]);
+        assert_eq!(pin_feed(init_feed(&program, &lp, &ctx, &feed, &price, 30, FEED_SOURCE_PYTH)).unwrap().data[5..], [FEED_SOURCE_PYTH, 1]);
+        assert_eq!(pin_feed(set_oracle_decimals(&program, &lp, &ctx, 9)), Err(ProgramError::InvalidArgument));
+        assert!(!ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+        let ix = set_feed_divergence(&program, &lp, &ctx, &feed, 150, true);
+        assert_eq!(ix.data, [TAG_SET_FEED_DIVERGENCE, 150, 0, 1]);
+        let ix = set_feed_slot_age(&program, &lp, &ctx, &feed, 300, false);
+        assert_eq!(ix.data, [TAG_SET_FEED_SLOT_AGE, 44, 1, 0]);
+        let ix = set_feed_fallback(&program, &lp, &ctx, &feed, true, [0, 25, 300]);
+        assert_eq!(ix.data, [TAG_SET_FEED_FALLBACK, 1, 0, 0, 25, 0, 44, 1]);
+        assert!(!ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+        assert!(!ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+        let ix = set_oracle_decimals(&program, &lp, &ctx, 9);
+        assert_eq!(ix.data, [TAG_SET_ORACLE_DECIMALS, 9]);
+        assert!(ix.accounts[0].is_signer && ix.accounts[1].is_writable);
+    }
+
+    #[test]
+    fn test_set_schedule_data() {
+        let (program, authority, ctx, schedule) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let entry = ScheduleEntry { start_slot: 5, end_slot: 9, multiplier_bps: 20_000 };
+        let ix = set_schedule(&program, &authority, &ctx, &schedule, &[entry; 2]).unwrap();
+        assert_eq!(ix.data.len(), 2 + 2 * SCHED_ARG_LEN);
+        assert_eq!(ix.data[..2], [TAG_SET_SCHEDULE, 2]);
+        assert_eq!(ix.data[2 + SCHED_ARG_LEN..], [&5u64.to_le_bytes()[..], &9u64.to_le_bytes(), &20_000u32.to_le_bytes()].concat());
+        assert!(ix.accounts[0].is_signer && !ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+        let nine = [entry; MAX_SCHEDULE_ENTRIES + 1];
+        assert_eq!(set_schedule(&program, &authority, &ctx, &schedule, &nine), Err(ProgramError::InvalidArgument));
+        assert_eq!(init_schedule(&program, &authority, &ctx, &schedule).data, [TAG_INIT_SCHEDULE]);
+    }
+
+    #[test]
+    fn test_set_fee_tiers_data() {
+        let (program, authority, ctx, schedule) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let tier = FeeTier { min_notional: 250_000, fee_bps: 4 };
+        let ix = set_fee_tiers(&program, &authority, &ctx, &schedule, &[tier]).unwrap();
+        assert_eq!(ix.data, [&[TAG_SET_FEE_TIERS, 1][..], &250_000u128.to_le_bytes(), &4u32.to_le_bytes()].concat());
+        assert_eq!(ix.data.len(), 2 + FEE_TIER_ARG_LEN);
+        assert!(ix.accounts[0].is_signer && !ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+        let five = [tier; MAX_FEE_TIERS + 1];
+        assert_eq!(set_fee_tiers(&program, &authority, &ctx, &schedule, &five), Err(ProgramError::InvalidArgument));
+        let ix = set_rebate(&program, &authority, &ctx, &schedule, 7);
+        assert_eq!(ix.data, [TAG_SET_REBATE, 7, 0]);
+        assert!(ix.accounts[0].is_signer && !ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+        let ix = set_fee_discount(&program, &authority, &ctx, &schedule, 10_000);
+        assert_eq!(ix.data, [TAG_SET_FEE_DISCOUNT, 0x10, 0x27]);
+        assert!(ix.accounts[0].is_signer && !ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+        let ix = set_min_notional(&program, &authority, &ctx, &schedule, 1_000);
+        assert_eq!(ix.data, [&[TAG_SET_MIN_NOTIONAL][..], &1_000u128.to_le_bytes()].concat());
+        assert!(ix.accounts[0].is_signer && !ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+        let ix = set_inventory_decay(&program, &authority, &ctx, &schedule, 300);
+        assert_eq!(ix.data, [TAG_SET_INVENTORY_DECAY, 0x2c, 0x01, 0, 0]);
+        assert!(ix.accounts[0].is_signer && !ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+        let ix = set_inventory_band(&program, &authority, &ctx, &schedule, 2_000_000, 100);
+        assert_eq!(ix.data, [&[TAG_SET_INVENTORY_BAND][..], &2_000_000u128.to_le_bytes(), &100u32.to_le_bytes()].concat());
+        assert!(ix.accounts[0].is_signer && !ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+        let ix = set_inventory_caps(&program, &authority, &ctx, &schedule, 0, 500_000);
+        assert_eq!(ix.data, [&[TAG_SET_INVENTORY_CAPS][..], &0u128.to_le_bytes(), &500_000u128.to_le_bytes()].concat());
+        assert!(ix.accounts[0].is_signer && !ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+        let ix = set_reduce_only_threshold(&program, &authority, &ctx, &schedule, 8_000);
+        assert_eq!(ix.data, [TAG_SET_REDUCE_ONLY_THRESHOLD, 0x40, 0x1f]);
+        assert!(ix.accounts[0].is_signer && !ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+        let ix = set_inventory_depth_cap(&program, &authority, &ctx, &schedule, 30_000);
+        assert_eq!(ix.data, [TAG_SET_INVENTORY_DEPTH_CAP, 0x30, 0x75, 0, 0]);
+        assert!(ix.accounts[0].is_signer && !ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+    }
+
+    #[test]
+    fn test_transfer_inventory_data() {
+        let (program, from, to) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (from_authority, to_authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = transfer_inventory(&program, &from_authority, &from, &to_authority, &to, -500);
+        assert_eq!(ix.data, [&[TAG_TRANSFER_INVENTORY][..], &(-500i128).to_le_bytes()].concat());
+        let ix = on_market(on_market(ix, 0), 3);
+        assert_eq!(ix.data[17..], [0, 0, 3, 0]);
+        let keys: Vec<_> = ix.accounts.iter().map(|a| (a.pubkey, a.is_signer, a.is_writable)).collect();
+        assert_eq!(keys, [(from_authority, true, false), (from, false, true), (to_authority, true, false), (to, false, true)]);
+    }
+
+    #[test]
+    fn test_create_context_derives_the_address() {
+        let (program, lp_pda, slab, payer) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let len = (CTX_EXT_LEN + CREATION_LEN) as u32;
+        let ix = create_context(&program, &lp_pda, &slab, &payer, len);
+        assert_eq!(ix.data, [&[TAG_CREATE_CONTEXT][..], &len.to_le_bytes()].concat());
+        let (ctx, bump) = context_address(&program, &lp_pda, &slab);
+        assert_eq!(Pubkey::create_program_address(&[CONTEXT_SEED, lp_pda.as_ref(), slab.as_ref(), &[bump]], &program), Ok(ctx));
+        let keys: Vec<_> = ix.accounts.iter().map(|a| (a.pubkey, a.is_signer, a.is_writable)).collect();
+        assert_eq!(keys[..4], [(lp_pda, false, false), (ctx, false, true), (slab, false, false), (payer, true, true)]);
+        // Another market is another context
+        assert_ne!(context_address(&program, &lp_pda, &payer).0, ctx);
+    }
+
+    #[test]
+    fn test_sync_inventory_data() {
+        let (program, ctx, slab) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = sync_inventory(&program, &ctx, &slab, 0x0102);
+        assert_eq!(ix.data, [TAG_SYNC_INVENTORY, 0x02, 0x01]);
+        let keys: Vec<_> = ix.accounts.iter().map(|a| (a.pubkey, a.is_signer, a.is_writable)).collect();
+        assert_eq!(keys, [(ctx, false, true), (slab, false, false)]);
+    }
+
+    #[test]
+    fn test_set_signals_data() {
+        let (program, authority, ctx, schedule) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let signals = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let ix = set_signals(&program, &authority, &ctx, &schedule, &signals);
+        assert_eq!(ix.data, [TAG_SET_SIGNALS]);
+        let keys: Vec<_> = ix.accounts.iter().map(|a| (a.pubkey, a.is_signer, a.is_writable)).collect();
+        assert_eq!(keys, [(authority, true, false), (ctx, false, false), (schedule, false, true), (signals[0], false, false), (signals[1], false, false)]);
+    }
+
+    #[test]
+    fn test_bind_slab_data() {
+        let (program, authority, ctx, slab) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = bind_slab(&program, &authority, &ctx, &slab);
+        assert_eq!(ix.data, [TAG_BIND_SLAB]);
+        let keys: Vec<_> = ix.accounts.iter().map(|a| (a.pubkey, a.is_signer, a.is_writable)).collect();
+        assert_eq!(keys, [(authority, true, false), (ctx, false, true), (slab, false, false)]);
+    }
+
+    #[test]
+    fn test_tip_jar_data() {
+        let (program, authority, ctx, jar) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = init_tip_jar(&program, &authority, &ctx, &jar, 10, 5_000);
+        assert_eq!(ix.data, [&[TAG_INIT_TIP_JAR][..], &10u64.to_le_bytes(), &5_000u64.to_le_bytes()].concat());
+        assert!(ix.accounts[0].is_signer && !ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+        assert_eq!(set_tip(&program, &authority, &ctx, &jar, 10, 5_000).data[1..], ix.data[1..]);
+        let close = close_tip_jar(&program, &authority, &ctx, &jar, &authority);
+        assert_eq!((close.data, close.accounts[3].pubkey), (vec![TAG_CLOSE_TIP_JAR], authority));
+
+        let keeper = Pubkey::new_unique();
+        let ix = with_tip_jar(update_credibility(&program, &ctx, &jar), &jar, &keeper).unwrap();
+        assert_eq!(ix.accounts.iter().map(|m| m.pubkey).collect::<Vec<_>>()[3..], [jar, keeper]);
+        assert!(ix.accounts[3].is_writable && !ix.accounts[3].is_signer && ix.accounts[4].is_signer);
+        assert_eq!(with_tip_jar(set_tip(&program, &authority, &ctx, &jar, 1, 1), &jar, &keeper), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_referral_data() {
+        let (program, authority, ctx, referral) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = init_referral(&program, &authority, &ctx, &referral, 2_000);
+        assert_eq!(ix.data, [&[TAG_INIT_REFERRAL][..], &2_000u16.to_le_bytes()].concat());
+        assert!(ix.accounts[0].is_signer && !ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+        let set = set_referral_share(&program, &authority, &ctx, &referral, 2_000);
+        assert_eq!((set.data[0], &set.data[1..]), (TAG_SET_REFERRAL_SHARE, &ix.data[1..]));
+
+        let (slab, schedule, referrer) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let call = MatchCall { trade_size: 1_000, ..MatchCall::default() };
+        let bare = match_trade(&program, &authority, &ctx, None, None, &call).unwrap();
+        assert_eq!(with_referral(bare, &referral, &referrer), Err(ProgramError::InvalidArgument));
+        let ix = match_trade(&program, &authority, &ctx, Some(&slab), None, &call).unwrap();
+        let ix = with_referral(with_schedule(ix, &schedule).unwrap(), &referral, &referrer).unwrap();
+        assert_eq!(ix.data.len(), REFERRED_CALL_LEN);
+        assert_eq!((&ix.data[..CALL_LEN], &ix.data[CALL_REFERRER_OFF..]), (&call.pack()[..], referrer.as_ref()));
+        assert_eq!(ix.accounts.iter().map(|m| m.pubkey).collect::<Vec<_>>()[2..], [slab, referral, schedule]);
+        assert!(ix.accounts[3].is_writable);
+        assert_eq!(with_referral(ix, &referral, &referrer), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_trader_data() {
+        let (program, trader, ctx, account) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = init_trader(&program, &trader, &ctx, &account);
+        assert_eq!(ix.data, [TAG_INIT_TRADER]);
+        assert!(ix.accounts[0].is_signer && ix.accounts[2].is_writable);
+
+        let tiers = [LoyaltyTier { min_volume: 10_000, discount_bps: 1 }];
+        let ix = set_loyalty_tiers(&program, &trader, &ctx, &account, &tiers).unwrap();
+        assert_eq!(ix.data, [&[TAG_SET_LOYALTY_TIERS, 1][..], &10_000u128.to_le_bytes(), &1u32.to_le_bytes()].concat());
+        let too_many = [LoyaltyTier::default(); MAX_LOYALTY_TIERS + 1];
+        assert_eq!(set_loyalty_tiers(&program, &trader, &ctx, &account, &too_many), Err(ProgramError::InvalidArgument));
+
+        // After the slab and the referral account, whichever goes in first
+        let (lp, slab, referral, referrer) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = match_trade(&program, &lp, &ctx, Some(&slab), None, &MatchCall::default()).unwrap();
+        let a = with_referral(with_trader(ix.clone(), &account, &trader).unwrap(), &referral, &referrer).unwrap();
+        let b = with_trader(with_referral(ix, &referral, &referrer).unwrap(), &account, &trader).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.accounts.iter().map(|m| m.pubkey).collect::<Vec<_>>()[2..], [slab, referral, account, trader]);
+        assert!(a.accounts[4].is_writable && a.accounts[5].is_signer);
+        assert_eq!(with_trader(a, &account, &trader), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_record_withdrawal_data() {
+        let (program, authority, ctx, stats) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = record_withdrawal(&program, &authority, &ctx, &stats, 1_250_000);
+        assert_eq!(ix.data, [&[TAG_RECORD_WITHDRAWAL][..], &1_250_000u128.to_le_bytes()].concat());
+        assert_eq!(ix.accounts.iter().map(|m| m.pubkey).collect::<Vec<_>>(), [authority, ctx, stats]);
+        assert!(ix.accounts[0].is_signer && !ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+    }
+
+    #[test]
+    fn test_skim_data() {
+        let (program, authority, ctx, stats) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = set_skim(&program, &authority, &ctx, &stats, 2_500);
+        assert_eq!(ix.data, [TAG_SET_SKIM, 0xc4, 0x09]);
+        assert!(ix.accounts[0].is_signer && ix.accounts[2].is_writable);
+
+        let claim = SkimClaim { authority, ctx, stats, amount: 7, ..SkimClaim::default() };
+        let ix = claim_skim(&program, &claim);
+        assert_eq!(ix.data, [&[TAG_CLAIM_SKIM][..], &7u64.to_le_bytes()].concat());
+        assert_eq!(ix.accounts.len(), 8);
+        let writable: Vec<bool> = ix.accounts.iter().map(|m| m.is_writable).collect();
+        assert_eq!(writable, [false, false, true, true, true, true, false, false]);
+    }
+
+    #[test]
+    fn test_protocol_fee_data() {
+        let (program, authority, ctx, stats, treasury) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = init_stats_with_protocol_fee(&program, &authority, &ctx, &stats, &treasury, 2_000);
+        assert_eq!(ix.data, [TAG_INIT_STATS, 0xd0, 0x07]);
+        assert_eq!(ix.accounts.iter().map(|m| m.pubkey).collect::<Vec<_>>(), [authority, ctx, stats, treasury]);
+        assert!(!ix.accounts[3].is_writable && !ix.accounts[3].is_signer);
+
+        let ix = sweep_protocol_fee(&program, &treasury, &ctx, &stats, 7);
+        assert_eq!(ix.data, [&[TAG_SWEEP_PROTOCOL_FEE][..], &7u128.to_le_bytes()].concat());
+        assert!(ix.accounts[0].is_signer && !ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+    }
+
+    #[test]
+    fn test_set_market_accounts_and_data() {
+        let (program, authority, ctx, slab) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = set_market(&program, &authority, &ctx, &slab, 5, u128::MAX);
+        assert_eq!(ix.data.len(), 33);
+        assert_eq!((ix.data[0], &ix.data[1..17], &ix.data[17..]), (TAG_SET_MARKET, &5u128.to_le_bytes()[..], &[0xff; 16][..]));
+        assert!(ix.accounts[0].is_signer && ix.accounts[1].is_writable);
+        assert!(ix.accounts[2].pubkey == slab && !ix.accounts[2].is_writable && !ix.accounts[2].is_signer);
+    }
+}
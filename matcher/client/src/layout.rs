@@ -0,0 +1,629 @@
+//! Byte layouts shared with the on-chain program.
+//!
+//! Offsets are in bytes and values little-endian throughout. The program's test
+//! suite asserts these constants against its own and runs the builders through
+//! it, so a layout change that isn't mirrored here fails the program's tests
+//! rather than an integrator's transaction.
+
+// Instruction tags
+pub const TAG_MATCH: u8 = 0x00;
+pub const TAG_INIT: u8 = 0x02;
+pub const TAG_UPDATE_CREDIBILITY: u8 = 0x03;
+pub const TAG_UPDATE_PARAMS: u8 = 0x04;
+pub const TAG_CLOSE: u8 = 0x05;
+pub const TAG_SET_PAUSED: u8 = 0x06;
+pub const TAG_MIGRATE: u8 = 0x07;
+pub const TAG_QUOTE_PREVIEW: u8 = 0x08;
+pub const TAG_PROPOSE_PARAMS: u8 = 0x09;
+pub const TAG_COMMIT_PARAMS: u8 = 0x0A;
+pub const TAG_SET_REDUCE_ONLY: u8 = 0x0B;
+pub const TAG_SETTLE_INVENTORY: u8 = 0x0C;
+pub const TAG_PROPOSE_AUTHORITY: u8 = 0x0D;
+pub const TAG_ACCEPT_AUTHORITY: u8 = 0x0E;
+pub const TAG_SET_RETURN_DATA_ONLY: u8 = 0x0F;
+pub const TAG_SET_SOFT_REJECT: u8 = 0x10;
+pub const TAG_QUOTE_TWO_SIDED: u8 = 0x11;
+pub const TAG_INIT_STATS: u8 = 0x12;
+pub const TAG_SET_RFQ_SIGNER: u8 = 0x13;
+pub const TAG_POST_RFQ: u8 = 0x14;
+pub const TAG_SET_MARKET: u8 = 0x15;
+pub const TAG_SET_NO_BIDS: u8 = 0x16;
+pub const TAG_SET_NO_ASKS: u8 = 0x17;
+pub const TAG_INIT_SCHEDULE: u8 = 0x18;
+pub const TAG_SET_SCHEDULE: u8 = 0x19;
+pub const TAG_INIT_LADDER: u8 = 0x1A;
+pub const TAG_PUBLISH_LADDER: u8 = 0x1B;
+pub const TAG_INIT_FEED: u8 = 0x1C;
+pub const TAG_ADD_FEED: u8 = 0x1D;
+pub const TAG_SET_FEED_DIVERGENCE: u8 = 0x1E;
+pub const TAG_SET_ORACLE_DECIMALS: u8 = 0x1F;
+pub const TAG_SET_FEED_SLOT_AGE: u8 = 0x20;
+pub const TAG_SET_FEED_FALLBACK: u8 = 0x21;
+pub const TAG_SET_FEE_TIERS: u8 = 0x22;
+pub const TAG_SET_REBATE: u8 = 0x23;
+pub const TAG_RECORD_WITHDRAWAL: u8 = 0x24;
+pub const TAG_SET_FEE_DISCOUNT: u8 = 0x25;
+pub const TAG_INIT_TIP_JAR: u8 = 0x26;
+pub const TAG_SET_TIP: u8 = 0x27;
+pub const TAG_CLOSE_TIP_JAR: u8 = 0x28;
+pub const TAG_INIT_REFERRAL: u8 = 0x29;
+pub const TAG_SET_REFERRAL_SHARE: u8 = 0x2A;
+pub const TAG_SET_MIN_NOTIONAL: u8 = 0x2B;
+pub const TAG_SET_SKIM: u8 = 0x2C;
+pub const TAG_CLAIM_SKIM: u8 = 0x2D;
+pub const TAG_INIT_TRADER: u8 = 0x2E;
+pub const TAG_SET_LOYALTY_TIERS: u8 = 0x2F;
+pub const TAG_SWEEP_PROTOCOL_FEE: u8 = 0x30;
+pub const TAG_SET_INVENTORY_DECAY: u8 = 0x31;
+pub const TAG_SET_INVENTORY_BAND: u8 = 0x32;
+pub const TAG_SET_INVENTORY_CAPS: u8 = 0x33;
+pub const TAG_SET_REDUCE_ONLY_THRESHOLD: u8 = 0x34;
+pub const TAG_TRANSFER_INVENTORY: u8 = 0x35;
+pub const TAG_SET_INVENTORY_DEPTH_CAP: u8 = 0x36;
+pub const TAG_SYNC_INVENTORY: u8 = 0x37;
+pub const TAG_CREATE_CONTEXT: u8 = 0x38;
+pub const TAG_BIND_SLAB: u8 = 0x39;
+pub const TAG_SET_SIGNALS: u8 = 0x3A;
+
+// Context magic: "PERCMATC"
+pub const MAGIC: u64 = 0x5045_5243_4d41_5443;
+pub const VERSION: u32 = 6;
+pub const VERSION_V5: u32 = 5;
+pub const KIND_PLAIN: u8 = 1;
+pub const KIND_CREDIBILITY: u8 = 2;
+pub const KIND_MULTI: u8 = 3;
+pub const KIND_STABLE: u8 = 4;
+
+// CreateContext derives the context from [CONTEXT_SEED, lp_pda, slab]
+pub const CONTEXT_SEED: &[u8] = b"context";
+
+// Match call input: tag, req_id (u64), lp_idx (u16), lp_account_id (u64),
+// oracle_price_e6 (u64), trade_size (i128), zero padding, then optionally
+// the referrer's pubkey
+pub const CALL_REQ_ID_OFF: usize = 1;
+pub const CALL_LP_IDX_OFF: usize = 9;
+pub const CALL_LP_ACCOUNT_ID_OFF: usize = 11;
+pub const CALL_ORACLE_PRICE_OFF: usize = 19;
+pub const CALL_TRADE_SIZE_OFF: usize = 27;
+pub const CALL_LEN: usize = 67;
+pub const CALL_REFERRER_OFF: usize = CALL_LEN;
+pub const REFERRED_CALL_LEN: usize = CALL_REFERRER_OFF + 32;
+
+// QuotePreview / QuoteTwoSided input: tag, oracle_price_e6 (u64), size (16 bytes)
+pub const PREVIEW_CALL_LEN: usize = 25;
+
+// RFQ quote, signed off-chain by the RFQ signer and carried by PostRfq after
+// its tag: ctx, price_e6 (u64), size (i128, signed like a trade), expiry_slot
+// (u64), nonce (u64)
+pub const RFQ_CTX_OFF: usize = 0;
+pub const RFQ_PRICE_OFF: usize = 32;
+pub const RFQ_SIZE_OFF: usize = 40;
+pub const RFQ_EXPIRY_OFF: usize = 56;
+pub const RFQ_NONCE_OFF: usize = 64;
+pub const RFQ_MSG_LEN: usize = 72;
+
+// Ed25519 program instruction data: count, pad, then per signature seven u16s
+// (signature, key and message offsets, each with an instruction index;
+// u16::MAX means the ed25519 instruction's own data)
+pub const ED25519_OFFSETS_OFF: usize = 2;
+pub const ED25519_SIG_OFF: usize = 0;
+pub const ED25519_SIG_IX_OFF: usize = 2;
+pub const ED25519_KEY_OFF: usize = 4;
+pub const ED25519_KEY_IX_OFF: usize = 6;
+pub const ED25519_MSG_OFF: usize = 8;
+pub const ED25519_MSG_SIZE_OFF: usize = 10;
+pub const ED25519_MSG_IX_OFF: usize = 12;
+pub const ED25519_OFFSETS_LEN: usize = 14;
+
+// MatcherReturn (64 bytes)
+pub const RET_ABI_VERSION_OFF: usize = 0;
+pub const RET_FLAGS_OFF: usize = 4;
+pub const RET_EXEC_PRICE_OFF: usize = 8;
+pub const RET_EXEC_SIZE_OFF: usize = 16;
+pub const RET_REQ_ID_OFF: usize = 32;
+pub const RET_LP_ACCOUNT_ID_OFF: usize = 40;
+pub const RET_ORACLE_ECHO_OFF: usize = 48;
+pub const RET_REJECT_REASON_OFF: usize = 56;
+pub const RET_LEN: usize = 64;
+pub const MATCHER_ABI_VERSION: u32 = 1;
+pub const FLAG_VALID: u32 = 1;
+
+// Match return data: MatcherReturn, quote breakdown, fill sequence number,
+// hedge target
+pub const MATCH_RET_BREAKDOWN_OFF: usize = RET_LEN;
+pub const MATCH_RET_FILL_SEQ_OFF: usize = RET_LEN + QUOTE_LEN;
+pub const MATCH_RET_HEDGE_OFF: usize = MATCH_RET_FILL_SEQ_OFF + 8;
+pub const MATCH_RET_LEN: usize = MATCH_RET_HEDGE_OFF + 16;
+
+// Quote breakdown: QuotePreview's return data, also embedded in Match and
+// QuoteTwoSided returns
+pub const QUOTE_EXEC_PRICE_OFF: usize = 0;
+pub const QUOTE_COVERAGE_OFF: usize = 8;
+pub const QUOTE_TIER_OFF: usize = 16;
+pub const QUOTE_FILL_PCT_OFF: usize = 24;
+pub const QUOTE_TIER_SPREAD_OFF: usize = 32;
+pub const QUOTE_IMBALANCE_OFF: usize = 40;
+pub const QUOTE_IMPACT_OFF: usize = 48;
+pub const QUOTE_STALE_OFF: usize = 56;
+pub const QUOTE_CRANK_OFF: usize = 64;
+pub const QUOTE_LIQ_OFF: usize = 72;
+pub const QUOTE_SPREAD_OFF: usize = 80;
+pub const QUOTE_SKEW_OFF: usize = 88;
+pub const QUOTE_FUNDING_SKEW_OFF: usize = 96;
+pub const QUOTE_FEE_OFF: usize = 104;
+pub const QUOTE_NEW_INVENTORY_OFF: usize = 112;
+pub const QUOTE_FILL_SIZE_OFF: usize = 128;
+pub const QUOTE_DISCOUNT_OFF: usize = 144;
+pub const QUOTE_FLOW_OFF: usize = 152;
+pub const QUOTE_ANCHOR_OFF: usize = 160;
+pub const QUOTE_SIGNAL_OFF: usize = 168;
+pub const QUOTE_SCHEDULE_OFF: usize = 176;
+pub const QUOTE_ORACLE_OFF: usize = 184;
+pub const QUOTE_REBATE_OFF: usize = 192;
+pub const QUOTE_LEN: usize = 200;
+
+// QuoteTwoSided return data
+pub const TWO_SIDED_BID_REASON_OFF: usize = 0;
+pub const TWO_SIDED_ASK_REASON_OFF: usize = 8;
+pub const TWO_SIDED_BID_OFF: usize = 16;
+pub const TWO_SIDED_ASK_OFF: usize = TWO_SIDED_BID_OFF + QUOTE_LEN;
+pub const TWO_SIDED_LEN: usize = TWO_SIDED_ASK_OFF + QUOTE_LEN;
+
+// Context (relative to CTX_BASE)
+pub const CTX_MAGIC_OFF: usize = 0;
+pub const CTX_VERSION_OFF: usize = 8;
+pub const CTX_KIND_OFF: usize = 12;
+pub const CTX_ORACLE_DECIMALS_OFF: usize = 13;
+pub const CTX_SPOT_WEIGHT_OFF: usize = 14;
+pub const CTX_LP_PDA_OFF: usize = 16;
+pub const CTX_BASE_FEE_OFF: usize = 48;
+pub const CTX_MIN_SPREAD_OFF: usize = 52;
+pub const CTX_MAX_SPREAD_OFF: usize = 56;
+pub const CTX_IMBALANCE_K_OFF: usize = 60;
+pub const CTX_LIQUIDITY_OFF: usize = 64;
+pub const CTX_MAX_FILL_OFF: usize = 80;
+pub const CTX_INVENTORY_OFF: usize = 96;
+pub const CTX_LAST_ORACLE_OFF: usize = 112;
+pub const CTX_LAST_EXEC_OFF: usize = 120;
+pub const CTX_MAX_INVENTORY_OFF: usize = 128;
+pub const CTX_INSURANCE_OFF: usize = 144;
+pub const CTX_TOTAL_OI_OFF: usize = 160;
+pub const CTX_MARKET_AGE_OFF: usize = 176;
+pub const CTX_LAST_DEFICIT_OFF: usize = 184;
+pub const CTX_SNAPSHOT_SLOT_OFF: usize = 192;
+pub const CTX_AGE_HALFLIFE_OFF: usize = 200;
+pub const CTX_INSURANCE_WEIGHT_OFF: usize = 204;
+pub const CTX_IMPACT_MODE_OFF: usize = 208;
+pub const CTX_COVERAGE_CURVE_OFF: usize = 209;
+pub const CTX_FLAGS_OFF: usize = 210;
+pub const CTX_BUMP_OFF: usize = 211;
+pub const CTX_IMPACT_K_OFF: usize = 212;
+pub const CTX_SKEW_K_OFF: usize = 216;
+pub const CTX_CURVE_POINTS_OFF: usize = 220;
+pub const CTX_PEG_BAND_OFF: usize = 228;
+pub const CTX_LAST_CRANK_OFF: usize = 232;
+pub const CTX_LAST_LIQS_OFF: usize = 240;
+pub const CTX_LIQ_HEAT_OFF: usize = 248;
+pub const CTX_LIQ_HALFLIFE_OFF: usize = 252;
+pub const CTX_BASE: usize = 64;
+/// Size of a v5 context account.
+pub const CTX_LEN: usize = 320;
+/// Decimals SetOracleDecimals takes for the caller's price; 0 (the default)
+/// and 6 are both e6.
+pub const ORACLE_DECIMALS_E6: u8 = 6;
+pub const MAX_ORACLE_DECIMALS: u8 = 18;
+
+// Extension (relative to EXT_BASE, v6 only)
+pub const EXT_FUNDING_RATE_OFF: usize = 0;
+pub const EXT_FUNDING_HORIZON_OFF: usize = 8;
+pub const EXT_LIQ_AUCTION_SLOTS_OFF: usize = 12;
+pub const EXT_SLAB_OFF: usize = 16;
+pub const EXT_PENDING_SLOT_OFF: usize = 48;
+pub const EXT_PENDING_PARAMS_OFF: usize = 56;
+pub const EXT_AUTHORITY_OFF: usize = 160;
+pub const EXT_PENDING_AUTHORITY_OFF: usize = 192;
+pub const EXT_PERCOLATOR_OFF: usize = 224;
+pub const EXT_ORACLE_JUMP_BPS_OFF: usize = 256;
+pub const EXT_LIQ_AUCTION_BPS_OFF: usize = 258;
+pub const EXT_ORACLE_JUMP_WINDOW_OFF: usize = 260;
+pub const EXT_LAST_ORACLE_SLOT_OFF: usize = 264;
+pub const EXT_FILL_SEQ_OFF: usize = 272;
+pub const EXT_CUM_NOTIONAL_OFF: usize = 280;
+pub const EXT_CUM_FEES_OFF: usize = 296;
+pub const EXT_GROSS_LONG_OFF: usize = 312;
+pub const EXT_GROSS_SHORT_OFF: usize = 328;
+pub const EXT_FLOW_K_OFF: usize = 344;
+pub const EXT_TWAP_WINDOW_OFF: usize = 348;
+pub const EXT_PENDING_PARAMS_TAIL_OFF: usize = 352;
+pub const EXT_RFQ_SIGNER_OFF: usize = 384;
+pub const EXT_STATS_OFF: usize = 416;
+pub const EXT_SIGNAL_WEIGHTS_OFF: usize = 448;
+pub const EXT_DEFICIT_HALFLIFE_OFF: usize = 456;
+pub const EXT_MARKET_SLOTS_OFF: usize = 460;
+pub const EXT_RFQ_PRICE_OFF: usize = 464;
+pub const EXT_RFQ_EXPIRY_OFF: usize = 472;
+pub const EXT_RFQ_NONCE_OFF: usize = 480;
+pub const EXT_RFQ_SIZE_OFF: usize = 488;
+pub const EXT_LAST_LIQ_SLOT_OFF: usize = 504;
+/// A proposal's first PENDING_HEAD_LEN bytes sit at EXT_PENDING_PARAMS_OFF,
+/// the rest at EXT_PENDING_PARAMS_TAIL_OFF.
+pub const PENDING_HEAD_LEN: usize = 104;
+pub const EXT_BASE: usize = 320;
+pub const EXT_LEN: usize = 512;
+/// Size of a v6 context account.
+pub const CTX_EXT_LEN: usize = EXT_BASE + EXT_LEN;
+
+// Shared context market table: MAX_MARKETS entries after the extension, one
+// per slab, each with its own caps, inventory and snapshots
+pub const MKT_SLAB_OFF: usize = 0;
+pub const MKT_MAX_FILL_OFF: usize = 32;
+pub const MKT_MAX_INVENTORY_OFF: usize = 48;
+pub const MKT_INVENTORY_OFF: usize = 64;
+pub const MKT_INSURANCE_OFF: usize = 80;
+pub const MKT_TOTAL_OI_OFF: usize = 96;
+pub const MKT_GROSS_LONG_OFF: usize = 112;
+pub const MKT_GROSS_SHORT_OFF: usize = 128;
+pub const MKT_SNAPSHOT_SLOT_OFF: usize = 144;
+pub const MKT_LAST_CRANK_OFF: usize = 152;
+pub const MKT_LAST_LIQS_OFF: usize = 160;
+pub const MKT_FUNDING_RATE_OFF: usize = 168;
+pub const MKT_MARKET_AGE_OFF: usize = 176;
+pub const MKT_LAST_DEFICIT_OFF: usize = 184;
+pub const MKT_LAST_LIQ_SLOT_OFF: usize = 192;
+pub const MKT_LAST_ORACLE_OFF: usize = 200;
+pub const MKT_LAST_ORACLE_SLOT_OFF: usize = 208;
+pub const MKT_LAST_EXEC_OFF: usize = 216;
+pub const MKT_LIQ_HEAT_OFF: usize = 224;
+pub const MARKETS_BASE: usize = CTX_EXT_LEN;
+pub const MARKET_LEN: usize = 256;
+pub const MAX_MARKETS: usize = 8;
+/// Size of a shared (multi-market) v6 context account.
+pub const CTX_SHARED_LEN: usize = MARKETS_BASE + MAX_MARKETS * MARKET_LEN;
+
+// Creation record (v6): CREATION_LEN bytes right after the layout, at
+// CTX_EXT_LEN on a single-market context and CTX_SHARED_LEN on a shared one,
+// in any account created that much longer, by CreateContext or at a keypair
+// address. CreateContext sets `derived` (1) on the account it makes; Init
+// writes the slot and the key that signed it (the authority, or the LP PDA).
+// Nothing changes them after.
+pub const CREATION_SLOT_OFF: usize = 0;
+pub const CREATION_CREATOR_OFF: usize = 8;
+pub const CREATION_DERIVED_OFF: usize = 40;
+pub const CREATION_LEN: usize = 48;
+
+// Context flags (CTX_FLAGS_OFF)
+pub const CTX_FLAG_PAUSED: u8 = 1 << 0;
+pub const CTX_FLAG_REDUCE_ONLY: u8 = 1 << 1;
+pub const CTX_FLAG_RETURN_DATA_ONLY: u8 = 1 << 2;
+pub const CTX_FLAG_SOFT_REJECT: u8 = 1 << 3;
+pub const CTX_FLAG_NO_BIDS: u8 = 1 << 4;
+pub const CTX_FLAG_NO_ASKS: u8 = 1 << 5;
+pub const CTX_FLAG_SCHEDULED: u8 = 1 << 6;
+pub const CTX_FLAG_FEED: u8 = 1 << 7;
+
+// Stats account: header, ring of fills, ring of oracle samples
+pub const STATS_MAGIC: u64 = 0x5045_5243_5354_4154;
+pub const STATS_VERSION: u32 = 1;
+pub const STATS_MAGIC_OFF: usize = 0;
+pub const STATS_VERSION_OFF: usize = 8;
+pub const STATS_CAPACITY_OFF: usize = 12;
+pub const STATS_CTX_OFF: usize = 16;
+pub const STATS_HEAD_OFF: usize = 48;
+pub const STATS_COUNT_OFF: usize = 52;
+pub const STATS_ORACLE_HEAD_OFF: usize = 56;
+pub const STATS_ORACLE_COUNT_OFF: usize = 60;
+pub const STATS_HEADER_LEN: usize = 64;
+pub const STATS_CAPACITY: usize = 64;
+pub const FILL_SEQ_OFF: usize = 0;
+pub const FILL_SLOT_OFF: usize = 8;
+pub const FILL_SIZE_OFF: usize = 16;
+pub const FILL_PRICE_OFF: usize = 32;
+pub const FILL_SPREAD_OFF: usize = 40;
+pub const FILL_LEN: usize = 48;
+pub const ORACLE_CAPACITY: usize = 32;
+pub const ORACLE_SLOT_OFF: usize = 0;
+pub const ORACLE_PRICE_OFF: usize = 8;
+pub const ORACLE_SAMPLE_LEN: usize = 16;
+pub const STATS_ORACLE_RING_OFF: usize = STATS_HEADER_LEN + STATS_CAPACITY * FILL_LEN;
+/// Size of a stats account.
+pub const STATS_LEN: usize = STATS_ORACLE_RING_OFF + ORACLE_CAPACITY * ORACLE_SAMPLE_LEN;
+// Fee ledger, after the rings of a stats account of STATS_LEDGER_LEN bytes or
+// more: the fees RecordWithdrawal recorded against cum_fees
+pub const STATS_WITHDRAWN_OFF: usize = STATS_LEN;
+pub const STATS_WITHDRAWALS_OFF: usize = STATS_WITHDRAWN_OFF + 16;
+pub const STATS_LAST_WITHDRAWAL_OFF: usize = STATS_WITHDRAWALS_OFF + 8;
+/// Size of a stats account with room for the fee ledger.
+pub const STATS_LEDGER_LEN: usize = STATS_LAST_WITHDRAWAL_OFF + 8;
+// Insurance skim, after the fee ledger of a stats account of STATS_SKIM_LEN
+// bytes or more: the share of each fill's spread earmarked for insurance
+pub const STATS_SKIM_BPS_OFF: usize = STATS_LEDGER_LEN;
+pub const STATS_SKIM_ACCRUED_OFF: usize = STATS_SKIM_BPS_OFF + 8;
+pub const STATS_SKIM_CLAIMED_OFF: usize = STATS_SKIM_ACCRUED_OFF + 16;
+pub const STATS_SKIM_CLAIMS_OFF: usize = STATS_SKIM_CLAIMED_OFF + 16;
+/// Size of a stats account with room for the insurance skim.
+pub const STATS_SKIM_LEN: usize = STATS_SKIM_CLAIMS_OFF + 8;
+// Protocol fee, after the skim of a stats account of STATS_PROTOCOL_LEN bytes
+// or more: the treasury's cut of each fill's fee and what it swept
+pub const STATS_TREASURY_OFF: usize = STATS_SKIM_LEN;
+pub const STATS_PROTOCOL_FEE_BPS_OFF: usize = STATS_TREASURY_OFF + 32;
+pub const STATS_PROTOCOL_ACCRUED_OFF: usize = STATS_PROTOCOL_FEE_BPS_OFF + 8;
+pub const STATS_PROTOCOL_SWEPT_OFF: usize = STATS_PROTOCOL_ACCRUED_OFF + 16;
+pub const STATS_PROTOCOL_SWEEPS_OFF: usize = STATS_PROTOCOL_SWEPT_OFF + 16;
+/// Size of a stats account with room for the protocol fee.
+pub const STATS_PROTOCOL_LEN: usize = STATS_PROTOCOL_SWEEPS_OFF + 8;
+// Inventory PnL, after the protocol fee: entry price, mark, realized and
+// unrealized PnL
+pub const STATS_PNL_ENTRY_OFF: usize = STATS_PROTOCOL_LEN;
+pub const STATS_PNL_MARK_OFF: usize = STATS_PNL_ENTRY_OFF + 8;
+pub const STATS_PNL_REALIZED_OFF: usize = STATS_PNL_MARK_OFF + 8;
+pub const STATS_PNL_UNREALIZED_OFF: usize = STATS_PNL_REALIZED_OFF + 16;
+/// Size of a stats account that tracks inventory PnL.
+pub const STATS_PNL_LEN: usize = STATS_PNL_UNREALIZED_OFF + 16;
+
+// Signal account: registered in the schedule by SetSignals and passed after
+// the stats account, each widens the spread by value_bps * weight_bps / 10000
+// until stale_after_slot
+pub const SIGNAL_MAGIC: u64 = 0x5045_5243_5349_474e; // "PERCSIGN"
+pub const SIGNAL_MAGIC_OFF: usize = 0;
+pub const SIGNAL_VALUE_OFF: usize = 8;
+pub const SIGNAL_WEIGHT_OFF: usize = 12;
+pub const SIGNAL_STALE_AFTER_OFF: usize = 16;
+/// Size of a signal account.
+pub const SIGNAL_LEN: usize = 32;
+/// Most signal accounts a schedule may register.
+pub const MAX_SIGNAL_ACCOUNTS: usize = 4;
+
+// Schedule account: bound to a scheduled context, slot ranges that multiply
+// its spread by multiplier_bps / 10000
+pub const SCHEDULE_MAGIC: u64 = 0x5045_5243_5343_4844; // "PERCSCHD"
+pub const SCHEDULE_VERSION: u32 = 1;
+pub const SCHEDULE_MAGIC_OFF: usize = 0;
+pub const SCHEDULE_VERSION_OFF: usize = 8;
+pub const SCHEDULE_COUNT_OFF: usize = 12;
+pub const SCHEDULE_CTX_OFF: usize = 16;
+pub const SCHEDULE_HEADER_LEN: usize = 48;
+/// Most entries a schedule holds.
+pub const MAX_SCHEDULE_ENTRIES: usize = 8;
+pub const SCHED_START_OFF: usize = 0;
+pub const SCHED_END_OFF: usize = 8;
+pub const SCHED_MULT_OFF: usize = 16;
+pub const SCHED_ENTRY_LEN: usize = 24;
+/// Size of a schedule account.
+pub const SCHEDULE_LEN: usize = SCHEDULE_HEADER_LEN + MAX_SCHEDULE_ENTRIES * SCHED_ENTRY_LEN;
+/// One SetSchedule entry: start_slot, end_slot, multiplier_bps, unpadded.
+pub const SCHED_ARG_LEN: usize = 20;
+
+// Fee tiers, after the entries of a schedule account of SCHEDULE_FEES_LEN
+// bytes or more: trades of at least min_notional (0 = unused) pay fee_bps in
+// place of base_fee_bps
+pub const FEE_TIERS_OFF: usize = SCHEDULE_LEN;
+/// Most fee tiers a schedule account holds.
+pub const MAX_FEE_TIERS: usize = 4;
+pub const FEE_TIER_NOTIONAL_OFF: usize = 0;
+pub const FEE_TIER_FEE_OFF: usize = 16;
+pub const FEE_TIER_LEN: usize = 24;
+/// Size of a schedule account with room for fee tiers.
+pub const SCHEDULE_FEES_LEN: usize = FEE_TIERS_OFF + MAX_FEE_TIERS * FEE_TIER_LEN;
+/// One SetFeeTiers tier: min_notional, fee_bps, unpadded.
+pub const FEE_TIER_ARG_LEN: usize = 20;
+// Rebate, after the fee tiers of a schedule account of SCHEDULE_REBATE_LEN
+// bytes or more: taken off the cost of fills that shrink |inventory|; then the
+// share of the fee waived at the full credibility discount
+pub const SCHEDULE_REBATE_OFF: usize = SCHEDULE_FEES_LEN;
+pub const SCHEDULE_FEE_DISCOUNT_OFF: usize = SCHEDULE_REBATE_OFF + 2;
+/// Size of a schedule account with room for a rebate.
+pub const SCHEDULE_REBATE_LEN: usize = SCHEDULE_REBATE_OFF + 8;
+// Minimum notional, after the rebate: trades whose notional at the oracle
+// falls below it are refused as dust
+pub const SCHEDULE_MIN_NOTIONAL_OFF: usize = SCHEDULE_REBATE_LEN;
+/// Size of a schedule account with room for a minimum notional.
+pub const SCHEDULE_MIN_NOTIONAL_LEN: usize = SCHEDULE_MIN_NOTIONAL_OFF + 16;
+// Loyalty tiers, after the minimum notional: discount_bps off the fee of a
+// trader whose account has traded at least min_volume (0 = unused)
+pub const LOYALTY_TIERS_OFF: usize = SCHEDULE_MIN_NOTIONAL_LEN;
+pub const MAX_LOYALTY_TIERS: usize = 4;
+pub const LOYALTY_TIER_VOLUME_OFF: usize = 0;
+pub const LOYALTY_TIER_DISCOUNT_OFF: usize = 16;
+pub const LOYALTY_TIER_LEN: usize = 24;
+/// Size of a schedule account with room for loyalty tiers.
+pub const SCHEDULE_LOYALTY_LEN: usize = LOYALTY_TIERS_OFF + MAX_LOYALTY_TIERS * LOYALTY_TIER_LEN;
+pub const LOYALTY_TIER_ARG_LEN: usize = 20;
+// Inventory decay, after the loyalty tiers of a schedule account of
+// SCHEDULE_DECAY_LEN bytes or more: the halflife, in slots, of the inventory
+// the imbalance and skew penalties see
+pub const SCHEDULE_INVENTORY_HALFLIFE_OFF: usize = SCHEDULE_LOYALTY_LEN;
+/// Size of a schedule account with room for the inventory decay.
+pub const SCHEDULE_DECAY_LEN: usize = SCHEDULE_INVENTORY_HALFLIFE_OFF + 8;
+// Inventory band, after the decay: past soft_inventory, fills that go deeper
+// pay up to band_k_bps more
+pub const SCHEDULE_SOFT_INVENTORY_OFF: usize = SCHEDULE_DECAY_LEN;
+pub const SCHEDULE_BAND_K_OFF: usize = SCHEDULE_SOFT_INVENTORY_OFF + 16;
+/// Size of a schedule account with room for an inventory band.
+pub const SCHEDULE_BAND_LEN: usize = SCHEDULE_BAND_K_OFF + 8;
+// Inventory caps, after the band: separate limits on a long and a short book
+pub const SCHEDULE_MAX_LONG_OFF: usize = SCHEDULE_BAND_LEN;
+pub const SCHEDULE_MAX_SHORT_OFF: usize = SCHEDULE_MAX_LONG_OFF + 16;
+/// Size of a schedule account with room for inventory caps.
+pub const SCHEDULE_CAPS_LEN: usize = SCHEDULE_MAX_SHORT_OFF + 16;
+// Reduce-only threshold, after the caps: u16 bps of the cap
+pub const SCHEDULE_REDUCE_ONLY_BPS_OFF: usize = SCHEDULE_CAPS_LEN;
+/// Size of a schedule account with room for a reduce-only threshold.
+pub const SCHEDULE_REDUCE_ONLY_LEN: usize = SCHEDULE_REDUCE_ONLY_BPS_OFF + 8;
+// Depth-relative cap, after the reduce-only threshold: u32 bps of
+// liquidity_notional_e6
+pub const SCHEDULE_MAX_INVENTORY_BPS_OFF: usize = SCHEDULE_REDUCE_ONLY_LEN;
+/// Size of a schedule account with room for a depth-relative inventory cap.
+pub const SCHEDULE_DEPTH_CAP_LEN: usize = SCHEDULE_MAX_INVENTORY_BPS_OFF + 8;
+// Signal registry, after the depth-relative cap: a u8 count, then
+// MAX_SIGNAL_ACCOUNTS entries of the signal account and its owner
+pub const SCHEDULE_SIGNAL_COUNT_OFF: usize = SCHEDULE_DEPTH_CAP_LEN;
+pub const SCHEDULE_SIGNALS_OFF: usize = SCHEDULE_SIGNAL_COUNT_OFF + 8;
+pub const SIGNAL_ENTRY_KEY_OFF: usize = 0;
+pub const SIGNAL_ENTRY_OWNER_OFF: usize = 32;
+pub const SIGNAL_ENTRY_LEN: usize = 64;
+/// Size of a schedule account with room for a signal registry.
+pub const SCHEDULE_SIGNALS_LEN: usize = SCHEDULE_SIGNALS_OFF + MAX_SIGNAL_ACCOUNTS * SIGNAL_ENTRY_LEN;
+
+// Ladder account: bound to a context, the levels PublishLadder last priced
+// each way; level i is one trade of i * step_size
+pub const LADDER_MAGIC: u64 = 0x5045_5243_4c41_4452; // "PERCLADR"
+pub const LADDER_VERSION: u32 = 1;
+pub const LADDER_MAGIC_OFF: usize = 0;
+pub const LADDER_VERSION_OFF: usize = 8;
+pub const LADDER_MARKET_OFF: usize = 12;
+pub const LADDER_CTX_OFF: usize = 16;
+pub const LADDER_SLOT_OFF: usize = 48;
+pub const LADDER_ORACLE_OFF: usize = 56;
+pub const LADDER_STEP_OFF: usize = 64;
+pub const LADDER_BID_COUNT_OFF: usize = 80;
+pub const LADDER_ASK_COUNT_OFF: usize = 84;
+pub const LADDER_BID_REASON_OFF: usize = 88;
+pub const LADDER_ASK_REASON_OFF: usize = 92;
+pub const LADDER_HEADER_LEN: usize = 96;
+/// Levels a ladder holds each way.
+pub const MAX_LADDER_LEVELS: usize = 8;
+pub const LEVEL_SIZE_OFF: usize = 0;
+pub const LEVEL_PRICE_OFF: usize = 16;
+pub const LEVEL_SPREAD_OFF: usize = 24;
+pub const LEVEL_LEN: usize = 32;
+pub const LADDER_BIDS_OFF: usize = LADDER_HEADER_LEN;
+pub const LADDER_ASKS_OFF: usize = LADDER_BIDS_OFF + MAX_LADDER_LEVELS * LEVEL_LEN;
+/// Size of a ladder account.
+pub const LADDER_LEN: usize = LADDER_ASKS_OFF + MAX_LADDER_LEVELS * LEVEL_LEN;
+
+// Tip jar: bound to a context, the tip UpdateCredibility pays a keeper per
+// stale slot, its cap and the running totals; the escrow is its lamports
+// above rent exemption
+pub const TIP_JAR_MAGIC: u64 = 0x5045_5243_5449_5053; // "PERCTIPS"
+pub const TIP_JAR_VERSION: u32 = 1;
+pub const TIP_JAR_MAGIC_OFF: usize = 0;
+pub const TIP_JAR_VERSION_OFF: usize = 8;
+pub const TIP_JAR_CTX_OFF: usize = 16;
+pub const TIP_JAR_PER_SLOT_OFF: usize = 48;
+pub const TIP_JAR_MAX_OFF: usize = 56;
+pub const TIP_JAR_PAID_OFF: usize = 64;
+pub const TIP_JAR_COUNT_OFF: usize = 72;
+pub const TIP_JAR_LEN: usize = 80;
+
+// Referral account: bound to a context, the share of each fill's fee Match
+// credits to the referrer named in its data, then per referrer its key, the
+// fees credited to it and the fills it referred
+pub const REFERRAL_MAGIC: u64 = 0x5045_5243_5245_4653; // "PERCREFS"
+pub const REFERRAL_VERSION: u32 = 1;
+pub const REFERRAL_MAGIC_OFF: usize = 0;
+pub const REFERRAL_VERSION_OFF: usize = 8;
+pub const REFERRAL_CTX_OFF: usize = 16;
+pub const REFERRAL_SHARE_OFF: usize = 48;
+pub const REFERRAL_COUNT_OFF: usize = 50;
+pub const REFERRAL_HEADER_LEN: usize = 56;
+pub const MAX_REFERRERS: usize = 16;
+pub const REFERRER_KEY_OFF: usize = 0;
+pub const REFERRER_ACCRUED_OFF: usize = 32;
+pub const REFERRER_FILLS_OFF: usize = 48;
+pub const REFERRER_LEN: usize = 56;
+/// Size of a referral account.
+pub const REFERRAL_LEN: usize = REFERRAL_HEADER_LEN + MAX_REFERRERS * REFERRER_LEN;
+
+// Trader account: bound to a context and a trader's key, the notional Match
+// recorded for the trader's fills, how many and the slot of the last
+pub const TRADER_MAGIC: u64 = 0x5045_5243_5452_4452; // "PERCTRDR"
+pub const TRADER_VERSION: u32 = 1;
+pub const TRADER_MAGIC_OFF: usize = 0;
+pub const TRADER_VERSION_OFF: usize = 8;
+pub const TRADER_CTX_OFF: usize = 16;
+pub const TRADER_KEY_OFF: usize = 48;
+pub const TRADER_VOLUME_OFF: usize = 80;
+pub const TRADER_FILLS_OFF: usize = 96;
+pub const TRADER_LAST_SLOT_OFF: usize = 104;
+/// Size of a trader account.
+pub const TRADER_LEN: usize = 112;
+
+// Feed account: bound to a context, the oracle feeds Match takes the median
+// of, or the first good one of (each a Pyth feed id, or a pinned Pyth,
+// Switchboard or Chainlink account's key, its source and its fallback
+// penalty), the oldest price it takes (in seconds and in slots) and how far
+// apart the feeds may be
+pub const FEED_MAGIC: u64 = 0x5045_5243_4645_4544; // "PERCFEED"
+pub const FEED_VERSION: u32 = 1;
+pub const FEED_MAGIC_OFF: usize = 0;
+pub const FEED_VERSION_OFF: usize = 8;
+pub const FEED_MAX_AGE_OFF: usize = 12;
+pub const FEED_CTX_OFF: usize = 16;
+pub const FEED_COUNT_OFF: usize = 48;
+pub const FEED_WIDEN_OFF: usize = 49;
+pub const FEED_MAX_DIVERGENCE_OFF: usize = 50;
+pub const FEED_MAX_AGE_SLOTS_OFF: usize = 52;
+pub const FEED_SLOT_WIDEN_OFF: usize = 54;
+pub const FEED_FALLBACK_OFF: usize = 55;
+pub const FEED_HEADER_LEN: usize = 56;
+/// Most feeds a feed account holds.
+pub const MAX_FEEDS: usize = 3;
+pub const FEED_ENTRY_ID_OFF: usize = 0;
+pub const FEED_ENTRY_SOURCE_OFF: usize = 32;
+pub const FEED_ENTRY_PINNED_OFF: usize = 33;
+pub const FEED_ENTRY_PENALTY_OFF: usize = 34;
+pub const FEED_ENTRY_LEN: usize = 40;
+/// Size of a feed account.
+pub const FEED_LEN: usize = FEED_HEADER_LEN + MAX_FEEDS * FEED_ENTRY_LEN;
+/// Feed sources: a Pyth PriceUpdateV2, a Switchboard on-demand pull feed, or
+/// a Chainlink OCR2 feed.
+pub const FEED_SOURCE_PYTH: u8 = 0;
+pub const FEED_SOURCE_SWITCHBOARD: u8 = 1;
+pub const FEED_SOURCE_CHAINLINK: u8 = 2;
+
+// Percolator slab fields read by UpdateCredibility: a 72-byte header (the
+// admin key at 16; zero or the system program once burned), a 320-byte config,
+// then the risk engine
+pub const SLAB_ADMIN_OFF: usize = 16;
+pub const SLAB_HEADER_LEN: usize = 72;
+pub const SLAB_CONFIG_LEN: usize = 320;
+pub const SLAB_ENGINE_OFF: usize = SLAB_HEADER_LEN + SLAB_CONFIG_LEN;
+pub const SLAB_ENGINE_LEN: usize = 400;
+/// Smallest slab UpdateCredibility accepts.
+pub const SLAB_MIN_LEN: usize = SLAB_ENGINE_OFF + SLAB_ENGINE_LEN;
+// Engine fields (relative to SLAB_ENGINE_OFF)
+pub const ENGINE_INSURANCE_OFF: usize = 16;
+pub const ENGINE_FUNDING_RATE_OFF: usize = 224;
+pub const ENGINE_LAST_CRANK_OFF: usize = 232;
+pub const ENGINE_TOTAL_OI_OFF: usize = 248;
+pub const ENGINE_LIFETIME_LIQS_OFF: usize = 328;
+// Percolator's account table, as SyncInventory reads it: SLAB_ACCOUNT_LEN
+// bytes per account from ENGINE_ACCOUNTS_OFF into the engine, indexed like
+// a Match's lp_idx
+pub const ENGINE_ACCOUNTS_OFF: usize = 9136;
+pub const SLAB_ACCOUNTS_OFF: usize = SLAB_ENGINE_OFF + ENGINE_ACCOUNTS_OFF;
+pub const SLAB_ACCOUNT_LEN: usize = 240;
+// Account fields (relative to the account)
+pub const ACCT_KIND_OFF: usize = 24;
+pub const ACCT_POSITION_OFF: usize = 80;
+pub const ACCT_MATCHER_PROGRAM_OFF: usize = 120;
+pub const ACCT_MATCHER_CONTEXT_OFF: usize = 152;
+/// ACCT_KIND_OFF of an LP account (users are 0).
+pub const ACCT_KIND_LP: u8 = 1;
+
+// SettleInventory modes
+pub const SETTLE_SET: u8 = 0;
+pub const SETTLE_ADJUST: u8 = 1;
+
+// Size impact curves (impact_mode)
+pub const IMPACT_NONE: u8 = 0;
+pub const IMPACT_LINEAR: u8 = 1;
+pub const IMPACT_SQRT: u8 = 2;
+
+// Coverage curves (coverage_curve)
+pub const CURVE_LINEAR: u8 = 0;
+pub const CURVE_SQRT: u8 = 1;
+pub const CURVE_PIECEWISE: u8 = 2;
+pub const CURVE_POINTS: usize = 4;
+
+// Credibility signals, indexing signal_weights (multi-signal kind)
+pub const SIGNAL_COVERAGE: usize = 0;
+pub const SIGNAL_AGE: usize = 1;
+pub const SIGNAL_DEFICIT: usize = 2;
+pub const SIGNAL_LIQUIDATIONS: usize = 3;
+pub const SIGNALS: usize = 4;
+
+/// Required prefix of the parameter block.
+pub const PARAMS_LEN: usize = 72;
+/// Full parameter block including every optional trailing field.
+pub const PARAMS_MAX_LEN: usize = PARAMS_LEN + 64;
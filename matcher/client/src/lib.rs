@@ -0,0 +1,21 @@
+//! Client SDK for the credibility matcher.
+//!
+//! Typed instruction builders ([`instruction`]), the parameter block
+//! ([`params::MatcherParams`]), a decoder for context accounts
+//! ([`context::MatcherContext`]) and the byte layouts of the context, stats,
+//! signal, schedule, ladder, feed, tip jar, referral and trader accounts and return data ([`layout`]), so integrators
+//! don't hand-roll instruction bytes that break on every version bump. The
+//! `test-support` feature adds fixtures for tests (`test_support`).
+
+pub mod context;
+pub mod instruction;
+pub mod layout;
+pub mod params;
+#[cfg(feature = "test-support")]
+pub mod test_support;
+
+pub use context::{
+    ContextExtension, Creation, FeeLedger, FeeTier, InsuranceSkim, InventoryPnl, Ladder, LadderLevel, LoyaltyTier, MarketBook, MatcherContext, MatcherReturn,
+    ProtocolFee, Referrals, Referrer, RegisteredSignal, ScheduleEntry, SignalAccount, SpreadSchedule, TipJar, TraderAccount,
+};
+pub use params::MatcherParams;
@@ -0,0 +1,195 @@
+//! The tunable parameter block carried by Init, UpdateParams and ProposeParams.
+
+use crate::layout::{CURVE_POINTS, PARAMS_LEN, PARAMS_MAX_LEN, SIGNALS};
+
+/// Matcher parameters in wire order. `Default` is all zeros, which is also
+/// "off" for every optional field.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MatcherParams {
+    pub base_fee_bps: u32,
+    pub min_spread_bps: u32,
+    pub max_spread_bps: u32,
+    pub imbalance_k_bps: u32,
+    pub liquidity_e6: u128,
+    pub max_fill: u128,
+    pub max_inventory: u128,
+    pub age_halflife: u32,
+    pub insurance_weight_bps: u32,
+    pub impact_mode: u8,
+    pub impact_k_bps: u32,
+    pub skew_k_bps: u32,
+    pub coverage_curve: u8,
+    pub curve_points: [u16; CURVE_POINTS],
+    pub liq_halflife: u32,
+    pub funding_horizon: u32,
+    pub oracle_jump_bps: u16,
+    pub oracle_jump_window: u32,
+    pub flow_k_bps: u32,
+    pub twap_window_slots: u32,
+    /// Relative weight of each credibility signal, by `SIGNAL_*` index; the
+    /// multi-signal kind needs them to sum to 10000.
+    pub signal_weights: [u16; SIGNALS],
+    pub deficit_halflife: u32,
+    /// Opening premium of the liquidation auction, decaying to zero over
+    /// `liq_auction_slots`; both zero for off.
+    pub liq_auction_bps: u16,
+    pub liq_auction_slots: u32,
+    /// Half-width of the stable kind's price band around 1.0, in e6.
+    pub peg_band_e6: u32,
+    /// With TWAP anchoring on, the live oracle's share of the anchor; 0 is
+    /// all TWAP.
+    pub spot_weight_bps: u16,
+}
+
+impl MatcherParams {
+    /// Encode the full block, every optional field included.
+    pub fn pack(&self) -> [u8; PARAMS_MAX_LEN] {
+        let mut w = Writer::default();
+        w.put(&self.base_fee_bps.to_le_bytes());
+        w.put(&self.min_spread_bps.to_le_bytes());
+        w.put(&self.max_spread_bps.to_le_bytes());
+        w.put(&self.imbalance_k_bps.to_le_bytes());
+        w.put(&self.liquidity_e6.to_le_bytes());
+        w.put(&self.max_fill.to_le_bytes());
+        w.put(&self.max_inventory.to_le_bytes());
+        w.put(&self.age_halflife.to_le_bytes());
+        w.put(&self.insurance_weight_bps.to_le_bytes());
+        w.put(&[self.impact_mode]);
+        w.put(&self.impact_k_bps.to_le_bytes());
+        w.put(&self.skew_k_bps.to_le_bytes());
+        w.put(&[self.coverage_curve]);
+        for point in self.curve_points {
+            w.put(&point.to_le_bytes());
+        }
+        w.put(&self.liq_halflife.to_le_bytes());
+        w.put(&self.funding_horizon.to_le_bytes());
+        w.put(&self.oracle_jump_bps.to_le_bytes());
+        w.put(&self.oracle_jump_window.to_le_bytes());
+        w.put(&self.flow_k_bps.to_le_bytes());
+        w.put(&self.twap_window_slots.to_le_bytes());
+        for weight in self.signal_weights {
+            w.put(&weight.to_le_bytes());
+        }
+        w.put(&self.deficit_halflife.to_le_bytes());
+        w.put(&self.liq_auction_bps.to_le_bytes());
+        w.put(&self.liq_auction_slots.to_le_bytes());
+        w.put(&self.peg_band_e6.to_le_bytes());
+        w.put(&self.spot_weight_bps.to_le_bytes());
+        debug_assert_eq!(w.len, PARAMS_MAX_LEN);
+        w.buf
+    }
+
+    /// Decode a block as the program does: the first PARAMS_LEN bytes are
+    /// required, missing trailing fields read as zero.
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        if data.len() < PARAMS_LEN {
+            return None;
+        }
+        let mut full = [0u8; PARAMS_MAX_LEN];
+        let n = data.len().min(PARAMS_MAX_LEN);
+        full[..n].copy_from_slice(&data[..n]);
+        let mut r = Reader { data: &full, off: 0 };
+        Some(Self {
+            base_fee_bps: u32::from_le_bytes(r.take()),
+            min_spread_bps: u32::from_le_bytes(r.take()),
+            max_spread_bps: u32::from_le_bytes(r.take()),
+            imbalance_k_bps: u32::from_le_bytes(r.take()),
+            liquidity_e6: u128::from_le_bytes(r.take()),
+            max_fill: u128::from_le_bytes(r.take()),
+            max_inventory: u128::from_le_bytes(r.take()),
+            age_halflife: u32::from_le_bytes(r.take()),
+            insurance_weight_bps: u32::from_le_bytes(r.take()),
+            impact_mode: r.take::<1>()[0],
+            impact_k_bps: u32::from_le_bytes(r.take()),
+            skew_k_bps: u32::from_le_bytes(r.take()),
+            coverage_curve: r.take::<1>()[0],
+            curve_points: [(); CURVE_POINTS].map(|_| u16::from_le_bytes(r.take())),
+            liq_halflife: u32::from_le_bytes(r.take()),
+            funding_horizon: u32::from_le_bytes(r.take()),
+            oracle_jump_bps: u16::from_le_bytes(r.take()),
+            oracle_jump_window: u32::from_le_bytes(r.take()),
+            flow_k_bps: u32::from_le_bytes(r.take()),
+            twap_window_slots: u32::from_le_bytes(r.take()),
+            signal_weights: [(); SIGNALS].map(|_| u16::from_le_bytes(r.take())),
+            deficit_halflife: u32::from_le_bytes(r.take()),
+            liq_auction_bps: u16::from_le_bytes(r.take()),
+            liq_auction_slots: u32::from_le_bytes(r.take()),
+            peg_band_e6: u32::from_le_bytes(r.take()),
+            spot_weight_bps: u16::from_le_bytes(r.take()),
+        })
+    }
+}
+
+struct Writer {
+    buf: [u8; PARAMS_MAX_LEN],
+    len: usize,
+}
+
+impl Default for Writer {
+    fn default() -> Self {
+        Self { buf: [0u8; PARAMS_MAX_LEN], len: 0 }
+    }
+}
+
+impl Writer {
+    fn put(&mut self, bytes: &[u8]) {
+        self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    off: usize,
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> [u8; N] {
+        let out = self.data[self.off..self.off + N].try_into().unwrap();
+        self.off += N;
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_unpack_roundtrip() {
+        let params = MatcherParams {
+            base_fee_bps: 5,
+            min_spread_bps: 10,
+            max_spread_bps: 200,
+            liquidity_e6: 1 << 90,
+            coverage_curve: 2,
+            curve_points: [1, 2, 3, 4],
+            oracle_jump_bps: 500,
+            oracle_jump_window: 10,
+            twap_window_slots: 300,
+            signal_weights: [4_000, 2_000, 2_000, 2_000],
+            deficit_halflife: 900,
+            liq_auction_bps: 150,
+            liq_auction_slots: 20,
+            peg_band_e6: 500,
+            spot_weight_bps: 3_000,
+            ..Default::default()
+        };
+        let packed = params.pack();
+        assert_eq!(&packed[..4], &5u32.to_le_bytes());
+        assert_eq!(&packed[108..112], &300u32.to_le_bytes());
+        assert_eq!(&packed[112..114], &4_000u16.to_le_bytes());
+        assert_eq!(&packed[120..124], &900u32.to_le_bytes());
+        assert_eq!(&packed[124..126], &150u16.to_le_bytes());
+        assert_eq!(&packed[126..130], &20u32.to_le_bytes());
+        assert_eq!(&packed[130..134], &500u32.to_le_bytes());
+        assert_eq!(&packed[PARAMS_MAX_LEN - 2..], &3_000u16.to_le_bytes());
+        assert_eq!(MatcherParams::unpack(&packed), Some(params));
+
+        // Trailing fields are optional; the prefix is not
+        let prefix = MatcherParams::unpack(&packed[..PARAMS_LEN]).unwrap();
+        assert_eq!(prefix.max_spread_bps, 200);
+        assert_eq!(prefix.twap_window_slots, 0);
+        assert_eq!(MatcherParams::unpack(&packed[..PARAMS_LEN - 1]), None);
+    }
+}
@@ -0,0 +1,90 @@
+//! Fixtures for tests that run the matcher against a percolator slab.
+//!
+//! Enabled by the `test-support` feature. [`SlabFixture`] writes the fields
+//! UpdateCredibility and SyncInventory read at their offsets in [`crate::layout`], so tests say
+//! what the market looks like instead of placing bytes by hand:
+//!
+//! ```
+//! use provenance_client::test_support::SlabFixture;
+//!
+//! // An adminless market at 30% coverage
+//! let slab = SlabFixture { insurance: 300, total_oi: 1_000, ..Default::default() }.to_bytes();
+//! ```
+
+use solana_program::pubkey::Pubkey;
+
+use crate::layout::*;
+
+/// A percolator slab, as far as the matcher can see it. Everything else in the
+/// image is zero.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SlabFixture {
+    /// The market admin; the default (zero) key is a burned admin, so the
+    /// market ages.
+    pub admin: Pubkey,
+    pub insurance: u128,
+    pub total_oi: u128,
+    pub last_crank_slot: u64,
+    pub lifetime_liqs: u64,
+    pub funding_rate_bps_per_slot: i64,
+    /// An account in the engine's table, for SyncInventory.
+    pub lp: Option<SlabAccount>,
+    /// Bytes in the image; at least `SLAB_MIN_LEN` for UpdateCredibility to
+    /// accept it.
+    pub len: usize,
+}
+
+impl Default for SlabFixture {
+    fn default() -> Self {
+        Self {
+            admin: Pubkey::default(),
+            insurance: 0,
+            total_oi: 0,
+            last_crank_slot: 0,
+            lifetime_liqs: 0,
+            funding_rate_bps_per_slot: 0,
+            lp: None,
+            len: SLAB_MIN_LEN,
+        }
+    }
+}
+
+impl SlabFixture {
+    /// The account data. Fields past a short `len` are left out.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let account_end = self.lp.map_or(0, |lp| SLAB_ACCOUNTS_OFF + (lp.index as usize + 1) * SLAB_ACCOUNT_LEN);
+        let mut data = vec![0u8; self.len.max(SLAB_MIN_LEN).max(account_end)];
+        let e = |off: usize| SLAB_ENGINE_OFF + off;
+        write(&mut data, SLAB_ADMIN_OFF, self.admin.as_ref());
+        write(&mut data, e(ENGINE_INSURANCE_OFF), &self.insurance.to_le_bytes());
+        write(&mut data, e(ENGINE_FUNDING_RATE_OFF), &self.funding_rate_bps_per_slot.to_le_bytes());
+        write(&mut data, e(ENGINE_LAST_CRANK_OFF), &self.last_crank_slot.to_le_bytes());
+        write(&mut data, e(ENGINE_TOTAL_OI_OFF), &self.total_oi.to_le_bytes());
+        write(&mut data, e(ENGINE_LIFETIME_LIQS_OFF), &self.lifetime_liqs.to_le_bytes());
+        if let Some(lp) = self.lp {
+            let a = |off: usize| SLAB_ACCOUNTS_OFF + lp.index as usize * SLAB_ACCOUNT_LEN + off;
+            write(&mut data, a(ACCT_KIND_OFF), &[lp.kind]);
+            write(&mut data, a(ACCT_POSITION_OFF), &lp.position_size.to_le_bytes());
+            write(&mut data, a(ACCT_MATCHER_PROGRAM_OFF), lp.matcher_program.as_ref());
+            write(&mut data, a(ACCT_MATCHER_CONTEXT_OFF), lp.matcher_context.as_ref());
+        }
+        data.truncate(self.len);
+        data
+    }
+}
+
+/// One account of the engine's table. `len` must reach past it for the slab
+/// to carry it: `SLAB_ACCOUNTS_OFF + (index + 1) * SLAB_ACCOUNT_LEN`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SlabAccount {
+    pub index: u16,
+    /// `ACCT_KIND_LP` for an LP.
+    pub kind: u8,
+    pub position_size: i128,
+    pub matcher_program: Pubkey,
+    pub matcher_context: Pubkey,
+}
+
+fn write(data: &mut [u8], off: usize, bytes: &[u8]) {
+    data[off..off + bytes.len()].copy_from_slice(bytes);
+}
@@ -0,0 +1,1829 @@
+//! Pricing core of the credibility matcher.
+//!
+//! [`quote`] is the whole pricing path — coverage tier, fill clip, spread
+//! penalties, skew and execution price — as a pure function of the matcher's
+//! parameters and state. The on-chain program calls it on every Match and
+//! QuotePreview; simulators and backtests call the same function, so an
+//! off-chain replay prices exactly as the chain does.
+//!
+//! [`plain::quote`] prices the plain matcher kind, which shares the oracle and
+//! inventory checks but none of the credibility terms. [`multi::quote`]
+//! prices the multi-signal kind: the spread follows a weighted blend of
+//! several credibility signals instead of the coverage ratio alone.
+//! [`stable::quote`] prices the stable kind for pegged markets: a price band
+//! around the peg stands in for the inventory terms.
+//! [`rfq::fill`] fills a trade at a price the LP signed off-chain, under the
+//! same oracle and inventory checks.
+//!
+//! No allocation, no I/O, no Solana types: decoding accounts and logging are
+//! the caller's job.
+
+#![no_std]
+
+pub mod multi;
+pub mod plain;
+pub mod rfq;
+pub mod stable;
+
+pub const BPS: u64 = 10_000;
+
+// Size impact curves (impact_mode)
+pub const IMPACT_NONE: u8 = 0;
+pub const IMPACT_LINEAR: u8 = 1;
+pub const IMPACT_SQRT: u8 = 2;
+
+// Coverage curves (coverage_curve): shape of the insurance discount across NORMAL
+pub const CURVE_LINEAR: u8 = 0;
+pub const CURVE_SQRT: u8 = 1;
+pub const CURVE_PIECEWISE: u8 = 2;
+pub const CURVE_POINTS: usize = 4;
+
+// Credibility signals, indexing signal_weights (multi-signal kind)
+pub const SIGNAL_COVERAGE: usize = 0;
+pub const SIGNAL_AGE: usize = 1;
+pub const SIGNAL_DEFICIT: usize = 2;
+pub const SIGNAL_LIQUIDATIONS: usize = 3;
+pub const SIGNALS: usize = 4;
+pub const SIGNAL_NAMES: [&str; SIGNALS] = ["coverage", "age", "deficit", "liquidations"];
+
+// =============================================================================
+// Coverage Tiers — the market prices its own fragility
+//
+// These thresholds are hardcoded, not configurable. The math is the governance.
+// =============================================================================
+
+/// Coverage < 10%: CRITICAL
+/// Spread locked at max. Fill capped at 25% of base max_fill.
+/// The market is telling you: "I might not survive a liquidation cascade."
+pub const TIER_CRITICAL_BPS: u64 = 1_000; // 10% in bps
+
+/// Coverage < 25%: FRAGILE
+/// Spread interpolates sharply toward max. Fill capped at 50%.
+/// Thin insurance = expensive trading. No exceptions.
+pub const TIER_FRAGILE_BPS: u64 = 2_500; // 25% in bps
+
+/// Coverage < 100%: NORMAL
+/// Linear discount from insurance_weight_bps. Full fill allowed.
+/// The standard operating range.
+pub const TIER_NORMAL_BPS: u64 = 10_000; // 100% in bps
+
+/// Coverage < 200%: STRONG
+/// Full insurance discount. Spreads at their tightest for the base formula.
+/// The market has proven solvency.
+pub const TIER_STRONG_BPS: u64 = 20_000; // 200% in bps
+
+// Coverage >= 200%: FORTIFIED
+// Spread at min_spread floor. Fill gets 50% bonus above base max_fill.
+// Overcollateralized markets get the best pricing.
+
+// Tier ids, as reported by QuotePreview
+pub const TIER_CRITICAL: u8 = 0;
+pub const TIER_FRAGILE: u8 = 1;
+pub const TIER_NORMAL: u8 = 2;
+pub const TIER_STRONG: u8 = 3;
+pub const TIER_FORTIFIED: u8 = 4;
+pub const TIER_NAMES: [&str; 5] = ["CRITICAL", "FRAGILE", "NORMAL", "STRONG", "FORTIFIED"];
+
+// Fill multipliers (in percent, applied to max_fill_abs)
+pub const FILL_PCT_CRITICAL: u128 = 25;
+pub const FILL_PCT_FRAGILE: u128 = 50;
+pub const FILL_PCT_NORMAL: u128 = 100;
+pub const FILL_PCT_STRONG: u128 = 100;
+pub const FILL_PCT_FORTIFIED: u128 = 150;
+
+// =============================================================================
+// Snapshot staleness — an old snapshot is not evidence of current solvency
+// =============================================================================
+
+/// Snapshots younger than this carry no penalty (~5 minutes at 400ms/slot).
+/// Comfortably covers a keeper cranking every 30-60 seconds.
+pub const SNAPSHOT_GRACE_SLOTS: u64 = 750;
+
+/// Past the grace period, spread widens by 1 bps per this many slots (~1 minute).
+/// After ~3 hours without an update a 200 bps max_spread is fully reached.
+pub const STALE_SLOTS_PER_BPS: u64 = 150;
+
+/// The percolator crank may lag this long before it costs anything (~1 minute).
+/// Past it, funding and liquidations are stale and the insurance figure with them.
+pub const CRANK_GRACE_SLOTS: u64 = 150;
+
+/// Past the crank grace period, spread widens by 1 bps per this many slots (~20s).
+pub const CRANK_SLOTS_PER_BPS: u64 = 50;
+
+// =============================================================================
+// Liquidation heat — a burst of liquidations is the insurance fund being tested
+// =============================================================================
+
+/// liq_heat fixed-point scale: 1000 = one liquidation.
+pub const LIQ_HEAT_SCALE: u64 = 1_000;
+
+/// Each (undecayed) liquidation widens the spread by this much.
+/// A burst of 20 liquidations pushes a 200 bps max_spread market to its cap.
+pub const LIQ_PENALTY_BPS: u64 = 10;
+
+// =============================================================================
+// Reduce-only — a nearly full book only takes flow that unwinds it
+// =============================================================================
+
+/// Past this share of max_inventory_abs the matcher goes reduce-only on its own,
+/// leaving the last 5% of the cap for the trades that bring it back down,
+/// unless `State::reduce_only_bps` sets another.
+pub const AUTO_REDUCE_ONLY_BPS: u128 = 9_500;
+
+/// The parameters the pricing path reads. Terms whose coefficient is zero are
+/// off, which is also how a v5 context (no extension) prices.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Params {
+    pub base_fee_bps: u32,
+    pub min_spread_bps: u32,
+    pub max_spread_bps: u32,
+    pub imbalance_k_bps: u32,
+    pub liquidity_e6: u128,
+    /// Base fill limit before the tier multiplier; 0 = unlimited.
+    pub max_fill: u128,
+    /// |inventory| cap; 0 = unlimited.
+    pub max_inventory: u128,
+    pub insurance_weight_bps: u32,
+    pub impact_mode: u8,
+    pub impact_k_bps: u32,
+    pub skew_k_bps: u32,
+    pub coverage_curve: u8,
+    pub curve_points: [u16; CURVE_POINTS],
+    pub liq_halflife: u32,
+    pub funding_horizon: u32,
+    pub oracle_jump_bps: u16,
+    pub oracle_jump_window: u32,
+    pub flow_k_bps: u32,
+    pub twap_window_slots: u32,
+    /// With TWAP anchoring on, the live oracle's share of the anchor; the
+    /// TWAP takes the rest. 0 = all TWAP.
+    pub spot_weight_bps: u16,
+    /// Halflife of the market-age signal.
+    pub age_halflife: u32,
+    /// Relative weight of each credibility signal, by SIGNAL_* index. Only
+    /// the multi-signal kind reads these three.
+    pub signal_weights: [u16; SIGNALS],
+    /// Halflife of the deficit-recency signal.
+    pub deficit_halflife: u32,
+    /// Dutch-auction premium right after a liquidation, falling to zero over
+    /// `liq_auction_slots`; see [`liq_auction_bps`]. 0 slots = off.
+    pub liq_auction_bps: u16,
+    pub liq_auction_slots: u32,
+    /// Half-width of the stable kind's price band around the peg, in e6;
+    /// see [`stable::quote`]. Only the stable kind reads it.
+    pub peg_band_e6: u32,
+}
+
+/// Matcher state as of the trade: the LP's book, the last credibility
+/// snapshot, and the clock.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct State {
+    pub current_slot: u64,
+    /// What takers have bought on net. The LP holds the opposite, so a
+    /// positive inventory is a short LP book.
+    pub inventory: i128,
+    pub insurance_snapshot: u128,
+    pub total_oi_snapshot: u128,
+    pub snapshot_slot: u64,
+    pub last_crank_slot: u64,
+    pub liq_heat: u64,
+    pub funding_rate_bps_per_slot: i64,
+    /// Oracle price of the last fill, and its slot, for the circuit breaker.
+    pub last_oracle_price_e6: u64,
+    pub last_oracle_slot: u64,
+    pub gross_long: u128,
+    pub gross_short: u128,
+    /// The LP's reduce-only flag; the near-cap limit applies regardless.
+    pub reduce_only: bool,
+    /// TWAP over `twap_window_slots` (see [`twap`]); required when the window
+    /// is set, ignored otherwise.
+    pub twap_price_e6: Option<u64>,
+    /// Slots the market had aged since its admin was burned, as of the snapshot.
+    pub market_age: u64,
+    /// Last snapshot that found the insurance fund drawn down; 0 = never.
+    pub last_deficit_slot: u64,
+    /// Last snapshot that found new liquidations; 0 = never.
+    pub last_liq_slot: u64,
+    /// External signal surcharge: the weighted sum of the fresh signal
+    /// accounts passed with the trade (see [`signal_bps`]).
+    pub signal_bps: u64,
+    /// What the LP's spread schedule multiplies the spread by this slot, in
+    /// bps (BPS = 1x); at or below BPS, nothing (see [`schedule_mult_bps`]).
+    pub spread_mult_bps: u64,
+    /// Oracle surcharge: the confidence interval of the oracle feeds the
+    /// price was taken from, plus how far apart they are when the LP widens on
+    /// that (see [`divergence_bps`]).
+    pub oracle_bps: u64,
+    /// The LP's rebate for fills that shrink |inventory|, in bps (see
+    /// [`rebate_bps`]).
+    pub rebate_bps: u64,
+    /// Share of the fee the LP waives at the full credibility discount, in
+    /// bps; that share of it part way (see [`scaled_fee_bps`]).
+    pub fee_discount_bps: u64,
+    /// Slots over which the inventory the imbalance and skew penalties see
+    /// halves while no fill comes in, counted from `last_oracle_slot`; 0 = no
+    /// decay (see [`decayed_inventory`]).
+    pub inventory_halflife: u32,
+    /// The soft inventory band: a fill that takes |inventory| past it pays
+    /// up to `band_k_bps` more, sharply more toward max_inventory, which
+    /// still rejects; 0 = no band (see [`band_bps`]).
+    pub soft_inventory: u128,
+    pub band_k_bps: u64,
+    /// Caps on the LP's long book (inventory < 0) and its short book
+    /// (inventory > 0), for an LP that can warehouse one side more cheaply
+    /// than the other; 0 = max_inventory on that side (see [`inventory_cap`]).
+    pub max_long_inventory: u128,
+    pub max_short_inventory: u128,
+    /// Share of the side's cap, in bps, past which the book goes reduce-only
+    /// on its own; 0 = AUTO_REDUCE_ONLY_BPS.
+    pub reduce_only_bps: u64,
+}
+
+/// Why a trade gets no quote.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuoteError {
+    ZeroOraclePrice,
+    /// The oracle moved more than oracle_jump_bps from the last fill's print
+    /// inside the window.
+    OracleJump { last_price_e6: u64, moved_bps: u64, elapsed_slots: u64 },
+    /// twap_window_slots is set but `State::twap_price_e6` is missing.
+    StatsRequired,
+    InventoryOverflow { fill_size: i128 },
+    /// Reduce-only (set, or `near_cap`) and the fill grows |inventory|.
+    ReduceOnly { near_cap: bool, new_inventory: i128 },
+    InventoryLimit,
+}
+
+/// Everything the pricing path decided for one trade, term by term.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Quote {
+    /// How far credibility brought the spread in from max_spread_bps:
+    /// tier_spread_bps = max_spread_bps - discount_bps.
+    pub discount_bps: u64,
+    pub coverage_bps: u64,
+    pub tier: u8,
+    pub fill_pct: u128,
+    pub tier_spread_bps: u64,
+    /// The imbalance penalty, plus the soft band's surcharge.
+    pub imbalance_bps: u64,
+    pub impact_bps: u64,
+    pub stale_bps: u64,
+    pub crank_bps: u64,
+    pub liq_bps: u64,
+    /// Surcharge for trading with one-way gross flow (flow_k_bps).
+    pub flow_bps: u64,
+    /// Surcharge from external signal accounts (`State::signal_bps`).
+    pub signal_bps: u64,
+    /// Surcharge from the LP's spread schedule (`State::spread_mult_bps`).
+    pub schedule_bps: u64,
+    /// Surcharge from the oracle feeds (`State::oracle_bps`).
+    pub oracle_bps: u64,
+    /// Tier spread plus all penalties, clamped to [1, max(max_spread_bps, 1)].
+    pub spread_bps: u64,
+    /// Total mid shift (inventory + funding), within ±spread_bps.
+    pub skew_bps: i64,
+    pub funding_skew_bps: i64,
+    /// base_fee_bps, or the fee tier's, less what `State::fee_discount_bps`
+    /// waives of it.
+    pub fee_bps: u64,
+    /// Taken off spread plus fee for a fill that shrinks |inventory|
+    /// (`State::rebate_bps`, within the cost left after the skew).
+    pub rebate_bps: u64,
+    /// Price the spread is applied around: the oracle, its TWAP, or a blend of the two.
+    pub anchor_price_e6: u64,
+    pub exec_price_e6: u64,
+    /// Signed size actually filled: the request, clipped to the tier's limit.
+    pub fill_size: i128,
+    pub new_inventory: i128,
+}
+
+// =============================================================================
+// Pricing path shared by Match and QuotePreview
+//
+//   1. Compute coverage ratio (insurance / OI)
+//   2. Determine tier → sets spread multiplier and fill cap
+//   3. Clip the fill to the tier's limit
+//   4. Add inventory imbalance and size penalties
+//   5. Clamp to [min_spread, max_spread]
+//   6. Calculate execution price around the oracle, or its TWAP
+// =============================================================================
+pub fn quote(params: &Params, state: &State, oracle_price_e6: u64, trade_size: i128) -> Result<Quote, QuoteError> {
+    let anchor_price_e6 = anchor_price(params, state, oracle_price_e6)?;
+
+    // =========================================================================
+    // STEP 1: Compute coverage ratio in bps (0 = no insurance, 10000 = 100%)
+    // =========================================================================
+    let coverage_bps = coverage_bps(state.insurance_snapshot, state.total_oi_snapshot);
+
+    // =========================================================================
+    // STEP 2: Determine tier → spread adjustment and fill multiplier
+    // =========================================================================
+    let standing = coverage_standing(params, coverage_bps);
+    price_standing(params, state, anchor_price_e6, trade_size, standing)
+}
+
+/// Step 2 of the pricing path: the coverage tier, its spread and its fill
+/// multiplier.
+pub(crate) fn coverage_standing(params: &Params, coverage_bps: u64) -> Standing {
+    let min_spread_bps = params.min_spread_bps as u64;
+    let max_spread_bps = params.max_spread_bps as u64;
+    let insurance_weight_bps = params.insurance_weight_bps as u64;
+
+    let (tier, spread_bps, fill_pct) = if coverage_bps < TIER_CRITICAL_BPS {
+        // CRITICAL: <10% coverage. Max spread. Severely limited fills.
+        (TIER_CRITICAL, max_spread_bps, FILL_PCT_CRITICAL)
+    } else if coverage_bps < TIER_FRAGILE_BPS {
+        // FRAGILE: 10-25%. Interpolate between max_spread and 75% of spread range.
+        // Linear interpolation: progress from 10% to 25%
+        let progress = coverage_bps - TIER_CRITICAL_BPS; // 0..1500
+        let range = TIER_FRAGILE_BPS - TIER_CRITICAL_BPS; // 1500
+        let spread_range = max_spread_bps.saturating_sub(min_spread_bps);
+        // At 10%: spread = max_spread. At 25%: spread = max_spread - 25% of range
+        let reduction = (spread_range / 4) * progress / range;
+        (
+            TIER_FRAGILE,
+            max_spread_bps.saturating_sub(reduction),
+            FILL_PCT_FRAGILE,
+        )
+    } else if coverage_bps < TIER_NORMAL_BPS {
+        // NORMAL: 25-100%. Discount from insurance weight, shaped by coverage_curve.
+        // coverage_fraction = (coverage - 25%) / 75%
+        let progress = coverage_bps - TIER_FRAGILE_BPS; // 0..7500
+        let range = TIER_NORMAL_BPS - TIER_FRAGILE_BPS; // 7500
+        let discount = coverage_discount_bps(
+            params.coverage_curve,
+            &params.curve_points,
+            insurance_weight_bps,
+            progress,
+            range,
+        );
+        let base = max_spread_bps - max_spread_bps.saturating_sub(min_spread_bps) / 4; // starts where FRAGILE ends
+        // A weight above three quarters of the spread range would otherwise
+        // take NORMAL below the STRONG tier it leads into
+        (
+            TIER_NORMAL,
+            base.saturating_sub(discount).max(min_spread_bps),
+            FILL_PCT_NORMAL,
+        )
+    } else if coverage_bps < TIER_STRONG_BPS {
+        // STRONG: 100-200%. Full insurance discount applied. Tight spreads.
+        let discount = insurance_weight_bps;
+        (
+            TIER_STRONG,
+            min_spread_bps.saturating_add(insurance_weight_bps).saturating_sub(discount),
+            FILL_PCT_STRONG,
+        )
+    } else {
+        // FORTIFIED: >200%. Minimum spread. Bonus fill capacity.
+        (TIER_FORTIFIED, min_spread_bps, FILL_PCT_FORTIFIED)
+    };
+
+    Standing { coverage_bps, tier, fill_pct, spread_bps }
+}
+
+/// Where a credibility kind's evidence puts the market before the trade is
+/// looked at: the coverage it reports, the tier and its fill multiplier, and
+/// the spread the per-trade terms are added to.
+pub(crate) struct Standing {
+    pub coverage_bps: u64,
+    pub tier: u8,
+    pub fill_pct: u128,
+    pub spread_bps: u64,
+}
+
+/// Steps 3 to 6 of the pricing path, from a kind's standing.
+pub(crate) fn price_standing(
+    params: &Params,
+    state: &State,
+    anchor_price_e6: u64,
+    trade_size: i128,
+    standing: Standing,
+) -> Result<Quote, QuoteError> {
+    let Standing { coverage_bps, tier, fill_pct, spread_bps } = standing;
+    let max_spread_bps = params.max_spread_bps as u64;
+    let discount_bps = max_spread_bps.saturating_sub(spread_bps);
+    // The fee comes down with the spread: a market credible enough to earn
+    // the whole discount earns the LP's whole fee discount too
+    let base_fee_bps = scaled_fee_bps(
+        params.base_fee_bps as u64,
+        state.fee_discount_bps,
+        discount_bps,
+        max_spread_bps.saturating_sub(params.min_spread_bps as u64),
+    );
+    let liquidity_e6 = params.liquidity_e6;
+    let current_slot = state.current_slot;
+    let inventory = state.inventory;
+    // What the penalties price: the LP is taken to hedge elsewhere as slots
+    // pass without flow. The book itself keeps the full inventory.
+    let penalty_inventory = decayed_inventory(inventory, current_slot, state.last_oracle_slot, state.inventory_halflife);
+
+    // =========================================================================
+    // STEP 3: Clip the fill to the tier's limit
+    //
+    // An oversize trade is filled up to the limit rather than refused; the
+    // matcher return carries the clipped size and percolator routes the rest.
+    // The same goes for what is left of the inventory cap on its side.
+    // =========================================================================
+    let fill_size = clip_headroom(params, state, clip_fill(trade_size, params.max_fill, fill_pct));
+
+    // =========================================================================
+    // STEP 4: Inventory imbalance penalty (standard market-making, all tiers)
+    // =========================================================================
+    // Past the soft band, a fill that goes deeper pays sharply more, well
+    // before the hard limit on that side refuses it
+    let filled_inventory = inventory.saturating_add(fill_size);
+    let imbalance_cost = imbalance_bps(params.imbalance_k_bps as u64, penalty_inventory, liquidity_e6)
+        .saturating_add(band_bps(state, inventory_cap(params, state, filled_inventory), filled_inventory));
+    let mut final_spread = spread_bps.saturating_add(imbalance_cost);
+
+    // Size impact: large takers pay more than a 1-unit trade, on what they get
+    let abs_size = fill_size.unsigned_abs();
+    let impact_cost = impact_bps(params.impact_mode, params.impact_k_bps as u64, abs_size, liquidity_e6);
+    final_spread = final_spread.saturating_add(impact_cost);
+
+    // Staleness: the insurance discount can't outlive the snapshot that earned it
+    let stale_cost = staleness_bps(current_slot, state.snapshot_slot);
+    final_spread = final_spread.saturating_add(stale_cost);
+
+    // Crank freshness: a percolator that hasn't cranked has stale funding and
+    // liquidation state, so its insurance figure is unreliable too
+    let crank_cost = crank_lag_bps(current_slot, state.last_crank_slot);
+    final_spread = final_spread.saturating_add(crank_cost);
+
+    // Liquidation heat, decayed from the snapshot to now, plus the auction
+    // premium while the last liquidation is fresh
+    let heat_now = decay_halflife(
+        state.liq_heat,
+        current_slot.saturating_sub(state.snapshot_slot),
+        params.liq_halflife as u64,
+    );
+    let auction_cost = liq_auction_bps(
+        current_slot,
+        state.last_liq_slot,
+        params.liq_auction_bps as u64,
+        params.liq_auction_slots as u64,
+    );
+    let liq_cost = liq_heat_bps(heat_now).saturating_add(auction_cost);
+    final_spread = final_spread.saturating_add(liq_cost);
+
+    // One-way flow: a small net can hide heavy flow in one direction (the LP
+    // hedged or settled the rest away). Trades that continue the dominant
+    // direction pay flow_k_bps scaled by how lopsided gross flow has been.
+    let flow_cost = flow_bps(params.flow_k_bps as u64, state.gross_long, state.gross_short, fill_size);
+    final_spread = final_spread.saturating_add(flow_cost);
+
+    // External signals: whatever the caller folded in from signal accounts.
+    // Only ever widens the spread.
+    final_spread = final_spread.saturating_add(state.signal_bps);
+
+    // Oracle uncertainty: a feed unsure of its own price, or feeds that
+    // disagree (the price is their median), quote at least that much wider
+    final_spread = final_spread.saturating_add(state.oracle_bps);
+
+    // Spread schedule: the LP's own multiplier around known events, on
+    // everything above
+    let schedule_cost = schedule_bps(final_spread, state.spread_mult_bps);
+    final_spread = final_spread.saturating_add(schedule_cost);
+
+    // =========================================================================
+    // STEP 5: Clamp spread to [1, max_spread_bps]; never below 1 bps, even
+    // when max_spread_bps is 0
+    // =========================================================================
+    final_spread = final_spread.clamp(1, max_spread_bps.max(1));
+
+    // Directional skew: shift the mid against the LP's book so the side that
+    // brings it back to flat gets the better price. Bounded by the spread, so
+    // the quote never crosses the oracle.
+    //
+    // Funding skew: when longs pay (rate > 0) the toxic flow is a trader going
+    // short into the settlement, so shift the quote down the same way a long
+    // LP book does. The combined skew stays within the spread.
+    let funding_skew = funding_skew_bps(state.funding_rate_bps_per_slot, params.funding_horizon as u64);
+    let skew = skew_bps(params.skew_k_bps as u64, penalty_inventory, liquidity_e6, final_spread)
+        .saturating_add(funding_skew)
+        .clamp(-(final_spread as i64), final_spread as i64);
+
+    let new_inventory = book_fill(params, state, fill_size)?;
+
+    // =========================================================================
+    // STEP 6: Calculate execution price
+    //
+    // A fill that brings the book back toward flat earns the LP's rebate off
+    // the cost, so takers are paid to take the side the LP wants
+    // =========================================================================
+    let total_cost_bps = final_spread + base_fee_bps;
+    let rebate = rebate_bps(state.rebate_bps, inventory, new_inventory, total_cost_bps, skew);
+    let exec_price_e6 = exec_price(anchor_price_e6, total_cost_bps - rebate, skew, fill_size > 0);
+
+    Ok(Quote {
+        coverage_bps,
+        tier,
+        fill_pct,
+        discount_bps,
+        tier_spread_bps: spread_bps,
+        imbalance_bps: imbalance_cost,
+        impact_bps: impact_cost,
+        stale_bps: stale_cost,
+        crank_bps: crank_cost,
+        liq_bps: liq_cost,
+        flow_bps: flow_cost,
+        signal_bps: state.signal_bps,
+        schedule_bps: schedule_cost,
+        oracle_bps: state.oracle_bps,
+        spread_bps: final_spread,
+        skew_bps: skew,
+        funding_skew_bps: funding_skew,
+        fee_bps: base_fee_bps,
+        rebate_bps: rebate,
+        anchor_price_e6,
+        exec_price_e6,
+        fill_size,
+        new_inventory,
+    })
+}
+
+// =============================================================================
+// Steps every matcher kind shares: the oracle checks up front, the fill clip,
+// and the inventory checks at the end
+// =============================================================================
+
+/// The price a quote is built around, once the oracle has passed the checks:
+/// nonzero, and inside the circuit breaker. That is the oracle itself, or its
+/// TWAP when twap_window_slots is set, blended with the oracle by
+/// spot_weight_bps.
+pub(crate) fn anchor_price(params: &Params, state: &State, oracle_price_e6: u64) -> Result<u64, QuoteError> {
+    if oracle_price_e6 == 0 {
+        return Err(QuoteError::ZeroOraclePrice);
+    }
+
+    // Circuit breaker: refuse a print that moved too far from the last one
+    // inside the window. The last price only advances on a fill, so once the
+    // window lapses the next match re-anchors at whatever the oracle says.
+    let jump_bps = params.oracle_jump_bps as u64;
+    let last_price = state.last_oracle_price_e6;
+    let elapsed = state.current_slot.saturating_sub(state.last_oracle_slot);
+    if jump_bps > 0 && last_price > 0 && elapsed <= params.oracle_jump_window as u64 {
+        // Compared unrounded: |move| / last > jump_bps / 10000
+        let moved = last_price.abs_diff(oracle_price_e6) as u128 * BPS as u128;
+        if moved > jump_bps as u128 * last_price as u128 {
+            return Err(QuoteError::OracleJump {
+                last_price_e6: last_price,
+                moved_bps: (moved / last_price as u128) as u64,
+                elapsed_slots: elapsed,
+            });
+        }
+    }
+
+    // TWAP anchoring: quote around the time-weighted average of recent
+    // recorded prices, so a single manipulated print barely moves the quote.
+    // The live print keeps spot_weight_bps of the anchor, and a print moves
+    // the quote by at most that share of its move.
+    if params.twap_window_slots > 0 {
+        let twap = state.twap_price_e6.ok_or(QuoteError::StatsRequired)? as u128;
+        let spot = (params.spot_weight_bps as u128).min(BPS as u128);
+        Ok(((oracle_price_e6 as u128 * spot + twap * (BPS as u128 - spot)) / BPS as u128) as u64)
+    } else {
+        Ok(oracle_price_e6)
+    }
+}
+
+/// `trade_size` clipped to `fill_pct`% of `max_fill`, keeping its sign; a
+/// `max_fill` of 0 is unlimited.
+pub(crate) fn clip_fill(trade_size: i128, max_fill: u128, fill_pct: u128) -> i128 {
+    let effective_max_fill = max_fill.saturating_mul(fill_pct) / 100;
+    if effective_max_fill > 0 && trade_size.unsigned_abs() > effective_max_fill {
+        // effective_max_fill < |trade_size| <= 2^127, so it fits in i128
+        effective_max_fill as i128 * trade_size.signum()
+    } else {
+        trade_size
+    }
+}
+
+/// `fill_size` clipped to the room left under the cap on the side it pushes
+/// the book toward, keeping its sign, so the last of the cap still fills
+/// instead of refusing the trade; 0 with no room left.
+pub(crate) fn clip_headroom(params: &Params, state: &State, fill_size: i128) -> i128 {
+    let max_inventory = inventory_cap(params, state, fill_size);
+    if max_inventory == 0 {
+        return fill_size;
+    }
+    // The inventory measured in the fill's direction
+    let toward = if fill_size < 0 { state.inventory.saturating_neg() } else { state.inventory };
+    let headroom = if toward < 0 {
+        max_inventory.saturating_add(toward.unsigned_abs())
+    } else {
+        max_inventory.saturating_sub(toward as u128)
+    };
+    if fill_size.unsigned_abs() > headroom {
+        // headroom < |fill_size| <= 2^127, so it fits in i128
+        headroom as i128 * fill_size.signum()
+    } else {
+        fill_size
+    }
+}
+
+/// max_inventory as `max_inventory_bps` of the quoting depth: that share of
+/// the liquidity_e6 notional, in base units at the oracle, so the limit moves
+/// with the depth. 0 (no cap from depth) without a depth, a share or a
+/// price; otherwise at least 1, since a 0 cap would be unlimited.
+pub fn depth_inventory_cap(liquidity_e6: u128, max_inventory_bps: u64, oracle_price_e6: u64) -> u128 {
+    if liquidity_e6 == 0 || max_inventory_bps == 0 || oracle_price_e6 == 0 {
+        return 0;
+    }
+    let notional = liquidity_e6.saturating_mul(max_inventory_bps as u128) / BPS as u128;
+    (notional.saturating_mul(1_000_000) / oracle_price_e6 as u128).max(1)
+}
+
+/// The cap on |inventory| on `inventory`'s side of flat: the state's cap on
+/// the LP's short book for a positive inventory, on its long book otherwise,
+/// where set, max_inventory otherwise; 0 = unlimited.
+pub fn inventory_cap(params: &Params, state: &State, inventory: i128) -> u128 {
+    let side = if inventory > 0 { state.max_short_inventory } else { state.max_long_inventory };
+    if side != 0 { side } else { params.max_inventory }
+}
+
+/// The LP's inventory after `fill_size`, unless the fill overflows it, grows
+/// it while reduce-only, or leaves it past its side's cap (only a book
+/// already past a lowered cap gets there once the fill is clipped to the
+/// headroom).
+pub(crate) fn book_fill(params: &Params, state: &State, fill_size: i128) -> Result<i128, QuoteError> {
+    let inventory = state.inventory;
+    let Some(new_inventory) = inventory.checked_add(fill_size) else {
+        return Err(QuoteError::InventoryOverflow { fill_size });
+    };
+
+    // Reduce-only: set by the LP, or automatic near the inventory cap
+    let max_inventory = inventory_cap(params, state, inventory);
+    let threshold_bps = if state.reduce_only_bps != 0 { state.reduce_only_bps as u128 } else { AUTO_REDUCE_ONLY_BPS };
+    let near_cap = max_inventory > 0
+        && inventory.unsigned_abs().saturating_mul(BPS as u128) >= max_inventory.saturating_mul(threshold_bps);
+    if (state.reduce_only || near_cap) && new_inventory.unsigned_abs() >= inventory.unsigned_abs() {
+        return Err(QuoteError::ReduceOnly { near_cap, new_inventory });
+    }
+
+    // Enforce max inventory, on the side the fill leaves the book
+    let max_inventory = inventory_cap(params, state, new_inventory);
+    if max_inventory > 0 && new_inventory.unsigned_abs() > max_inventory {
+        return Err(QuoteError::InventoryLimit);
+    }
+    Ok(new_inventory)
+}
+
+/// Inventory imbalance penalty: imbalance_k_bps per liquidity_e6 of
+/// |inventory|. Off when either is zero.
+pub fn imbalance_bps(imbalance_k_bps: u64, inventory: i128, liquidity_e6: u128) -> u64 {
+    if liquidity_e6 == 0 {
+        return 0;
+    }
+    ((imbalance_k_bps as u128).saturating_mul(inventory.unsigned_abs()) / liquidity_e6).min(u64::MAX as u128) as u64
+}
+
+/// Soft band surcharge for a fill that leaves |inventory| at
+/// |new_inventory|: nothing unless it grows |inventory| past
+/// `State::soft_inventory`, then `band_k_bps` times the square of how far
+/// into the band it got, the band running from the soft limit to
+/// max_inventory (or as wide again without one). Reaches the whole
+/// `band_k_bps` at the hard limit.
+pub fn band_bps(state: &State, max_inventory: u128, new_inventory: i128) -> u64 {
+    let (soft, new) = (state.soft_inventory, new_inventory.unsigned_abs());
+    if soft == 0 || state.band_k_bps == 0 || new <= soft || new <= state.inventory.unsigned_abs() {
+        return 0;
+    }
+    let width = if max_inventory > soft { max_inventory - soft } else { soft };
+    let depth_bps = ((new - soft).saturating_mul(BPS as u128) / width).min(BPS as u128);
+    (state.band_k_bps as u128 * depth_bps * depth_bps / (BPS as u128 * BPS as u128)) as u64
+}
+
+/// The size an off-chain hedger should trade to bring `new_inventory` back
+/// within `neutral` of flat: nothing inside the band, otherwise the excess,
+/// signed like the LP's trade. The LP holds the opposite of the inventory,
+/// so a positive inventory is a short book that buys the excess back. A
+/// neutral band of 0 hedges the whole inventory.
+pub fn hedge_size(new_inventory: i128, neutral: u128) -> i128 {
+    let excess = new_inventory.unsigned_abs().saturating_sub(neutral).min(i128::MAX as u128) as i128;
+    if new_inventory > 0 { excess } else { -excess }
+}
+
+/// Coverage ratio in bps (0 = no insurance, 10000 = 100%) as `quote` sees it.
+pub fn coverage_bps(insurance: u128, total_oi: u128) -> u64 {
+    if total_oi > 0 {
+        let ratio = insurance.saturating_mul(BPS as u128).checked_div(total_oi).unwrap_or(0);
+        // Allow >10000 (>100% coverage) — don't cap here
+        ratio.min(u64::MAX as u128) as u64
+    } else {
+        // No OI: if there's any insurance, treat as FORTIFIED; else NORMAL
+        if insurance > 0 { TIER_STRONG_BPS } else { TIER_FRAGILE_BPS }
+    }
+}
+
+/// The tier `quote` prices a coverage ratio in.
+pub fn coverage_tier(coverage_bps: u64) -> u8 {
+    match coverage_bps {
+        c if c < TIER_CRITICAL_BPS => TIER_CRITICAL,
+        c if c < TIER_FRAGILE_BPS => TIER_FRAGILE,
+        c if c < TIER_NORMAL_BPS => TIER_NORMAL,
+        c if c < TIER_STRONG_BPS => TIER_STRONG,
+        _ => TIER_FORTIFIED,
+    }
+}
+
+/// A tier's fill multiplier, in percent of max_fill.
+pub fn tier_fill_pct(tier: u8) -> u128 {
+    match tier {
+        TIER_CRITICAL => FILL_PCT_CRITICAL,
+        TIER_FRAGILE => FILL_PCT_FRAGILE,
+        TIER_NORMAL => FILL_PCT_NORMAL,
+        TIER_STRONG => FILL_PCT_STRONG,
+        _ => FILL_PCT_FORTIFIED,
+    }
+}
+
+/// Time-weighted average of recorded oracle prices over the last `window`
+/// slots. `samples` are (slot, price), newest first. Each sample holds until
+/// the next one; the sample in force when the window opened covers its start.
+/// With no history inside the window, `oracle_price_e6` is used.
+pub fn twap(
+    samples: impl IntoIterator<Item = (u64, u64)>,
+    window: u64,
+    current_slot: u64,
+    oracle_price_e6: u64,
+) -> u64 {
+    let window_start = current_slot.saturating_sub(window);
+    let (mut weighted, mut total) = (0u128, 0u128);
+    let mut end = current_slot;
+    for (slot, price) in samples {
+        let start = slot.max(window_start);
+        if end > start {
+            weighted += price as u128 * (end - start) as u128;
+            total += (end - start) as u128;
+        }
+        end = end.min(start);
+        if slot <= window_start {
+            break;
+        }
+    }
+    if total == 0 {
+        return oracle_price_e6;
+    }
+    (weighted / total) as u64
+}
+
+/// Size impact in bps for a fill of `abs_size` against `liquidity_e6` depth.
+///
+/// Linear:      impact_k_bps * size / liquidity
+/// Square-root: impact_k_bps * sqrt(size / liquidity)
+///
+/// Square-root is the classic empirical impact law: it charges large fills
+/// more than small ones without making the largest fills unquotable.
+pub fn impact_bps(mode: u8, impact_k_bps: u64, abs_size: u128, liquidity_e6: u128) -> u64 {
+    if liquidity_e6 == 0 || impact_k_bps == 0 || abs_size == 0 {
+        return 0;
+    }
+    let cost = match mode {
+        IMPACT_LINEAR => (impact_k_bps as u128).saturating_mul(abs_size) / liquidity_e6,
+        IMPACT_SQRT => {
+            // sqrt(size / liquidity) in 1e6 fixed point
+            let ratio_e12 = abs_size.saturating_mul(1_000_000_000_000) / liquidity_e6;
+            (impact_k_bps as u128).saturating_mul(isqrt(ratio_e12)) / 1_000_000
+        }
+        _ => 0,
+    };
+    cost.min(u64::MAX as u128) as u64
+}
+
+/// Insurance discount in bps for `progress` out of `range` through the NORMAL tier.
+///
+/// Linear:    weight * x
+/// Sqrt:      weight * sqrt(x) — early coverage earns most of the discount
+/// Piecewise: weight * interpolate(0, p20, p40, p60, p80, 10000) at x, where the
+///            four operator-supplied points are in bps of the full discount
+pub fn coverage_discount_bps(
+    curve: u8,
+    points: &[u16; CURVE_POINTS],
+    weight_bps: u64,
+    progress: u64,
+    range: u64,
+) -> u64 {
+    if weight_bps == 0 || range == 0 {
+        return 0;
+    }
+    let progress = progress.min(range);
+    let shaped_bps = match curve {
+        CURVE_SQRT => isqrt((progress * BPS / range * BPS) as u128) as u64,
+        CURVE_PIECEWISE => {
+            // Knots at x = 0, 20%, 40%, 60%, 80%, 100%
+            let segments = CURVE_POINTS as u64 + 1;
+            let x = progress * BPS / range;
+            let seg_len = BPS / segments;
+            let seg = (x / seg_len).min(segments - 1) as usize;
+            let knot = |i: usize| -> u64 {
+                match i {
+                    0 => 0,
+                    i if i > CURVE_POINTS => BPS,
+                    i => points[i - 1] as u64,
+                }
+            };
+            let (y0, y1) = (knot(seg), knot(seg + 1));
+            let dx = x - seg as u64 * seg_len;
+            y0 + (y1.saturating_sub(y0)) * dx / seg_len
+        }
+        _ => return (weight_bps * progress) / range,
+    };
+    weight_bps * shaped_bps / BPS
+}
+
+/// Staleness penalty in bps for a snapshot taken at `snapshot_slot`.
+///
+/// Zero within SNAPSHOT_GRACE_SLOTS, then +1 bps every STALE_SLOTS_PER_BPS.
+/// A context that was never updated (snapshot_slot = 0) has no discount to
+/// protect, so it carries no penalty either.
+pub fn staleness_bps(current_slot: u64, snapshot_slot: u64) -> u64 {
+    lag_penalty_bps(current_slot, snapshot_slot, SNAPSHOT_GRACE_SLOTS, STALE_SLOTS_PER_BPS)
+}
+
+/// The lamports a keeper earns for refreshing a snapshot taken at
+/// `snapshot_slot`: `lamports_per_slot` for every slot past
+/// SNAPSHOT_GRACE_SLOTS, the slots the staleness penalty has been charging
+/// for, up to `max_tip`. The first snapshot (`snapshot_slot` = 0) earns
+/// nothing; there was no stale discount to protect.
+pub fn keeper_tip(lamports_per_slot: u64, max_tip: u64, current_slot: u64, snapshot_slot: u64) -> u64 {
+    if snapshot_slot == 0 {
+        return 0;
+    }
+    let stale_slots = current_slot.saturating_sub(snapshot_slot).saturating_sub(SNAPSHOT_GRACE_SLOTS);
+    lamports_per_slot.saturating_mul(stale_slots).min(max_tip)
+}
+
+/// Crank lag penalty in bps given the percolator's `last_crank_slot` as of the
+/// latest snapshot. Unknown (0) carries no penalty.
+pub fn crank_lag_bps(current_slot: u64, last_crank_slot: u64) -> u64 {
+    lag_penalty_bps(current_slot, last_crank_slot, CRANK_GRACE_SLOTS, CRANK_SLOTS_PER_BPS)
+}
+
+/// +1 bps per `slots_per_bps` slots elapsed since `since_slot`, beyond `grace_slots`.
+fn lag_penalty_bps(current_slot: u64, since_slot: u64, grace_slots: u64, slots_per_bps: u64) -> u64 {
+    if since_slot == 0 {
+        return 0;
+    }
+    current_slot
+        .saturating_sub(since_slot)
+        .saturating_sub(grace_slots)
+        / slots_per_bps
+}
+
+/// Liquidation heat penalty in bps: LIQ_PENALTY_BPS per liquidation.
+pub fn liq_heat_bps(heat: u64) -> u64 {
+    heat.saturating_mul(LIQ_PENALTY_BPS) / LIQ_HEAT_SCALE
+}
+
+/// Dutch-auction premium in bps for a fill `auction_slots` or fewer after the
+/// liquidation seen at `last_liq_slot`: `start_bps` in that slot, falling
+/// linearly to zero. Flow right after a liquidation is the most toxic, and
+/// keepers racing for it take a worse price the earlier they come. No
+/// liquidation seen (0), or no auction (0 slots): zero.
+pub fn liq_auction_bps(current_slot: u64, last_liq_slot: u64, start_bps: u64, auction_slots: u64) -> u64 {
+    if last_liq_slot == 0 || auction_slots == 0 {
+        return 0;
+    }
+    let left = auction_slots.saturating_sub(current_slot.saturating_sub(last_liq_slot));
+    (start_bps as u128 * left as u128 / auction_slots as u128) as u64
+}
+
+/// Exponential decay: value * 0.5^(elapsed / halflife).
+///
+/// Whole halflives are exact shifts; the remainder is interpolated linearly
+/// within the halflife. halflife = 0 decays to nothing.
+pub fn decay_halflife(value: u64, elapsed: u64, halflife: u64) -> u64 {
+    if halflife == 0 {
+        return 0;
+    }
+    let halvings = elapsed / halflife;
+    if halvings >= 64 {
+        return 0;
+    }
+    let v = value >> halvings;
+    let rem = elapsed % halflife;
+    v - ((v as u128 * rem as u128) / (2 * halflife as u128)) as u64
+}
+
+/// The inventory the imbalance and skew penalties see: `inventory` halved
+/// every `halflife` slots since the last fill, interpolated like
+/// [`decay_halflife`], toward zero. No halflife, or no fill yet, leaves it
+/// whole.
+pub fn decayed_inventory(inventory: i128, current_slot: u64, last_fill_slot: u64, halflife: u32) -> i128 {
+    if halflife == 0 || last_fill_slot == 0 {
+        return inventory;
+    }
+    let elapsed = current_slot.saturating_sub(last_fill_slot);
+    let halvings = elapsed / halflife as u64;
+    if halvings >= 128 {
+        return 0;
+    }
+    let v = inventory.unsigned_abs() >> halvings;
+    // v * rem / (2 * halflife) without overflowing: rem < halflife < 2^32
+    let (rem, span) = ((elapsed % halflife as u64) as u128, 2 * halflife as u128);
+    let v = v - (v / span * rem + v % span * rem / span);
+    if inventory < 0 {
+        (v as i128).wrapping_neg()
+    } else {
+        v as i128
+    }
+}
+
+/// Signed mid-price skew in bps: -skew_k_bps * inventory / liquidity.
+///
+/// `inventory` is what takers have bought on net, so the LP holds the
+/// opposite. Positive when the LP is long (inventory < 0): both sides quote
+/// lower, so takers buy it back toward flat. Negative when it is short: both
+/// quote higher and takers sell. Clamped to ±cap_bps, and to ±i64::MAX.
+pub fn skew_bps(skew_k_bps: u64, inventory: i128, liquidity_e6: u128, cap_bps: u64) -> i64 {
+    if liquidity_e6 == 0 || skew_k_bps == 0 {
+        return 0;
+    }
+    let magnitude = ((skew_k_bps as u128).saturating_mul(inventory.unsigned_abs()) / liquidity_e6)
+        .min(cap_bps.min(i64::MAX as u64) as u128) as i64;
+    if inventory > 0 { -magnitude } else { magnitude }
+}
+
+/// One-way flow surcharge in bps for a trade of `fill_size`: flow_k_bps times
+/// |long - short| / (long + short) when the trade goes the dominant way, else 0.
+pub fn flow_bps(flow_k_bps: u64, gross_long: u128, gross_short: u128, fill_size: i128) -> u64 {
+    let dominant_long = gross_long > gross_short;
+    if flow_k_bps == 0 || gross_long == gross_short || fill_size == 0 || (fill_size > 0) != dominant_long {
+        return 0;
+    }
+    let one_way = gross_long.abs_diff(gross_short);
+    // Scale both down together so the product can't overflow
+    let shift = (128 - one_way.leading_zeros()).saturating_sub(64);
+    let gross = (gross_long >> shift).saturating_add(gross_short >> shift);
+    ((flow_k_bps as u128 * (one_way >> shift)) / gross.max(1)) as u64
+}
+
+/// External signal surcharge in bps: each `(value_bps, weight_bps)` signal
+/// contributes value * weight / 10000, summed saturating.
+pub fn signal_bps(signals: impl IntoIterator<Item = (u32, u16)>) -> u64 {
+    signals
+        .into_iter()
+        .fold(0u64, |sum, (value, weight)| sum.saturating_add(value as u64 * weight as u64 / BPS))
+}
+
+/// The median of `prices`, which it sorts: the middle one, or the mean of
+/// the middle two (rounded down) for an even count; None when there are none.
+pub fn median_price(prices: &mut [u64]) -> Option<u64> {
+    prices.sort_unstable();
+    let mid = prices.len() / 2;
+    match prices.len() {
+        0 => None,
+        n if n % 2 == 1 => Some(prices[mid]),
+        _ => Some(((prices[mid - 1] as u128 + prices[mid] as u128) / 2) as u64),
+    }
+}
+
+/// How far apart `prices` are, in bps of `median`: (max - min) * 10000 /
+/// median, rounded down; 0 for fewer than two prices or a zero median.
+pub fn divergence_bps(prices: &[u64], median: u64) -> u64 {
+    let (Some(min), Some(max)) = (prices.iter().min(), prices.iter().max()) else {
+        return 0;
+    };
+    if median == 0 {
+        return 0;
+    }
+    ((max - min) as u128 * BPS as u128 / median as u128).min(u64::MAX as u128) as u64
+}
+
+/// The spread multiplier a schedule puts in force at `slot`, in bps: the
+/// largest of the `(start_slot, end_slot, multiplier_bps)` entries whose
+/// range, both ends included, holds it; 0 when none does.
+pub fn schedule_mult_bps(entries: impl IntoIterator<Item = (u64, u64, u32)>, slot: u64) -> u64 {
+    entries
+        .into_iter()
+        .filter(|&(start, end, _)| (start..=end).contains(&slot))
+        .fold(0, |mult, (_, _, mult_bps)| mult.max(mult_bps as u64))
+}
+
+/// The fee a trade of `notional` pays under a table of `(min_notional,
+/// fee_bps)` tiers: the fee of the largest tier it reaches, None below them
+/// all (the base fee applies). A tier with a zero minimum is unused.
+pub fn tier_fee_bps(tiers: impl IntoIterator<Item = (u128, u32)>, notional: u128) -> Option<u32> {
+    tiers
+        .into_iter()
+        .filter(|&(min_notional, _)| min_notional != 0 && notional >= min_notional)
+        .max_by_key(|&(min_notional, _)| min_notional)
+        .map(|(_, fee_bps)| fee_bps)
+}
+
+/// The rebate a fill taking the book from `inventory` to `new_inventory`
+/// earns off `cost_bps`: `rebate_bps` when it shrinks |inventory|, nothing
+/// otherwise. Never more than the cost left after `skew_bps`, so a rebated
+/// price still doesn't cross the oracle.
+pub fn rebate_bps(rebate_bps: u64, inventory: i128, new_inventory: i128, cost_bps: u64, skew_bps: i64) -> u64 {
+    if new_inventory.unsigned_abs() >= inventory.unsigned_abs() {
+        return 0;
+    }
+    rebate_bps.min(cost_bps.saturating_sub(skew_bps.unsigned_abs()))
+}
+
+/// What is left of `fee_bps` once `weight_bps` of it is waived at the full
+/// credibility discount (`discount_bps` = `range_bps`, the tier spread down
+/// at min_spread_bps), and that share of it part way. A context with no
+/// spread range has no discount to follow and keeps the whole fee.
+pub fn scaled_fee_bps(fee_bps: u64, weight_bps: u64, discount_bps: u64, range_bps: u64) -> u64 {
+    if range_bps == 0 {
+        return fee_bps;
+    }
+    let waived = fee_bps as u128 * weight_bps.min(BPS) as u128 * discount_bps.min(range_bps) as u128
+        / (BPS as u128 * range_bps as u128);
+    fee_bps - waived as u64
+}
+
+/// What multiplying `spread_bps` by `mult_bps` / 10000 adds to it, rounded
+/// down; nothing at or below 1x.
+pub fn schedule_bps(spread_bps: u64, mult_bps: u64) -> u64 {
+    let extra = mult_bps.saturating_sub(BPS) as u128;
+    (spread_bps as u128 * extra / BPS as u128).min(u64::MAX as u128) as u64
+}
+
+/// Funding skew in bps: the funding a position opened now would pay or earn
+/// over `horizon_slots`. Positive when longs pay.
+pub fn funding_skew_bps(rate_bps_per_slot: i64, horizon_slots: u64) -> i64 {
+    (rate_bps_per_slot as i128 * horizon_slots as i128).clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+/// Execution price: oracle * (1 ± total_cost_bps / 10000), shifted down by
+/// skew_bps. Saturates at u64::MAX rather than wrapping.
+pub fn exec_price(oracle_price_e6: u64, total_cost_bps: u64, skew_bps: i64, is_buy: bool) -> u64 {
+    let cost = total_cost_bps as i128;
+    let adj_bps = if is_buy {
+        // Buying: oracle + spread
+        BPS as i128 + cost - skew_bps as i128
+    } else {
+        // Selling: oracle - spread
+        BPS as i128 - cost - skew_bps as i128
+    };
+    let numer = (oracle_price_e6 as u128).saturating_mul(adj_bps.max(0) as u128);
+    (numer / (BPS as u128)).min(u64::MAX as u128) as u64
+}
+
+/// Integer square root (floor), Newton's method.
+pub fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> Params {
+        Params {
+            base_fee_bps: 5,
+            min_spread_bps: 10,
+            max_spread_bps: 200,
+            liquidity_e6: 10_000_000_000_000,
+            max_fill: 1_000_000,
+            insurance_weight_bps: 50,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_quote_tiers_and_clip() {
+        // 5% coverage: CRITICAL, max spread, fill capped at 25%
+        let state = State { insurance_snapshot: 5, total_oi_snapshot: 100, ..Default::default() };
+        let q = quote(&params(), &state, 100_000_000, 1_000_000).unwrap();
+        assert_eq!(q.tier, TIER_CRITICAL);
+        assert_eq!(q.spread_bps, 200);
+        assert_eq!(q.fill_size, 250_000);
+        assert_eq!(q.new_inventory, 250_000);
+        assert_eq!(q.exec_price_e6, 100_000_000 * (BPS + 205) / BPS);
+
+        // 300% coverage: FORTIFIED, min spread, sells below the oracle
+        let state = State { insurance_snapshot: 300, total_oi_snapshot: 100, ..Default::default() };
+        let q = quote(&params(), &state, 100_000_000, -1_000).unwrap();
+        assert_eq!(q.tier, TIER_FORTIFIED);
+        assert_eq!(q.spread_bps, 10);
+        assert_eq!(q.exec_price_e6, 100_000_000 * (BPS - 15) / BPS);
+
+        // A zero max spread still quotes the 1 bps floor
+        let flat = Params { min_spread_bps: 0, max_spread_bps: 0, ..params() };
+        assert_eq!(quote(&flat, &state, 100_000_000, 1_000).unwrap().spread_bps, 1);
+    }
+
+    #[test]
+    fn test_skew_favours_the_side_that_flattens_the_book() {
+        let skewed = Params { skew_k_bps: 50, ..params() };
+        for inventory in [5_000_000_000_000, -5_000_000_000_000] {
+            let state = State { inventory, insurance_snapshot: 100, total_oi_snapshot: 100, ..Default::default() };
+            let price = |params: &Params, size: i128| quote(params, &state, 100_000_000, size).unwrap();
+            let (buy, sell) = (price(&skewed, 1_000), price(&skewed, -1_000));
+            let (flat_buy, flat_sell) = (price(&params(), 1_000), price(&params(), -1_000));
+            assert_ne!(buy.skew_bps, 0);
+
+            // Takers get the better price on exactly the side that shrinks
+            // |inventory|, and pay for the side that grows it
+            let (better, worse) = if buy.exec_price_e6 < flat_buy.exec_price_e6 {
+                assert!(sell.exec_price_e6 < flat_sell.exec_price_e6);
+                (buy, sell)
+            } else {
+                assert!(buy.exec_price_e6 > flat_buy.exec_price_e6 && sell.exec_price_e6 > flat_sell.exec_price_e6);
+                (sell, buy)
+            };
+            assert!(better.new_inventory.unsigned_abs() < inventory.unsigned_abs());
+            assert!(worse.new_inventory.unsigned_abs() > inventory.unsigned_abs());
+        }
+    }
+
+    #[test]
+    fn test_quote_errors() {
+        let state = State::default();
+        assert_eq!(quote(&params(), &state, 0, 1), Err(QuoteError::ZeroOraclePrice));
+
+        let twap_params = Params { twap_window_slots: 10, ..params() };
+        assert_eq!(quote(&twap_params, &state, 1, 1), Err(QuoteError::StatsRequired));
+        let anchored = State { twap_price_e6: Some(50_000_000), ..state };
+        assert_eq!(quote(&twap_params, &anchored, 100_000_000, 1).unwrap().anchor_price_e6, 50_000_000);
+        // 70/30: the print keeps 30% of the anchor
+        let blended = Params { spot_weight_bps: 3_000, ..twap_params };
+        assert_eq!(quote(&blended, &anchored, 100_000_000, 1).unwrap().anchor_price_e6, 65_000_000);
+        let untwapped = Params { spot_weight_bps: 3_000, ..params() };
+        assert_eq!(quote(&untwapped, &anchored, 100_000_000, 1).unwrap().anchor_price_e6, 100_000_000);
+
+        let breaker = Params { oracle_jump_bps: 100, oracle_jump_window: 10, ..params() };
+        let last = State { current_slot: 105, last_oracle_price_e6: 100, last_oracle_slot: 100, ..state };
+        assert_eq!(
+            quote(&breaker, &last, 102, 1),
+            Err(QuoteError::OracleJump { last_price_e6: 100, moved_bps: 200, elapsed_slots: 5 })
+        );
+        assert!(quote(&breaker, &State { current_slot: 111, ..last }, 102, 1).is_ok());
+
+        let full = State { inventory: 95, reduce_only: false, ..state };
+        let capped = Params { max_inventory: 100, ..params() };
+        assert_eq!(quote(&capped, &full, 1, 1), Err(QuoteError::ReduceOnly { near_cap: true, new_inventory: 96 }));
+        assert!(quote(&capped, &full, 1, -1).is_ok());
+        let short = State { inventory: i128::MIN, ..state };
+        assert_eq!(quote(&params(), &short, 1, -1), Err(QuoteError::InventoryOverflow { fill_size: -1 }));
+    }
+
+    #[test]
+    fn test_coverage_tier_matches_quote() {
+        for insurance in [0, 9, 10, 24, 25, 99, 100, 199, 200, 1_000] {
+            let state = State { insurance_snapshot: insurance, total_oi_snapshot: 100, ..Default::default() };
+            let q = quote(&params(), &state, 100_000_000, 1).unwrap();
+            assert_eq!(q.coverage_bps, coverage_bps(insurance, 100));
+            assert_eq!(q.tier, coverage_tier(q.coverage_bps), "insurance {insurance}");
+            assert_eq!(q.fill_pct, tier_fill_pct(q.tier));
+        }
+        assert_eq!(coverage_tier(coverage_bps(0, 0)), TIER_NORMAL);
+        assert_eq!(coverage_tier(coverage_bps(1, 0)), TIER_FORTIFIED);
+    }
+
+    #[test]
+    fn test_liq_auction_tightens_linearly() {
+        assert_eq!(liq_auction_bps(1_000, 1_000, 300, 100), 300);
+        assert_eq!(liq_auction_bps(1_025, 1_000, 300, 100), 225);
+        assert_eq!(liq_auction_bps(1_099, 1_000, 300, 100), 3);
+        assert_eq!(liq_auction_bps(1_100, 1_000, 300, 100), 0);
+        assert_eq!(liq_auction_bps(1_000, 0, 300, 100), 0);
+        assert_eq!(liq_auction_bps(1_000, 1_000, 300, 0), 0);
+
+        // Reported with the liquidation heat, and paid on both sides
+        let auction = Params { liq_auction_bps: 100, liq_auction_slots: 50, max_spread_bps: 1_000, ..params() };
+        let state = State { insurance_snapshot: 300, total_oi_snapshot: 100, current_slot: 1_010, last_liq_slot: 1_000, ..Default::default() };
+        for size in [1_000, -1_000] {
+            let q = quote(&auction, &state, 100_000_000, size).unwrap();
+            assert_eq!((q.liq_bps, q.spread_bps), (80, 90));
+        }
+        let later = State { current_slot: 1_050, ..state };
+        assert_eq!(quote(&auction, &later, 100_000_000, 1_000).unwrap().liq_bps, 0);
+    }
+
+    #[test]
+    fn test_signals_widen_the_spread() {
+        assert_eq!(signal_bps([]), 0);
+        assert_eq!(signal_bps([(40, 5_000), (30, 10_000), (7, 0)]), 50);
+        assert_eq!(signal_bps([(u32::MAX, u16::MAX); 3]), 3 * (u32::MAX as u64 * u16::MAX as u64 / BPS));
+
+        let state = State { insurance_snapshot: 300, total_oi_snapshot: 100, ..Default::default() };
+        let base = quote(&params(), &state, 100_000_000, 1).unwrap();
+        let signalled = quote(&params(), &State { signal_bps: 25, ..state }, 100_000_000, 1).unwrap();
+        assert_eq!((signalled.signal_bps, signalled.spread_bps), (25, base.spread_bps + 25));
+        // Clamped with everything else
+        let loud = quote(&params(), &State { signal_bps: u64::MAX, ..state }, 100_000_000, 1).unwrap();
+        assert_eq!(loud.spread_bps, params().max_spread_bps as u64);
+    }
+
+    #[test]
+    fn test_schedule_multiplies_the_spread() {
+        let entries = [(100, 200, 15_000), (150, 150, 30_000), (300, 400, 5_000)];
+        assert_eq!(schedule_mult_bps(entries, 99), 0);
+        assert_eq!((schedule_mult_bps(entries, 100), schedule_mult_bps(entries, 200)), (15_000, 15_000));
+        // Overlapping entries: the widest wins
+        assert_eq!(schedule_mult_bps(entries, 150), 30_000);
+        assert_eq!(schedule_mult_bps(entries, 350), 5_000);
+        assert_eq!(schedule_bps(30, 15_000), 15);
+        assert_eq!((schedule_bps(30, 5_000), schedule_bps(30, 0)), (0, 0));
+        assert_eq!(schedule_bps(u64::MAX, u64::MAX), u64::MAX);
+
+        let state = State { insurance_snapshot: 300, total_oi_snapshot: 100, signal_bps: 10, ..Default::default() };
+        let base = quote(&params(), &state, 100_000_000, 1).unwrap();
+        let doubled = quote(&params(), &State { spread_mult_bps: 20_000, ..state }, 100_000_000, 1).unwrap();
+        // On the signals too
+        assert_eq!((doubled.schedule_bps, doubled.spread_bps), (base.spread_bps, 2 * base.spread_bps));
+        let wild = quote(&params(), &State { spread_mult_bps: u64::MAX, ..state }, 100_000_000, 1).unwrap();
+        assert_eq!(wild.spread_bps, params().max_spread_bps as u64);
+    }
+
+    #[test]
+    fn test_fee_tiers_pick_the_largest_reached() {
+        let tiers = [(1_000_000, 8), (100_000_000, 3), (0, 50), (10_000_000, 5)];
+        assert_eq!(tier_fee_bps(tiers, 999_999), None);
+        assert_eq!((tier_fee_bps(tiers, 1_000_000), tier_fee_bps(tiers, 50_000_000)), (Some(8), Some(5)));
+        assert_eq!(tier_fee_bps(tiers, u128::MAX), Some(3));
+        assert_eq!(tier_fee_bps([(0, 50); 4], u128::MAX), None);
+    }
+
+    #[test]
+    fn test_rebate_pays_inventory_reducing_flow() {
+        // Only a fill that leaves |inventory| smaller, flipping sides included
+        assert_eq!(rebate_bps(4, 1_000, 1_010, 15, 0), 0);
+        assert_eq!((rebate_bps(4, 1_000, 990, 15, 0), rebate_bps(4, 1_000, -500, 15, 0)), (4, 4));
+        assert_eq!((rebate_bps(4, 1_000, -1_000, 15, 0), rebate_bps(4, 0, 0, 15, 0)), (0, 0));
+        // Never past the oracle, skew included
+        assert_eq!((rebate_bps(100, 1_000, 990, 15, 0), rebate_bps(100, 1_000, 990, 15, -10)), (15, 5));
+        assert_eq!(rebate_bps(u64::MAX, i128::MIN, 0, 15, i64::MIN), 0);
+
+        let state = State { insurance_snapshot: 300, total_oi_snapshot: 100, inventory: 1_000, rebate_bps: 4, ..Default::default() };
+        let buy = quote(&params(), &state, 100_000_000, 10).unwrap();
+        assert_eq!((buy.rebate_bps, buy.exec_price_e6), (0, 100_150_000));
+        let sell = quote(&params(), &state, 100_000_000, -10).unwrap();
+        assert_eq!((sell.rebate_bps, sell.fee_bps, sell.exec_price_e6), (4, 5, 99_890_000));
+        let generous = quote(&params(), &State { rebate_bps: 100, ..state }, 100_000_000, -10).unwrap();
+        assert_eq!((generous.rebate_bps, generous.exec_price_e6), (15, 100_000_000));
+    }
+
+    #[test]
+    fn test_inventory_penalties_decay_without_flow() {
+        assert_eq!(decayed_inventory(1_000, 1_500, 1_000, 500), 500);
+        assert_eq!((decayed_inventory(-1_000, 1_250, 1_000, 500), decayed_inventory(-1_000, 1_000, 1_000, 500)), (-750, -1_000));
+        // Off without a halflife or a fill to count from
+        assert_eq!((decayed_inventory(1_000, 9_999, 1_000, 0), decayed_inventory(1_000, 9_999, 0, 500)), (1_000, 1_000));
+        assert_eq!(decayed_inventory(i128::MIN, u64::MAX, 1, 1), 0);
+        assert_eq!(decayed_inventory(i128::MIN, 1, 1, u32::MAX), i128::MIN);
+        assert_eq!(decayed_inventory(i128::MAX, u64::MAX, 1, u32::MAX), 0);
+
+        let params = Params { imbalance_k_bps: 100, skew_k_bps: 50, ..params() };
+        let state = State {
+            current_slot: 1_500,
+            snapshot_slot: 1_500,
+            last_crank_slot: 1_500,
+            insurance_snapshot: 300,
+            total_oi_snapshot: 100,
+            inventory: 2_000_000_000_000,
+            last_oracle_slot: 1_000,
+            ..Default::default()
+        };
+        let full = quote(&params, &state, 100_000_000, -10).unwrap();
+        let decayed = quote(&params, &State { inventory_halflife: 500, ..state }, 100_000_000, -10).unwrap();
+        assert_eq!((full.imbalance_bps, decayed.imbalance_bps), (20, 10));
+        assert_eq!((full.skew_bps, decayed.skew_bps), (-10, -5));
+        // The book still carries every unit
+        assert_eq!(decayed.new_inventory, full.new_inventory);
+        let plain = plain::quote(&params, &State { inventory_halflife: 500, ..state }, 100_000_000, -10).unwrap();
+        assert_eq!(plain.imbalance_bps, 10);
+    }
+
+    #[test]
+    fn test_soft_band_escalates_toward_the_hard_limit() {
+        let band = State { inventory: 500, soft_inventory: 1_000, band_k_bps: 400, ..Default::default() };
+        // Nothing inside the band, a quarter of the way in pays a sixteenth,
+        // the hard limit all of it
+        assert_eq!((band_bps(&band, 2_000, 1_000), band_bps(&band, 2_000, 1_250), band_bps(&band, 2_000, 2_000)), (0, 25, 400));
+        assert_eq!((band_bps(&band, 2_000, -1_500), band_bps(&band, 2_000, 9_999)), (100, 400));
+        // Unwinding pays nothing, nor does anything without a band
+        let deep = State { inventory: 1_800, ..band };
+        assert_eq!((band_bps(&deep, 2_000, 1_700), band_bps(&deep, 2_000, 1_900)), (0, 324));
+        assert_eq!(band_bps(&State { soft_inventory: 0, ..band }, 2_000, 2_000), 0);
+        // Without a hard limit the band is as wide as the soft one
+        assert_eq!(band_bps(&band, 0, 1_500), 100);
+        assert_eq!(band_bps(&State { band_k_bps: u64::MAX, ..band }, 0, i128::MIN), u64::MAX);
+
+        let params = Params { max_inventory: 2_000_000, ..params() };
+        let state = State {
+            insurance_snapshot: 300,
+            total_oi_snapshot: 100,
+            inventory: 1_000_000,
+            soft_inventory: 1_000_000,
+            band_k_bps: 40,
+            ..Default::default()
+        };
+        let deeper = quote(&params, &state, 100_000_000, 500_000).unwrap();
+        let back = quote(&params, &state, 100_000_000, -500_000).unwrap();
+        assert_eq!((deeper.imbalance_bps, back.imbalance_bps), (10, 0));
+        assert_eq!(deeper.spread_bps, back.spread_bps + 10);
+        assert_eq!(quote(&params, &state, 100_000_000, 1_000_001).unwrap().fill_size, 1_000_000);
+    }
+
+    #[test]
+    fn test_side_caps_limit_each_direction() {
+        let params = Params { max_inventory: 1_000, ..params() };
+        let lopsided = State { max_long_inventory: 2_000, max_short_inventory: 100, ..Default::default() };
+        // The LP is short a positive inventory and long a negative one
+        assert_eq!((inventory_cap(&params, &lopsided, -1), inventory_cap(&params, &lopsided, 1)), (2_000, 100));
+        assert_eq!(inventory_cap(&params, &State { max_long_inventory: 0, ..lopsided }, 0), 1_000);
+        assert_eq!(inventory_cap(&Params::default(), &State { max_short_inventory: 0, ..lopsided }, 1), 0);
+
+        // A long book may run past max_inventory as takers sell to it; a
+        // short one stops well short as they buy
+        let long = State { inventory: -1_500, ..lopsided };
+        assert_eq!(quote(&params, &long, 100_000_000, -500).unwrap().new_inventory, -2_000);
+        assert_eq!(quote(&params, &long, 100_000_000, -501).unwrap().fill_size, -500);
+        assert_eq!(quote(&params, &lopsided, 100_000_000, 101).unwrap().fill_size, 100);
+        // Flipping sides lands under the other side's cap
+        assert_eq!(quote(&params, &long, 100_000_000, 1_600).unwrap().new_inventory, 100);
+        assert_eq!(quote(&params, &long, 100_000_000, 1_601).unwrap().new_inventory, 100);
+        // Reduce-only kicks in near the cap of the side the book is on
+        let near = State { inventory: 95, ..lopsided };
+        assert_eq!(quote(&params, &near, 100_000_000, 1), Err(QuoteError::ReduceOnly { near_cap: true, new_inventory: 96 }));
+        assert!(quote(&params, &State { inventory: -950, ..lopsided }, 100_000_000, -1).is_ok());
+    }
+
+    #[test]
+    fn test_reduce_only_threshold_is_configurable() {
+        let params = Params { max_inventory: 1_000, ..params() };
+        let early = State { inventory: 800, reduce_only_bps: 8_000, ..Default::default() };
+        assert_eq!(quote(&params, &early, 100_000_000, 1), Err(QuoteError::ReduceOnly { near_cap: true, new_inventory: 801 }));
+        assert!(quote(&params, &early, 100_000_000, -1).is_ok());
+        assert!(quote(&params, &State { inventory: 799, ..early }, 100_000_000, 1).is_ok());
+        // 0 keeps the default, 10000 only the cap itself
+        assert!(quote(&params, &State { reduce_only_bps: 0, ..early }, 100_000_000, 1).is_ok());
+        let late = State { inventory: 999, reduce_only_bps: 10_000, ..early };
+        assert!(quote(&params, &late, 100_000_000, 1).is_ok());
+        assert_eq!(quote(&params, &late, 100_000_000, 2).unwrap().new_inventory, 1_000);
+    }
+
+    #[test]
+    fn test_depth_inventory_cap_follows_depth_and_price() {
+        // Half the depth's notional: 500 at 100, 1000 at 50
+        assert_eq!(depth_inventory_cap(100_000_000_000, 5_000, 100_000_000), 500_000_000);
+        assert_eq!(depth_inventory_cap(100_000_000_000, 5_000, 50_000_000), 1_000_000_000);
+        assert_eq!(depth_inventory_cap(200_000_000_000, 5_000, 100_000_000), 1_000_000_000);
+        // More than the depth is fine; off without any of the three
+        assert_eq!(depth_inventory_cap(100_000_000_000, 30_000, 100_000_000), 3_000_000_000);
+        assert_eq!(depth_inventory_cap(0, 5_000, 100_000_000), 0);
+        assert_eq!(depth_inventory_cap(100_000_000_000, 0, 100_000_000), 0);
+        assert_eq!(depth_inventory_cap(100_000_000_000, 5_000, 0), 0);
+        // Never rounds down to unlimited, and saturates
+        assert_eq!(depth_inventory_cap(1, 1, u64::MAX), 1);
+        assert_eq!(depth_inventory_cap(u128::MAX, u32::MAX as u64, 1), u128::MAX);
+    }
+
+    #[test]
+    fn test_fill_clipped_to_inventory_headroom() {
+        let params = Params { max_inventory: 1_000, max_fill: 300, ..params() };
+        let state = State { inventory: 900, ..Default::default() };
+        // The tighter of max_fill and the room left under the cap
+        let q = quote(&params, &state, 100_000_000, 500).unwrap();
+        assert_eq!((q.fill_size, q.new_inventory), (100, 1_000));
+        let q = quote(&params, &State { inventory: 500, ..state }, 100_000_000, 500).unwrap();
+        assert_eq!(q.fill_size, 300);
+        // Priced as the clipped fill
+        assert_eq!(q.exec_price_e6, quote(&params, &State { inventory: 500, ..state }, 100_000_000, 300).unwrap().exec_price_e6);
+        // Selling has the whole short side too
+        let q = quote(&Params { max_fill: 0, ..params }, &state, 100_000_000, -5_000).unwrap();
+        assert_eq!(q.new_inventory, -1_000);
+        // No room at all answers reduce-only; uncapped books pass through
+        let full = State { inventory: 1_000, reduce_only_bps: 10_000, ..state };
+        assert!(matches!(quote(&params, &full, 100_000_000, 1), Err(QuoteError::ReduceOnly { near_cap: true, .. })));
+        assert_eq!(clip_headroom(&Params::default(), &state, i128::MAX), i128::MAX);
+        // Only a book already past a lowered cap still hits the limit
+        assert_eq!(quote(&params, &State { inventory: 1_500, ..state }, 100_000_000, -1), Err(QuoteError::InventoryLimit));
+    }
+
+    #[test]
+    fn test_hedge_size_trades_back_to_the_neutral_band() {
+        assert_eq!((hedge_size(900, 1_000), hedge_size(-1_000, 1_000), hedge_size(0, 0)), (0, 0, 0));
+        assert_eq!((hedge_size(1_250, 1_000), hedge_size(-1_250, 1_000)), (250, -250));
+        assert_eq!((hedge_size(1_250, 0), hedge_size(-7, 0)), (1_250, -7));
+        assert_eq!((hedge_size(i128::MIN, 0), hedge_size(i128::MAX, 0)), (-i128::MAX, i128::MAX));
+
+        // The LP's position is -inventory, as the PnL marks it and percolator
+        // books it: the hedge takes it back to within the band, never past flat
+        for (inventory, neutral) in [(1_250, 1_000), (-1_250, 1_000), (7, 0), (-7, 0), (900, 1_000)] {
+            let position: i128 = -inventory;
+            let hedged = position + hedge_size(inventory, neutral);
+            assert!(hedged.unsigned_abs() <= position.unsigned_abs());
+            assert!(hedged.unsigned_abs() <= neutral);
+            assert!(hedged == 0 || hedged.signum() == position.signum());
+        }
+    }
+
+    #[test]
+    fn test_keeper_tip_grows_with_staleness() {
+        assert_eq!(keeper_tip(10, 50_000, 1_000 + SNAPSHOT_GRACE_SLOTS, 1_000), 0);
+        assert_eq!(keeper_tip(10, 50_000, 1_100 + SNAPSHOT_GRACE_SLOTS, 1_000), 1_000);
+        assert_eq!(keeper_tip(10, 50_000, u64::MAX, 1_000), 50_000);
+        assert_eq!(keeper_tip(u64::MAX, u64::MAX, 2_000 + SNAPSHOT_GRACE_SLOTS, 1_000), u64::MAX);
+        // No tip for the first snapshot, or a clock behind the last one
+        assert_eq!((keeper_tip(10, 50_000, 1_000_000, 0), keeper_tip(10, 50_000, 5, 1_000)), (0, 0));
+    }
+
+    #[test]
+    fn test_fee_discount_follows_the_spread_discount() {
+        assert_eq!(scaled_fee_bps(10, 5_000, 150, 150), 5);
+        assert_eq!((scaled_fee_bps(10, 5_000, 75, 150), scaled_fee_bps(10, 5_000, 0, 150)), (8, 10));
+        assert_eq!((scaled_fee_bps(10, u64::MAX, u64::MAX, 150), scaled_fee_bps(10, 5_000, 150, 0)), (0, 10));
+
+        // FORTIFIED earns the whole discount, CRITICAL none of it
+        let strong = State { insurance_snapshot: 300, total_oi_snapshot: 100, fee_discount_bps: 4_000, ..Default::default() };
+        let q = quote(&params(), &strong, 100_000_000, 10).unwrap();
+        assert_eq!((q.fee_bps, q.exec_price_e6), (3, 100_130_000));
+        let weak = State { insurance_snapshot: 5, ..strong };
+        assert_eq!(quote(&params(), &weak, 100_000_000, 10).unwrap().fee_bps, 5);
+        let off = State { fee_discount_bps: 0, ..strong };
+        assert_eq!(quote(&params(), &off, 100_000_000, 10).unwrap().fee_bps, 5);
+    }
+
+    #[test]
+    fn test_oracle_divergence_widens_the_spread() {
+        assert_eq!(median_price(&mut []), None);
+        assert_eq!(median_price(&mut [7]), Some(7));
+        assert_eq!(median_price(&mut [101, 99]), Some(100));
+        assert_eq!(median_price(&mut [u64::MAX, u64::MAX - 1]), Some(u64::MAX - 1));
+        // One print far off can't move it
+        assert_eq!(median_price(&mut [100, 1_000_000, 101]), Some(101));
+        assert_eq!(divergence_bps(&[100_000_000], 100_000_000), 0);
+        assert_eq!(divergence_bps(&[99_000_000, 100_000_000, 101_500_000], 100_000_000), 250);
+        assert_eq!(divergence_bps(&[0, u64::MAX], 1), u64::MAX);
+        assert_eq!(divergence_bps(&[1, 2], 0), 0);
+
+        let state = State { insurance_snapshot: 300, total_oi_snapshot: 100, ..Default::default() };
+        let base = quote(&params(), &state, 100_000_000, 1).unwrap();
+        let split = quote(&params(), &State { oracle_bps: 25, ..state }, 100_000_000, 1).unwrap();
+        assert_eq!((split.oracle_bps, split.spread_bps), (25, base.spread_bps + 25));
+        let wild = quote(&params(), &State { oracle_bps: u64::MAX, ..state }, 100_000_000, 1).unwrap();
+        assert_eq!(wild.spread_bps, params().max_spread_bps as u64);
+    }
+
+    #[test]
+    fn test_twap_holds_each_sample_until_the_next() {
+        assert_eq!(twap([], 100, 1_000, 7), 7);
+        // 300 since slot 970, 100 before it
+        let samples = [(970, 300), (900, 100)];
+        assert_eq!(twap(samples, 80, 1_000, 7), (100 * 50 + 300 * 30) / 80);
+        assert_eq!(twap(samples, 10, 1_000, 7), 300);
+    }
+
+    #[test]
+    fn test_helpers_saturate_at_the_edges() {
+        // A cap beyond i64 can't wrap the skew negative
+        assert_eq!(skew_bps(u64::MAX, -1, 1, u64::MAX), i64::MAX);
+        assert_eq!(skew_bps(u64::MAX, 1, 1, u64::MAX), -i64::MAX);
+        // Nor can a cost beyond any spread wrap the price
+        assert_eq!(exec_price(u64::MAX, u64::MAX, 0, true), u64::MAX);
+        assert_eq!(exec_price(u64::MAX, u64::MAX, i64::MIN, true), u64::MAX);
+        assert_eq!(exec_price(u64::MAX, u64::MAX, 0, false), 0);
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// Any parameter block at all, valid or not.
+        fn any_params() -> impl Strategy<Value = Params> {
+            (
+                (any::<u32>(), any::<u32>(), any::<u32>(), any::<u32>(), any::<u128>(), any::<u128>(), any::<u128>()),
+                (any::<u32>(), any::<u8>(), any::<u32>(), any::<u32>(), any::<u8>(), any::<[u16; CURVE_POINTS]>()),
+                (any::<u32>(), any::<u32>(), any::<u16>(), any::<u32>(), any::<u32>(), any::<u32>()),
+                (any::<u32>(), any::<[u16; SIGNALS]>(), any::<u32>(), any::<u16>(), any::<u32>(), any::<u32>(), any::<u16>()),
+            )
+                .prop_map(|(a, b, c, d)| Params {
+                    base_fee_bps: a.0,
+                    min_spread_bps: a.1,
+                    max_spread_bps: a.2,
+                    imbalance_k_bps: a.3,
+                    liquidity_e6: a.4,
+                    max_fill: a.5,
+                    max_inventory: a.6,
+                    insurance_weight_bps: b.0,
+                    impact_mode: b.1,
+                    impact_k_bps: b.2,
+                    skew_k_bps: b.3,
+                    coverage_curve: b.4,
+                    curve_points: b.5,
+                    liq_halflife: c.0,
+                    funding_horizon: c.1,
+                    oracle_jump_bps: c.2,
+                    oracle_jump_window: c.3,
+                    flow_k_bps: c.4,
+                    twap_window_slots: c.5,
+                    age_halflife: d.0,
+                    signal_weights: d.1,
+                    deficit_halflife: d.2,
+                    liq_auction_bps: d.3,
+                    liq_auction_slots: d.4,
+                    peg_band_e6: d.5,
+                    spot_weight_bps: d.6,
+                })
+        }
+
+        /// Any state at all.
+        fn any_state() -> impl Strategy<Value = State> {
+            (
+                (any::<u64>(), any::<i128>(), any::<u128>(), any::<u128>(), any::<u64>(), any::<u64>(), any::<u64>()),
+                (any::<i64>(), any::<u64>(), any::<u64>(), any::<u128>(), any::<u128>(), any::<bool>(), any::<Option<u64>>()),
+                (any::<u64>(), any::<u64>(), any::<u64>(), any::<u64>(), any::<u64>(), any::<u64>(), any::<u64>(), any::<u64>()),
+                (any::<u32>(), any::<u128>(), any::<u64>(), any::<u128>(), any::<u128>(), any::<u64>()),
+            )
+                .prop_map(|(a, b, c, d)| State {
+                    current_slot: a.0,
+                    inventory: a.1,
+                    insurance_snapshot: a.2,
+                    total_oi_snapshot: a.3,
+                    snapshot_slot: a.4,
+                    last_crank_slot: a.5,
+                    liq_heat: a.6,
+                    funding_rate_bps_per_slot: b.0,
+                    last_oracle_price_e6: b.1,
+                    last_oracle_slot: b.2,
+                    gross_long: b.3,
+                    gross_short: b.4,
+                    reduce_only: b.5,
+                    twap_price_e6: b.6,
+                    market_age: c.0,
+                    last_deficit_slot: c.1,
+                    last_liq_slot: c.2,
+                    signal_bps: c.3,
+                    spread_mult_bps: c.4,
+                    oracle_bps: c.5,
+                    rebate_bps: c.6,
+                    fee_discount_bps: c.7,
+                    inventory_halflife: d.0,
+                    soft_inventory: d.1,
+                    band_k_bps: d.2,
+                    max_long_inventory: d.3,
+                    max_short_inventory: d.4,
+                    reduce_only_bps: d.5,
+                })
+        }
+
+        /// A bps setting, with the ends of its range drawn often.
+        fn bps() -> impl Strategy<Value = u32> {
+            prop_oneof![Just(0), Just(BPS as u32), 0..=BPS as u32]
+        }
+
+        /// Parameters the program would accept at Init, quoting around the
+        /// oracle (no TWAP) with the breaker off.
+        fn valid_params() -> impl Strategy<Value = Params> {
+            (
+                (bps(), bps(), bps(), bps(), bps()),
+                (1..=u64::MAX as u128, 0..=u64::MAX as u128, 0..=IMPACT_SQRT, bps(), bps()),
+                (0..=CURVE_PIECEWISE, any::<[u16; CURVE_POINTS]>(), any::<u32>(), 0..=1_000u32, bps()),
+            )
+                .prop_map(|(a, b, c)| {
+                    let mut curve_points = c.1.map(|p| p % (BPS as u16 + 1));
+                    curve_points.sort_unstable();
+                    Params {
+                        base_fee_bps: a.0,
+                        min_spread_bps: a.1.min(a.2),
+                        max_spread_bps: a.1.max(a.2),
+                        imbalance_k_bps: a.3,
+                        insurance_weight_bps: a.4,
+                        liquidity_e6: b.0,
+                        max_fill: b.1,
+                        impact_mode: b.2,
+                        impact_k_bps: b.3,
+                        skew_k_bps: b.4,
+                        coverage_curve: c.0,
+                        curve_points,
+                        liq_halflife: c.2,
+                        funding_horizon: c.3,
+                        flow_k_bps: c.4,
+                        ..Default::default()
+                    }
+                })
+        }
+
+        /// A live market's state: bounded book, recent snapshot and crank.
+        fn live_state() -> impl Strategy<Value = State> {
+            (
+                (1_000_000..2_000_000u64, -(1i128 << 64)..(1i128 << 64), 0..(1u128 << 64), 0..(1u128 << 64)),
+                (0..1_000_000u64, 0..1_000_000u64, 0..100_000u64, -1_000..1_000i64, 0..(1u128 << 64), 0..(1u128 << 64)),
+            )
+                .prop_map(|(a, b)| State {
+                    current_slot: a.0,
+                    inventory: a.1,
+                    insurance_snapshot: a.2,
+                    total_oi_snapshot: a.3,
+                    snapshot_slot: a.0 - b.0,
+                    last_crank_slot: a.0 - b.1,
+                    liq_heat: b.2,
+                    funding_rate_bps_per_slot: b.3,
+                    gross_long: b.4,
+                    gross_short: b.5,
+                    ..Default::default()
+                })
+        }
+
+        proptest! {
+            #[test]
+            fn prop_quote_never_panics(
+                params in any_params(),
+                state in any_state(),
+                oracle in any::<u64>(),
+                size in any::<i128>(),
+            ) {
+                let _ = quote(&params, &state, oracle, size);
+            }
+
+            #[test]
+            fn prop_spread_and_skew_in_bounds(
+                params in valid_params(),
+                state in live_state(),
+                oracle in 1..=u64::MAX,
+                size in any::<i128>(),
+            ) {
+                if let Ok(q) = quote(&params, &state, oracle, size) {
+                    prop_assert!(q.spread_bps >= 1);
+                    prop_assert!(q.spread_bps <= (params.max_spread_bps as u64).max(1));
+                    prop_assert!(q.skew_bps.unsigned_abs() <= q.spread_bps);
+                    prop_assert!(q.fill_size.unsigned_abs() <= size.unsigned_abs());
+                    prop_assert_eq!(q.fill_size.signum(), size.signum());
+                }
+            }
+
+            #[test]
+            fn prop_exec_never_crosses_oracle(
+                params in valid_params(),
+                state in live_state(),
+                oracle in 1..=u64::MAX,
+                size in any::<i128>(),
+            ) {
+                if let Ok(q) = quote(&params, &state, oracle, size) {
+                    if q.fill_size > 0 {
+                        prop_assert!(q.exec_price_e6 >= oracle, "buy at {} under {}", q.exec_price_e6, oracle);
+                    } else {
+                        prop_assert!(q.exec_price_e6 <= oracle, "sell at {} over {}", q.exec_price_e6, oracle);
+                    }
+                }
+            }
+
+            #[test]
+            fn prop_more_coverage_never_costs_more(
+                params in valid_params(),
+                state in live_state(),
+                oracle in 1..=u64::MAX,
+                size in any::<i128>(),
+                insurance in (0..(1u128 << 64), 0..(1u128 << 64)),
+            ) {
+                let (low, high) = (insurance.0.min(insurance.1), insurance.0.max(insurance.1));
+                let at = |insurance_snapshot| quote(&params, &State { insurance_snapshot, ..state }, oracle, size);
+                if let (Ok(a), Ok(b)) = (at(low), at(high)) {
+                    prop_assert!(b.coverage_bps >= a.coverage_bps);
+                    prop_assert!(b.tier >= a.tier);
+                    prop_assert!(b.fill_pct >= a.fill_pct);
+                    prop_assert!(b.tier_spread_bps <= a.tier_spread_bps);
+                    // A bigger fill can carry more impact; compare like for like
+                    if a.fill_size == b.fill_size {
+                        prop_assert!(b.spread_bps <= a.spread_bps);
+                        if a.fill_size > 0 {
+                            prop_assert!(b.exec_price_e6 <= a.exec_price_e6);
+                        } else {
+                            prop_assert!(b.exec_price_e6 >= a.exec_price_e6);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// =============================================================================
+// Bounded model checking (Kani)
+//
+//   cargo kani -p provenance-core
+//
+// Proofs over every input, where the property tests above only sample. Kani
+// also fails a proof on any arithmetic overflow or panic along the way.
+// =============================================================================
+#[cfg(kani)]
+mod verification {
+    use super::*;
+
+    fn any_params() -> Params {
+        Params {
+            base_fee_bps: kani::any(),
+            min_spread_bps: kani::any(),
+            max_spread_bps: kani::any(),
+            imbalance_k_bps: kani::any(),
+            liquidity_e6: kani::any(),
+            max_fill: kani::any(),
+            max_inventory: kani::any(),
+            insurance_weight_bps: kani::any(),
+            impact_mode: kani::any(),
+            impact_k_bps: kani::any(),
+            skew_k_bps: kani::any(),
+            coverage_curve: kani::any(),
+            curve_points: kani::any(),
+            liq_halflife: kani::any(),
+            funding_horizon: kani::any(),
+            oracle_jump_bps: kani::any(),
+            oracle_jump_window: kani::any(),
+            flow_k_bps: kani::any(),
+            twap_window_slots: kani::any(),
+            age_halflife: kani::any(),
+            signal_weights: kani::any(),
+            deficit_halflife: kani::any(),
+            liq_auction_bps: kani::any(),
+            liq_auction_slots: kani::any(),
+            peg_band_e6: kani::any(),
+            spot_weight_bps: kani::any(),
+        }
+    }
+
+    fn any_state() -> State {
+        State {
+            current_slot: kani::any(),
+            inventory: kani::any(),
+            insurance_snapshot: kani::any(),
+            total_oi_snapshot: kani::any(),
+            snapshot_slot: kani::any(),
+            last_crank_slot: kani::any(),
+            liq_heat: kani::any(),
+            funding_rate_bps_per_slot: kani::any(),
+            last_oracle_price_e6: kani::any(),
+            last_oracle_slot: kani::any(),
+            gross_long: kani::any(),
+            gross_short: kani::any(),
+            reduce_only: kani::any(),
+            twap_price_e6: kani::any(),
+            market_age: kani::any(),
+            last_deficit_slot: kani::any(),
+            last_liq_slot: kani::any(),
+            signal_bps: kani::any(),
+            spread_mult_bps: kani::any(),
+            oracle_bps: kani::any(),
+            rebate_bps: kani::any(),
+            fee_discount_bps: kani::any(),
+            inventory_halflife: kani::any(),
+            soft_inventory: kani::any(),
+            band_k_bps: kani::any(),
+            max_long_inventory: kani::any(),
+            max_short_inventory: kani::any(),
+            reduce_only_bps: kani::any(),
+        }
+    }
+
+    /// Every quote's spread is in [1, max(max_spread_bps, 1)], its skew inside
+    /// the spread, and both survive the i64 casts unchanged. The square-root
+    /// shapes are left out: isqrt's Newton loop has no bound the solver can
+    /// unroll, and it only sizes a penalty or discount that the final clamp
+    /// bounds either way.
+    #[kani::proof]
+    fn quote_spread_in_bounds() {
+        let params = any_params();
+        kani::assume(params.impact_mode != IMPACT_SQRT && params.coverage_curve != CURVE_SQRT);
+        let state = any_state();
+        if let Ok(q) = quote(&params, &state, kani::any(), kani::any()) {
+            assert!(q.spread_bps >= 1);
+            assert!(q.spread_bps <= (params.max_spread_bps as u64).max(1));
+            assert!(q.spread_bps <= i64::MAX as u64);
+            assert!(q.skew_bps.unsigned_abs() <= q.spread_bps);
+        }
+    }
+
+    /// exec_price is oracle * (10000 ± cost - skew) / 10000 exactly whenever
+    /// that fits in a u64, u64::MAX above it and 0 below zero: it never wraps
+    /// into a wrong price. A skew within the cost never crosses the oracle.
+    #[kani::proof]
+    fn exec_price_never_wraps() {
+        let (oracle, cost, skew, is_buy): (u64, u64, i64, bool) = (kani::any(), kani::any(), kani::any(), kani::any());
+        let price = exec_price(oracle, cost, skew, is_buy);
+
+        let adj = BPS as i128 + if is_buy { cost as i128 } else { -(cost as i128) } - skew as i128;
+        let expected = match (oracle as u128).checked_mul(adj.max(0) as u128) {
+            Some(numer) => (numer / BPS as u128).min(u64::MAX as u128) as u64,
+            None => u64::MAX,
+        };
+        assert_eq!(price, expected);
+        if skew.unsigned_abs() <= cost {
+            assert!(if is_buy { price >= oracle } else { price <= oracle });
+        }
+    }
+
+    /// skew_bps is within ±cap_bps for any cap, on the inventory's side.
+    #[kani::proof]
+    fn skew_within_cap() {
+        let (k, inventory, liquidity, cap): (u64, i128, u128, u64) = (kani::any(), kani::any(), kani::any(), kani::any());
+        let skew = skew_bps(k, inventory, liquidity, cap);
+        assert!(skew.unsigned_abs() <= cap);
+        assert!(skew == 0 || (skew > 0) == (inventory < 0));
+    }
+}
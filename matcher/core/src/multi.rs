@@ -0,0 +1,236 @@
+//! The multi-signal credibility matcher (kind 3).
+//!
+//! The credibility kind reads one piece of evidence, the insurance ratio. This
+//! one scores four, each from 0 (no credibility) to 10000 (full):
+//!
+//! - coverage: the insurance ratio, full at 100%
+//! - age: how long the market has run with its admin burned, half credit at
+//!   age_halflife
+//! - deficit: how long since a snapshot last found the insurance fund drawn
+//!   down, half credit deficit_halflife after it; full if none ever has
+//! - liquidations: liquidation heat decayed to now, less LIQ_SIGNAL_BPS per
+//!   liquidation
+//!
+//! and blends them by `signal_weights`. The blend takes the tier spread from
+//! max_spread_bps (score 0) down to min_spread_bps (score 10000). The fill
+//! multiplier still follows the coverage tier, so the size the LP takes on at
+//! once stays tied to what the fund could absorb, and every per-trade term is
+//! [`crate::quote`]'s.
+
+use crate::{
+    anchor_price, coverage_bps, coverage_tier, decay_halflife, price_standing, tier_fill_pct, Params, Quote,
+    QuoteError, Standing, State, BPS, LIQ_HEAT_SCALE, SIGNALS, SIGNAL_AGE, SIGNAL_COVERAGE, SIGNAL_DEFICIT,
+    SIGNAL_LIQUIDATIONS,
+};
+
+/// Each (undecayed) liquidation takes this much off the liquidations signal;
+/// 20 in a burst leave it at zero.
+pub const LIQ_SIGNAL_BPS: u64 = 500;
+
+/// Quote a trade on a multi-signal context. The breakdown reads as the
+/// credibility kind's: coverage and tier are the insurance ratio's, and
+/// discount_bps is what the blended score took off max_spread_bps.
+pub fn quote(params: &Params, state: &State, oracle_price_e6: u64, trade_size: i128) -> Result<Quote, QuoteError> {
+    let anchor_price_e6 = anchor_price(params, state, oracle_price_e6)?;
+    let min_spread_bps = params.min_spread_bps as u64;
+    let max_spread_bps = params.max_spread_bps as u64;
+
+    let coverage_bps = coverage_bps(state.insurance_snapshot, state.total_oi_snapshot);
+    let tier = coverage_tier(coverage_bps);
+    let score = blend_bps(&params.signal_weights, &signal_scores(params, state));
+    let spread_bps = max_spread_bps - max_spread_bps.saturating_sub(min_spread_bps) * score / BPS;
+
+    let standing = Standing { coverage_bps, tier, fill_pct: tier_fill_pct(tier), spread_bps };
+    price_standing(params, state, anchor_price_e6, trade_size, standing)
+}
+
+/// Every signal's score in bps, by SIGNAL_* index.
+pub fn signal_scores(params: &Params, state: &State) -> [u64; SIGNALS] {
+    let mut scores = [0; SIGNALS];
+    scores[SIGNAL_COVERAGE] = coverage_bps(state.insurance_snapshot, state.total_oi_snapshot).min(BPS);
+    scores[SIGNAL_AGE] = recovered_bps(state.market_age, params.age_halflife as u64);
+    scores[SIGNAL_DEFICIT] = match state.last_deficit_slot {
+        0 => BPS,
+        slot => recovered_bps(state.current_slot.saturating_sub(slot), params.deficit_halflife as u64),
+    };
+    let heat_now = decay_halflife(
+        state.liq_heat,
+        state.current_slot.saturating_sub(state.snapshot_slot),
+        params.liq_halflife as u64,
+    );
+    scores[SIGNAL_LIQUIDATIONS] = BPS.saturating_sub(heat_now.saturating_mul(LIQ_SIGNAL_BPS) / LIQ_HEAT_SCALE);
+    scores
+}
+
+/// The weighted mean of `scores`. Weights are relative; all zero scores 0.
+pub fn blend_bps(weights: &[u16; SIGNALS], scores: &[u64; SIGNALS]) -> u64 {
+    let total: u64 = weights.iter().map(|&w| w as u64).sum();
+    if total == 0 {
+        return 0;
+    }
+    let weighted: u128 = weights.iter().zip(scores).map(|(&w, &s)| w as u128 * s.min(BPS) as u128).sum();
+    (weighted / total as u128) as u64
+}
+
+/// Credit for `elapsed` slots of good behavior: none at first, half after one
+/// halflife, approaching full. No halflife earns none.
+fn recovered_bps(elapsed: u64, halflife: u64) -> u64 {
+    if halflife == 0 {
+        return 0;
+    }
+    BPS - decay_halflife(BPS, elapsed, halflife)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TIER_CRITICAL, TIER_NORMAL};
+
+    const ALL_COVERAGE: [u16; SIGNALS] = [BPS as u16, 0, 0, 0];
+
+    fn params() -> Params {
+        Params {
+            base_fee_bps: 5,
+            min_spread_bps: 10,
+            max_spread_bps: 210,
+            max_fill: 1_000,
+            age_halflife: 1_000,
+            deficit_halflife: 100,
+            liq_halflife: 100,
+            signal_weights: [4_000, 2_000, 2_000, 2_000],
+            ..Params::default()
+        }
+    }
+
+    /// A seasoned market at 50% coverage: no deficit, no liquidations, aged
+    /// well past its halflife.
+    fn state() -> State {
+        State {
+            current_slot: 1_000_000,
+            snapshot_slot: 1_000_000,
+            insurance_snapshot: 500,
+            total_oi_snapshot: 1_000,
+            market_age: 64_000,
+            ..State::default()
+        }
+    }
+
+    #[test]
+    fn test_signal_scores() {
+        assert_eq!(signal_scores(&params(), &state()), [5_000, BPS, BPS, BPS]);
+
+        let young = State { market_age: 1_000, insurance_snapshot: 3_000, ..state() };
+        assert_eq!(signal_scores(&params(), &young)[..2], [BPS, 5_000]);
+        // No halflife, no credit for age
+        assert_eq!(signal_scores(&Params { age_halflife: 0, ..params() }, &state())[SIGNAL_AGE], 0);
+
+        let deficit = |since: u64| signal_scores(&params(), &State { last_deficit_slot: 1_000_000 - since, ..state() });
+        assert_eq!(deficit(0)[SIGNAL_DEFICIT], 0);
+        assert_eq!(deficit(100)[SIGNAL_DEFICIT], 5_000);
+        assert_eq!(deficit(200)[SIGNAL_DEFICIT], 7_500);
+
+        // Four liquidations at the snapshot, one halflife ago
+        let hot = State { liq_heat: 4 * LIQ_HEAT_SCALE, snapshot_slot: 999_900, ..state() };
+        assert_eq!(signal_scores(&params(), &hot)[SIGNAL_LIQUIDATIONS], BPS - 2 * LIQ_SIGNAL_BPS);
+        let burst = State { liq_heat: 100 * LIQ_HEAT_SCALE, ..state() };
+        assert_eq!(signal_scores(&params(), &burst)[SIGNAL_LIQUIDATIONS], 0);
+    }
+
+    #[test]
+    fn test_blend_is_the_weighted_mean() {
+        let scores = [5_000, BPS, 0, BPS];
+        assert_eq!(blend_bps(&[4_000, 2_000, 2_000, 2_000], &scores), 6_000);
+        // Weights are relative
+        assert_eq!(blend_bps(&[2, 1, 1, 1], &scores), 6_000);
+        assert_eq!(blend_bps(&ALL_COVERAGE, &scores), 5_000);
+        assert_eq!(blend_bps(&[0; SIGNALS], &scores), 0);
+        assert_eq!(blend_bps(&[u16::MAX; SIGNALS], &[u64::MAX; SIGNALS]), BPS);
+    }
+
+    #[test]
+    fn test_quote_spreads_by_the_blend() {
+        // Score 8000 (coverage 5000 at 40%, the rest full): 20% of the way from min to max
+        let q = quote(&params(), &state(), 100_000_000, 5_000).unwrap();
+        assert_eq!((q.coverage_bps, q.tier, q.fill_pct), (5_000, TIER_NORMAL, 100));
+        assert_eq!((q.tier_spread_bps, q.discount_bps, q.spread_bps), (50, 160, 50));
+        assert_eq!(q.fill_size, 1_000);
+        assert_eq!(q.exec_price_e6, 100_000_000 * (BPS + 55) / BPS);
+
+        // A fresh deficit costs its 20% of the spread range
+        let hit = State { last_deficit_slot: 1_000_000, ..state() };
+        assert_eq!(quote(&params(), &hit, 100_000_000, 5_000).unwrap().spread_bps, 90);
+
+        // The fill follows the coverage tier however good the other signals are
+        let thin = State { insurance_snapshot: 50, ..state() };
+        let q = quote(&params(), &thin, 100_000_000, 5_000).unwrap();
+        assert_eq!((q.tier, q.fill_size), (TIER_CRITICAL, 250));
+        assert_eq!(q.spread_bps, 210 - 200 * 6_200 / BPS);
+
+        // No weights, no credit
+        let unweighted = Params { signal_weights: [0; SIGNALS], ..params() };
+        assert_eq!(quote(&unweighted, &state(), 100_000_000, 5_000).unwrap().spread_bps, 210);
+    }
+
+    #[test]
+    fn test_shares_the_credibility_terms() {
+        // Weighted to coverage alone, full coverage quotes min spread like
+        // the credibility kind's STRONG tier, and the per-trade terms match
+        let p = Params {
+            signal_weights: ALL_COVERAGE,
+            imbalance_k_bps: 100,
+            liquidity_e6: 1_000_000,
+            skew_k_bps: 50,
+            ..params()
+        };
+        let s = State { insurance_snapshot: 1_500, inventory: 100_000, last_crank_slot: 990_000, ..state() };
+        let multi = quote(&p, &s, 100_000_000, -700).unwrap();
+        let credibility = crate::quote(&p, &s, 100_000_000, -700).unwrap();
+        assert_eq!(multi.tier_spread_bps, 10);
+        assert_eq!(multi, Quote { discount_bps: 200, ..credibility });
+
+        assert_eq!(quote(&p, &s, 0, 1), Err(QuoteError::ZeroOraclePrice));
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        proptest! {
+            #[test]
+            fn prop_tier_spread_between_min_and_max(
+                spreads in (0..=BPS as u32, 0..=BPS as u32),
+                weights in any::<[u16; SIGNALS]>(),
+                halflives in any::<(u32, u32, u32)>(),
+                state in (any::<u64>(), any::<u128>(), any::<u128>(), any::<u64>(), any::<u64>(), any::<u64>(), any::<u64>()),
+                size in any::<i128>(),
+            ) {
+                let params = Params {
+                    min_spread_bps: spreads.0.min(spreads.1),
+                    max_spread_bps: spreads.0.max(spreads.1),
+                    signal_weights: weights,
+                    age_halflife: halflives.0,
+                    deficit_halflife: halflives.1,
+                    liq_halflife: halflives.2,
+                    ..Params::default()
+                };
+                let state = State {
+                    current_slot: state.0,
+                    insurance_snapshot: state.1,
+                    total_oi_snapshot: state.2,
+                    snapshot_slot: state.3,
+                    liq_heat: state.4,
+                    market_age: state.5,
+                    last_deficit_slot: state.6,
+                    ..State::default()
+                };
+                for score in signal_scores(&params, &state) {
+                    prop_assert!(score <= BPS);
+                }
+                let q = quote(&params, &state, 100_000_000, size).unwrap();
+                prop_assert!(q.tier_spread_bps >= params.min_spread_bps as u64);
+                prop_assert!(q.tier_spread_bps <= params.max_spread_bps as u64);
+                prop_assert_eq!(q.tier_spread_bps + q.discount_bps, params.max_spread_bps as u64);
+            }
+        }
+    }
+}
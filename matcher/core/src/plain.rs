@@ -0,0 +1,132 @@
+//! The plain matcher (kind 1): imbalance-only pricing.
+//!
+//! The vanilla market maker the credibility matcher grew out of. The spread is
+//! min_spread_bps plus the inventory imbalance penalty and soft band, clamped like any other
+//! quote, and the fill is clipped to max_fill with no tier multiplier. Nothing
+//! the slab reports moves it: no coverage tier, staleness, crank lag,
+//! liquidation heat or funding skew, and the size, skew and flow terms are
+//! off; only the oracle feeds' uncertainty widens it, the LP's own spread
+//! schedule multiplies it and its rebate applies. The oracle checks and the inventory limits are the
+//! same as [`crate::quote`]'s, so both kinds can run side by side on the same
+//! context layout and be compared trade for trade.
+
+use crate::{
+    anchor_price, band_bps, book_fill, clip_fill, clip_headroom, decayed_inventory, exec_price, imbalance_bps, inventory_cap, rebate_bps, schedule_bps, Params,
+    Quote, QuoteError, State, TIER_NORMAL,
+};
+
+/// Quote a trade on a plain context. The breakdown has the credibility
+/// quote's shape: the tier reads NORMAL at full fill, coverage and the
+/// credibility penalties read zero, and tier_spread_bps is min_spread_bps.
+pub fn quote(params: &Params, state: &State, oracle_price_e6: u64, trade_size: i128) -> Result<Quote, QuoteError> {
+    let anchor_price_e6 = anchor_price(params, state, oracle_price_e6)?;
+    let min_spread_bps = params.min_spread_bps as u64;
+    let max_spread_bps = params.max_spread_bps as u64;
+
+    let fill_pct = 100;
+    let fill_size = clip_headroom(params, state, clip_fill(trade_size, params.max_fill, fill_pct));
+    let inventory = decayed_inventory(state.inventory, state.current_slot, state.last_oracle_slot, state.inventory_halflife);
+    let filled_inventory = state.inventory.saturating_add(fill_size);
+    let imbalance = imbalance_bps(params.imbalance_k_bps as u64, inventory, params.liquidity_e6)
+        .saturating_add(band_bps(state, inventory_cap(params, state, filled_inventory), filled_inventory));
+    let spread_bps = min_spread_bps.saturating_add(imbalance).saturating_add(state.oracle_bps);
+    let schedule = schedule_bps(spread_bps, state.spread_mult_bps);
+    let spread_bps = spread_bps.saturating_add(schedule).clamp(1, max_spread_bps.max(1));
+    let new_inventory = book_fill(params, state, fill_size)?;
+
+    let fee_bps = params.base_fee_bps as u64;
+    let rebate_bps = rebate_bps(state.rebate_bps, state.inventory, new_inventory, spread_bps + fee_bps, 0);
+    Ok(Quote {
+        discount_bps: max_spread_bps.saturating_sub(min_spread_bps),
+        tier: TIER_NORMAL,
+        fill_pct,
+        tier_spread_bps: min_spread_bps,
+        imbalance_bps: imbalance,
+        schedule_bps: schedule,
+        oracle_bps: state.oracle_bps,
+        spread_bps,
+        fee_bps,
+        rebate_bps,
+        anchor_price_e6,
+        exec_price_e6: exec_price(anchor_price_e6, spread_bps + fee_bps - rebate_bps, 0, fill_size > 0),
+        fill_size,
+        new_inventory,
+        ..Quote::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> Params {
+        Params {
+            base_fee_bps: 5,
+            min_spread_bps: 10,
+            max_spread_bps: 200,
+            imbalance_k_bps: 100,
+            liquidity_e6: 1_000_000,
+            max_fill: 1_000,
+            insurance_weight_bps: 50,
+            impact_mode: crate::IMPACT_LINEAR,
+            impact_k_bps: 30,
+            skew_k_bps: 50,
+            ..Params::default()
+        }
+    }
+
+    #[test]
+    fn test_prices_imbalance_only() {
+        // No insurance, never cranked: the credibility quote is at max spread
+        // with a quarter fill, the plain one doesn't care
+        let state = State {
+            current_slot: 1_000_000,
+            inventory: 200_000,
+            total_oi_snapshot: 1_000_000,
+            liq_heat: 5_000,
+            ..State::default()
+        };
+        let q = quote(&params(), &state, 100_000_000, 5_000).unwrap();
+        assert_eq!(q.imbalance_bps, 20);
+        assert_eq!((q.spread_bps, q.fee_bps), (30, 5));
+        assert_eq!(q.exec_price_e6, 100_350_000);
+        assert_eq!((q.fill_size, q.new_inventory), (1_000, 201_000));
+        assert_eq!((q.coverage_bps, q.stale_bps, q.crank_bps, q.liq_bps, q.impact_bps, q.skew_bps), (0, 0, 0, 0, 0, 0));
+        assert_eq!(q.tier_spread_bps + q.discount_bps, 200);
+
+        let credibility = crate::quote(&params(), &state, 100_000_000, 5_000).unwrap();
+        assert_eq!(credibility.fill_size, 250);
+        assert!(credibility.spread_bps > q.spread_bps);
+
+        // Clamped to max_spread_bps like any quote; selling takes the bid
+        let heavy = State { inventory: 10_000_000, ..state };
+        let q = quote(&params(), &heavy, 100_000_000, -10).unwrap();
+        assert_eq!(q.spread_bps, 200);
+        assert_eq!(q.exec_price_e6, 97_950_000);
+
+        // Signals don't move it; the schedule does
+        let q = quote(&params(), &State { spread_mult_bps: 15_000, signal_bps: 40, ..state }, 100_000_000, 5_000).unwrap();
+        assert_eq!((q.schedule_bps, q.spread_bps, q.signal_bps), (15, 45, 0));
+        // So does the oracle surcharge, schedule and all
+        let q = quote(&params(), &State { spread_mult_bps: 15_000, oracle_bps: 10, ..state }, 100_000_000, 5_000).unwrap();
+        assert_eq!((q.oracle_bps, q.schedule_bps, q.spread_bps), (10, 20, 60));
+    }
+
+    #[test]
+    fn test_shares_oracle_and_inventory_checks() {
+        let p = Params { max_inventory: 1_000, oracle_jump_bps: 100, oracle_jump_window: 10, ..params() };
+        let state = State { current_slot: 5, last_oracle_price_e6: 100_000_000, last_oracle_slot: 1, ..State::default() };
+        assert_eq!(quote(&p, &state, 0, 1), Err(QuoteError::ZeroOraclePrice));
+        assert!(matches!(quote(&p, &state, 102_000_000, 1), Err(QuoteError::OracleJump { .. })));
+        assert_eq!(quote(&p, &State { inventory: 500, ..state }, 100_000_000, 600).unwrap().fill_size, 500);
+        assert_eq!(quote(&p, &State { inventory: 1_500, ..state }, 100_000_000, -1), Err(QuoteError::InventoryLimit));
+        assert!(matches!(
+            quote(&p, &State { reduce_only: true, ..state }, 100_000_000, 1),
+            Err(QuoteError::ReduceOnly { near_cap: false, .. })
+        ));
+        let twap = Params { twap_window_slots: 10, ..p };
+        assert_eq!(quote(&twap, &state, 100_000_000, 1), Err(QuoteError::StatsRequired));
+        let q = quote(&twap, &State { twap_price_e6: Some(99_000_000), ..state }, 100_000_000, 1).unwrap();
+        assert_eq!(q.anchor_price_e6, 99_000_000);
+    }
+}
@@ -0,0 +1,111 @@
+//! RFQ fills: trading against a price the LP's market maker signed off-chain.
+//!
+//! The program checks the signature, the expiry and the nonce when the quote
+//! is posted and keeps what is left of it; this module only decides what a
+//! trade gets against it. A quote is one-sided: its size is signed like a
+//! trade (positive: takers may buy), and it fills trades on that side at its
+//! price, clipped to the size left and to max_fill, until its expiry slot. The
+//! oracle checks and the inventory limits are [`crate::quote`]'s, so an RFQ
+//! fill can't take the book anywhere an algorithmic one couldn't.
+
+use crate::{anchor_price, book_fill, clip_fill, clip_headroom, Params, Quote, QuoteError, State, TIER_NORMAL};
+
+/// A posted quote, as the program keeps it between PostRfq and the fills.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rfq {
+    pub price_e6: u64,
+    /// Size left to fill, signed like a trade.
+    pub size_left: i128,
+    /// Last slot the quote fills in.
+    pub expiry_slot: u64,
+}
+
+impl Rfq {
+    /// Whether the quote fills `trade_size` at `current_slot`: it is live,
+    /// unexpired and on the trade's side.
+    pub fn covers(&self, trade_size: i128, current_slot: u64) -> bool {
+        self.price_e6 != 0
+            && current_slot <= self.expiry_slot
+            && trade_size != 0
+            && trade_size.signum() == self.size_left.signum()
+    }
+}
+
+/// Fill a trade the quote [covers](Rfq::covers). The breakdown carries the
+/// quoted price, the fill and the anchor the oracle checks passed; the tier
+/// reads NORMAL at full fill and every spread term reads zero.
+pub fn fill(params: &Params, state: &State, rfq: &Rfq, oracle_price_e6: u64, trade_size: i128) -> Result<Quote, QuoteError> {
+    let anchor_price_e6 = anchor_price(params, state, oracle_price_e6)?;
+    let fill_pct = 100;
+    let fill_size = clip_fill(trade_size, params.max_fill, fill_pct);
+    let fill_size = clip_headroom(params, state, clip_fill(fill_size, rfq.size_left.unsigned_abs(), fill_pct));
+    let new_inventory = book_fill(params, state, fill_size)?;
+    Ok(Quote {
+        tier: TIER_NORMAL,
+        fill_pct,
+        anchor_price_e6,
+        exec_price_e6: rfq.price_e6,
+        fill_size,
+        new_inventory,
+        ..Quote::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SLOT: u64 = 1_000;
+
+    fn rfq() -> Rfq {
+        Rfq { price_e6: 101_000_000, size_left: 500, expiry_slot: SLOT }
+    }
+
+    fn state() -> State {
+        State { current_slot: SLOT, inventory: -100, ..State::default() }
+    }
+
+    #[test]
+    fn test_covers_its_side_until_expiry() {
+        assert!(rfq().covers(1, SLOT));
+        assert!(rfq().covers(10_000, SLOT - 1));
+        assert!(!rfq().covers(1, SLOT + 1));
+        assert!(!rfq().covers(-1, SLOT));
+        assert!(!rfq().covers(0, SLOT));
+        assert!(!Rfq { price_e6: 0, ..rfq() }.covers(1, SLOT));
+        assert!(!Rfq { size_left: 0, ..rfq() }.covers(1, SLOT));
+        assert!(Rfq { size_left: -500, ..rfq() }.covers(-1, SLOT));
+    }
+
+    #[test]
+    fn test_fills_at_the_quoted_price() {
+        let params = Params { max_fill: 300, ..Params::default() };
+        let q = fill(&params, &state(), &rfq(), 100_000_000, 200).unwrap();
+        assert_eq!((q.exec_price_e6, q.anchor_price_e6), (101_000_000, 100_000_000));
+        assert_eq!((q.fill_size, q.new_inventory), (200, 100));
+        assert_eq!((q.tier, q.fill_pct, q.spread_bps, q.fee_bps), (TIER_NORMAL, 100, 0, 0));
+
+        // Clipped to max_fill, then to the size left
+        assert_eq!(fill(&params, &state(), &rfq(), 100_000_000, 10_000).unwrap().fill_size, 300);
+        let nearly_done = Rfq { size_left: 40, ..rfq() };
+        assert_eq!(fill(&params, &state(), &nearly_done, 100_000_000, 10_000).unwrap().fill_size, 40);
+        let selling = Rfq { size_left: -40, ..rfq() };
+        assert_eq!(fill(&params, &state(), &selling, 100_000_000, -10_000).unwrap().fill_size, -40);
+    }
+
+    #[test]
+    fn test_keeps_the_oracle_and_inventory_checks() {
+        let params = Params { max_inventory: 150, ..Params::default() };
+        assert_eq!(fill(&params, &state(), &rfq(), 0, 1), Err(QuoteError::ZeroOraclePrice));
+        assert_eq!(fill(&params, &state(), &rfq(), 100_000_000, 300).unwrap().new_inventory, 150);
+        let reducing = State { reduce_only: true, ..state() };
+        assert!(fill(&params, &reducing, &rfq(), 100_000_000, 50).is_ok());
+        assert!(matches!(
+            fill(&params, &reducing, &rfq(), 100_000_000, 250),
+            Err(QuoteError::ReduceOnly { .. })
+        ));
+        let breaker = Params { oracle_jump_bps: 100, oracle_jump_window: 10, ..params };
+        let last = State { last_oracle_price_e6: 90_000_000, last_oracle_slot: SLOT, ..state() };
+        assert!(matches!(fill(&breaker, &last, &rfq(), 100_000_000, 1), Err(QuoteError::OracleJump { .. })));
+    }
+}
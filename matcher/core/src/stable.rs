@@ -0,0 +1,119 @@
+//! The stable-pair matcher (kind 4).
+//!
+//! The credibility kind's inventory terms price the LP's book against its
+//! quoting depth. On a stablecoin perp the book swings far more than the price
+//! ever should, and those terms quote it out of the market. This kind prices
+//! the pair around what it is pegged to, PEG_PRICE_E6:
+//!
+//! - band: `peg_band_e6` either side of the peg, plus however far the anchor
+//!   has drifted off it, as bps of the anchor
+//! - coverage: what the coverage tier adds over min_spread_bps, so a thin
+//!   insurance fund still costs what it does on any other kind
+//!
+//! Their sum, at least min_spread_bps, is the tier spread. Imbalance and size
+//! impact are off; the fill clip, skew, inventory cap and every other
+//! per-trade term are [`crate::quote`]'s. A depeg widens the band until
+//! max_spread_bps caps it.
+
+use crate::{anchor_price, coverage_bps, coverage_standing, price_standing, Params, Quote, QuoteError, State, BPS};
+
+/// The peg, 1.0 in e6.
+pub const PEG_PRICE_E6: u64 = 1_000_000;
+
+/// Quote a trade on a stable context. The breakdown reads as the credibility
+/// kind's, with the band folded into tier_spread_bps.
+pub fn quote(params: &Params, state: &State, oracle_price_e6: u64, trade_size: i128) -> Result<Quote, QuoteError> {
+    let anchor_price_e6 = anchor_price(params, state, oracle_price_e6)?;
+    let min_spread_bps = params.min_spread_bps as u64;
+
+    let mut standing = coverage_standing(params, coverage_bps(state.insurance_snapshot, state.total_oi_snapshot));
+    let premium = standing.spread_bps.saturating_sub(min_spread_bps);
+    standing.spread_bps = band_bps(params.peg_band_e6 as u64, anchor_price_e6).saturating_add(premium).max(min_spread_bps);
+
+    let params = Params { imbalance_k_bps: 0, impact_k_bps: 0, ..*params };
+    price_standing(&params, state, anchor_price_e6, trade_size, standing)
+}
+
+/// The peg band as bps of `anchor_price_e6`: `band_e6` plus the anchor's
+/// distance from the peg, rounded up. Zero anchor prices are the caller's to
+/// refuse; this returns u64::MAX for one.
+pub fn band_bps(band_e6: u64, anchor_price_e6: u64) -> u64 {
+    if anchor_price_e6 == 0 {
+        return u64::MAX;
+    }
+    let width = band_e6 as u128 + anchor_price_e6.abs_diff(PEG_PRICE_E6) as u128;
+    (width * BPS as u128).div_ceil(anchor_price_e6 as u128).min(u64::MAX as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TIER_CRITICAL, TIER_FORTIFIED};
+
+    fn params() -> Params {
+        Params {
+            base_fee_bps: 1,
+            min_spread_bps: 1,
+            max_spread_bps: 200,
+            imbalance_k_bps: 500,
+            impact_mode: crate::IMPACT_LINEAR,
+            impact_k_bps: 500,
+            liquidity_e6: 1_000_000_000,
+            peg_band_e6: 200,
+            ..Params::default()
+        }
+    }
+
+    /// Fully covered and freshly snapshotted, holding a book as deep as the
+    /// quoting depth.
+    fn state() -> State {
+        State {
+            current_slot: 1_000,
+            snapshot_slot: 1_000,
+            last_crank_slot: 1_000,
+            insurance_snapshot: 300,
+            total_oi_snapshot: 100,
+            inventory: 1_000_000_000,
+            ..State::default()
+        }
+    }
+
+    #[test]
+    fn test_band_bps() {
+        assert_eq!(band_bps(100, PEG_PRICE_E6), 1);
+        assert_eq!(band_bps(0, PEG_PRICE_E6), 0);
+        // 0.5% off the peg plus the band, rounded up
+        assert_eq!(band_bps(100, 995_000), 52);
+        assert_eq!(band_bps(100, 1_005_000), 51);
+        assert_eq!(band_bps(100, 0), u64::MAX);
+        assert_eq!(band_bps(u64::MAX, 1), u64::MAX);
+    }
+
+    #[test]
+    fn test_quotes_the_band_not_the_book() {
+        let q = quote(&params(), &state(), PEG_PRICE_E6, 1_000_000).unwrap();
+        assert_eq!((q.tier, q.tier_spread_bps, q.spread_bps), (TIER_FORTIFIED, 2, 2));
+        assert_eq!((q.imbalance_bps, q.impact_bps), (0, 0));
+        assert_eq!(q.exec_price_e6, PEG_PRICE_E6 * (BPS + 3) / BPS);
+        // The credibility kind prices the same book out of the market
+        assert_eq!(crate::quote(&params(), &state(), PEG_PRICE_E6, 1_000_000).unwrap().spread_bps, 200);
+
+        // min_spread_bps still floors the band
+        let floored = Params { min_spread_bps: 5, ..params() };
+        assert_eq!(quote(&floored, &state(), PEG_PRICE_E6, 1_000_000).unwrap().spread_bps, 5);
+    }
+
+    #[test]
+    fn test_depeg_and_thin_cover_widen() {
+        let at = |state: &State, oracle| quote(&params(), state, oracle, 1_000_000).unwrap();
+        assert_eq!(at(&state(), 995_000).spread_bps, 53);
+        // 2% off the peg hits the cap
+        assert_eq!(at(&state(), 980_000).spread_bps, 200);
+
+        // CRITICAL coverage adds the whole spread range on top of the band
+        let thin = State { insurance_snapshot: 5, ..state() };
+        let q = at(&thin, PEG_PRICE_E6);
+        assert_eq!((q.tier, q.tier_spread_bps, q.discount_bps), (TIER_CRITICAL, 201, 0));
+        assert_eq!(q.spread_bps, 200);
+    }
+}
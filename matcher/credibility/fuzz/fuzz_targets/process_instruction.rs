@@ -0,0 +1,163 @@
+//! Arbitrary instruction data against arbitrary accounts.
+//!
+//! Accounts are laid out in the runtime's serialized input format and read
+//! back with the real entrypoint deserializer, so duplicates alias and data
+//! can grow exactly as on-chain. Keys and owners come from a small pool
+//! (the program, the system program, the sysvars and a few others) so that
+//! owner checks, duplicates and sysvar checks are all reachable; account data
+//! is raw bytes or a context, stats or slab buffer of the right size with the
+//! magic set and arbitrary patches on top. Any error is fine; a panic is a bug.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use credibility_matcher::process_instruction;
+use libfuzzer_sys::fuzz_target;
+use provenance_client::layout::*;
+use solana_program::{
+    clock::Clock,
+    entrypoint::{deserialize, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER, SUCCESS},
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar,
+};
+use std::{cell::Cell, sync::Once};
+
+const PROGRAM_ID: Pubkey = Pubkey::new_from_array([0xC7; 32]);
+const MAX_ACCOUNTS: usize = 6;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    slot: u64,
+    accounts: Vec<Account>,
+    data: Vec<u8>,
+}
+
+#[derive(Arbitrary, Debug)]
+struct Account {
+    key: u8,
+    owner: u8,
+    signer: bool,
+    writable: bool,
+    lamports: u64,
+    data: Data,
+}
+
+#[derive(Arbitrary, Debug)]
+enum Data {
+    Raw(Vec<u8>),
+    Context { extended: bool, initialized: bool, patches: Vec<(u16, u8)> },
+    Stats { patches: Vec<(u16, u8)> },
+    Slab { patches: Vec<(u16, u8)> },
+}
+
+fn key(index: u8) -> Pubkey {
+    match index % 8 {
+        0 => PROGRAM_ID,
+        1 => solana_program::system_program::id(),
+        2 => sysvar::clock::id(),
+        3 => sysvar::rent::id(),
+        i => Pubkey::new_from_array([i; 32]),
+    }
+}
+
+impl Data {
+    fn bytes(&self) -> Vec<u8> {
+        let (mut data, patches) = match self {
+            Data::Raw(data) => return data.clone(),
+            Data::Context { extended, initialized, patches } => {
+                let mut data = vec![0u8; if *extended { CTX_EXT_LEN } else { CTX_LEN }];
+                if *initialized {
+                    let version = if *extended { VERSION } else { VERSION_V5 };
+                    data[CTX_BASE + CTX_MAGIC_OFF..CTX_BASE + CTX_MAGIC_OFF + 8].copy_from_slice(&MAGIC.to_le_bytes());
+                    data[CTX_BASE + CTX_VERSION_OFF..CTX_BASE + CTX_VERSION_OFF + 4].copy_from_slice(&version.to_le_bytes());
+                    data[CTX_BASE + CTX_KIND_OFF] = KIND_CREDIBILITY;
+                }
+                (data, patches)
+            }
+            Data::Stats { patches } => (vec![0u8; STATS_LEN], patches),
+            Data::Slab { patches } => (vec![0u8; SLAB_MIN_LEN], patches),
+        };
+        for &(offset, byte) in patches {
+            let len = data.len();
+            data[offset as usize % len] = byte;
+        }
+        data
+    }
+}
+
+thread_local! {
+    static SLOT: Cell<u64> = const { Cell::new(0) };
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock { slot: SLOT.with(|s| s.get()), ..Clock::default() };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_log_data(&self, _fields: &[&[u8]]) {}
+
+    fn sol_set_return_data(&self, _data: &[u8]) {}
+}
+
+/// The runtime's serialized input: accounts (a duplicate as an index to the
+/// first), instruction data, program id.
+fn serialize(accounts: &[Account], data: &[u8]) -> Vec<u8> {
+    let keys: Vec<Pubkey> = accounts.iter().map(|a| key(a.key)).collect();
+    let mut input = Vec::new();
+    input.extend_from_slice(&(accounts.len() as u64).to_le_bytes());
+    for (i, a) in accounts.iter().enumerate() {
+        if let Some(dup) = keys[..i].iter().position(|k| *k == keys[i]) {
+            input.push(dup as u8);
+            input.extend_from_slice(&[0u8; 7]);
+            continue;
+        }
+        let bytes = a.data.bytes();
+        input.push(NON_DUP_MARKER);
+        input.extend_from_slice(&[a.signer as u8, a.writable as u8, 0]);
+        input.extend_from_slice(&[0u8; 4]);
+        input.extend_from_slice(keys[i].as_ref());
+        input.extend_from_slice(key(a.owner % 4).as_ref());
+        input.extend_from_slice(&a.lamports.to_le_bytes());
+        input.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+        input.extend_from_slice(&bytes);
+        input.resize(input.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+        input.resize(input.len().next_multiple_of(8), 0);
+        input.extend_from_slice(&0u64.to_le_bytes());
+    }
+    input.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    input.extend_from_slice(data);
+    input.extend_from_slice(PROGRAM_ID.as_ref());
+    input
+}
+
+fuzz_target!(|input: Input| {
+    static STUBS: Once = Once::new();
+    STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(Stubs));
+    });
+    if input.accounts.len() > MAX_ACCOUNTS {
+        return;
+    }
+    SLOT.with(|s| s.set(input.slot));
+
+    let serialized = serialize(&input.accounts, &input.data);
+    // u64-backed for the 8-byte alignment deserialize relies on
+    let mut aligned = vec![0u64; serialized.len().div_ceil(8)];
+    let bytes = unsafe { std::slice::from_raw_parts_mut(aligned.as_mut_ptr() as *mut u8, serialized.len()) };
+    bytes.copy_from_slice(&serialized);
+    let (program_id, accounts, data) = unsafe { deserialize(bytes.as_mut_ptr()) };
+    let _ = process_instruction(program_id, &accounts, data);
+});
@@ -0,0 +1,308 @@
+//! The context as a typed view.
+//!
+//! `MatcherContext` is the 256 bytes at `CTX_BASE`, `ContextExtension` the v6
+//! extension at `EXT_BASE` and `MarketEntry` one entry of a shared context's
+//! market table, each cast in place over the account data rather than
+//! decoded: fields are little-endian byte arrays with alignment 1, so the cast
+//! works at any address, and reading or writing one touches only its own
+//! bytes. The offsets in `layout` stay the source of truth for clients; the
+//! assertions below hold every field of each struct to its constant, so the
+//! two can't drift apart.
+
+use bytemuck::{Pod, Zeroable};
+use core::mem::{offset_of, size_of};
+use solana_program::pubkey::Pubkey;
+
+use crate::layout::*;
+
+macro_rules! le {
+    ($($name:ident($ty:ty, $len:literal)),* $(,)?) => {$(
+        /// A little-endian integer stored as bytes, so it has alignment 1.
+        #[repr(transparent)]
+        #[derive(Clone, Copy, Default, Pod, Zeroable)]
+        pub struct $name([u8; $len]);
+
+        impl $name {
+            pub fn get(&self) -> $ty {
+                <$ty>::from_le_bytes(self.0)
+            }
+
+            pub fn set(&mut self, value: $ty) {
+                self.0 = value.to_le_bytes();
+            }
+        }
+    )*};
+}
+
+le!(LeU16(u16, 2), LeU32(u32, 4), LeU64(u64, 8), LeI64(i64, 8), LeU128(u128, 16), LeI128(i128, 16));
+
+/// The context fields, named as in the crate-root table.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct MatcherContext {
+    pub magic: LeU64,
+    pub version: LeU32,
+    pub kind: u8,
+    pub oracle_decimals: u8,
+    pub spot_weight_bps: LeU16,
+    pub lp_pda: [u8; 32],
+    pub base_fee_bps: LeU32,
+    pub min_spread_bps: LeU32,
+    pub max_spread_bps: LeU32,
+    pub imbalance_k_bps: LeU32,
+    pub liquidity_notional_e6: LeU128,
+    pub max_fill_abs: LeU128,
+    pub inventory_base: LeI128,
+    pub last_oracle_price_e6: LeU64,
+    pub last_exec_price_e6: LeU64,
+    pub max_inventory_abs: LeU128,
+    pub insurance_snapshot: LeU128,
+    pub total_oi_snapshot: LeU128,
+    pub market_age_slots: LeU64,
+    pub last_deficit_slot: LeU64,
+    pub snapshot_slot: LeU64,
+    pub age_halflife_slots: LeU32,
+    pub insurance_weight_bps: LeU32,
+    pub impact_mode: u8,
+    pub coverage_curve: u8,
+    pub flags: u8,
+    /// CreateContext's bump, meaningful when the creation record says the
+    /// context is derived.
+    pub bump: u8,
+    pub impact_k_bps: LeU32,
+    pub skew_k_bps: LeU32,
+    pub curve_points: [LeU16; 4],
+    pub peg_band_e6: LeU32,
+    pub last_crank_slot: LeU64,
+    pub last_lifetime_liqs: LeU64,
+    pub liq_heat: LeU32,
+    pub liq_halflife_slots: LeU32,
+}
+
+impl MatcherContext {
+    /// The context in an account's data, which must be at least `CTX_LEN`.
+    pub fn view(data: &[u8]) -> &Self {
+        bytemuck::from_bytes(&data[CTX_BASE..CTX_LEN])
+    }
+
+    pub fn view_mut(data: &mut [u8]) -> &mut Self {
+        bytemuck::from_bytes_mut(&mut data[CTX_BASE..CTX_LEN])
+    }
+
+    pub fn lp_pda(&self) -> Pubkey {
+        Pubkey::new_from_array(self.lp_pda)
+    }
+
+    pub fn has_flag(&self, flag: u8) -> bool {
+        self.flags & flag != 0
+    }
+}
+
+const _: () = assert!(size_of::<MatcherContext>() == CTX_LEN - CTX_BASE);
+const _: () = assert!(offset_of!(MatcherContext, magic) == CTX_MAGIC_OFF);
+const _: () = assert!(offset_of!(MatcherContext, version) == CTX_VERSION_OFF);
+const _: () = assert!(offset_of!(MatcherContext, kind) == CTX_KIND_OFF);
+const _: () = assert!(offset_of!(MatcherContext, oracle_decimals) == CTX_ORACLE_DECIMALS_OFF);
+const _: () = assert!(offset_of!(MatcherContext, spot_weight_bps) == CTX_SPOT_WEIGHT_OFF);
+const _: () = assert!(offset_of!(MatcherContext, lp_pda) == CTX_LP_PDA_OFF);
+const _: () = assert!(offset_of!(MatcherContext, base_fee_bps) == CTX_BASE_FEE_OFF);
+const _: () = assert!(offset_of!(MatcherContext, min_spread_bps) == CTX_MIN_SPREAD_OFF);
+const _: () = assert!(offset_of!(MatcherContext, max_spread_bps) == CTX_MAX_SPREAD_OFF);
+const _: () = assert!(offset_of!(MatcherContext, imbalance_k_bps) == CTX_IMBALANCE_K_OFF);
+const _: () = assert!(offset_of!(MatcherContext, liquidity_notional_e6) == CTX_LIQUIDITY_OFF);
+const _: () = assert!(offset_of!(MatcherContext, max_fill_abs) == CTX_MAX_FILL_OFF);
+const _: () = assert!(offset_of!(MatcherContext, inventory_base) == CTX_INVENTORY_OFF);
+const _: () = assert!(offset_of!(MatcherContext, last_oracle_price_e6) == CTX_LAST_ORACLE_OFF);
+const _: () = assert!(offset_of!(MatcherContext, last_exec_price_e6) == CTX_LAST_EXEC_OFF);
+const _: () = assert!(offset_of!(MatcherContext, max_inventory_abs) == CTX_MAX_INVENTORY_OFF);
+const _: () = assert!(offset_of!(MatcherContext, insurance_snapshot) == CTX_INSURANCE_OFF);
+const _: () = assert!(offset_of!(MatcherContext, total_oi_snapshot) == CTX_TOTAL_OI_OFF);
+const _: () = assert!(offset_of!(MatcherContext, market_age_slots) == CTX_MARKET_AGE_OFF);
+const _: () = assert!(offset_of!(MatcherContext, last_deficit_slot) == CTX_LAST_DEFICIT_OFF);
+const _: () = assert!(offset_of!(MatcherContext, snapshot_slot) == CTX_SNAPSHOT_SLOT_OFF);
+const _: () = assert!(offset_of!(MatcherContext, age_halflife_slots) == CTX_AGE_HALFLIFE_OFF);
+const _: () = assert!(offset_of!(MatcherContext, insurance_weight_bps) == CTX_INSURANCE_WEIGHT_OFF);
+const _: () = assert!(offset_of!(MatcherContext, impact_mode) == CTX_IMPACT_MODE_OFF);
+const _: () = assert!(offset_of!(MatcherContext, coverage_curve) == CTX_COVERAGE_CURVE_OFF);
+const _: () = assert!(offset_of!(MatcherContext, flags) == CTX_FLAGS_OFF);
+const _: () = assert!(offset_of!(MatcherContext, bump) == CTX_BUMP_OFF);
+const _: () = assert!(offset_of!(MatcherContext, impact_k_bps) == CTX_IMPACT_K_OFF);
+const _: () = assert!(offset_of!(MatcherContext, skew_k_bps) == CTX_SKEW_K_OFF);
+const _: () = assert!(offset_of!(MatcherContext, curve_points) == CTX_CURVE_POINTS_OFF);
+const _: () = assert!(offset_of!(MatcherContext, peg_band_e6) == CTX_PEG_BAND_OFF);
+const _: () = assert!(offset_of!(MatcherContext, last_crank_slot) == CTX_LAST_CRANK_OFF);
+const _: () = assert!(offset_of!(MatcherContext, last_lifetime_liqs) == CTX_LAST_LIQS_OFF);
+const _: () = assert!(offset_of!(MatcherContext, liq_heat) == CTX_LIQ_HEAT_OFF);
+const _: () = assert!(offset_of!(MatcherContext, liq_halflife_slots) == CTX_LIQ_HALFLIFE_OFF);
+
+/// The v6 extension, named as in the crate-root table.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct ContextExtension {
+    pub funding_rate_bps_per_slot: LeI64,
+    pub funding_horizon_slots: LeU32,
+    pub liq_auction_slots: LeU32,
+    pub slab: [u8; 32],
+    pub pending_activation_slot: LeU64,
+    pub pending_params: [u8; PENDING_HEAD_LEN],
+    pub authority: [u8; 32],
+    pub pending_authority: [u8; 32],
+    pub percolator_program: [u8; 32],
+    pub oracle_jump_bps: LeU16,
+    pub liq_auction_bps: LeU16,
+    pub oracle_jump_window_slots: LeU32,
+    pub last_oracle_slot: LeU64,
+    pub fill_seq: LeU64,
+    pub cum_notional: LeU128,
+    pub cum_fees: LeU128,
+    pub gross_long: LeU128,
+    pub gross_short: LeU128,
+    pub flow_k_bps: LeU32,
+    pub twap_window_slots: LeU32,
+    pub pending_params_tail: [u8; PENDING_TAIL_LEN],
+    pub rfq_signer: [u8; 32],
+    pub stats: [u8; 32],
+    pub signal_weights: [LeU16; 4],
+    pub deficit_halflife_slots: LeU32,
+    pub market_slots: LeU32,
+    pub rfq_price_e6: LeU64,
+    pub rfq_expiry_slot: LeU64,
+    pub rfq_nonce: LeU64,
+    pub rfq_size_left: LeI128,
+    pub last_liq_slot: LeU64,
+}
+
+impl ContextExtension {
+    /// The extension in a v6 account's data, which must be at least
+    /// `CTX_EXT_LEN`.
+    pub fn view(data: &[u8]) -> &Self {
+        bytemuck::from_bytes(&data[EXT_BASE..CTX_EXT_LEN])
+    }
+
+    pub fn view_mut(data: &mut [u8]) -> &mut Self {
+        bytemuck::from_bytes_mut(&mut data[EXT_BASE..CTX_EXT_LEN])
+    }
+
+    /// The bound slab; the default key when unbound or shared.
+    pub fn slab(&self) -> Pubkey {
+        Pubkey::new_from_array(self.slab)
+    }
+
+    pub fn authority(&self) -> Pubkey {
+        Pubkey::new_from_array(self.authority)
+    }
+
+    pub fn pending_authority(&self) -> Pubkey {
+        Pubkey::new_from_array(self.pending_authority)
+    }
+
+    pub fn percolator_program(&self) -> Pubkey {
+        Pubkey::new_from_array(self.percolator_program)
+    }
+
+    pub fn rfq_signer(&self) -> Pubkey {
+        Pubkey::new_from_array(self.rfq_signer)
+    }
+
+    pub fn stats(&self) -> Pubkey {
+        Pubkey::new_from_array(self.stats)
+    }
+}
+
+const _: () = assert!(size_of::<ContextExtension>() == EXT_LEN);
+const _: () = assert!(offset_of!(ContextExtension, funding_rate_bps_per_slot) == EXT_FUNDING_RATE_OFF);
+const _: () = assert!(offset_of!(ContextExtension, funding_horizon_slots) == EXT_FUNDING_HORIZON_OFF);
+const _: () = assert!(offset_of!(ContextExtension, liq_auction_slots) == EXT_LIQ_AUCTION_SLOTS_OFF);
+const _: () = assert!(offset_of!(ContextExtension, slab) == EXT_SLAB_OFF);
+const _: () = assert!(offset_of!(ContextExtension, pending_activation_slot) == EXT_PENDING_SLOT_OFF);
+const _: () = assert!(offset_of!(ContextExtension, pending_params) == EXT_PENDING_PARAMS_OFF);
+const _: () = assert!(offset_of!(ContextExtension, authority) == EXT_AUTHORITY_OFF);
+const _: () = assert!(offset_of!(ContextExtension, pending_authority) == EXT_PENDING_AUTHORITY_OFF);
+const _: () = assert!(offset_of!(ContextExtension, percolator_program) == EXT_PERCOLATOR_OFF);
+const _: () = assert!(offset_of!(ContextExtension, oracle_jump_bps) == EXT_ORACLE_JUMP_BPS_OFF);
+const _: () = assert!(offset_of!(ContextExtension, liq_auction_bps) == EXT_LIQ_AUCTION_BPS_OFF);
+const _: () = assert!(offset_of!(ContextExtension, oracle_jump_window_slots) == EXT_ORACLE_JUMP_WINDOW_OFF);
+const _: () = assert!(offset_of!(ContextExtension, last_oracle_slot) == EXT_LAST_ORACLE_SLOT_OFF);
+const _: () = assert!(offset_of!(ContextExtension, fill_seq) == EXT_FILL_SEQ_OFF);
+const _: () = assert!(offset_of!(ContextExtension, cum_notional) == EXT_CUM_NOTIONAL_OFF);
+const _: () = assert!(offset_of!(ContextExtension, cum_fees) == EXT_CUM_FEES_OFF);
+const _: () = assert!(offset_of!(ContextExtension, gross_long) == EXT_GROSS_LONG_OFF);
+const _: () = assert!(offset_of!(ContextExtension, gross_short) == EXT_GROSS_SHORT_OFF);
+const _: () = assert!(offset_of!(ContextExtension, flow_k_bps) == EXT_FLOW_K_OFF);
+const _: () = assert!(offset_of!(ContextExtension, twap_window_slots) == EXT_TWAP_WINDOW_OFF);
+const _: () = assert!(offset_of!(ContextExtension, pending_params_tail) == EXT_PENDING_PARAMS_TAIL_OFF);
+const _: () = assert!(offset_of!(ContextExtension, rfq_signer) == EXT_RFQ_SIGNER_OFF);
+const _: () = assert!(offset_of!(ContextExtension, stats) == EXT_STATS_OFF);
+const _: () = assert!(offset_of!(ContextExtension, signal_weights) == EXT_SIGNAL_WEIGHTS_OFF);
+const _: () = assert!(offset_of!(ContextExtension, deficit_halflife_slots) == EXT_DEFICIT_HALFLIFE_OFF);
+const _: () = assert!(offset_of!(ContextExtension, market_slots) == EXT_MARKET_SLOTS_OFF);
+const _: () = assert!(offset_of!(ContextExtension, rfq_price_e6) == EXT_RFQ_PRICE_OFF);
+const _: () = assert!(offset_of!(ContextExtension, rfq_expiry_slot) == EXT_RFQ_EXPIRY_OFF);
+const _: () = assert!(offset_of!(ContextExtension, rfq_nonce) == EXT_RFQ_NONCE_OFF);
+const _: () = assert!(offset_of!(ContextExtension, rfq_size_left) == EXT_RFQ_SIZE_OFF);
+const _: () = assert!(offset_of!(ContextExtension, last_liq_slot) == EXT_LAST_LIQ_SLOT_OFF);
+
+/// One market of a shared context's table, named as in the crate-root table.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct MarketEntry {
+    pub slab: [u8; 32],
+    pub max_fill: LeU128,
+    pub max_inventory: LeU128,
+    pub inventory: LeI128,
+    pub insurance_snapshot: LeU128,
+    pub total_oi_snapshot: LeU128,
+    pub gross_long: LeU128,
+    pub gross_short: LeU128,
+    pub snapshot_slot: LeU64,
+    pub last_crank_slot: LeU64,
+    pub last_lifetime_liqs: LeU64,
+    pub funding_rate_bps_per_slot: LeI64,
+    pub market_age_slots: LeU64,
+    pub last_deficit_slot: LeU64,
+    pub last_liq_slot: LeU64,
+    pub last_oracle_price_e6: LeU64,
+    pub last_oracle_slot: LeU64,
+    pub last_exec_price_e6: LeU64,
+    pub liq_heat: LeU32,
+    pub _pad0: [u8; 4],
+    pub _reserved: [u8; 24],
+}
+
+impl MarketEntry {
+    /// The market table of a shared account's data, which must be at least
+    /// `CTX_SHARED_LEN`: all MAX_MARKETS entries, listed or free.
+    pub fn table(data: &[u8]) -> &[Self] {
+        bytemuck::cast_slice(&data[MARKETS_BASE..CTX_SHARED_LEN])
+    }
+
+    pub fn table_mut(data: &mut [u8]) -> &mut [Self] {
+        bytemuck::cast_slice_mut(&mut data[MARKETS_BASE..CTX_SHARED_LEN])
+    }
+
+    /// The market's slab; the default key on a free entry.
+    pub fn slab(&self) -> Pubkey {
+        Pubkey::new_from_array(self.slab)
+    }
+}
+
+const _: () = assert!(size_of::<MarketEntry>() == MARKET_LEN);
+const _: () = assert!(offset_of!(MarketEntry, slab) == MKT_SLAB_OFF);
+const _: () = assert!(offset_of!(MarketEntry, max_fill) == MKT_MAX_FILL_OFF);
+const _: () = assert!(offset_of!(MarketEntry, max_inventory) == MKT_MAX_INVENTORY_OFF);
+const _: () = assert!(offset_of!(MarketEntry, inventory) == MKT_INVENTORY_OFF);
+const _: () = assert!(offset_of!(MarketEntry, insurance_snapshot) == MKT_INSURANCE_OFF);
+const _: () = assert!(offset_of!(MarketEntry, total_oi_snapshot) == MKT_TOTAL_OI_OFF);
+const _: () = assert!(offset_of!(MarketEntry, gross_long) == MKT_GROSS_LONG_OFF);
+const _: () = assert!(offset_of!(MarketEntry, gross_short) == MKT_GROSS_SHORT_OFF);
+const _: () = assert!(offset_of!(MarketEntry, snapshot_slot) == MKT_SNAPSHOT_SLOT_OFF);
+const _: () = assert!(offset_of!(MarketEntry, last_crank_slot) == MKT_LAST_CRANK_OFF);
+const _: () = assert!(offset_of!(MarketEntry, last_lifetime_liqs) == MKT_LAST_LIQS_OFF);
+const _: () = assert!(offset_of!(MarketEntry, funding_rate_bps_per_slot) == MKT_FUNDING_RATE_OFF);
+const _: () = assert!(offset_of!(MarketEntry, market_age_slots) == MKT_MARKET_AGE_OFF);
+const _: () = assert!(offset_of!(MarketEntry, last_deficit_slot) == MKT_LAST_DEFICIT_OFF);
+const _: () = assert!(offset_of!(MarketEntry, last_liq_slot) == MKT_LAST_LIQ_SLOT_OFF);
+const _: () = assert!(offset_of!(MarketEntry, last_oracle_price_e6) == MKT_LAST_ORACLE_OFF);
+const _: () = assert!(offset_of!(MarketEntry, last_oracle_slot) == MKT_LAST_ORACLE_SLOT_OFF);
+const _: () = assert!(offset_of!(MarketEntry, last_exec_price_e6) == MKT_LAST_EXEC_OFF);
+const _: () = assert!(offset_of!(MarketEntry, liq_heat) == MKT_LIQ_HEAT_OFF);
@@ -0,0 +1,241 @@
+//! Matcher error codes.
+//!
+//! Every matcher-specific failure surfaces as `ProgramError::Custom(code)`
+//! with one of the codes below. Codes are part of the ABI: they are never
+//! renumbered or reused, so a client built against this crate (with the
+//! `no-entrypoint` feature) can decode any failed transaction with
+//! [`MatcherError::from_code`].
+//!
+//! Malformed input (short data, missing accounts, wrong signer) still uses the
+//! matching built-in `ProgramError` variant.
+
+use provenance_core::QuoteError;
+use solana_program::program_error::ProgramError;
+
+macro_rules! matcher_errors {
+    ($($(#[$doc:meta])* $name:ident = $code:literal => $msg:literal,)*) => {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        #[repr(u32)]
+        pub enum MatcherError {
+            $($(#[$doc])* $name = $code,)*
+        }
+
+        impl MatcherError {
+            /// Every variant, in code order.
+            pub const ALL: &'static [MatcherError] = &[$(MatcherError::$name,)*];
+
+            /// Decode a `ProgramError::Custom` code.
+            pub fn from_code(code: u32) -> Option<Self> {
+                match code {
+                    $($code => Some(MatcherError::$name),)*
+                    _ => None,
+                }
+            }
+
+            /// Short human-readable description.
+            pub fn message(self) -> &'static str {
+                match self {
+                    $(MatcherError::$name => $msg,)*
+                }
+            }
+        }
+    };
+}
+
+matcher_errors! {
+    /// The LP paused the matcher; every match is refused.
+    Paused = 1 => "matcher is paused",
+    /// Reduce-only mode (manual or near the cap) and the trade grows |inventory|.
+    ReduceOnly = 2 => "reduce-only: trade would grow inventory",
+    /// inventory + trade_size leaves the i128 range.
+    InventoryOverflow = 3 => "inventory overflow",
+    /// Retired: oversize trades are now clipped to the tier-scaled max fill.
+    /// Kept so the code is never reused.
+    MaxFillExceeded = 4 => "trade exceeds the tier fill limit",
+    /// The trade would take |inventory| past max_inventory_abs.
+    InventoryLimit = 5 => "trade would exceed the inventory limit",
+    /// The caller passed a zero oracle price.
+    ZeroOraclePrice = 6 => "zero oracle price",
+    /// The context has no matcher magic (never initialized, or closed).
+    NotInitialized = 7 => "context not initialized",
+    /// The signing LP PDA is not the one stored in the context.
+    PdaMismatch = 8 => "LP PDA mismatch",
+    /// The signer is not the context's authority.
+    AuthorityMismatch = 9 => "authority mismatch",
+    /// Init was asked for a matcher kind this program doesn't implement.
+    WrongKind = 10 => "unsupported matcher kind",
+    /// The feature lives in the v6 extension and the context is v5.
+    ExtensionRequired = 11 => "needs a v6 (extended) context",
+    /// Instant parameter changes are refused once the market has traded.
+    MarketLive = 12 => "market is live; use ProposeParams/CommitParams",
+    /// The slab is not the one the context was bound to.
+    SlabMismatch = 13 => "slab mismatch",
+    /// The slab is not owned by the percolator program recorded at init.
+    SlabOwnerMismatch = 14 => "slab owned by the wrong program",
+    /// The clock account is not the Clock sysvar.
+    InvalidClock = 15 => "expected the Clock sysvar",
+    /// The signer is not the proposed authority.
+    NotPendingAuthority = 16 => "not the pending authority",
+    /// Migrate was called on a context that isn't v5.
+    NothingToMigrate = 17 => "nothing to migrate",
+    /// CommitParams without a proposal.
+    NoPendingProposal = 18 => "no pending parameter proposal",
+    /// CommitParams before the activation slot.
+    TimelockActive = 19 => "parameter proposal still timelocked",
+    /// impact_mode is not one of the IMPACT_* values.
+    UnknownImpactMode = 20 => "unknown impact_mode",
+    /// coverage_curve is not one of the CURVE_* values.
+    UnknownCoverageCurve = 21 => "unknown coverage_curve",
+    /// Piecewise curve points are decreasing or above 10000.
+    InvalidCurvePoints = 22 => "curve points must be non-decreasing and <= 10000",
+    /// min_spread_bps > max_spread_bps.
+    SpreadOrder = 23 => "min_spread_bps above max_spread_bps",
+    /// max_spread_bps above 10000.
+    SpreadTooWide = 24 => "max_spread_bps above 10000",
+    /// base_fee_bps above 10000.
+    FeeTooHigh = 25 => "base_fee_bps above 10000",
+    /// insurance_weight_bps above 10000.
+    InsuranceWeightTooHigh = 26 => "insurance_weight_bps above 10000",
+    /// A depth-scaled coefficient (imbalance, impact, skew) with zero liquidity.
+    LiquidityRequired = 27 => "liquidity_notional_e6 required by a depth-scaled term",
+    /// The same account was passed in two roles of one instruction.
+    DuplicateAccount = 28 => "account passed more than once",
+    /// The oracle moved past oracle_jump_bps inside the breaker window.
+    OracleJump = 29 => "oracle moved too far since the last fill",
+    /// Only one of oracle_jump_bps / oracle_jump_window_slots is set.
+    InvalidOracleBreaker = 30 => "oracle breaker needs both a threshold and a window",
+    /// The stats account is not the one InitStats bound to this context.
+    StatsMismatch = 31 => "stats account not bound to this context",
+    /// The context has a bound stats account and it wasn't passed: Match
+    /// always needs it, and quotes do with TWAP anchoring on.
+    StatsRequired = 32 => "the bound stats account is required",
+    /// Signal weights don't sum to 10000 (required by the multi-signal kind),
+    /// or a weighted signal has no halflife.
+    InvalidSignalWeights = 33 => "signal weights must sum to 10000, each weighted signal with a halflife",
+    /// PostRfq on a context with no RFQ signer.
+    RfqSignerUnset = 34 => "no RFQ signer set",
+    /// PostRfq without an ed25519 verification of the quote by the RFQ signer
+    /// right before it.
+    RfqUnverified = 35 => "RFQ quote not verified for the RFQ signer",
+    /// The RFQ quote's expiry slot has passed.
+    RfqExpired = 36 => "RFQ quote expired",
+    /// The RFQ quote's nonce is not above the last one posted.
+    RfqReplayed = 37 => "RFQ nonce already used",
+    /// The RFQ quote is for another context, or has a zero price or size.
+    InvalidRfq = 38 => "RFQ quote for another context or empty",
+    /// Only one of liq_auction_bps / liq_auction_slots is set.
+    InvalidLiqAuction = 39 => "liquidation auction needs both a premium and a length",
+    /// On a shared context: the slab (or market index) is not in the market
+    /// table, or wasn't passed.
+    UnknownMarket = 40 => "market not in this context's market table",
+    /// SetMarket with every market slot taken, or on a context with no table.
+    MarketTableFull = 41 => "no free market slot",
+    /// RFQ quotes and the stats account price one market; a shared context
+    /// can't use them.
+    SharedContext = 42 => "not available on a shared context",
+    /// A signal account without the magic or a whole layout, weighted above
+    /// 100%, or more than MAX_SIGNAL_ACCOUNTS of them.
+    InvalidSignal = 43 => "invalid signal account",
+    /// The trade is on a side the LP has turned off with SetNoBids or
+    /// SetNoAsks.
+    SideDisabled = 44 => "the LP isn't quoting this side",
+    /// A scheduled context's schedule account is missing, or isn't the one
+    /// InitSchedule bound to it.
+    ScheduleMismatch = 45 => "schedule account not bound to this context",
+    /// SetSchedule with more than MAX_SCHEDULE_ENTRIES entries, a range that
+    /// ends before it starts, or a multiplier below 1x.
+    InvalidSchedule = 46 => "invalid spread schedule",
+    /// A ladder account InitLadder didn't bind to this context.
+    LadderMismatch = 47 => "ladder account not bound to this context",
+    /// A context's feed account is missing, or isn't the one InitFeed bound
+    /// to it.
+    FeedMismatch = 48 => "feed account not bound to this context",
+    /// The price account is missing, isn't one the feed's source publishes
+    /// (a fully verified Pyth PriceUpdateV2, a Switchboard pull feed, a
+    /// Chainlink OCR2 feed with a round), is for another feed, or has a price
+    /// that isn't positive.
+    InvalidFeedPrice = 49 => "invalid oracle price account",
+    /// The feed's price was published longer ago than its max_age_secs.
+    FeedPriceStale = 50 => "oracle price too old",
+    /// The feeds' prices are further apart than the feed account's
+    /// max_divergence_bps, and it isn't set to widen instead.
+    FeedDivergence = 51 => "oracle feeds disagree",
+    /// AddFeed on a feed account that already holds MAX_FEEDS feeds, or
+    /// with a feed it already holds.
+    InvalidFeedSet = 52 => "feed account full, or feed already bound",
+    /// spot_weight_bps above 10000.
+    SpotWeightTooHigh = 53 => "spot_weight_bps above 10000",
+    /// SetFeeTiers with more than MAX_FEE_TIERS tiers, minimums that aren't
+    /// positive and rising, or a fee above 10000.
+    InvalidFeeTiers = 54 => "fee tiers must rise in notional, each at most 10000 bps",
+    /// SetRebate above 10000.
+    RebateTooHigh = 55 => "rebate_bps above 10000",
+    /// RecordWithdrawal for more than the context's cum_fees less what was
+    /// already recorded.
+    WithdrawalExceedsFees = 56 => "withdrawal exceeds the fees accrued",
+    /// SetFeeDiscount above 10000.
+    FeeDiscountTooHigh = 57 => "fee_discount_bps above 10000",
+    /// A tip jar InitTipJar didn't bind to this context.
+    TipJarMismatch = 58 => "tip jar not bound to this context",
+    /// InitReferral or SetReferralShare above 10000.
+    ReferralShareTooHigh = 59 => "referral share_bps above 10000",
+    /// A referral account InitReferral didn't bind to this context.
+    ReferralMismatch = 60 => "referral account not bound to this context",
+    /// A trade whose notional at the oracle is below the schedule's
+    /// min_notional_e6. Soft-rejectable.
+    BelowMinNotional = 61 => "trade notional below the minimum",
+    /// SetSkim above 10000.
+    SkimTooHigh = 62 => "skim_bps above 10000",
+    /// ClaimSkim for more than the skim accrued and not yet claimed.
+    SkimExceedsAccrued = 63 => "claim exceeds the skim accrued",
+    /// SetLoyaltyTiers with more than MAX_LOYALTY_TIERS tiers, minimums that
+    /// aren't positive and rising, or a discount above 10000.
+    InvalidLoyaltyTiers = 64 => "loyalty tiers must rise in volume, each at most 10000 bps",
+    /// A trader account InitTrader didn't bind to this context and the
+    /// trader passed after it.
+    TraderMismatch = 65 => "trader account not bound to this context and trader",
+    /// InitStats with a protocol_fee_bps above 10000.
+    ProtocolFeeTooHigh = 66 => "protocol_fee_bps above 10000",
+    /// SweepProtocolFee signed by a key other than the stats account's
+    /// treasury.
+    TreasuryMismatch = 67 => "signer is not the stats account's treasury",
+    /// SweepProtocolFee for more than the protocol fee accrued and not yet
+    /// swept.
+    SweepExceedsAccrued = 68 => "sweep exceeds the protocol fee accrued",
+    /// SetInventoryBand with a soft limit at or past max_inventory, or a
+    /// band_k_bps above 10000.
+    InvalidInventoryBand = 69 => "soft inventory must sit below max_inventory, band_k_bps at most 10000",
+    /// SetReduceOnlyThreshold above 10000.
+    ReduceOnlyThresholdTooHigh = 70 => "reduce_only_bps above 10000",
+    /// SyncInventory on a slab account that isn't an LP routed to this
+    /// program and context.
+    LpAccountMismatch = 71 => "slab account is not an LP matched through this context",
+    /// A context not at the address CreateContext derives from its LP PDA
+    /// and slab.
+    ContextAddressMismatch = 72 => "context is not at its LP PDA and slab's address",
+}
+
+impl From<MatcherError> for ProgramError {
+    fn from(e: MatcherError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+impl From<QuoteError> for MatcherError {
+    fn from(e: QuoteError) -> Self {
+        match e {
+            QuoteError::ZeroOraclePrice => MatcherError::ZeroOraclePrice,
+            QuoteError::OracleJump { .. } => MatcherError::OracleJump,
+            QuoteError::StatsRequired => MatcherError::StatsRequired,
+            QuoteError::InventoryOverflow { .. } => MatcherError::InventoryOverflow,
+            QuoteError::ReduceOnly { .. } => MatcherError::ReduceOnly,
+            QuoteError::InventoryLimit => MatcherError::InventoryLimit,
+        }
+    }
+}
+
+impl core::fmt::Display for MatcherError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} ({})", self.message(), *self as u32)
+    }
+}
@@ -0,0 +1,1255 @@
+//! Account and return-data layouts.
+//!
+//! Every offset the program reads or writes is a constant here. Below them,
+//! each layout is listed field by field, pads included, and checked at
+//! compile time to tile its region exactly: moving one constant without its
+//! neighbours, or growing a field into the next, fails the build. The tests
+//! hold the same tables against the ones documented in the crate root and the
+//! README, which is what clients build against.
+
+use crate::PARAMS_MAX_LEN;
+
+// Context magic: "PERCMATC"
+pub const MAGIC: u64 = 0x5045_5243_4d41_5443;
+pub const VERSION: u32 = 6;
+pub const VERSION_V5: u32 = 5;
+pub const KIND_PLAIN: u8 = 1;
+pub const KIND_CREDIBILITY: u8 = 2;
+pub const KIND_MULTI: u8 = 3;
+pub const KIND_STABLE: u8 = 4;
+
+// CreateContext derives the context from [CONTEXT_SEED, lp_pda, slab]
+pub const CONTEXT_SEED: &[u8] = b"context";
+
+// MatcherReturn layout (64 bytes): emitted with sol_set_return_data and, unless
+// CTX_FLAG_RETURN_DATA_ONLY is set, also written to the first 64 bytes of the
+// context account
+pub const RET_ABI_VERSION_OFF: usize = 0;
+pub const RET_FLAGS_OFF: usize = 4;
+pub const RET_EXEC_PRICE_OFF: usize = 8;
+pub const RET_EXEC_SIZE_OFF: usize = 16;
+pub const RET_REQ_ID_OFF: usize = 32;
+pub const RET_LP_ACCOUNT_ID_OFF: usize = 40;
+pub const RET_ORACLE_ECHO_OFF: usize = 48;
+pub const RET_REJECT_REASON_OFF: usize = 56;
+pub const RET_LEN: usize = 64;
+
+// Match return data: MatcherReturn, the quote breakdown, then the fill's
+// sequence number (0 when nothing filled, and on v5 contexts) and the hedge
+// target, the size that brings the book back within its neutral band (0 when
+// nothing filled)
+pub const MATCH_RET_FILL_SEQ_OFF: usize = RET_LEN + QUOTE_LEN;
+pub const MATCH_RET_HEDGE_OFF: usize = MATCH_RET_FILL_SEQ_OFF + 8;
+pub const MATCH_RET_LEN: usize = MATCH_RET_HEDGE_OFF + 16;
+
+pub const MATCHER_ABI_VERSION: u32 = 1;
+pub const FLAG_VALID: u32 = 1;
+
+// Matcher call input layout (67 bytes), optionally followed by the
+// referrer's pubkey
+pub const CALL_LEN: usize = 67;
+pub const CALL_REFERRER_OFF: usize = CALL_LEN;
+pub const REFERRED_CALL_LEN: usize = CALL_REFERRER_OFF + 32;
+
+// RFQ quote, the message the LP's signer signs off-chain and PostRfq carries
+// after its tag (72 bytes): the context it is for, the price, the size (signed
+// like a trade), the last slot it fills in and a nonce above every earlier one
+pub const RFQ_CTX_OFF: usize = 0;
+pub const RFQ_PRICE_OFF: usize = 32;
+pub const RFQ_SIZE_OFF: usize = 40;
+pub const RFQ_EXPIRY_OFF: usize = 56;
+pub const RFQ_NONCE_OFF: usize = 64;
+pub const RFQ_MSG_LEN: usize = 72;
+
+// Ed25519 program instruction data, as PostRfq reads the one before it: a
+// signature count, a pad byte, then per signature seven u16s locating the
+// signature, key and message (instruction index u16::MAX: in this data)
+pub const ED25519_OFFSETS_OFF: usize = 2;
+pub const ED25519_SIG_OFF: usize = 0;
+pub const ED25519_SIG_IX_OFF: usize = 2;
+pub const ED25519_KEY_OFF: usize = 4;
+pub const ED25519_KEY_IX_OFF: usize = 6;
+pub const ED25519_MSG_OFF: usize = 8;
+pub const ED25519_MSG_SIZE_OFF: usize = 10;
+pub const ED25519_MSG_IX_OFF: usize = 12;
+pub const ED25519_OFFSETS_LEN: usize = 14;
+
+// QuotePreview input: tag, oracle_price_e6 (u64), trade_size (i128).
+// QuoteTwoSided takes the same shape with an unsigned size.
+pub const PREVIEW_CALL_LEN: usize = 25;
+
+// QuoteTwoSided return data layout (sol_set_return_data, 384 bytes): a reject
+// reason per side (0 = quoted, else the MatcherError code), then each side's
+// quote breakdown (zeroed when that side is rejected)
+pub const TWO_SIDED_BID_REASON_OFF: usize = 0;
+pub const TWO_SIDED_ASK_REASON_OFF: usize = 8;
+pub const TWO_SIDED_BID_OFF: usize = 16;
+pub const TWO_SIDED_ASK_OFF: usize = TWO_SIDED_BID_OFF + QUOTE_LEN;
+pub const TWO_SIDED_LEN: usize = TWO_SIDED_ASK_OFF + QUOTE_LEN;
+
+// Quote breakdown (184 bytes): QuotePreview's whole return data, and appended
+// after the MatcherReturn in Match's
+pub const QUOTE_EXEC_PRICE_OFF: usize = 0;
+pub const QUOTE_COVERAGE_OFF: usize = 8;
+pub const QUOTE_TIER_OFF: usize = 16;
+pub const QUOTE_FILL_PCT_OFF: usize = 24;
+pub const QUOTE_TIER_SPREAD_OFF: usize = 32;
+pub const QUOTE_IMBALANCE_OFF: usize = 40;
+pub const QUOTE_IMPACT_OFF: usize = 48;
+pub const QUOTE_STALE_OFF: usize = 56;
+pub const QUOTE_CRANK_OFF: usize = 64;
+pub const QUOTE_LIQ_OFF: usize = 72;
+pub const QUOTE_SPREAD_OFF: usize = 80;
+pub const QUOTE_SKEW_OFF: usize = 88;
+pub const QUOTE_FUNDING_SKEW_OFF: usize = 96;
+pub const QUOTE_FEE_OFF: usize = 104;
+pub const QUOTE_NEW_INVENTORY_OFF: usize = 112;
+pub const QUOTE_FILL_SIZE_OFF: usize = 128;
+pub const QUOTE_DISCOUNT_OFF: usize = 144;
+pub const QUOTE_FLOW_OFF: usize = 152;
+pub const QUOTE_ANCHOR_OFF: usize = 160;
+pub const QUOTE_SIGNAL_OFF: usize = 168;
+pub const QUOTE_SCHEDULE_OFF: usize = 176;
+pub const QUOTE_ORACLE_OFF: usize = 184;
+pub const QUOTE_REBATE_OFF: usize = 192;
+pub const QUOTE_LEN: usize = 200;
+
+// Context offsets (relative to byte 64)
+pub const CTX_MAGIC_OFF: usize = 0;
+pub const CTX_VERSION_OFF: usize = 8;
+pub const CTX_KIND_OFF: usize = 12;
+pub const CTX_ORACLE_DECIMALS_OFF: usize = 13;
+pub const CTX_SPOT_WEIGHT_OFF: usize = 14;
+pub const CTX_LP_PDA_OFF: usize = 16;
+pub const CTX_BASE_FEE_OFF: usize = 48;
+pub const CTX_MIN_SPREAD_OFF: usize = 52;
+pub const CTX_MAX_SPREAD_OFF: usize = 56;
+pub const CTX_IMBALANCE_K_OFF: usize = 60;
+pub const CTX_LIQUIDITY_OFF: usize = 64;
+pub const CTX_MAX_FILL_OFF: usize = 80;
+pub const CTX_INVENTORY_OFF: usize = 96;
+pub const CTX_LAST_ORACLE_OFF: usize = 112;
+pub const CTX_LAST_EXEC_OFF: usize = 120;
+pub const CTX_MAX_INVENTORY_OFF: usize = 128;
+pub const CTX_INSURANCE_OFF: usize = 144;
+pub const CTX_TOTAL_OI_OFF: usize = 160;
+pub const CTX_MARKET_AGE_OFF: usize = 176;
+pub const CTX_LAST_DEFICIT_OFF: usize = 184;
+pub const CTX_SNAPSHOT_SLOT_OFF: usize = 192;
+pub const CTX_AGE_HALFLIFE_OFF: usize = 200;
+pub const CTX_INSURANCE_WEIGHT_OFF: usize = 204;
+pub const CTX_IMPACT_MODE_OFF: usize = 208;
+pub const CTX_COVERAGE_CURVE_OFF: usize = 209;
+pub const CTX_FLAGS_OFF: usize = 210;
+pub const CTX_BUMP_OFF: usize = 211;
+pub const CTX_IMPACT_K_OFF: usize = 212;
+pub const CTX_SKEW_K_OFF: usize = 216;
+pub const CTX_CURVE_POINTS_OFF: usize = 220;
+pub const CTX_PEG_BAND_OFF: usize = 228;
+pub const CTX_LAST_CRANK_OFF: usize = 232;
+pub const CTX_LAST_LIQS_OFF: usize = 240;
+pub const CTX_LIQ_HEAT_OFF: usize = 248;
+pub const CTX_LIQ_HALFLIFE_OFF: usize = 252;
+
+pub const CTX_BASE: usize = 64;
+pub const CTX_LEN: usize = 320;
+
+// Decimals SetOracleDecimals takes for the caller's price; 0 (the default)
+// and 6 are both e6
+pub const ORACLE_DECIMALS_E6: u8 = 6;
+pub const MAX_ORACLE_DECIMALS: u8 = 18;
+
+// Extension offsets (relative to byte 320, v6 only)
+pub const EXT_BASE: usize = 320;
+pub const EXT_LEN: usize = 512;
+pub const CTX_EXT_LEN: usize = EXT_BASE + EXT_LEN;
+pub const EXT_FUNDING_RATE_OFF: usize = 0;
+pub const EXT_FUNDING_HORIZON_OFF: usize = 8;
+pub const EXT_LIQ_AUCTION_SLOTS_OFF: usize = 12;
+pub const EXT_SLAB_OFF: usize = 16;
+pub const EXT_PENDING_SLOT_OFF: usize = 48;
+pub const EXT_PENDING_PARAMS_OFF: usize = 56;
+pub const EXT_AUTHORITY_OFF: usize = 160;
+pub const EXT_PENDING_AUTHORITY_OFF: usize = 192;
+pub const EXT_PERCOLATOR_OFF: usize = 224;
+pub const EXT_ORACLE_JUMP_BPS_OFF: usize = 256;
+pub const EXT_LIQ_AUCTION_BPS_OFF: usize = 258;
+pub const EXT_ORACLE_JUMP_WINDOW_OFF: usize = 260;
+pub const EXT_LAST_ORACLE_SLOT_OFF: usize = 264;
+pub const EXT_FILL_SEQ_OFF: usize = 272;
+pub const EXT_CUM_NOTIONAL_OFF: usize = 280;
+pub const EXT_CUM_FEES_OFF: usize = 296;
+pub const EXT_GROSS_LONG_OFF: usize = 312;
+pub const EXT_GROSS_SHORT_OFF: usize = 328;
+pub const EXT_FLOW_K_OFF: usize = 344;
+pub const EXT_TWAP_WINDOW_OFF: usize = 348;
+pub const EXT_PENDING_PARAMS_TAIL_OFF: usize = 352;
+pub const EXT_RFQ_SIGNER_OFF: usize = 384;
+pub const EXT_STATS_OFF: usize = 416;
+pub const EXT_SIGNAL_WEIGHTS_OFF: usize = 448;
+pub const EXT_DEFICIT_HALFLIFE_OFF: usize = 456;
+pub const EXT_MARKET_SLOTS_OFF: usize = 460;
+pub const EXT_RFQ_PRICE_OFF: usize = 464;
+pub const EXT_RFQ_EXPIRY_OFF: usize = 472;
+pub const EXT_RFQ_NONCE_OFF: usize = 480;
+pub const EXT_RFQ_SIZE_OFF: usize = 488;
+pub const EXT_LAST_LIQ_SLOT_OFF: usize = 504;
+
+// Shared contexts: a v6 context initialized in an account of at least
+// CTX_SHARED_LEN bytes keeps a table of MAX_MARKETS market books after the
+// extension, one per percolator slab it quotes, and EXT_MARKET_SLOTS_OFF says
+// how many. Each book holds what the single-market layout keeps once for the
+// whole context, plus the market's own caps over max_fill and max_inventory
+// (0 = the context's). An entry with the default slab key is free.
+pub const MARKETS_BASE: usize = CTX_EXT_LEN;
+pub const MARKET_LEN: usize = 256;
+pub const MAX_MARKETS: usize = 8;
+pub const CTX_SHARED_LEN: usize = MARKETS_BASE + MAX_MARKETS * MARKET_LEN;
+
+// Creation record (v6): CREATION_LEN bytes right after the layout, at
+// CTX_EXT_LEN on a single-market context and CTX_SHARED_LEN on a shared one,
+// in any account created that much longer, by CreateContext or at a keypair
+// address. CreateContext sets `derived` (1) on the account it makes; Init
+// writes the slot and the key that signed it (the authority, or the LP PDA).
+// Nothing changes them after.
+pub const CREATION_SLOT_OFF: usize = 0;
+pub const CREATION_CREATOR_OFF: usize = 8;
+pub const CREATION_DERIVED_OFF: usize = 40;
+pub const CREATION_LEN: usize = 48;
+pub const MKT_SLAB_OFF: usize = 0;
+pub const MKT_MAX_FILL_OFF: usize = 32;
+pub const MKT_MAX_INVENTORY_OFF: usize = 48;
+pub const MKT_INVENTORY_OFF: usize = 64;
+pub const MKT_INSURANCE_OFF: usize = 80;
+pub const MKT_TOTAL_OI_OFF: usize = 96;
+pub const MKT_GROSS_LONG_OFF: usize = 112;
+pub const MKT_GROSS_SHORT_OFF: usize = 128;
+pub const MKT_SNAPSHOT_SLOT_OFF: usize = 144;
+pub const MKT_LAST_CRANK_OFF: usize = 152;
+pub const MKT_LAST_LIQS_OFF: usize = 160;
+pub const MKT_FUNDING_RATE_OFF: usize = 168;
+pub const MKT_MARKET_AGE_OFF: usize = 176;
+pub const MKT_LAST_DEFICIT_OFF: usize = 184;
+pub const MKT_LAST_LIQ_SLOT_OFF: usize = 192;
+pub const MKT_LAST_ORACLE_OFF: usize = 200;
+pub const MKT_LAST_ORACLE_SLOT_OFF: usize = 208;
+pub const MKT_LAST_EXEC_OFF: usize = 216;
+pub const MKT_LIQ_HEAT_OFF: usize = 224;
+
+// A proposal is stored split: the first PENDING_HEAD_LEN bytes of the wire
+// block where v6 first kept it, the rest in the tail slot
+pub const PENDING_HEAD_LEN: usize = 104;
+pub const PENDING_TAIL_LEN: usize = 32;
+
+// Stats account layout (a separate account bound to a v6 context by InitStats):
+// a 64-byte header, a ring of the last STATS_CAPACITY fills, then a ring of the
+// last ORACLE_CAPACITY oracle prices
+pub const STATS_MAGIC: u64 = 0x5045_5243_5354_4154; // "PERCSTAT"
+pub const STATS_VERSION: u32 = 1;
+pub const STATS_MAGIC_OFF: usize = 0;
+pub const STATS_VERSION_OFF: usize = 8;
+pub const STATS_CAPACITY_OFF: usize = 12;
+pub const STATS_CTX_OFF: usize = 16;
+pub const STATS_HEAD_OFF: usize = 48;
+pub const STATS_COUNT_OFF: usize = 52;
+pub const STATS_ORACLE_HEAD_OFF: usize = 56;
+pub const STATS_ORACLE_COUNT_OFF: usize = 60;
+pub const STATS_HEADER_LEN: usize = 64;
+pub const STATS_CAPACITY: usize = 64;
+// Ring entry: fill_seq, slot, exec_size (i128), exec_price_e6, spread_bps
+pub const FILL_SEQ_OFF: usize = 0;
+pub const FILL_SLOT_OFF: usize = 8;
+pub const FILL_SIZE_OFF: usize = 16;
+pub const FILL_PRICE_OFF: usize = 32;
+pub const FILL_SPREAD_OFF: usize = 40;
+pub const FILL_LEN: usize = 48;
+// Oracle sample: slot, oracle_price_e6
+pub const ORACLE_CAPACITY: usize = 32;
+pub const ORACLE_SLOT_OFF: usize = 0;
+pub const ORACLE_PRICE_OFF: usize = 8;
+pub const ORACLE_SAMPLE_LEN: usize = 16;
+pub const STATS_ORACLE_RING_OFF: usize = STATS_HEADER_LEN + STATS_CAPACITY * FILL_LEN;
+pub const STATS_LEN: usize = STATS_ORACLE_RING_OFF + ORACLE_CAPACITY * ORACLE_SAMPLE_LEN;
+// Fee ledger: a stats account of STATS_LEDGER_LEN bytes or more also records
+// what RecordWithdrawal says the LP took out of the context's cum_fees: the
+// total, how many withdrawals and the slot of the last
+pub const STATS_WITHDRAWN_OFF: usize = STATS_LEN;
+pub const STATS_WITHDRAWALS_OFF: usize = STATS_WITHDRAWN_OFF + 16;
+pub const STATS_LAST_WITHDRAWAL_OFF: usize = STATS_WITHDRAWALS_OFF + 8;
+pub const STATS_LEDGER_LEN: usize = STATS_LAST_WITHDRAWAL_OFF + 8;
+// Insurance skim: one of STATS_SKIM_LEN bytes or more also earmarks skim_bps
+// of each fill's spread for percolator's insurance fund, accrued here until
+// ClaimSkim pays it in: the rate, the total accrued, the total claimed and
+// how many claims
+pub const STATS_SKIM_BPS_OFF: usize = STATS_LEDGER_LEN;
+pub const STATS_SKIM_ACCRUED_OFF: usize = STATS_SKIM_BPS_OFF + 8;
+pub const STATS_SKIM_CLAIMED_OFF: usize = STATS_SKIM_ACCRUED_OFF + 16;
+pub const STATS_SKIM_CLAIMS_OFF: usize = STATS_SKIM_CLAIMED_OFF + 16;
+pub const STATS_SKIM_LEN: usize = STATS_SKIM_CLAIMS_OFF + 8;
+// Protocol fee: one of STATS_PROTOCOL_LEN bytes or more, given a treasury and
+// protocol_fee_bps by InitStats, also accrues protocol_fee_bps of each fill's
+// fee to the treasury, apart from the LP's share, until SweepProtocolFee
+// records it paid: the treasury, the rate, the total accrued, the total swept
+// and how many sweeps
+pub const STATS_TREASURY_OFF: usize = STATS_SKIM_LEN;
+pub const STATS_PROTOCOL_FEE_BPS_OFF: usize = STATS_TREASURY_OFF + 32;
+pub const STATS_PROTOCOL_ACCRUED_OFF: usize = STATS_PROTOCOL_FEE_BPS_OFF + 8;
+pub const STATS_PROTOCOL_SWEPT_OFF: usize = STATS_PROTOCOL_ACCRUED_OFF + 16;
+pub const STATS_PROTOCOL_SWEEPS_OFF: usize = STATS_PROTOCOL_SWEPT_OFF + 16;
+pub const STATS_PROTOCOL_LEN: usize = STATS_PROTOCOL_SWEEPS_OFF + 8;
+// Inventory PnL: one of STATS_PNL_LEN bytes or more also marks the book to
+// the oracle on every fill: the average entry price of the inventory, the
+// oracle it was last marked at, the PnL realized by fills that shrank it and
+// the unrealized PnL of what's left, in quote units
+pub const STATS_PNL_ENTRY_OFF: usize = STATS_PROTOCOL_LEN;
+pub const STATS_PNL_MARK_OFF: usize = STATS_PNL_ENTRY_OFF + 8;
+pub const STATS_PNL_REALIZED_OFF: usize = STATS_PNL_MARK_OFF + 8;
+pub const STATS_PNL_UNREALIZED_OFF: usize = STATS_PNL_REALIZED_OFF + 16;
+pub const STATS_PNL_LEN: usize = STATS_PNL_UNREALIZED_OFF + 16;
+
+// Signal account layout: any account, owned by any program, that starts with
+// SIGNAL_MAGIC. The LP registers up to MAX_SIGNAL_ACCOUNTS of them in its
+// schedule, and every trade passes those after the stats account; each widens
+// the spread by value_bps * weight_bps / 10000 until its stale_after_slot has
+// passed
+pub const SIGNAL_MAGIC: u64 = 0x5045_5243_5349_474e; // "PERCSIGN"
+pub const SIGNAL_MAGIC_OFF: usize = 0;
+pub const SIGNAL_VALUE_OFF: usize = 8;
+pub const SIGNAL_WEIGHT_OFF: usize = 12;
+pub const SIGNAL_STALE_AFTER_OFF: usize = 16;
+pub const SIGNAL_LEN: usize = 32;
+pub const MAX_SIGNAL_ACCOUNTS: usize = 4;
+
+// Schedule account layout (a separate account bound to a context by
+// InitSchedule, which sets CTX_FLAG_SCHEDULED): a 48-byte header, then up to
+// MAX_SCHEDULE_ENTRIES slot ranges, each multiplying the spread by
+// multiplier_bps / 10000 from its start_slot through its end_slot
+pub const SCHEDULE_MAGIC: u64 = 0x5045_5243_5343_4844; // "PERCSCHD"
+pub const SCHEDULE_VERSION: u32 = 1;
+pub const SCHEDULE_MAGIC_OFF: usize = 0;
+pub const SCHEDULE_VERSION_OFF: usize = 8;
+pub const SCHEDULE_COUNT_OFF: usize = 12;
+pub const SCHEDULE_CTX_OFF: usize = 16;
+pub const SCHEDULE_HEADER_LEN: usize = 48;
+pub const MAX_SCHEDULE_ENTRIES: usize = 8;
+// Entry: start_slot, end_slot, multiplier_bps
+pub const SCHED_START_OFF: usize = 0;
+pub const SCHED_END_OFF: usize = 8;
+pub const SCHED_MULT_OFF: usize = 16;
+pub const SCHED_ENTRY_LEN: usize = 24;
+pub const SCHEDULE_LEN: usize = SCHEDULE_HEADER_LEN + MAX_SCHEDULE_ENTRIES * SCHED_ENTRY_LEN;
+// SetSchedule's entries: start_slot, end_slot, multiplier_bps, unpadded
+pub const SCHED_ARG_LEN: usize = 20;
+// Fee tiers: a schedule account of SCHEDULE_FEES_LEN bytes or more also holds
+// up to MAX_FEE_TIERS notional tiers after its entries, each charging fee_bps
+// in place of base_fee_bps on trades of at least min_notional (0 = unused)
+pub const FEE_TIERS_OFF: usize = SCHEDULE_LEN;
+pub const MAX_FEE_TIERS: usize = 4;
+pub const FEE_TIER_NOTIONAL_OFF: usize = 0;
+pub const FEE_TIER_FEE_OFF: usize = 16;
+pub const FEE_TIER_LEN: usize = 24;
+pub const SCHEDULE_FEES_LEN: usize = FEE_TIERS_OFF + MAX_FEE_TIERS * FEE_TIER_LEN;
+// SetFeeTiers' tiers: min_notional, fee_bps, unpadded
+pub const FEE_TIER_ARG_LEN: usize = 20;
+// Rebate: a schedule account of SCHEDULE_REBATE_LEN bytes or more also holds
+// the rebate_bps taken off the cost of fills that shrink |inventory|, and the
+// share of the fee waived at the full credibility discount
+pub const SCHEDULE_REBATE_OFF: usize = SCHEDULE_FEES_LEN;
+pub const SCHEDULE_FEE_DISCOUNT_OFF: usize = SCHEDULE_REBATE_OFF + 2;
+pub const SCHEDULE_REBATE_LEN: usize = SCHEDULE_REBATE_OFF + 8;
+// Minimum notional: a schedule account of SCHEDULE_MIN_NOTIONAL_LEN bytes or
+// more also holds the notional at the oracle (as the fee tiers figure it)
+// below which a trade is refused as dust (0 = none)
+pub const SCHEDULE_MIN_NOTIONAL_OFF: usize = SCHEDULE_REBATE_LEN;
+pub const SCHEDULE_MIN_NOTIONAL_LEN: usize = SCHEDULE_MIN_NOTIONAL_OFF + 16;
+// Loyalty tiers: a schedule account of SCHEDULE_LOYALTY_LEN bytes or more
+// also holds up to MAX_LOYALTY_TIERS volume tiers, each taking discount_bps
+// off the fee of a trader whose account has traded at least min_volume with
+// the context (0 = unused). Laid out like the fee tiers
+pub const LOYALTY_TIERS_OFF: usize = SCHEDULE_MIN_NOTIONAL_LEN;
+pub const MAX_LOYALTY_TIERS: usize = 4;
+pub const LOYALTY_TIER_VOLUME_OFF: usize = 0;
+pub const LOYALTY_TIER_DISCOUNT_OFF: usize = 16;
+pub const LOYALTY_TIER_LEN: usize = 24;
+pub const SCHEDULE_LOYALTY_LEN: usize = LOYALTY_TIERS_OFF + MAX_LOYALTY_TIERS * LOYALTY_TIER_LEN;
+// SetLoyaltyTiers' tiers: min_volume, discount_bps, unpadded
+pub const LOYALTY_TIER_ARG_LEN: usize = 20;
+// Inventory decay: a schedule account of SCHEDULE_DECAY_LEN bytes or more also
+// holds the slots over which the inventory the imbalance and skew penalties
+// see halves while no fill comes in (0 = no decay)
+pub const SCHEDULE_INVENTORY_HALFLIFE_OFF: usize = SCHEDULE_LOYALTY_LEN;
+pub const SCHEDULE_DECAY_LEN: usize = SCHEDULE_INVENTORY_HALFLIFE_OFF + 8;
+// Inventory band: a schedule account of SCHEDULE_BAND_LEN bytes or more also
+// holds the soft inventory limit past which fills that go deeper pay up to
+// band_k_bps more, max_inventory staying the hard limit (0 = no band)
+pub const SCHEDULE_SOFT_INVENTORY_OFF: usize = SCHEDULE_DECAY_LEN;
+pub const SCHEDULE_BAND_K_OFF: usize = SCHEDULE_SOFT_INVENTORY_OFF + 16;
+pub const SCHEDULE_BAND_LEN: usize = SCHEDULE_BAND_K_OFF + 8;
+// Inventory caps: a schedule account of SCHEDULE_CAPS_LEN bytes or more also
+// holds separate caps on a long and a short book (0 = max_inventory)
+pub const SCHEDULE_MAX_LONG_OFF: usize = SCHEDULE_BAND_LEN;
+pub const SCHEDULE_MAX_SHORT_OFF: usize = SCHEDULE_MAX_LONG_OFF + 16;
+pub const SCHEDULE_CAPS_LEN: usize = SCHEDULE_MAX_SHORT_OFF + 16;
+// Reduce-only threshold: a schedule account of SCHEDULE_REDUCE_ONLY_LEN bytes
+// or more also holds the share of the cap, in bps, past which the book goes
+// reduce-only on its own (0 = AUTO_REDUCE_ONLY_BPS)
+pub const SCHEDULE_REDUCE_ONLY_BPS_OFF: usize = SCHEDULE_CAPS_LEN;
+pub const SCHEDULE_REDUCE_ONLY_LEN: usize = SCHEDULE_REDUCE_ONLY_BPS_OFF + 8;
+// Depth-relative cap: a schedule account of SCHEDULE_DEPTH_CAP_LEN bytes or
+// more also holds max_inventory in bps of liquidity_notional_e6, converted at
+// the oracle; 0 = max_inventory_abs as set
+pub const SCHEDULE_MAX_INVENTORY_BPS_OFF: usize = SCHEDULE_REDUCE_ONLY_LEN;
+pub const SCHEDULE_DEPTH_CAP_LEN: usize = SCHEDULE_MAX_INVENTORY_BPS_OFF + 8;
+// Signal registry: a schedule account of SCHEDULE_SIGNALS_LEN bytes or more
+// also holds the signal accounts SetSignals registered, each with the program
+// that owned it then; trades must pass exactly the first count of them, in
+// order
+pub const SCHEDULE_SIGNAL_COUNT_OFF: usize = SCHEDULE_DEPTH_CAP_LEN;
+pub const SCHEDULE_SIGNALS_OFF: usize = SCHEDULE_SIGNAL_COUNT_OFF + 8;
+pub const SIGNAL_ENTRY_KEY_OFF: usize = 0;
+pub const SIGNAL_ENTRY_OWNER_OFF: usize = 32;
+pub const SIGNAL_ENTRY_LEN: usize = 64;
+pub const SCHEDULE_SIGNALS_LEN: usize = SCHEDULE_SIGNALS_OFF + MAX_SIGNAL_ACCOUNTS * SIGNAL_ENTRY_LEN;
+
+// Ladder account layout (a separate account bound to a context by InitLadder
+// and written by PublishLadder): a 96-byte header, then MAX_LADDER_LEVELS bid
+// levels and as many ask levels. Level i prices one trade of i * step_size
+pub const LADDER_MAGIC: u64 = 0x5045_5243_4c41_4452; // "PERCLADR"
+pub const LADDER_VERSION: u32 = 1;
+pub const LADDER_MAGIC_OFF: usize = 0;
+pub const LADDER_VERSION_OFF: usize = 8;
+pub const LADDER_MARKET_OFF: usize = 12;
+pub const LADDER_CTX_OFF: usize = 16;
+pub const LADDER_SLOT_OFF: usize = 48;
+pub const LADDER_ORACLE_OFF: usize = 56;
+pub const LADDER_STEP_OFF: usize = 64;
+pub const LADDER_BID_COUNT_OFF: usize = 80;
+pub const LADDER_ASK_COUNT_OFF: usize = 84;
+pub const LADDER_BID_REASON_OFF: usize = 88;
+pub const LADDER_ASK_REASON_OFF: usize = 92;
+pub const LADDER_HEADER_LEN: usize = 96;
+pub const MAX_LADDER_LEVELS: usize = 8;
+// Level: size (unsigned, after clipping), exec_price_e6, spread_bps
+pub const LEVEL_SIZE_OFF: usize = 0;
+pub const LEVEL_PRICE_OFF: usize = 16;
+pub const LEVEL_SPREAD_OFF: usize = 24;
+pub const LEVEL_LEN: usize = 32;
+pub const LADDER_BIDS_OFF: usize = LADDER_HEADER_LEN;
+pub const LADDER_ASKS_OFF: usize = LADDER_BIDS_OFF + MAX_LADDER_LEVELS * LEVEL_LEN;
+pub const LADDER_LEN: usize = LADDER_ASKS_OFF + MAX_LADDER_LEVELS * LEVEL_LEN;
+
+// Tip jar layout (a separate account bound to a context by InitTipJar): the
+// tip UpdateCredibility pays a keeper per slot the snapshot was stale past the
+// grace period, its cap, and what it has paid. Its lamports above rent
+// exemption are the escrow the tips come out of
+pub const TIP_JAR_MAGIC: u64 = 0x5045_5243_5449_5053; // "PERCTIPS"
+pub const TIP_JAR_VERSION: u32 = 1;
+pub const TIP_JAR_MAGIC_OFF: usize = 0;
+pub const TIP_JAR_VERSION_OFF: usize = 8;
+pub const TIP_JAR_CTX_OFF: usize = 16;
+pub const TIP_JAR_PER_SLOT_OFF: usize = 48;
+pub const TIP_JAR_MAX_OFF: usize = 56;
+pub const TIP_JAR_PAID_OFF: usize = 64;
+pub const TIP_JAR_COUNT_OFF: usize = 72;
+pub const TIP_JAR_LEN: usize = 80;
+
+// Referral account layout (a separate account bound to a context by
+// InitReferral): the share of each fill's fee Match credits to the referrer
+// named in its data, then a table of what each referrer has been credited
+pub const REFERRAL_MAGIC: u64 = 0x5045_5243_5245_4653; // "PERCREFS"
+pub const REFERRAL_VERSION: u32 = 1;
+pub const REFERRAL_MAGIC_OFF: usize = 0;
+pub const REFERRAL_VERSION_OFF: usize = 8;
+pub const REFERRAL_CTX_OFF: usize = 16;
+pub const REFERRAL_SHARE_OFF: usize = 48;
+pub const REFERRAL_COUNT_OFF: usize = 50;
+pub const REFERRAL_HEADER_LEN: usize = 56;
+pub const MAX_REFERRERS: usize = 16;
+// Referrer: its key, the fees credited to it (notional units, like
+// cum_fees) and the fills it referred
+pub const REFERRER_KEY_OFF: usize = 0;
+pub const REFERRER_ACCRUED_OFF: usize = 32;
+pub const REFERRER_FILLS_OFF: usize = 48;
+pub const REFERRER_LEN: usize = 56;
+pub const REFERRAL_LEN: usize = REFERRAL_HEADER_LEN + MAX_REFERRERS * REFERRER_LEN;
+
+// Trader account layout (a separate account a trader binds to a context and
+// to their own key with InitTrader): the notional of every fill Match
+// recorded for them (figured like cum_notional), how many and the slot of
+// the last, for the schedule's loyalty tiers
+pub const TRADER_MAGIC: u64 = 0x5045_5243_5452_4452; // "PERCTRDR"
+pub const TRADER_VERSION: u32 = 1;
+pub const TRADER_MAGIC_OFF: usize = 0;
+pub const TRADER_VERSION_OFF: usize = 8;
+pub const TRADER_CTX_OFF: usize = 16;
+pub const TRADER_KEY_OFF: usize = 48;
+pub const TRADER_VOLUME_OFF: usize = 80;
+pub const TRADER_FILLS_OFF: usize = 96;
+pub const TRADER_LAST_SLOT_OFF: usize = 104;
+pub const TRADER_LEN: usize = 112;
+
+// Feed account layout (a separate account bound to a context by InitFeed,
+// which sets CTX_FLAG_FEED): the oracle feeds Match takes the median of, how
+// old a price it still takes (in seconds, and in slots since it was posted),
+// how far apart the feeds may be, and whether they are instead a fallback
+// order (each with the spread it adds when priced off). Each entry
+// names a feed and the program that publishes it: a Pyth feed by its feed
+// id (or, pinned, by its price account's key), a Switchboard or Chainlink
+// one by its account's key
+pub const FEED_MAGIC: u64 = 0x5045_5243_4645_4544; // "PERCFEED"
+pub const FEED_VERSION: u32 = 1;
+pub const FEED_MAGIC_OFF: usize = 0;
+pub const FEED_VERSION_OFF: usize = 8;
+pub const FEED_MAX_AGE_OFF: usize = 12;
+pub const FEED_CTX_OFF: usize = 16;
+pub const FEED_COUNT_OFF: usize = 48;
+pub const FEED_WIDEN_OFF: usize = 49;
+pub const FEED_MAX_DIVERGENCE_OFF: usize = 50;
+pub const FEED_MAX_AGE_SLOTS_OFF: usize = 52;
+pub const FEED_SLOT_WIDEN_OFF: usize = 54;
+pub const FEED_FALLBACK_OFF: usize = 55;
+pub const FEED_HEADER_LEN: usize = 56;
+pub const MAX_FEEDS: usize = 3;
+pub const FEED_ENTRY_ID_OFF: usize = 0;
+pub const FEED_ENTRY_SOURCE_OFF: usize = 32;
+pub const FEED_ENTRY_PINNED_OFF: usize = 33;
+pub const FEED_ENTRY_PENALTY_OFF: usize = 34;
+pub const FEED_ENTRY_LEN: usize = 40;
+pub const FEED_LEN: usize = FEED_HEADER_LEN + MAX_FEEDS * FEED_ENTRY_LEN;
+// Feed sources
+pub const FEED_SOURCE_PYTH: u8 = 0;
+pub const FEED_SOURCE_SWITCHBOARD: u8 = 1;
+pub const FEED_SOURCE_CHAINLINK: u8 = 2;
+
+// Pyth PriceUpdateV2 account (pyth-solana-receiver), as Match reads it: an
+// Anchor discriminator, the write authority, a fully verified update (with a
+// partial one the fields after it sit a byte later), then the price message
+// and the slot the update was posted at
+pub const PYTH_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+pub const PYTH_VERIFICATION_OFF: usize = 40;
+pub const PYTH_VERIFIED_FULL: u8 = 1;
+pub const PYTH_FEED_ID_OFF: usize = 41;
+pub const PYTH_PRICE_OFF: usize = 73;
+pub const PYTH_CONF_OFF: usize = 81;
+pub const PYTH_EXPONENT_OFF: usize = 89;
+pub const PYTH_PUBLISH_TIME_OFF: usize = 93;
+pub const PYTH_POSTED_SLOT_OFF: usize = 125;
+pub const PYTH_MIN_LEN: usize = 133;
+
+// Switchboard on-demand PullFeedAccountData, as Match reads it: an Anchor
+// discriminator, 32 oracle submissions of 64 bytes, the feed's config (the
+// last update's unix time among it), then the current result, whose value is
+// an i128 scaled by 1e18, and the slot it was taken at
+pub const SWITCHBOARD_DISCRIMINATOR: [u8; 8] = [196, 27, 108, 196, 10, 215, 219, 40];
+pub const SWITCHBOARD_UPDATED_OFF: usize = 2216;
+pub const SWITCHBOARD_VALUE_OFF: usize = 2264;
+pub const SWITCHBOARD_SLOT_OFF: usize = 2368;
+pub const SWITCHBOARD_MIN_LEN: usize = SWITCHBOARD_SLOT_OFF + 8;
+
+// Chainlink OCR2 feed (the store program's Transmissions account), as Match
+// reads it: an Anchor discriminator and a 192-byte header (the answer's
+// decimals, the latest round id, and the live ring's length and cursor among
+// it), then the live ring of 48-byte transmissions. The latest round is the
+// one before the cursor: its slot, unix time (u32) and answer (i128)
+pub const CHAINLINK_DISCRIMINATOR: [u8; 8] = [96, 179, 69, 66, 128, 129, 73, 117];
+pub const CHAINLINK_DECIMALS_OFF: usize = 138;
+pub const CHAINLINK_ROUND_ID_OFF: usize = 143;
+pub const CHAINLINK_LIVE_LENGTH_OFF: usize = 148;
+pub const CHAINLINK_LIVE_CURSOR_OFF: usize = 152;
+pub const CHAINLINK_HEADER_LEN: usize = 200;
+pub const CHAINLINK_SLOT_OFF: usize = 0;
+pub const CHAINLINK_TIMESTAMP_OFF: usize = 8;
+pub const CHAINLINK_ANSWER_OFF: usize = 16;
+pub const CHAINLINK_TRANSMISSION_LEN: usize = 48;
+pub const CHAINLINK_MIN_LEN: usize = CHAINLINK_HEADER_LEN + CHAINLINK_TRANSMISSION_LEN;
+
+// Percolator slab, as UpdateCredibility reads it: a 72-byte header (the admin
+// key at 16; zero or the system program once burned), a 320-byte config, then
+// the risk engine, whose first 400 bytes must be present
+pub const SLAB_ADMIN_OFF: usize = 16;
+pub const SLAB_HEADER_LEN: usize = 72;
+pub const SLAB_CONFIG_LEN: usize = 320;
+pub const SLAB_ENGINE_OFF: usize = SLAB_HEADER_LEN + SLAB_CONFIG_LEN;
+pub const SLAB_ENGINE_LEN: usize = 400;
+pub const SLAB_MIN_LEN: usize = SLAB_ENGINE_OFF + SLAB_ENGINE_LEN;
+// Engine fields (relative to SLAB_ENGINE_OFF)
+pub const ENGINE_INSURANCE_OFF: usize = 16;
+pub const ENGINE_FUNDING_RATE_OFF: usize = 224;
+pub const ENGINE_LAST_CRANK_OFF: usize = 232;
+pub const ENGINE_TOTAL_OI_OFF: usize = 248;
+pub const ENGINE_LIFETIME_LIQS_OFF: usize = 328;
+// Percolator's account table, as SyncInventory reads it: SLAB_ACCOUNT_LEN
+// bytes per account from ENGINE_ACCOUNTS_OFF into the engine, indexed like
+// a Match's lp_idx
+pub const ENGINE_ACCOUNTS_OFF: usize = 9136;
+pub const SLAB_ACCOUNTS_OFF: usize = SLAB_ENGINE_OFF + ENGINE_ACCOUNTS_OFF;
+pub const SLAB_ACCOUNT_LEN: usize = 240;
+// Account fields (relative to the account)
+pub const ACCT_KIND_OFF: usize = 24;
+pub const ACCT_POSITION_OFF: usize = 80;
+pub const ACCT_MATCHER_PROGRAM_OFF: usize = 120;
+pub const ACCT_MATCHER_CONTEXT_OFF: usize = 152;
+/// ACCT_KIND_OFF of an LP account (users are 0).
+pub const ACCT_KIND_LP: u8 = 1;
+
+// Context flags (CTX_FLAGS_OFF)
+pub const CTX_FLAG_PAUSED: u8 = 1 << 0;
+pub const CTX_FLAG_REDUCE_ONLY: u8 = 1 << 1;
+pub const CTX_FLAG_RETURN_DATA_ONLY: u8 = 1 << 2;
+pub const CTX_FLAG_SOFT_REJECT: u8 = 1 << 3;
+pub const CTX_FLAG_NO_BIDS: u8 = 1 << 4;
+pub const CTX_FLAG_NO_ASKS: u8 = 1 << 5;
+pub const CTX_FLAG_SCHEDULED: u8 = 1 << 6;
+pub const CTX_FLAG_FEED: u8 = 1 << 7;
+
+// SettleInventory modes
+pub const SETTLE_SET: u8 = 0;
+pub const SETTLE_ADJUST: u8 = 1;
+
+
+// =============================================================================
+// Field tables — name, offset, size — checked to cover each region exactly
+// =============================================================================
+
+/// A named byte range within a layout.
+pub type Field = (&'static str, usize, usize);
+
+/// Whether `fields`, in order, cover `0..len` with no gap or overlap.
+const fn tiles(fields: &[Field], len: usize) -> bool {
+    let mut end = 0;
+    let mut i = 0;
+    while i < fields.len() {
+        if fields[i].1 != end {
+            return false;
+        }
+        end += fields[i].2;
+        i += 1;
+    }
+    end == len
+}
+
+pub const RET_FIELDS: &[Field] = &[
+    ("abi_version", RET_ABI_VERSION_OFF, 4),
+    ("flags", RET_FLAGS_OFF, 4),
+    ("exec_price_e6", RET_EXEC_PRICE_OFF, 8),
+    ("exec_size", RET_EXEC_SIZE_OFF, 16),
+    ("req_id", RET_REQ_ID_OFF, 8),
+    ("lp_account_id", RET_LP_ACCOUNT_ID_OFF, 8),
+    ("oracle_price_e6", RET_ORACLE_ECHO_OFF, 8),
+    ("reject_reason", RET_REJECT_REASON_OFF, 8),
+];
+
+pub const MATCH_RET_FIELDS: &[Field] = &[
+    ("matcher_return", 0, RET_LEN),
+    ("breakdown", RET_LEN, QUOTE_LEN),
+    ("fill_seq", MATCH_RET_FILL_SEQ_OFF, 8),
+    ("hedge_size", MATCH_RET_HEDGE_OFF, 16),
+];
+
+pub const QUOTE_FIELDS: &[Field] = &[
+    ("exec_price_e6", QUOTE_EXEC_PRICE_OFF, 8),
+    ("coverage_bps", QUOTE_COVERAGE_OFF, 8),
+    ("tier", QUOTE_TIER_OFF, 8),
+    ("fill_pct", QUOTE_FILL_PCT_OFF, 8),
+    ("tier_spread_bps", QUOTE_TIER_SPREAD_OFF, 8),
+    ("imbalance_bps", QUOTE_IMBALANCE_OFF, 8),
+    ("impact_bps", QUOTE_IMPACT_OFF, 8),
+    ("stale_bps", QUOTE_STALE_OFF, 8),
+    ("crank_bps", QUOTE_CRANK_OFF, 8),
+    ("liq_bps", QUOTE_LIQ_OFF, 8),
+    ("spread_bps", QUOTE_SPREAD_OFF, 8),
+    ("skew_bps", QUOTE_SKEW_OFF, 8),
+    ("funding_skew_bps", QUOTE_FUNDING_SKEW_OFF, 8),
+    ("fee_bps", QUOTE_FEE_OFF, 8),
+    ("new_inventory", QUOTE_NEW_INVENTORY_OFF, 16),
+    ("fill_size", QUOTE_FILL_SIZE_OFF, 16),
+    ("discount_bps", QUOTE_DISCOUNT_OFF, 8),
+    ("flow_bps", QUOTE_FLOW_OFF, 8),
+    ("anchor_price_e6", QUOTE_ANCHOR_OFF, 8),
+    ("signal_bps", QUOTE_SIGNAL_OFF, 8),
+    ("schedule_bps", QUOTE_SCHEDULE_OFF, 8),
+    ("oracle_bps", QUOTE_ORACLE_OFF, 8),
+    ("rebate_bps", QUOTE_REBATE_OFF, 8),
+];
+
+pub const TWO_SIDED_FIELDS: &[Field] = &[
+    ("bid_reason", TWO_SIDED_BID_REASON_OFF, 8),
+    ("ask_reason", TWO_SIDED_ASK_REASON_OFF, 8),
+    ("bid", TWO_SIDED_BID_OFF, QUOTE_LEN),
+    ("ask", TWO_SIDED_ASK_OFF, QUOTE_LEN),
+];
+
+/// The context, relative to `CTX_BASE`; names as in the crate-root table.
+pub const CTX_FIELDS: &[Field] = &[
+    ("magic", CTX_MAGIC_OFF, 8),
+    ("version", CTX_VERSION_OFF, 4),
+    ("kind", CTX_KIND_OFF, 1),
+    ("oracle_decimals", CTX_ORACLE_DECIMALS_OFF, 1),
+    ("spot_weight_bps", CTX_SPOT_WEIGHT_OFF, 2),
+    ("lp_pda", CTX_LP_PDA_OFF, 32),
+    ("base_fee_bps", CTX_BASE_FEE_OFF, 4),
+    ("min_spread_bps", CTX_MIN_SPREAD_OFF, 4),
+    ("max_spread_bps", CTX_MAX_SPREAD_OFF, 4),
+    ("imbalance_k_bps", CTX_IMBALANCE_K_OFF, 4),
+    ("liquidity_notional_e6", CTX_LIQUIDITY_OFF, 16),
+    ("max_fill_abs", CTX_MAX_FILL_OFF, 16),
+    ("inventory_base", CTX_INVENTORY_OFF, 16),
+    ("last_oracle_price_e6", CTX_LAST_ORACLE_OFF, 8),
+    ("last_exec_price_e6", CTX_LAST_EXEC_OFF, 8),
+    ("max_inventory_abs", CTX_MAX_INVENTORY_OFF, 16),
+    ("insurance_snapshot", CTX_INSURANCE_OFF, 16),
+    ("total_oi_snapshot", CTX_TOTAL_OI_OFF, 16),
+    ("market_age_slots", CTX_MARKET_AGE_OFF, 8),
+    ("last_deficit_slot", CTX_LAST_DEFICIT_OFF, 8),
+    ("snapshot_slot", CTX_SNAPSHOT_SLOT_OFF, 8),
+    ("age_halflife_slots", CTX_AGE_HALFLIFE_OFF, 4),
+    ("insurance_weight_bps", CTX_INSURANCE_WEIGHT_OFF, 4),
+    ("impact_mode", CTX_IMPACT_MODE_OFF, 1),
+    ("coverage_curve", CTX_COVERAGE_CURVE_OFF, 1),
+    ("flags", CTX_FLAGS_OFF, 1),
+    ("bump", CTX_BUMP_OFF, 1),
+    ("impact_k_bps", CTX_IMPACT_K_OFF, 4),
+    ("skew_k_bps", CTX_SKEW_K_OFF, 4),
+    ("curve_points", CTX_CURVE_POINTS_OFF, 8),
+    ("peg_band_e6", CTX_PEG_BAND_OFF, 4),
+    ("last_crank_slot", CTX_LAST_CRANK_OFF, 8),
+    ("last_lifetime_liqs", CTX_LAST_LIQS_OFF, 8),
+    ("liq_heat", CTX_LIQ_HEAT_OFF, 4),
+    ("liq_halflife_slots", CTX_LIQ_HALFLIFE_OFF, 4),
+];
+
+/// The v6 extension, relative to `EXT_BASE`; names as in the crate-root table.
+pub const EXT_FIELDS: &[Field] = &[
+    ("funding_rate_bps_per_slot", EXT_FUNDING_RATE_OFF, 8),
+    ("funding_horizon_slots", EXT_FUNDING_HORIZON_OFF, 4),
+    ("liq_auction_slots", EXT_LIQ_AUCTION_SLOTS_OFF, 4),
+    ("slab", EXT_SLAB_OFF, 32),
+    ("pending_activation_slot", EXT_PENDING_SLOT_OFF, 8),
+    ("pending_params", EXT_PENDING_PARAMS_OFF, PENDING_HEAD_LEN),
+    ("authority", EXT_AUTHORITY_OFF, 32),
+    ("pending_authority", EXT_PENDING_AUTHORITY_OFF, 32),
+    ("percolator_program", EXT_PERCOLATOR_OFF, 32),
+    ("oracle_jump_bps", EXT_ORACLE_JUMP_BPS_OFF, 2),
+    ("liq_auction_bps", EXT_LIQ_AUCTION_BPS_OFF, 2),
+    ("oracle_jump_window_slots", EXT_ORACLE_JUMP_WINDOW_OFF, 4),
+    ("last_oracle_slot", EXT_LAST_ORACLE_SLOT_OFF, 8),
+    ("fill_seq", EXT_FILL_SEQ_OFF, 8),
+    ("cum_notional", EXT_CUM_NOTIONAL_OFF, 16),
+    ("cum_fees", EXT_CUM_FEES_OFF, 16),
+    ("gross_long", EXT_GROSS_LONG_OFF, 16),
+    ("gross_short", EXT_GROSS_SHORT_OFF, 16),
+    ("flow_k_bps", EXT_FLOW_K_OFF, 4),
+    ("twap_window_slots", EXT_TWAP_WINDOW_OFF, 4),
+    ("pending_params_tail", EXT_PENDING_PARAMS_TAIL_OFF, PENDING_TAIL_LEN),
+    ("rfq_signer", EXT_RFQ_SIGNER_OFF, 32),
+    ("stats", EXT_STATS_OFF, 32),
+    ("signal_weights", EXT_SIGNAL_WEIGHTS_OFF, 8),
+    ("deficit_halflife_slots", EXT_DEFICIT_HALFLIFE_OFF, 4),
+    ("market_slots", EXT_MARKET_SLOTS_OFF, 4),
+    ("rfq_price_e6", EXT_RFQ_PRICE_OFF, 8),
+    ("rfq_expiry_slot", EXT_RFQ_EXPIRY_OFF, 8),
+    ("rfq_nonce", EXT_RFQ_NONCE_OFF, 8),
+    ("rfq_size_left", EXT_RFQ_SIZE_OFF, 16),
+    ("last_liq_slot", EXT_LAST_LIQ_SLOT_OFF, 8),
+];
+
+pub const MARKET_FIELDS: &[Field] = &[
+    ("slab", MKT_SLAB_OFF, 32),
+    ("max_fill", MKT_MAX_FILL_OFF, 16),
+    ("max_inventory", MKT_MAX_INVENTORY_OFF, 16),
+    ("inventory", MKT_INVENTORY_OFF, 16),
+    ("insurance_snapshot", MKT_INSURANCE_OFF, 16),
+    ("total_oi_snapshot", MKT_TOTAL_OI_OFF, 16),
+    ("gross_long", MKT_GROSS_LONG_OFF, 16),
+    ("gross_short", MKT_GROSS_SHORT_OFF, 16),
+    ("snapshot_slot", MKT_SNAPSHOT_SLOT_OFF, 8),
+    ("last_crank_slot", MKT_LAST_CRANK_OFF, 8),
+    ("last_lifetime_liqs", MKT_LAST_LIQS_OFF, 8),
+    ("funding_rate_bps_per_slot", MKT_FUNDING_RATE_OFF, 8),
+    ("market_age_slots", MKT_MARKET_AGE_OFF, 8),
+    ("last_deficit_slot", MKT_LAST_DEFICIT_OFF, 8),
+    ("last_liq_slot", MKT_LAST_LIQ_SLOT_OFF, 8),
+    ("last_oracle_price_e6", MKT_LAST_ORACLE_OFF, 8),
+    ("last_oracle_slot", MKT_LAST_ORACLE_SLOT_OFF, 8),
+    ("last_exec_price_e6", MKT_LAST_EXEC_OFF, 8),
+    ("liq_heat", MKT_LIQ_HEAT_OFF, 4),
+    ("_pad0", 228, 4),
+    ("_reserved", 232, 24),
+];
+
+pub const CREATION_FIELDS: &[Field] = &[
+    ("created_slot", CREATION_SLOT_OFF, 8),
+    ("creator", CREATION_CREATOR_OFF, 32),
+    ("derived", CREATION_DERIVED_OFF, 1),
+    ("_pad", CREATION_DERIVED_OFF + 1, 7),
+];
+
+pub const RFQ_FIELDS: &[Field] = &[
+    ("ctx", RFQ_CTX_OFF, 32),
+    ("price_e6", RFQ_PRICE_OFF, 8),
+    ("size", RFQ_SIZE_OFF, 16),
+    ("expiry_slot", RFQ_EXPIRY_OFF, 8),
+    ("nonce", RFQ_NONCE_OFF, 8),
+];
+
+pub const ED25519_OFFSETS_FIELDS: &[Field] = &[
+    ("signature_offset", ED25519_SIG_OFF, 2),
+    ("signature_instruction_index", ED25519_SIG_IX_OFF, 2),
+    ("public_key_offset", ED25519_KEY_OFF, 2),
+    ("public_key_instruction_index", ED25519_KEY_IX_OFF, 2),
+    ("message_data_offset", ED25519_MSG_OFF, 2),
+    ("message_data_size", ED25519_MSG_SIZE_OFF, 2),
+    ("message_instruction_index", ED25519_MSG_IX_OFF, 2),
+];
+
+pub const STATS_HEADER_FIELDS: &[Field] = &[
+    ("magic", STATS_MAGIC_OFF, 8),
+    ("version", STATS_VERSION_OFF, 4),
+    ("capacity", STATS_CAPACITY_OFF, 4),
+    ("ctx", STATS_CTX_OFF, 32),
+    ("head", STATS_HEAD_OFF, 4),
+    ("count", STATS_COUNT_OFF, 4),
+    ("oracle_head", STATS_ORACLE_HEAD_OFF, 4),
+    ("oracle_count", STATS_ORACLE_COUNT_OFF, 4),
+];
+
+pub const FILL_FIELDS: &[Field] = &[
+    ("fill_seq", FILL_SEQ_OFF, 8),
+    ("slot", FILL_SLOT_OFF, 8),
+    ("exec_size", FILL_SIZE_OFF, 16),
+    ("exec_price_e6", FILL_PRICE_OFF, 8),
+    ("spread_bps", FILL_SPREAD_OFF, 8),
+];
+
+pub const ORACLE_SAMPLE_FIELDS: &[Field] = &[
+    ("slot", ORACLE_SLOT_OFF, 8),
+    ("oracle_price_e6", ORACLE_PRICE_OFF, 8),
+];
+
+pub const SIGNAL_FIELDS: &[Field] = &[
+    ("magic", SIGNAL_MAGIC_OFF, 8),
+    ("value_bps", SIGNAL_VALUE_OFF, 4),
+    ("weight_bps", SIGNAL_WEIGHT_OFF, 2),
+    ("_pad", SIGNAL_WEIGHT_OFF + 2, 2),
+    ("stale_after_slot", SIGNAL_STALE_AFTER_OFF, 8),
+    ("_reserved", SIGNAL_STALE_AFTER_OFF + 8, 8),
+];
+
+pub const SCHEDULE_HEADER_FIELDS: &[Field] = &[
+    ("magic", SCHEDULE_MAGIC_OFF, 8),
+    ("version", SCHEDULE_VERSION_OFF, 4),
+    ("count", SCHEDULE_COUNT_OFF, 4),
+    ("ctx", SCHEDULE_CTX_OFF, 32),
+];
+
+pub const SCHED_ENTRY_FIELDS: &[Field] = &[
+    ("start_slot", SCHED_START_OFF, 8),
+    ("end_slot", SCHED_END_OFF, 8),
+    ("multiplier_bps", SCHED_MULT_OFF, 4),
+    ("_pad", SCHED_MULT_OFF + 4, 4),
+];
+
+/// The schedule account as a whole: header, then the entries.
+pub const SCHEDULE_FIELDS: &[Field] = &[
+    ("header", 0, SCHEDULE_HEADER_LEN),
+    ("entries", SCHEDULE_HEADER_LEN, MAX_SCHEDULE_ENTRIES * SCHED_ENTRY_LEN),
+];
+
+pub const FEE_TIER_FIELDS: &[Field] = &[
+    ("min_notional", FEE_TIER_NOTIONAL_OFF, 16),
+    ("fee_bps", FEE_TIER_FEE_OFF, 4),
+    ("_pad", FEE_TIER_FEE_OFF + 4, 4),
+];
+
+/// A schedule account with fee tiers: the schedule, then the tiers.
+pub const SCHEDULE_FEES_FIELDS: &[Field] = &[
+    ("schedule", 0, SCHEDULE_LEN),
+    ("fee_tiers", FEE_TIERS_OFF, MAX_FEE_TIERS * FEE_TIER_LEN),
+];
+
+/// A schedule account with a rebate: the fee tiers, then the rebate and the
+/// fee discount.
+pub const SCHEDULE_REBATE_FIELDS: &[Field] = &[
+    ("schedule_fees", 0, SCHEDULE_FEES_LEN),
+    ("rebate_bps", SCHEDULE_REBATE_OFF, 2),
+    ("fee_discount_bps", SCHEDULE_FEE_DISCOUNT_OFF, 2),
+    ("_pad", SCHEDULE_FEE_DISCOUNT_OFF + 2, 4),
+];
+
+/// A schedule account with a minimum notional, after the rebate.
+pub const SCHEDULE_MIN_NOTIONAL_FIELDS: &[Field] = &[
+    ("schedule_rebate", 0, SCHEDULE_REBATE_LEN),
+    ("min_notional_e6", SCHEDULE_MIN_NOTIONAL_OFF, 16),
+];
+
+pub const LOYALTY_TIER_FIELDS: &[Field] = &[
+    ("min_volume", LOYALTY_TIER_VOLUME_OFF, 16),
+    ("discount_bps", LOYALTY_TIER_DISCOUNT_OFF, 4),
+    ("_pad", LOYALTY_TIER_DISCOUNT_OFF + 4, 4),
+];
+
+/// A schedule account with loyalty tiers, after the minimum notional.
+pub const SCHEDULE_LOYALTY_FIELDS: &[Field] = &[
+    ("schedule_min_notional", 0, SCHEDULE_MIN_NOTIONAL_LEN),
+    ("loyalty_tiers", LOYALTY_TIERS_OFF, MAX_LOYALTY_TIERS * LOYALTY_TIER_LEN),
+];
+
+/// A schedule account with inventory decay, after the loyalty tiers.
+pub const SCHEDULE_DECAY_FIELDS: &[Field] = &[
+    ("schedule_loyalty", 0, SCHEDULE_LOYALTY_LEN),
+    ("inventory_halflife", SCHEDULE_INVENTORY_HALFLIFE_OFF, 4),
+    ("_pad", SCHEDULE_INVENTORY_HALFLIFE_OFF + 4, 4),
+];
+
+/// A schedule account with an inventory band, after the decay.
+pub const SCHEDULE_BAND_FIELDS: &[Field] = &[
+    ("schedule_decay", 0, SCHEDULE_DECAY_LEN),
+    ("soft_inventory", SCHEDULE_SOFT_INVENTORY_OFF, 16),
+    ("band_k_bps", SCHEDULE_BAND_K_OFF, 4),
+    ("_pad", SCHEDULE_BAND_K_OFF + 4, 4),
+];
+
+/// A schedule account with inventory caps, after the band.
+pub const SCHEDULE_CAPS_FIELDS: &[Field] = &[
+    ("schedule_band", 0, SCHEDULE_BAND_LEN),
+    ("max_long_inventory", SCHEDULE_MAX_LONG_OFF, 16),
+    ("max_short_inventory", SCHEDULE_MAX_SHORT_OFF, 16),
+];
+
+/// A schedule account with a reduce-only threshold, after the caps.
+pub const SCHEDULE_REDUCE_ONLY_FIELDS: &[Field] = &[
+    ("schedule_caps", 0, SCHEDULE_CAPS_LEN),
+    ("reduce_only_bps", SCHEDULE_REDUCE_ONLY_BPS_OFF, 2),
+    ("_pad", SCHEDULE_REDUCE_ONLY_BPS_OFF + 2, 6),
+];
+
+/// A schedule account with a depth-relative inventory cap, after the
+/// reduce-only threshold.
+pub const SCHEDULE_DEPTH_CAP_FIELDS: &[Field] = &[
+    ("schedule_reduce_only", 0, SCHEDULE_REDUCE_ONLY_LEN),
+    ("max_inventory_bps", SCHEDULE_MAX_INVENTORY_BPS_OFF, 4),
+    ("_pad", SCHEDULE_MAX_INVENTORY_BPS_OFF + 4, 4),
+];
+
+pub const SIGNAL_ENTRY_FIELDS: &[Field] = &[
+    ("signal", SIGNAL_ENTRY_KEY_OFF, 32),
+    ("owner", SIGNAL_ENTRY_OWNER_OFF, 32),
+];
+
+/// A schedule account with a signal registry, after the depth-relative cap.
+pub const SCHEDULE_SIGNALS_FIELDS: &[Field] = &[
+    ("schedule_depth_cap", 0, SCHEDULE_DEPTH_CAP_LEN),
+    ("signal_count", SCHEDULE_SIGNAL_COUNT_OFF, 1),
+    ("_pad", SCHEDULE_SIGNAL_COUNT_OFF + 1, 7),
+    ("signals", SCHEDULE_SIGNALS_OFF, MAX_SIGNAL_ACCOUNTS * SIGNAL_ENTRY_LEN),
+];
+
+pub const LADDER_HEADER_FIELDS: &[Field] = &[
+    ("magic", LADDER_MAGIC_OFF, 8),
+    ("version", LADDER_VERSION_OFF, 4),
+    ("market", LADDER_MARKET_OFF, 2),
+    ("_pad", LADDER_MARKET_OFF + 2, 2),
+    ("ctx", LADDER_CTX_OFF, 32),
+    ("slot", LADDER_SLOT_OFF, 8),
+    ("oracle_price_e6", LADDER_ORACLE_OFF, 8),
+    ("step_size", LADDER_STEP_OFF, 16),
+    ("bid_count", LADDER_BID_COUNT_OFF, 4),
+    ("ask_count", LADDER_ASK_COUNT_OFF, 4),
+    ("bid_reason", LADDER_BID_REASON_OFF, 4),
+    ("ask_reason", LADDER_ASK_REASON_OFF, 4),
+];
+
+pub const LEVEL_FIELDS: &[Field] = &[
+    ("size", LEVEL_SIZE_OFF, 16),
+    ("exec_price_e6", LEVEL_PRICE_OFF, 8),
+    ("spread_bps", LEVEL_SPREAD_OFF, 8),
+];
+
+/// The ladder account as a whole: header, bids, asks.
+pub const LADDER_FIELDS: &[Field] = &[
+    ("header", 0, LADDER_HEADER_LEN),
+    ("bids", LADDER_BIDS_OFF, MAX_LADDER_LEVELS * LEVEL_LEN),
+    ("asks", LADDER_ASKS_OFF, MAX_LADDER_LEVELS * LEVEL_LEN),
+];
+
+pub const TIP_JAR_FIELDS: &[Field] = &[
+    ("magic", TIP_JAR_MAGIC_OFF, 8),
+    ("version", TIP_JAR_VERSION_OFF, 4),
+    ("_pad", TIP_JAR_VERSION_OFF + 4, 4),
+    ("ctx", TIP_JAR_CTX_OFF, 32),
+    ("lamports_per_slot", TIP_JAR_PER_SLOT_OFF, 8),
+    ("max_tip", TIP_JAR_MAX_OFF, 8),
+    ("paid", TIP_JAR_PAID_OFF, 8),
+    ("tips", TIP_JAR_COUNT_OFF, 8),
+];
+
+pub const REFERRAL_HEADER_FIELDS: &[Field] = &[
+    ("magic", REFERRAL_MAGIC_OFF, 8),
+    ("version", REFERRAL_VERSION_OFF, 4),
+    ("_pad", REFERRAL_VERSION_OFF + 4, 4),
+    ("ctx", REFERRAL_CTX_OFF, 32),
+    ("share_bps", REFERRAL_SHARE_OFF, 2),
+    ("referrers", REFERRAL_COUNT_OFF, 2),
+    ("_pad1", REFERRAL_COUNT_OFF + 2, 4),
+];
+
+pub const REFERRER_FIELDS: &[Field] = &[
+    ("referrer", REFERRER_KEY_OFF, 32),
+    ("accrued_fees", REFERRER_ACCRUED_OFF, 16),
+    ("fills", REFERRER_FILLS_OFF, 8),
+];
+
+pub const REFERRAL_FIELDS: &[Field] = &[
+    ("header", 0, REFERRAL_HEADER_LEN),
+    ("entries", REFERRAL_HEADER_LEN, MAX_REFERRERS * REFERRER_LEN),
+];
+
+pub const TRADER_FIELDS: &[Field] = &[
+    ("magic", TRADER_MAGIC_OFF, 8),
+    ("version", TRADER_VERSION_OFF, 4),
+    ("_pad", TRADER_VERSION_OFF + 4, 4),
+    ("ctx", TRADER_CTX_OFF, 32),
+    ("trader", TRADER_KEY_OFF, 32),
+    ("volume", TRADER_VOLUME_OFF, 16),
+    ("fills", TRADER_FILLS_OFF, 8),
+    ("last_slot", TRADER_LAST_SLOT_OFF, 8),
+];
+
+pub const FEED_HEADER_FIELDS: &[Field] = &[
+    ("magic", FEED_MAGIC_OFF, 8),
+    ("version", FEED_VERSION_OFF, 4),
+    ("max_age_secs", FEED_MAX_AGE_OFF, 4),
+    ("ctx", FEED_CTX_OFF, 32),
+    ("count", FEED_COUNT_OFF, 1),
+    ("widen", FEED_WIDEN_OFF, 1),
+    ("max_divergence_bps", FEED_MAX_DIVERGENCE_OFF, 2),
+    ("max_age_slots", FEED_MAX_AGE_SLOTS_OFF, 2),
+    ("slot_widen", FEED_SLOT_WIDEN_OFF, 1),
+    ("fallback", FEED_FALLBACK_OFF, 1),
+];
+
+pub const FEED_ENTRY_FIELDS: &[Field] = &[
+    ("feed_id", FEED_ENTRY_ID_OFF, 32),
+    ("source", FEED_ENTRY_SOURCE_OFF, 1),
+    ("pinned", FEED_ENTRY_PINNED_OFF, 1),
+    ("penalty_bps", FEED_ENTRY_PENALTY_OFF, 2),
+    ("_pad", FEED_ENTRY_PENALTY_OFF + 2, 4),
+];
+
+/// The feed account as a whole: header, then the entries.
+pub const FEED_FIELDS: &[Field] = &[
+    ("header", 0, FEED_HEADER_LEN),
+    ("entries", FEED_HEADER_LEN, MAX_FEEDS * FEED_ENTRY_LEN),
+];
+
+/// The stats account as a whole: header, fill ring, oracle ring.
+pub const STATS_FIELDS: &[Field] = &[
+    ("header", 0, STATS_HEADER_LEN),
+    ("fills", STATS_HEADER_LEN, STATS_CAPACITY * FILL_LEN),
+    ("oracle", STATS_ORACLE_RING_OFF, ORACLE_CAPACITY * ORACLE_SAMPLE_LEN),
+];
+
+/// A stats account with a fee ledger: the rings, then the ledger.
+pub const STATS_LEDGER_FIELDS: &[Field] = &[
+    ("stats", 0, STATS_LEN),
+    ("withdrawn_fees", STATS_WITHDRAWN_OFF, 16),
+    ("withdrawals", STATS_WITHDRAWALS_OFF, 8),
+    ("last_withdrawal_slot", STATS_LAST_WITHDRAWAL_OFF, 8),
+];
+
+/// A stats account with an insurance skim: the ledger, then the skim.
+pub const STATS_SKIM_FIELDS: &[Field] = &[
+    ("ledger", 0, STATS_LEDGER_LEN),
+    ("skim_bps", STATS_SKIM_BPS_OFF, 2),
+    ("_pad", STATS_SKIM_BPS_OFF + 2, 6),
+    ("skim_accrued", STATS_SKIM_ACCRUED_OFF, 16),
+    ("skim_claimed", STATS_SKIM_CLAIMED_OFF, 16),
+    ("skim_claims", STATS_SKIM_CLAIMS_OFF, 8),
+];
+
+/// A stats account with a protocol fee: the skim, then the treasury's cut.
+pub const STATS_PROTOCOL_FIELDS: &[Field] = &[
+    ("skim", 0, STATS_SKIM_LEN),
+    ("treasury", STATS_TREASURY_OFF, 32),
+    ("protocol_fee_bps", STATS_PROTOCOL_FEE_BPS_OFF, 2),
+    ("_pad", STATS_PROTOCOL_FEE_BPS_OFF + 2, 6),
+    ("protocol_accrued", STATS_PROTOCOL_ACCRUED_OFF, 16),
+    ("protocol_swept", STATS_PROTOCOL_SWEPT_OFF, 16),
+    ("protocol_sweeps", STATS_PROTOCOL_SWEEPS_OFF, 8),
+];
+
+/// A stats account with inventory PnL, after the protocol fee.
+pub const STATS_PNL_FIELDS: &[Field] = &[
+    ("protocol", 0, STATS_PROTOCOL_LEN),
+    ("entry_price_e6", STATS_PNL_ENTRY_OFF, 8),
+    ("mark_price_e6", STATS_PNL_MARK_OFF, 8),
+    ("realized_pnl", STATS_PNL_REALIZED_OFF, 16),
+    ("unrealized_pnl", STATS_PNL_UNREALIZED_OFF, 16),
+];
+
+const _: () = assert!(tiles(RET_FIELDS, RET_LEN));
+const _: () = assert!(tiles(MATCH_RET_FIELDS, MATCH_RET_LEN));
+const _: () = assert!(tiles(QUOTE_FIELDS, QUOTE_LEN));
+const _: () = assert!(tiles(TWO_SIDED_FIELDS, TWO_SIDED_LEN));
+const _: () = assert!(tiles(CTX_FIELDS, CTX_LEN - CTX_BASE));
+const _: () = assert!(tiles(EXT_FIELDS, EXT_LEN));
+const _: () = assert!(tiles(MARKET_FIELDS, MARKET_LEN));
+const _: () = assert!(tiles(CREATION_FIELDS, CREATION_LEN));
+const _: () = assert!(tiles(RFQ_FIELDS, RFQ_MSG_LEN));
+const _: () = assert!(tiles(ED25519_OFFSETS_FIELDS, ED25519_OFFSETS_LEN));
+const _: () = assert!(tiles(STATS_HEADER_FIELDS, STATS_HEADER_LEN));
+const _: () = assert!(tiles(FILL_FIELDS, FILL_LEN));
+const _: () = assert!(tiles(ORACLE_SAMPLE_FIELDS, ORACLE_SAMPLE_LEN));
+const _: () = assert!(tiles(STATS_FIELDS, STATS_LEN));
+const _: () = assert!(tiles(STATS_LEDGER_FIELDS, STATS_LEDGER_LEN));
+const _: () = assert!(tiles(STATS_SKIM_FIELDS, STATS_SKIM_LEN));
+const _: () = assert!(tiles(STATS_PROTOCOL_FIELDS, STATS_PROTOCOL_LEN));
+const _: () = assert!(tiles(STATS_PNL_FIELDS, STATS_PNL_LEN));
+const _: () = assert!(tiles(SIGNAL_FIELDS, SIGNAL_LEN));
+const _: () = assert!(tiles(SCHEDULE_HEADER_FIELDS, SCHEDULE_HEADER_LEN));
+const _: () = assert!(tiles(SCHED_ENTRY_FIELDS, SCHED_ENTRY_LEN));
+const _: () = assert!(tiles(SCHEDULE_FIELDS, SCHEDULE_LEN));
+const _: () = assert!(tiles(FEE_TIER_FIELDS, FEE_TIER_LEN));
+const _: () = assert!(tiles(SCHEDULE_FEES_FIELDS, SCHEDULE_FEES_LEN));
+const _: () = assert!(tiles(SCHEDULE_REBATE_FIELDS, SCHEDULE_REBATE_LEN));
+const _: () = assert!(tiles(SCHEDULE_MIN_NOTIONAL_FIELDS, SCHEDULE_MIN_NOTIONAL_LEN));
+const _: () = assert!(tiles(LOYALTY_TIER_FIELDS, LOYALTY_TIER_LEN));
+const _: () = assert!(tiles(SCHEDULE_LOYALTY_FIELDS, SCHEDULE_LOYALTY_LEN));
+const _: () = assert!(tiles(SCHEDULE_DECAY_FIELDS, SCHEDULE_DECAY_LEN));
+const _: () = assert!(tiles(SCHEDULE_BAND_FIELDS, SCHEDULE_BAND_LEN));
+const _: () = assert!(tiles(SCHEDULE_CAPS_FIELDS, SCHEDULE_CAPS_LEN));
+const _: () = assert!(tiles(SCHEDULE_REDUCE_ONLY_FIELDS, SCHEDULE_REDUCE_ONLY_LEN));
+const _: () = assert!(tiles(SCHEDULE_DEPTH_CAP_FIELDS, SCHEDULE_DEPTH_CAP_LEN));
+const _: () = assert!(tiles(SIGNAL_ENTRY_FIELDS, SIGNAL_ENTRY_LEN));
+const _: () = assert!(tiles(SCHEDULE_SIGNALS_FIELDS, SCHEDULE_SIGNALS_LEN));
+const _: () = assert!(tiles(LADDER_HEADER_FIELDS, LADDER_HEADER_LEN));
+const _: () = assert!(tiles(LEVEL_FIELDS, LEVEL_LEN));
+const _: () = assert!(tiles(LADDER_FIELDS, LADDER_LEN));
+const _: () = assert!(tiles(TIP_JAR_FIELDS, TIP_JAR_LEN));
+const _: () = assert!(tiles(REFERRAL_HEADER_FIELDS, REFERRAL_HEADER_LEN));
+const _: () = assert!(tiles(REFERRER_FIELDS, REFERRER_LEN));
+const _: () = assert!(tiles(REFERRAL_FIELDS, REFERRAL_LEN));
+const _: () = assert!(tiles(TRADER_FIELDS, TRADER_LEN));
+const _: () = assert!(tiles(FEED_HEADER_FIELDS, FEED_HEADER_LEN));
+const _: () = assert!(tiles(FEED_ENTRY_FIELDS, FEED_ENTRY_LEN));
+const _: () = assert!(tiles(FEED_FIELDS, FEED_LEN));
+
+// The regions themselves: the return prefix, then the context, then the
+// extension, with no gap between them
+const _: () = assert!(CTX_BASE == RET_LEN);
+const _: () = assert!(EXT_BASE == CTX_LEN);
+const _: () = assert!(CTX_LEN - CTX_BASE == 256);
+const _: () = assert!(EXT_LEN == 512);
+// The market table follows the extension
+const _: () = assert!(MARKETS_BASE == EXT_BASE + EXT_LEN);
+// A proposal fits the split slots that hold it
+const _: () = assert!(PARAMS_MAX_LEN <= PENDING_HEAD_LEN + PENDING_TAIL_LEN);
+// The oracle fields read stay inside the accounts
+const _: () = assert!(PYTH_PUBLISH_TIME_OFF + 8 <= PYTH_MIN_LEN);
+const _: () = assert!(SWITCHBOARD_UPDATED_OFF + 8 <= SWITCHBOARD_VALUE_OFF);
+const _: () = assert!(PYTH_POSTED_SLOT_OFF + 8 <= PYTH_MIN_LEN);
+const _: () = assert!(CHAINLINK_LIVE_CURSOR_OFF + 4 <= CHAINLINK_HEADER_LEN);
+const _: () = assert!(CHAINLINK_ANSWER_OFF + 16 <= CHAINLINK_TRANSMISSION_LEN);
+// The slab fields read stay inside the required prefix
+const _: () = assert!(SLAB_ADMIN_OFF + 32 <= SLAB_HEADER_LEN);
+const _: () = assert!(ENGINE_LIFETIME_LIQS_OFF + 8 <= SLAB_ENGINE_LEN);
+const _: () = assert!(ACCT_MATCHER_CONTEXT_OFF + 32 <= SLAB_ACCOUNT_LEN);
+// Match's return data: MatcherReturn, then the breakdown, the sequence number
+// and the hedge target
+const _: () = assert!(MATCH_RET_LEN == RET_LEN + QUOTE_LEN + 8 + 16);
+// Both TWO_SIDED_* sides are whole breakdowns
+const _: () = assert!(TWO_SIDED_LEN == 16 + 2 * QUOTE_LEN);
+// A flag byte holds every flag
+const _: () = assert!(
+    (CTX_FLAG_PAUSED
+        | CTX_FLAG_REDUCE_ONLY
+        | CTX_FLAG_RETURN_DATA_ONLY
+        | CTX_FLAG_SOFT_REJECT
+        | CTX_FLAG_NO_BIDS
+        | CTX_FLAG_NO_ASKS
+        | CTX_FLAG_SCHEDULED
+        | CTX_FLAG_FEED)
+        .count_ones()
+        == 8
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `(offset, size, field)` rows of the markdown table under `heading` in
+    /// `doc`, which may be `//!`-prefixed.
+    fn doc_rows(doc: &str, heading: &str) -> Vec<(usize, usize, String)> {
+        let mut rows = Vec::new();
+        let mut inside = false;
+        for line in doc.lines() {
+            let line = line.trim_start_matches("//!").trim();
+            if line.starts_with('#') {
+                inside = line.starts_with(heading);
+                continue;
+            }
+            if !inside || !line.starts_with('|') {
+                continue;
+            }
+            let cells: Vec<&str> = line.trim_matches('|').split('|').map(str::trim).collect();
+            if let (Ok(offset), Ok(size)) = (cells[0].parse(), cells[1].parse()) {
+                rows.push((offset, size, cells[2].to_string()));
+            }
+        }
+        rows
+    }
+
+    fn table(fields: &[Field]) -> Vec<(usize, usize, String)> {
+        fields.iter().map(|&(name, offset, size)| (offset, size, name.to_string())).collect()
+    }
+
+    #[test]
+    fn test_context_matches_documented_table() {
+        let doc = doc_rows(include_str!("lib.rs"), "## Context Layout");
+        assert_eq!(doc, table(CTX_FIELDS));
+    }
+
+    #[test]
+    fn test_extension_matches_documented_table() {
+        let doc = doc_rows(include_str!("lib.rs"), "## Extension Layout");
+        assert_eq!(doc, table(EXT_FIELDS));
+    }
+
+    #[test]
+    fn test_market_matches_documented_table() {
+        let doc = doc_rows(include_str!("lib.rs"), "## Market Layout");
+        assert_eq!(doc, table(MARKET_FIELDS));
+    }
+
+    #[test]
+    fn test_quote_matches_readme_table() {
+        // Two offset/field column pairs; the field cell may carry a note
+        let readme = include_str!("../README.md");
+        let mut doc = Vec::new();
+        for line in readme.lines().filter(|l| l.starts_with('|')) {
+            let cells: Vec<&str> = line.trim_matches('|').split('|').map(str::trim).collect();
+            if cells.len() != 4 || cells[1].contains("Name") {
+                continue;
+            }
+            for pair in cells.chunks(2) {
+                if let Ok(offset) = pair[0].parse::<usize>() {
+                    let name = pair[1].split_whitespace().next().unwrap_or_default();
+                    doc.push((offset, name.to_string()));
+                }
+            }
+        }
+        // The error table shares the shape, with codes in place of offsets
+        doc.retain(|(_, name)| name.chars().next().is_some_and(|c| c.is_ascii_lowercase()));
+        doc.sort();
+        let fields: Vec<(usize, String)> = QUOTE_FIELDS.iter().map(|&(name, offset, _)| (offset, name.to_string())).collect();
+        assert_eq!(doc, fields);
+    }
+
+    #[test]
+    fn test_tiles_catches_gaps_and_overlaps() {
+        assert!(tiles(&[("a", 0, 4), ("b", 4, 4)], 8));
+        assert!(!tiles(&[("a", 0, 4), ("b", 5, 3)], 8));
+        assert!(!tiles(&[("a", 0, 4), ("b", 3, 5)], 8));
+        assert!(!tiles(&[("a", 0, 4), ("b", 4, 4)], 12));
+    }
+}
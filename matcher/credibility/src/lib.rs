@@ -19,9 +19,10 @@
 //! | Offset | Size | Field                    | Description                          |
 //! |--------|------|--------------------------|--------------------------------------|
 //! | 0      | 8    | magic                    | 0x5045_5243_4d41_5443 ("PERCMATC")   |
-//! | 8      | 4    | version                  | 5                                    |
-//! | 12     | 1    | kind                     | 2 = Credibility                      |
-//! | 13     | 3    | _pad0                    |                                      |
+//! | 8      | 4    | version                  | 5 (320-byte) or 6 (extended)         |
+//! | 12     | 1    | kind                     | 1 Plain, 2 Cred., 3 Multi, 4 Stable  |
+//! | 13     | 1    | oracle_decimals          | Caller's price decimals (0 = e6)     |
+//! | 14     | 2    | spot_weight_bps          | Live oracle's share of a TWAP anchor |
 //! | 16     | 32   | lp_pda                   | LP PDA for signature verification    |
 //! | 48     | 4    | base_fee_bps             | Base trading fee                     |
 //! | 52     | 4    | min_spread_bps           | Minimum spread floor (FORTIFIED)     |
@@ -40,101 +41,155 @@
 //! | 192    | 8    | snapshot_slot            | Slot when snapshots were updated      |
 //! | 200    | 4    | age_halflife_slots       | Halflife for age discount (u32)       |
 //! | 204    | 4    | insurance_weight_bps     | How much insurance ratio affects spread|
-//! | 208    | 48   | _reserved                |                                       |
+//! | 208    | 1    | impact_mode              | 0 = off, 1 = linear, 2 = square-root  |
+//! | 209    | 1    | coverage_curve           | 0 = linear, 1 = sqrt, 2 = piecewise   |
+//! | 210    | 1    | flags                    | CTX_FLAG_* bits                       |
+//! | 211    | 1    | bump                     | CreateContext's bump (0 = keypair)    |
+//! | 212    | 4    | impact_k_bps             | Size impact multiplier                |
+//! | 216    | 4    | skew_k_bps               | Inventory mid-price skew multiplier   |
+//! | 220    | 8    | curve_points             | Piecewise curve at 20/40/60/80% (u16) |
+//! | 228    | 4    | peg_band_e6              | Stable kind's band around the peg     |
+//! | 232    | 8    | last_crank_slot          | Percolator crank slot at snapshot     |
+//! | 240    | 8    | last_lifetime_liqs       | Percolator lifetime liqs at snapshot  |
+//! | 248    | 4    | liq_heat                 | Decayed liquidation count (x1000)     |
+//! | 252    | 4    | liq_halflife_slots       | Halflife for liq_heat decay (0 = off) |
+//!
+//! ## Extension Layout (512 bytes, starting at byte 320 of a v6 account)
+//!
+//! Contexts created in a 320-byte account are v5 and have no extension: the
+//! signals below read as zero and their pricing terms are off. Accounts of at
+//! least 832 bytes are initialized as v6.
+//!
+//! | Offset | Size | Field                    | Description                          |
+//! |--------|------|--------------------------|--------------------------------------|
+//! | 0      | 8    | funding_rate_bps_per_slot| Percolator funding rate at snapshot  |
+//! | 8      | 4    | funding_horizon_slots    | Funding skew horizon (0 = off)       |
+//! | 12     | 4    | liq_auction_slots        | Liquidation auction length (0 = off) |
+//! | 16     | 32   | slab                     | Bound percolator slab (0 = unbound)  |
+//! | 48     | 8    | pending_activation_slot  | Proposed params apply from (0 = none)|
+//! | 56     | 104  | pending_params           | Proposed parameter block, first 104  |
+//! | 160    | 32   | authority                | Admin key (0 = the LP PDA)           |
+//! | 192    | 32   | pending_authority        | Proposed admin awaiting acceptance   |
+//! | 224    | 32   | percolator_program       | Owner of the bound slab at init      |
+//! | 256    | 2    | oracle_jump_bps          | Max oracle move per window (0 = off) |
+//! | 258    | 2    | liq_auction_bps          | Auction premium at the liquidation   |
+//! | 260    | 4    | oracle_jump_window_slots | Circuit breaker window               |
+//! | 264    | 8    | last_oracle_slot         | Slot of last_oracle_price_e6         |
+//! | 272    | 8    | fill_seq                 | Fills so far; the last fill's number |
+//! | 280    | 16   | cum_notional             | Traded notional, |size| * price / 1e6|
+//! | 296    | 16   | cum_fees                 | fee share of cum_notional            |
+//! | 312    | 16   | gross_long               | Total size bought by takers (base)   |
+//! | 328    | 16   | gross_short              | Total size sold by takers (base)     |
+//! | 344    | 4    | flow_k_bps               | One-way flow penalty (0 = off)       |
+//! | 348    | 4    | twap_window_slots        | Anchor quotes to a TWAP (0 = off)    |
+//! | 352    | 32   | pending_params_tail      | Rest of the proposed parameter block |
+//! | 384    | 32   | rfq_signer               | Key signing RFQ quotes (zero = off)  |
+//! | 416    | 32   | stats                    | Bound stats account (zero = none)    |
+//! | 448    | 8    | signal_weights           | Multi-signal weights (u16 x 4)       |
+//! | 456    | 4    | deficit_halflife_slots   | Halflife for deficit recovery        |
+//! | 460    | 4    | market_slots             | Market table entries (0 = single)    |
+//! | 464    | 8    | rfq_price_e6             | Posted RFQ price (0 = none)          |
+//! | 472    | 8    | rfq_expiry_slot          | Last slot the posted RFQ fills in    |
+//! | 480    | 8    | rfq_nonce                | Nonce of the last posted RFQ         |
+//! | 488    | 16   | rfq_size_left            | Posted RFQ size left, signed (i128)  |
+//! | 504    | 8    | last_liq_slot            | Last snapshot with new liquidations  |
+//!
+//! ## Market Layout (256 bytes per entry, from byte 832 of a shared account)
+//!
+//! A v6 context initialized in an account of at least CTX_SHARED_LEN bytes is
+//! shared: it quotes up to eight percolator markets for one LP PDA, each with
+//! its own book below. Match and UpdateCredibility find the book by the slab
+//! they are passed; the context's own inventory, snapshot and flow fields stay
+//! zero. Parameters, flags, the authority and the fill counters are shared.
+//!
+//! | Offset | Size | Field                    | Description                          |
+//! |--------|------|--------------------------|--------------------------------------|
+//! | 0      | 32   | slab                     | The market's slab (zero = free entry)|
+//! | 32     | 16   | max_fill                 | Market fill cap (0 = max_fill_abs)   |
+//! | 48     | 16   | max_inventory            | Market inventory cap (0 = context's) |
+//! | 64     | 16   | inventory                | LP inventory in this market (i128)   |
+//! | 80     | 16   | insurance_snapshot       | Insurance fund balance snapshot      |
+//! | 96     | 16   | total_oi_snapshot        | Total open interest snapshot         |
+//! | 112    | 16   | gross_long               | Total size bought by takers (base)   |
+//! | 128    | 16   | gross_short              | Total size sold by takers (base)     |
+//! | 144    | 8    | snapshot_slot            | Slot when snapshots were updated     |
+//! | 152    | 8    | last_crank_slot          | Percolator crank slot at snapshot    |
+//! | 160    | 8    | last_lifetime_liqs       | Percolator lifetime liqs at snapshot |
+//! | 168    | 8    | funding_rate_bps_per_slot| Percolator funding rate at snapshot  |
+//! | 176    | 8    | market_age_slots         | Slots since admin burn               |
+//! | 184    | 8    | last_deficit_slot        | Last slot with liquidation deficit   |
+//! | 192    | 8    | last_liq_slot            | Last snapshot with new liquidations  |
+//! | 200    | 8    | last_oracle_price_e6     | Oracle price at the last fill        |
+//! | 208    | 8    | last_oracle_slot         | Slot of last_oracle_price_e6         |
+//! | 216    | 8    | last_exec_price_e6       | Last execution price                 |
+//! | 224    | 4    | liq_heat                 | Decayed liquidation count (x1000)    |
+//! | 228    | 4    | _pad0                    |                                      |
+//! | 232    | 24   | _reserved                |                                      |
 
+use solana_instructions_sysvar::{load_current_index_checked, load_instruction_at_checked};
 use solana_program::{
-    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, msg,
-    program_error::ProgramError, pubkey::Pubkey,
+    account_info::AccountInfo, clock::Clock, ed25519_program, entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction}, msg, program::{invoke, invoke_signed, set_return_data}, program_error::ProgramError,
+    pubkey::Pubkey, rent::Rent, sysvar::Sysvar,
 };
+use solana_system_interface::instruction as system_instruction;
 
-entrypoint!(process_instruction);
-
-// Context magic: "PERCMATC"
-const MAGIC: u64 = 0x5045_5243_4d41_5443;
-const VERSION: u32 = 5;
-const KIND_CREDIBILITY: u8 = 2;
-
-// Return data layout (first 64 bytes of context account)
-const RET_ABI_VERSION_OFF: usize = 0;
-const RET_FLAGS_OFF: usize = 4;
-const RET_EXEC_PRICE_OFF: usize = 8;
-const RET_EXEC_SIZE_OFF: usize = 16;
-const RET_REQ_ID_OFF: usize = 32;
-const RET_LP_ACCOUNT_ID_OFF: usize = 40;
-const RET_ORACLE_ECHO_OFF: usize = 48;
-const RET_RESERVED_OFF: usize = 56;
-
-const MATCHER_ABI_VERSION: u32 = 1;
-const FLAG_VALID: u32 = 1;
-
-// Matcher call input layout (67 bytes)
-const CALL_LEN: usize = 67;
+use provenance_core::{
+    self as pricing, decay_halflife, hedge_size, Quote, QuoteError, BPS, CURVE_LINEAR, CURVE_PIECEWISE, CURVE_POINTS,
+    IMPACT_NONE, IMPACT_SQRT, LIQ_HEAT_SCALE, SIGNALS, SIGNAL_AGE, SIGNAL_DEFICIT, SIGNAL_LIQUIDATIONS, SIGNAL_NAMES,
+    TIER_NAMES, stable::PEG_PRICE_E6,
+};
 
-// Context offsets (relative to byte 64)
-const CTX_MAGIC_OFF: usize = 0;
-const CTX_VERSION_OFF: usize = 8;
-const CTX_KIND_OFF: usize = 12;
-const CTX_LP_PDA_OFF: usize = 16;
-const CTX_BASE_FEE_OFF: usize = 48;
-const CTX_MIN_SPREAD_OFF: usize = 52;
-const CTX_MAX_SPREAD_OFF: usize = 56;
-const CTX_IMBALANCE_K_OFF: usize = 60;
-const CTX_LIQUIDITY_OFF: usize = 64;
-const CTX_MAX_FILL_OFF: usize = 80;
-const CTX_INVENTORY_OFF: usize = 96;
-const CTX_LAST_ORACLE_OFF: usize = 112;
-const CTX_LAST_EXEC_OFF: usize = 120;
-const CTX_MAX_INVENTORY_OFF: usize = 128;
-const CTX_INSURANCE_OFF: usize = 144;
-const CTX_TOTAL_OI_OFF: usize = 160;
-const CTX_MARKET_AGE_OFF: usize = 176;
-const CTX_LAST_DEFICIT_OFF: usize = 184;
-const CTX_SNAPSHOT_SLOT_OFF: usize = 192;
-const CTX_AGE_HALFLIFE_OFF: usize = 200;
-const CTX_INSURANCE_WEIGHT_OFF: usize = 204;
+pub mod context;
+pub mod error;
+mod layout;
 
-const CTX_BASE: usize = 64;
+use bytemuck::Zeroable;
+use context::{ContextExtension, MarketEntry, MatcherContext};
+use error::MatcherError;
+use layout::*;
 
-const BPS: u64 = 10_000;
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
 
 // =============================================================================
-// Coverage Tiers — the market prices its own fragility
-//
-// These thresholds are hardcoded, not configurable. The math is the governance.
+// Parameter timelock — takers get a day's notice before the terms change
 // =============================================================================
 
-/// Coverage < 10%: CRITICAL
-/// Spread locked at max. Fill capped at 25% of base max_fill.
-/// The market is telling you: "I might not survive a liquidation cascade."
-const TIER_CRITICAL_BPS: u64 = 1_000; // 10% in bps
+/// Proposed parameters can be committed this many slots after the proposal
+/// (~24 hours at 400ms/slot).
+const PARAMS_TIMELOCK_SLOTS: u64 = 216_000;
 
-/// Coverage < 25%: FRAGILE
-/// Spread interpolates sharply toward max. Fill capped at 50%.
-/// Thin insurance = expensive trading. No exceptions.
-const TIER_FRAGILE_BPS: u64 = 2_500; // 25% in bps
+/// Programs whose PriceUpdateV2 accounts a Pyth feed takes: the Pyth
+/// receiver, which posts pulled updates, and the push oracle behind the
+/// sponsored feeds.
+const PYTH_OWNERS: [Pubkey; 2] = [
+    Pubkey::from_str_const("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ"),
+    Pubkey::from_str_const("pythWSnswVUd12oZpeFP8e9CVaEqJg25g1Vtc2biRsT"),
+];
 
-/// Coverage < 100%: NORMAL
-/// Linear discount from insurance_weight_bps. Full fill allowed.
-/// The standard operating range.
-const TIER_NORMAL_BPS: u64 = 10_000; // 100% in bps
+/// The Switchboard on-demand program, owner of the pull feeds a Switchboard
+/// feed takes.
+const SWITCHBOARD_OWNER: Pubkey = Pubkey::from_str_const("SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv");
 
-/// Coverage < 200%: STRONG
-/// Full insurance discount. Spreads at their tightest for the base formula.
-/// The market has proven solvency.
-const TIER_STRONG_BPS: u64 = 20_000; // 200% in bps
+/// The Chainlink store program, owner of the OCR2 feeds a Chainlink feed
+/// takes.
+const CHAINLINK_OWNER: Pubkey = Pubkey::from_str_const("HEvSKofvBgfaexv23kMabbYqxasxU3mQ4ibBMEmJWHny");
 
-/// Coverage >= 200%: FORTIFIED
-/// Spread at min_spread floor. Fill gets 50% bonus above base max_fill.
-/// Overcollateralized markets get the best pricing.
+/// FEED_SOURCE_* names, for logs.
+const FEED_SOURCE_NAMES: [&str; 3] = ["Pyth", "Switchboard", "Chainlink"];
 
-// Fill multipliers (in percent, applied to max_fill_abs)
-const FILL_PCT_CRITICAL: u128 = 25;
-const FILL_PCT_FRAGILE: u128 = 50;
-const FILL_PCT_NORMAL: u128 = 100;
-const FILL_PCT_STRONG: u128 = 100;
-const FILL_PCT_FORTIFIED: u128 = 150;
+/// The percolator program. A context with no slab binding to pin an owner,
+/// v5 included, takes slabs this program owns.
+const PERCOLATOR_PROGRAM_ID: Pubkey = Pubkey::from_str_const("2SSnp35m7FQ7cRLNKGdW5UzjYFF6RBUNq7d3m5mqNByp");
 
-fn process_instruction(
+/// Percolator's TopUpInsurance tag, `[9, amount (u64)]`, which ClaimSkim
+/// invokes to pay the skim into the slab's insurance fund.
+const PERCOLATOR_TOP_UP_INSURANCE: u8 = 9;
+
+/// Program entrypoint. Public so hosts that embed the program (the
+/// `no-entrypoint` build) can dispatch to it directly.
+pub fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
@@ -147,6 +202,61 @@ fn process_instruction(
         0x00 => process_match(program_id, accounts, data),
         0x02 => process_init(program_id, accounts, data),
         0x03 => process_update_credibility(program_id, accounts, data),
+        0x04 => process_update_params(program_id, accounts, data),
+        0x05 => process_close(program_id, accounts, data),
+        0x06 => process_set_paused(program_id, accounts, data),
+        0x07 => process_migrate(program_id, accounts, data),
+        0x08 => process_quote_preview(program_id, accounts, data),
+        0x09 => process_propose_params(program_id, accounts, data),
+        0x0A => process_commit_params(program_id, accounts, data),
+        0x0B => process_set_reduce_only(program_id, accounts, data),
+        0x0C => process_settle_inventory(program_id, accounts, data),
+        0x0D => process_propose_authority(program_id, accounts, data),
+        0x0E => process_accept_authority(program_id, accounts, data),
+        0x0F => process_set_return_data_only(program_id, accounts, data),
+        0x10 => process_set_soft_reject(program_id, accounts, data),
+        0x11 => process_quote_two_sided(program_id, accounts, data),
+        0x12 => process_init_stats(program_id, accounts, data),
+        0x13 => process_set_rfq_signer(program_id, accounts, data),
+        0x14 => process_post_rfq(program_id, accounts, data),
+        0x15 => process_set_market(program_id, accounts, data),
+        0x16 => process_set_no_bids(program_id, accounts, data),
+        0x17 => process_set_no_asks(program_id, accounts, data),
+        0x18 => process_init_schedule(program_id, accounts, data),
+        0x19 => process_set_schedule(program_id, accounts, data),
+        0x1A => process_init_ladder(program_id, accounts, data),
+        0x1B => process_publish_ladder(program_id, accounts, data),
+        0x1C => process_init_feed(program_id, accounts, data),
+        0x1D => process_add_feed(program_id, accounts, data),
+        0x1E => process_set_feed_divergence(program_id, accounts, data),
+        0x1F => process_set_oracle_decimals(program_id, accounts, data),
+        0x20 => process_set_feed_slot_age(program_id, accounts, data),
+        0x21 => process_set_feed_fallback(program_id, accounts, data),
+        0x22 => process_set_fee_tiers(program_id, accounts, data),
+        0x23 => process_set_rebate(program_id, accounts, data),
+        0x24 => process_record_withdrawal(program_id, accounts, data),
+        0x25 => process_set_fee_discount(program_id, accounts, data),
+        0x26 => process_init_tip_jar(program_id, accounts, data),
+        0x27 => process_set_tip(program_id, accounts, data),
+        0x28 => process_close_tip_jar(program_id, accounts, data),
+        0x29 => process_init_referral(program_id, accounts, data),
+        0x2A => process_set_referral_share(program_id, accounts, data),
+        0x2B => process_set_min_notional(program_id, accounts, data),
+        0x2C => process_set_skim(program_id, accounts, data),
+        0x2D => process_claim_skim(program_id, accounts, data),
+        0x2E => process_init_trader(program_id, accounts, data),
+        0x2F => process_set_loyalty_tiers(program_id, accounts, data),
+        0x30 => process_sweep_protocol_fee(program_id, accounts, data),
+        0x31 => process_set_inventory_decay(program_id, accounts, data),
+        0x32 => process_set_inventory_band(program_id, accounts, data),
+        0x33 => process_set_inventory_caps(program_id, accounts, data),
+        0x34 => process_set_reduce_only_threshold(program_id, accounts, data),
+        0x35 => process_transfer_inventory(program_id, accounts, data),
+        0x36 => process_set_inventory_depth_cap(program_id, accounts, data),
+        0x37 => process_sync_inventory(program_id, accounts, data),
+        0x38 => process_create_context(program_id, accounts, data),
+        0x39 => process_bind_slab(program_id, accounts, data),
+        0x3A => process_set_signals(program_id, accounts, data),
         _ => Err(ProgramError::InvalidInstructionData),
     }
 }
@@ -154,17 +264,16 @@ fn process_instruction(
 // =============================================================================
 // Match Instruction (tag 0x00)
 //
-// Pricing logic:
-//   1. Compute coverage ratio (insurance / OI)
-//   2. Determine tier → sets spread multiplier and fill cap
-//   3. Add inventory imbalance penalty
-//   4. Clamp to [min_spread, max_spread]
-//   5. Apply fill limit based on tier
+// Prices the trade with `quote`, then commits it: inventory and last prices
+// are updated and the MatcherReturn is written for percolator. Data running
+// on past CALL_LEN names the referrer a bound referral account credits with
+// its share of the fill's fee. A trader account passed with its trader gets
+// the schedule's loyalty discount and records the fill's volume.
 //
 // The result: thin liquidity is automatically expensive.
 // =============================================================================
 fn process_match(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
 ) -> ProgramResult {
@@ -177,6 +286,10 @@ fn process_match(
 
     let lp_pda = &accounts[0];
     let ctx_account = &accounts[1];
+    check_ctx_account(program_id, ctx_account)?;
+    // Match reads every account it is passed (the signal accounts run to the
+    // end), so none of them may repeat
+    check_distinct(accounts)?;
 
     if !lp_pda.is_signer {
         msg!("ERROR: LP PDA must be a signer");
@@ -184,221 +297,511 @@ fn process_match(
     }
 
     let mut ctx_data = ctx_account.try_borrow_mut_data()?;
-    if ctx_data.len() < 320 {
+    if ctx_data.len() < CTX_LEN {
         return Err(ProgramError::AccountDataTooSmall);
     }
 
-    let magic = u64::from_le_bytes(ctx_data[CTX_BASE..CTX_BASE + 8].try_into().unwrap());
-    if magic != MAGIC {
+    let context = MatcherContext::view(&ctx_data);
+    if context.magic.get() != MAGIC {
         msg!("ERROR: Invalid context magic");
-        return Err(ProgramError::InvalidAccountData);
+        return Err(MatcherError::NotInitialized.into());
     }
 
-    let stored_pda = Pubkey::new_from_array(
-        ctx_data[CTX_BASE + CTX_LP_PDA_OFF..CTX_BASE + CTX_LP_PDA_OFF + 32]
-            .try_into()
-            .unwrap(),
-    );
-    if *lp_pda.key != stored_pda {
+    if *lp_pda.key != context.lp_pda() {
         msg!("ERROR: LP PDA mismatch");
-        return Err(ProgramError::InvalidAccountData);
+        return Err(MatcherError::PdaMismatch.into());
+    }
+
+    if context.has_flag(CTX_FLAG_PAUSED) {
+        msg!("REJECT: matcher is paused");
+        return Err(MatcherError::Paused.into());
+    }
+
+    // The slab is optional here; when the caller passes it, it must be ours.
+    // A shared context needs it to know which market's book the trade is on.
+    let slab_account = accounts.get(2);
+    if let Some(slab_account) = slab_account {
+        check_bound_slab(slab_account, &ctx_data)?;
+    }
+    let book = book_for(&ctx_data, slab_account.map(|a| a.key))?;
+    // A referral account, told apart by its magic, may follow it; then a
+    // trader account, also told apart by its magic, and its trader, who
+    // signs; then a scheduled context's schedule, then a context's feed
+    // account and a price account per feed, then the stats account, which
+    // has to be the one bound by InitStats, and any signal accounts
+    let (referral_account, rest) = match accounts.get(3..).unwrap_or_default() {
+        [first, rest @ ..] if is_referral(first) => (Some(first), rest),
+        rest => (None, rest),
+    };
+    if let Some(referral_account) = referral_account {
+        check_bound_referral(program_id, referral_account, ctx_account.key)?;
+    }
+    let (trader_account, rest) = match rest {
+        [first, trader, rest @ ..] if is_trader(first) => {
+            check_bound_trader(program_id, first, trader, ctx_account.key)?;
+            (Some(first), rest)
+        }
+        [first] if is_trader(first) => {
+            msg!("ERROR: The trader account needs its trader after it");
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        rest => (None, rest),
+    };
+    let (schedule_account, rest) = split_schedule(program_id, ctx_account.key, rest, &ctx_data)?;
+    let (feed_account, rest) = split_feed(program_id, ctx_account.key, rest, &ctx_data)?;
+    let (price_accounts, rest) = match feed_account {
+        Some(feed_account) => {
+            let count = feed_count(&feed_account.try_borrow_data()?);
+            if rest.len() < count {
+                msg!("ERROR: The feeds' {} price accounts weren't all passed", count);
+                return Err(MatcherError::InvalidFeedPrice.into());
+            }
+            rest.split_at(count)
+        }
+        None => (&[][..], rest),
+    };
+    let (stats_account, signal_accounts) = stats_and_signals(program_id, rest, &ctx_data, schedule_account)?;
+    // Once InitStats has bound one, every fill goes through it: the protocol
+    // fee, the insurance skim and the PnL accrue there, and a taker could
+    // otherwise skip them by leaving it out
+    if stats_account.is_none() && is_extended(&ctx_data) && ContextExtension::view(&ctx_data).stats() != Pubkey::default() {
+        msg!("ERROR: The context's stats account wasn't passed");
+        return Err(MatcherError::StatsRequired.into());
     }
 
     // Parse CPI call data
     let req_id = u64::from_le_bytes(data[1..9].try_into().unwrap());
     let _lp_idx = u16::from_le_bytes(data[9..11].try_into().unwrap());
     let lp_account_id = u64::from_le_bytes(data[11..19].try_into().unwrap());
-    let oracle_price_e6 = u64::from_le_bytes(data[19..27].try_into().unwrap());
+    let call_price_e6 = u64::from_le_bytes(data[19..27].try_into().unwrap());
     let trade_size = i128::from_le_bytes(data[27..43].try_into().unwrap());
+    let referrer = data.get(CALL_REFERRER_OFF..REFERRED_CALL_LEN).map(|key| Pubkey::new_from_array(key.try_into().unwrap()));
 
-    if oracle_price_e6 == 0 {
-        msg!("ERROR: Zero oracle price");
-        return Err(ProgramError::InvalidInstructionData);
-    }
-
-    // Read context parameters
-    let base_fee_bps = read_u32(&ctx_data, CTX_BASE + CTX_BASE_FEE_OFF) as u64;
-    let min_spread_bps = read_u32(&ctx_data, CTX_BASE + CTX_MIN_SPREAD_OFF) as u64;
-    let max_spread_bps = read_u32(&ctx_data, CTX_BASE + CTX_MAX_SPREAD_OFF) as u64;
-    let imbalance_k_bps = read_u32(&ctx_data, CTX_BASE + CTX_IMBALANCE_K_OFF) as u64;
-    let liquidity_e6 = read_u128(&ctx_data, CTX_BASE + CTX_LIQUIDITY_OFF);
-    let base_max_fill = read_u128(&ctx_data, CTX_BASE + CTX_MAX_FILL_OFF);
-    let inventory = read_i128(&ctx_data, CTX_BASE + CTX_INVENTORY_OFF);
-    let max_inventory = read_u128(&ctx_data, CTX_BASE + CTX_MAX_INVENTORY_OFF);
-
-    // Read credibility signals
-    let insurance_snapshot = read_u128(&ctx_data, CTX_BASE + CTX_INSURANCE_OFF);
-    let total_oi_snapshot = read_u128(&ctx_data, CTX_BASE + CTX_TOTAL_OI_OFF);
-    let insurance_weight_bps = read_u32(&ctx_data, CTX_BASE + CTX_INSURANCE_WEIGHT_OFF) as u64;
-
-    // =========================================================================
-    // STEP 1: Compute coverage ratio in bps (0 = no insurance, 10000 = 100%)
-    // =========================================================================
-    let coverage_bps: u64 = if total_oi_snapshot > 0 {
-        let ratio = ((insurance_snapshot as u128) * (BPS as u128))
-            .checked_div(total_oi_snapshot as u128)
-            .unwrap_or(0);
-        // Allow >10000 (>100% coverage) — don't cap here
-        ratio.min(u64::MAX as u128) as u64
-    } else {
-        // No OI: if there's any insurance, treat as FORTIFIED; else NORMAL
-        if insurance_snapshot > 0 { TIER_STRONG_BPS } else { TIER_FRAGILE_BPS }
-    };
-
-    // =========================================================================
-    // STEP 2: Determine tier → spread adjustment and fill multiplier
-    // =========================================================================
-    let (tier_name, spread_bps, fill_pct) = if coverage_bps < TIER_CRITICAL_BPS {
-        // CRITICAL: <10% coverage. Max spread. Severely limited fills.
-        ("CRITICAL", max_spread_bps, FILL_PCT_CRITICAL)
-    } else if coverage_bps < TIER_FRAGILE_BPS {
-        // FRAGILE: 10-25%. Interpolate between max_spread and 75% of spread range.
-        // Linear interpolation: progress from 10% to 25%
-        let progress = coverage_bps - TIER_CRITICAL_BPS; // 0..1500
-        let range = TIER_FRAGILE_BPS - TIER_CRITICAL_BPS; // 1500
-        let spread_range = max_spread_bps - min_spread_bps;
-        // At 10%: spread = max_spread. At 25%: spread = max_spread - 25% of range
-        let reduction = (spread_range / 4) * (progress as u64) / (range as u64);
-        (
-            "FRAGILE",
-            max_spread_bps.saturating_sub(reduction),
-            FILL_PCT_FRAGILE,
-        )
-    } else if coverage_bps < TIER_NORMAL_BPS {
-        // NORMAL: 25-100%. Standard linear discount from insurance weight.
-        // coverage_fraction = (coverage - 25%) / 75%
-        let progress = coverage_bps - TIER_FRAGILE_BPS; // 0..7500
-        let range = TIER_NORMAL_BPS - TIER_FRAGILE_BPS; // 7500
-        let discount = if insurance_weight_bps > 0 {
-            (insurance_weight_bps * progress as u64) / range as u64
-        } else {
-            0
-        };
-        let base = max_spread_bps - (max_spread_bps - min_spread_bps) / 4; // starts where FRAGILE ends
-        (
-            "NORMAL",
-            base.saturating_sub(discount),
-            FILL_PCT_NORMAL,
-        )
-    } else if coverage_bps < TIER_STRONG_BPS {
-        // STRONG: 100-200%. Full insurance discount applied. Tight spreads.
-        let discount = insurance_weight_bps;
-        (
-            "STRONG",
-            min_spread_bps.saturating_add(insurance_weight_bps).saturating_sub(discount),
-            FILL_PCT_STRONG,
-        )
-    } else {
-        // FORTIFIED: >200%. Minimum spread. Bonus fill capacity.
-        ("FORTIFIED", min_spread_bps, FILL_PCT_FORTIFIED)
-    };
-
-    // =========================================================================
-    // STEP 3: Inventory imbalance penalty (standard market-making, all tiers)
-    // =========================================================================
-    let mut final_spread = spread_bps;
-    if liquidity_e6 > 0 && imbalance_k_bps > 0 {
-        let inventory_abs = inventory.unsigned_abs();
-        let imbalance_cost = (imbalance_k_bps as u128)
-            .checked_mul(inventory_abs)
-            .unwrap_or(u128::MAX)
-            / liquidity_e6;
-        final_spread = final_spread.saturating_add(imbalance_cost as u64);
-    }
-
-    // =========================================================================
-    // STEP 4: Clamp spread to [1, max_spread_bps]
-    // =========================================================================
-    final_spread = final_spread.clamp(1, max_spread_bps);
-
-    // =========================================================================
-    // STEP 5: Apply fill limit based on tier
-    // =========================================================================
-    let effective_max_fill = if base_max_fill > 0 {
-        (base_max_fill * fill_pct) / 100
-    } else {
-        0 // 0 means unlimited in the original design
+    // A context with a feed prices off it (the median, with several) rather
+    // than the caller's word, scaled to e6 by oracle_decimals; the
+    // MatcherReturn still echoes the price percolator sent
+    let clock = Clock::get()?;
+    let (oracle_price_e6, oracle_bps) = match feed_account {
+        Some(feed_account) => feed_price(feed_account, price_accounts, &clock)?,
+        None => (oracle_e6(&ctx_data, call_price_e6), 0),
     };
 
-    let abs_size = trade_size.unsigned_abs();
-    if effective_max_fill > 0 && abs_size > effective_max_fill {
-        msg!(
-            "REJECT: trade {} exceeds tier {} fill limit {} (base {} * {}%)",
-            abs_size, tier_name, effective_max_fill, base_max_fill, fill_pct
-        );
-        return Err(ProgramError::InvalidInstructionData);
+    // MatcherReturn, followed by the quote breakdown, fill sequence and hedge
+    // target
+    let matcher_return = |exec_price_e6: u64, exec_size: i128, reject_reason: u64, breakdown: &[u8; QUOTE_LEN], fill_seq: u64, hedge: i128| {
+        let mut ret = [0u8; MATCH_RET_LEN];
+        write_u32(&mut ret, RET_ABI_VERSION_OFF, MATCHER_ABI_VERSION);
+        write_u32(&mut ret, RET_FLAGS_OFF, FLAG_VALID);
+        write_u64(&mut ret, RET_EXEC_PRICE_OFF, exec_price_e6);
+        write_i128(&mut ret, RET_EXEC_SIZE_OFF, exec_size);
+        write_u64(&mut ret, RET_REQ_ID_OFF, req_id);
+        write_u64(&mut ret, RET_LP_ACCOUNT_ID_OFF, lp_account_id);
+        write_u64(&mut ret, RET_ORACLE_ECHO_OFF, call_price_e6);
+        write_u64(&mut ret, RET_REJECT_REASON_OFF, reject_reason);
+        ret[RET_LEN..MATCH_RET_FILL_SEQ_OFF].copy_from_slice(breakdown);
+        write_u64(&mut ret, MATCH_RET_FILL_SEQ_OFF, fill_seq);
+        write_i128(&mut ret, MATCH_RET_HEDGE_OFF, hedge);
+        ret
+    };
+
+    let current_slot = clock.slot;
+    let rfq = posted_rfq(&ctx_data).filter(|rfq| rfq.covers(trade_size, current_slot));
+    let inventory_band = schedule_inventory_band(schedule_account)?;
+    let stats_data = stats_account.map(|a| a.try_borrow_data()).transpose()?;
+    let companions = Companions {
+        stats_data: stats_data.as_deref().map(|d| &d[..]),
+        signal_bps: signal_surcharge(signal_accounts, current_slot)?,
+        spread_mult_bps: spread_mult(schedule_account, current_slot)?,
+        fee_tiers: fee_tiers(schedule_account)?,
+        rebate_bps: schedule_rebate(schedule_account)?,
+        fee_discount_bps: schedule_fee_discount(schedule_account)?,
+        min_notional_e6: schedule_min_notional(schedule_account)?,
+        inventory_halflife: schedule_inventory_halflife(schedule_account)?,
+        inventory_band,
+        inventory_caps: schedule_inventory_caps(schedule_account)?,
+        reduce_only_bps: schedule_reduce_only_bps(schedule_account)?,
+        max_inventory_bps: schedule_max_inventory_bps(schedule_account)?,
+        loyalty_bps: loyalty_discount(schedule_account, trader_account)?,
+        oracle_bps,
+    };
+    let quoted = quote(&ctx_data, &book, &companions, oracle_price_e6, trade_size, current_slot);
+    drop(stats_data);
+    let q = match quoted {
+        Ok(q) => q,
+        Err(err) => match soft_reject_reason(&err) {
+            // Soft-reject mode: answer "no quote" with a zero fill and the
+            // reason, so percolator can route elsewhere instead of aborting.
+            // A side the LP has turned off always answers this way, as does
+            // a book gone reduce-only near its cap (without the LP's switch,
+            // nothing else refuses with ReduceOnly).
+            Some(reason)
+                if reason == MatcherError::SideDisabled
+                    || (reason == MatcherError::ReduceOnly && !MatcherContext::view(&ctx_data).has_flag(CTX_FLAG_REDUCE_ONLY))
+                    || MatcherContext::view(&ctx_data).has_flag(CTX_FLAG_SOFT_REJECT) =>
+            {
+                let ret = matcher_return(oracle_price_e6, 0, reason as u64, &[0u8; QUOTE_LEN], 0, 0);
+                emit_matcher_return(&mut ctx_data, &ret);
+                msg!("credibility-match: soft reject {} (code {}) size=0/{}", reason.message(), reason as u32, trade_size);
+                return Ok(());
+            }
+            _ => return Err(err),
+        },
+    };
+
+    // Update state. The context's last prices follow the latest fill on any
+    // market, so a shared context counts as live once one of them has traded.
+    write_i128(&mut ctx_data, book.inventory, q.new_inventory);
+    MatcherContext::view_mut(&mut ctx_data).last_oracle_price_e6.set(oracle_price_e6);
+    write_u64(&mut ctx_data, book.last_oracle_price, oracle_price_e6);
+    if let Some(off) = book.last_oracle_slot {
+        write_u64(&mut ctx_data, off, current_slot);
     }
+    // Fills are numbered from 1 so downstream accounting can spot a gap or
+    // reorder; v5 contexts have nowhere to keep the counter
+    let mut fill_seq = 0;
+    if is_extended(&ctx_data) {
+        let ext = ContextExtension::view_mut(&mut ctx_data);
+        fill_seq = ext.fill_seq.get().wrapping_add(1);
+        ext.fill_seq.set(fill_seq);
 
-    // Enforce max inventory
-    let new_inventory = inventory + trade_size;
-    if max_inventory > 0 {
-        let new_abs = new_inventory.unsigned_abs();
-        if new_abs > max_inventory {
-            msg!("ERROR: Would exceed inventory limit");
-            return Err(ProgramError::InvalidInstructionData);
+        // Telemetry for dashboards; fill_seq doubles as the trade count.
+        // Notional is figured the way percolator figures it for its fees.
+        let notional = q.fill_size.unsigned_abs().saturating_mul(q.exec_price_e6 as u128) / 1_000_000;
+        let fees = notional.saturating_mul(q.fee_bps as u128) / BPS as u128;
+        ext.cum_notional.set(ext.cum_notional.get().saturating_add(notional));
+        ext.cum_fees.set(ext.cum_fees.get().saturating_add(fees));
+        if let (Some(referral_account), Some(referrer)) = (referral_account, referrer) {
+            credit_referrer(&mut referral_account.try_borrow_mut_data()?, &referrer, fees);
+        }
+
+        let gross_off = if q.fill_size > 0 { book.gross_long } else { book.gross_short };
+        if let Some(off) = gross_off {
+            let gross = read_u128(&ctx_data, off).saturating_add(q.fill_size.unsigned_abs());
+            write_u128(&mut ctx_data, off, gross);
+        }
+    }
+    MatcherContext::view_mut(&mut ctx_data).last_exec_price_e6.set(q.exec_price_e6);
+    write_u64(&mut ctx_data, book.last_exec_price, q.exec_price_e6);
+    // A fill against the posted RFQ uses it up; what's left stays posted
+    if let Some(rfq) = rfq {
+        let size_left = rfq.size_left - q.fill_size;
+        ContextExtension::view_mut(&mut ctx_data).rfq_size_left.set(size_left);
+        if size_left == 0 {
+            clear_rfq(&mut ctx_data);
         }
+        msg!("credibility-rfq-fill: price={} size={} left={}", rfq.price_e6, q.fill_size, size_left);
+    }
+    if let Some(stats_account) = stats_account {
+        let mut stats_data = stats_account.try_borrow_mut_data()?;
+        record_fill(&mut stats_data, fill_seq, current_slot, &q);
+        record_oracle(&mut stats_data, current_slot, oracle_price_e6);
+        record_skim(&mut stats_data, &q);
+        record_protocol_fee(&mut stats_data, &q);
+        record_pnl(&mut stats_data, &q, oracle_price_e6);
+    }
+    if let Some(trader_account) = trader_account {
+        record_trade(&mut trader_account.try_borrow_mut_data()?, current_slot, &q);
     }
 
-    // =========================================================================
-    // STEP 6: Calculate execution price
-    // =========================================================================
-    let total_cost_bps = final_spread + base_fee_bps;
-    let exec_price_e6 = if trade_size > 0 {
-        // Buying: oracle + spread
-        let numer = (oracle_price_e6 as u128) * ((BPS as u128) + (total_cost_bps as u128));
-        (numer / (BPS as u128)) as u64
-    } else {
-        // Selling: oracle - spread
-        let numer = (oracle_price_e6 as u128)
-            * ((BPS as u128) - total_cost_bps.min(BPS) as u128);
-        (numer / (BPS as u128)) as u64
-    };
-
-    // Update state
-    write_i128(&mut ctx_data, CTX_BASE + CTX_INVENTORY_OFF, new_inventory);
-    write_u64(&mut ctx_data, CTX_BASE + CTX_LAST_ORACLE_OFF, oracle_price_e6);
-    write_u64(&mut ctx_data, CTX_BASE + CTX_LAST_EXEC_OFF, exec_price_e6);
-
-    // Write MatcherReturn
-    write_u32(&mut ctx_data, RET_ABI_VERSION_OFF, MATCHER_ABI_VERSION);
-    write_u32(&mut ctx_data, RET_FLAGS_OFF, FLAG_VALID);
-    write_u64(&mut ctx_data, RET_EXEC_PRICE_OFF, exec_price_e6);
-    write_i128(&mut ctx_data, RET_EXEC_SIZE_OFF, trade_size);
-    write_u64(&mut ctx_data, RET_REQ_ID_OFF, req_id);
-    write_u64(&mut ctx_data, RET_LP_ACCOUNT_ID_OFF, lp_account_id);
-    write_u64(&mut ctx_data, RET_ORACLE_ECHO_OFF, oracle_price_e6);
-    write_u64(&mut ctx_data, RET_RESERVED_OFF, 0);
+    // What a hedger should trade elsewhere: the inventory past the soft band,
+    // or all of it without one
+    let hedge = hedge_size(q.new_inventory, inventory_band.0);
+    let ret = matcher_return(q.exec_price_e6, q.fill_size, 0, &breakdown(&q), fill_seq, hedge);
+    emit_matcher_return(&mut ctx_data, &ret);
 
     msg!(
-        "credibility-match: tier={} coverage={}bps spread={}bps fee={}bps fill_cap={}% price={} size={}",
-        tier_name, coverage_bps, final_spread, base_fee_bps, fill_pct, exec_price_e6, trade_size
+        "credibility-match: seq={} tier={} coverage={}bps spread={}bps impact={}bps stale={}bps crank={}bps liq={}bps skew={}bps (funding {}bps) fee={}bps fill_cap={}% price={} size={}/{} hedge={}",
+        fill_seq, TIER_NAMES[q.tier as usize], q.coverage_bps, q.spread_bps, q.impact_bps, q.stale_bps, q.crank_bps, q.liq_bps, q.skew_bps, q.funding_skew_bps, q.fee_bps, q.fill_pct, q.exec_price_e6, q.fill_size, trade_size, hedge
     );
 
     Ok(())
 }
 
+/// Credit `referrer` with the referral account's share of a fill's `fees`,
+/// taking the next free entry for a referrer it hasn't seen. Once all
+/// MAX_REFERRERS entries are taken a new referrer earns nothing, and neither
+/// does the default key; the fill goes through either way.
+fn credit_referrer(referral_data: &mut [u8], referrer: &Pubkey, fees: u128) {
+    if *referrer == Pubkey::default() {
+        return;
+    }
+    let count = read_u16(referral_data, REFERRAL_COUNT_OFF) as usize;
+    let entry = |i: usize| REFERRAL_HEADER_LEN + i * REFERRER_LEN;
+    let index = match (0..count).find(|&i| read_pubkey(referral_data, entry(i) + REFERRER_KEY_OFF) == *referrer) {
+        Some(index) => index,
+        None if count < MAX_REFERRERS => {
+            referral_data[entry(count) + REFERRER_KEY_OFF..entry(count) + REFERRER_KEY_OFF + 32].copy_from_slice(referrer.as_ref());
+            write_u16(referral_data, REFERRAL_COUNT_OFF, count as u16 + 1);
+            count
+        }
+        None => {
+            msg!("credibility-referral: no room for referrer {}", referrer);
+            return;
+        }
+    };
+
+    let off = entry(index);
+    let share_bps = read_u16(referral_data, REFERRAL_SHARE_OFF) as u128;
+    let credit = fees.saturating_mul(share_bps) / BPS as u128;
+    let accrued = read_u128(referral_data, off + REFERRER_ACCRUED_OFF).saturating_add(credit);
+    let fills = read_u64(referral_data, off + REFERRER_FILLS_OFF).saturating_add(1);
+    write_u128(referral_data, off + REFERRER_ACCRUED_OFF, accrued);
+    write_u64(referral_data, off + REFERRER_FILLS_OFF, fills);
+    msg!("credibility-referral: referrer={} credit={} accrued={}", referrer, credit, accrued);
+}
+
+/// Encode a quote as the breakdown QuotePreview returns and Match appends to
+/// its MatcherReturn.
+fn breakdown(q: &Quote) -> [u8; QUOTE_LEN] {
+    let mut ret = [0u8; QUOTE_LEN];
+    write_u64(&mut ret, QUOTE_EXEC_PRICE_OFF, q.exec_price_e6);
+    write_u64(&mut ret, QUOTE_COVERAGE_OFF, q.coverage_bps);
+    write_u64(&mut ret, QUOTE_TIER_OFF, q.tier as u64);
+    write_u64(&mut ret, QUOTE_FILL_PCT_OFF, q.fill_pct as u64);
+    write_u64(&mut ret, QUOTE_TIER_SPREAD_OFF, q.tier_spread_bps);
+    write_u64(&mut ret, QUOTE_IMBALANCE_OFF, q.imbalance_bps);
+    write_u64(&mut ret, QUOTE_IMPACT_OFF, q.impact_bps);
+    write_u64(&mut ret, QUOTE_STALE_OFF, q.stale_bps);
+    write_u64(&mut ret, QUOTE_CRANK_OFF, q.crank_bps);
+    write_u64(&mut ret, QUOTE_LIQ_OFF, q.liq_bps);
+    write_u64(&mut ret, QUOTE_SPREAD_OFF, q.spread_bps);
+    write_i64(&mut ret, QUOTE_SKEW_OFF, q.skew_bps);
+    write_i64(&mut ret, QUOTE_FUNDING_SKEW_OFF, q.funding_skew_bps);
+    write_u64(&mut ret, QUOTE_FEE_OFF, q.fee_bps);
+    write_i128(&mut ret, QUOTE_NEW_INVENTORY_OFF, q.new_inventory);
+    write_i128(&mut ret, QUOTE_FILL_SIZE_OFF, q.fill_size);
+    write_u64(&mut ret, QUOTE_DISCOUNT_OFF, q.discount_bps);
+    write_u64(&mut ret, QUOTE_FLOW_OFF, q.flow_bps);
+    write_u64(&mut ret, QUOTE_ANCHOR_OFF, q.anchor_price_e6);
+    write_u64(&mut ret, QUOTE_SIGNAL_OFF, q.signal_bps);
+    write_u64(&mut ret, QUOTE_SCHEDULE_OFF, q.schedule_bps);
+    write_u64(&mut ret, QUOTE_ORACLE_OFF, q.oracle_bps);
+    write_u64(&mut ret, QUOTE_REBATE_OFF, q.rebate_bps);
+    ret
+}
+
+/// What the accounts after a quoting instruction's fixed ones bring to the
+/// price: the bound stats account's data, for the TWAP, the signal accounts'
+/// surcharge, the schedule's multiplier for the slot, its fee tiers, rebate,
+/// fee discount, minimum notional, inventory halflife, band, caps,
+/// reduce-only threshold and depth cap, the loyalty
+/// discount a trader account earns, and what the oracle feeds' disagreement
+/// adds.
+struct Companions<'a> {
+    stats_data: Option<&'a [u8]>,
+    signal_bps: u64,
+    spread_mult_bps: u64,
+    fee_tiers: FeeTiers,
+    rebate_bps: u64,
+    fee_discount_bps: u64,
+    min_notional_e6: u128,
+    inventory_halflife: u32,
+    /// The soft inventory limit and band_k_bps.
+    inventory_band: (u128, u64),
+    /// The long and short inventory caps.
+    inventory_caps: (u128, u128),
+    reduce_only_bps: u64,
+    /// max_inventory in bps of the quoting depth; 0 = as set.
+    max_inventory_bps: u32,
+    loyalty_bps: u32,
+    oracle_bps: u64,
+}
+
+/// A schedule's fee tiers as `(min_notional, fee_bps)`, unused ones zero.
+type FeeTiers = [(u128, u32); MAX_FEE_TIERS];
+
+// =============================================================================
+// Pricing path shared by Match and QuotePreview
+//
+// The math lives in provenance-core; this decodes its inputs from the context
+// and the caller's companion accounts, prices with the context's kind and
+// logs what it decided. Reads the accounts, never writes them.
+// =============================================================================
+fn quote(
+    ctx_data: &[u8],
+    book: &Book,
+    companions: &Companions,
+    oracle_price_e6: u64,
+    trade_size: i128,
+    current_slot: u64,
+) -> Result<Quote, ProgramError> {
+    // A side the LP has turned off is refused before anything is priced
+    let flags = MatcherContext::view(ctx_data).flags;
+    let side_off = match trade_size {
+        1.. => flags & CTX_FLAG_NO_ASKS != 0,
+        ..0 => flags & CTX_FLAG_NO_BIDS != 0,
+        0 => false,
+    };
+    if side_off {
+        msg!("REJECT: the LP isn't quoting {}", if trade_size > 0 { "asks" } else { "bids" });
+        return Err(MatcherError::SideDisabled.into());
+    }
+
+    let mut params = Params::load(ctx_data).pricing();
+    // A cap set as a share of the depth replaces max_inventory_abs, at this
+    // oracle, so it follows the liquidity the LP quotes
+    let depth_cap = pricing::depth_inventory_cap(params.liquidity_e6, companions.max_inventory_bps as u64, oracle_price_e6);
+    if depth_cap != 0 {
+        params.max_inventory = depth_cap;
+    }
+    // A market's own caps tighten or loosen the context's; zero keeps them
+    if let Some((max_fill_off, max_inventory_off)) = book.caps {
+        let (max_fill, max_inventory) = (read_u128(ctx_data, max_fill_off), read_u128(ctx_data, max_inventory_off));
+        if max_fill != 0 {
+            params.max_fill = max_fill;
+        }
+        if max_inventory != 0 {
+            params.max_inventory = max_inventory;
+        }
+    }
+    // Dust is refused before it costs a full pricing pass; a fee tier the
+    // trade's notional at the oracle reaches replaces the base fee
+    let notional = trade_size.unsigned_abs().saturating_mul(oracle_price_e6 as u128) / 1_000_000;
+    if notional < companions.min_notional_e6 {
+        msg!("REJECT: notional {} below the minimum {}", notional, companions.min_notional_e6);
+        return Err(MatcherError::BelowMinNotional.into());
+    }
+    if let Some(fee_bps) = pricing::tier_fee_bps(companions.fee_tiers, notional) {
+        msg!("credibility-fee-tier: notional={} fee={}bps (base {}bps)", notional, fee_bps, params.base_fee_bps);
+        params.base_fee_bps = fee_bps;
+    }
+    if companions.loyalty_bps > 0 {
+        let fee_bps = params.base_fee_bps.saturating_sub(companions.loyalty_bps);
+        msg!("credibility-loyalty: fee={}bps ({}bps off)", fee_bps, companions.loyalty_bps);
+        params.base_fee_bps = fee_bps;
+    }
+    let opt_u64 = |off: Option<usize>| off.map_or(0, |off| read_u64(ctx_data, off));
+    let opt_u128 = |off: Option<usize>| off.map_or(0, |off| read_u128(ctx_data, off));
+    let twap_window = params.twap_window_slots as u64;
+    let state = pricing::State {
+        current_slot,
+        inventory: read_i128(ctx_data, book.inventory),
+        insurance_snapshot: read_u128(ctx_data, book.insurance),
+        total_oi_snapshot: read_u128(ctx_data, book.total_oi),
+        snapshot_slot: read_u64(ctx_data, book.snapshot_slot),
+        last_crank_slot: read_u64(ctx_data, book.last_crank_slot),
+        liq_heat: read_u32(ctx_data, book.liq_heat) as u64,
+        funding_rate_bps_per_slot: book.funding_rate.map_or(0, |off| read_i64(ctx_data, off)),
+        last_oracle_price_e6: read_u64(ctx_data, book.last_oracle_price),
+        last_oracle_slot: opt_u64(book.last_oracle_slot),
+        gross_long: opt_u128(book.gross_long),
+        gross_short: opt_u128(book.gross_short),
+        reduce_only: MatcherContext::view(ctx_data).has_flag(CTX_FLAG_REDUCE_ONLY),
+        twap_price_e6: match companions.stats_data {
+            Some(stats_data) if twap_window > 0 => {
+                Some(twap_price(stats_data, twap_window, current_slot, oracle_price_e6))
+            }
+            _ => None,
+        },
+        market_age: read_u64(ctx_data, book.market_age),
+        last_deficit_slot: read_u64(ctx_data, book.last_deficit_slot),
+        last_liq_slot: opt_u64(book.last_liq_slot),
+        signal_bps: companions.signal_bps,
+        spread_mult_bps: companions.spread_mult_bps,
+        oracle_bps: companions.oracle_bps,
+        rebate_bps: companions.rebate_bps,
+        fee_discount_bps: companions.fee_discount_bps,
+        inventory_halflife: companions.inventory_halflife,
+        soft_inventory: companions.inventory_band.0,
+        band_k_bps: companions.inventory_band.1,
+        max_long_inventory: companions.inventory_caps.0,
+        max_short_inventory: companions.inventory_caps.1,
+        reduce_only_bps: companions.reduce_only_bps,
+    };
+
+    // Every kind reads the same context; the plain one ignores the snapshots.
+    // A posted RFQ quote on the trade's side takes precedence over all of them.
+    let price = match MatcherContext::view(ctx_data).kind {
+        KIND_PLAIN => pricing::plain::quote,
+        KIND_MULTI => pricing::multi::quote,
+        KIND_STABLE => pricing::stable::quote,
+        _ => pricing::quote,
+    };
+    let rfq = posted_rfq(ctx_data).filter(|rfq| rfq.covers(trade_size, current_slot));
+    let quoted = match &rfq {
+        Some(rfq) => pricing::rfq::fill(&params, &state, rfq, oracle_price_e6, trade_size),
+        None => price(&params, &state, oracle_price_e6, trade_size),
+    };
+    let q = quoted.map_err(|err| {
+        match err {
+            QuoteError::ZeroOraclePrice => msg!("ERROR: Zero oracle price"),
+            QuoteError::OracleJump { last_price_e6, moved_bps, elapsed_slots } => msg!(
+                "REJECT: oracle moved {}bps ({} -> {}) in {} slots, limit {}bps",
+                moved_bps, last_price_e6, oracle_price_e6, elapsed_slots, params.oracle_jump_bps
+            ),
+            QuoteError::StatsRequired => msg!("ERROR: TWAP anchoring needs the bound stats account"),
+            QuoteError::InventoryOverflow { fill_size } => {
+                msg!("ERROR: Inventory {} + {} overflows", state.inventory, fill_size)
+            }
+            QuoteError::ReduceOnly { near_cap, new_inventory } => msg!(
+                "REJECT: reduce-only{}: inventory {} -> {}",
+                if near_cap { " (near cap)" } else { "" }, state.inventory, new_inventory
+            ),
+            QuoteError::InventoryLimit => msg!("ERROR: Would exceed inventory limit"),
+        }
+        ProgramError::from(MatcherError::from(err))
+    })?;
+
+    if q.fill_size != trade_size {
+        match rfq {
+            Some(rfq) => msg!(
+                "CLIP: trade {} exceeds RFQ fill limit {} ({} left, max_fill {})",
+                trade_size, q.fill_size.unsigned_abs(), rfq.size_left.unsigned_abs(), params.max_fill
+            ),
+            None => msg!(
+                "CLIP: trade {} exceeds tier {} fill limit {} (base {} * {}%)",
+                trade_size, TIER_NAMES[q.tier as usize], q.fill_size.unsigned_abs(), params.max_fill, q.fill_pct
+            ),
+        }
+    }
+    Ok(q)
+}
+
 // =============================================================================
-// Init Instruction (tag 0x02) — unchanged from v1
+// Init Instruction (tag 0x02)
+//
+// Sets up a zeroed, program-owned, rent-exempt context of a layout size: 320
+// bytes for v5, 832 or more for v6, 2880 or more for a shared table. Signed by
+// the LP PDA, or by the separate authority a v6 context records. A context
+// CreateContext made must name the pair it was derived from and take the LP
+// PDA's signature; one at a keypair address is the legacy path. Passing the
+// slab binds it and pins its owner as the percolator program.
 // =============================================================================
 fn process_init(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
 ) -> ProgramResult {
     if accounts.len() < 2 {
         return Err(ProgramError::NotEnoughAccountKeys);
     }
-    if data.len() < 74 {
+    if data.len() < 2 + PARAMS_LEN {
         return Err(ProgramError::InvalidInstructionData);
     }
 
     let lp_pda = &accounts[0];
     let ctx_account = &accounts[1];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..accounts.len().min(4)])?;
 
     let mut ctx_data = ctx_account.try_borrow_mut_data()?;
-    if ctx_data.len() < 320 {
+    if ctx_data.len() < CTX_LEN {
+        msg!("ERROR: Context is {} bytes; needs {} (v5) or {} (v6)", ctx_data.len(), CTX_LEN, CTX_EXT_LEN);
         return Err(ProgramError::AccountDataTooSmall);
     }
+    // Between the two layouts the extension wouldn't fit, and the account
+    // would silently come up as v5 with rent paid for bytes nothing uses
+    if ctx_data.len() > CTX_LEN && ctx_data.len() < CTX_EXT_LEN {
+        msg!("ERROR: Context is {} bytes; needs {} (v5) or {} (v6)", ctx_data.len(), CTX_LEN, CTX_EXT_LEN);
+        return Err(ProgramError::InvalidAccountData);
+    }
+    // A context below the exemption threshold can be collected mid-life,
+    // taking the inventory with it
+    let min_balance = Rent::get()?.minimum_balance(ctx_data.len());
+    if ctx_account.lamports() < min_balance {
+        msg!("ERROR: Context holds {} lamports; rent exemption needs {}", ctx_account.lamports(), min_balance);
+        return Err(ProgramError::AccountNotRentExempt);
+    }
 
     let existing_magic = u64::from_le_bytes(ctx_data[CTX_BASE..CTX_BASE + 8].try_into().unwrap());
     if existing_magic == MAGIC {
@@ -406,52 +809,165 @@ fn process_init(
         return Err(ProgramError::AccountAlreadyInitialized);
     }
 
-    let mut off = 1;
-    let kind = data[off]; off += 1;
-    if kind != KIND_CREDIBILITY {
-        msg!("ERROR: Expected kind=2 (Credibility)");
-        return Err(ProgramError::InvalidInstructionData);
-    }
-
-    let base_fee_bps = u32::from_le_bytes(data[off..off + 4].try_into().unwrap()); off += 4;
-    let min_spread_bps = u32::from_le_bytes(data[off..off + 4].try_into().unwrap()); off += 4;
-    let max_spread_bps = u32::from_le_bytes(data[off..off + 4].try_into().unwrap()); off += 4;
-    let imbalance_k_bps = u32::from_le_bytes(data[off..off + 4].try_into().unwrap()); off += 4;
-    let liquidity_e6 = u128::from_le_bytes(data[off..off + 16].try_into().unwrap()); off += 16;
-    let max_fill = u128::from_le_bytes(data[off..off + 16].try_into().unwrap()); off += 16;
-    let max_inventory = u128::from_le_bytes(data[off..off + 16].try_into().unwrap()); off += 16;
-    let age_halflife = u32::from_le_bytes(data[off..off + 4].try_into().unwrap()); off += 4;
-    let insurance_weight_bps = u32::from_le_bytes(data[off..off + 4].try_into().unwrap());
-
-    write_u64(&mut ctx_data, CTX_BASE + CTX_MAGIC_OFF, MAGIC);
-    write_u32(&mut ctx_data, CTX_BASE + CTX_VERSION_OFF, VERSION);
-    ctx_data[CTX_BASE + CTX_KIND_OFF] = kind;
-    ctx_data[CTX_BASE + CTX_LP_PDA_OFF..CTX_BASE + CTX_LP_PDA_OFF + 32]
-        .copy_from_slice(&lp_pda.key.to_bytes());
-    write_u32(&mut ctx_data, CTX_BASE + CTX_BASE_FEE_OFF, base_fee_bps);
-    write_u32(&mut ctx_data, CTX_BASE + CTX_MIN_SPREAD_OFF, min_spread_bps);
-    write_u32(&mut ctx_data, CTX_BASE + CTX_MAX_SPREAD_OFF, max_spread_bps);
-    write_u32(&mut ctx_data, CTX_BASE + CTX_IMBALANCE_K_OFF, imbalance_k_bps);
-    write_u128(&mut ctx_data, CTX_BASE + CTX_LIQUIDITY_OFF, liquidity_e6);
-    write_u128(&mut ctx_data, CTX_BASE + CTX_MAX_FILL_OFF, max_fill);
-    write_i128(&mut ctx_data, CTX_BASE + CTX_INVENTORY_OFF, 0);
-    write_u128(&mut ctx_data, CTX_BASE + CTX_MAX_INVENTORY_OFF, max_inventory);
-    write_u32(&mut ctx_data, CTX_BASE + CTX_AGE_HALFLIFE_OFF, age_halflife);
-    write_u32(&mut ctx_data, CTX_BASE + CTX_INSURANCE_WEIGHT_OFF, insurance_weight_bps);
+    let kind = data[1];
+    if !matches!(kind, KIND_PLAIN | KIND_CREDIBILITY | KIND_MULTI | KIND_STABLE) {
+        msg!("ERROR: Expected kind=1 (Plain), kind=2 (Credibility), kind=3 (Multi-signal) or kind=4 (Stable)");
+        return Err(MatcherError::WrongKind.into());
+    }
+
+    let params = Params::unpack(&data[2..]);
+    let extended = ctx_data.len() >= CTX_EXT_LEN;
+    let shared = ctx_data.len() >= CTX_SHARED_LEN;
+    params.validate(kind, extended)?;
 
-    msg!(
-        "credibility-init-v2: fee={}bps spread=[{},{}]bps tiers=CRITICAL<10%<FRAGILE<25%<NORMAL<100%<STRONG<200%<FORTIFIED",
-        base_fee_bps, min_spread_bps, max_spread_bps
-    );
+    let slab = accounts.get(2);
+    if slab.is_some() && !extended {
+        msg!("ERROR: Slab binding needs a v6 context ({} bytes)", CTX_EXT_LEN);
+        return Err(MatcherError::ExtensionRequired.into());
+    }
+    let authority = accounts.get(3).map(|a| *a.key);
+    if authority.is_some() && !extended {
+        msg!("ERROR: A separate authority needs a v6 context ({} bytes)", CTX_EXT_LEN);
+        return Err(MatcherError::ExtensionRequired.into());
+    }
+    // The LP PDA, or the authority it hands the context to, vouches for the
+    // pair; a derived context is the LP's own address, so only it may take one.
+    // Whichever signed is the creator the record keeps
+    let creator = match accounts.get(3) {
+        Some(authority) if authority.is_signer && !is_derived(&ctx_data) => *authority.key,
+        _ if lp_pda.is_signer => *lp_pda.key,
+        _ => {
+            msg!("ERROR: The LP PDA or the authority it records must sign Init");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+    };
+    // A context CreateContext made takes only the pair it was derived from;
+    // one at a keypair address is the legacy path, whatever its size
+    if is_derived(&ctx_data) {
+        let slab = slab.map_or(Pubkey::default(), |slab| *slab.key);
+        check_ctx_address(program_id, ctx_account.key, lp_pda.key, &slab, MatcherContext::view(&ctx_data).bump)?;
+    }
+
+    let context = MatcherContext::view_mut(&mut ctx_data);
+    context.magic.set(MAGIC);
+    context.version.set(if extended { VERSION } else { VERSION_V5 });
+    context.kind = kind;
+    context.lp_pda = lp_pda.key.to_bytes();
+    context.inventory_base.set(0);
+    if extended {
+        let slab_key = slab.map(|a| *a.key).unwrap_or_default().to_bytes();
+        let ext = ContextExtension::view_mut(&mut ctx_data);
+        ext.funding_rate_bps_per_slot.set(0);
+        ext.market_slots.set(if shared { MAX_MARKETS as u32 } else { 0 });
+        // Whoever owns the slab now is the percolator program; pin it so a
+        // look-alike account under another owner is refused later
+        ext.percolator_program = slab.map(|a| *a.owner).unwrap_or_default().to_bytes();
+        ext.authority = authority.unwrap_or_default().to_bytes();
+        ext.pending_authority = [0; 32];
+        // A shared context lists the slab as its first market instead of
+        // binding the whole context to it
+        if shared {
+            let table = MarketEntry::table_mut(&mut ctx_data);
+            table.fill(MarketEntry::zeroed());
+            table[0].slab = slab_key;
+        } else {
+            ContextExtension::view_mut(&mut ctx_data).slab = slab_key;
+        }
+    }
+    params.store(&mut ctx_data);
+    if let Some(off) = creation_off(ctx_data.len()) {
+        let slot = Clock::get()?.slot;
+        write_u64(&mut ctx_data, off + CREATION_SLOT_OFF, slot);
+        ctx_data[off + CREATION_CREATOR_OFF..off + CREATION_CREATOR_OFF + 32].copy_from_slice(creator.as_ref());
+        msg!("credibility-created: slot={} creator={}", slot, creator);
+    }
+
+    if kind == KIND_PLAIN {
+        msg!(
+            "plain-init: fee={}bps spread=[{},{}]bps imbalance_k={}bps",
+            params.base_fee_bps, params.min_spread_bps, params.max_spread_bps, params.imbalance_k_bps
+        );
+    } else if kind == KIND_MULTI {
+        let [coverage, age, deficit, liquidations] = params.signal_weights;
+        msg!(
+            "multi-init: fee={}bps spread=[{},{}]bps weights coverage={} age={} deficit={} liquidations={}",
+            params.base_fee_bps, params.min_spread_bps, params.max_spread_bps, coverage, age, deficit, liquidations
+        );
+    } else if kind == KIND_STABLE {
+        msg!(
+            "stable-init: fee={}bps spread=[{},{}]bps band={}e-6 around {}",
+            params.base_fee_bps, params.min_spread_bps, params.max_spread_bps, params.peg_band_e6, PEG_PRICE_E6
+        );
+    } else {
+        msg!(
+            "credibility-init-v2: fee={}bps spread=[{},{}]bps tiers=CRITICAL<10%<FRAGILE<25%<NORMAL<100%<STRONG<200%<FORTIFIED",
+            params.base_fee_bps, params.min_spread_bps, params.max_spread_bps
+        );
+    }
+
+    Ok(())
+}
+
+// =============================================================================
+// Update Params Instruction (tag 0x04)
+//
+// Replaces every tunable parameter in one shot. Data after the tag is the same
+// parameter block Init takes after its kind byte; omitted trailing fields reset
+// to their defaults, exactly as at init. State (inventory, snapshots, last
+// prices) is untouched.
+//
+// Only before the first fill: once the market is live an instant change is a
+// rug vector, and parameters move through ProposeParams/CommitParams instead.
+// =============================================================================
+fn process_update_params(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 1 + PARAMS_LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..2])?;
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    check_authority(authority, &ctx_data)?;
+
+    if MatcherContext::view(&ctx_data).last_oracle_price_e6.get() != 0 {
+        msg!("ERROR: Market is live; parameters change via ProposeParams/CommitParams");
+        return Err(MatcherError::MarketLive.into());
+    }
+
+    let new = Params::unpack(&data[1..]);
+    new.validate(MatcherContext::view(&ctx_data).kind, is_extended(&ctx_data))?;
+
+    let old = Params::load(&ctx_data);
+    old.log_changes(&new);
+    new.store(&mut ctx_data);
+
+    msg!("credibility-update-params: ok");
 
     Ok(())
 }
 
 // =============================================================================
-// Update Credibility Instruction (tag 0x03) — unchanged from v1
+// Update Credibility Instruction (tag 0x03)
+//
+// Permissionless: anyone may refresh the snapshot, so only the slab the
+// context is bound to (or lists, when shared) is read, under the percolator
+// program pinned with it; a look-alike account can't feed it numbers. A
+// context with no binding, v5 included, takes a slab PERCOLATOR_PROGRAM_ID owns.
+// A bound tip jar passed after the clock, with the keeper (signer) after it,
+// pays the keeper for the staleness the refresh cleared.
 // =============================================================================
 fn process_update_credibility(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     _data: &[u8],
 ) -> ProgramResult {
@@ -462,29 +978,31 @@ fn process_update_credibility(
     let ctx_account = &accounts[0];
     let slab_account = &accounts[1];
     let clock_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..accounts.len().min(5)])?;
+    let tip_accounts = match &accounts[3..] {
+        [] => None,
+        [tip_jar, keeper, ..] => Some((tip_jar, keeper)),
+        [_] => return Err(ProgramError::NotEnoughAccountKeys),
+    };
 
     let mut ctx_data = ctx_account.try_borrow_mut_data()?;
-    if ctx_data.len() < 320 {
+    if ctx_data.len() < CTX_LEN {
         return Err(ProgramError::AccountDataTooSmall);
     }
 
     let magic = u64::from_le_bytes(ctx_data[CTX_BASE..CTX_BASE + 8].try_into().unwrap());
     if magic != MAGIC {
         msg!("ERROR: Context not initialized");
-        return Err(ProgramError::UninitializedAccount);
+        return Err(MatcherError::NotInitialized.into());
     }
 
-    let slab_data = slab_account.try_borrow_data()?;
+    check_pinned_slab(slab_account, &ctx_data)?;
+    let book = book_for(&ctx_data, Some(slab_account.key))?;
 
-    const SLAB_HEADER_LEN: usize = 72;
-    const SLAB_CONFIG_LEN: usize = 320;
-    const SLAB_ENGINE_OFF: usize = SLAB_HEADER_LEN + SLAB_CONFIG_LEN;
-    const ENGINE_INSURANCE_OFF: usize = 16;
-    const ENGINE_TOTAL_OI_OFF: usize = 248;
-    const ENGINE_LAST_CRANK_OFF: usize = 232;
-    const ENGINE_LIFETIME_LIQS_OFF: usize = 328;
+    let slab_data = slab_account.try_borrow_data()?;
 
-    if slab_data.len() < SLAB_ENGINE_OFF + 400 {
+    if slab_data.len() < SLAB_MIN_LEN {
         msg!("ERROR: Slab too small");
         return Err(ProgramError::AccountDataTooSmall);
     }
@@ -495,106 +1013,4820 @@ fn process_update_credibility(
     let oi_off = SLAB_ENGINE_OFF + ENGINE_TOTAL_OI_OFF;
     let total_oi = u128::from_le_bytes(slab_data[oi_off..oi_off + 16].try_into().unwrap());
 
-    let admin_bytes: [u8; 32] = slab_data[16..48].try_into().unwrap();
+    let admin_bytes: [u8; 32] = slab_data[SLAB_ADMIN_OFF..SLAB_ADMIN_OFF + 32].try_into().unwrap();
     let admin_is_burned = admin_bytes == [0u8; 32]
         || Pubkey::new_from_array(admin_bytes) == solana_program::system_program::id();
 
-    let clock_data = clock_account.try_borrow_data()?;
-    let current_slot = if clock_data.len() >= 8 {
-        u64::from_le_bytes(clock_data[0..8].try_into().unwrap())
-    } else {
-        0
-    };
+    // The clock account stays in the list for compatibility, but it must be the
+    // real sysvar and the slot comes from the runtime: a forged slot would
+    // poison market age, liquidation heat decay and snapshot staleness.
+    if *clock_account.key != solana_program::sysvar::clock::id() {
+        msg!("ERROR: Expected the Clock sysvar, got {}", clock_account.key);
+        return Err(MatcherError::InvalidClock.into());
+    }
+    let current_slot = Clock::get()?.slot;
+
+    let crank_off = SLAB_ENGINE_OFF + ENGINE_LAST_CRANK_OFF;
+    let last_crank_slot = u64::from_le_bytes(slab_data[crank_off..crank_off + 8].try_into().unwrap());
 
-    let _crank_off = SLAB_ENGINE_OFF + ENGINE_LAST_CRANK_OFF;
-    let _liq_off = SLAB_ENGINE_OFF + ENGINE_LIFETIME_LIQS_OFF;
+    let liq_off = SLAB_ENGINE_OFF + ENGINE_LIFETIME_LIQS_OFF;
+    let lifetime_liqs = u64::from_le_bytes(slab_data[liq_off..liq_off + 8].try_into().unwrap());
 
-    let existing_age = read_u64(&ctx_data, CTX_BASE + CTX_MARKET_AGE_OFF);
-    let existing_snapshot_slot = read_u64(&ctx_data, CTX_BASE + CTX_SNAPSHOT_SLOT_OFF);
+    let funding_off = SLAB_ENGINE_OFF + ENGINE_FUNDING_RATE_OFF;
+    let funding_rate = i64::from_le_bytes(slab_data[funding_off..funding_off + 8].try_into().unwrap());
+
+    let existing_age = read_u64(&ctx_data, book.market_age);
+    let existing_snapshot_slot = read_u64(&ctx_data, book.snapshot_slot);
+
+    // A fund smaller than at the last snapshot has paid out for a deficit. The
+    // first snapshot has nothing to compare against.
+    let prev_insurance = read_u128(&ctx_data, book.insurance);
+    let drawn_down = existing_snapshot_slot > 0 && insurance_balance < prev_insurance;
+    let last_deficit_slot = if drawn_down {
+        current_slot
+    } else {
+        read_u64(&ctx_data, book.last_deficit_slot)
+    };
     let market_age = if existing_snapshot_slot > 0 && admin_is_burned {
         existing_age + current_slot.saturating_sub(existing_snapshot_slot)
-    } else if admin_is_burned {
-        0
     } else {
         0
     };
 
-    // Compute coverage for logging
-    let coverage_bps: u64 = if total_oi > 0 {
-        (((insurance_balance as u128) * (BPS as u128)) / (total_oi as u128)).min(u64::MAX as u128) as u64
+    // Liquidation heat: decay what we had, then add the liquidations since the
+    // last snapshot. The first snapshot only records the baseline — history
+    // from before the matcher existed is not a spike.
+    let liq_halflife = MatcherContext::view(&ctx_data).liq_halflife_slots.get() as u64;
+    let prev_liqs = read_u64(&ctx_data, book.last_liqs);
+    let liq_delta = if existing_snapshot_slot > 0 {
+        lifetime_liqs.saturating_sub(prev_liqs)
     } else {
         0
     };
-
-    let tier = if coverage_bps < TIER_CRITICAL_BPS {
-        "CRITICAL"
-    } else if coverage_bps < TIER_FRAGILE_BPS {
-        "FRAGILE"
-    } else if coverage_bps < TIER_NORMAL_BPS {
-        "NORMAL"
-    } else if coverage_bps < TIER_STRONG_BPS {
-        "STRONG"
+    let liq_heat = if liq_halflife > 0 {
+        let prev_heat = read_u32(&ctx_data, book.liq_heat) as u64;
+        let elapsed = current_slot.saturating_sub(existing_snapshot_slot);
+        decay_halflife(prev_heat, elapsed, liq_halflife)
+            .saturating_add(liq_delta.saturating_mul(LIQ_HEAT_SCALE))
+            .min(u32::MAX as u64)
     } else {
-        "FORTIFIED"
+        0
     };
 
-    write_u128(&mut ctx_data, CTX_BASE + CTX_INSURANCE_OFF, insurance_balance);
-    write_u128(&mut ctx_data, CTX_BASE + CTX_TOTAL_OI_OFF, total_oi);
-    write_u64(&mut ctx_data, CTX_BASE + CTX_MARKET_AGE_OFF, market_age);
-    write_u64(&mut ctx_data, CTX_BASE + CTX_SNAPSHOT_SLOT_OFF, current_slot);
+    // Coverage and tier for logging, as Match will price them
+    let coverage_bps = pricing::coverage_bps(insurance_balance, total_oi);
+    let tier = TIER_NAMES[pricing::coverage_tier(coverage_bps) as usize];
+
+    write_u128(&mut ctx_data, book.insurance, insurance_balance);
+    write_u128(&mut ctx_data, book.total_oi, total_oi);
+    write_u64(&mut ctx_data, book.market_age, market_age);
+    write_u64(&mut ctx_data, book.last_deficit_slot, last_deficit_slot);
+    write_u64(&mut ctx_data, book.snapshot_slot, current_slot);
+    write_u64(&mut ctx_data, book.last_crank_slot, last_crank_slot);
+    write_u64(&mut ctx_data, book.last_liqs, lifetime_liqs);
+    write_u32(&mut ctx_data, book.liq_heat, liq_heat as u32);
+    if let Some(off) = book.funding_rate {
+        write_i64(&mut ctx_data, off, funding_rate);
+    }
+    // New liquidations open the auction window, whether or not it is on
+    if let Some(off) = book.last_liq_slot.filter(|_| liq_delta > 0) {
+        write_u64(&mut ctx_data, off, current_slot);
+    }
 
     msg!(
-        "credibility-update-v2: insurance={} oi={} coverage={}bps tier={} age={} burned={}",
-        insurance_balance, total_oi, coverage_bps, tier, market_age, admin_is_burned
+        "credibility-update-v2: insurance={} oi={} coverage={}bps tier={} age={} burned={} crank_slot={} liqs=+{} heat={} funding={}bps/slot",
+        insurance_balance, total_oi, coverage_bps, tier, market_age, admin_is_burned, last_crank_slot, liq_delta, liq_heat, funding_rate
+    );
+    if drawn_down {
+        msg!("credibility-deficit: insurance {} -> {} at slot {}", prev_insurance, insurance_balance, current_slot);
+    }
+    if let Some((tip_jar, keeper)) = tip_accounts {
+        pay_keeper_tip(program_id, ctx_account.key, tip_jar, keeper, current_slot, existing_snapshot_slot)?;
+    }
+
+    Ok(())
+}
+
+/// Pay `keeper` the tip a bound tip jar owes for refreshing a snapshot taken
+/// at `snapshot_slot`, as far as its lamports above rent exemption go.
+fn pay_keeper_tip(
+    program_id: &Pubkey,
+    ctx_key: &Pubkey,
+    tip_jar: &AccountInfo,
+    keeper: &AccountInfo,
+    current_slot: u64,
+    snapshot_slot: u64,
+) -> ProgramResult {
+    check_bound_tip_jar(program_id, tip_jar, ctx_key)?;
+    if !keeper.is_signer {
+        msg!("ERROR: The keeper must sign for a tip");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut tip_data = tip_jar.try_borrow_mut_data()?;
+    let owed = pricing::keeper_tip(
+        read_u64(&tip_data, TIP_JAR_PER_SLOT_OFF),
+        read_u64(&tip_data, TIP_JAR_MAX_OFF),
+        current_slot,
+        snapshot_slot,
     );
+    let escrow = tip_jar.lamports().saturating_sub(Rent::get()?.minimum_balance(tip_data.len()));
+    let tip = owed.min(escrow);
+    if tip == 0 {
+        if owed > 0 {
+            msg!("credibility-keeper-tip: owed={} but the tip jar is empty", owed);
+        }
+        return Ok(());
+    }
+
+    let keeper_lamports = keeper.lamports().checked_add(tip).ok_or(ProgramError::ArithmeticOverflow)?;
+    **tip_jar.try_borrow_mut_lamports()? -= tip;
+    **keeper.try_borrow_mut_lamports()? = keeper_lamports;
+    let paid = read_u64(&tip_data, TIP_JAR_PAID_OFF).saturating_add(tip);
+    let tips = read_u64(&tip_data, TIP_JAR_COUNT_OFF).saturating_add(1);
+    write_u64(&mut tip_data, TIP_JAR_PAID_OFF, paid);
+    write_u64(&mut tip_data, TIP_JAR_COUNT_OFF, tips);
+
+    msg!("credibility-keeper-tip: keeper={} tip={} owed={} stale_since={}", keeper.key, tip, owed, snapshot_slot);
 
     Ok(())
 }
 
 // =============================================================================
-// Helpers
+// Close Instruction (tag 0x05)
+//
+// Zeroes the context (clearing magic, so it can never be mistaken for a live
+// matcher again) and sweeps its rent lamports to `destination`. A bound stats
+// account passed fourth is zeroed and swept along with it, as is a ladder
+// after that.
 // =============================================================================
+fn process_close(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
 
-fn read_u32(data: &[u8], off: usize) -> u32 {
-    u32::from_le_bytes(data[off..off + 4].try_into().unwrap())
-}
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    check_ctx_account(program_id, ctx_account)?;
+    let destination = &accounts[2];
+    // Sweeping the rent back to the authority itself is fine; only the
+    // accounts being closed have to stand alone
+    check_distinct(&accounts[..2])?;
+    check_distinct(&accounts[1..accounts.len().min(7)])?;
 
-fn read_u64(data: &[u8], off: usize) -> u64 {
-    u64::from_le_bytes(data[off..off + 8].try_into().unwrap())
-}
+    // A scheduled context's schedule comes next and then a context's feed
+    // account, closed along with it so they can't outlive the context;
+    // so are the bound stats account and a ladder, when passed after that.
+    // The ladder is told apart by its magic
+    let (schedule_account, feed_account, stats_account, ladder_account) = {
+        let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+        check_authority(authority, &ctx_data)?;
+        let (schedule_account, rest) = split_schedule(program_id, ctx_account.key, &accounts[3..], &ctx_data)?;
+        let (feed_account, rest) = split_feed(program_id, ctx_account.key, rest, &ctx_data)?;
+        let (stats_account, ladder_account) = match rest {
+            [first, ..] if is_ladder(first) => (None, Some(first)),
+            [first, rest @ ..] => (Some(first), rest.first()),
+            [] => (None, None),
+        };
+        if let Some(stats_account) = stats_account {
+            check_bound_stats(program_id, stats_account, &ctx_data)?;
+            stats_account.try_borrow_mut_data()?.fill(0);
+        }
+        if let Some(ladder_account) = ladder_account {
+            check_bound_ladder(program_id, ladder_account, ctx_account.key)?;
+            ladder_account.try_borrow_mut_data()?.fill(0);
+        }
+        for account in [schedule_account, feed_account].into_iter().flatten() {
+            account.try_borrow_mut_data()?.fill(0);
+        }
+        ctx_data.fill(0);
+        (schedule_account, feed_account, stats_account, ladder_account)
+    };
 
-fn read_u128(data: &[u8], off: usize) -> u128 {
-    u128::from_le_bytes(data[off..off + 16].try_into().unwrap())
-}
+    let mut reclaimed = ctx_account.lamports();
+    **ctx_account.try_borrow_mut_lamports()? = 0;
+    for account in [schedule_account, feed_account, stats_account, ladder_account].into_iter().flatten() {
+        reclaimed = reclaimed
+            .checked_add(account.lamports())
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        **account.try_borrow_mut_lamports()? = 0;
+    }
+    let dest_lamports = destination
+        .lamports()
+        .checked_add(reclaimed)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    **destination.try_borrow_mut_lamports()? = dest_lamports;
 
-fn read_i128(data: &[u8], off: usize) -> i128 {
-    i128::from_le_bytes(data[off..off + 16].try_into().unwrap())
-}
+    msg!("credibility-close: reclaimed={} lamports to {}", reclaimed, destination.key);
 
-fn write_u32(data: &mut [u8], off: usize, val: u32) {
-    data[off..off + 4].copy_from_slice(&val.to_le_bytes());
+    Ok(())
 }
 
-fn write_u64(data: &mut [u8], off: usize, val: u64) {
-    data[off..off + 8].copy_from_slice(&val.to_le_bytes());
-}
+// =============================================================================
+// Set Paused Instruction (tag 0x06)
+//
+// Emergency brake: while paused every Match is rejected with Paused.
+// UpdateCredibility keeps running so snapshots are fresh on unpause.
+// =============================================================================
+fn process_set_paused(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let paused = set_ctx_flag(program_id, accounts, data, CTX_FLAG_PAUSED)?;
+    msg!("credibility-set-paused: paused={}", paused);
 
-fn write_u128(data: &mut [u8], off: usize, val: u128) {
-    data[off..off + 16].copy_from_slice(&val.to_le_bytes());
+    Ok(())
 }
 
-fn write_i128(data: &mut [u8], off: usize, val: i128) {
-    data[off..off + 16].copy_from_slice(&val.to_le_bytes());
+// =============================================================================
+// Set Reduce-Only Instruction (tag 0x0B)
+//
+// Wind-down mode: only trades that shrink |inventory| are accepted; the rest
+// fail with ReduceOnly. The matcher also behaves this way on its own once
+// |inventory| reaches AUTO_REDUCE_ONLY_BPS of the cap (or the schedule's
+// reduce_only_bps), soft-rejecting what it refuses.
+// =============================================================================
+fn process_set_reduce_only(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let reduce_only = set_ctx_flag(program_id, accounts, data, CTX_FLAG_REDUCE_ONLY)?;
+    msg!("credibility-set-reduce-only: reduce_only={}", reduce_only);
+
+    Ok(())
 }
 
 // =============================================================================
-// Tests
+// Settle Inventory Instruction (tag 0x0C)
+//
+// Reconciles inventory_base with the LP's real position after it hedged or
+// withdrew outside the matcher. Data: [0x0C, mode, value (i128)] where mode 0
+// sets the inventory to `value` and mode 1 adds `value` to it. The delta is
+// always logged so the history can be audited from transaction logs. On a
+// shared context an optional u16 after the value picks the market's book.
 // =============================================================================
-#[cfg(test)]
-mod tests {
-    use super::*;
+fn process_settle_inventory(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 18 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
 
-    fn compute_tier(coverage_bps: u64) -> (&'static str, u128) {
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..2])?;
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    check_authority(authority, &ctx_data)?;
+
+    let mode = data[1];
+    let value = i128::from_le_bytes(data[2..18].try_into().unwrap());
+    let book = book_at(&ctx_data, read_opt_u16(data, 18))?;
+    let old = read_i128(&ctx_data, book.inventory);
+    let new = match mode {
+        SETTLE_SET => value,
+        SETTLE_ADJUST => old.checked_add(value).ok_or(ProgramError::ArithmeticOverflow)?,
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+    let delta = new.checked_sub(old).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    write_i128(&mut ctx_data, book.inventory, new);
+
+    msg!("credibility-settle-inventory: {} -> {} (delta {})", old, new, delta);
+
+    Ok(())
+}
+
+// =============================================================================
+// Propose Authority Instruction (tag 0x0D)
+//
+// First half of an authority rotation: the current authority names its
+// successor. Nothing changes until the successor accepts, so a typo can't
+// orphan the context. Proposing the zero key cancels. v6 only.
+// =============================================================================
+fn process_propose_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 33 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..2])?;
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    check_authority(authority, &ctx_data)?;
+    if !is_extended(&ctx_data) {
+        msg!("ERROR: Authority rotation needs a v6 context; Migrate first");
+        return Err(MatcherError::ExtensionRequired.into());
+    }
+
+    let proposed = read_pubkey(data, 1);
+    ContextExtension::view_mut(&mut ctx_data).pending_authority = proposed.to_bytes();
+
+    msg!("credibility-propose-authority: {} -> {}", authority.key, proposed);
+
+    Ok(())
+}
+
+// =============================================================================
+// Accept Authority Instruction (tag 0x0E)
+//
+// Second half: the proposed key signs to take over administration.
+// =============================================================================
+fn process_accept_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let new_authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..2])?;
+
+    if !new_authority.is_signer {
+        msg!("ERROR: New authority must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    if ctx_data.len() < CTX_LEN {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    if MatcherContext::view(&ctx_data).magic.get() != MAGIC {
+        msg!("ERROR: Context not initialized");
+        return Err(MatcherError::NotInitialized.into());
+    }
+    if !is_extended(&ctx_data) {
+        return Err(MatcherError::ExtensionRequired.into());
+    }
+    let pending = ContextExtension::view(&ctx_data).pending_authority();
+    if pending == Pubkey::default() || pending != *new_authority.key {
+        msg!("ERROR: {} is not the pending authority", new_authority.key);
+        return Err(MatcherError::NotPendingAuthority.into());
+    }
+
+    let old = current_authority(&ctx_data);
+    let ext = ContextExtension::view_mut(&mut ctx_data);
+    ext.authority = pending.to_bytes();
+    ext.pending_authority = [0; 32];
+
+    msg!("credibility-accept-authority: {} -> {}", old, pending);
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Return-Data-Only Instruction (tag 0x0F)
+//
+// Match always emits its MatcherReturn via sol_set_return_data. By default it
+// also writes the same 64 bytes to the front of the context account, as the
+// original ABI did; once every caller reads return data the authority can turn
+// that copy off and save the write.
+// =============================================================================
+fn process_set_return_data_only(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let return_data_only = set_ctx_flag(program_id, accounts, data, CTX_FLAG_RETURN_DATA_ONLY)?;
+    msg!("credibility-set-return-data-only: return_data_only={}", return_data_only);
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Soft-Reject Instruction (tag 0x10)
+//
+// With soft-reject on, a Match the LP's inventory can't take (reduce-only,
+// inventory limit, overflow) succeeds with exec_size = 0 and the MatcherError
+// code in reject_reason instead of failing the whole percolator transaction.
+// =============================================================================
+fn process_set_soft_reject(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let soft_reject = set_ctx_flag(program_id, accounts, data, CTX_FLAG_SOFT_REJECT)?;
+    msg!("credibility-set-soft-reject: soft_reject={}", soft_reject);
+
+    Ok(())
+}
+
+// =============================================================================
+// Set No-Bids / Set No-Asks Instructions (tags 0x16, 0x17)
+//
+// One-sided quoting: with bids off the LP buys nothing (a taker's sell), with
+// asks off it sells nothing (a taker's buy), e.g. quoting only the side that
+// winds inventory down. A Match on a side that is off succeeds with a zero
+// fill and SideDisabled in reject_reason, soft-reject mode or not.
+// =============================================================================
+fn process_set_no_bids(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let no_bids = set_ctx_flag(program_id, accounts, data, CTX_FLAG_NO_BIDS)?;
+    msg!("credibility-set-no-bids: no_bids={}", no_bids);
+
+    Ok(())
+}
+
+fn process_set_no_asks(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let no_asks = set_ctx_flag(program_id, accounts, data, CTX_FLAG_NO_ASKS)?;
+    msg!("credibility-set-no-asks: no_asks={}", no_asks);
+
+    Ok(())
+}
+
+// =============================================================================
+// Init Schedule Instruction (tag 0x18)
+//
+// Binds a spread schedule account to a context, v5 or v6: a table of slot
+// ranges the LP fills in with SetSchedule ahead of known events (oracle
+// maintenance, upgrades), each multiplying the spread while it runs. From here
+// on Match, QuotePreview and QuoteTwoSided need the schedule right after
+// their fixed accounts, so no trade can leave it out. The account is created
+// by the authority, owned by this program and SCHEDULE_LEN bytes; the context
+// keeps it until Close, which closes both.
+// =============================================================================
+fn process_init_schedule(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let schedule_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_ctx_owner(program_id, schedule_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    check_authority(authority, &ctx_data)?;
+    if MatcherContext::view(&ctx_data).has_flag(CTX_FLAG_SCHEDULED) {
+        msg!("ERROR: Context already has a schedule");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let mut schedule_data = schedule_account.try_borrow_mut_data()?;
+    if schedule_data.len() < SCHEDULE_LEN {
+        msg!("ERROR: Schedule account is {} bytes; needs {}", schedule_data.len(), SCHEDULE_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    let min_balance = Rent::get()?.minimum_balance(schedule_data.len());
+    if schedule_account.lamports() < min_balance {
+        msg!("ERROR: Schedule account holds {} lamports; rent exemption needs {}", schedule_account.lamports(), min_balance);
+        return Err(ProgramError::AccountNotRentExempt);
+    }
+    if read_u64(&schedule_data, SCHEDULE_MAGIC_OFF) == SCHEDULE_MAGIC {
+        msg!("ERROR: Schedule account already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    schedule_data.fill(0);
+    write_u64(&mut schedule_data, SCHEDULE_MAGIC_OFF, SCHEDULE_MAGIC);
+    write_u32(&mut schedule_data, SCHEDULE_VERSION_OFF, SCHEDULE_VERSION);
+    schedule_data[SCHEDULE_CTX_OFF..SCHEDULE_CTX_OFF + 32].copy_from_slice(ctx_account.key.as_ref());
+    MatcherContext::view_mut(&mut ctx_data).flags |= CTX_FLAG_SCHEDULED;
+
+    msg!("credibility-init-schedule: schedule={} entries={}", schedule_account.key, MAX_SCHEDULE_ENTRIES);
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Schedule Instruction (tag 0x19)
+//
+// Replaces the bound schedule's table: `[0x19, count (u8)]` then `count`
+// entries of start_slot (u64), end_slot (u64) and multiplier_bps (u32). A
+// range holds both its ends, and where ranges overlap the widest multiplier
+// applies. A multiplier only widens the spread, so a new table counts from
+// the next trade with no timelock; a count of 0 clears it.
+// =============================================================================
+fn process_set_schedule(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 2 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let count = data[1] as usize;
+    if count > MAX_SCHEDULE_ENTRIES {
+        msg!("ERROR: {} schedule entries, at most {}", count, MAX_SCHEDULE_ENTRIES);
+        return Err(MatcherError::InvalidSchedule.into());
+    }
+    if data.len() < 2 + count * SCHED_ARG_LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let schedule_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_schedule(program_id, schedule_account, ctx_account.key)?;
+
+    let mut entries = [0u8; MAX_SCHEDULE_ENTRIES * SCHED_ENTRY_LEN];
+    for (i, arg) in data[2..2 + count * SCHED_ARG_LEN].chunks_exact(SCHED_ARG_LEN).enumerate() {
+        let (start, end, mult_bps) = (read_u64(arg, 0), read_u64(arg, 8), read_u32(arg, 16));
+        if start > end || (mult_bps as u64) < BPS {
+            msg!("ERROR: Schedule entry {}: slots {}..={} at {}bps", i, start, end, mult_bps);
+            return Err(MatcherError::InvalidSchedule.into());
+        }
+        let entry = &mut entries[i * SCHED_ENTRY_LEN..(i + 1) * SCHED_ENTRY_LEN];
+        write_u64(entry, SCHED_START_OFF, start);
+        write_u64(entry, SCHED_END_OFF, end);
+        write_u32(entry, SCHED_MULT_OFF, mult_bps);
+        msg!("credibility-schedule: slots {}..={} x{}bps", start, end, mult_bps);
+    }
+
+    let mut schedule_data = schedule_account.try_borrow_mut_data()?;
+    write_u32(&mut schedule_data, SCHEDULE_COUNT_OFF, count as u32);
+    schedule_data[SCHEDULE_HEADER_LEN..SCHEDULE_LEN].copy_from_slice(&entries);
+
+    msg!("credibility-set-schedule: entries={}", count);
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Fee Tiers Instruction (tag 0x22)
+//
+// Replaces the fee tiers of a schedule account created with room for them
+// (SCHEDULE_FEES_LEN bytes): `[0x22, count (u8)]` then `count` tiers of
+// min_notional (u128) and fee_bps (u32), minimums positive and rising. A
+// trade whose notional at the oracle price reaches a tier pays the largest
+// such tier's fee in place of base_fee_bps, so large trades can be charged
+// more, or less, than small ones. A count of 0 clears them. Fees bypass the
+// parameter timelock here, so like SetOracleDecimals it only works before
+// the first fill.
+// =============================================================================
+fn process_set_fee_tiers(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 2 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let count = data[1] as usize;
+    if count > MAX_FEE_TIERS {
+        msg!("ERROR: {} fee tiers, at most {}", count, MAX_FEE_TIERS);
+        return Err(MatcherError::InvalidFeeTiers.into());
+    }
+    if data.len() < 2 + count * FEE_TIER_ARG_LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let schedule_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_schedule(program_id, schedule_account, ctx_account.key)?;
+    if MatcherContext::view(&ctx_data).last_oracle_price_e6.get() != 0 {
+        msg!("ERROR: Market is live; fee tiers are fixed");
+        return Err(MatcherError::MarketLive.into());
+    }
+
+    let mut tiers = [0u8; MAX_FEE_TIERS * FEE_TIER_LEN];
+    let mut floor = 0;
+    for (i, arg) in data[2..2 + count * FEE_TIER_ARG_LEN].chunks_exact(FEE_TIER_ARG_LEN).enumerate() {
+        let (min_notional, fee_bps) = (read_u128(arg, 0), read_u32(arg, 16));
+        if min_notional <= floor || fee_bps as u64 > BPS {
+            msg!("ERROR: Fee tier {}: {}bps from {}", i, fee_bps, min_notional);
+            return Err(MatcherError::InvalidFeeTiers.into());
+        }
+        floor = min_notional;
+        let tier = &mut tiers[i * FEE_TIER_LEN..(i + 1) * FEE_TIER_LEN];
+        write_u128(tier, FEE_TIER_NOTIONAL_OFF, min_notional);
+        write_u32(tier, FEE_TIER_FEE_OFF, fee_bps);
+        msg!("credibility-fee-tier: from {} at {}bps", min_notional, fee_bps);
+    }
+
+    let mut schedule_data = schedule_account.try_borrow_mut_data()?;
+    let Some(table) = schedule_data.get_mut(FEE_TIERS_OFF..SCHEDULE_FEES_LEN) else {
+        msg!("ERROR: Schedule account has no room for fee tiers; create it with {} bytes", SCHEDULE_FEES_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    };
+    table.copy_from_slice(&tiers);
+
+    msg!("credibility-set-fee-tiers: tiers={}", count);
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Rebate Instruction (tag 0x23)
+//
+// `[0x23, rebate_bps (u16)]` on a schedule account created with room for it
+// (SCHEDULE_REBATE_LEN bytes). A fill that leaves |inventory| smaller gets
+// rebate_bps off its spread and fee, so takers are paid to take the side the
+// LP wants and the book turns over without manual hedging. The rebate stops
+// where the price would cross the oracle. It only ever improves a taker's
+// price, so like SetSchedule it needs no timelock; 0 turns it off.
+// =============================================================================
+fn process_set_rebate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 3 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let rebate_bps = read_u16(data, 1);
+    if rebate_bps as u64 > BPS {
+        msg!("ERROR: rebate {} > 10000 bps", rebate_bps);
+        return Err(MatcherError::RebateTooHigh.into());
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let schedule_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_schedule(program_id, schedule_account, ctx_account.key)?;
+
+    let mut schedule_data = schedule_account.try_borrow_mut_data()?;
+    if schedule_data.len() < SCHEDULE_REBATE_LEN {
+        msg!("ERROR: Schedule account has no room for a rebate; create it with {} bytes", SCHEDULE_REBATE_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    write_u16(&mut schedule_data, SCHEDULE_REBATE_OFF, rebate_bps);
+
+    msg!("credibility-set-rebate: rebate={}bps", rebate_bps);
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Min Notional Instruction (tag 0x2B)
+//
+// `[0x2B, min_notional_e6 (u128)]` on a schedule account created with room
+// for it (SCHEDULE_MIN_NOTIONAL_LEN bytes). A trade whose notional at the
+// oracle, figured as for the fee tiers, falls below it is refused with
+// BelowMinNotional (soft-rejected in soft-reject mode) before it is priced:
+// dust costs a full Match and moves the book for no fee worth having. Like
+// pausing it takes effect at once; 0 turns it off.
+// =============================================================================
+fn process_set_min_notional(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 17 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let min_notional_e6 = read_u128(data, 1);
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let schedule_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_schedule(program_id, schedule_account, ctx_account.key)?;
+
+    let mut schedule_data = schedule_account.try_borrow_mut_data()?;
+    if schedule_data.len() < SCHEDULE_MIN_NOTIONAL_LEN {
+        msg!("ERROR: Schedule account has no room for a minimum notional; create it with {} bytes", SCHEDULE_MIN_NOTIONAL_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    write_u128(&mut schedule_data, SCHEDULE_MIN_NOTIONAL_OFF, min_notional_e6);
+
+    msg!("credibility-set-min-notional: min_notional={}", min_notional_e6);
+
+    Ok(())
+}
+
+// =============================================================================
+// Record Withdrawal Instruction (tag 0x24)
+//
+// `[0x24, amount (u128)]`. The matcher never holds the LP's fees, so it can't
+// pay them out; this records that the LP took `amount` out of the fees the
+// context accrued (cum_fees), in the fee ledger of a stats account created
+// with STATS_LEDGER_LEN bytes, so fee revenue reconciles against vault flows.
+// The ledger never records more than was accrued, less the treasury's cut on
+// a stats account with a protocol fee.
+// =============================================================================
+fn process_record_withdrawal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 17 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let amount = read_u128(data, 1);
+    if amount == 0 {
+        msg!("ERROR: Nothing to record");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let stats_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_stats(program_id, stats_account, &ctx_data)?;
+
+    let mut stats_data = stats_account.try_borrow_mut_data()?;
+    if stats_data.len() < STATS_LEDGER_LEN {
+        msg!("ERROR: Stats account has no fee ledger; create it with {} bytes", STATS_LEDGER_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    let mut accrued = ContextExtension::view(&ctx_data).cum_fees.get();
+    if stats_data.len() >= STATS_PROTOCOL_LEN {
+        accrued = accrued.saturating_sub(read_u128(&stats_data, STATS_PROTOCOL_ACCRUED_OFF));
+    }
+    let withdrawn = read_u128(&stats_data, STATS_WITHDRAWN_OFF);
+    let Some(total) = withdrawn.checked_add(amount).filter(|total| *total <= accrued) else {
+        msg!("ERROR: Withdrawal of {} with {} of {} already withdrawn", amount, withdrawn, accrued);
+        return Err(MatcherError::WithdrawalExceedsFees.into());
+    };
+    let withdrawals = read_u64(&stats_data, STATS_WITHDRAWALS_OFF).saturating_add(1);
+    let slot = Clock::get()?.slot;
+    write_u128(&mut stats_data, STATS_WITHDRAWN_OFF, total);
+    write_u64(&mut stats_data, STATS_WITHDRAWALS_OFF, withdrawals);
+    write_u64(&mut stats_data, STATS_LAST_WITHDRAWAL_OFF, slot);
+
+    msg!(
+        "credibility-fee-withdrawal: amount={} withdrawn={} accrued={} available={} n={}",
+        amount, total, accrued, accrued - total, withdrawals
+    );
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Skim Instruction (tag 0x2C)
+//
+// `[0x2C, skim_bps (u16)]` on a stats account created with room for the skim
+// (STATS_SKIM_LEN bytes). Every Match that passes it then earmarks skim_bps
+// of the fill's spread, on its notional, for the insurance fund, accrued in
+// the account until ClaimSkim pays it in, so trading itself builds the
+// coverage the credibility tiers price off. 0 stops accruing; what accrued
+// stays claimable.
+// =============================================================================
+fn process_set_skim(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 3 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let skim_bps = read_u16(data, 1);
+    if skim_bps as u64 > BPS {
+        msg!("ERROR: skim_bps {} above {}", skim_bps, BPS);
+        return Err(MatcherError::SkimTooHigh.into());
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let stats_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_stats(program_id, stats_account, &ctx_data)?;
+
+    let mut stats_data = stats_account.try_borrow_mut_data()?;
+    if stats_data.len() < STATS_SKIM_LEN {
+        msg!("ERROR: Stats account has no room for a skim; create it with {} bytes", STATS_SKIM_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    write_u16(&mut stats_data, STATS_SKIM_BPS_OFF, skim_bps);
+
+    msg!("credibility-set-skim: skim={}bps", skim_bps);
+
+    Ok(())
+}
+
+// =============================================================================
+// Claim Skim Instruction (tag 0x2D)
+//
+// `[0x2D, amount (u64)]`. Pays `amount` of the skim accrued in the stats
+// account into the insurance fund of the slab the context is bound to, by
+// invoking percolator's TopUpInsurance with the authority as the user: the
+// tokens come out of the authority's `source` account into the slab's vault.
+// Accounts: authority (signer), ctx, stats (writable), slab (writable),
+// source (writable), vault (writable), token program, percolator program.
+// Never more than was accrued and not yet claimed.
+// =============================================================================
+fn process_claim_skim(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 8 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 9 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let amount = read_u64(data, 1);
+    if amount == 0 {
+        msg!("ERROR: Nothing to claim");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let stats_account = &accounts[2];
+    let slab = &accounts[3];
+    let source = &accounts[4];
+    let vault = &accounts[5];
+    let token_program = &accounts[6];
+    let percolator_program = &accounts[7];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..6])?;
+
+    let (claimed, claims) = {
+        let ctx_data = ctx_account.try_borrow_data()?;
+        check_authority(authority, &ctx_data)?;
+        check_bound_stats(program_id, stats_account, &ctx_data)?;
+        // Only the slab the context was bound to at init, and only through
+        // the percolator program that owns it
+        if ContextExtension::view(&ctx_data).slab() == Pubkey::default() {
+            msg!("ERROR: Context is not bound to a slab");
+            return Err(MatcherError::SlabMismatch.into());
+        }
+        check_bound_slab(slab, &ctx_data)?;
+        if ContextExtension::view(&ctx_data).percolator_program() != *percolator_program.key {
+            msg!("ERROR: Percolator program {} does not own the slab", percolator_program.key);
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let stats_data = stats_account.try_borrow_data()?;
+        if stats_data.len() < STATS_SKIM_LEN {
+            msg!("ERROR: Stats account has no skim; create it with {} bytes", STATS_SKIM_LEN);
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        let accrued = read_u128(&stats_data, STATS_SKIM_ACCRUED_OFF);
+        let claimed = read_u128(&stats_data, STATS_SKIM_CLAIMED_OFF);
+        let Some(total) = claimed.checked_add(amount as u128).filter(|total| *total <= accrued) else {
+            msg!("ERROR: Claim of {} with {} of {} already claimed", amount, claimed, accrued);
+            return Err(MatcherError::SkimExceedsAccrued.into());
+        };
+        (total, read_u64(&stats_data, STATS_SKIM_CLAIMS_OFF).saturating_add(1))
+    };
+
+    let mut ix_data = [0u8; 9];
+    ix_data[0] = PERCOLATOR_TOP_UP_INSURANCE;
+    ix_data[1..].copy_from_slice(&amount.to_le_bytes());
+    invoke(
+        &Instruction {
+            program_id: *percolator_program.key,
+            accounts: vec![
+                AccountMeta::new_readonly(*authority.key, true),
+                AccountMeta::new(*slab.key, false),
+                AccountMeta::new(*source.key, false),
+                AccountMeta::new(*vault.key, false),
+                AccountMeta::new_readonly(*token_program.key, false),
+            ],
+            data: ix_data.to_vec(),
+        },
+        &[authority.clone(), slab.clone(), source.clone(), vault.clone(), token_program.clone(), percolator_program.clone()],
+    )?;
+
+    let mut stats_data = stats_account.try_borrow_mut_data()?;
+    write_u128(&mut stats_data, STATS_SKIM_CLAIMED_OFF, claimed);
+    write_u64(&mut stats_data, STATS_SKIM_CLAIMS_OFF, claims);
+
+    msg!(
+        "credibility-claim-skim: amount={} claimed={} accrued={} n={}",
+        amount, claimed, read_u128(&stats_data, STATS_SKIM_ACCRUED_OFF), claims
+    );
+
+    Ok(())
+}
+
+// =============================================================================
+// Init Trader Instruction (tag 0x2E)
+//
+// `[0x2E]`. A trader binds a companion trader account to the context and to
+// their own key; Match then records the notional of every fill it is passed
+// with, and the schedule's loyalty tiers discount the trader's fee by that
+// volume, rewarding repeat flow. The trader creates the account, owned by
+// this program and TRADER_LEN bytes, and signs; the authority has no say.
+// =============================================================================
+fn process_init_trader(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let trader = &accounts[0];
+    let ctx_account = &accounts[1];
+    let trader_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_ctx_owner(program_id, trader_account)?;
+    check_distinct(&accounts[..3])?;
+    if !trader.is_signer {
+        msg!("ERROR: Trader must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    if ctx_data.len() < CTX_LEN || MatcherContext::view(&ctx_data).magic.get() != MAGIC {
+        msg!("ERROR: Context not initialized");
+        return Err(MatcherError::NotInitialized.into());
+    }
+
+    let mut trader_data = trader_account.try_borrow_mut_data()?;
+    if trader_data.len() < TRADER_LEN {
+        msg!("ERROR: Trader account is {} bytes; needs {}", trader_data.len(), TRADER_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    let min_balance = Rent::get()?.minimum_balance(trader_data.len());
+    if trader_account.lamports() < min_balance {
+        msg!("ERROR: Trader account holds {} lamports; rent exemption needs {}", trader_account.lamports(), min_balance);
+        return Err(ProgramError::AccountNotRentExempt);
+    }
+    if read_u64(&trader_data, TRADER_MAGIC_OFF) == TRADER_MAGIC {
+        msg!("ERROR: Trader account already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    trader_data.fill(0);
+    write_u64(&mut trader_data, TRADER_MAGIC_OFF, TRADER_MAGIC);
+    write_u32(&mut trader_data, TRADER_VERSION_OFF, TRADER_VERSION);
+    trader_data[TRADER_CTX_OFF..TRADER_CTX_OFF + 32].copy_from_slice(ctx_account.key.as_ref());
+    trader_data[TRADER_KEY_OFF..TRADER_KEY_OFF + 32].copy_from_slice(trader.key.as_ref());
+
+    msg!("credibility-init-trader: trader={} account={}", trader.key, trader_account.key);
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Loyalty Tiers Instruction (tag 0x2F)
+//
+// Replaces the loyalty tiers of a schedule account created with room for them
+// (SCHEDULE_LOYALTY_LEN bytes): `[0x2F, count (u8)]` then `count` tiers of
+// min_volume (u128) and discount_bps (u32), minimums positive and rising. A
+// trader whose account has traded at least a tier's volume with the context
+// gets the largest such tier's discount_bps off the fee, after the fee tiers
+// and before the credibility discount. A count of 0 clears them. A discount
+// only ever lowers what takers pay, so like SetRebate it takes effect at
+// once.
+// =============================================================================
+fn process_set_loyalty_tiers(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 2 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let count = data[1] as usize;
+    if count > MAX_LOYALTY_TIERS {
+        msg!("ERROR: {} loyalty tiers, at most {}", count, MAX_LOYALTY_TIERS);
+        return Err(MatcherError::InvalidLoyaltyTiers.into());
+    }
+    if data.len() < 2 + count * LOYALTY_TIER_ARG_LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let schedule_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_schedule(program_id, schedule_account, ctx_account.key)?;
+
+    let mut tiers = [0u8; MAX_LOYALTY_TIERS * LOYALTY_TIER_LEN];
+    let mut floor = 0;
+    for (i, arg) in data[2..2 + count * LOYALTY_TIER_ARG_LEN].chunks_exact(LOYALTY_TIER_ARG_LEN).enumerate() {
+        let (min_volume, discount_bps) = (read_u128(arg, 0), read_u32(arg, 16));
+        if min_volume <= floor || discount_bps as u64 > BPS {
+            msg!("ERROR: Loyalty tier {}: {}bps off from {}", i, discount_bps, min_volume);
+            return Err(MatcherError::InvalidLoyaltyTiers.into());
+        }
+        floor = min_volume;
+        let tier = &mut tiers[i * LOYALTY_TIER_LEN..(i + 1) * LOYALTY_TIER_LEN];
+        write_u128(tier, LOYALTY_TIER_VOLUME_OFF, min_volume);
+        write_u32(tier, LOYALTY_TIER_DISCOUNT_OFF, discount_bps);
+        msg!("credibility-loyalty-tier: from {} at {}bps off", min_volume, discount_bps);
+    }
+
+    let mut schedule_data = schedule_account.try_borrow_mut_data()?;
+    let Some(table) = schedule_data.get_mut(LOYALTY_TIERS_OFF..SCHEDULE_LOYALTY_LEN) else {
+        msg!("ERROR: Schedule account has no room for loyalty tiers; create it with {} bytes", SCHEDULE_LOYALTY_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    };
+    table.copy_from_slice(&tiers);
+
+    msg!("credibility-set-loyalty-tiers: tiers={}", count);
+
+    Ok(())
+}
+
+// =============================================================================
+// Sweep Protocol Fee Instruction (tag 0x30)
+//
+// `[0x30, amount (u128)]`, signed by the treasury InitStats named. Like
+// RecordWithdrawal for the LP, this records that the treasury took `amount`
+// of the protocol fee accrued in the stats account; the fees themselves sit
+// with percolator, never here. Accounts: treasury (signer), ctx, stats
+// (writable). The stats account needn't still be bound, so a rebind leaves
+// what it accrued sweepable. Never more than was accrued and not yet swept.
+// =============================================================================
+fn process_sweep_protocol_fee(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 17 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let amount = read_u128(data, 1);
+    if amount == 0 {
+        msg!("ERROR: Nothing to sweep");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let treasury = &accounts[0];
+    let ctx_account = &accounts[1];
+    let stats_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+    if !treasury.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut stats_data = stats_account.try_borrow_mut_data()?;
+    if stats_account.owner != program_id
+        || stats_data.len() < STATS_LEN
+        || read_u64(&stats_data, STATS_MAGIC_OFF) != STATS_MAGIC
+        || read_pubkey(&stats_data, STATS_CTX_OFF) != *ctx_account.key
+    {
+        msg!("ERROR: Stats account mismatch");
+        return Err(MatcherError::StatsMismatch.into());
+    }
+    if stats_data.len() < STATS_PROTOCOL_LEN {
+        msg!("ERROR: Stats account has no protocol fee; create it with {} bytes", STATS_PROTOCOL_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    let bound = read_pubkey(&stats_data, STATS_TREASURY_OFF);
+    if bound == Pubkey::default() || bound != *treasury.key {
+        msg!("ERROR: Treasury is {}", bound);
+        return Err(MatcherError::TreasuryMismatch.into());
+    }
+    let accrued = read_u128(&stats_data, STATS_PROTOCOL_ACCRUED_OFF);
+    let swept = read_u128(&stats_data, STATS_PROTOCOL_SWEPT_OFF);
+    let Some(total) = swept.checked_add(amount).filter(|total| *total <= accrued) else {
+        msg!("ERROR: Sweep of {} with {} of {} already swept", amount, swept, accrued);
+        return Err(MatcherError::SweepExceedsAccrued.into());
+    };
+    let sweeps = read_u64(&stats_data, STATS_PROTOCOL_SWEEPS_OFF).saturating_add(1);
+    write_u128(&mut stats_data, STATS_PROTOCOL_SWEPT_OFF, total);
+    write_u64(&mut stats_data, STATS_PROTOCOL_SWEEPS_OFF, sweeps);
+
+    msg!(
+        "credibility-protocol-sweep: amount={} swept={} accrued={} available={} n={}",
+        amount, total, accrued, accrued - total, sweeps
+    );
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Inventory Decay Instruction (tag 0x31)
+//
+// `[0x31, halflife_slots (u32)]` on a schedule account created with room for
+// it (SCHEDULE_DECAY_LEN bytes). The LP hedges its inventory elsewhere over
+// time, so the imbalance and skew penalties price it as halving every
+// halflife_slots since the last fill, worked out lazily at each quote; the
+// book, its caps and the rebate keep the full inventory. 0 turns it off.
+// Like the fee tiers it reprices without the timelock, so it only works
+// before the first fill.
+// =============================================================================
+fn process_set_inventory_decay(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 5 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let halflife_slots = read_u32(data, 1);
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let schedule_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_schedule(program_id, schedule_account, ctx_account.key)?;
+    if MatcherContext::view(&ctx_data).last_oracle_price_e6.get() != 0 {
+        msg!("ERROR: Market is live; the inventory decay is fixed");
+        return Err(MatcherError::MarketLive.into());
+    }
+
+    let mut schedule_data = schedule_account.try_borrow_mut_data()?;
+    if schedule_data.len() < SCHEDULE_DECAY_LEN {
+        msg!("ERROR: Schedule account has no room for a decay; create it with {} bytes", SCHEDULE_DECAY_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    write_u32(&mut schedule_data, SCHEDULE_INVENTORY_HALFLIFE_OFF, halflife_slots);
+
+    msg!("credibility-set-inventory-decay: halflife={} slots", halflife_slots);
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Inventory Band Instruction (tag 0x32)
+//
+// `[0x32, soft_inventory (u128), band_k_bps (u32)]` on a schedule account
+// created with room for it (SCHEDULE_BAND_LEN bytes). max_inventory stays the
+// hard limit that rejects; past soft_inventory, a fill that takes |inventory|
+// deeper pays band_k_bps times the square of how far into the band it gets,
+// so the quote widens sharply on the way to the limit instead of going from
+// normal to refused in one fill. A soft_inventory of 0 turns it off. Like the
+// fee tiers it reprices without the timelock, so it only works before the
+// first fill.
+// =============================================================================
+fn process_set_inventory_band(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 21 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (soft_inventory, band_k_bps) = (read_u128(data, 1), read_u32(data, 17));
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let schedule_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_schedule(program_id, schedule_account, ctx_account.key)?;
+    let max_inventory = Params::load(&ctx_data).max_inventory;
+    if band_k_bps as u64 > BPS || (max_inventory != 0 && soft_inventory >= max_inventory) {
+        msg!("ERROR: Band from {} at {}bps, max_inventory {}", soft_inventory, band_k_bps, max_inventory);
+        return Err(MatcherError::InvalidInventoryBand.into());
+    }
+    if MatcherContext::view(&ctx_data).last_oracle_price_e6.get() != 0 {
+        msg!("ERROR: Market is live; the inventory band is fixed");
+        return Err(MatcherError::MarketLive.into());
+    }
+
+    let mut schedule_data = schedule_account.try_borrow_mut_data()?;
+    if schedule_data.len() < SCHEDULE_BAND_LEN {
+        msg!("ERROR: Schedule account has no room for a band; create it with {} bytes", SCHEDULE_BAND_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    write_u128(&mut schedule_data, SCHEDULE_SOFT_INVENTORY_OFF, soft_inventory);
+    write_u32(&mut schedule_data, SCHEDULE_BAND_K_OFF, band_k_bps);
+
+    msg!("credibility-set-inventory-band: soft={} k={}bps", soft_inventory, band_k_bps);
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Inventory Caps Instruction (tag 0x33)
+//
+// `[0x33, max_long_inventory (u128), max_short_inventory (u128)]` on a
+// schedule account created with room for them (SCHEDULE_CAPS_LEN bytes). An
+// LP that can warehouse one direction more cheaply than the other caps each
+// side on its own: the long cap limits a negative inventory (takers sold to
+// the LP), the short cap a positive one; a zero cap leaves that side at max_inventory (the
+// market's, on a shared context). Like SetMarket's caps they only refuse
+// fills, so they apply at once, live market or not.
+// =============================================================================
+fn process_set_inventory_caps(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 33 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (max_long, max_short) = (read_u128(data, 1), read_u128(data, 17));
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let schedule_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_schedule(program_id, schedule_account, ctx_account.key)?;
+
+    let mut schedule_data = schedule_account.try_borrow_mut_data()?;
+    if schedule_data.len() < SCHEDULE_CAPS_LEN {
+        msg!("ERROR: Schedule account has no room for caps; create it with {} bytes", SCHEDULE_CAPS_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    write_u128(&mut schedule_data, SCHEDULE_MAX_LONG_OFF, max_long);
+    write_u128(&mut schedule_data, SCHEDULE_MAX_SHORT_OFF, max_short);
+
+    msg!("credibility-set-inventory-caps: long={} short={}", max_long, max_short);
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Reduce-Only Threshold Instruction (tag 0x34)
+//
+// `[0x34, reduce_only_bps (u16)]` on a schedule account created with room for
+// it (SCHEDULE_REDUCE_ONLY_LEN bytes): the share of the side's cap past which
+// the book goes reduce-only on its own, in place of AUTO_REDUCE_ONLY_BPS (0
+// restores it). Past it, trades that grow |inventory| get a zero fill with
+// ReduceOnly as the reason, soft-reject mode or not, and the rest fill as
+// usual. It only refuses fills, so it applies at once.
+// =============================================================================
+fn process_set_reduce_only_threshold(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 3 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let reduce_only_bps = read_u16(data, 1);
+    if reduce_only_bps as u64 > BPS {
+        msg!("ERROR: reduce-only threshold {} > 10000 bps", reduce_only_bps);
+        return Err(MatcherError::ReduceOnlyThresholdTooHigh.into());
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let schedule_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_schedule(program_id, schedule_account, ctx_account.key)?;
+
+    let mut schedule_data = schedule_account.try_borrow_mut_data()?;
+    if schedule_data.len() < SCHEDULE_REDUCE_ONLY_LEN {
+        msg!("ERROR: Schedule account has no room for a threshold; create it with {} bytes", SCHEDULE_REDUCE_ONLY_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    write_u16(&mut schedule_data, SCHEDULE_REDUCE_ONLY_BPS_OFF, reduce_only_bps);
+
+    msg!("credibility-set-reduce-only-threshold: {}bps", reduce_only_bps);
+
+    Ok(())
+}
+
+// =============================================================================
+// Transfer Inventory Instruction (tag 0x35)
+//
+// Moves inventory from one context's book to another's in one step, so an
+// operator running several matchers on a market can rebalance risk between
+// them without trading through it. Data: [0x35, amount (i128)] with optional
+// u16 market indices for the source and destination books after it, as in
+// SettleInventory. The source's inventory drops by `amount` and the
+// destination's grows by it. Both authorities sign, both books must quote the
+// same slab, and neither may end up past its max_inventory.
+// =============================================================================
+fn process_transfer_inventory(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 4 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 17 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let from_authority = &accounts[0];
+    let from_ctx = &accounts[1];
+    let to_authority = &accounts[2];
+    let to_ctx = &accounts[3];
+    check_ctx_account(program_id, from_ctx)?;
+    check_ctx_account(program_id, to_ctx)?;
+    if from_ctx.key == to_ctx.key {
+        msg!("ERROR: Account {} passed more than once", from_ctx.key);
+        return Err(MatcherError::DuplicateAccount.into());
+    }
+
+    let mut from_data = from_ctx.try_borrow_mut_data()?;
+    let mut to_data = to_ctx.try_borrow_mut_data()?;
+    check_authority(from_authority, &from_data)?;
+    check_authority(to_authority, &to_data)?;
+
+    let amount = read_i128(data, 1);
+    let (from_market, to_market) = (read_opt_u16(data, 17), read_opt_u16(data, 19));
+    let from_book = book_at(&from_data, from_market)?;
+    let to_book = book_at(&to_data, to_market)?;
+    // Inventory only means the same thing on the same market
+    let from_slab = book_slab(&from_data, from_market);
+    if from_slab == Pubkey::default() || from_slab != book_slab(&to_data, to_market) {
+        msg!("ERROR: Books quote different slabs");
+        return Err(MatcherError::SlabMismatch.into());
+    }
+
+    let from_old = read_i128(&from_data, from_book.inventory);
+    let to_old = read_i128(&to_data, to_book.inventory);
+    let from_new = from_old.checked_sub(amount).ok_or(ProgramError::ArithmeticOverflow)?;
+    let to_new = to_old.checked_add(amount).ok_or(ProgramError::ArithmeticOverflow)?;
+    for (ctx_data, book, new) in [(&from_data, &from_book, from_new), (&to_data, &to_book, to_new)] {
+        let max_inventory = book_max_inventory(ctx_data, book);
+        if max_inventory > 0 && new.unsigned_abs() > max_inventory {
+            msg!("ERROR: Would leave a book at {} past its limit {}", new, max_inventory);
+            return Err(MatcherError::InventoryLimit.into());
+        }
+    }
+
+    write_i128(&mut from_data, from_book.inventory, from_new);
+    write_i128(&mut to_data, to_book.inventory, to_new);
+
+    msg!(
+        "credibility-transfer-inventory: {} from {} ({} -> {}) to {} ({} -> {})",
+        amount,
+        from_ctx.key,
+        from_old,
+        from_new,
+        to_ctx.key,
+        to_old,
+        to_new
+    );
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Inventory Depth Cap Instruction (tag 0x36)
+//
+// `[0x36, max_inventory_bps (u32)]` on a schedule account created with room
+// for it (SCHEDULE_DEPTH_CAP_LEN bytes): max_inventory_abs as a share of
+// liquidity_notional_e6, converted to base units at each trade's oracle, so
+// raising or lowering the depth moves the limit with it. Above 10000 allows
+// more than the depth; 0 goes back to max_inventory_abs as set. A market's
+// own max_inventory still wins on a shared context. Like SetMarket's caps it
+// applies at once.
+// =============================================================================
+fn process_set_inventory_depth_cap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 5 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let max_inventory_bps = read_u32(data, 1);
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let schedule_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_schedule(program_id, schedule_account, ctx_account.key)?;
+
+    let mut schedule_data = schedule_account.try_borrow_mut_data()?;
+    if schedule_data.len() < SCHEDULE_DEPTH_CAP_LEN {
+        msg!("ERROR: Schedule account has no room for a depth cap; create it with {} bytes", SCHEDULE_DEPTH_CAP_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    write_u32(&mut schedule_data, SCHEDULE_MAX_INVENTORY_BPS_OFF, max_inventory_bps);
+
+    msg!("credibility-set-inventory-depth-cap: {}bps of depth", max_inventory_bps);
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Signals Instruction (tag 0x3A)
+//
+// `[0x3A]`, with up to MAX_SIGNAL_ACCOUNTS signal accounts after the schedule,
+// on a schedule account created with room for them (SCHEDULE_SIGNALS_LEN
+// bytes): registers them, and the programs that own them now, as the signals
+// Match and the quotes price off. From then on every one of those has to be
+// passed them all, in this order, so a taker can't leave out the signal that
+// would widen its price, nor pass one of its own. None clears the list.
+// =============================================================================
+fn process_set_signals(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let schedule_account = &accounts[2];
+    let signal_accounts = &accounts[3..];
+    if signal_accounts.len() > MAX_SIGNAL_ACCOUNTS {
+        msg!("ERROR: {} signal accounts, at most {}", signal_accounts.len(), MAX_SIGNAL_ACCOUNTS);
+        return Err(MatcherError::InvalidSignal.into());
+    }
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(accounts)?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_schedule(program_id, schedule_account, ctx_account.key)?;
+    for account in signal_accounts {
+        read_signal(account)?;
+    }
+
+    let mut schedule_data = schedule_account.try_borrow_mut_data()?;
+    if schedule_data.len() < SCHEDULE_SIGNALS_LEN {
+        msg!("ERROR: Schedule account has no room for signals; create it with {} bytes", SCHEDULE_SIGNALS_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    schedule_data[SCHEDULE_SIGNAL_COUNT_OFF..SCHEDULE_SIGNALS_LEN].fill(0);
+    schedule_data[SCHEDULE_SIGNAL_COUNT_OFF] = signal_accounts.len() as u8;
+    let table = &mut schedule_data[SCHEDULE_SIGNALS_OFF..SCHEDULE_SIGNALS_LEN];
+    for (account, entry) in signal_accounts.iter().zip(table.chunks_exact_mut(SIGNAL_ENTRY_LEN)) {
+        entry[SIGNAL_ENTRY_KEY_OFF..SIGNAL_ENTRY_KEY_OFF + 32].copy_from_slice(account.key.as_ref());
+        entry[SIGNAL_ENTRY_OWNER_OFF..SIGNAL_ENTRY_OWNER_OFF + 32].copy_from_slice(account.owner.as_ref());
+        msg!("credibility-set-signals: {} owned by {}", account.key, account.owner);
+    }
+    msg!("credibility-set-signals: {} registered", signal_accounts.len());
+
+    Ok(())
+}
+
+// =============================================================================
+// Sync Inventory Instruction (tag 0x37)
+//
+// Reconciles inventory_base with the LP's position in percolator's own books,
+// so a missed or failed fill can't leave the matcher pricing a book the LP no
+// longer holds. Data: [0x37, lp_idx (u16)], the index a Match names. Reads the
+// account at lp_idx from the bound slab (any percolator slab on an unbound
+// context), which must be an LP whose matcher program and context are this
+// one; percolator books the LP the other side of every fill, so the inventory
+// becomes minus its position_size. Permissionless, like UpdateCredibility: the
+// slab is the only input. The drift is logged.
+// =============================================================================
+fn process_sync_inventory(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 3 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let ctx_account = &accounts[0];
+    let slab_account = &accounts[1];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..2])?;
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    if ctx_data.len() < CTX_LEN {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    if MatcherContext::view(&ctx_data).magic.get() != MAGIC {
+        msg!("ERROR: Context not initialized");
+        return Err(MatcherError::NotInitialized.into());
+    }
+    // Only a slab the context is bound to, or lists, can speak for the LP
+    check_pinned_slab(slab_account, &ctx_data)?;
+    let book = book_for(&ctx_data, Some(slab_account.key))?;
+
+    let slab_data = slab_account.try_borrow_data()?;
+    let lp_idx = read_u16(data, 1);
+    let account_off = SLAB_ACCOUNTS_OFF + lp_idx as usize * SLAB_ACCOUNT_LEN;
+    let Some(account) = slab_data.get(account_off..account_off + SLAB_ACCOUNT_LEN) else {
+        msg!("ERROR: Slab has no account {}", lp_idx);
+        return Err(ProgramError::AccountDataTooSmall);
+    };
+    if account[ACCT_KIND_OFF] != ACCT_KIND_LP
+        || read_pubkey(account, ACCT_MATCHER_PROGRAM_OFF) != *program_id
+        || read_pubkey(account, ACCT_MATCHER_CONTEXT_OFF) != *ctx_account.key
+    {
+        msg!("ERROR: Account {} is not an LP matched through this context", lp_idx);
+        return Err(MatcherError::LpAccountMismatch.into());
+    }
+
+    let position = read_i128(account, ACCT_POSITION_OFF);
+    let new = position.checked_neg().ok_or(ProgramError::ArithmeticOverflow)?;
+    let old = read_i128(&ctx_data, book.inventory);
+    let drift = old.checked_sub(new).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    write_i128(&mut ctx_data, book.inventory, new);
+
+    msg!("credibility-sync-inventory: {} -> {} (drift {}) from account {}", old, new, drift, lp_idx);
+
+    Ok(())
+}
+
+// =============================================================================
+// Create Context Instruction (tag 0x38)
+//
+// Creates a context at the address derived from [CONTEXT_SEED, lp_pda, slab],
+// so an LP has one canonical context per market that anyone can find. Data:
+// [0x38, len (u32)], the account size, CTX_EXT_LEN + CREATION_LEN or more:
+// the derivation needs the slab, which only v6 keeps, and the creation record
+// is where the context is marked derived. The payer funds the rent. The bump
+// is stored at CTX_BUMP_OFF and the record's `derived` set; Init must then be
+// passed the same LP PDA and slab, and every instruction after it rederives
+// the address from the context.
+// =============================================================================
+fn process_create_context(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 5 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 5 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let lp_pda = &accounts[0];
+    let ctx_account = &accounts[1];
+    let slab = &accounts[2];
+    let payer = &accounts[3];
+    let system_program = &accounts[4];
+    check_distinct(&accounts[..4])?;
+    if !payer.is_signer {
+        msg!("ERROR: Payer must sign to fund the context");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let len = read_u32(data, 1) as usize;
+    let Some(record) = creation_off(len) else {
+        msg!("ERROR: A derived context needs a v6 account with a creation record ({} bytes)", CTX_EXT_LEN + CREATION_LEN);
+        return Err(MatcherError::ExtensionRequired.into());
+    };
+    let (address, bump) = Pubkey::find_program_address(&[CONTEXT_SEED, lp_pda.key.as_ref(), slab.key.as_ref()], program_id);
+    if address != *ctx_account.key {
+        msg!("ERROR: Context should be {}", address);
+        return Err(MatcherError::ContextAddressMismatch.into());
+    }
+
+    // Lamports sent to the address beforehand would make create_account
+    // fail, so top it up, then allocate and assign it
+    let seeds: &[&[u8]] = &[CONTEXT_SEED, lp_pda.key.as_ref(), slab.key.as_ref(), &[bump]];
+    let shortfall = Rent::get()?.minimum_balance(len).saturating_sub(ctx_account.lamports());
+    if shortfall > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, ctx_account.key, shortfall),
+            &[payer.clone(), ctx_account.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(ctx_account.key, len as u64),
+        &[ctx_account.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(ctx_account.key, program_id),
+        &[ctx_account.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+
+    check_ctx_owner(program_id, ctx_account)?;
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    if ctx_data.len() < len {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    MatcherContext::view_mut(&mut ctx_data).bump = bump;
+    ctx_data[record + CREATION_DERIVED_OFF] = 1;
+
+    msg!("credibility-create-context: {} lp_pda={} slab={} bump={} size={}", ctx_account.key, lp_pda.key, slab.key, bump, len);
+
+    Ok(())
+}
+
+// =============================================================================
+// Bind Slab Instruction (tag 0x39)
+//
+// Binds a v6 context Init left unbound (it was given no slab, or it was
+// migrated from v5) to `slab` and pins the slab's owner as the percolator
+// program, as Init does, so UpdateCredibility and SyncInventory read only it.
+// The binding is for life: a bound context, a shared one (SetMarket lists its
+// markets) and one at a CreateContext address, derived from the slab it was
+// made for, are refused.
+// =============================================================================
+fn process_bind_slab(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let slab = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    check_authority(authority, &ctx_data)?;
+    if !is_extended(&ctx_data) {
+        msg!("ERROR: Slab binding needs a v6 context; Migrate first");
+        return Err(MatcherError::ExtensionRequired.into());
+    }
+    if is_shared(&ctx_data) {
+        msg!("ERROR: A shared context lists its slabs with SetMarket");
+        return Err(MatcherError::SharedContext.into());
+    }
+    if book_slab(&ctx_data, 0) != Pubkey::default() {
+        msg!("ERROR: Context is already bound to {}", book_slab(&ctx_data, 0));
+        return Err(MatcherError::SlabMismatch.into());
+    }
+    if is_derived(&ctx_data) {
+        msg!("ERROR: Context is at the address of the slab it was created for");
+        return Err(MatcherError::ContextAddressMismatch.into());
+    }
+    // A system-owned account is no program's slab, and would pin nothing
+    if *slab.owner == solana_program::system_program::id() {
+        msg!("ERROR: Slab {} is not owned by a program", slab.key);
+        return Err(MatcherError::SlabOwnerMismatch.into());
+    }
+    check_bound_slab(slab, &ctx_data)?;
+
+    let ext = ContextExtension::view_mut(&mut ctx_data);
+    ext.slab = slab.key.to_bytes();
+    ext.percolator_program = slab.owner.to_bytes();
+
+    msg!("credibility-bind-slab: slab={} percolator={}", slab.key, slab.owner);
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Fee Discount Instruction (tag 0x25)
+//
+// `[0x25, fee_discount_bps (u16)]` on a schedule account created with room for
+// a rebate (SCHEDULE_REBATE_LEN bytes). The fee comes down with the spread: a
+// quote whose tier spread earned the whole credibility discount (down at
+// min_spread_bps) waives fee_discount_bps of its fee, one part way that share
+// of it. Highly credible markets then compete on total cost, not spread alone.
+// The plain kind has no discount to follow and RFQ fills carry no fee, so
+// neither changes. Like SetRebate it only ever improves a taker's price and
+// takes effect at once; 0 turns it off.
+// =============================================================================
+fn process_set_fee_discount(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 3 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let fee_discount_bps = read_u16(data, 1);
+    if fee_discount_bps as u64 > BPS {
+        msg!("ERROR: fee discount {} > 10000 bps", fee_discount_bps);
+        return Err(MatcherError::FeeDiscountTooHigh.into());
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let schedule_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_schedule(program_id, schedule_account, ctx_account.key)?;
+
+    let mut schedule_data = schedule_account.try_borrow_mut_data()?;
+    if schedule_data.len() < SCHEDULE_REBATE_LEN {
+        msg!("ERROR: Schedule account has no room for a fee discount; create it with {} bytes", SCHEDULE_REBATE_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    write_u16(&mut schedule_data, SCHEDULE_FEE_DISCOUNT_OFF, fee_discount_bps);
+
+    msg!("credibility-set-fee-discount: fee_discount={}bps", fee_discount_bps);
+
+    Ok(())
+}
+
+// =============================================================================
+// Init Tip Jar Instruction (tag 0x26)
+//
+// `[0x26, lamports_per_slot (u64), max_tip (u64)]`. Binds a companion tip jar
+// to the context so the permissionless UpdateCredibility gets called on quiet
+// markets: each refresh pays its keeper lamports_per_slot for every slot the
+// snapshot had been stale past SNAPSHOT_GRACE_SLOTS, up to max_tip. The
+// account is created by the authority, owned by this program and TIP_JAR_LEN
+// bytes; its lamports above rent exemption are the escrow, topped up with
+// plain transfers, and a tip never dips into the rent.
+// =============================================================================
+fn process_init_tip_jar(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 17 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let tip_jar = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_ctx_owner(program_id, tip_jar)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+
+    let mut tip_data = tip_jar.try_borrow_mut_data()?;
+    if tip_data.len() < TIP_JAR_LEN {
+        msg!("ERROR: Tip jar is {} bytes; needs {}", tip_data.len(), TIP_JAR_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    let min_balance = Rent::get()?.minimum_balance(tip_data.len());
+    if tip_jar.lamports() < min_balance {
+        msg!("ERROR: Tip jar holds {} lamports; rent exemption needs {}", tip_jar.lamports(), min_balance);
+        return Err(ProgramError::AccountNotRentExempt);
+    }
+    if read_u64(&tip_data, TIP_JAR_MAGIC_OFF) == TIP_JAR_MAGIC {
+        msg!("ERROR: Tip jar already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let (lamports_per_slot, max_tip) = (read_u64(data, 1), read_u64(data, 9));
+    tip_data.fill(0);
+    write_u64(&mut tip_data, TIP_JAR_MAGIC_OFF, TIP_JAR_MAGIC);
+    write_u32(&mut tip_data, TIP_JAR_VERSION_OFF, TIP_JAR_VERSION);
+    tip_data[TIP_JAR_CTX_OFF..TIP_JAR_CTX_OFF + 32].copy_from_slice(ctx_account.key.as_ref());
+    write_u64(&mut tip_data, TIP_JAR_PER_SLOT_OFF, lamports_per_slot);
+    write_u64(&mut tip_data, TIP_JAR_MAX_OFF, max_tip);
+
+    msg!(
+        "credibility-init-tip-jar: tip_jar={} per_slot={} max={} escrow={}",
+        tip_jar.key, lamports_per_slot, max_tip, tip_jar.lamports() - min_balance
+    );
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Tip Instruction (tag 0x27)
+//
+// `[0x27, lamports_per_slot (u64), max_tip (u64)]` on a bound tip jar. The
+// escrow is the LP's own, so the tip changes at once; a zero max_tip stops
+// the tips without closing the jar.
+// =============================================================================
+fn process_set_tip(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 17 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let tip_jar = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_tip_jar(program_id, tip_jar, ctx_account.key)?;
+
+    let (lamports_per_slot, max_tip) = (read_u64(data, 1), read_u64(data, 9));
+    let mut tip_data = tip_jar.try_borrow_mut_data()?;
+    write_u64(&mut tip_data, TIP_JAR_PER_SLOT_OFF, lamports_per_slot);
+    write_u64(&mut tip_data, TIP_JAR_MAX_OFF, max_tip);
+
+    msg!("credibility-set-tip: per_slot={} max={}", lamports_per_slot, max_tip);
+
+    Ok(())
+}
+
+// =============================================================================
+// Close Tip Jar Instruction (tag 0x28)
+//
+// Zeroes a bound tip jar and sweeps its lamports, escrow and rent alike, to
+// `destination`.
+// =============================================================================
+fn process_close_tip_jar(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 4 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let tip_jar = &accounts[2];
+    let destination = &accounts[3];
+    check_ctx_account(program_id, ctx_account)?;
+    // As with Close, the authority may take the lamports itself
+    check_distinct(&accounts[..3])?;
+    check_distinct(&accounts[1..4])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_tip_jar(program_id, tip_jar, ctx_account.key)?;
+
+    tip_jar.try_borrow_mut_data()?.fill(0);
+    let reclaimed = tip_jar.lamports();
+    let dest_lamports = destination
+        .lamports()
+        .checked_add(reclaimed)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    **tip_jar.try_borrow_mut_lamports()? = 0;
+    **destination.try_borrow_mut_lamports()? = dest_lamports;
+
+    msg!("credibility-close-tip-jar: reclaimed={} lamports to {}", reclaimed, destination.key);
+
+    Ok(())
+}
+
+// =============================================================================
+// Init Referral Instruction (tag 0x29)
+//
+// `[0x29, share_bps (u16)]`. Binds a companion referral account to the
+// context: a Match that passes it, and names a referrer after its call data,
+// credits that referrer with share_bps of the fill's fee, so integrators
+// routing flow to the LP can be paid a revenue share off-chain. The account
+// is created by the authority, owned by this program and REFERRAL_LEN bytes.
+// =============================================================================
+fn process_init_referral(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 3 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let share_bps = read_u16(data, 1);
+    if share_bps as u64 > BPS {
+        msg!("ERROR: referral share {} > 10000 bps", share_bps);
+        return Err(MatcherError::ReferralShareTooHigh.into());
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let referral_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_ctx_owner(program_id, referral_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+
+    let mut referral_data = referral_account.try_borrow_mut_data()?;
+    if referral_data.len() < REFERRAL_LEN {
+        msg!("ERROR: Referral account is {} bytes; needs {}", referral_data.len(), REFERRAL_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    let min_balance = Rent::get()?.minimum_balance(referral_data.len());
+    if referral_account.lamports() < min_balance {
+        msg!("ERROR: Referral account holds {} lamports; rent exemption needs {}", referral_account.lamports(), min_balance);
+        return Err(ProgramError::AccountNotRentExempt);
+    }
+    if read_u64(&referral_data, REFERRAL_MAGIC_OFF) == REFERRAL_MAGIC {
+        msg!("ERROR: Referral account already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    referral_data.fill(0);
+    write_u64(&mut referral_data, REFERRAL_MAGIC_OFF, REFERRAL_MAGIC);
+    write_u32(&mut referral_data, REFERRAL_VERSION_OFF, REFERRAL_VERSION);
+    referral_data[REFERRAL_CTX_OFF..REFERRAL_CTX_OFF + 32].copy_from_slice(ctx_account.key.as_ref());
+    write_u16(&mut referral_data, REFERRAL_SHARE_OFF, share_bps);
+
+    msg!("credibility-init-referral: referral={} share={}bps", referral_account.key, share_bps);
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Referral Share Instruction (tag 0x2A)
+//
+// `[0x2A, share_bps (u16)]` on a bound referral account. Applies to fills
+// from then on; what referrers were already credited stays.
+// =============================================================================
+fn process_set_referral_share(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 3 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let share_bps = read_u16(data, 1);
+    if share_bps as u64 > BPS {
+        msg!("ERROR: referral share {} > 10000 bps", share_bps);
+        return Err(MatcherError::ReferralShareTooHigh.into());
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let referral_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_referral(program_id, referral_account, ctx_account.key)?;
+
+    write_u16(&mut referral_account.try_borrow_mut_data()?, REFERRAL_SHARE_OFF, share_bps);
+
+    msg!("credibility-set-referral-share: share={}bps", share_bps);
+
+    Ok(())
+}
+
+// =============================================================================
+// Init Ladder Instruction (tag 0x1A)
+//
+// Binds a companion ladder account to the context, for PublishLadder to fill.
+// The account is created by the authority, owned by this program and
+// LADDER_LEN bytes. Nothing reads it back, so a context may have several (one
+// per market of a shared context, say); Close reclaims one.
+// =============================================================================
+fn process_init_ladder(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let ladder_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_ctx_owner(program_id, ladder_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+
+    let mut ladder_data = ladder_account.try_borrow_mut_data()?;
+    if ladder_data.len() < LADDER_LEN {
+        msg!("ERROR: Ladder account is {} bytes; needs {}", ladder_data.len(), LADDER_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    let min_balance = Rent::get()?.minimum_balance(ladder_data.len());
+    if ladder_account.lamports() < min_balance {
+        msg!("ERROR: Ladder account holds {} lamports; rent exemption needs {}", ladder_account.lamports(), min_balance);
+        return Err(ProgramError::AccountNotRentExempt);
+    }
+    if read_u64(&ladder_data, LADDER_MAGIC_OFF) == LADDER_MAGIC {
+        msg!("ERROR: Ladder account already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    ladder_data.fill(0);
+    write_u64(&mut ladder_data, LADDER_MAGIC_OFF, LADDER_MAGIC);
+    write_u32(&mut ladder_data, LADDER_VERSION_OFF, LADDER_VERSION);
+    ladder_data[LADDER_CTX_OFF..LADDER_CTX_OFF + 32].copy_from_slice(ctx_account.key.as_ref());
+
+    msg!("credibility-init-ladder: ladder={} levels={}", ladder_account.key, MAX_LADDER_LEVELS);
+
+    Ok(())
+}
+
+// =============================================================================
+// Publish Ladder Instruction (tag 0x1B)
+//
+// Prices a ladder of trades each way through the Match path and writes it to
+// a bound ladder account, so percolator and routers can read the LP's depth
+// like a resting book instead of simulating quotes. Takes QuotePreview's data,
+// with the size as the step: level i on each side is one trade of i * step,
+// at the price and clipped size Match would fill it at this slot. A side stops
+// at its first clipped level, or with the reason the inventory refuses it
+// (reduce-only, inventory limit, side off); anything that blocks both sides
+// fails the call. The companion accounts follow the ladder as they follow the
+// slab in Match. Only the authority publishes: the oracle price is its word.
+// =============================================================================
+fn process_publish_ladder(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < PREVIEW_CALL_LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let ladder_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..accounts.len().min(5 + MAX_SIGNAL_ACCOUNTS)])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_quotable(&ctx_data)?;
+    check_bound_ladder(program_id, ladder_account, ctx_account.key)?;
+    let (schedule_account, rest) = split_schedule(program_id, ctx_account.key, &accounts[3..], &ctx_data)?;
+    let (stats_account, signal_accounts) = stats_and_signals(program_id, rest, &ctx_data, schedule_account)?;
+    let stats_data = stats_account.map(|a| a.try_borrow_data()).transpose()?;
+
+    let oracle_price_e6 = oracle_e6(&ctx_data, u64::from_le_bytes(data[1..9].try_into().unwrap()));
+    let step = u128::from_le_bytes(data[9..25].try_into().unwrap());
+    // The top level's size has to fit a signed trade
+    if step == 0 || step.checked_mul(MAX_LADDER_LEVELS as u128).is_none_or(|top| top > i128::MAX as u128) {
+        msg!("ERROR: Ladder step {} is zero or too large", step);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let market = read_opt_u16(data, PREVIEW_CALL_LEN);
+    let book = book_at(&ctx_data, market)?;
+    let current_slot = Clock::get()?.slot;
+    let companions = Companions {
+        stats_data: stats_data.as_deref().map(|d| &d[..]),
+        signal_bps: signal_surcharge(signal_accounts, current_slot)?,
+        spread_mult_bps: spread_mult(schedule_account, current_slot)?,
+        fee_tiers: fee_tiers(schedule_account)?,
+        rebate_bps: schedule_rebate(schedule_account)?,
+        fee_discount_bps: schedule_fee_discount(schedule_account)?,
+        min_notional_e6: schedule_min_notional(schedule_account)?,
+        inventory_halflife: schedule_inventory_halflife(schedule_account)?,
+        inventory_band: schedule_inventory_band(schedule_account)?,
+        inventory_caps: schedule_inventory_caps(schedule_account)?,
+        reduce_only_bps: schedule_reduce_only_bps(schedule_account)?,
+        max_inventory_bps: schedule_max_inventory_bps(schedule_account)?,
+        loyalty_bps: 0,
+        oracle_bps: 0,
+    };
+
+    // A side that fails the call fails it as a whole, so no half-written
+    // ladder lands
+    let mut ladder_data = ladder_account.try_borrow_mut_data()?;
+    ladder_data[LADDER_BID_COUNT_OFF..LADDER_LEN].fill(0);
+    write_u16(&mut ladder_data, LADDER_MARKET_OFF, market);
+    write_u64(&mut ladder_data, LADDER_SLOT_OFF, current_slot);
+    write_u64(&mut ladder_data, LADDER_ORACLE_OFF, oracle_price_e6);
+    write_u128(&mut ladder_data, LADDER_STEP_OFF, step);
+    let mut counts = [0u32; 2];
+    for (side, (sign, levels_off, count_off, reason_off)) in [
+        (-1, LADDER_BIDS_OFF, LADDER_BID_COUNT_OFF, LADDER_BID_REASON_OFF),
+        (1, LADDER_ASKS_OFF, LADDER_ASK_COUNT_OFF, LADDER_ASK_REASON_OFF),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        for level in 0..MAX_LADDER_LEVELS {
+            let trade_size = sign * (step * (level as u128 + 1)) as i128;
+            let q = match quote(&ctx_data, &book, &companions, oracle_price_e6, trade_size, current_slot) {
+                Ok(q) => q,
+                Err(err) => match soft_reject_reason(&err) {
+                    Some(reason) => {
+                        write_u32(&mut ladder_data, reason_off, reason as u32);
+                        break;
+                    }
+                    None => return Err(err),
+                },
+            };
+            let out = levels_off + level * LEVEL_LEN;
+            write_u128(&mut ladder_data, out + LEVEL_SIZE_OFF, q.fill_size.unsigned_abs());
+            write_u64(&mut ladder_data, out + LEVEL_PRICE_OFF, q.exec_price_e6);
+            write_u64(&mut ladder_data, out + LEVEL_SPREAD_OFF, q.spread_bps);
+            counts[side] += 1;
+            if q.fill_size != trade_size {
+                break;
+            }
+        }
+        write_u32(&mut ladder_data, count_off, counts[side]);
+    }
+
+    msg!(
+        "credibility-ladder: bids={} asks={} step={} oracle={} slot={}",
+        counts[0], counts[1], step, oracle_price_e6, current_slot
+    );
+
+    Ok(())
+}
+
+// =============================================================================
+// Init Feed Instruction (tag 0x1C)
+//
+// Binds a feed account to a context, v5 or v6, so Match prices off an oracle
+// account instead of the oracle price in the call: `[0x1C, max_age_secs
+// (u32), source (u8, optional), pinned (u8, optional)]`, the source one of
+// the FEED_SOURCE_* values (Pyth without it), with a price account to take
+// the feed from after the feed account: a Pyth PriceUpdateV2, whose feed id
+// is kept, or a Switchboard pull feed or Chainlink OCR2 feed, whose key is.
+// Pinned, a Pyth feed is keyed by its price account too (a push oracle
+// account, say), so a caller can't pick among the updates posted for the
+// feed id. From here on Match needs the feed account and a price account for
+// each of its feeds, published no more than max_age_secs
+// before the cluster's clock, after the schedule (if any). The feed account
+// is created by the authority, owned by this program and FEED_LEN bytes; the
+// context keeps it until Close, which closes both. AddFeed binds more feeds
+// to it.
+// =============================================================================
+fn process_init_feed(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 4 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 5 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let max_age_secs = read_u32(data, 1);
+    let source = data.get(5).copied().unwrap_or(FEED_SOURCE_PYTH);
+    let pinned = data.get(6).copied().unwrap_or(0);
+    if max_age_secs == 0 || source > FEED_SOURCE_CHAINLINK || pinned > 1 {
+        msg!("ERROR: Feed max_age_secs {} from source {}, pinned {}", max_age_secs, source, pinned);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let feed_account = &accounts[2];
+    let price_account = &accounts[3];
+    check_ctx_account(program_id, ctx_account)?;
+    check_ctx_owner(program_id, feed_account)?;
+    check_distinct(&accounts[..4])?;
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    check_authority(authority, &ctx_data)?;
+    if MatcherContext::view(&ctx_data).has_flag(CTX_FLAG_FEED) {
+        msg!("ERROR: Context already has a feed");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    let feed_id = read_bound_feed(source, pinned, price_account)?.feed_id;
+
+    let mut feed_data = feed_account.try_borrow_mut_data()?;
+    if feed_data.len() < FEED_LEN {
+        msg!("ERROR: Feed account is {} bytes; needs {}", feed_data.len(), FEED_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    let min_balance = Rent::get()?.minimum_balance(feed_data.len());
+    if feed_account.lamports() < min_balance {
+        msg!("ERROR: Feed account holds {} lamports; rent exemption needs {}", feed_account.lamports(), min_balance);
+        return Err(ProgramError::AccountNotRentExempt);
+    }
+    if read_u64(&feed_data, FEED_MAGIC_OFF) == FEED_MAGIC {
+        msg!("ERROR: Feed account already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    feed_data.fill(0);
+    write_u64(&mut feed_data, FEED_MAGIC_OFF, FEED_MAGIC);
+    write_u32(&mut feed_data, FEED_VERSION_OFF, FEED_VERSION);
+    write_u32(&mut feed_data, FEED_MAX_AGE_OFF, max_age_secs);
+    feed_data[FEED_CTX_OFF..FEED_CTX_OFF + 32].copy_from_slice(ctx_account.key.as_ref());
+    feed_data[FEED_COUNT_OFF] = 1;
+    write_feed_entry(&mut feed_data, 0, &feed_id, source, pinned);
+    MatcherContext::view_mut(&mut ctx_data).flags |= CTX_FLAG_FEED;
+
+    msg!(
+        "credibility-init-feed: feed={} source={} id={} pinned={} max_age={}s",
+        feed_account.key, FEED_SOURCE_NAMES[source as usize], Pubkey::new_from_array(feed_id), pinned == 1, max_age_secs
+    );
+
+    Ok(())
+}
+
+// =============================================================================
+// Add Feed Instruction (tag 0x1D)
+//
+// Binds one more oracle feed to a context's feed account: `[0x1D, source
+// (u8), pinned (u8, optional)]`, with a price account to take the feed from after the feed account,
+// as InitFeed does. Up to MAX_FEEDS feeds, each bound once; Match then needs
+// a price account for each, in the order they were bound, and prices off
+// their median, so a single feed that is off (or manipulated) can't move the
+// price on its own. SetFeedDivergence says what happens when they disagree.
+// =============================================================================
+fn process_add_feed(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 4 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 2 || data[1] > FEED_SOURCE_CHAINLINK || data.get(2).is_some_and(|p| *p > 1) {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let source = data[1];
+    let pinned = data.get(2).copied().unwrap_or(0);
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let feed_account = &accounts[2];
+    let price_account = &accounts[3];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..4])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_feed(program_id, feed_account, ctx_account.key)?;
+    let feed_id = read_bound_feed(source, pinned, price_account)?.feed_id;
+
+    let mut feed_data = feed_account.try_borrow_mut_data()?;
+    let count = feed_count(&feed_data);
+    if count == MAX_FEEDS {
+        msg!("ERROR: Feed account already holds {} feeds", MAX_FEEDS);
+        return Err(MatcherError::InvalidFeedSet.into());
+    }
+    if feed_entries(&feed_data).any(|entry| entry[FEED_ENTRY_ID_OFF..FEED_ENTRY_ID_OFF + 32] == feed_id) {
+        msg!("ERROR: Feed {} is already bound", Pubkey::new_from_array(feed_id));
+        return Err(MatcherError::InvalidFeedSet.into());
+    }
+    write_feed_entry(&mut feed_data, count, &feed_id, source, pinned);
+    feed_data[FEED_COUNT_OFF] = count as u8 + 1;
+
+    msg!(
+        "credibility-add-feed: feed={} source={} id={} feeds={}",
+        feed_account.key, FEED_SOURCE_NAMES[source as usize], Pubkey::new_from_array(feed_id), count + 1
+    );
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Feed Divergence Instruction (tag 0x1E)
+//
+// How far apart a context's feeds may be: `[0x1E, max_divergence_bps (u16),
+// widen (u8)]`. Divergence is the spread between the highest and lowest
+// feed price in bps of their median. Past max_divergence_bps Match refuses
+// the trade (FeedDivergence), or with widen set quotes it with the whole
+// divergence added to the spread. 0 turns the check off. Like a schedule,
+// it only ever refuses or widens, so it takes effect with no timelock.
+// =============================================================================
+fn process_set_feed_divergence(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 4 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let max_divergence_bps = read_u16(data, 1);
+    let widen = data[3];
+    if max_divergence_bps as u64 > BPS || widen > 1 {
+        msg!("ERROR: Feed divergence {}bps, widen {}", max_divergence_bps, widen);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let feed_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_feed(program_id, feed_account, ctx_account.key)?;
+
+    let mut feed_data = feed_account.try_borrow_mut_data()?;
+    write_u16(&mut feed_data, FEED_MAX_DIVERGENCE_OFF, max_divergence_bps);
+    feed_data[FEED_WIDEN_OFF] = widen;
+
+    msg!("credibility-set-feed-divergence: max={}bps widen={}", max_divergence_bps, widen == 1);
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Feed Slot Age Instruction (tag 0x20)
+//
+// How many slots old a feed's price may be: `[0x20, max_age_slots (u16),
+// widen (u8)]`. A price's slot is the one it landed on chain at (a Pyth
+// update's posted slot, a Switchboard result's or a Chainlink round's slot),
+// which a publisher can't backdate the way it can a unix timestamp. Past
+// max_age_slots Match refuses the trade (FeedPriceStale), or with widen set
+// quotes it at max_spread_bps. 0 turns the check off, leaving max_age_secs.
+// Like SetFeedDivergence it takes effect with no timelock.
+// =============================================================================
+fn process_set_feed_slot_age(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 4 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let max_age_slots = read_u16(data, 1);
+    let widen = data[3];
+    if widen > 1 {
+        msg!("ERROR: Feed slot age widen {}", widen);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let feed_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_feed(program_id, feed_account, ctx_account.key)?;
+
+    let mut feed_data = feed_account.try_borrow_mut_data()?;
+    write_u16(&mut feed_data, FEED_MAX_AGE_SLOTS_OFF, max_age_slots);
+    feed_data[FEED_SLOT_WIDEN_OFF] = widen;
+
+    msg!("credibility-set-feed-slot-age: max={} slots widen={}", max_age_slots, widen == 1);
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Feed Fallback Instruction (tag 0x21)
+//
+// Turns a context's feeds from a median into a fallback order: `[0x21,
+// fallback (u8), penalty_bps (u16) for each of MAX_FEEDS feeds]`. With
+// fallback set, Match prices off the first feed, in the order they were
+// bound, whose price is fresh and positive, and adds that feed's penalty to
+// the spread, so a market keeps quoting, wider, through one feed's outage.
+// A price account that isn't the feed's still fails the Match: only a stale
+// or empty price moves it down the list. Divergence isn't checked, there
+// being one price. Like SetFeedDivergence it takes effect with no timelock.
+// =============================================================================
+fn process_set_feed_fallback(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 2 + 2 * MAX_FEEDS {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let fallback = data[1];
+    let penalties: [u16; MAX_FEEDS] = core::array::from_fn(|i| read_u16(data, 2 + 2 * i));
+    if fallback > 1 || penalties.iter().any(|p| *p as u64 > BPS) {
+        msg!("ERROR: Feed fallback {}, penalties {:?}bps", fallback, penalties);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let feed_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_authority(authority, &ctx_data)?;
+    check_bound_feed(program_id, feed_account, ctx_account.key)?;
+
+    let mut feed_data = feed_account.try_borrow_mut_data()?;
+    feed_data[FEED_FALLBACK_OFF] = fallback;
+    for (i, penalty) in penalties.iter().enumerate() {
+        write_u16(&mut feed_data, FEED_HEADER_LEN + i * FEED_ENTRY_LEN + FEED_ENTRY_PENALTY_OFF, *penalty);
+    }
+
+    msg!("credibility-set-feed-fallback: fallback={} penalties={:?}bps", fallback == 1, penalties);
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Oracle Decimals Instruction (tag 0x1F)
+//
+// `[0x1F, decimals (u8)]`: the decimals of the oracle price Match,
+// QuotePreview, QuoteTwoSided and PublishLadder are called with, 6 through
+// MAX_ORACLE_DECIMALS, so an integration passing e8 or e9 prices doesn't have
+// to scale them itself. The program divides them down to e6, rounding down;
+// 0 (the default) or 6 takes them as e6. Everything it returns and records
+// stays e6, and only the MatcherReturn's oracle echo repeats the caller's
+// price. A fed context's prices are unaffected. Like UpdateParams it only
+// works before the first fill: on a live market a wrong scale would trade at
+// a price orders of magnitude off.
+// =============================================================================
+fn process_set_oracle_decimals(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 2 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let decimals = data[1];
+    if decimals != 0 && !(ORACLE_DECIMALS_E6..=MAX_ORACLE_DECIMALS).contains(&decimals) {
+        msg!("ERROR: Oracle decimals {} outside {}..={}", decimals, ORACLE_DECIMALS_E6, MAX_ORACLE_DECIMALS);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..2])?;
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    check_authority(authority, &ctx_data)?;
+    if MatcherContext::view(&ctx_data).last_oracle_price_e6.get() != 0 {
+        msg!("ERROR: Market is live; the oracle scale is fixed");
+        return Err(MatcherError::MarketLive.into());
+    }
+
+    let context = MatcherContext::view_mut(&mut ctx_data);
+    let was = context.oracle_decimals;
+    context.oracle_decimals = decimals;
+
+    msg!("credibility-set-oracle-decimals: {} -> {}", was, decimals);
+
+    Ok(())
+}
+
+// =============================================================================
+// QuoteTwoSided Instruction (tag 0x11)
+//
+// Prices `size` both ways at once — the bid the LP pays for a taker's sell and
+// the ask it charges for a taker's buy — so a router can show the spread and
+// pick a side without two preview calls. Read-only, like QuotePreview.
+//
+// A side the LP's inventory can't take (reduce-only, inventory limit) comes
+// back zeroed with its reason code; the other side is still quoted. Anything
+// that blocks both sides (paused, bad oracle) fails the call. The market index
+// is read as QuotePreview reads it.
+// =============================================================================
+fn process_quote_two_sided(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.is_empty() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < PREVIEW_CALL_LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let ctx_account = &accounts[0];
+    check_ctx_account(program_id, ctx_account)?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_quotable(&ctx_data)?;
+    // Then a scheduled context's schedule, the optional bound stats account,
+    // for the TWAP, and signals
+    check_distinct(&accounts[..accounts.len().min(3 + MAX_SIGNAL_ACCOUNTS)])?;
+    let (schedule_account, rest) = split_schedule(program_id, ctx_account.key, &accounts[1..], &ctx_data)?;
+    let (stats_account, signal_accounts) = stats_and_signals(program_id, rest, &ctx_data, schedule_account)?;
+    let stats_data = stats_account.map(|a| a.try_borrow_data()).transpose()?;
+
+    let oracle_price_e6 = oracle_e6(&ctx_data, u64::from_le_bytes(data[1..9].try_into().unwrap()));
+    let size = u128::from_le_bytes(data[9..25].try_into().unwrap());
+    let Ok(size) = i128::try_from(size) else {
+        return Err(ProgramError::InvalidInstructionData);
+    };
+    let book = book_at(&ctx_data, read_opt_u16(data, PREVIEW_CALL_LEN))?;
+    let current_slot = Clock::get()?.slot;
+    let companions = Companions {
+        stats_data: stats_data.as_deref().map(|d| &d[..]),
+        signal_bps: signal_surcharge(signal_accounts, current_slot)?,
+        spread_mult_bps: spread_mult(schedule_account, current_slot)?,
+        fee_tiers: fee_tiers(schedule_account)?,
+        rebate_bps: schedule_rebate(schedule_account)?,
+        fee_discount_bps: schedule_fee_discount(schedule_account)?,
+        min_notional_e6: schedule_min_notional(schedule_account)?,
+        inventory_halflife: schedule_inventory_halflife(schedule_account)?,
+        inventory_band: schedule_inventory_band(schedule_account)?,
+        inventory_caps: schedule_inventory_caps(schedule_account)?,
+        reduce_only_bps: schedule_reduce_only_bps(schedule_account)?,
+        max_inventory_bps: schedule_max_inventory_bps(schedule_account)?,
+        loyalty_bps: 0,
+        oracle_bps: 0,
+    };
+
+    let mut ret = [0u8; TWO_SIDED_LEN];
+    for (trade_size, reason_off, quote_off) in [
+        (-size, TWO_SIDED_BID_REASON_OFF, TWO_SIDED_BID_OFF),
+        (size, TWO_SIDED_ASK_REASON_OFF, TWO_SIDED_ASK_OFF),
+    ] {
+        match quote(&ctx_data, &book, &companions, oracle_price_e6, trade_size, current_slot) {
+            Ok(q) => ret[quote_off..quote_off + QUOTE_LEN].copy_from_slice(&breakdown(&q)),
+            Err(err) => match soft_reject_reason(&err) {
+                Some(reason) => write_u64(&mut ret, reason_off, reason as u64),
+                None => return Err(err),
+            },
+        }
+    }
+    set_return_data(&ret);
+
+    Ok(())
+}
+
+// =============================================================================
+// Init Stats Instruction (tag 0x12)
+//
+// Binds a companion stats account to a v6 context. Every Match that passes it
+// (after the slab, and the schedule of a scheduled context) appends the fill
+// to its ring of the last STATS_CAPACITY fills, giving keepers and analysts
+// recent history on chain.
+// The account is created by the authority, owned by this program and
+// STATS_LEN bytes; binding a new one replaces the old binding.
+// `[0x12, protocol_fee_bps (u16)]` with a treasury account after the stats
+// account, on one of STATS_PROTOCOL_LEN bytes, also sets the treasury's cut
+// of every fee the account sees, fixed for its life.
+// =============================================================================
+fn process_init_stats(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let protocol_fee_bps = if data.len() >= 3 { read_u16(data, 1) } else { 0 };
+    if protocol_fee_bps as u64 > BPS {
+        msg!("ERROR: protocol_fee_bps {} above {}", protocol_fee_bps, BPS);
+        return Err(MatcherError::ProtocolFeeTooHigh.into());
+    }
+    let treasury = accounts.get(3);
+    if protocol_fee_bps > 0 && treasury.is_none() {
+        msg!("ERROR: A protocol fee needs a treasury account");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let stats_account = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_ctx_owner(program_id, stats_account)?;
+    check_distinct(&accounts[..accounts.len().min(4)])?;
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    check_authority(authority, &ctx_data)?;
+    if !is_extended(&ctx_data) {
+        msg!("ERROR: A stats account needs a v6 context; Migrate first");
+        return Err(MatcherError::ExtensionRequired.into());
+    }
+    if is_shared(&ctx_data) {
+        msg!("ERROR: The stats rings hold one market's fills and prices");
+        return Err(MatcherError::SharedContext.into());
+    }
+
+    let mut stats_data = stats_account.try_borrow_mut_data()?;
+    if stats_data.len() < STATS_LEN {
+        msg!("ERROR: Stats account is {} bytes; needs {}", stats_data.len(), STATS_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    let min_balance = Rent::get()?.minimum_balance(stats_data.len());
+    if stats_account.lamports() < min_balance {
+        msg!("ERROR: Stats account holds {} lamports; rent exemption needs {}", stats_account.lamports(), min_balance);
+        return Err(ProgramError::AccountNotRentExempt);
+    }
+    if read_u64(&stats_data, STATS_MAGIC_OFF) == STATS_MAGIC {
+        msg!("ERROR: Stats account already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    if treasury.is_some() && stats_data.len() < STATS_PROTOCOL_LEN {
+        msg!("ERROR: Stats account has no room for a protocol fee; create it with {} bytes", STATS_PROTOCOL_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+
+    stats_data.fill(0);
+    write_u64(&mut stats_data, STATS_MAGIC_OFF, STATS_MAGIC);
+    write_u32(&mut stats_data, STATS_VERSION_OFF, STATS_VERSION);
+    write_u32(&mut stats_data, STATS_CAPACITY_OFF, STATS_CAPACITY as u32);
+    stats_data[STATS_CTX_OFF..STATS_CTX_OFF + 32].copy_from_slice(ctx_account.key.as_ref());
+    if let Some(treasury) = treasury {
+        stats_data[STATS_TREASURY_OFF..STATS_TREASURY_OFF + 32].copy_from_slice(treasury.key.as_ref());
+        write_u16(&mut stats_data, STATS_PROTOCOL_FEE_BPS_OFF, protocol_fee_bps);
+        msg!("credibility-protocol-fee: treasury={} fee={}bps", treasury.key, protocol_fee_bps);
+    }
+    ContextExtension::view_mut(&mut ctx_data).stats = stats_account.key.to_bytes();
+
+    msg!("credibility-init-stats: stats={} capacity={}", stats_account.key, STATS_CAPACITY);
+
+    Ok(())
+}
+
+// =============================================================================
+// Set RFQ Signer Instruction (tag 0x13)
+//
+// Names the key whose off-chain quotes PostRfq accepts: the LP's market maker,
+// pricing by hand while the program still enforces the limits. Setting it, to
+// a new key or the same one, withdraws any posted quote; the default key turns
+// RFQ off. The nonce carries over, so no quote signed before can come back.
+// =============================================================================
+fn process_set_rfq_signer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 33 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..2])?;
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    check_authority(authority, &ctx_data)?;
+    if !is_extended(&ctx_data) {
+        msg!("ERROR: RFQ needs a v6 context; Migrate first");
+        return Err(MatcherError::ExtensionRequired.into());
+    }
+    if is_shared(&ctx_data) {
+        msg!("ERROR: An RFQ quote prices one market");
+        return Err(MatcherError::SharedContext.into());
+    }
+
+    let signer = read_pubkey(data, 1);
+    ContextExtension::view_mut(&mut ctx_data).rfq_signer = signer.to_bytes();
+    clear_rfq(&mut ctx_data);
+
+    msg!("credibility-set-rfq-signer: signer={}", signer);
+
+    Ok(())
+}
+
+// =============================================================================
+// Post RFQ Instruction (tag 0x14)
+//
+// Posts a quote the RFQ signer signed off-chain. Until its size is used up or
+// its expiry slot passes, Matches on its side fill at its price instead of
+// the kind's, clipped to max_fill and under the usual oracle and inventory
+// checks; the other side prices as before. A new quote replaces the posted
+// one.
+//
+// The signature is the ed25519 program's to check: this instruction must come
+// right after an ed25519 instruction verifying exactly this quote by the RFQ
+// signer, which it finds through the instructions sysvar. Permissionless, so
+// the taker can post the quote in the same transaction as the trade.
+// =============================================================================
+fn process_post_rfq(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 1 + RFQ_MSG_LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let ctx_account = &accounts[0];
+    let instructions = &accounts[1];
+    check_ctx_account(program_id, ctx_account)?;
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    check_quotable(&ctx_data)?;
+    if !is_extended(&ctx_data) {
+        msg!("ERROR: RFQ needs a v6 context; Migrate first");
+        return Err(MatcherError::ExtensionRequired.into());
+    }
+    let signer = ContextExtension::view(&ctx_data).rfq_signer();
+    if signer == Pubkey::default() {
+        msg!("ERROR: No RFQ signer set");
+        return Err(MatcherError::RfqSignerUnset.into());
+    }
+
+    let message = &data[1..1 + RFQ_MSG_LEN];
+    check_rfq_signature(instructions, &signer, message)?;
+
+    let rfq_ctx = read_pubkey(message, RFQ_CTX_OFF);
+    let price_e6 = read_u64(message, RFQ_PRICE_OFF);
+    let size = read_i128(message, RFQ_SIZE_OFF);
+    let expiry_slot = read_u64(message, RFQ_EXPIRY_OFF);
+    let nonce = read_u64(message, RFQ_NONCE_OFF);
+    if rfq_ctx != *ctx_account.key || price_e6 == 0 || size == 0 {
+        msg!("ERROR: RFQ for {} at price {} size {}", rfq_ctx, price_e6, size);
+        return Err(MatcherError::InvalidRfq.into());
+    }
+    let current_slot = Clock::get()?.slot;
+    if current_slot > expiry_slot {
+        msg!("REJECT: RFQ expired at slot {} (now {})", expiry_slot, current_slot);
+        return Err(MatcherError::RfqExpired.into());
+    }
+    let last_nonce = ContextExtension::view(&ctx_data).rfq_nonce.get();
+    if nonce <= last_nonce {
+        msg!("REJECT: RFQ nonce {} not above {}", nonce, last_nonce);
+        return Err(MatcherError::RfqReplayed.into());
+    }
+
+    let ext = ContextExtension::view_mut(&mut ctx_data);
+    ext.rfq_price_e6.set(price_e6);
+    ext.rfq_expiry_slot.set(expiry_slot);
+    ext.rfq_nonce.set(nonce);
+    ext.rfq_size_left.set(size);
+
+    msg!("credibility-post-rfq: nonce={} price={} size={} expiry={}", nonce, price_e6, size, expiry_slot);
+
+    Ok(())
+}
+
+// =============================================================================
+// Set Market Instruction (tag 0x15)
+//
+// Lists a slab in a shared context's market table, or updates its caps when
+// it's already there. Each market keeps its own inventory and snapshots and
+// may override max_fill and max_inventory; a zero cap keeps the context's.
+// The slab has to be owned by the same percolator program as the others, so
+// one LP PDA quotes several markets of one deployment.
+// =============================================================================
+fn process_set_market(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 33 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    let slab = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    check_authority(authority, &ctx_data)?;
+    if !is_shared(&ctx_data) {
+        msg!("ERROR: No market table; init a {}-byte context", CTX_SHARED_LEN);
+        return Err(MatcherError::MarketTableFull.into());
+    }
+    check_bound_slab(slab, &ctx_data)?;
+
+    let max_fill = read_u128(data, 1);
+    let max_inventory = read_u128(data, 17);
+    let index = match market_index(&ctx_data, slab.key) {
+        Some(index) => index,
+        None => {
+            let free = MarketEntry::table(&ctx_data)[..market_slots(&ctx_data)]
+                .iter()
+                .position(|market| market.slab() == Pubkey::default());
+            let Some(index) = free else {
+                msg!("ERROR: All {} markets taken", market_slots(&ctx_data));
+                return Err(MatcherError::MarketTableFull.into());
+            };
+            let market = &mut MarketEntry::table_mut(&mut ctx_data)[index];
+            *market = MarketEntry::zeroed();
+            market.slab = slab.key.to_bytes();
+            // The first market listed pins the percolator, as Init does
+            let ext = ContextExtension::view_mut(&mut ctx_data);
+            if ext.percolator_program() == Pubkey::default() {
+                ext.percolator_program = slab.owner.to_bytes();
+            }
+            index
+        }
+    };
+    let market = &mut MarketEntry::table_mut(&mut ctx_data)[index];
+    market.max_fill.set(max_fill);
+    market.max_inventory.set(max_inventory);
+
+    msg!("credibility-set-market: index={} slab={} max_fill={} max_inventory={}", index, slab.key, max_fill, max_inventory);
+
+    Ok(())
+}
+
+/// Shared body of the authority-gated `[tag, 0|1]` flag toggles. Returns the new state.
+fn set_ctx_flag(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+    flag: u8,
+) -> Result<bool, ProgramError> {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 2 || data[1] > 1 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..2])?;
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    check_authority(authority, &ctx_data)?;
+
+    let on = data[1] == 1;
+    let context = MatcherContext::view_mut(&mut ctx_data);
+    context.flags = if on { context.flags | flag } else { context.flags & !flag };
+
+    Ok(on)
+}
+
+// =============================================================================
+// Migrate Instruction (tag 0x07)
+//
+// Upgrades a v5 (320-byte) context to v6 in place: grows the account to
+// CTX_EXT_LEN, zero-fills the extension and bumps the version. Every v5 field
+// keeps its offset, so inventory, snapshots and parameters carry over as-is and
+// the new extension signals start disabled. The payer tops up rent.
+//
+// Permissionless: the result prices exactly like the v5 context until the LP
+// opts into extension features via UpdateParams.
+// =============================================================================
+fn process_migrate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let ctx_account = &accounts[0];
+    let payer = &accounts[1];
+    let system_program = &accounts[2];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..3])?;
+
+    {
+        let ctx_data = ctx_account.try_borrow_data()?;
+        if ctx_data.len() < CTX_LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        if MatcherContext::view(&ctx_data).magic.get() != MAGIC {
+            msg!("ERROR: Context not initialized");
+            return Err(MatcherError::NotInitialized.into());
+        }
+        let version = MatcherContext::view(&ctx_data).version.get();
+        if version != VERSION_V5 {
+            msg!("ERROR: Nothing to migrate from version {}", version);
+            return Err(MatcherError::NothingToMigrate.into());
+        }
+    }
+
+    let required = Rent::get()?.minimum_balance(CTX_EXT_LEN);
+    let shortfall = required.saturating_sub(ctx_account.lamports());
+    if shortfall > 0 {
+        if !payer.is_signer {
+            msg!("ERROR: Payer must sign to fund {} lamports of rent", shortfall);
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        invoke(
+            &system_instruction::transfer(payer.key, ctx_account.key, shortfall),
+            &[payer.clone(), ctx_account.clone(), system_program.clone()],
+        )?;
+    }
+
+    ctx_account.resize(CTX_EXT_LEN)?;
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    *ContextExtension::view_mut(&mut ctx_data) = ContextExtension::zeroed();
+    MatcherContext::view_mut(&mut ctx_data).version.set(VERSION);
+
+    msg!(
+        "credibility-migrate: v{} -> v{} size={} rent_topup={}",
+        VERSION_V5, VERSION, CTX_EXT_LEN, shortfall
+    );
+
+    Ok(())
+}
+
+// =============================================================================
+// QuotePreview Instruction (tag 0x08)
+//
+// Runs the full match pricing path against the current context and returns
+// the exec price with its spread breakdown via return data. Nothing is
+// written, so frontends can simulate it instead of re-implementing the math.
+// Rejects exactly where a match would (paused, oracle jump, inventory limit).
+// On a shared context an optional u16 after the size picks the market (0 when
+// absent); a single-market context ignores it.
+// =============================================================================
+fn process_quote_preview(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.is_empty() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < PREVIEW_CALL_LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let ctx_account = &accounts[0];
+    check_ctx_account(program_id, ctx_account)?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_quotable(&ctx_data)?;
+    // Then a scheduled context's schedule, the optional bound stats account,
+    // for the TWAP, and signals
+    check_distinct(&accounts[..accounts.len().min(3 + MAX_SIGNAL_ACCOUNTS)])?;
+    let (schedule_account, rest) = split_schedule(program_id, ctx_account.key, &accounts[1..], &ctx_data)?;
+    let (stats_account, signal_accounts) = stats_and_signals(program_id, rest, &ctx_data, schedule_account)?;
+    let stats_data = stats_account.map(|a| a.try_borrow_data()).transpose()?;
+
+    let oracle_price_e6 = oracle_e6(&ctx_data, u64::from_le_bytes(data[1..9].try_into().unwrap()));
+    let trade_size = i128::from_le_bytes(data[9..25].try_into().unwrap());
+    let book = book_at(&ctx_data, read_opt_u16(data, PREVIEW_CALL_LEN))?;
+    let current_slot = Clock::get()?.slot;
+    let companions = Companions {
+        stats_data: stats_data.as_deref().map(|d| &d[..]),
+        signal_bps: signal_surcharge(signal_accounts, current_slot)?,
+        spread_mult_bps: spread_mult(schedule_account, current_slot)?,
+        fee_tiers: fee_tiers(schedule_account)?,
+        rebate_bps: schedule_rebate(schedule_account)?,
+        fee_discount_bps: schedule_fee_discount(schedule_account)?,
+        min_notional_e6: schedule_min_notional(schedule_account)?,
+        inventory_halflife: schedule_inventory_halflife(schedule_account)?,
+        inventory_band: schedule_inventory_band(schedule_account)?,
+        inventory_caps: schedule_inventory_caps(schedule_account)?,
+        reduce_only_bps: schedule_reduce_only_bps(schedule_account)?,
+        max_inventory_bps: schedule_max_inventory_bps(schedule_account)?,
+        loyalty_bps: 0,
+        oracle_bps: 0,
+    };
+
+    let q = quote(&ctx_data, &book, &companions, oracle_price_e6, trade_size, current_slot)?;
+
+    set_return_data(&breakdown(&q));
+
+    Ok(())
+}
+
+// =============================================================================
+// Propose Params Instruction (tag 0x09)
+//
+// Queues a parameter block (same wire form as UpdateParams) that CommitParams
+// can apply PARAMS_TIMELOCK_SLOTS later. A new proposal replaces the pending
+// one and restarts the clock; a bare `[0x09]` cancels it. v6 only — the
+// proposal lives in the extension.
+// =============================================================================
+fn process_propose_params(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() != 1 && data.len() < 1 + PARAMS_LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let ctx_account = &accounts[1];
+    check_ctx_account(program_id, ctx_account)?;
+    check_distinct(&accounts[..2])?;
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    check_authority(authority, &ctx_data)?;
+    if !is_extended(&ctx_data) {
+        msg!("ERROR: Timelocked params need a v6 context; Migrate first");
+        return Err(MatcherError::ExtensionRequired.into());
+    }
+
+    if data.len() == 1 {
+        let was = ContextExtension::view(&ctx_data).pending_activation_slot.get();
+        ContextExtension::view_mut(&mut ctx_data).pending_activation_slot.set(0);
+        write_pending_params(&mut ctx_data, &[0u8; PARAMS_MAX_LEN]);
+        msg!("credibility-propose-params: cancelled (was due at slot {})", was);
+        return Ok(());
+    }
+
+    let new = Params::unpack(&data[1..]);
+    new.validate(MatcherContext::view(&ctx_data).kind, true)?;
+
+    let activation_slot = Clock::get()?.slot.saturating_add(PARAMS_TIMELOCK_SLOTS);
+    ContextExtension::view_mut(&mut ctx_data).pending_activation_slot.set(activation_slot);
+    write_pending_params(&mut ctx_data, &new.pack());
+
+    msg!("credibility-propose-params: activation_slot={}", activation_slot);
+    Params::load(&ctx_data).log_changes(&new);
+
+    Ok(())
+}
+
+// =============================================================================
+// Commit Params Instruction (tag 0x0A)
+//
+// Applies the pending proposal once its activation slot is reached. Until then
+// matches keep pricing with the current parameters. Permissionless: the LP's
+// intent was fixed at proposal time, so anyone may execute it.
+// =============================================================================
+fn process_commit_params(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    if accounts.is_empty() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let ctx_account = &accounts[0];
+    check_ctx_account(program_id, ctx_account)?;
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    if ctx_data.len() < CTX_LEN {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    if MatcherContext::view(&ctx_data).magic.get() != MAGIC {
+        msg!("ERROR: Context not initialized");
+        return Err(MatcherError::NotInitialized.into());
+    }
+    let activation_slot = if is_extended(&ctx_data) {
+        ContextExtension::view(&ctx_data).pending_activation_slot.get()
+    } else {
+        0
+    };
+    if activation_slot == 0 {
+        msg!("ERROR: No pending parameter proposal");
+        return Err(MatcherError::NoPendingProposal.into());
+    }
+    let current_slot = Clock::get()?.slot;
+    if current_slot < activation_slot {
+        msg!("ERROR: Proposal is timelocked until slot {} (now {})", activation_slot, current_slot);
+        return Err(MatcherError::TimelockActive.into());
+    }
+
+    let new = Params::unpack(&read_pending_params(&ctx_data));
+    Params::load(&ctx_data).log_changes(&new);
+    new.store(&mut ctx_data);
+    ContextExtension::view_mut(&mut ctx_data).pending_activation_slot.set(0);
+    write_pending_params(&mut ctx_data, &[0u8; PARAMS_MAX_LEN]);
+
+    msg!("credibility-commit-params: applied at slot {}", current_slot);
+
+    Ok(())
+}
+
+/// Reassemble the pending proposal from its head and tail slots.
+fn read_pending_params(ctx: &[u8]) -> [u8; PARAMS_MAX_LEN] {
+    let ext = ContextExtension::view(ctx);
+    let mut block = [0u8; PARAMS_MAX_LEN];
+    block[..PENDING_HEAD_LEN].copy_from_slice(&ext.pending_params);
+    block[PENDING_HEAD_LEN..].copy_from_slice(&ext.pending_params_tail[..PARAMS_MAX_LEN - PENDING_HEAD_LEN]);
+    block
+}
+
+/// Store a wire parameter block (or zeros to clear) across the head and tail slots.
+fn write_pending_params(ctx: &mut [u8], block: &[u8; PARAMS_MAX_LEN]) {
+    let ext = ContextExtension::view_mut(ctx);
+    ext.pending_params.copy_from_slice(&block[..PENDING_HEAD_LEN]);
+    ext.pending_params_tail[..PARAMS_MAX_LEN - PENDING_HEAD_LEN].copy_from_slice(&block[PENDING_HEAD_LEN..]);
+}
+
+// =============================================================================
+// Parameters
+// =============================================================================
+
+/// Required prefix of the parameter block: base_fee .. insurance_weight.
+const PARAMS_LEN: usize = 72;
+
+/// Full parameter block including every optional trailing field.
+const PARAMS_MAX_LEN: usize = PARAMS_LEN + 64;
+
+/// Tunable parameters, as carried by Init (after the kind byte) and UpdateParams.
+///
+/// Fields past PARAMS_LEN are optional on the wire; older clients omit them and
+/// get zero, which is the v1 behavior for every one of them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Params {
+    base_fee_bps: u32,
+    min_spread_bps: u32,
+    max_spread_bps: u32,
+    imbalance_k_bps: u32,
+    liquidity_e6: u128,
+    max_fill: u128,
+    max_inventory: u128,
+    age_halflife: u32,
+    insurance_weight_bps: u32,
+    impact_mode: u8,
+    impact_k_bps: u32,
+    skew_k_bps: u32,
+    coverage_curve: u8,
+    curve_points: [u16; CURVE_POINTS],
+    liq_halflife: u32,
+    funding_horizon: u32,
+    oracle_jump_bps: u16,
+    oracle_jump_window: u32,
+    flow_k_bps: u32,
+    twap_window_slots: u32,
+    signal_weights: [u16; SIGNALS],
+    deficit_halflife: u32,
+    liq_auction_bps: u16,
+    liq_auction_slots: u32,
+    peg_band_e6: u32,
+    spot_weight_bps: u16,
+}
+
+impl Params {
+    /// Parse a parameter block. Caller guarantees `data.len() >= PARAMS_LEN`.
+    fn unpack(data: &[u8]) -> Self {
+        let mut off = 0;
+        let base_fee_bps = read_u32(data, off); off += 4;
+        let min_spread_bps = read_u32(data, off); off += 4;
+        let max_spread_bps = read_u32(data, off); off += 4;
+        let imbalance_k_bps = read_u32(data, off); off += 4;
+        let liquidity_e6 = read_u128(data, off); off += 16;
+        let max_fill = read_u128(data, off); off += 16;
+        let max_inventory = read_u128(data, off); off += 16;
+        let age_halflife = read_u32(data, off); off += 4;
+        let insurance_weight_bps = read_u32(data, off); off += 4;
+
+        // Optional trailing fields
+        let impact_mode = data.get(off).copied().unwrap_or(IMPACT_NONE); off += 1;
+        let impact_k_bps = read_opt_u32(data, off); off += 4;
+        let skew_k_bps = read_opt_u32(data, off); off += 4;
+        let coverage_curve = data.get(off).copied().unwrap_or(CURVE_LINEAR); off += 1;
+        let mut curve_points = [0u16; CURVE_POINTS];
+        for point in curve_points.iter_mut() {
+            *point = read_opt_u16(data, off); off += 2;
+        }
+        let liq_halflife = read_opt_u32(data, off); off += 4;
+        let funding_horizon = read_opt_u32(data, off); off += 4;
+        let oracle_jump_bps = read_opt_u16(data, off); off += 2;
+        let oracle_jump_window = read_opt_u32(data, off); off += 4;
+        let flow_k_bps = read_opt_u32(data, off); off += 4;
+        let twap_window_slots = read_opt_u32(data, off); off += 4;
+        let mut signal_weights = [0u16; SIGNALS];
+        for weight in signal_weights.iter_mut() {
+            *weight = read_opt_u16(data, off); off += 2;
+        }
+        let deficit_halflife = read_opt_u32(data, off); off += 4;
+        let liq_auction_bps = read_opt_u16(data, off); off += 2;
+        let liq_auction_slots = read_opt_u32(data, off); off += 4;
+        let peg_band_e6 = read_opt_u32(data, off); off += 4;
+        let spot_weight_bps = read_opt_u16(data, off);
+
+        Self {
+            base_fee_bps,
+            min_spread_bps,
+            max_spread_bps,
+            imbalance_k_bps,
+            liquidity_e6,
+            max_fill,
+            max_inventory,
+            age_halflife,
+            insurance_weight_bps,
+            impact_mode,
+            impact_k_bps,
+            skew_k_bps,
+            coverage_curve,
+            curve_points,
+            liq_halflife,
+            funding_horizon,
+            oracle_jump_bps,
+            oracle_jump_window,
+            flow_k_bps,
+            twap_window_slots,
+            signal_weights,
+            deficit_halflife,
+            liq_auction_bps,
+            liq_auction_slots,
+            peg_band_e6,
+            spot_weight_bps,
+        }
+    }
+
+    /// Reject parameter sets that would quote nonsense for a context of
+    /// `kind`. Shared by Init, UpdateParams and ProposeParams.
+    fn validate(&self, kind: u8, extended: bool) -> ProgramResult {
+        if self.min_spread_bps > self.max_spread_bps {
+            msg!("ERROR: min_spread {} > max_spread {}", self.min_spread_bps, self.max_spread_bps);
+            return Err(MatcherError::SpreadOrder.into());
+        }
+        if self.max_spread_bps as u64 > BPS {
+            msg!("ERROR: max_spread {} > 10000 bps", self.max_spread_bps);
+            return Err(MatcherError::SpreadTooWide.into());
+        }
+        if self.base_fee_bps as u64 > BPS {
+            msg!("ERROR: base_fee {} > 10000 bps", self.base_fee_bps);
+            return Err(MatcherError::FeeTooHigh.into());
+        }
+        // A band wider than the peg itself quotes over 10000 bps
+        if self.peg_band_e6 as u64 > PEG_PRICE_E6 {
+            msg!("ERROR: peg_band {} > {} (the peg)", self.peg_band_e6, PEG_PRICE_E6);
+            return Err(MatcherError::SpreadTooWide.into());
+        }
+        if self.insurance_weight_bps as u64 > BPS {
+            msg!("ERROR: insurance_weight {} > 10000 bps", self.insurance_weight_bps);
+            return Err(MatcherError::InsuranceWeightTooHigh.into());
+        }
+        // Every depth-scaled term divides by liquidity and silently drops out
+        // at zero; a nonzero coefficient there is a misconfiguration
+        if self.liquidity_e6 == 0
+            && (self.imbalance_k_bps != 0 || self.impact_k_bps != 0 || self.skew_k_bps != 0)
+        {
+            msg!("ERROR: imbalance_k/impact_k/skew_k need a nonzero liquidity_notional_e6");
+            return Err(MatcherError::LiquidityRequired.into());
+        }
+        if self.impact_mode > IMPACT_SQRT {
+            msg!("ERROR: Unknown impact_mode {}", self.impact_mode);
+            return Err(MatcherError::UnknownImpactMode.into());
+        }
+        if self.coverage_curve > CURVE_PIECEWISE {
+            msg!("ERROR: Unknown coverage_curve {}", self.coverage_curve);
+            return Err(MatcherError::UnknownCoverageCurve.into());
+        }
+        if self.coverage_curve == CURVE_PIECEWISE
+            && (self.curve_points.windows(2).any(|w| w[0] > w[1])
+                || self.curve_points[CURVE_POINTS - 1] as u64 > BPS)
+        {
+            msg!("ERROR: Curve points must be non-decreasing and <= 10000");
+            return Err(MatcherError::InvalidCurvePoints.into());
+        }
+        if !extended && self.funding_horizon != 0 {
+            msg!("ERROR: Funding skew needs an extended context ({} bytes)", CTX_EXT_LEN);
+            return Err(MatcherError::ExtensionRequired.into());
+        }
+        // A threshold with no window would never reset: the last price only
+        // moves on a fill, and the breaker refuses every fill
+        if (self.oracle_jump_bps == 0) != (self.oracle_jump_window == 0) {
+            msg!("ERROR: oracle_jump_bps and oracle_jump_window_slots are set together");
+            return Err(MatcherError::InvalidOracleBreaker.into());
+        }
+        if !extended && self.oracle_jump_bps != 0 {
+            msg!("ERROR: Oracle circuit breaker needs an extended context ({} bytes)", CTX_EXT_LEN);
+            return Err(MatcherError::ExtensionRequired.into());
+        }
+        if !extended && self.flow_k_bps != 0 {
+            msg!("ERROR: Flow penalty needs an extended context ({} bytes)", CTX_EXT_LEN);
+            return Err(MatcherError::ExtensionRequired.into());
+        }
+        if !extended && self.twap_window_slots != 0 {
+            msg!("ERROR: TWAP anchoring needs an extended context ({} bytes)", CTX_EXT_LEN);
+            return Err(MatcherError::ExtensionRequired.into());
+        }
+        if self.spot_weight_bps as u64 > BPS {
+            msg!("ERROR: spot_weight {} > 10000 bps", self.spot_weight_bps);
+            return Err(MatcherError::SpotWeightTooHigh.into());
+        }
+        if !extended && self.spot_weight_bps != 0 {
+            msg!("ERROR: TWAP anchoring needs an extended context ({} bytes)", CTX_EXT_LEN);
+            return Err(MatcherError::ExtensionRequired.into());
+        }
+        // A premium with no auction to run it in, or an auction with nothing
+        // to charge, is a half-finished setting
+        if (self.liq_auction_bps == 0) != (self.liq_auction_slots == 0) {
+            msg!("ERROR: liq_auction_bps and liq_auction_slots are set together");
+            return Err(MatcherError::InvalidLiqAuction.into());
+        }
+        if !extended && self.liq_auction_slots != 0 {
+            msg!("ERROR: Liquidation auctions need an extended context ({} bytes)", CTX_EXT_LEN);
+            return Err(MatcherError::ExtensionRequired.into());
+        }
+        let weighted = self.signal_weights.iter().any(|&w| w != 0);
+        if !extended && (weighted || self.deficit_halflife != 0) {
+            msg!("ERROR: Signal weights need an extended context ({} bytes)", CTX_EXT_LEN);
+            return Err(MatcherError::ExtensionRequired.into());
+        }
+        let total: u64 = self.signal_weights.iter().map(|&w| w as u64).sum();
+        if (weighted || kind == KIND_MULTI) && total != BPS {
+            msg!("ERROR: Signal weights {:?} sum to {}, not 10000", self.signal_weights, total);
+            return Err(MatcherError::InvalidSignalWeights.into());
+        }
+        // A weighted signal with no halflife can never earn credit
+        let halflives = [
+            (SIGNAL_AGE, self.age_halflife),
+            (SIGNAL_DEFICIT, self.deficit_halflife),
+            (SIGNAL_LIQUIDATIONS, self.liq_halflife),
+        ];
+        for (signal, halflife) in halflives {
+            if self.signal_weights[signal] != 0 && halflife == 0 {
+                msg!("ERROR: The {} signal is weighted but has no halflife", SIGNAL_NAMES[signal]);
+                return Err(MatcherError::InvalidSignalWeights.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Encode as a full-length parameter block; `unpack` round-trips it.
+    fn pack(&self) -> [u8; PARAMS_MAX_LEN] {
+        let mut data = [0u8; PARAMS_MAX_LEN];
+        let mut off = 0;
+        write_u32(&mut data, off, self.base_fee_bps); off += 4;
+        write_u32(&mut data, off, self.min_spread_bps); off += 4;
+        write_u32(&mut data, off, self.max_spread_bps); off += 4;
+        write_u32(&mut data, off, self.imbalance_k_bps); off += 4;
+        write_u128(&mut data, off, self.liquidity_e6); off += 16;
+        write_u128(&mut data, off, self.max_fill); off += 16;
+        write_u128(&mut data, off, self.max_inventory); off += 16;
+        write_u32(&mut data, off, self.age_halflife); off += 4;
+        write_u32(&mut data, off, self.insurance_weight_bps); off += 4;
+        data[off] = self.impact_mode; off += 1;
+        write_u32(&mut data, off, self.impact_k_bps); off += 4;
+        write_u32(&mut data, off, self.skew_k_bps); off += 4;
+        data[off] = self.coverage_curve; off += 1;
+        for point in self.curve_points {
+            write_u16(&mut data, off, point); off += 2;
+        }
+        write_u32(&mut data, off, self.liq_halflife); off += 4;
+        write_u32(&mut data, off, self.funding_horizon); off += 4;
+        write_u16(&mut data, off, self.oracle_jump_bps); off += 2;
+        write_u32(&mut data, off, self.oracle_jump_window); off += 4;
+        write_u32(&mut data, off, self.flow_k_bps); off += 4;
+        write_u32(&mut data, off, self.twap_window_slots); off += 4;
+        for weight in self.signal_weights {
+            write_u16(&mut data, off, weight); off += 2;
+        }
+        write_u32(&mut data, off, self.deficit_halflife); off += 4;
+        write_u16(&mut data, off, self.liq_auction_bps); off += 2;
+        write_u32(&mut data, off, self.liq_auction_slots); off += 4;
+        write_u32(&mut data, off, self.peg_band_e6); off += 4;
+        write_u16(&mut data, off, self.spot_weight_bps);
+        data
+    }
+
+    fn load(ctx: &[u8]) -> Self {
+        let ext = is_extended(ctx).then(|| ContextExtension::view(ctx));
+        let context = MatcherContext::view(ctx);
+        Self {
+            base_fee_bps: context.base_fee_bps.get(),
+            min_spread_bps: context.min_spread_bps.get(),
+            max_spread_bps: context.max_spread_bps.get(),
+            imbalance_k_bps: context.imbalance_k_bps.get(),
+            liquidity_e6: context.liquidity_notional_e6.get(),
+            max_fill: context.max_fill_abs.get(),
+            max_inventory: context.max_inventory_abs.get(),
+            age_halflife: context.age_halflife_slots.get(),
+            insurance_weight_bps: context.insurance_weight_bps.get(),
+            impact_mode: context.impact_mode,
+            impact_k_bps: context.impact_k_bps.get(),
+            skew_k_bps: context.skew_k_bps.get(),
+            coverage_curve: context.coverage_curve,
+            curve_points: context.curve_points.map(|point| point.get()),
+            liq_halflife: context.liq_halflife_slots.get(),
+            peg_band_e6: context.peg_band_e6.get(),
+            spot_weight_bps: context.spot_weight_bps.get(),
+            funding_horizon: ext.map_or(0, |ext| ext.funding_horizon_slots.get()),
+            oracle_jump_bps: ext.map_or(0, |ext| ext.oracle_jump_bps.get()),
+            oracle_jump_window: ext.map_or(0, |ext| ext.oracle_jump_window_slots.get()),
+            flow_k_bps: ext.map_or(0, |ext| ext.flow_k_bps.get()),
+            twap_window_slots: ext.map_or(0, |ext| ext.twap_window_slots.get()),
+            signal_weights: ext.map_or([0; SIGNALS], |ext| ext.signal_weights.map(|weight| weight.get())),
+            deficit_halflife: ext.map_or(0, |ext| ext.deficit_halflife_slots.get()),
+            liq_auction_bps: ext.map_or(0, |ext| ext.liq_auction_bps.get()),
+            liq_auction_slots: ext.map_or(0, |ext| ext.liq_auction_slots.get()),
+        }
+    }
+
+    /// Write into a context. Extension fields are only written to v6 accounts;
+    /// `validate` has already rejected nonzero values for v5.
+    fn store(&self, ctx: &mut [u8]) {
+        let context = MatcherContext::view_mut(ctx);
+        context.base_fee_bps.set(self.base_fee_bps);
+        context.min_spread_bps.set(self.min_spread_bps);
+        context.max_spread_bps.set(self.max_spread_bps);
+        context.imbalance_k_bps.set(self.imbalance_k_bps);
+        context.liquidity_notional_e6.set(self.liquidity_e6);
+        context.max_fill_abs.set(self.max_fill);
+        context.max_inventory_abs.set(self.max_inventory);
+        context.age_halflife_slots.set(self.age_halflife);
+        context.insurance_weight_bps.set(self.insurance_weight_bps);
+        context.impact_mode = self.impact_mode;
+        context.impact_k_bps.set(self.impact_k_bps);
+        context.skew_k_bps.set(self.skew_k_bps);
+        context.coverage_curve = self.coverage_curve;
+        for (i, point) in self.curve_points.iter().enumerate() {
+            context.curve_points[i].set(*point);
+        }
+        context.liq_halflife_slots.set(self.liq_halflife);
+        context.peg_band_e6.set(self.peg_band_e6);
+        context.spot_weight_bps.set(self.spot_weight_bps);
+        if ctx.len() >= CTX_EXT_LEN {
+            let ext = ContextExtension::view_mut(ctx);
+            ext.funding_horizon_slots.set(self.funding_horizon);
+            ext.oracle_jump_bps.set(self.oracle_jump_bps);
+            ext.oracle_jump_window_slots.set(self.oracle_jump_window);
+            ext.flow_k_bps.set(self.flow_k_bps);
+            ext.twap_window_slots.set(self.twap_window_slots);
+            for (i, weight) in self.signal_weights.iter().enumerate() {
+                ext.signal_weights[i].set(*weight);
+            }
+            ext.deficit_halflife_slots.set(self.deficit_halflife);
+            ext.liq_auction_bps.set(self.liq_auction_bps);
+            ext.liq_auction_slots.set(self.liq_auction_slots);
+        }
+    }
+
+    /// The subset the pricing path reads.
+    fn pricing(&self) -> pricing::Params {
+        pricing::Params {
+            base_fee_bps: self.base_fee_bps,
+            min_spread_bps: self.min_spread_bps,
+            max_spread_bps: self.max_spread_bps,
+            imbalance_k_bps: self.imbalance_k_bps,
+            liquidity_e6: self.liquidity_e6,
+            max_fill: self.max_fill,
+            max_inventory: self.max_inventory,
+            insurance_weight_bps: self.insurance_weight_bps,
+            impact_mode: self.impact_mode,
+            impact_k_bps: self.impact_k_bps,
+            skew_k_bps: self.skew_k_bps,
+            coverage_curve: self.coverage_curve,
+            curve_points: self.curve_points,
+            liq_halflife: self.liq_halflife,
+            funding_horizon: self.funding_horizon,
+            oracle_jump_bps: self.oracle_jump_bps,
+            oracle_jump_window: self.oracle_jump_window,
+            flow_k_bps: self.flow_k_bps,
+            twap_window_slots: self.twap_window_slots,
+            age_halflife: self.age_halflife,
+            signal_weights: self.signal_weights,
+            deficit_halflife: self.deficit_halflife,
+            liq_auction_bps: self.liq_auction_bps,
+            liq_auction_slots: self.liq_auction_slots,
+            peg_band_e6: self.peg_band_e6,
+            spot_weight_bps: self.spot_weight_bps,
+        }
+    }
+
+    /// Log every field that differs between `self` (old) and `new`.
+    fn log_changes(&self, new: &Params) {
+        fn field(name: &str, old: u128, new: u128) {
+            if old != new {
+                msg!("  {}: {} -> {}", name, old, new);
+            }
+        }
+        field("base_fee_bps", self.base_fee_bps as u128, new.base_fee_bps as u128);
+        field("min_spread_bps", self.min_spread_bps as u128, new.min_spread_bps as u128);
+        field("max_spread_bps", self.max_spread_bps as u128, new.max_spread_bps as u128);
+        field("imbalance_k_bps", self.imbalance_k_bps as u128, new.imbalance_k_bps as u128);
+        field("liquidity_notional_e6", self.liquidity_e6, new.liquidity_e6);
+        field("max_fill_abs", self.max_fill, new.max_fill);
+        field("max_inventory_abs", self.max_inventory, new.max_inventory);
+        field("age_halflife_slots", self.age_halflife as u128, new.age_halflife as u128);
+        field("insurance_weight_bps", self.insurance_weight_bps as u128, new.insurance_weight_bps as u128);
+        field("impact_mode", self.impact_mode as u128, new.impact_mode as u128);
+        field("impact_k_bps", self.impact_k_bps as u128, new.impact_k_bps as u128);
+        field("skew_k_bps", self.skew_k_bps as u128, new.skew_k_bps as u128);
+        field("coverage_curve", self.coverage_curve as u128, new.coverage_curve as u128);
+        if self.curve_points != new.curve_points {
+            msg!("  curve_points: {:?} -> {:?}", self.curve_points, new.curve_points);
+        }
+        field("liq_halflife_slots", self.liq_halflife as u128, new.liq_halflife as u128);
+        field("funding_horizon_slots", self.funding_horizon as u128, new.funding_horizon as u128);
+        field("oracle_jump_bps", self.oracle_jump_bps as u128, new.oracle_jump_bps as u128);
+        field("oracle_jump_window_slots", self.oracle_jump_window as u128, new.oracle_jump_window as u128);
+        field("flow_k_bps", self.flow_k_bps as u128, new.flow_k_bps as u128);
+        field("twap_window_slots", self.twap_window_slots as u128, new.twap_window_slots as u128);
+        if self.signal_weights != new.signal_weights {
+            msg!("  signal_weights: {:?} -> {:?}", self.signal_weights, new.signal_weights);
+        }
+        field("deficit_halflife_slots", self.deficit_halflife as u128, new.deficit_halflife as u128);
+        field("liq_auction_bps", self.liq_auction_bps as u128, new.liq_auction_bps as u128);
+        field("liq_auction_slots", self.liq_auction_slots as u128, new.liq_auction_slots as u128);
+        field("peg_band_e6", self.peg_band_e6 as u128, new.peg_band_e6 as u128);
+        field("spot_weight_bps", self.spot_weight_bps as u128, new.spot_weight_bps as u128);
+    }
+}
+
+// =============================================================================
+// Helpers
+// =============================================================================
+
+/// Reject a context account this program doesn't own. Without this, an account
+/// owned by any other program could be shaped to pass every layout check.
+fn check_ctx_owner(program_id: &Pubkey, ctx_account: &AccountInfo) -> ProgramResult {
+    if ctx_account.owner != program_id {
+        msg!("ERROR: Context not owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// The owner check for a context. One CreateContext made must also still sit
+/// at the address its LP PDA and slab (market 0's, if shared) derive, so there
+/// is only ever one live context per pair.
+fn check_ctx_account(program_id: &Pubkey, ctx_account: &AccountInfo) -> ProgramResult {
+    check_ctx_owner(program_id, ctx_account)?;
+    let ctx_data = ctx_account.try_borrow_data()?;
+    if !is_derived(&ctx_data) {
+        return Ok(());
+    }
+    let context = MatcherContext::view(&ctx_data);
+    if context.magic.get() != MAGIC {
+        return Ok(());
+    }
+    check_ctx_address(program_id, ctx_account.key, &context.lp_pda(), &book_slab(&ctx_data, 0), context.bump)
+}
+
+fn check_ctx_address(program_id: &Pubkey, ctx: &Pubkey, lp_pda: &Pubkey, slab: &Pubkey, bump: u8) -> ProgramResult {
+    match Pubkey::create_program_address(&[CONTEXT_SEED, lp_pda.as_ref(), slab.as_ref(), &[bump]], program_id) {
+        Ok(address) if address == *ctx => Ok(()),
+        _ => {
+            msg!("ERROR: Context is not at the address of LP PDA {} and slab {}", lp_pda, slab);
+            Err(MatcherError::ContextAddressMismatch.into())
+        }
+    }
+}
+
+/// Checks QuotePreview and QuoteTwoSided make before pricing: a live,
+/// unpaused context. Match makes the same ones alongside its signer checks.
+fn check_quotable(ctx_data: &[u8]) -> ProgramResult {
+    if ctx_data.len() < CTX_LEN {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    if MatcherContext::view(ctx_data).magic.get() != MAGIC {
+        msg!("ERROR: Invalid context magic");
+        return Err(MatcherError::NotInitialized.into());
+    }
+    if MatcherContext::view(ctx_data).has_flag(CTX_FLAG_PAUSED) {
+        msg!("REJECT: matcher is paused");
+        return Err(MatcherError::Paused.into());
+    }
+    Ok(())
+}
+
+/// The caller's oracle price in e6: divided down from the context's
+/// oracle_decimals, rounding down, or as passed at 0 or 6.
+fn oracle_e6(ctx_data: &[u8], price: u64) -> u64 {
+    match MatcherContext::view(ctx_data).oracle_decimals {
+        decimals @ ORACLE_DECIMALS_E6..=MAX_ORACLE_DECIMALS => {
+            price / 10u64.pow((decimals - ORACLE_DECIMALS_E6) as u32)
+        }
+        _ => price,
+    }
+}
+
+/// Emit a Match result the standard way, via sol_set_return_data. The
+/// MatcherReturn part also goes to the front of the context account for
+/// callers that still read it back, unless CTX_FLAG_RETURN_DATA_ONLY is set.
+fn emit_matcher_return(ctx_data: &mut [u8], ret: &[u8; MATCH_RET_LEN]) {
+    set_return_data(ret);
+    if !MatcherContext::view(ctx_data).has_flag(CTX_FLAG_RETURN_DATA_ONLY) {
+        ctx_data[..RET_LEN].copy_from_slice(&ret[..RET_LEN]);
+    }
+}
+
+/// Match failures soft-reject mode turns into a zero fill: the request is
+/// well-formed, this LP just can't take it on its current inventory, or isn't
+/// quoting that side at all.
+fn soft_reject_reason(err: &ProgramError) -> Option<MatcherError> {
+    let ProgramError::Custom(code) = err else {
+        return None;
+    };
+    MatcherError::from_code(*code).filter(|e| {
+        matches!(
+            e,
+            MatcherError::ReduceOnly
+                | MatcherError::InventoryLimit
+                | MatcherError::InventoryOverflow
+                | MatcherError::SideDisabled
+                | MatcherError::BelowMinNotional
+        )
+    })
+}
+
+/// Reject accounts that alias one another. The runtime hands duplicates the
+/// same underlying data, so an LP PDA or slab that is also the context would
+/// see the matcher's writes interleave with its own.
+fn check_distinct(accounts: &[AccountInfo]) -> ProgramResult {
+    for (i, a) in accounts.iter().enumerate() {
+        if accounts[..i].iter().any(|b| b.key == a.key) {
+            msg!("ERROR: Account {} passed more than once", a.key);
+            return Err(MatcherError::DuplicateAccount.into());
+        }
+    }
+    Ok(())
+}
+
+/// Verify `authority` signed and controls this initialized context: the
+/// rotated authority if one has been accepted, otherwise the LP PDA.
+fn check_authority(authority: &AccountInfo, ctx_data: &[u8]) -> ProgramResult {
+    if !authority.is_signer {
+        msg!("ERROR: Authority must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ctx_data.len() < CTX_LEN {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    let magic = MatcherContext::view(ctx_data).magic.get();
+    if magic != MAGIC {
+        msg!("ERROR: Context not initialized");
+        return Err(MatcherError::NotInitialized.into());
+    }
+    if *authority.key != current_authority(ctx_data) {
+        msg!("ERROR: Authority mismatch");
+        return Err(MatcherError::AuthorityMismatch.into());
+    }
+    Ok(())
+}
+
+/// The key that may administer the context: the stored authority on v6 once
+/// set, the LP PDA otherwise.
+fn current_authority(ctx_data: &[u8]) -> Pubkey {
+    if is_extended(ctx_data) {
+        let stored = ContextExtension::view(ctx_data).authority();
+        if stored != Pubkey::default() {
+            return stored;
+        }
+    }
+    MatcherContext::view(ctx_data).lp_pda()
+}
+
+/// Reject a stats account other than the one InitStats bound to this context.
+fn check_bound_stats(program_id: &Pubkey, stats: &AccountInfo, ctx_data: &[u8]) -> ProgramResult {
+    if !is_extended(ctx_data) {
+        msg!("ERROR: A stats account needs a v6 context");
+        return Err(MatcherError::ExtensionRequired.into());
+    }
+    let bound = ContextExtension::view(ctx_data).stats();
+    if stats.owner != program_id || bound == Pubkey::default() || bound != *stats.key {
+        msg!("ERROR: Stats account mismatch");
+        return Err(MatcherError::StatsMismatch.into());
+    }
+    if stats.data_len() < STATS_LEN {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    Ok(())
+}
+
+fn check_bound_schedule(program_id: &Pubkey, schedule: &AccountInfo, ctx_key: &Pubkey) -> ProgramResult {
+    let data = schedule.try_borrow_data()?;
+    if schedule.owner != program_id
+        || data.len() < SCHEDULE_LEN
+        || read_u64(&data, SCHEDULE_MAGIC_OFF) != SCHEDULE_MAGIC
+        || read_pubkey(&data, SCHEDULE_CTX_OFF) != *ctx_key
+    {
+        msg!("ERROR: Schedule account mismatch");
+        return Err(MatcherError::ScheduleMismatch.into());
+    }
+    Ok(())
+}
+
+/// Reject a ladder account other than one InitLadder bound to this context.
+fn check_bound_ladder(program_id: &Pubkey, ladder: &AccountInfo, ctx_key: &Pubkey) -> ProgramResult {
+    let data = ladder.try_borrow_data()?;
+    if ladder.owner != program_id
+        || data.len() < LADDER_LEN
+        || read_u64(&data, LADDER_MAGIC_OFF) != LADDER_MAGIC
+        || read_pubkey(&data, LADDER_CTX_OFF) != *ctx_key
+    {
+        msg!("ERROR: Ladder account mismatch");
+        return Err(MatcherError::LadderMismatch.into());
+    }
+    Ok(())
+}
+
+/// Reject a tip jar other than one InitTipJar bound to this context.
+fn check_bound_tip_jar(program_id: &Pubkey, tip_jar: &AccountInfo, ctx_key: &Pubkey) -> ProgramResult {
+    let data = tip_jar.try_borrow_data()?;
+    if tip_jar.owner != program_id
+        || data.len() < TIP_JAR_LEN
+        || read_u64(&data, TIP_JAR_MAGIC_OFF) != TIP_JAR_MAGIC
+        || read_pubkey(&data, TIP_JAR_CTX_OFF) != *ctx_key
+    {
+        msg!("ERROR: Tip jar mismatch");
+        return Err(MatcherError::TipJarMismatch.into());
+    }
+    Ok(())
+}
+
+/// Reject a referral account other than one InitReferral bound to this
+/// context.
+fn check_bound_referral(program_id: &Pubkey, referral: &AccountInfo, ctx_key: &Pubkey) -> ProgramResult {
+    let data = referral.try_borrow_data()?;
+    if referral.owner != program_id
+        || data.len() < REFERRAL_LEN
+        || read_u64(&data, REFERRAL_MAGIC_OFF) != REFERRAL_MAGIC
+        || read_pubkey(&data, REFERRAL_CTX_OFF) != *ctx_key
+    {
+        msg!("ERROR: Referral account mismatch");
+        return Err(MatcherError::ReferralMismatch.into());
+    }
+    Ok(())
+}
+
+/// Reject a trader account InitTrader didn't bind to this context and to
+/// `trader`, and one whose trader didn't sign: a volume discount is the
+/// trader's own.
+fn check_bound_trader(program_id: &Pubkey, trader_account: &AccountInfo, trader: &AccountInfo, ctx_key: &Pubkey) -> ProgramResult {
+    let data = trader_account.try_borrow_data()?;
+    if trader_account.owner != program_id
+        || data.len() < TRADER_LEN
+        || read_u64(&data, TRADER_MAGIC_OFF) != TRADER_MAGIC
+        || read_pubkey(&data, TRADER_CTX_OFF) != *ctx_key
+        || read_pubkey(&data, TRADER_KEY_OFF) != *trader.key
+    {
+        msg!("ERROR: Trader account mismatch");
+        return Err(MatcherError::TraderMismatch.into());
+    }
+    if !trader.is_signer {
+        msg!("ERROR: Trader {} must sign", trader.key);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+/// Reject a feed account other than the one InitFeed bound to this
+/// context.
+fn check_bound_feed(program_id: &Pubkey, feed: &AccountInfo, ctx_key: &Pubkey) -> ProgramResult {
+    let data = feed.try_borrow_data()?;
+    if feed.owner != program_id
+        || data.len() < FEED_LEN
+        || read_u64(&data, FEED_MAGIC_OFF) != FEED_MAGIC
+        || read_pubkey(&data, FEED_CTX_OFF) != *ctx_key
+    {
+        msg!("ERROR: Feed account mismatch");
+        return Err(MatcherError::FeedMismatch.into());
+    }
+    Ok(())
+}
+
+/// Take a context's feed account off the front of the accounts
+/// after its schedule, where it has to be; other contexts take none.
+fn split_feed<'a, 'b>(
+    program_id: &Pubkey,
+    ctx_key: &Pubkey,
+    rest: &'a [AccountInfo<'b>],
+    ctx_data: &[u8],
+) -> Result<(Option<&'a AccountInfo<'b>>, &'a [AccountInfo<'b>]), ProgramError> {
+    if !MatcherContext::view(ctx_data).has_flag(CTX_FLAG_FEED) {
+        return Ok((None, rest));
+    }
+    let Some((feed, rest)) = rest.split_first() else {
+        msg!("ERROR: The context's feed account wasn't passed");
+        return Err(MatcherError::FeedMismatch.into());
+    };
+    check_bound_feed(program_id, feed, ctx_key)?;
+    Ok((Some(feed), rest))
+}
+
+/// What a price account says: the feed it is for, when it was published
+/// (unix seconds), the price as e6, None unless positive and in range, and
+/// how uncertain the publisher is of it, in bps of the price.
+struct FeedReading {
+    feed_id: [u8; 32],
+    published: i64,
+    slot: u64,
+    price_e6: Option<u64>,
+    conf_bps: u64,
+}
+
+/// Read a price account from `source`: a fully verified PriceUpdateV2 owned by
+/// one of PYTH_OWNERS, a pull feed owned by SWITCHBOARD_OWNER, or an OCR2
+/// feed owned by CHAINLINK_OWNER that has had a round.
+fn read_feed(source: u8, price_account: &AccountInfo) -> Result<FeedReading, ProgramError> {
+    let data = price_account.try_borrow_data()?;
+    match source {
+        FEED_SOURCE_PYTH
+            if PYTH_OWNERS.contains(price_account.owner)
+                && data.len() >= PYTH_MIN_LEN
+                && data[..8] == PYTH_DISCRIMINATOR
+                && data[PYTH_VERIFICATION_OFF] == PYTH_VERIFIED_FULL =>
+        {
+            Ok(FeedReading {
+                feed_id: data[PYTH_FEED_ID_OFF..PYTH_FEED_ID_OFF + 32].try_into().unwrap(),
+                published: read_i64(&data, PYTH_PUBLISH_TIME_OFF),
+                slot: read_u64(&data, PYTH_POSTED_SLOT_OFF),
+                price_e6: scale_e6(read_i64(&data, PYTH_PRICE_OFF), read_i32(&data, PYTH_EXPONENT_OFF)),
+                conf_bps: conf_bps(read_i64(&data, PYTH_PRICE_OFF), read_u64(&data, PYTH_CONF_OFF)),
+            })
+        }
+        FEED_SOURCE_SWITCHBOARD
+            if *price_account.owner == SWITCHBOARD_OWNER
+                && data.len() >= SWITCHBOARD_MIN_LEN
+                && data[..8] == SWITCHBOARD_DISCRIMINATOR =>
+        {
+            Ok(FeedReading {
+                feed_id: price_account.key.to_bytes(),
+                published: read_i64(&data, SWITCHBOARD_UPDATED_OFF),
+                slot: read_u64(&data, SWITCHBOARD_SLOT_OFF),
+                price_e6: u64::try_from(read_i128(&data, SWITCHBOARD_VALUE_OFF) / 1_000_000_000_000).ok().filter(|p| *p > 0),
+                conf_bps: 0,
+            })
+        }
+        FEED_SOURCE_CHAINLINK
+            if *price_account.owner == CHAINLINK_OWNER
+                && data.len() >= CHAINLINK_MIN_LEN
+                && data[..8] == CHAINLINK_DISCRIMINATOR =>
+        {
+            let Some(round) = chainlink_round(&data) else {
+                msg!("ERROR: Chainlink feed {} has no round yet", price_account.key);
+                return Err(MatcherError::InvalidFeedPrice.into());
+            };
+            let decimals = data[CHAINLINK_DECIMALS_OFF] as i32;
+            Ok(FeedReading {
+                feed_id: price_account.key.to_bytes(),
+                published: read_u32(round, CHAINLINK_TIMESTAMP_OFF) as i64,
+                slot: read_u64(round, CHAINLINK_SLOT_OFF),
+                price_e6: i64::try_from(read_i128(round, CHAINLINK_ANSWER_OFF)).ok().and_then(|answer| scale_e6(answer, -decimals)),
+                conf_bps: 0,
+            })
+        }
+        _ => {
+            let name = FEED_SOURCE_NAMES.get(source as usize).unwrap_or(&"unknown");
+            msg!("ERROR: {} is not a {} price account", price_account.key, name);
+            Err(MatcherError::InvalidFeedPrice.into())
+        }
+    }
+}
+
+/// A price account read as `source`, keyed by the account itself when
+/// `pinned` rather than by the feed it carries.
+fn read_bound_feed(source: u8, pinned: u8, price_account: &AccountInfo) -> Result<FeedReading, ProgramError> {
+    let mut reading = read_feed(source, price_account)?;
+    if pinned == 1 {
+        reading.feed_id = price_account.key.to_bytes();
+    }
+    Ok(reading)
+}
+
+/// A Chainlink feed's latest round: the transmission before the live ring's
+/// cursor, or None before the first round or past the account's end.
+fn chainlink_round(data: &[u8]) -> Option<&[u8]> {
+    let live_length = read_u32(data, CHAINLINK_LIVE_LENGTH_OFF) as usize;
+    if read_u32(data, CHAINLINK_ROUND_ID_OFF) == 0 || live_length == 0 {
+        return None;
+    }
+    let cursor = read_u32(data, CHAINLINK_LIVE_CURSOR_OFF) as usize % live_length;
+    let latest = (cursor + live_length - 1) % live_length;
+    data.get(CHAINLINK_HEADER_LEN + latest * CHAINLINK_TRANSMISSION_LEN..)?.get(..CHAINLINK_TRANSMISSION_LEN)
+}
+
+/// How many feeds a feed account holds, at most MAX_FEEDS.
+fn feed_count(feed_data: &[u8]) -> usize {
+    (feed_data[FEED_COUNT_OFF] as usize).min(MAX_FEEDS)
+}
+
+/// A feed account's entries in use, in the order they were bound.
+fn feed_entries(feed_data: &[u8]) -> impl Iterator<Item = &[u8]> {
+    feed_data[FEED_HEADER_LEN..FEED_LEN].chunks_exact(FEED_ENTRY_LEN).take(feed_count(feed_data))
+}
+
+fn write_feed_entry(feed_data: &mut [u8], index: usize, feed_id: &[u8; 32], source: u8, pinned: u8) {
+    let entry = &mut feed_data[FEED_HEADER_LEN + index * FEED_ENTRY_LEN..][..FEED_ENTRY_LEN];
+    entry[FEED_ENTRY_ID_OFF..FEED_ENTRY_ID_OFF + 32].copy_from_slice(feed_id);
+    entry[FEED_ENTRY_SOURCE_OFF] = source;
+    entry[FEED_ENTRY_PINNED_OFF] = pinned;
+}
+
+/// The median of the bound feeds' prices as e6 (or, set to fall back, the
+/// first good one's), and what their uncertainty and disagreement (or the
+/// fallback's penalty) add to the spread. `price_accounts` hold one price
+/// account per feed, in the order they were bound; each is checked against
+/// its feed's source and id, and its max age as of `clock`, in seconds and
+/// (if set) slots. A price from ahead of the clock counts as fresh.
+fn feed_price(feed: &AccountInfo, price_accounts: &[AccountInfo], clock: &Clock) -> Result<(u64, u64), ProgramError> {
+    let feed_data = feed.try_borrow_data()?;
+    let max_age = read_u32(&feed_data, FEED_MAX_AGE_OFF) as i64;
+    let max_age_slots = read_u16(&feed_data, FEED_MAX_AGE_SLOTS_OFF) as u64;
+    let count = feed_count(&feed_data);
+    let fallback = feed_data[FEED_FALLBACK_OFF] == 1;
+    let mut prices = [0u64; MAX_FEEDS];
+    let mut conf_bps = 0;
+    let mut slot_stale = false;
+    for (i, (entry, price_account)) in feed_entries(&feed_data).zip(price_accounts).enumerate() {
+        let source = entry[FEED_ENTRY_SOURCE_OFF];
+        let reading = read_bound_feed(source, entry[FEED_ENTRY_PINNED_OFF], price_account)?;
+        if reading.feed_id[..] != entry[FEED_ENTRY_ID_OFF..FEED_ENTRY_ID_OFF + 32] {
+            msg!("ERROR: Price account {} is for feed {}", i, Pubkey::new_from_array(reading.feed_id));
+            return Err(MatcherError::InvalidFeedPrice.into());
+        }
+        let age = clock.unix_timestamp.saturating_sub(reading.published);
+        let slots = clock.slot.saturating_sub(reading.slot);
+        let slot_widen = feed_data[FEED_SLOT_WIDEN_OFF] == 1;
+        let checked = if age > max_age {
+            msg!("REJECT: Price {} published at {}, {}s ago; max age {}s", i, reading.published, age, max_age);
+            Err(MatcherError::FeedPriceStale)
+        } else if max_age_slots != 0 && slots > max_age_slots && !slot_widen {
+            msg!("REJECT: Price {} posted at slot {}, {} slots ago; max {}", i, reading.slot, slots, max_age_slots);
+            Err(MatcherError::FeedPriceStale)
+        } else {
+            reading.price_e6.ok_or_else(|| {
+                msg!("ERROR: Price account {} holds no positive e6 price", i);
+                MatcherError::InvalidFeedPrice
+            })
+        };
+        let price_e6 = match checked {
+            Ok(price_e6) => price_e6,
+            Err(_) if fallback && i + 1 < count => continue,
+            Err(fault) => return Err(fault.into()),
+        };
+        if max_age_slots != 0 && slots > max_age_slots {
+            msg!("credibility-feed: price {} is {} slots old; quoting at max spread", i, slots);
+            slot_stale = true;
+        }
+        msg!(
+            "credibility-feed: source={} price={} conf={}bps age={}s",
+            FEED_SOURCE_NAMES[source as usize], price_e6, reading.conf_bps, age
+        );
+
+        // Falling back, the first good feed is the price, at its penalty
+        if fallback {
+            let penalty_bps = read_u16(entry, FEED_ENTRY_PENALTY_OFF) as u64;
+            let stale_bps = if slot_stale { BPS } else { 0 };
+            msg!("credibility-feeds: feed {} of {} penalty={}bps", i, count, penalty_bps);
+            return Ok((price_e6, reading.conf_bps.saturating_add(penalty_bps).saturating_add(stale_bps)));
+        }
+        prices[i] = price_e6;
+        conf_bps = conf_bps.max(reading.conf_bps);
+    }
+
+    // The least certain feed's confidence interval always widens the spread.
+    // The feeds that disagree most can still only pull the median so far;
+    // past the LP's limit, refuse or widen by the whole disagreement too
+    let prices = &mut prices[..count];
+    let median = pricing::median_price(prices).ok_or(MatcherError::InvalidFeedPrice)?;
+    let divergence = pricing::divergence_bps(prices, median);
+    let max_divergence = read_u16(&feed_data, FEED_MAX_DIVERGENCE_OFF) as u64;
+    let divergence_bps = match max_divergence {
+        0 => 0,
+        max if divergence <= max => 0,
+        max if feed_data[FEED_WIDEN_OFF] == 0 => {
+            msg!("REJECT: Feeds {}bps apart around {}; max {}bps", divergence, median, max);
+            return Err(MatcherError::FeedDivergence.into());
+        }
+        _ => divergence,
+    };
+    if prices.len() > 1 {
+        msg!("credibility-feeds: median={} divergence={}bps widen={}bps", median, divergence, divergence_bps);
+    }
+
+    // A whole 100% on top of a stale price leaves the clamp at max_spread_bps
+    let stale_bps = if slot_stale { BPS } else { 0 };
+    Ok((median, conf_bps.saturating_add(divergence_bps).saturating_add(stale_bps)))
+}
+
+/// A confidence interval `conf` in bps of `price`, both in the same
+/// exponent, rounded up; 0 for a price that isn't positive.
+fn conf_bps(price: i64, conf: u64) -> u64 {
+    match u64::try_from(price) {
+        Ok(price) if price > 0 => (conf as u128 * BPS as u128).div_ceil(price as u128).min(u64::MAX as u128) as u64,
+        _ => 0,
+    }
+}
+
+/// `price * 10^exponent` in units of 1e-6, rounded down; None unless it is
+/// positive and fits a u64.
+fn scale_e6(price: i64, exponent: i32) -> Option<u64> {
+    let price = u64::try_from(price).ok()?;
+    let shift = exponent.checked_add(6)?;
+    let scaled = if shift >= 0 {
+        price.checked_mul(10u64.checked_pow(shift as u32)?)?
+    } else {
+        10u64.checked_pow(shift.unsigned_abs()).map_or(0, |div| price / div)
+    };
+    (scaled > 0).then_some(scaled)
+}
+
+/// Take a scheduled context's schedule account off the front of the optional
+/// accounts after an instruction's fixed ones, where it has to be; other
+/// contexts take none.
+fn split_schedule<'a, 'b>(
+    program_id: &Pubkey,
+    ctx_key: &Pubkey,
+    rest: &'a [AccountInfo<'b>],
+    ctx_data: &[u8],
+) -> Result<(Option<&'a AccountInfo<'b>>, &'a [AccountInfo<'b>]), ProgramError> {
+    if !MatcherContext::view(ctx_data).has_flag(CTX_FLAG_SCHEDULED) {
+        return Ok((None, rest));
+    }
+    let Some((schedule, rest)) = rest.split_first() else {
+        msg!("ERROR: The context's schedule account wasn't passed");
+        return Err(MatcherError::ScheduleMismatch.into());
+    };
+    check_bound_schedule(program_id, schedule, ctx_key)?;
+    Ok((Some(schedule), rest))
+}
+
+/// The spread multiplier the schedule puts in force at `current_slot`, in
+/// bps; 0 (none) without a schedule.
+fn spread_mult(schedule: Option<&AccountInfo>, current_slot: u64) -> Result<u64, ProgramError> {
+    let Some(schedule) = schedule else {
+        return Ok(0);
+    };
+    let data = schedule.try_borrow_data()?;
+    let count = (read_u32(&data, SCHEDULE_COUNT_OFF) as usize).min(MAX_SCHEDULE_ENTRIES);
+    let entries = data[SCHEDULE_HEADER_LEN..].chunks_exact(SCHED_ENTRY_LEN).take(count).map(|entry| {
+        (read_u64(entry, SCHED_START_OFF), read_u64(entry, SCHED_END_OFF), read_u32(entry, SCHED_MULT_OFF))
+    });
+    Ok(pricing::schedule_mult_bps(entries, current_slot))
+}
+
+/// The fee tiers of a schedule account long enough to hold them; none
+/// without one.
+fn fee_tiers(schedule: Option<&AccountInfo>) -> Result<FeeTiers, ProgramError> {
+    let mut tiers = [(0, 0); MAX_FEE_TIERS];
+    let Some(schedule) = schedule else {
+        return Ok(tiers);
+    };
+    let data = schedule.try_borrow_data()?;
+    if let Some(table) = data.get(FEE_TIERS_OFF..SCHEDULE_FEES_LEN) {
+        for (tier, entry) in tiers.iter_mut().zip(table.chunks_exact(FEE_TIER_LEN)) {
+            *tier = (read_u128(entry, FEE_TIER_NOTIONAL_OFF), read_u32(entry, FEE_TIER_FEE_OFF));
+        }
+    }
+    Ok(tiers)
+}
+
+/// The rebate of a schedule account long enough to hold one; none without.
+fn schedule_rebate(schedule: Option<&AccountInfo>) -> Result<u64, ProgramError> {
+    let Some(schedule) = schedule else {
+        return Ok(0);
+    };
+    let data = schedule.try_borrow_data()?;
+    Ok(if data.len() < SCHEDULE_REBATE_LEN { 0 } else { read_u16(&data, SCHEDULE_REBATE_OFF) as u64 })
+}
+
+/// The fee discount of a schedule account long enough to hold one; none
+/// without.
+fn schedule_fee_discount(schedule: Option<&AccountInfo>) -> Result<u64, ProgramError> {
+    let Some(schedule) = schedule else {
+        return Ok(0);
+    };
+    let data = schedule.try_borrow_data()?;
+    Ok(if data.len() < SCHEDULE_REBATE_LEN { 0 } else { read_u16(&data, SCHEDULE_FEE_DISCOUNT_OFF) as u64 })
+}
+
+/// The minimum notional of a schedule account long enough to hold one; none
+/// without.
+fn schedule_min_notional(schedule: Option<&AccountInfo>) -> Result<u128, ProgramError> {
+    let Some(schedule) = schedule else {
+        return Ok(0);
+    };
+    let data = schedule.try_borrow_data()?;
+    Ok(if data.len() < SCHEDULE_MIN_NOTIONAL_LEN { 0 } else { read_u128(&data, SCHEDULE_MIN_NOTIONAL_OFF) })
+}
+
+/// The inventory halflife of a schedule account long enough to hold one;
+/// none without.
+fn schedule_inventory_halflife(schedule: Option<&AccountInfo>) -> Result<u32, ProgramError> {
+    let Some(schedule) = schedule else {
+        return Ok(0);
+    };
+    let data = schedule.try_borrow_data()?;
+    Ok(if data.len() < SCHEDULE_DECAY_LEN { 0 } else { read_u32(&data, SCHEDULE_INVENTORY_HALFLIFE_OFF) })
+}
+
+/// The soft inventory limit and band_k_bps of a schedule account long enough
+/// to hold them; no band without.
+fn schedule_inventory_band(schedule: Option<&AccountInfo>) -> Result<(u128, u64), ProgramError> {
+    let Some(schedule) = schedule else {
+        return Ok((0, 0));
+    };
+    let data = schedule.try_borrow_data()?;
+    if data.len() < SCHEDULE_BAND_LEN {
+        return Ok((0, 0));
+    }
+    Ok((read_u128(&data, SCHEDULE_SOFT_INVENTORY_OFF), read_u32(&data, SCHEDULE_BAND_K_OFF) as u64))
+}
+
+/// The long and short inventory caps of a schedule account long enough to
+/// hold them; max_inventory both ways without.
+fn schedule_inventory_caps(schedule: Option<&AccountInfo>) -> Result<(u128, u128), ProgramError> {
+    let Some(schedule) = schedule else {
+        return Ok((0, 0));
+    };
+    let data = schedule.try_borrow_data()?;
+    if data.len() < SCHEDULE_CAPS_LEN {
+        return Ok((0, 0));
+    }
+    Ok((read_u128(&data, SCHEDULE_MAX_LONG_OFF), read_u128(&data, SCHEDULE_MAX_SHORT_OFF)))
+}
+
+/// The reduce-only threshold of a schedule account long enough to hold one;
+/// 0 (AUTO_REDUCE_ONLY_BPS) without.
+fn schedule_reduce_only_bps(schedule: Option<&AccountInfo>) -> Result<u64, ProgramError> {
+    let Some(schedule) = schedule else {
+        return Ok(0);
+    };
+    let data = schedule.try_borrow_data()?;
+    Ok(if data.len() < SCHEDULE_REDUCE_ONLY_LEN { 0 } else { read_u16(&data, SCHEDULE_REDUCE_ONLY_BPS_OFF) as u64 })
+}
+
+/// The depth-relative inventory cap of a schedule account long enough to hold
+/// one; 0 (max_inventory_abs as set) without.
+fn schedule_max_inventory_bps(schedule: Option<&AccountInfo>) -> Result<u32, ProgramError> {
+    let Some(schedule) = schedule else {
+        return Ok(0);
+    };
+    let data = schedule.try_borrow_data()?;
+    Ok(if data.len() < SCHEDULE_DEPTH_CAP_LEN { 0 } else { read_u32(&data, SCHEDULE_MAX_INVENTORY_BPS_OFF) })
+}
+
+/// The loyalty discount of a trader account: the largest of the schedule's
+/// loyalty tiers its volume reaches; none without a trader account or a
+/// schedule long enough for the tiers.
+fn loyalty_discount(schedule: Option<&AccountInfo>, trader_account: Option<&AccountInfo>) -> Result<u32, ProgramError> {
+    let (Some(schedule), Some(trader_account)) = (schedule, trader_account) else {
+        return Ok(0);
+    };
+    let data = schedule.try_borrow_data()?;
+    let Some(table) = data.get(LOYALTY_TIERS_OFF..SCHEDULE_LOYALTY_LEN) else {
+        return Ok(0);
+    };
+    let volume = read_u128(&trader_account.try_borrow_data()?, TRADER_VOLUME_OFF);
+    let tiers = table
+        .chunks_exact(LOYALTY_TIER_LEN)
+        .map(|tier| (read_u128(tier, LOYALTY_TIER_VOLUME_OFF), read_u32(tier, LOYALTY_TIER_DISCOUNT_OFF)));
+    Ok(pricing::tier_fee_bps(tiers, volume).unwrap_or(0))
+}
+
+/// Split the optional accounts after an instruction's fixed ones into the
+/// stats account and the signal accounts that follow it. The first is taken
+/// for the stats account unless it carries SIGNAL_MAGIC, and then has to be
+/// the bound one. The rest have to be the signals the schedule registers,
+/// all of them and in order, under the programs that owned them then.
+fn stats_and_signals<'a, 'b>(
+    program_id: &Pubkey,
+    rest: &'a [AccountInfo<'b>],
+    ctx_data: &[u8],
+    schedule: Option<&AccountInfo>,
+) -> Result<(Option<&'a AccountInfo<'b>>, &'a [AccountInfo<'b>]), ProgramError> {
+    let (stats_account, signal_accounts) = match rest.split_first() {
+        Some((first, signals)) if !is_signal(first) => (Some(first), signals),
+        _ => (None, rest),
+    };
+    if let Some(stats_account) = stats_account {
+        check_bound_stats(program_id, stats_account, ctx_data)?;
+    }
+    let (registered, count) = registered_signals(schedule)?;
+    if signal_accounts.len() != count {
+        msg!("ERROR: {} signal accounts passed, {} registered", signal_accounts.len(), count);
+        return Err(MatcherError::InvalidSignal.into());
+    }
+    for (account, (key, owner)) in signal_accounts.iter().zip(&registered) {
+        if account.key != key || account.owner != owner {
+            msg!("ERROR: Signal account {} isn't the registered {} under {}", account.key, key, owner);
+            return Err(MatcherError::InvalidSignal.into());
+        }
+    }
+    Ok((stats_account, signal_accounts))
+}
+
+/// The signal accounts SetSignals registered in a schedule account long
+/// enough to hold them, with their owners; none without one.
+fn registered_signals(
+    schedule: Option<&AccountInfo>,
+) -> Result<([(Pubkey, Pubkey); MAX_SIGNAL_ACCOUNTS], usize), ProgramError> {
+    let mut registered = [(Pubkey::default(), Pubkey::default()); MAX_SIGNAL_ACCOUNTS];
+    let Some(schedule) = schedule else {
+        return Ok((registered, 0));
+    };
+    let data = schedule.try_borrow_data()?;
+    if data.len() < SCHEDULE_SIGNALS_LEN {
+        return Ok((registered, 0));
+    }
+    let count = (data[SCHEDULE_SIGNAL_COUNT_OFF] as usize).min(MAX_SIGNAL_ACCOUNTS);
+    let table = &data[SCHEDULE_SIGNALS_OFF..SCHEDULE_SIGNALS_LEN];
+    for (slot, entry) in registered.iter_mut().zip(table.chunks_exact(SIGNAL_ENTRY_LEN)).take(count) {
+        *slot = (read_pubkey(entry, SIGNAL_ENTRY_KEY_OFF), read_pubkey(entry, SIGNAL_ENTRY_OWNER_OFF));
+    }
+    Ok((registered, count))
+}
+
+fn is_ladder(account: &AccountInfo) -> bool {
+    account.try_borrow_data().is_ok_and(|data| data.len() >= 8 && read_u64(&data, LADDER_MAGIC_OFF) == LADDER_MAGIC)
+}
+
+fn is_referral(account: &AccountInfo) -> bool {
+    account.try_borrow_data().is_ok_and(|data| data.len() >= 8 && read_u64(&data, REFERRAL_MAGIC_OFF) == REFERRAL_MAGIC)
+}
+
+fn is_trader(account: &AccountInfo) -> bool {
+    account.try_borrow_data().is_ok_and(|data| data.len() >= 8 && read_u64(&data, TRADER_MAGIC_OFF) == TRADER_MAGIC)
+}
+
+fn is_signal(account: &AccountInfo) -> bool {
+    account.try_borrow_data().is_ok_and(|data| data.len() >= 8 && read_u64(&data, SIGNAL_MAGIC_OFF) == SIGNAL_MAGIC)
+}
+
+/// A signal account's value_bps, weight_bps and stale_after_slot, if it is
+/// one: the magic, the whole layout and a weight of at most 100%.
+fn read_signal(account: &AccountInfo) -> Result<(u32, u16, u64), ProgramError> {
+    let data = account.try_borrow_data()?;
+    if data.len() < SIGNAL_LEN || read_u64(&data, SIGNAL_MAGIC_OFF) != SIGNAL_MAGIC {
+        msg!("ERROR: {} is not a signal account", account.key);
+        return Err(MatcherError::InvalidSignal.into());
+    }
+    let weight_bps = read_u16(&data, SIGNAL_WEIGHT_OFF);
+    if weight_bps as u64 > BPS {
+        msg!("ERROR: signal {} weighted {}bps", account.key, weight_bps);
+        return Err(MatcherError::InvalidSignal.into());
+    }
+    Ok((read_u32(&data, SIGNAL_VALUE_OFF), weight_bps, read_u64(&data, SIGNAL_STALE_AFTER_OFF)))
+}
+
+/// The spread surcharge from the registered signal accounts as of
+/// `current_slot`: each one's value_bps at its weight_bps, skipping those
+/// past stale_after_slot.
+fn signal_surcharge(signal_accounts: &[AccountInfo], current_slot: u64) -> Result<u64, ProgramError> {
+    let mut signals = [(0u32, 0u16); MAX_SIGNAL_ACCOUNTS];
+    for (signal, account) in signals.iter_mut().zip(signal_accounts) {
+        let (value_bps, weight_bps, stale_after) = read_signal(account)?;
+        if current_slot > stale_after {
+            msg!("credibility-signal: {} stale since slot {}, ignored", account.key, stale_after);
+            continue;
+        }
+        *signal = (value_bps, weight_bps);
+    }
+    Ok(pricing::signal_bps(signals))
+}
+
+/// Append a fill to the stats ring, overwriting the oldest once it is full.
+fn record_fill(stats_data: &mut [u8], fill_seq: u64, slot: u64, q: &Quote) {
+    let head = read_u32(stats_data, STATS_HEAD_OFF) as usize % STATS_CAPACITY;
+    let entry = STATS_HEADER_LEN + head * FILL_LEN;
+    write_u64(stats_data, entry + FILL_SEQ_OFF, fill_seq);
+    write_u64(stats_data, entry + FILL_SLOT_OFF, slot);
+    write_i128(stats_data, entry + FILL_SIZE_OFF, q.fill_size);
+    write_u64(stats_data, entry + FILL_PRICE_OFF, q.exec_price_e6);
+    write_u64(stats_data, entry + FILL_SPREAD_OFF, q.spread_bps);
+    write_u32(stats_data, STATS_HEAD_OFF, ((head + 1) % STATS_CAPACITY) as u32);
+    let count = read_u32(stats_data, STATS_COUNT_OFF).saturating_add(1).min(STATS_CAPACITY as u32);
+    write_u32(stats_data, STATS_COUNT_OFF, count);
+}
+
+/// Add a fill to the trader account's volume, figured like cum_notional.
+fn record_trade(trader_data: &mut [u8], slot: u64, q: &Quote) {
+    let notional = q.fill_size.unsigned_abs().saturating_mul(q.exec_price_e6 as u128) / 1_000_000;
+    let volume = read_u128(trader_data, TRADER_VOLUME_OFF).saturating_add(notional);
+    write_u128(trader_data, TRADER_VOLUME_OFF, volume);
+    write_u64(trader_data, TRADER_FILLS_OFF, read_u64(trader_data, TRADER_FILLS_OFF).saturating_add(1));
+    write_u64(trader_data, TRADER_LAST_SLOT_OFF, slot);
+}
+
+/// Earmark the stats account's skim_bps of a fill's spread, on its notional
+/// figured as for cum_notional, for the insurance fund. Stats accounts
+/// without room for the skim, or with none set, accrue nothing.
+fn record_skim(stats_data: &mut [u8], q: &Quote) {
+    if stats_data.len() < STATS_SKIM_LEN {
+        return;
+    }
+    let skim_bps = read_u16(stats_data, STATS_SKIM_BPS_OFF) as u128;
+    if skim_bps == 0 {
+        return;
+    }
+    let notional = q.fill_size.unsigned_abs().saturating_mul(q.exec_price_e6 as u128) / 1_000_000;
+    let skim = notional.saturating_mul(q.spread_bps as u128).saturating_mul(skim_bps) / (BPS as u128 * BPS as u128);
+    let accrued = read_u128(stats_data, STATS_SKIM_ACCRUED_OFF).saturating_add(skim);
+    write_u128(stats_data, STATS_SKIM_ACCRUED_OFF, accrued);
+}
+
+/// Accrue the treasury's cut of a fill's fee, figured like cum_fees, on a
+/// stats account InitStats gave a protocol fee.
+fn record_protocol_fee(stats_data: &mut [u8], q: &Quote) {
+    if stats_data.len() < STATS_PROTOCOL_LEN {
+        return;
+    }
+    let protocol_bps = read_u16(stats_data, STATS_PROTOCOL_FEE_BPS_OFF) as u128;
+    if protocol_bps == 0 {
+        return;
+    }
+    let notional = q.fill_size.unsigned_abs().saturating_mul(q.exec_price_e6 as u128) / 1_000_000;
+    let fees = notional.saturating_mul(q.fee_bps as u128) / BPS as u128;
+    let cut = fees.saturating_mul(protocol_bps) / BPS as u128;
+    let accrued = read_u128(stats_data, STATS_PROTOCOL_ACCRUED_OFF).saturating_add(cut);
+    write_u128(stats_data, STATS_PROTOCOL_ACCRUED_OFF, accrued);
+}
+
+/// Mark the book to the oracle after a fill, on a stats account of
+/// STATS_PNL_LEN bytes. A fill that grows |inventory| averages its execution
+/// price into the entry price; one that shrinks it realizes the gap to the
+/// entry on what it closed, and one that flips it starts over at its price.
+/// Both sides are figured as the backtester figures the LP: a fill pays it
+/// size × exec price, and the inventory is worth -inventory × the oracle.
+fn record_pnl(stats_data: &mut [u8], q: &Quote, oracle_price_e6: u64) {
+    if stats_data.len() < STATS_PNL_LEN {
+        return;
+    }
+    let (fill, new_inventory) = (q.fill_size, q.new_inventory);
+    let inventory = new_inventory - fill;
+    let exec = q.exec_price_e6 as i128;
+    let mut entry = read_u64(stats_data, STATS_PNL_ENTRY_OFF) as i128;
+
+    if inventory.signum() * fill.signum() < 0 {
+        let closed = fill.unsigned_abs().min(inventory.unsigned_abs()) as i128;
+        let gain = closed.saturating_mul(entry - exec).saturating_mul(inventory.signum()) / 1_000_000;
+        let realized = read_i128(stats_data, STATS_PNL_REALIZED_OFF).saturating_add(gain);
+        write_i128(stats_data, STATS_PNL_REALIZED_OFF, realized);
+    }
+    if new_inventory == 0 {
+        entry = 0;
+    } else if new_inventory.signum() != inventory.signum() {
+        entry = exec;
+    } else if new_inventory.unsigned_abs() > inventory.unsigned_abs() {
+        let held = inventory.unsigned_abs().saturating_mul(entry as u128);
+        let added = fill.unsigned_abs().saturating_mul(exec as u128);
+        entry = (held.saturating_add(added) / new_inventory.unsigned_abs()).min(u64::MAX as u128) as i128;
+    }
+    let unrealized = new_inventory.saturating_mul(entry - oracle_price_e6 as i128) / 1_000_000;
+    write_u64(stats_data, STATS_PNL_ENTRY_OFF, entry as u64);
+    write_u64(stats_data, STATS_PNL_MARK_OFF, oracle_price_e6);
+    write_i128(stats_data, STATS_PNL_UNREALIZED_OFF, unrealized);
+}
+
+/// Record the oracle price a fill traded against. A second print in the same
+/// slot replaces the first, so a burst of fills can't flush the history.
+fn record_oracle(stats_data: &mut [u8], slot: u64, oracle_price_e6: u64) {
+    let count = read_u32(stats_data, STATS_ORACLE_COUNT_OFF) as usize;
+    let mut head = read_u32(stats_data, STATS_ORACLE_HEAD_OFF) as usize % ORACLE_CAPACITY;
+    let newest = (head + ORACLE_CAPACITY - 1) % ORACLE_CAPACITY;
+    let newest_off = STATS_ORACLE_RING_OFF + newest * ORACLE_SAMPLE_LEN;
+    let same_slot = count > 0 && read_u64(stats_data, newest_off + ORACLE_SLOT_OFF) == slot;
+    let sample = if same_slot {
+        newest_off
+    } else {
+        let off = STATS_ORACLE_RING_OFF + head * ORACLE_SAMPLE_LEN;
+        head = (head + 1) % ORACLE_CAPACITY;
+        write_u32(stats_data, STATS_ORACLE_HEAD_OFF, head as u32);
+        write_u32(stats_data, STATS_ORACLE_COUNT_OFF, (count + 1).min(ORACLE_CAPACITY) as u32);
+        off
+    };
+    write_u64(stats_data, sample + ORACLE_SLOT_OFF, slot);
+    write_u64(stats_data, sample + ORACLE_PRICE_OFF, oracle_price_e6);
+}
+
+/// Time-weighted average of the recorded oracle prices over the last
+/// `window` slots. Each sample holds until the next one; the sample in force
+/// when the window opened covers its start. The current print is not part of
+/// it until a fill records it. With no history, the current print is used.
+fn twap_price(stats_data: &[u8], window: u64, current_slot: u64, oracle_price_e6: u64) -> u64 {
+    let count = (read_u32(stats_data, STATS_ORACLE_COUNT_OFF) as usize).min(ORACLE_CAPACITY);
+    let head = read_u32(stats_data, STATS_ORACLE_HEAD_OFF) as usize % ORACLE_CAPACITY;
+    let samples = (1..=count).map(|i| {
+        let off = STATS_ORACLE_RING_OFF + ((head + ORACLE_CAPACITY - i) % ORACLE_CAPACITY) * ORACLE_SAMPLE_LEN;
+        (read_u64(stats_data, off + ORACLE_SLOT_OFF), read_u64(stats_data, off + ORACLE_PRICE_OFF))
+    });
+    pricing::twap(samples, window, current_slot, oracle_price_e6)
+}
+
+/// The posted RFQ quote, if there is one. v5 contexts have nowhere to keep it.
+fn posted_rfq(ctx_data: &[u8]) -> Option<pricing::rfq::Rfq> {
+    if !is_extended(ctx_data) {
+        return None;
+    }
+    let ext = ContextExtension::view(ctx_data);
+    let rfq = pricing::rfq::Rfq {
+        price_e6: ext.rfq_price_e6.get(),
+        size_left: ext.rfq_size_left.get(),
+        expiry_slot: ext.rfq_expiry_slot.get(),
+    };
+    (rfq.price_e6 != 0).then_some(rfq)
+}
+
+/// Withdraw the posted RFQ quote, keeping its nonce.
+fn clear_rfq(ctx_data: &mut [u8]) {
+    let ext = ContextExtension::view_mut(ctx_data);
+    ext.rfq_price_e6.set(0);
+    ext.rfq_expiry_slot.set(0);
+    ext.rfq_size_left.set(0);
+}
+
+/// Require the instruction before this one to be the ed25519 program's,
+/// verifying one signature by `signer` over exactly `message`, with the key and
+/// message in its own data. The ed25519 program fails the transaction on a bad
+/// signature, so a matching instruction means the quote is the signer's.
+fn check_rfq_signature(instructions: &AccountInfo, signer: &Pubkey, message: &[u8]) -> ProgramResult {
+    let unverified = || {
+        msg!("ERROR: PostRfq must follow an ed25519 verification of the quote by {}", signer);
+        ProgramError::from(MatcherError::RfqUnverified)
+    };
+    let current = load_current_index_checked(instructions)? as usize;
+    let Some(previous) = current.checked_sub(1) else {
+        return Err(unverified());
+    };
+    let verify = load_instruction_at_checked(previous, instructions)?;
+    let data = &verify.data[..];
+    if verify.program_id != ed25519_program::ID
+        || data.len() < ED25519_OFFSETS_OFF + ED25519_OFFSETS_LEN
+        || data[0] != 1
+    {
+        return Err(unverified());
+    }
+
+    let offset = |off: usize| read_u16(data, ED25519_OFFSETS_OFF + off) as usize;
+    let inline = [ED25519_SIG_IX_OFF, ED25519_KEY_IX_OFF, ED25519_MSG_IX_OFF]
+        .iter()
+        .all(|&off| offset(off) == u16::MAX as usize);
+    let (key_off, msg_off) = (offset(ED25519_KEY_OFF), offset(ED25519_MSG_OFF));
+    let signed_key = data.get(key_off..key_off + 32);
+    let signed_message = data.get(msg_off..msg_off + offset(ED25519_MSG_SIZE_OFF));
+    if !inline || signed_key != Some(signer.as_ref()) || signed_message != Some(message) {
+        return Err(unverified());
+    }
+    Ok(())
+}
+
+/// Reject a slab other than the one the context was bound to, or one no
+/// longer owned by the percolator program recorded alongside it.
+/// Unbound contexts (v5, or v6 initialized without a slab) accept any slab;
+/// check_pinned_slab holds them to PERCOLATOR_PROGRAM_ID where the slab is
+/// the only input.
+fn check_bound_slab(slab: &AccountInfo, ctx_data: &[u8]) -> ProgramResult {
+    if !is_extended(ctx_data) {
+        return Ok(());
+    }
+    let ext = ContextExtension::view(ctx_data);
+    let bound = ext.slab();
+    if bound != Pubkey::default() && bound != *slab.key {
+        msg!("ERROR: Slab mismatch");
+        return Err(MatcherError::SlabMismatch.into());
+    }
+    let percolator = ext.percolator_program();
+    if percolator != Pubkey::default() && percolator != *slab.owner {
+        msg!("ERROR: Slab owned by {}, expected {}", slab.owner, percolator);
+        return Err(MatcherError::SlabOwnerMismatch.into());
+    }
+    Ok(())
+}
+
+/// check_bound_slab for the permissionless instructions that read the slab.
+/// A context with a bound slab (or a market table) holds the slab to it and
+/// the percolator program pinned with it. One with no binding, v5 included,
+/// has nothing pinned, so the slab must be owned by PERCOLATOR_PROGRAM_ID
+/// rather than any account's bytes being taken.
+fn check_pinned_slab(slab: &AccountInfo, ctx_data: &[u8]) -> ProgramResult {
+    let bound = is_extended(ctx_data)
+        && ContextExtension::view(ctx_data).percolator_program() != Pubkey::default()
+        && (is_shared(ctx_data) || book_slab(ctx_data, 0) != Pubkey::default());
+    if bound {
+        return check_bound_slab(slab, ctx_data);
+    }
+    if *slab.owner != PERCOLATOR_PROGRAM_ID {
+        msg!("ERROR: Slab owned by {}, expected {} for an unbound context", slab.owner, PERCOLATOR_PROGRAM_ID);
+        return Err(MatcherError::SlabOwnerMismatch.into());
+    }
+    Ok(())
+}
+
+/// Where one market's inventory and snapshots live in a context: the context
+/// fields themselves, or an entry of a shared context's market table. `None`
+/// offsets are state a v5 context has no room for; it reads as zero and isn't
+/// written. The one path that still goes by offset, since the same code runs
+/// on both; the typed views in `context` hold each offset to its field.
+#[derive(Clone, Copy)]
+struct Book {
+    inventory: usize,
+    last_oracle_price: usize,
+    last_exec_price: usize,
+    insurance: usize,
+    total_oi: usize,
+    snapshot_slot: usize,
+    last_crank_slot: usize,
+    last_liqs: usize,
+    liq_heat: usize,
+    market_age: usize,
+    last_deficit_slot: usize,
+    funding_rate: Option<usize>,
+    last_oracle_slot: Option<usize>,
+    gross_long: Option<usize>,
+    gross_short: Option<usize>,
+    last_liq_slot: Option<usize>,
+    /// The market's own (max_fill, max_inventory) overrides
+    caps: Option<(usize, usize)>,
+}
+
+impl Book {
+    fn context(ctx_data: &[u8]) -> Self {
+        let ext = |off: usize| is_extended(ctx_data).then_some(EXT_BASE + off);
+        Self {
+            inventory: CTX_BASE + CTX_INVENTORY_OFF,
+            last_oracle_price: CTX_BASE + CTX_LAST_ORACLE_OFF,
+            last_exec_price: CTX_BASE + CTX_LAST_EXEC_OFF,
+            insurance: CTX_BASE + CTX_INSURANCE_OFF,
+            total_oi: CTX_BASE + CTX_TOTAL_OI_OFF,
+            snapshot_slot: CTX_BASE + CTX_SNAPSHOT_SLOT_OFF,
+            last_crank_slot: CTX_BASE + CTX_LAST_CRANK_OFF,
+            last_liqs: CTX_BASE + CTX_LAST_LIQS_OFF,
+            liq_heat: CTX_BASE + CTX_LIQ_HEAT_OFF,
+            market_age: CTX_BASE + CTX_MARKET_AGE_OFF,
+            last_deficit_slot: CTX_BASE + CTX_LAST_DEFICIT_OFF,
+            funding_rate: ext(EXT_FUNDING_RATE_OFF),
+            last_oracle_slot: ext(EXT_LAST_ORACLE_SLOT_OFF),
+            gross_long: ext(EXT_GROSS_LONG_OFF),
+            gross_short: ext(EXT_GROSS_SHORT_OFF),
+            last_liq_slot: ext(EXT_LAST_LIQ_SLOT_OFF),
+            caps: None,
+        }
+    }
+
+    fn market(index: usize) -> Self {
+        let base = MARKETS_BASE + index * MARKET_LEN;
+        Self {
+            inventory: base + MKT_INVENTORY_OFF,
+            last_oracle_price: base + MKT_LAST_ORACLE_OFF,
+            last_exec_price: base + MKT_LAST_EXEC_OFF,
+            insurance: base + MKT_INSURANCE_OFF,
+            total_oi: base + MKT_TOTAL_OI_OFF,
+            snapshot_slot: base + MKT_SNAPSHOT_SLOT_OFF,
+            last_crank_slot: base + MKT_LAST_CRANK_OFF,
+            last_liqs: base + MKT_LAST_LIQS_OFF,
+            liq_heat: base + MKT_LIQ_HEAT_OFF,
+            market_age: base + MKT_MARKET_AGE_OFF,
+            last_deficit_slot: base + MKT_LAST_DEFICIT_OFF,
+            funding_rate: Some(base + MKT_FUNDING_RATE_OFF),
+            last_oracle_slot: Some(base + MKT_LAST_ORACLE_SLOT_OFF),
+            gross_long: Some(base + MKT_GROSS_LONG_OFF),
+            gross_short: Some(base + MKT_GROSS_SHORT_OFF),
+            last_liq_slot: Some(base + MKT_LAST_LIQ_SLOT_OFF),
+            caps: Some((base + MKT_MAX_FILL_OFF, base + MKT_MAX_INVENTORY_OFF)),
+        }
+    }
+}
+
+/// Entries in the context's market table; 0 for a single-market context.
+/// Never more than the table holds.
+fn market_slots(ctx_data: &[u8]) -> usize {
+    if !is_extended(ctx_data) || ctx_data.len() < CTX_SHARED_LEN {
+        return 0;
+    }
+    (ContextExtension::view(ctx_data).market_slots.get() as usize).min(MAX_MARKETS)
+}
+
+fn is_shared(ctx_data: &[u8]) -> bool {
+    market_slots(ctx_data) > 0
+}
+
+/// Where the creation record of a context `len` bytes long starts, if it has
+/// room for one after its layout. Init lays out a context by its size alone
+/// (v6 from CTX_EXT_LEN, shared from CTX_SHARED_LEN), so this holds before it
+/// runs too.
+fn creation_off(len: usize) -> Option<usize> {
+    let off = if len >= CTX_SHARED_LEN { CTX_SHARED_LEN } else { CTX_EXT_LEN };
+    (len >= off + CREATION_LEN).then_some(off)
+}
+
+/// Whether CreateContext made this context at its derived address. The
+/// record's flag, not the bump, says so: 0 is a valid bump.
+fn is_derived(ctx_data: &[u8]) -> bool {
+    creation_off(ctx_data.len()).is_some_and(|off| ctx_data[off + CREATION_DERIVED_OFF] != 0)
+}
+
+/// Table entry listing this slab, if any.
+fn market_index(ctx_data: &[u8], slab: &Pubkey) -> Option<usize> {
+    if *slab == Pubkey::default() {
+        return None;
+    }
+    MarketEntry::table(ctx_data)[..market_slots(ctx_data)].iter().position(|market| market.slab() == *slab)
+}
+
+/// The book a Match or snapshot for this slab uses: the context's own on a
+/// single-market context, the slab's entry on a shared one.
+fn book_for(ctx_data: &[u8], slab: Option<&Pubkey>) -> Result<Book, ProgramError> {
+    if !is_shared(ctx_data) {
+        return Ok(Book::context(ctx_data));
+    }
+    match slab.and_then(|slab| market_index(ctx_data, slab)) {
+        Some(index) => Ok(Book::market(index)),
+        None => {
+            msg!("ERROR: Slab {:?} not in the market table", slab);
+            Err(MatcherError::UnknownMarket.into())
+        }
+    }
+}
+
+/// The book at a market-table index, for instructions that name no slab.
+/// Index 0 on a single-market context is the context's own.
+fn book_at(ctx_data: &[u8], index: u16) -> Result<Book, ProgramError> {
+    let index = index as usize;
+    if !is_shared(ctx_data) {
+        if index != 0 {
+            msg!("ERROR: Market {} on a single-market context", index);
+            return Err(MatcherError::UnknownMarket.into());
+        }
+        return Ok(Book::context(ctx_data));
+    }
+    let slab = (index < market_slots(ctx_data)).then(|| MarketEntry::table(ctx_data)[index].slab());
+    if slab.is_none_or(|slab| slab == Pubkey::default()) {
+        msg!("ERROR: Market {} not in the market table", index);
+        return Err(MatcherError::UnknownMarket.into());
+    }
+    Ok(Book::market(index))
+}
+
+/// The slab the book at a market-table index quotes; the default key for an
+/// unbound or v5 context. The index must have passed `book_at`.
+fn book_slab(ctx_data: &[u8], index: u16) -> Pubkey {
+    if is_shared(ctx_data) {
+        MarketEntry::table(ctx_data)[index as usize].slab()
+    } else if is_extended(ctx_data) {
+        ContextExtension::view(ctx_data).slab()
+    } else {
+        Pubkey::default()
+    }
+}
+
+/// A book's cap on |inventory|: its market's own where set, the context's
+/// otherwise; 0 = unlimited.
+fn book_max_inventory(ctx_data: &[u8], book: &Book) -> u128 {
+    let market = book.caps.map_or(0, |(_, max_inventory_off)| read_u128(ctx_data, max_inventory_off));
+    if market != 0 { market } else { MatcherContext::view(ctx_data).max_inventory_abs.get() }
+}
+
+/// Read an optional little-endian u16 from instruction data; absent → 0.
+fn read_opt_u16(data: &[u8], off: usize) -> u16 {
+    data.get(off..off + 2)
+        .map_or(0, |b| u16::from_le_bytes(b.try_into().unwrap()))
+}
+
+/// Read an optional little-endian u32 from instruction data; absent → 0.
+fn read_opt_u32(data: &[u8], off: usize) -> u32 {
+    data.get(off..off + 4)
+        .map_or(0, |b| u32::from_le_bytes(b.try_into().unwrap()))
+}
+
+/// A v6 context with room for the extension region.
+fn is_extended(ctx_data: &[u8]) -> bool {
+    ctx_data.len() >= CTX_EXT_LEN && MatcherContext::view(ctx_data).version.get() >= VERSION
+}
+
+fn read_pubkey(data: &[u8], off: usize) -> Pubkey {
+    Pubkey::new_from_array(data[off..off + 32].try_into().unwrap())
+}
+
+fn read_u16(data: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes(data[off..off + 2].try_into().unwrap())
+}
+
+fn read_u32(data: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes(data[off..off + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], off: usize) -> u64 {
+    u64::from_le_bytes(data[off..off + 8].try_into().unwrap())
+}
+
+fn read_i32(data: &[u8], off: usize) -> i32 {
+    i32::from_le_bytes(data[off..off + 4].try_into().unwrap())
+}
+
+fn read_i64(data: &[u8], off: usize) -> i64 {
+    i64::from_le_bytes(data[off..off + 8].try_into().unwrap())
+}
+
+fn read_u128(data: &[u8], off: usize) -> u128 {
+    u128::from_le_bytes(data[off..off + 16].try_into().unwrap())
+}
+
+fn read_i128(data: &[u8], off: usize) -> i128 {
+    i128::from_le_bytes(data[off..off + 16].try_into().unwrap())
+}
+
+fn write_u16(data: &mut [u8], off: usize, val: u16) {
+    data[off..off + 2].copy_from_slice(&val.to_le_bytes());
+}
+
+fn write_u32(data: &mut [u8], off: usize, val: u32) {
+    data[off..off + 4].copy_from_slice(&val.to_le_bytes());
+}
+
+fn write_u64(data: &mut [u8], off: usize, val: u64) {
+    data[off..off + 8].copy_from_slice(&val.to_le_bytes());
+}
+
+fn write_i64(data: &mut [u8], off: usize, val: i64) {
+    data[off..off + 8].copy_from_slice(&val.to_le_bytes());
+}
+
+fn write_u128(data: &mut [u8], off: usize, val: u128) {
+    data[off..off + 16].copy_from_slice(&val.to_le_bytes());
+}
+
+fn write_i128(data: &mut [u8], off: usize, val: i128) {
+    data[off..off + 16].copy_from_slice(&val.to_le_bytes());
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use provenance_core::{
+        coverage_discount_bps, crank_lag_bps, exec_price, flow_bps, funding_skew_bps, impact_bps, isqrt,
+        liq_heat_bps, skew_bps, staleness_bps, CRANK_GRACE_SLOTS, CRANK_SLOTS_PER_BPS, CURVE_SQRT,
+        FILL_PCT_CRITICAL, FILL_PCT_FORTIFIED, FILL_PCT_FRAGILE, FILL_PCT_NORMAL, FILL_PCT_STRONG,
+        IMPACT_LINEAR, LIQ_PENALTY_BPS, SIGNAL_COVERAGE, SNAPSHOT_GRACE_SLOTS, STALE_SLOTS_PER_BPS, TIER_CRITICAL_BPS,
+        TIER_FRAGILE_BPS, TIER_NORMAL, TIER_NORMAL_BPS, TIER_STRONG_BPS,
+    };
+    use provenance_client::test_support::SlabFixture;
+
+    fn compute_tier(coverage_bps: u64) -> (&'static str, u128) {
         if coverage_bps < TIER_CRITICAL_BPS {
             ("CRITICAL", FILL_PCT_CRITICAL)
         } else if coverage_bps < TIER_FRAGILE_BPS {
@@ -604,116 +5836,5143 @@ mod tests {
         } else if coverage_bps < TIER_STRONG_BPS {
             ("STRONG", FILL_PCT_STRONG)
         } else {
-            ("FORTIFIED", FILL_PCT_FORTIFIED)
+            ("FORTIFIED", FILL_PCT_FORTIFIED)
+        }
+    }
+
+    #[test]
+    fn test_tier_critical_zero_insurance() {
+        let (tier, fill) = compute_tier(0);
+        assert_eq!(tier, "CRITICAL");
+        assert_eq!(fill, 25);
+    }
+
+    #[test]
+    fn test_tier_critical_5pct() {
+        let (tier, fill) = compute_tier(500); // 5%
+        assert_eq!(tier, "CRITICAL");
+        assert_eq!(fill, 25);
+    }
+
+    #[test]
+    fn test_tier_fragile_boundary() {
+        let (tier, fill) = compute_tier(1000); // exactly 10%
+        assert_eq!(tier, "FRAGILE");
+        assert_eq!(fill, 50);
+    }
+
+    #[test]
+    fn test_tier_fragile_20pct() {
+        let (tier, fill) = compute_tier(2000); // 20%
+        assert_eq!(tier, "FRAGILE");
+        assert_eq!(fill, 50);
+    }
+
+    #[test]
+    fn test_tier_normal_boundary() {
+        let (tier, fill) = compute_tier(2500); // exactly 25%
+        assert_eq!(tier, "NORMAL");
+        assert_eq!(fill, 100);
+    }
+
+    #[test]
+    fn test_tier_normal_50pct() {
+        let (tier, fill) = compute_tier(5000); // 50%
+        assert_eq!(tier, "NORMAL");
+        assert_eq!(fill, 100);
+    }
+
+    #[test]
+    fn test_tier_strong_boundary() {
+        let (tier, fill) = compute_tier(10000); // exactly 100%
+        assert_eq!(tier, "STRONG");
+        assert_eq!(fill, 100);
+    }
+
+    #[test]
+    fn test_tier_strong_150pct() {
+        let (tier, fill) = compute_tier(15000); // 150%
+        assert_eq!(tier, "STRONG");
+        assert_eq!(fill, 100);
+    }
+
+    #[test]
+    fn test_tier_fortified_boundary() {
+        let (tier, fill) = compute_tier(20000); // exactly 200%
+        assert_eq!(tier, "FORTIFIED");
+        assert_eq!(fill, 150);
+    }
+
+    #[test]
+    fn test_tier_fortified_300pct() {
+        let (tier, fill) = compute_tier(30000); // 300%
+        assert_eq!(tier, "FORTIFIED");
+        assert_eq!(fill, 150);
+    }
+
+    #[test]
+    fn test_fill_cap_math() {
+        let base_max_fill: u128 = 1_000_000_000; // 1 SOL
+        // CRITICAL: 25% of base
+        assert_eq!((base_max_fill * FILL_PCT_CRITICAL) / 100, 250_000_000);
+        // FRAGILE: 50%
+        assert_eq!((base_max_fill * FILL_PCT_FRAGILE) / 100, 500_000_000);
+        // NORMAL/STRONG: 100%
+        assert_eq!((base_max_fill * FILL_PCT_NORMAL) / 100, 1_000_000_000);
+        // FORTIFIED: 150%
+        assert_eq!((base_max_fill * FILL_PCT_FORTIFIED) / 100, 1_500_000_000);
+    }
+
+    #[test]
+    fn test_critical_gets_max_spread() {
+        // At CRITICAL tier, spread should be max_spread
+        let max_spread: u64 = 200;
+        let coverage_bps: u64 = 500; // 5% → CRITICAL
+        let spread = if coverage_bps < TIER_CRITICAL_BPS {
+            max_spread
+        } else {
+            0 // placeholder
+        };
+        assert_eq!(spread, 200);
+    }
+
+    #[test]
+    fn test_fortified_gets_min_spread() {
+        // At FORTIFIED tier, spread should be min_spread
+        let min_spread: u64 = 5;
+        let coverage_bps: u64 = 25000; // 250% → FORTIFIED
+        let spread = if coverage_bps >= TIER_STRONG_BPS {
+            min_spread
+        } else {
+            0
+        };
+        assert_eq!(spread, 5);
+    }
+
+    #[test]
+    fn test_isqrt() {
+        assert_eq!(isqrt(0), 0);
+        assert_eq!(isqrt(1), 1);
+        assert_eq!(isqrt(15), 3);
+        assert_eq!(isqrt(16), 4);
+        assert_eq!(isqrt(1_000_000_000_000), 1_000_000);
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+    }
+
+    #[test]
+    fn test_impact_disabled() {
+        let liq: u128 = 10_000_000_000_000;
+        assert_eq!(impact_bps(IMPACT_NONE, 100, liq, liq), 0);
+        assert_eq!(impact_bps(IMPACT_LINEAR, 0, liq, liq), 0);
+        assert_eq!(impact_bps(IMPACT_LINEAR, 100, liq, 0), 0);
+        assert_eq!(impact_bps(IMPACT_SQRT, 100, 0, liq), 0);
+    }
+
+    #[test]
+    fn test_impact_linear_curve() {
+        let liq: u128 = 10_000_000_000_000;
+        // Full depth → full k; 1% of depth → 1% of k
+        assert_eq!(impact_bps(IMPACT_LINEAR, 100, liq, liq), 100);
+        assert_eq!(impact_bps(IMPACT_LINEAR, 100, liq / 100, liq), 1);
+        assert_eq!(impact_bps(IMPACT_LINEAR, 100, liq * 2, liq), 200);
+        // A 1-unit trade pays nothing
+        assert_eq!(impact_bps(IMPACT_LINEAR, 100, 1, liq), 0);
+    }
+
+    #[test]
+    fn test_impact_sqrt_curve() {
+        let liq: u128 = 10_000_000_000_000;
+        assert_eq!(impact_bps(IMPACT_SQRT, 100, liq, liq), 100);
+        // 1% of depth → sqrt(0.01) = 10% of k
+        assert_eq!(impact_bps(IMPACT_SQRT, 100, liq / 100, liq), 10);
+        // 4x depth → 2x k
+        assert_eq!(impact_bps(IMPACT_SQRT, 100, liq * 4, liq), 200);
+        // Sqrt charges small fills more than linear, large fills less
+        assert!(impact_bps(IMPACT_SQRT, 100, liq / 100, liq) > impact_bps(IMPACT_LINEAR, 100, liq / 100, liq));
+        assert!(impact_bps(IMPACT_SQRT, 100, liq * 4, liq) < impact_bps(IMPACT_LINEAR, 100, liq * 4, liq));
+    }
+
+    #[test]
+    fn test_impact_monotonic_in_size() {
+        let liq: u128 = 1_000_000;
+        for mode in [IMPACT_LINEAR, IMPACT_SQRT] {
+            let mut prev = 0;
+            for size in (0..=4_000_000u128).step_by(50_000) {
+                let cost = impact_bps(mode, 250, size, liq);
+                assert!(cost >= prev);
+                prev = cost;
+            }
+        }
+    }
+
+    #[test]
+    fn test_skew_direction() {
+        let liq: u128 = 1_000_000;
+        // Takers net long, so the LP is short: quote higher
+        assert_eq!(skew_bps(100, 500_000, liq, 1_000), -50);
+        assert_eq!(skew_bps(100, -500_000, liq, 1_000), 50);
+        assert_eq!(skew_bps(100, 0, liq, 1_000), 0);
+        assert_eq!(skew_bps(0, 500_000, liq, 1_000), 0);
+        assert_eq!(skew_bps(100, 500_000, 0, 1_000), 0);
+    }
+
+    #[test]
+    fn test_skew_capped_by_spread() {
+        let liq: u128 = 1_000_000;
+        assert_eq!(skew_bps(100, 10_000_000, liq, 40), -40);
+        assert_eq!(skew_bps(100, -10_000_000, liq, 40), 40);
+        assert_eq!(skew_bps(100, i128::MIN, liq, 40), 40);
+    }
+
+    #[test]
+    fn test_exec_price_symmetric_without_skew() {
+        let oracle = 100_000_000; // $100
+        assert_eq!(exec_price(oracle, 50, 0, true), 100_500_000);
+        assert_eq!(exec_price(oracle, 50, 0, false), 99_500_000);
+        // Sell side floors at zero
+        assert_eq!(exec_price(oracle, 20_000, 0, false), 0);
+    }
+
+    #[test]
+    fn test_exec_price_positive_skew_lowers_both_sides() {
+        let oracle = 100_000_000;
+        // LP long: skew +30 → ask and bid both move down 30 bps
+        assert_eq!(exec_price(oracle, 50, 30, true), 100_200_000);
+        assert_eq!(exec_price(oracle, 50, 30, false), 99_200_000);
+        // LP short: skew -30 → both move up
+        assert_eq!(exec_price(oracle, 50, -30, true), 100_800_000);
+        assert_eq!(exec_price(oracle, 50, -30, false), 99_800_000);
+    }
+
+    #[test]
+    fn test_skewed_ask_never_below_oracle() {
+        let oracle = 100_000_000;
+        let spread = 40;
+        // An LP long as far as the cap lowers the ask all the way to the
+        // oracle, and one short raises the bid to it, but no further
+        let skew = skew_bps(1_000, -10_000_000, 1_000_000, spread);
+        assert_eq!(exec_price(oracle, spread, skew, true), oracle);
+        let skew = skew_bps(1_000, 10_000_000, 1_000_000, spread);
+        assert_eq!(exec_price(oracle, spread, skew, false), oracle);
+    }
+
+    const NORMAL_RANGE: u64 = TIER_NORMAL_BPS - TIER_FRAGILE_BPS;
+
+    #[test]
+    fn test_coverage_curve_linear_unchanged() {
+        let points = [0; CURVE_POINTS];
+        for progress in [0, 1, 75, 3_750, 7_499, 7_500] {
+            assert_eq!(
+                coverage_discount_bps(CURVE_LINEAR, &points, 50, progress, NORMAL_RANGE),
+                (50 * progress) / NORMAL_RANGE
+            );
+        }
+    }
+
+    #[test]
+    fn test_coverage_curve_sqrt() {
+        let points = [0; CURVE_POINTS];
+        // Endpoints match linear
+        assert_eq!(coverage_discount_bps(CURVE_SQRT, &points, 100, 0, NORMAL_RANGE), 0);
+        assert_eq!(coverage_discount_bps(CURVE_SQRT, &points, 100, NORMAL_RANGE, NORMAL_RANGE), 100);
+        // A quarter of the way through earns half the discount
+        assert_eq!(coverage_discount_bps(CURVE_SQRT, &points, 100, NORMAL_RANGE / 4, NORMAL_RANGE), 50);
+        // Early coverage is worth more than under linear
+        let early = NORMAL_RANGE / 100;
+        assert!(
+            coverage_discount_bps(CURVE_SQRT, &points, 100, early, NORMAL_RANGE)
+                > coverage_discount_bps(CURVE_LINEAR, &points, 100, early, NORMAL_RANGE)
+        );
+    }
+
+    #[test]
+    fn test_coverage_curve_piecewise_knots() {
+        // Front-loaded: 60% of the discount by 20% of the way through
+        let points = [6_000, 8_000, 9_000, 9_500];
+        let d = |progress| coverage_discount_bps(CURVE_PIECEWISE, &points, 100, progress, NORMAL_RANGE);
+        assert_eq!(d(0), 0);
+        assert_eq!(d(NORMAL_RANGE / 5), 60);
+        assert_eq!(d(NORMAL_RANGE * 2 / 5), 80);
+        assert_eq!(d(NORMAL_RANGE * 3 / 5), 90);
+        assert_eq!(d(NORMAL_RANGE * 4 / 5), 95);
+        assert_eq!(d(NORMAL_RANGE), 100);
+        // Interpolates between knots
+        assert_eq!(d(NORMAL_RANGE / 10), 30);
+        assert_eq!(d(NORMAL_RANGE * 9 / 10), 97);
+    }
+
+    #[test]
+    fn test_coverage_curve_piecewise_monotonic() {
+        let points = [1_000, 5_000, 5_000, 9_999];
+        let mut prev = 0;
+        for progress in 0..=NORMAL_RANGE {
+            let d = coverage_discount_bps(CURVE_PIECEWISE, &points, 10_000, progress, NORMAL_RANGE);
+            assert!(d >= prev);
+            prev = d;
+        }
+        assert_eq!(prev, 10_000);
+    }
+
+    #[test]
+    fn test_coverage_curve_zero_weight() {
+        let points = [10_000; CURVE_POINTS];
+        assert_eq!(coverage_discount_bps(CURVE_PIECEWISE, &points, 0, 5_000, NORMAL_RANGE), 0);
+        assert_eq!(coverage_discount_bps(CURVE_SQRT, &points, 0, 5_000, NORMAL_RANGE), 0);
+    }
+
+    #[test]
+    fn test_staleness_within_grace() {
+        assert_eq!(staleness_bps(1_000, 1_000), 0);
+        assert_eq!(staleness_bps(1_000 + SNAPSHOT_GRACE_SLOTS, 1_000), 0);
+        // Clock behind the snapshot (shouldn't happen) is not penalized
+        assert_eq!(staleness_bps(500, 1_000), 0);
+    }
+
+    #[test]
+    fn test_staleness_grows_past_grace() {
+        let snap = 1_000;
+        let past_grace = snap + SNAPSHOT_GRACE_SLOTS;
+        assert_eq!(staleness_bps(past_grace + STALE_SLOTS_PER_BPS - 1, snap), 0);
+        assert_eq!(staleness_bps(past_grace + STALE_SLOTS_PER_BPS, snap), 1);
+        assert_eq!(staleness_bps(past_grace + 200 * STALE_SLOTS_PER_BPS, snap), 200);
+    }
+
+    #[test]
+    fn test_staleness_never_updated() {
+        assert_eq!(staleness_bps(u64::MAX, 0), 0);
+    }
+
+    #[test]
+    fn test_crank_lag_penalty() {
+        let crank = 10_000;
+        assert_eq!(crank_lag_bps(crank, crank), 0);
+        assert_eq!(crank_lag_bps(crank + CRANK_GRACE_SLOTS, crank), 0);
+        assert_eq!(crank_lag_bps(crank + CRANK_GRACE_SLOTS + CRANK_SLOTS_PER_BPS, crank), 1);
+        assert_eq!(crank_lag_bps(crank + CRANK_GRACE_SLOTS + 40 * CRANK_SLOTS_PER_BPS, crank), 40);
+        // Never recorded
+        assert_eq!(crank_lag_bps(u64::MAX, 0), 0);
+    }
+
+    #[test]
+    fn test_decay_halflife() {
+        assert_eq!(decay_halflife(8_000, 0, 100), 8_000);
+        assert_eq!(decay_halflife(8_000, 100, 100), 4_000);
+        assert_eq!(decay_halflife(8_000, 300, 100), 1_000);
+        // Halfway through a halflife: linear step from 8000 toward 4000
+        assert_eq!(decay_halflife(8_000, 50, 100), 6_000);
+        assert_eq!(decay_halflife(8_000, 6_400, 100), 0);
+        assert_eq!(decay_halflife(u64::MAX, u64::MAX, 1), 0);
+        // Disabled signal
+        assert_eq!(decay_halflife(8_000, 0, 0), 0);
+    }
+
+    #[test]
+    fn test_decay_monotonic() {
+        let mut prev = u64::MAX;
+        for elapsed in 0..2_000 {
+            let v = decay_halflife(1_000_000, elapsed, 97);
+            assert!(v <= prev);
+            prev = v;
+        }
+    }
+
+    #[test]
+    fn test_liq_heat_penalty() {
+        assert_eq!(liq_heat_bps(0), 0);
+        assert_eq!(liq_heat_bps(LIQ_HEAT_SCALE), LIQ_PENALTY_BPS);
+        assert_eq!(liq_heat_bps(20 * LIQ_HEAT_SCALE), 200);
+        // Spike then decay: one halflife later the penalty halves
+        let heat = 20 * LIQ_HEAT_SCALE;
+        assert_eq!(liq_heat_bps(decay_halflife(heat, 9_000, 9_000)), 100);
+        assert_eq!(liq_heat_bps(u64::MAX), u64::MAX / LIQ_HEAT_SCALE);
+    }
+
+    #[test]
+    fn test_funding_skew() {
+        assert_eq!(funding_skew_bps(0, 1_000), 0);
+        assert_eq!(funding_skew_bps(3, 0), 0);
+        // Longs pay → quotes shift down; shorts pay → up
+        assert_eq!(funding_skew_bps(2, 10), 20);
+        assert_eq!(funding_skew_bps(-2, 10), -20);
+        assert_eq!(funding_skew_bps(i64::MAX, u64::MAX), i64::MAX);
+        assert_eq!(funding_skew_bps(i64::MIN, u64::MAX), i64::MIN);
+    }
+
+    #[test]
+    fn test_funding_skew_direction_on_price() {
+        let oracle = 100_000_000;
+        let spread = 50;
+        let skew = funding_skew_bps(1, 20).clamp(-spread, spread);
+        // Longs pay: selling into the settlement is what gets picked off
+        assert!(exec_price(oracle, spread as u64, skew, false) < exec_price(oracle, spread as u64, 0, false));
+        assert!(exec_price(oracle, spread as u64, skew, true) >= oracle);
+    }
+
+    // -------------------------------------------------------------------------
+    // Instruction-level helpers
+    // -------------------------------------------------------------------------
+
+    const LP: Pubkey = Pubkey::new_from_array([7u8; 32]);
+
+    fn init_data(funding_horizon: u32) -> Vec<u8> {
+        let mut d = vec![0x02, KIND_CREDIBILITY];
+        d.extend_from_slice(&params_data(funding_horizon));
+        d
+    }
+
+    fn update_params_data(params: &[u8]) -> Vec<u8> {
+        let mut d = vec![0x04];
+        d.extend_from_slice(params);
+        d
+    }
+
+    fn params_data(funding_horizon: u32) -> Vec<u8> {
+        let mut d = Vec::new();
+        d.extend_from_slice(&5u32.to_le_bytes()); // base_fee
+        d.extend_from_slice(&10u32.to_le_bytes()); // min_spread
+        d.extend_from_slice(&200u32.to_le_bytes()); // max_spread
+        d.extend_from_slice(&100u32.to_le_bytes()); // imbalance_k
+        d.extend_from_slice(&10_000_000_000_000u128.to_le_bytes()); // liquidity
+        d.extend_from_slice(&1_000_000_000_000u128.to_le_bytes()); // max_fill
+        d.extend_from_slice(&0u128.to_le_bytes()); // max_inventory
+        d.extend_from_slice(&2_160_000u32.to_le_bytes()); // age_halflife
+        d.extend_from_slice(&50u32.to_le_bytes()); // insurance_weight
+        d.push(IMPACT_NONE);
+        d.extend_from_slice(&0u32.to_le_bytes()); // impact_k
+        d.extend_from_slice(&0u32.to_le_bytes()); // skew_k
+        d.push(CURVE_LINEAR);
+        d.extend_from_slice(&[0u8; CURVE_POINTS * 2]);
+        d.extend_from_slice(&0u32.to_le_bytes()); // liq_halflife
+        d.extend_from_slice(&funding_horizon.to_le_bytes());
+        d.extend_from_slice(&0u16.to_le_bytes()); // oracle_jump_bps
+        d.extend_from_slice(&0u32.to_le_bytes()); // oracle_jump_window
+        d.extend_from_slice(&0u32.to_le_bytes()); // flow_k_bps
+        d.extend_from_slice(&0u32.to_le_bytes()); // twap_window_slots
+        d.extend_from_slice(&[0u8; SIGNALS * 2]); // signal_weights
+        d.extend_from_slice(&0u32.to_le_bytes()); // deficit_halflife
+        d.extend_from_slice(&0u16.to_le_bytes()); // liq_auction_bps
+        d.extend_from_slice(&0u32.to_le_bytes()); // liq_auction_slots
+        d.extend_from_slice(&0u32.to_le_bytes()); // peg_band_e6
+        d.extend_from_slice(&0u16.to_le_bytes()); // spot_weight_bps
+        d
+    }
+
+    const PROGRAM_ID: Pubkey = Pubkey::new_from_array([9u8; 32]);
+
+    #[derive(Clone)]
+    struct TestAccount {
+        key: Pubkey,
+        signer: bool,
+        lamports: u64,
+        data: Vec<u8>,
+        owner: Pubkey,
+    }
+
+    impl TestAccount {
+        fn new(key: Pubkey, signer: bool, lamports: u64, data: Vec<u8>) -> Self {
+            Self { key, signer, lamports, data, owner: PROGRAM_ID }
+        }
+    }
+
+    /// Serialize accounts in the runtime's aligned input format, deserialize them
+    /// with the real entrypoint helper and run the instruction. Going through the
+    /// runtime layout (rather than AccountInfo::new) makes duplicate accounts
+    /// share state and gives every account realloc headroom, exactly as on-chain.
+    fn run_accounts(accounts: &mut [TestAccount], data: &[u8]) -> ProgramResult {
+        use solana_program::entrypoint::{deserialize, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER};
+
+        install_stubs();
+        let mut input: Vec<u8> = Vec::new();
+        input.extend_from_slice(&(accounts.len() as u64).to_le_bytes());
+        for (i, a) in accounts.iter().enumerate() {
+            if let Some(dup) = accounts[..i].iter().position(|b| b.key == a.key) {
+                input.push(dup as u8);
+                input.extend_from_slice(&[0u8; 7]);
+                continue;
+            }
+            input.push(NON_DUP_MARKER);
+            input.extend_from_slice(&[a.signer as u8, 1, 0]); // signer, writable, executable
+            input.extend_from_slice(&[0u8; 4]); // original_data_len (filled by deserialize)
+            input.extend_from_slice(a.key.as_ref());
+            input.extend_from_slice(a.owner.as_ref());
+            input.extend_from_slice(&a.lamports.to_le_bytes());
+            input.extend_from_slice(&(a.data.len() as u64).to_le_bytes());
+            input.extend_from_slice(&a.data);
+            input.resize(input.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+            input.resize(input.len().next_multiple_of(8), 0);
+            input.extend_from_slice(&0u64.to_le_bytes()); // rent_epoch
+        }
+        input.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        input.extend_from_slice(data);
+        input.extend_from_slice(PROGRAM_ID.as_ref());
+
+        // u64-backed buffer for the 8-byte alignment deserialize relies on
+        let mut aligned = vec![0u64; input.len().div_ceil(8)];
+        let bytes = unsafe { std::slice::from_raw_parts_mut(aligned.as_mut_ptr() as *mut u8, input.len()) };
+        bytes.copy_from_slice(&input);
+
+        let (program_id, infos, ix_data) = unsafe { deserialize(bytes.as_mut_ptr()) };
+        let result = process_instruction(program_id, &infos, ix_data);
+        for (a, info) in accounts.iter_mut().zip(infos.iter()) {
+            a.lamports = info.lamports();
+            a.data = info.data.borrow().to_vec();
+        }
+        result
+    }
+
+    // Sysvar stubs so Clock::get / Rent::get work off-chain. The slot and
+    // unix time are per test thread.
+    thread_local! {
+        static SLOT: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+        static UNIX_TIME: std::cell::Cell<i64> = const { std::cell::Cell::new(0) };
+        static RETURN_DATA: std::cell::RefCell<Vec<u8>> = const { std::cell::RefCell::new(Vec::new()) };
+    }
+
+    struct TestStubs;
+
+    impl solana_program::program_stubs::SyscallStubs for TestStubs {
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            let clock = Clock { slot: SLOT.with(|s| s.get()), unix_timestamp: UNIX_TIME.with(|t| t.get()), ..Clock::default() };
+            unsafe { *(var_addr as *mut Clock) = clock };
+            solana_program::entrypoint::SUCCESS
+        }
+
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Rent) = Rent::default() };
+            solana_program::entrypoint::SUCCESS
+        }
+
+        fn sol_set_return_data(&self, data: &[u8]) {
+            RETURN_DATA.with(|r| *r.borrow_mut() = data.to_vec());
+        }
+    }
+
+    fn install_stubs() {
+        static STUBS: std::sync::Once = std::sync::Once::new();
+        STUBS.call_once(|| {
+            solana_program::program_stubs::set_syscall_stubs(Box::new(TestStubs));
+        });
+    }
+
+    fn set_slot(slot: u64) {
+        install_stubs();
+        SLOT.with(|s| s.set(slot));
+    }
+
+    fn set_unix_time(unix_time: i64) {
+        install_stubs();
+        UNIX_TIME.with(|t| t.set(unix_time));
+    }
+
+    /// A fresh, rent-exempt context account holding `data`.
+    fn ctx_account(data: Vec<u8>) -> TestAccount {
+        let lamports = Rent::default().minimum_balance(data.len());
+        TestAccount::new(Pubkey::new_unique(), false, lamports, data)
+    }
+
+    /// Run an [lp_pda, ctx] instruction against a context buffer.
+    fn run_lp_ix(ctx: &mut [u8], lp_key: &Pubkey, lp_signer: bool, data: &[u8]) -> ProgramResult {
+        let mut accounts = [
+            TestAccount::new(*lp_key, lp_signer, 0, vec![]),
+            ctx_account(ctx.to_vec()),
+        ];
+        let result = run_accounts(&mut accounts, data);
+        ctx.copy_from_slice(&accounts[1].data);
+        result
+    }
+
+    fn run_init(ctx: &mut [u8], data: &[u8]) -> ProgramResult {
+        run_lp_ix(ctx, &LP, true, data)
+    }
+
+    #[test]
+    fn test_init_legacy_account_is_v5() {
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        assert_eq!(read_u32(&ctx, CTX_BASE + CTX_VERSION_OFF), VERSION_V5);
+        assert!(!is_extended(&ctx));
+    }
+
+    #[test]
+    fn test_context_view_reads_the_account_bytes() {
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let context = MatcherContext::view(&ctx);
+        assert_eq!(context.magic.get(), MAGIC);
+        assert_eq!(context.version.get(), VERSION);
+        assert_eq!(context.kind, ctx[CTX_BASE + CTX_KIND_OFF]);
+        assert_eq!(context.lp_pda(), read_pubkey(&ctx, CTX_BASE + CTX_LP_PDA_OFF));
+        assert_eq!(context.max_spread_bps.get(), read_u32(&ctx, CTX_BASE + CTX_MAX_SPREAD_OFF));
+        let points: [u16; CURVE_POINTS] = std::array::from_fn(|i| read_u16(&ctx, CTX_BASE + CTX_CURVE_POINTS_OFF + i * 2));
+        assert_eq!(context.curve_points.map(|point| point.get()), points);
+
+        // Writes land at the field's offset and nowhere else
+        let before = ctx.clone();
+        MatcherContext::view_mut(&mut ctx).inventory_base.set(-42);
+        assert_eq!(read_i128(&ctx, CTX_BASE + CTX_INVENTORY_OFF), -42);
+        let off = CTX_BASE + CTX_INVENTORY_OFF;
+        assert_eq!((&ctx[..off], &ctx[off + 16..]), (&before[..off], &before[off + 16..]));
+    }
+
+    #[test]
+    fn test_extension_and_market_views_read_the_account_bytes() {
+        let slab = Pubkey::new_unique();
+        let mut ctx = init_shared(slab);
+        let ext = ContextExtension::view(&ctx);
+        assert_eq!(ext.market_slots.get(), read_u32(&ctx, EXT_BASE + EXT_MARKET_SLOTS_OFF));
+        assert_eq!(ext.percolator_program(), read_pubkey(&ctx, EXT_BASE + EXT_PERCOLATOR_OFF));
+        assert_eq!(MarketEntry::table(&ctx).len(), MAX_MARKETS);
+        assert_eq!(MarketEntry::table(&ctx)[0].slab(), slab);
+
+        // Writes land at the field's offset and nowhere else
+        let before = ctx.clone();
+        ContextExtension::view_mut(&mut ctx).rfq_size_left.set(-7);
+        let off = EXT_BASE + EXT_RFQ_SIZE_OFF;
+        assert_eq!(read_i128(&ctx, off), -7);
+        assert_eq!((&ctx[..off], &ctx[off + 16..]), (&before[..off], &before[off + 16..]));
+
+        let before = ctx.clone();
+        MarketEntry::table_mut(&mut ctx)[2].inventory.set(-42);
+        let off = MARKETS_BASE + 2 * MARKET_LEN + MKT_INVENTORY_OFF;
+        assert_eq!(read_i128(&ctx, off), -42);
+        assert_eq!((&ctx[..off], &ctx[off + 16..]), (&before[..off], &before[off + 16..]));
+    }
+
+    #[test]
+    fn test_init_extended_account_is_v6() {
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(30)).unwrap();
+        assert_eq!(read_u32(&ctx, CTX_BASE + CTX_VERSION_OFF), VERSION);
+        assert!(is_extended(&ctx));
+        assert_eq!(read_u32(&ctx, EXT_BASE + EXT_FUNDING_HORIZON_OFF), 30);
+    }
+
+    #[test]
+    fn test_plain_kind_prices_imbalance_only() {
+        set_slot(1_000);
+        let mut plain_init = init_data(0);
+        plain_init[1] = KIND_PLAIN;
+        let (mut plain, mut credibility) = (vec![0u8; CTX_LEN], vec![0u8; CTX_LEN]);
+        run_init(&mut plain, &plain_init).unwrap();
+        run_init(&mut credibility, &init_data(0)).unwrap();
+        assert_eq!(plain[CTX_BASE + CTX_KIND_OFF], KIND_PLAIN);
+        // An uninsured market with a long book, never cranked
+        for ctx in [&mut plain, &mut credibility] {
+            write_u128(ctx, CTX_BASE + CTX_TOTAL_OI_OFF, 1_000);
+            write_i128(ctx, CTX_BASE + CTX_INVENTORY_OFF, 2_000_000_000_000);
+        }
+
+        // min_spread plus imbalance, at full fill; credibility is at its max
+        let ret = run_preview(&plain, 100_000_000, 1_000).unwrap();
+        assert_eq!(read_u64(&ret, QUOTE_SPREAD_OFF), 10 + 20);
+        assert_eq!(read_u64(&ret, QUOTE_FILL_PCT_OFF), 100);
+        assert_eq!(read_u64(&ret, QUOTE_COVERAGE_OFF), 0);
+        assert_eq!(read_u64(&ret, QUOTE_EXEC_PRICE_OFF), 100_350_000);
+        let cred = run_preview(&credibility, 100_000_000, 1_000).unwrap();
+        assert_eq!(read_u64(&cred, QUOTE_SPREAD_OFF), 200);
+
+        // Match fills at the previewed price
+        run_lp_ix(&mut plain, &LP, true, &match_data(100_000_000, 1_000)).unwrap();
+        assert_eq!(read_u64(&plain, RET_EXEC_PRICE_OFF), 100_350_000);
+        assert_eq!(read_i128(&plain, CTX_BASE + CTX_INVENTORY_OFF), 2_000_000_001_000);
+
+        let mut other = init_data(0);
+        other[1] = 0;
+        assert_eq!(run_init(&mut vec![0u8; CTX_LEN], &other), Err(MatcherError::WrongKind.into()));
+    }
+
+    fn multi_init_data(weights: [u16; SIGNALS]) -> Vec<u8> {
+        let params = Params { signal_weights: weights, deficit_halflife: 1_000, liq_halflife: 100, ..Params::unpack(&params_data(0)) };
+        let mut d = vec![0x02, KIND_MULTI];
+        d.extend_from_slice(&params.pack());
+        d
+    }
+
+    #[test]
+    fn test_multi_kind_prices_blended_signals() {
+        set_slot(10_000);
+        let weights = [5_000, 2_000, 2_000, 1_000];
+        let mut multi = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut multi, &multi_init_data(weights)).unwrap();
+        assert_eq!(multi[CTX_BASE + CTX_KIND_OFF], KIND_MULTI);
+        assert_eq!(Params::load(&multi).signal_weights, weights);
+        assert_eq!(read_u32(&multi, EXT_BASE + EXT_DEFICIT_HALFLIFE_OFF), 1_000);
+
+        // 60% coverage on a brand-new market that took a deficit 1000 slots
+        // ago: 5000*6000 + 2000*0 + 2000*5000 + 1000*10000 over 10000 = 5000
+        write_u128(&mut multi, CTX_BASE + CTX_INSURANCE_OFF, 600);
+        write_u128(&mut multi, CTX_BASE + CTX_TOTAL_OI_OFF, 1_000);
+        write_u64(&mut multi, CTX_BASE + CTX_SNAPSHOT_SLOT_OFF, 10_000);
+        write_u64(&mut multi, CTX_BASE + CTX_LAST_DEFICIT_OFF, 9_000);
+        let ret = run_preview(&multi, 100_000_000, 1_000).unwrap();
+        assert_eq!(read_u64(&ret, QUOTE_TIER_SPREAD_OFF), 200 - 190 / 2);
+        assert_eq!(read_u64(&ret, QUOTE_COVERAGE_OFF), 6_000);
+        assert_eq!(read_u64(&ret, QUOTE_FILL_PCT_OFF), 100);
+
+        // Age is the only signal still short; earning it takes the spread in
+        write_u64(&mut multi, CTX_BASE + CTX_MARKET_AGE_OFF, 64 * 2_160_000);
+        let aged = run_preview(&multi, 100_000_000, 1_000).unwrap();
+        assert_eq!(read_u64(&aged, QUOTE_TIER_SPREAD_OFF), 200 - 190 * 7_000 / 10_000);
+
+        run_lp_ix(&mut multi, &LP, true, &match_data(100_000_000, 1_000)).unwrap();
+        assert_eq!(read_u64(&multi, RET_EXEC_PRICE_OFF), read_u64(&aged, QUOTE_EXEC_PRICE_OFF));
+    }
+
+    #[test]
+    fn test_multi_kind_validates_weights() {
+        let init = |ctx_len, data: &[u8]| run_init(&mut vec![0u8; ctx_len], data);
+        let weights_err = Err(MatcherError::InvalidSignalWeights.into());
+        assert_eq!(init(CTX_EXT_LEN, &multi_init_data([5_000, 2_000, 2_000, 999])), weights_err);
+        assert_eq!(init(CTX_EXT_LEN, &multi_init_data([0; SIGNALS])), weights_err);
+        assert_eq!(init(CTX_LEN, &multi_init_data([BPS as u16, 0, 0, 0])), Err(MatcherError::ExtensionRequired.into()));
+        assert_eq!(init(CTX_LEN, &[&[0x02, KIND_MULTI][..], &params_data(0)].concat()), weights_err);
+
+        // A weighted signal needs its halflife
+        let mut no_halflife = multi_init_data([5_000, 5_000, 0, 0]);
+        no_halflife[2 + 64..2 + 68].copy_from_slice(&0u32.to_le_bytes()); // age_halflife
+        assert_eq!(init(CTX_EXT_LEN, &no_halflife), weights_err);
+
+        // Other kinds may leave the weights unset, but a set of them still sums to 10000
+        let credibility = [&[0x02, KIND_CREDIBILITY][..], &multi_init_data([1, 0, 0, 0])[2..]].concat();
+        assert_eq!(init(CTX_EXT_LEN, &credibility), weights_err);
+
+        // And a multi-signal context can't have them cleared
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &multi_init_data([BPS as u16, 0, 0, 0])).unwrap();
+        assert_eq!(run_lp_ix(&mut ctx, &LP, true, &update_params_data(&params_data(0))), weights_err);
+    }
+
+    fn stable_init_data(peg_band_e6: u32) -> Vec<u8> {
+        let params = Params { min_spread_bps: 1, peg_band_e6, ..Params::unpack(&params_data(0)) };
+        let mut d = vec![0x02, KIND_STABLE];
+        d.extend_from_slice(&params.pack());
+        d
+    }
+
+    #[test]
+    fn test_stable_kind_prices_the_peg_band() {
+        set_slot(10_000);
+        // Fully covered, fresh snapshot, and long as much as the quoting depth
+        let fill = |ctx: &mut Vec<u8>| {
+            write_u128(ctx, CTX_BASE + CTX_INSURANCE_OFF, 3_000);
+            write_u128(ctx, CTX_BASE + CTX_TOTAL_OI_OFF, 1_000);
+            write_u64(ctx, CTX_BASE + CTX_SNAPSHOT_SLOT_OFF, 10_000);
+            write_u64(ctx, CTX_BASE + CTX_LAST_CRANK_OFF, 10_000);
+            write_i128(ctx, CTX_BASE + CTX_INVENTORY_OFF, 10_000_000_000_000);
+        };
+        let mut stable = vec![0u8; CTX_LEN];
+        run_init(&mut stable, &stable_init_data(200)).unwrap();
+        assert_eq!(stable[CTX_BASE + CTX_KIND_OFF], KIND_STABLE);
+        assert_eq!(read_u32(&stable, CTX_BASE + CTX_PEG_BAND_OFF), 200);
+        fill(&mut stable);
+
+        // At the peg: the 200e-6 band, no inventory terms
+        let ret = run_preview(&stable, 1_000_000, 1_000).unwrap();
+        assert_eq!(read_u64(&ret, QUOTE_TIER_SPREAD_OFF), 2);
+        assert_eq!(read_u64(&ret, QUOTE_IMBALANCE_OFF), 0);
+        assert_eq!(read_u64(&ret, QUOTE_SPREAD_OFF), 2);
+
+        // The credibility kind charges the same book its imbalance term
+        let mut credibility = vec![0u8; CTX_LEN];
+        run_init(&mut credibility, &[&[0x02, KIND_CREDIBILITY][..], &stable_init_data(200)[2..]].concat()).unwrap();
+        fill(&mut credibility);
+        let ret = run_preview(&credibility, 1_000_000, 1_000).unwrap();
+        assert_eq!(read_u64(&ret, QUOTE_IMBALANCE_OFF), 100);
+
+        // 1% off the peg widens the band by as much, rounded up
+        let depeg = run_preview(&stable, 990_000, 1_000).unwrap();
+        assert_eq!(read_u64(&depeg, QUOTE_SPREAD_OFF), 104);
+
+        run_lp_ix(&mut stable, &LP, true, &match_data(990_000, 1_000)).unwrap();
+        assert_eq!(read_u64(&stable, RET_EXEC_PRICE_OFF), read_u64(&depeg, QUOTE_EXEC_PRICE_OFF));
+
+        // A band wider than the peg is refused
+        let too_wide = run_init(&mut vec![0u8; CTX_LEN], &stable_init_data(1_000_001));
+        assert_eq!(too_wide, Err(MatcherError::SpreadTooWide.into()));
+    }
+
+    #[test]
+    fn test_init_funding_horizon_needs_extension() {
+        let mut ctx = vec![0u8; CTX_LEN];
+        assert_eq!(run_init(&mut ctx, &init_data(30)), Err(MatcherError::ExtensionRequired.into()));
+    }
+
+    #[test]
+    fn test_update_params_replaces_tunables() {
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        write_i128(&mut ctx, CTX_BASE + CTX_INVENTORY_OFF, -42);
+
+        let mut params = params_data(15);
+        params[4..8].copy_from_slice(&20u32.to_le_bytes()); // min_spread
+        params[8..12].copy_from_slice(&300u32.to_le_bytes()); // max_spread
+        run_lp_ix(&mut ctx, &LP, true, &update_params_data(&params)).unwrap();
+
+        let stored = Params::load(&ctx);
+        assert_eq!(stored, Params::unpack(&params));
+        assert_eq!(stored.min_spread_bps, 20);
+        assert_eq!(stored.max_spread_bps, 300);
+        assert_eq!(stored.funding_horizon, 15);
+        // State is untouched
+        assert_eq!(read_i128(&ctx, CTX_BASE + CTX_INVENTORY_OFF), -42);
+        assert_eq!(read_u64(&ctx, CTX_BASE + CTX_MAGIC_OFF), MAGIC);
+    }
+
+    #[test]
+    fn test_update_params_rejected_once_live() {
+        set_slot(1);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 1_000)).unwrap();
+        let before = ctx.clone();
+        assert_eq!(
+            run_lp_ix(&mut ctx, &LP, true, &update_params_data(&params_data(0))),
+            Err(MatcherError::MarketLive.into())
+        );
+        assert_eq!(ctx, before);
+    }
+
+    #[test]
+    fn test_oracle_decimals_scale_the_callers_price() {
+        set_slot(1);
+        let mut e6 = vec![0u8; CTX_LEN];
+        run_init(&mut e6, &init_data(0)).unwrap();
+        let mut e9 = e6.clone();
+        let bad = Err(ProgramError::InvalidInstructionData);
+        assert_eq!(run_lp_ix(&mut e9, &LP, true, &[0x1F, 5]), bad);
+        assert_eq!(run_lp_ix(&mut e9, &LP, true, &[0x1F, 19]), bad);
+        assert_eq!(
+            run_lp_ix(&mut e9, &Pubkey::new_unique(), true, &[0x1F, 9]),
+            Err(MatcherError::AuthorityMismatch.into())
+        );
+        run_lp_ix(&mut e9, &LP, true, &[0x1F, 9]).unwrap();
+        assert_eq!(e9[CTX_BASE + CTX_ORACLE_DECIMALS_OFF], 9);
+
+        // An e9 price quotes as its e6 equivalent, rounded down
+        let want = run_preview(&e6, 100_000_000, 1_000).unwrap();
+        assert_eq!(run_preview(&e9, 100_000_000_999, 1_000).unwrap(), want);
+
+        // Match records the e6 price and echoes the caller's
+        run_lp_ix(&mut e9, &LP, true, &match_data(100_000_000_999, 1_000)).unwrap();
+        assert_eq!(read_u64(&e9, RET_ORACLE_ECHO_OFF), 100_000_000_999);
+        assert_eq!(read_u64(&e9, RET_EXEC_PRICE_OFF), read_u64(&want, QUOTE_EXEC_PRICE_OFF));
+        assert_eq!(read_u64(&e9, CTX_BASE + CTX_LAST_ORACLE_OFF), 100_000_000);
+
+        // The scale is fixed once the market has traded
+        assert_eq!(run_lp_ix(&mut e9, &LP, true, &[0x1F, 6]), Err(MatcherError::MarketLive.into()));
+    }
+
+    fn breaker_params(jump_bps: u16, window: u32) -> Vec<u8> {
+        let mut params = params_data(0);
+        params[98..100].copy_from_slice(&jump_bps.to_le_bytes());
+        params[100..104].copy_from_slice(&window.to_le_bytes());
+        params
+    }
+
+    #[test]
+    fn test_oracle_jump_circuit_breaker() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        let mut init = vec![0x02, KIND_CREDIBILITY];
+        init.extend_from_slice(&breaker_params(500, 100)); // 5% per 100 slots
+        run_init(&mut ctx, &init).unwrap();
+
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 1)).unwrap();
+        assert_eq!(read_u64(&ctx, EXT_BASE + EXT_LAST_ORACLE_SLOT_OFF), 1_000);
+
+        // Exactly at the threshold is fine and re-anchors the last price
+        set_slot(1_050);
+        run_lp_ix(&mut ctx, &LP, true, &match_data(105_000_000, 1)).unwrap();
+
+        // Past it, in either direction, both Match and QuotePreview refuse
+        let before = ctx.clone();
+        for price in [110_250_001, 99_749_999] {
+            assert_eq!(
+                run_lp_ix(&mut ctx, &LP, true, &match_data(price, 1)),
+                Err(MatcherError::OracleJump.into())
+            );
+            assert_eq!(run_preview(&ctx, price, 1), Err(MatcherError::OracleJump.into()));
+        }
+        assert_eq!(ctx, before);
+
+        // Once the window lapses the next print re-anchors
+        set_slot(1_151);
+        run_lp_ix(&mut ctx, &LP, true, &match_data(200_000_000, 1)).unwrap();
+        assert_eq!(read_u64(&ctx, CTX_BASE + CTX_LAST_ORACLE_OFF), 200_000_000);
+    }
+
+    #[test]
+    fn test_oracle_breaker_validation() {
+        for (jump, window) in [(500, 0), (0, 100)] {
+            let mut ctx = vec![0u8; CTX_EXT_LEN];
+            let mut init = vec![0x02, KIND_CREDIBILITY];
+            init.extend_from_slice(&breaker_params(jump, window));
+            assert_eq!(run_init(&mut ctx, &init), Err(MatcherError::InvalidOracleBreaker.into()));
+        }
+        let mut v5 = vec![0u8; CTX_LEN];
+        let mut init = vec![0x02, KIND_CREDIBILITY];
+        init.extend_from_slice(&breaker_params(500, 100));
+        assert_eq!(run_init(&mut v5, &init), Err(MatcherError::ExtensionRequired.into()));
+
+        // Off by default; a v6 context with no breaker ignores any jump
+        set_slot(1);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 1)).unwrap();
+        run_lp_ix(&mut ctx, &LP, true, &match_data(900_000_000, 1)).unwrap();
+    }
+
+    #[test]
+    fn test_params_pack_roundtrip() {
+        let mut params = params_data(77);
+        params[72] = IMPACT_SQRT;
+        params[81] = CURVE_PIECEWISE;
+        params[82..90].copy_from_slice(&[1, 0, 2, 0, 3, 0, 4, 0]);
+        params[98..104].copy_from_slice(&[0xF4, 0x01, 10, 0, 0, 0]);
+        params[104..108].copy_from_slice(&25u32.to_le_bytes());
+        params[108..112].copy_from_slice(&300u32.to_le_bytes());
+        params[112..120].copy_from_slice(&[0x10, 0x27, 0, 0, 0, 0, 0, 0]);
+        params[120..124].copy_from_slice(&900u32.to_le_bytes());
+        params[124..130].copy_from_slice(&[0x96, 0, 20, 0, 0, 0]);
+        assert_eq!(params.len(), PARAMS_MAX_LEN);
+        let unpacked = Params::unpack(&params);
+        assert_eq!(unpacked.pack().to_vec(), params);
+        assert_eq!(Params::unpack(&unpacked.pack()), unpacked);
+    }
+
+    fn propose_data(params: &[u8]) -> Vec<u8> {
+        let mut d = vec![0x09];
+        d.extend_from_slice(params);
+        d
+    }
+
+    fn run_commit(ctx: &mut Vec<u8>) -> ProgramResult {
+        let mut accounts = [TestAccount::new(Pubkey::new_unique(), false, 0, ctx.clone())];
+        let result = run_accounts(&mut accounts, &[0x0A]);
+        *ctx = accounts[0].data.clone();
+        result
+    }
+
+    #[test]
+    fn test_propose_commit_after_timelock() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let old = Params::load(&ctx);
+
+        let mut params = params_data(0);
+        params[0..4].copy_from_slice(&40u32.to_le_bytes()); // base_fee
+        run_lp_ix(&mut ctx, &LP, true, &propose_data(&params)).unwrap();
+        let activation = 1_000 + PARAMS_TIMELOCK_SLOTS;
+        assert_eq!(read_u64(&ctx, EXT_BASE + EXT_PENDING_SLOT_OFF), activation);
+        assert_eq!(Params::load(&ctx), old);
+
+        // Too early: nothing changes, and matches still price with the old fee
+        set_slot(activation - 1);
+        assert_eq!(run_commit(&mut ctx), Err(MatcherError::TimelockActive.into()));
+        assert_eq!(Params::load(&ctx), old);
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 1_000)).unwrap();
+        let old_price = read_u64(&ctx, RET_EXEC_PRICE_OFF);
+
+        // On time: anyone may apply it, exactly once
+        set_slot(activation);
+        run_commit(&mut ctx).unwrap();
+        assert_eq!(Params::load(&ctx), Params::unpack(&params));
+        assert_eq!(read_u64(&ctx, EXT_BASE + EXT_PENDING_SLOT_OFF), 0);
+        assert_eq!(run_commit(&mut ctx), Err(MatcherError::NoPendingProposal.into()));
+
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 1_000)).unwrap();
+        assert!(read_u64(&ctx, RET_EXEC_PRICE_OFF) > old_price);
+    }
+
+    #[test]
+    fn test_propose_cancel_and_guards() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+
+        let data = propose_data(&params_data(0));
+        assert_eq!(run_lp_ix(&mut ctx, &LP, false, &data), Err(ProgramError::MissingRequiredSignature));
+        let mut bad = params_data(0);
+        bad[4..8].copy_from_slice(&500u32.to_le_bytes()); // min_spread > max_spread
+        assert_eq!(
+            run_lp_ix(&mut ctx, &LP, true, &propose_data(&bad)),
+            Err(MatcherError::SpreadOrder.into())
+        );
+
+        run_lp_ix(&mut ctx, &LP, true, &data).unwrap();
+        run_lp_ix(&mut ctx, &LP, true, &[0x09]).unwrap();
+        assert!(ctx[EXT_BASE + EXT_PENDING_SLOT_OFF..EXT_BASE + EXT_PENDING_PARAMS_OFF + PENDING_HEAD_LEN]
+            .iter()
+            .all(|b| *b == 0));
+        assert_eq!(read_pending_params(&ctx), [0u8; PARAMS_MAX_LEN]);
+        set_slot(1_000 + PARAMS_TIMELOCK_SLOTS);
+        assert_eq!(run_commit(&mut ctx), Err(MatcherError::NoPendingProposal.into()));
+
+        // v5 has nowhere to queue a proposal
+        let mut v5 = vec![0u8; CTX_LEN];
+        run_init(&mut v5, &init_data(0)).unwrap();
+        assert_eq!(run_lp_ix(&mut v5, &LP, true, &data), Err(MatcherError::ExtensionRequired.into()));
+        assert_eq!(run_commit(&mut v5), Err(MatcherError::NoPendingProposal.into()));
+    }
+
+    #[test]
+    fn test_update_params_rejects_min_above_max() {
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let before = ctx.clone();
+
+        let mut params = params_data(0);
+        params[4..8].copy_from_slice(&500u32.to_le_bytes()); // min_spread > max_spread
+        assert_eq!(
+            run_lp_ix(&mut ctx, &LP, true, &update_params_data(&params)),
+            Err(MatcherError::SpreadOrder.into())
+        );
+        assert_eq!(ctx, before);
+    }
+
+    #[test]
+    fn test_init_requires_rent_exempt_layout_sized_context() {
+        let init = |ctx: TestAccount| {
+            let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx];
+            let result = run_accounts(&mut accounts, &init_data(0));
+            if result.is_err() {
+                assert!(accounts[1].data.iter().all(|b| *b == 0));
+            }
+            result
+        };
+
+        assert_eq!(init(ctx_account(vec![0u8; CTX_LEN - 1])), Err(ProgramError::AccountDataTooSmall));
+        assert_eq!(init(ctx_account(vec![0u8; CTX_LEN + 1])), Err(ProgramError::InvalidAccountData));
+        assert_eq!(init(ctx_account(vec![0u8; CTX_EXT_LEN - 1])), Err(ProgramError::InvalidAccountData));
+
+        for len in [CTX_LEN, CTX_EXT_LEN] {
+            let mut short = ctx_account(vec![0u8; len]);
+            short.lamports -= 1;
+            assert_eq!(init(short), Err(ProgramError::AccountNotRentExempt));
+            init(ctx_account(vec![0u8; len])).unwrap();
+        }
+        // Larger v6 accounts leave room to grow
+        init(ctx_account(vec![0u8; CTX_EXT_LEN + 64])).unwrap();
+    }
+
+    #[test]
+    fn test_init_rejects_nonsense_params() {
+        let patch = |off: usize, bytes: &[u8]| {
+            let mut d = init_data(0);
+            d[2 + off..2 + off + bytes.len()].copy_from_slice(bytes);
+            d
+        };
+        let mut no_liquidity = patch(16, &0u128.to_le_bytes());
+        no_liquidity[2 + 12..2 + 16].copy_from_slice(&0u32.to_le_bytes()); // imbalance_k off
+        let mut impact_no_liquidity = no_liquidity.clone();
+        impact_no_liquidity[2 + 72] = IMPACT_LINEAR;
+        impact_no_liquidity[2 + 73..2 + 77].copy_from_slice(&1u32.to_le_bytes()); // impact_k
+        let mut skew_no_liquidity = no_liquidity.clone();
+        skew_no_liquidity[2 + 77..2 + 81].copy_from_slice(&1u32.to_le_bytes()); // skew_k
+
+        let cases = [
+            (patch(4, &500u32.to_le_bytes()), MatcherError::SpreadOrder),
+            (patch(8, &10_001u32.to_le_bytes()), MatcherError::SpreadTooWide),
+            (patch(0, &10_001u32.to_le_bytes()), MatcherError::FeeTooHigh),
+            (patch(68, &10_001u32.to_le_bytes()), MatcherError::InsuranceWeightTooHigh),
+            (patch(16, &0u128.to_le_bytes()), MatcherError::LiquidityRequired),
+            (impact_no_liquidity, MatcherError::LiquidityRequired),
+            (skew_no_liquidity, MatcherError::LiquidityRequired),
+        ];
+        for (data, err) in cases {
+            let mut ctx = vec![0u8; CTX_LEN];
+            assert_eq!(run_init(&mut ctx, &data), Err(err.into()));
+            assert_eq!(ctx, vec![0u8; CTX_LEN], "{:?} left the context touched", err);
+        }
+
+        // Zero liquidity is fine once every depth-scaled term is off, and the
+        // bounds themselves are inclusive
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &no_liquidity).unwrap();
+        let mut edge = patch(8, &10_000u32.to_le_bytes());
+        edge[2..6].copy_from_slice(&10_000u32.to_le_bytes());
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &edge).unwrap();
+    }
+
+    #[test]
+    fn test_update_params_requires_lp_signature() {
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let data = update_params_data(&params_data(0));
+        assert_eq!(
+            run_lp_ix(&mut ctx, &LP, false, &data),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            run_lp_ix(&mut ctx, &Pubkey::new_unique(), true, &data),
+            Err(MatcherError::AuthorityMismatch.into())
+        );
+    }
+
+    #[test]
+    fn test_update_params_uninitialized() {
+        let mut ctx = vec![0u8; CTX_LEN];
+        assert_eq!(
+            run_lp_ix(&mut ctx, &LP, true, &update_params_data(&params_data(0))),
+            Err(MatcherError::NotInitialized.into())
+        );
+    }
+
+    #[test]
+    fn test_close_reclaims_rent_and_clears_magic() {
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+
+        let mut accounts = [
+            TestAccount::new(LP, true, 0, vec![]),
+            TestAccount::new(Pubkey::new_unique(), false, 3_118_080, ctx),
+            TestAccount::new(Pubkey::new_unique(), false, 1_000, vec![]),
+        ];
+        run_accounts(&mut accounts, &[0x05]).unwrap();
+
+        assert_eq!(accounts[1].lamports, 0);
+        assert_eq!(accounts[2].lamports, 3_119_080);
+        assert!(accounts[1].data.iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_close_requires_lp_signature() {
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+
+        let mut accounts = [
+            TestAccount::new(LP, false, 0, vec![]),
+            TestAccount::new(Pubkey::new_unique(), false, 3_118_080, ctx.clone()),
+            TestAccount::new(Pubkey::new_unique(), false, 0, vec![]),
+        ];
+        assert_eq!(run_accounts(&mut accounts, &[0x05]), Err(ProgramError::MissingRequiredSignature));
+        assert_eq!(accounts[1].lamports, 3_118_080);
+        assert_eq!(accounts[1].data, ctx);
+
+        accounts[0] = TestAccount::new(Pubkey::new_unique(), true, 0, vec![]);
+        assert_eq!(run_accounts(&mut accounts, &[0x05]), Err(MatcherError::AuthorityMismatch.into()));
+        assert_eq!(accounts[2].lamports, 0);
+    }
+
+    #[test]
+    fn test_close_rejects_self_destination() {
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let ctx_key = Pubkey::new_unique();
+        let mut accounts = [
+            TestAccount::new(LP, true, 0, vec![]),
+            TestAccount::new(ctx_key, false, 3_118_080, ctx),
+            TestAccount::new(ctx_key, false, 3_118_080, vec![]),
+        ];
+        assert_eq!(run_accounts(&mut accounts, &[0x05]), Err(MatcherError::DuplicateAccount.into()));
+    }
+
+    #[test]
+    fn test_close_to_authority_itself() {
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let mut accounts = [
+            TestAccount::new(LP, true, 1_000, vec![]),
+            ctx_account(ctx),
+            TestAccount::new(LP, true, 1_000, vec![]),
+        ];
+        let rent = accounts[1].lamports;
+        run_accounts(&mut accounts, &[0x05]).unwrap();
+        assert_eq!(accounts[0].lamports, 1_000 + rent);
+    }
+
+    #[test]
+    fn test_aliased_accounts_rejected() {
+        set_slot(1);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let ctx_key = Pubkey::new_unique();
+        let dup = Err(MatcherError::DuplicateAccount.into());
+
+        // LP PDA doubling as the context
+        let mut accounts = [TestAccount::new(ctx_key, true, 0, ctx.clone()), TestAccount::new(ctx_key, true, 0, vec![])];
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1)), dup);
+        assert_eq!(run_accounts(&mut accounts, &update_params_data(&params_data(0))), dup);
+        assert_eq!(run_accounts(&mut accounts, &[0x06, 1]), dup);
+        assert_eq!(accounts[0].data, ctx);
+
+        // An account repeated however far down Match's list it comes
+        let slab = Pubkey::new_unique();
+        let mut accounts = vec![TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx.clone()), slab_account(slab, vec![])];
+        accounts.extend((0..9 + MAX_FEEDS + MAX_SIGNAL_ACCOUNTS).map(|_| TestAccount::new(Pubkey::new_unique(), false, 0, vec![])));
+        accounts.push(slab_account(slab, vec![]));
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1)), dup);
+        assert_eq!(accounts[1].data, ctx);
+
+        // Slab doubling as the context in UpdateCredibility
+        let mut accounts = [
+            TestAccount::new(ctx_key, false, 0, ctx.clone()),
+            TestAccount::new(ctx_key, false, 0, vec![]),
+            TestAccount::new(solana_program::sysvar::clock::id(), false, 0, vec![]),
+        ];
+        assert_eq!(run_accounts(&mut accounts, &[0x03]), dup);
+
+        // Slab doubling as the context, or the LP PDA named as a separate authority, at Init
+        let mut accounts = [
+            TestAccount::new(LP, false, 0, vec![]),
+            TestAccount { key: ctx_key, ..ctx_account(vec![0u8; CTX_EXT_LEN]) },
+            TestAccount::new(ctx_key, false, 0, vec![]),
+        ];
+        assert_eq!(run_accounts(&mut accounts, &init_data(0)), dup);
+        let mut accounts = [
+            TestAccount::new(LP, false, 0, vec![]),
+            ctx_account(vec![0u8; CTX_EXT_LEN]),
+            slab_account(Pubkey::new_unique(), vec![]),
+            TestAccount::new(LP, false, 0, vec![]),
+        ];
+        assert_eq!(run_accounts(&mut accounts, &init_data(0)), dup);
+        assert!(accounts[1].data.iter().all(|b| *b == 0));
+    }
+
+    fn match_data(oracle_price_e6: u64, trade_size: i128) -> Vec<u8> {
+        let mut d = vec![0x00];
+        d.extend_from_slice(&1u64.to_le_bytes()); // req_id
+        d.extend_from_slice(&0u16.to_le_bytes()); // lp_idx
+        d.extend_from_slice(&0u64.to_le_bytes()); // lp_account_id
+        d.extend_from_slice(&oracle_price_e6.to_le_bytes());
+        d.extend_from_slice(&trade_size.to_le_bytes());
+        d.resize(CALL_LEN, 0);
+        d
+    }
+
+    fn preview_data(oracle_price_e6: u64, trade_size: i128) -> Vec<u8> {
+        let mut d = vec![0x08];
+        d.extend_from_slice(&oracle_price_e6.to_le_bytes());
+        d.extend_from_slice(&trade_size.to_le_bytes());
+        d
+    }
+
+    /// Run QuotePreview against a context; returns the return data.
+    fn run_preview(ctx: &[u8], oracle_price_e6: u64, trade_size: i128) -> Result<Vec<u8>, ProgramError> {
+        let mut accounts = [TestAccount::new(Pubkey::new_unique(), false, 0, ctx.to_vec())];
+        RETURN_DATA.with(|r| r.borrow_mut().clear());
+        run_accounts(&mut accounts, &preview_data(oracle_price_e6, trade_size))?;
+        assert_eq!(accounts[0].data, ctx);
+        Ok(RETURN_DATA.with(|r| r.borrow().clone()))
+    }
+
+    #[test]
+    fn test_quote_preview_matches_match_without_writing() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        write_u128(&mut ctx, CTX_BASE + CTX_INSURANCE_OFF, 500);
+        write_u128(&mut ctx, CTX_BASE + CTX_TOTAL_OI_OFF, 1_000);
+        write_u64(&mut ctx, CTX_BASE + CTX_SNAPSHOT_SLOT_OFF, 1_000);
+        write_i128(&mut ctx, CTX_BASE + CTX_INVENTORY_OFF, 2_000_000_000_000);
+
+        // Anyone can preview: no signer, just the context, which stays untouched
+        let ret = run_preview(&ctx, 100_000_000, 1_000).unwrap();
+        assert_eq!(ret.len(), QUOTE_LEN);
+
+        assert_eq!(read_u64(&ret, QUOTE_COVERAGE_OFF), 5_000);
+        assert_eq!(read_u64(&ret, QUOTE_TIER_OFF), TIER_NORMAL as u64);
+        assert_eq!(read_u64(&ret, QUOTE_FILL_PCT_OFF), 100);
+        assert_eq!(read_u64(&ret, QUOTE_IMBALANCE_OFF), 20); // 100 * 2e12 / 1e13
+        assert_eq!(read_u64(&ret, QUOTE_FEE_OFF), 5);
+        let components = read_u64(&ret, QUOTE_TIER_SPREAD_OFF)
+            + read_u64(&ret, QUOTE_IMBALANCE_OFF)
+            + read_u64(&ret, QUOTE_IMPACT_OFF)
+            + read_u64(&ret, QUOTE_STALE_OFF)
+            + read_u64(&ret, QUOTE_CRANK_OFF)
+            + read_u64(&ret, QUOTE_LIQ_OFF)
+            + read_u64(&ret, QUOTE_FLOW_OFF);
+        assert_eq!(read_u64(&ret, QUOTE_SPREAD_OFF), components.clamp(1, 200));
+        assert_eq!(read_i128(&ret, QUOTE_NEW_INVENTORY_OFF), 2_000_000_001_000);
+        assert_eq!(read_i128(&ret, QUOTE_FILL_SIZE_OFF), 1_000);
+
+        // Coverage took the spread in from max_spread
+        assert_eq!(
+            read_u64(&ret, QUOTE_TIER_SPREAD_OFF) + read_u64(&ret, QUOTE_DISCOUNT_OFF),
+            200
+        );
+        assert!(read_u64(&ret, QUOTE_DISCOUNT_OFF) > 0);
+
+        // The committed match prices identically and returns the same breakdown
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 1_000)).unwrap();
+        assert_eq!(read_u64(&ctx, RET_EXEC_PRICE_OFF), read_u64(&ret, QUOTE_EXEC_PRICE_OFF));
+        let match_ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(&match_ret[RET_LEN..MATCH_RET_FILL_SEQ_OFF], &ret[..]);
+        assert_eq!(read_i128(&ctx, CTX_BASE + CTX_INVENTORY_OFF), 2_000_000_001_000);
+    }
+
+    #[test]
+    fn test_match_emits_return_data() {
+        set_slot(1);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+
+        RETURN_DATA.with(|r| r.borrow_mut().clear());
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 1_000)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(ret.len(), MATCH_RET_LEN);
+        assert_eq!(read_u32(&ret, RET_FLAGS_OFF), FLAG_VALID);
+        assert_eq!(read_i128(&ret, RET_EXEC_SIZE_OFF), 1_000);
+        // Without a band the hedger is told to buy back all the LP sold
+        assert_eq!(read_i128(&ret, MATCH_RET_HEDGE_OFF), 1_000);
+        // Legacy callers still find the same bytes at the front of the account
+        assert_eq!(&ctx[..RET_LEN], &ret[..RET_LEN]);
+
+        // Return-data-only: the account prefix is left alone
+        assert_eq!(run_lp_ix(&mut ctx, &LP, false, &[0x0F, 1]), Err(ProgramError::MissingRequiredSignature));
+        run_lp_ix(&mut ctx, &LP, true, &[0x0F, 1]).unwrap();
+        assert_eq!(ctx[CTX_BASE + CTX_FLAGS_OFF], CTX_FLAG_RETURN_DATA_ONLY);
+        let prefix = ctx[..RET_LEN].to_vec();
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, -2_000)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_i128(&ret, RET_EXEC_SIZE_OFF), -2_000);
+        assert_eq!(ctx[..RET_LEN], prefix[..]);
+
+        run_lp_ix(&mut ctx, &LP, true, &[0x0F, 0]).unwrap();
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 3_000)).unwrap();
+        assert_eq!(read_i128(&ctx, RET_EXEC_SIZE_OFF), 3_000);
+    }
+
+    #[test]
+    fn test_fill_sequence_numbers() {
+        set_slot(1);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        write_u128(&mut ctx, CTX_BASE + CTX_MAX_INVENTORY_OFF, 1_000);
+        run_lp_ix(&mut ctx, &LP, true, &[0x10, 1]).unwrap();
+
+        let last_seq = || RETURN_DATA.with(|r| read_u64(&r.borrow(), MATCH_RET_FILL_SEQ_OFF));
+        for expected in 1..=3 {
+            run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 10)).unwrap();
+            assert_eq!(last_seq(), expected);
+            assert_eq!(read_u64(&ctx, EXT_BASE + EXT_FILL_SEQ_OFF), expected);
+        }
+
+        // Neither a soft reject nor a failed match consumes a number
+        run_lp_ix(&mut ctx, &LP, true, &[0x0B, 1]).unwrap();
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 10)).unwrap();
+        assert_eq!(last_seq(), 0);
+        run_lp_ix(&mut ctx, &LP, true, &[0x0B, 0]).unwrap();
+        assert!(run_lp_ix(&mut ctx, &LP, true, &match_data(0, 10)).is_err());
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, -10)).unwrap();
+        assert_eq!(last_seq(), 4);
+
+        // v5 contexts don't count
+        let mut v5 = vec![0u8; CTX_LEN];
+        run_init(&mut v5, &init_data(0)).unwrap();
+        run_lp_ix(&mut v5, &LP, true, &match_data(100_000_000, 10)).unwrap();
+        assert_eq!(last_seq(), 0);
+    }
+
+    #[test]
+    fn test_telemetry_counters() {
+        set_slot(1);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+
+        let (mut notional, mut fees) = (0u128, 0u128);
+        for size in [2_000_000i128, -3_000_000, 5] {
+            run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, size)).unwrap();
+            let fill = size.unsigned_abs() * read_u64(&ctx, RET_EXEC_PRICE_OFF) as u128 / 1_000_000;
+            notional += fill;
+            fees += fill * 5 / 10_000; // base_fee_bps
+        }
+        assert_eq!(read_u64(&ctx, EXT_BASE + EXT_FILL_SEQ_OFF), 3);
+        assert_eq!(read_u128(&ctx, EXT_BASE + EXT_CUM_NOTIONAL_OFF), notional);
+        assert_eq!(read_u128(&ctx, EXT_BASE + EXT_CUM_FEES_OFF), fees);
+        assert!(fees > 0);
+
+        // v5 contexts have no counters and still match
+        let mut v5 = vec![0u8; CTX_LEN];
+        run_init(&mut v5, &init_data(0)).unwrap();
+        run_lp_ix(&mut v5, &LP, true, &match_data(100_000_000, 10)).unwrap();
+    }
+
+    #[test]
+    fn test_flow_penalty_from_gross_long_short() {
+        assert_eq!(flow_bps(100, 0, 0, 10), 0);
+        assert_eq!(flow_bps(100, 300, 100, 10), 50); // 100 * 200 / 400
+        assert_eq!(flow_bps(100, 300, 100, -10), 0); // against the flow
+        assert_eq!(flow_bps(100, 100, 300, -10), 50);
+        assert_eq!(flow_bps(0, 300, 100, 10), 0);
+        assert_eq!(flow_bps(100, u128::MAX, 0, 1), 100);
+
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+
+        // Gross flow accrues per side while the net goes back to zero
+        for size in [3_000_000i128, -1_000_000, -2_000_000] {
+            run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, size)).unwrap();
+        }
+        assert_eq!(read_i128(&ctx, CTX_BASE + CTX_INVENTORY_OFF), 0);
+        assert_eq!(read_u128(&ctx, EXT_BASE + EXT_GROSS_LONG_OFF), 3_000_000);
+        assert_eq!(read_u128(&ctx, EXT_BASE + EXT_GROSS_SHORT_OFF), 3_000_000);
+
+        // Set the coefficient through the timelock; the proposal spans the tail slot
+        let mut params = params_data(0);
+        params[104..108].copy_from_slice(&90u32.to_le_bytes());
+        run_lp_ix(&mut ctx, &LP, true, &propose_data(&params)).unwrap();
+        set_slot(1_000 + PARAMS_TIMELOCK_SLOTS);
+        run_commit(&mut ctx).unwrap();
+        assert_eq!(Params::load(&ctx).flow_k_bps, 90);
+        assert_eq!(read_pending_params(&ctx), [0u8; PARAMS_MAX_LEN]);
+
+        // Balanced so far: no surcharge either way
+        let ret = run_preview(&ctx, 100_000_000, 1_000).unwrap();
+        assert_eq!(read_u64(&ret, QUOTE_FLOW_OFF), 0);
+
+        // One more buy tips gross flow long: buys pay, sells don't
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 3_000_000)).unwrap();
+        let buy = run_preview(&ctx, 100_000_000, 1_000).unwrap();
+        assert_eq!(read_u64(&buy, QUOTE_FLOW_OFF), 30); // 90 * 3e6 / 9e6
+        let sell = run_preview(&ctx, 100_000_000, -1_000).unwrap();
+        assert_eq!(read_u64(&sell, QUOTE_FLOW_OFF), 0);
+
+        // v5 has nowhere to keep gross flow
+        let mut v5 = vec![0u8; CTX_LEN];
+        let mut init = vec![0x02, KIND_CREDIBILITY];
+        init.extend_from_slice(&params);
+        assert_eq!(run_init(&mut v5, &init), Err(MatcherError::ExtensionRequired.into()));
+    }
+
+    #[test]
+    fn test_stats_ring_records_recent_fills() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let mut accounts = [
+            TestAccount::new(LP, true, 0, vec![]),
+            ctx_account(ctx),
+            ctx_account(vec![0u8; STATS_LEN]),
+        ];
+        run_accounts(&mut accounts, &[0x12]).unwrap();
+        let [lp, ctx, stats] = accounts;
+        assert_eq!(read_u64(&stats.data, STATS_MAGIC_OFF), STATS_MAGIC);
+        assert_eq!(read_pubkey(&stats.data, STATS_CTX_OFF), ctx.key);
+        assert_eq!(read_pubkey(&ctx.data, EXT_BASE + EXT_STATS_OFF), stats.key);
+
+        // [lp, ctx, slab, stats]; wraps after STATS_CAPACITY fills
+        let slab = TestAccount::new(Pubkey::new_unique(), false, 0, vec![]);
+        let mut accounts = [lp, ctx, slab, stats];
+        let fills = STATS_CAPACITY as u64 + 6;
+        for i in 1..=fills {
+            set_slot(1_000 + i);
+            let size = if i % 2 == 0 { -1_000 } else { 2_000 };
+            run_accounts(&mut accounts, &match_data(100_000_000, size)).unwrap();
+        }
+        let stats = &accounts[3].data;
+        assert_eq!(read_u32(stats, STATS_COUNT_OFF), STATS_CAPACITY as u32);
+        assert_eq!(read_u32(stats, STATS_HEAD_OFF), 6);
+        let newest = STATS_HEADER_LEN + 5 * FILL_LEN;
+        assert_eq!(read_u64(stats, newest + FILL_SEQ_OFF), fills);
+        assert_eq!(read_u64(stats, newest + FILL_SLOT_OFF), 1_000 + fills);
+        assert_eq!(read_i128(stats, newest + FILL_SIZE_OFF), -1_000);
+        assert_eq!(read_u64(stats, newest + FILL_PRICE_OFF), read_u64(&accounts[1].data, CTX_BASE + CTX_LAST_EXEC_OFF));
+        assert!(read_u64(stats, newest + FILL_SPREAD_OFF) > 0);
+        let oldest = STATS_HEADER_LEN + 6 * FILL_LEN;
+        assert_eq!(read_u64(stats, oldest + FILL_SEQ_OFF), 7);
+
+        // Only the bound account is accepted
+        let mut other = vec![0u8; STATS_LEN];
+        other[..STATS_HEADER_LEN].copy_from_slice(&stats[..STATS_HEADER_LEN]);
+        let bound = std::mem::replace(&mut accounts[3], ctx_account(other));
+        assert_eq!(
+            run_accounts(&mut accounts, &match_data(100_000_000, 10)),
+            Err(MatcherError::StatsMismatch.into())
+        );
+
+        // Close takes the stats account with it
+        accounts[3] = bound;
+        let reclaimed = accounts[1].lamports + accounts[3].lamports;
+        accounts[2] = TestAccount::new(Pubkey::new_unique(), false, 0, vec![]);
+        run_accounts(&mut accounts, &[0x05]).unwrap();
+        assert_eq!(accounts[2].lamports, reclaimed);
+        assert_eq!(accounts[3].lamports, 0);
+        assert!(accounts[3].data.iter().all(|b| *b == 0));
+
+        // v5 has nowhere to record the binding
+        let mut v5 = vec![0u8; CTX_LEN];
+        run_init(&mut v5, &init_data(0)).unwrap();
+        let mut accounts = [
+            TestAccount::new(LP, true, 0, vec![]),
+            ctx_account(v5),
+            ctx_account(vec![0u8; STATS_LEN]),
+        ];
+        assert_eq!(run_accounts(&mut accounts, &[0x12]), Err(MatcherError::ExtensionRequired.into()));
+    }
+
+    #[test]
+    fn test_fee_ledger_records_withdrawals_up_to_accrued_fees() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; STATS_LEDGER_LEN])];
+        run_accounts(&mut accounts, &[0x12]).unwrap();
+        let [lp, ctx, stats] = accounts;
+
+        // Every fill accrues its fee, and with a stats account bound none can
+        // skip it
+        let slab = TestAccount::new(Pubkey::new_unique(), false, 0, vec![]);
+        let mut accounts = [lp, ctx, slab];
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000_000)), Err(MatcherError::StatsRequired.into()));
+        let [lp, ctx, slab] = accounts;
+        let mut accounts = [lp, ctx, slab, stats];
+        for _ in 0..3 {
+            run_accounts(&mut accounts, &match_data(100_000_000, 1_000_000)).unwrap();
+        }
+        let accrued = read_u128(&accounts[1].data, EXT_BASE + EXT_CUM_FEES_OFF);
+        let notional = read_u128(&accounts[1].data, EXT_BASE + EXT_CUM_NOTIONAL_OFF);
+        assert!(accrued > 0);
+        assert_eq!(accrued, 3 * (notional / 3 * 5 / BPS as u128));
+        let [lp, ctx, _, stats] = accounts;
+
+        let withdraw = |amount: u128| [&[0x24][..], &amount.to_le_bytes()].concat();
+        let mut accounts = [lp, ctx, stats];
+        assert_eq!(run_accounts(&mut accounts, &withdraw(0)), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(run_accounts(&mut accounts, &withdraw(1)[..16]), Err(ProgramError::InvalidInstructionData));
+        set_slot(1_200);
+        run_accounts(&mut accounts, &withdraw(accrued - 10)).unwrap();
+        set_slot(1_300);
+        run_accounts(&mut accounts, &withdraw(10)).unwrap();
+        let ledger = &accounts[2].data;
+        assert_eq!(read_u128(ledger, STATS_WITHDRAWN_OFF), accrued);
+        assert_eq!((read_u64(ledger, STATS_WITHDRAWALS_OFF), read_u64(ledger, STATS_LAST_WITHDRAWAL_OFF)), (2, 1_300));
+
+        // Never more than accrued, and only by the authority
+        let over = Err(MatcherError::WithdrawalExceedsFees.into());
+        assert_eq!(run_accounts(&mut accounts, &withdraw(1)), over);
+        assert_eq!(run_accounts(&mut accounts, &withdraw(u128::MAX)), over);
+        accounts[0].signer = false;
+        assert_eq!(run_accounts(&mut accounts, &withdraw(1)), Err(ProgramError::MissingRequiredSignature));
+        accounts[0].signer = true;
+
+        // The ledger lives only on a stats account with room for it
+        let [lp, ctx, stats] = accounts;
+        let short = TestAccount { data: stats.data[..STATS_LEN].to_vec(), ..stats };
+        let mut accounts = [lp, ctx, short];
+        assert_eq!(run_accounts(&mut accounts, &withdraw(1)), Err(ProgramError::AccountDataTooSmall));
+        accounts[2] = ctx_account(vec![0u8; STATS_LEDGER_LEN]);
+        assert_eq!(run_accounts(&mut accounts, &withdraw(1)), Err(MatcherError::StatsMismatch.into()));
+    }
+
+    #[test]
+    fn test_skim_accrues_from_spreads_and_claims_into_insurance() {
+        set_slot(1_000);
+        let slab_key = Pubkey::new_unique();
+        let ctx = init_bound(slab_key);
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; STATS_SKIM_LEN])];
+        run_accounts(&mut accounts, &[0x12]).unwrap();
+        let set_skim = |bps: u16| [&[0x2C][..], &bps.to_le_bytes()].concat();
+        assert_eq!(run_accounts(&mut accounts, &set_skim(10_001)), Err(MatcherError::SkimTooHigh.into()));
+        run_accounts(&mut accounts, &set_skim(5_000)).unwrap();
+        assert_eq!(read_u16(&accounts[2].data, STATS_SKIM_BPS_OFF), 5_000);
+        let [lp, ctx, stats] = accounts;
+
+        // Half of each fill's spread, on its notional
+        let mut accounts = [lp, ctx, slab_account(slab_key, vec![]), stats];
+        let mut expected = 0u128;
+        for i in 0..3 {
+            run_accounts(&mut accounts, &match_data(100_000_000, 1_000_000)).unwrap();
+            let fill = STATS_HEADER_LEN + i * FILL_LEN;
+            let stats = &accounts[3].data;
+            let notional = read_i128(stats, fill + FILL_SIZE_OFF).unsigned_abs() * read_u64(stats, fill + FILL_PRICE_OFF) as u128 / 1_000_000;
+            expected += notional * read_u64(stats, fill + FILL_SPREAD_OFF) as u128 * 5_000 / (BPS as u128 * BPS as u128);
+        }
+        let accrued = read_u128(&accounts[3].data, STATS_SKIM_ACCRUED_OFF);
+        assert!(accrued > 0);
+        assert_eq!(accrued, expected);
+
+        // Claimed through the bound slab's percolator program, never more
+        // than accrued
+        let [lp, ctx, slab, stats] = accounts;
+        let claim = |amount: u64| [&[0x2D][..], &amount.to_le_bytes()].concat();
+        let mut accounts = [
+            lp,
+            ctx,
+            stats,
+            slab,
+            TestAccount::new(Pubkey::new_unique(), false, 0, vec![]),
+            TestAccount::new(Pubkey::new_unique(), false, 0, vec![]),
+            TestAccount::new(Pubkey::new_unique(), false, 0, vec![]),
+            TestAccount::new(PERCOLATOR, false, 0, vec![]),
+        ];
+        assert_eq!(run_accounts(&mut accounts, &claim(0)), Err(ProgramError::InvalidInstructionData));
+        run_accounts(&mut accounts, &claim(accrued as u64 - 1)).unwrap();
+        run_accounts(&mut accounts, &claim(1)).unwrap();
+        assert_eq!(read_u128(&accounts[2].data, STATS_SKIM_CLAIMED_OFF), accrued);
+        assert_eq!(read_u64(&accounts[2].data, STATS_SKIM_CLAIMS_OFF), 2);
+        assert_eq!(run_accounts(&mut accounts, &claim(1)), Err(MatcherError::SkimExceedsAccrued.into()));
+
+        let percolator = std::mem::replace(&mut accounts[7], TestAccount::new(Pubkey::new_unique(), false, 0, vec![]));
+        assert_eq!(run_accounts(&mut accounts, &claim(1)), Err(ProgramError::IncorrectProgramId));
+        accounts[7] = percolator;
+        accounts[3] = slab_account(Pubkey::new_unique(), vec![]);
+        assert_eq!(run_accounts(&mut accounts, &claim(1)), Err(MatcherError::SlabMismatch.into()));
+
+        // A stats account without room for the skim takes none
+        let [lp, ctx, ..] = accounts;
+        let mut accounts = [lp, ctx, ctx_account(vec![0u8; STATS_LEDGER_LEN])];
+        run_accounts(&mut accounts, &[0x12]).unwrap();
+        assert_eq!(run_accounts(&mut accounts, &set_skim(1)), Err(ProgramError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn test_inventory_pnl_marks_the_book_to_the_oracle() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; STATS_PNL_LEN])];
+        run_accounts(&mut accounts, &[0x12]).unwrap();
+        let [lp, ctx, stats] = accounts;
+        let mut accounts = [lp, ctx, TestAccount::new(Pubkey::new_unique(), false, 0, vec![]), stats];
+        let fill = |accounts: &mut [TestAccount], oracle: u64, size: i128| {
+            run_accounts(accounts, &match_data(oracle, size)).unwrap();
+            RETURN_DATA.with(|r| read_u64(&r.borrow(), RET_EXEC_PRICE_OFF)) as i128
+        };
+        let pnl = |accounts: &[TestAccount]| {
+            let stats = &accounts[3].data;
+            (read_u64(stats, STATS_PNL_ENTRY_OFF) as i128, read_i128(stats, STATS_PNL_REALIZED_OFF), read_i128(stats, STATS_PNL_UNREALIZED_OFF))
+        };
+
+        // The first fill sets the entry and is marked at the oracle: the
+        // spread it paid shows up front
+        let first = fill(&mut accounts, 100_000_000, 1_000_000);
+        assert_eq!(pnl(&accounts), (first, 0, first - 100_000_000));
+        assert_eq!(read_u64(&accounts[3].data, STATS_PNL_MARK_OFF), 100_000_000);
+        // Adding at another price averages the entry; the mark follows the oracle
+        let second = fill(&mut accounts, 102_000_000, 1_000_000);
+        let entry = (first + second) / 2;
+        assert_eq!(pnl(&accounts), (entry, 0, 2 * (entry - 102_000_000)));
+        // Closing half realizes against the entry, and flipping past flat
+        // starts the other side over at the fill's price
+        let third = fill(&mut accounts, 101_000_000, -1_000_000);
+        assert_eq!(pnl(&accounts), (entry, entry - third, entry - 101_000_000));
+        let fourth = fill(&mut accounts, 101_000_000, -2_000_000);
+        let realized = entry - third + entry - fourth;
+        assert_eq!(pnl(&accounts), (fourth, realized, -(fourth - 101_000_000)));
+        // Together they come to what the backtester would mark: fills in, less
+        // the inventory of -1 at the oracle
+        let cash = first + second - third - 2 * fourth;
+        assert_eq!(realized - (fourth - 101_000_000), cash + 101_000_000);
+    }
+
+    #[test]
+    fn test_protocol_fee_accrues_to_treasury_apart_from_lp_fees() {
+        set_slot(1_000);
+        let treasury = Pubkey::new_unique();
+        let protocol_fee = |bps: u16| [&[0x12][..], &bps.to_le_bytes()].concat();
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let mut accounts = [
+            TestAccount::new(LP, true, 0, vec![]),
+            ctx_account(ctx),
+            ctx_account(vec![0u8; STATS_PROTOCOL_LEN]),
+            TestAccount::new(treasury, false, 0, vec![]),
+        ];
+        assert_eq!(run_accounts(&mut accounts, &protocol_fee(10_001)), Err(MatcherError::ProtocolFeeTooHigh.into()));
+        assert_eq!(run_accounts(&mut accounts[..3], &protocol_fee(2_000)), Err(ProgramError::NotEnoughAccountKeys));
+        run_accounts(&mut accounts, &protocol_fee(2_000)).unwrap();
+        assert_eq!(read_pubkey(&accounts[2].data, STATS_TREASURY_OFF), treasury);
+        assert_eq!(read_u16(&accounts[2].data, STATS_PROTOCOL_FEE_BPS_OFF), 2_000);
+        let [lp, ctx, stats, treasury] = accounts;
+
+        // A fifth of each fill's fee, figured like cum_fees
+        let mut accounts = [lp, ctx, TestAccount::new(Pubkey::new_unique(), false, 0, vec![]), stats];
+        let mut expected = 0u128;
+        for _ in 0..3 {
+            let before = read_u128(&accounts[1].data, EXT_BASE + EXT_CUM_FEES_OFF);
+            run_accounts(&mut accounts, &match_data(100_000_000, 1_000_000)).unwrap();
+            expected += (read_u128(&accounts[1].data, EXT_BASE + EXT_CUM_FEES_OFF) - before) * 2_000 / BPS as u128;
+        }
+        let fees = read_u128(&accounts[1].data, EXT_BASE + EXT_CUM_FEES_OFF);
+        let cut = read_u128(&accounts[3].data, STATS_PROTOCOL_ACCRUED_OFF);
+        assert!(cut > 0);
+        assert_eq!(cut, expected);
+
+        // A fill can't dodge the cut by leaving the stats account out
+        let [lp, ctx, slab, stats] = accounts;
+        let mut accounts = [lp, ctx, slab];
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000_000)), Err(MatcherError::StatsRequired.into()));
+        assert_eq!(read_u128(&accounts[1].data, EXT_BASE + EXT_CUM_FEES_OFF), fees);
+
+        // The LP can record withdrawing only its own share
+        let [lp, ctx, _] = accounts;
+        let amount = |tag: u8, amount: u128| [&[tag][..], &amount.to_le_bytes()].concat();
+        let mut accounts = [lp, ctx, stats];
+        assert_eq!(run_accounts(&mut accounts, &amount(0x24, fees - cut + 1)), Err(MatcherError::WithdrawalExceedsFees.into()));
+        run_accounts(&mut accounts, &amount(0x24, fees - cut)).unwrap();
+
+        // Only the treasury sweeps, never more than accrued
+        assert_eq!(run_accounts(&mut accounts, &amount(0x30, 1)), Err(MatcherError::TreasuryMismatch.into()));
+        let [lp, ctx, stats] = accounts;
+        let mut accounts = [TestAccount { signer: true, ..treasury }, ctx, stats];
+        assert_eq!(run_accounts(&mut accounts, &amount(0x30, 0)), Err(ProgramError::InvalidInstructionData));
+        run_accounts(&mut accounts, &amount(0x30, cut - 1)).unwrap();
+        run_accounts(&mut accounts, &amount(0x30, 1)).unwrap();
+        assert_eq!(read_u128(&accounts[2].data, STATS_PROTOCOL_SWEPT_OFF), cut);
+        assert_eq!(read_u64(&accounts[2].data, STATS_PROTOCOL_SWEEPS_OFF), 2);
+        assert_eq!(run_accounts(&mut accounts, &amount(0x30, 1)), Err(MatcherError::SweepExceedsAccrued.into()));
+        accounts[0].signer = false;
+        assert_eq!(run_accounts(&mut accounts, &amount(0x30, 1)), Err(ProgramError::MissingRequiredSignature));
+
+        // A stats account without room for it takes no protocol fee
+        let [treasury, ctx, _] = accounts;
+        let mut accounts = [lp, ctx, ctx_account(vec![0u8; STATS_SKIM_LEN]), treasury];
+        assert_eq!(run_accounts(&mut accounts, &protocol_fee(2_000)), Err(ProgramError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn test_twap_over_recorded_oracle_prices() {
+        let mut stats = vec![0u8; STATS_LEN];
+        assert_eq!(twap_price(&stats, 100, 1_000, 7), 7); // no history yet
+
+        record_oracle(&mut stats, 900, 100);
+        record_oracle(&mut stats, 950, 200);
+        record_oracle(&mut stats, 950, 300); // same slot: replaces 200
+        assert_eq!(read_u32(&stats, STATS_ORACLE_COUNT_OFF), 2);
+        // 100 held 900..950 (from 920 in the window), 300 held 950..1000
+        assert_eq!(twap_price(&stats, 80, 1_000, 7), (100 * 30 + 300 * 50) / 80);
+        assert_eq!(twap_price(&stats, 10, 1_000, 7), 300);
+
+        // The ring keeps the newest ORACLE_CAPACITY samples
+        for i in 0..ORACLE_CAPACITY as u64 + 3 {
+            record_oracle(&mut stats, 1_000 + i, 1_000 + i);
+        }
+        assert_eq!(read_u32(&stats, STATS_ORACLE_COUNT_OFF), ORACLE_CAPACITY as u32);
+        assert_eq!(read_u32(&stats, STATS_ORACLE_HEAD_OFF), 5);
+        let now = 1_000 + ORACLE_CAPACITY as u64 + 3;
+        assert_eq!(twap_price(&stats, 2, now, 7), ((now - 2) + (now - 1)) / 2);
+    }
+
+    #[test]
+    fn test_quotes_anchor_to_twap() {
+        set_slot(1_000);
+        let mut params = params_data(0);
+        params[108..112].copy_from_slice(&100u32.to_le_bytes()); // twap_window_slots
+        let mut init = vec![0x02, KIND_CREDIBILITY];
+        init.extend_from_slice(&params);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init).unwrap();
+
+        let mut accounts = [
+            TestAccount::new(LP, true, 0, vec![]),
+            ctx_account(ctx),
+            ctx_account(vec![0u8; STATS_LEN]),
+        ];
+        run_accounts(&mut accounts, &[0x12]).unwrap();
+        let [lp, ctx, stats] = accounts;
+        let slab = TestAccount::new(Pubkey::new_unique(), false, 0, vec![]);
+        let mut accounts = [lp, ctx, slab, stats];
+
+        // Without the stats account there is nothing to average
+        let (ctx_key, stats_key) = (accounts[1].key, accounts[3].key);
+        let mut no_stats = [
+            TestAccount::new(LP, true, 0, vec![]),
+            TestAccount::new(ctx_key, false, 0, accounts[1].data.clone()),
+        ];
+        assert_eq!(
+            run_accounts(&mut no_stats, &match_data(100_000_000, 10)),
+            Err(MatcherError::StatsRequired.into())
+        );
+
+        // First fill: no history, so the print itself is the anchor
+        run_accounts(&mut accounts, &match_data(100_000_000, 10)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_u64(&ret, RET_LEN + QUOTE_ANCHOR_OFF), 100_000_000);
+        set_slot(1_050);
+        run_accounts(&mut accounts, &match_data(102_000_000, 10)).unwrap();
+
+        // A spiked print is priced around the average, not the spike
+        set_slot(1_100);
+        let mut preview = [
+            TestAccount::new(ctx_key, false, 0, accounts[1].data.clone()),
+            TestAccount::new(stats_key, false, 0, accounts[3].data.clone()),
+        ];
+        run_accounts(&mut preview, &preview_data(150_000_000, 10)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_u64(&ret, QUOTE_ANCHOR_OFF), 101_000_000);
+        assert!(read_u64(&ret, QUOTE_EXEC_PRICE_OFF) < 110_000_000);
+        assert_eq!(
+            run_preview(&accounts[1].data, 150_000_000, 10),
+            Err(MatcherError::StatsRequired.into())
+        );
+
+        // Blended 70/30, the spike keeps 30% of its move
+        write_u16(&mut preview[0].data, CTX_BASE + CTX_SPOT_WEIGHT_OFF, 3_000);
+        run_accounts(&mut preview, &preview_data(150_000_000, 10)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_u64(&ret, QUOTE_ANCHOR_OFF), 115_700_000);
+
+        // The weight is a share of the anchor, and needs the extension
+        let mut blend = init.clone();
+        blend[2 + PARAMS_MAX_LEN - 2..].copy_from_slice(&10_001u16.to_le_bytes());
+        assert_eq!(run_init(&mut vec![0u8; CTX_EXT_LEN], &blend), Err(MatcherError::SpotWeightTooHigh.into()));
+        let mut v5 = init_data(0);
+        v5[2 + PARAMS_MAX_LEN - 2..].copy_from_slice(&3_000u16.to_le_bytes());
+        assert_eq!(run_init(&mut vec![0u8; CTX_LEN], &v5), Err(MatcherError::ExtensionRequired.into()));
+
+        // Off by default: quotes stay on the print
+        let mut plain = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut plain, &init_data(0)).unwrap();
+        let ret = run_preview(&plain, 150_000_000, 10).unwrap();
+        assert_eq!(read_u64(&ret, QUOTE_ANCHOR_OFF), 150_000_000);
+    }
+
+    fn signal(value_bps: u32, weight_bps: u16, stale_after_slot: u64) -> TestAccount {
+        let mut data = vec![0u8; SIGNAL_LEN];
+        write_u64(&mut data, SIGNAL_MAGIC_OFF, SIGNAL_MAGIC);
+        write_u32(&mut data, SIGNAL_VALUE_OFF, value_bps);
+        data[SIGNAL_WEIGHT_OFF..SIGNAL_WEIGHT_OFF + 2].copy_from_slice(&weight_bps.to_le_bytes());
+        write_u64(&mut data, SIGNAL_STALE_AFTER_OFF, stale_after_slot);
+        TestAccount::new(Pubkey::new_unique(), false, 0, data)
+    }
+
+    /// Run QuotePreview on `accounts`, the context first; returns the return
+    /// data.
+    fn run_preview_with(accounts: &[TestAccount], trade_size: i128) -> Result<Vec<u8>, ProgramError> {
+        let mut accounts = accounts.to_vec();
+        RETURN_DATA.with(|r| r.borrow_mut().clear());
+        run_accounts(&mut accounts, &preview_data(100_000_000, trade_size))?;
+        Ok(RETURN_DATA.with(|r| r.borrow().clone()))
+    }
+
+    /// A v6 context with a schedule of SCHEDULE_SIGNALS_LEN bytes that
+    /// registers `signals`; returns the LP, the context and the schedule.
+    fn registered(signals: &[TestAccount]) -> [TestAccount; 3] {
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; SCHEDULE_SIGNALS_LEN])];
+        run_accounts(&mut accounts, &[0x18]).unwrap();
+        let mut accounts = [&accounts[..], signals].concat();
+        run_accounts(&mut accounts, &[0x3A]).unwrap();
+        [accounts[0].clone(), accounts[1].clone(), accounts[2].clone()]
+    }
+
+    #[test]
+    fn test_signal_accounts_widen_the_spread() {
+        set_slot(1_000);
+        let mut plain = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut plain, &init_data(0)).unwrap();
+        let base = run_preview(&plain, 100_000_000, 1_000).unwrap();
+        assert_eq!(read_u64(&base, QUOTE_SIGNAL_OFF), 0);
+
+        // 20 at half weight plus 15 at full; the stale one is skipped
+        let signals = [signal(20, 5_000, 1_000), signal(15, 10_000, 2_000), signal(500, 10_000, 999)];
+        let [lp, ctx, schedule] = registered(&signals);
+        assert_eq!(schedule.data[SCHEDULE_SIGNAL_COUNT_OFF], 3);
+        for (i, signal) in signals.iter().enumerate() {
+            let entry = SCHEDULE_SIGNALS_OFF + i * SIGNAL_ENTRY_LEN;
+            assert_eq!(read_pubkey(&schedule.data, entry + SIGNAL_ENTRY_KEY_OFF), signal.key);
+            assert_eq!(read_pubkey(&schedule.data, entry + SIGNAL_ENTRY_OWNER_OFF), signal.owner);
+        }
+        let quote_accounts = [&[ctx.clone(), schedule.clone()][..], &signals].concat();
+        let ret = run_preview_with(&quote_accounts, 1_000).unwrap();
+        assert_eq!(read_u64(&ret, QUOTE_SIGNAL_OFF), 25);
+        assert_eq!(read_u64(&ret, QUOTE_SPREAD_OFF), read_u64(&base, QUOTE_SPREAD_OFF) + 25);
+        assert!(read_u64(&ret, QUOTE_EXEC_PRICE_OFF) > read_u64(&base, QUOTE_EXEC_PRICE_OFF));
+
+        // Both sides of a two-sided quote pay it
+        let mut accounts = quote_accounts.clone();
+        run_accounts(&mut accounts, &two_sided_data(100_000_000, 1_000)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(ret.len(), TWO_SIDED_LEN);
+        assert_eq!(read_u64(&ret, TWO_SIDED_BID_OFF + QUOTE_SIGNAL_OFF), 25);
+        assert_eq!(read_u64(&ret, TWO_SIDED_ASK_OFF + QUOTE_SIGNAL_OFF), 25);
+
+        // Match takes them after the stats account
+        let mut accounts = [lp, ctx, ctx_account(vec![0u8; STATS_LEN])];
+        run_accounts(&mut accounts, &[0x12]).unwrap();
+        let [lp, ctx, stats] = accounts;
+        let slab = TestAccount::new(Pubkey::new_unique(), false, 0, vec![]);
+        let mut accounts = [&[lp, ctx, slab, schedule, stats][..], &signals].concat();
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_000)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(ret.len(), MATCH_RET_LEN);
+        assert_eq!(read_u64(&ret, RET_LEN + QUOTE_SIGNAL_OFF), 25);
+        assert_eq!(read_u64(&ret, MATCH_RET_FILL_SEQ_OFF), 1);
+        assert_eq!(read_u32(&accounts[4].data, STATS_COUNT_OFF), 1);
+    }
+
+    #[test]
+    fn test_trades_pass_exactly_the_registered_signals() {
+        set_slot(1_000);
+        let invalid: ProgramError = MatcherError::InvalidSignal.into();
+        let signals = [signal(20, 10_000, 2_000), signal(15, 10_000, 2_000)];
+        let [lp, ctx, schedule] = registered(&signals);
+        let preview = |rest: &[TestAccount]| run_preview_with(&[&[ctx.clone(), schedule.clone()][..], rest].concat(), 1_000);
+        assert_eq!(read_u64(&preview(&signals).unwrap(), QUOTE_SIGNAL_OFF), 35);
+
+        // Left out, one short, out of order, swapped for another signal, or
+        // the right key under another program: all refused
+        assert_eq!(preview(&[]), Err(invalid.clone()));
+        assert_eq!(preview(&signals[..1]), Err(invalid.clone()));
+        assert_eq!(preview(&[signals[1].clone(), signals[0].clone()]), Err(invalid.clone()));
+        assert_eq!(preview(&[signals[0].clone(), signal(0, 0, 2_000)]), Err(invalid.clone()));
+        let reowned = TestAccount { owner: Pubkey::new_unique(), ..signals[1].clone() };
+        assert_eq!(preview(&[signals[0].clone(), reowned]), Err(invalid.clone()));
+        let slab = TestAccount::new(Pubkey::new_unique(), false, 0, vec![]);
+        let mut accounts = [lp.clone(), ctx.clone(), slab, schedule.clone(), signals[0].clone()];
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000)), Err(invalid.clone()));
+        // In the stats position, an account without the magic is taken for a
+        // stats account
+        let plain = TestAccount::new(Pubkey::new_unique(), false, 0, vec![0u8; SIGNAL_LEN]);
+        assert_eq!(preview(&[&[plain][..], &signals].concat()), Err(MatcherError::StatsMismatch.into()));
+
+        // A context that registers none takes none
+        let mut bare = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut bare, &init_data(0)).unwrap();
+        let bare = TestAccount::new(Pubkey::new_unique(), false, 0, bare);
+        assert_eq!(run_preview_with(&[bare, signals[0].clone()], 1_000), Err(invalid.clone()));
+
+        // SetSignals registers only signal accounts, up to the limit, on a
+        // schedule with room for them; none clears the list
+        let set = |rest: Vec<TestAccount>| {
+            let mut accounts = [vec![lp.clone(), ctx.clone(), schedule.clone()], rest].concat();
+            run_accounts(&mut accounts, &[0x3A])
+        };
+        assert_eq!(set(vec![signal(10, 10_001, 1_000)]), Err(invalid.clone()));
+        let mut short = signal(10, 10_000, 1_000);
+        short.data.truncate(SIGNAL_LEN - 8);
+        assert_eq!(set(vec![short]), Err(invalid.clone()));
+        assert_eq!(set(vec![TestAccount::new(Pubkey::new_unique(), false, 0, vec![0u8; SIGNAL_LEN])]), Err(invalid.clone()));
+        assert_eq!(set((0..=MAX_SIGNAL_ACCOUNTS).map(|_| signal(1, 10_000, 1_000)).collect()), Err(invalid.clone()));
+        let mut unsigned = [TestAccount { signer: false, ..lp.clone() }, ctx.clone(), schedule.clone()];
+        assert_eq!(run_accounts(&mut unsigned, &[0x3A]), Err(ProgramError::MissingRequiredSignature));
+        let mut other = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut other, &init_data(0)).unwrap();
+        let mut accounts = [lp.clone(), ctx_account(other), ctx_account(vec![0u8; SCHEDULE_DEPTH_CAP_LEN])];
+        run_accounts(&mut accounts, &[0x18]).unwrap();
+        assert_eq!(run_accounts(&mut accounts, &[0x3A]), Err(ProgramError::AccountDataTooSmall));
+        let mut accounts = [lp, ctx, schedule];
+        run_accounts(&mut accounts, &[0x3A]).unwrap();
+        assert_eq!(accounts[2].data[SCHEDULE_SIGNAL_COUNT_OFF], 0);
+        let [_, ctx, schedule] = accounts;
+        assert_eq!(read_u64(&run_preview_with(&[ctx, schedule], 1_000).unwrap(), QUOTE_SIGNAL_OFF), 0);
+    }
+
+    fn schedule_data(entries: &[(u64, u64, u32)]) -> Vec<u8> {
+        let mut d = vec![0x19, entries.len() as u8];
+        for (start, end, mult_bps) in entries {
+            d.extend_from_slice(&start.to_le_bytes());
+            d.extend_from_slice(&end.to_le_bytes());
+            d.extend_from_slice(&mult_bps.to_le_bytes());
+        }
+        d
+    }
+
+    #[test]
+    fn test_spread_schedule_multiplies_scheduled_slots() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let base = read_u64(&run_preview(&ctx, 100_000_000, 1_000).unwrap(), QUOTE_SPREAD_OFF);
+
+        // Any context can bind one, v5 included, but only one
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; SCHEDULE_LEN])];
+        run_accounts(&mut accounts, &[0x18]).unwrap();
+        assert_eq!(read_u64(&accounts[2].data, SCHEDULE_MAGIC_OFF), SCHEDULE_MAGIC);
+        assert_eq!(read_pubkey(&accounts[2].data, SCHEDULE_CTX_OFF), accounts[1].key);
+        assert_eq!(accounts[1].data[CTX_BASE + CTX_FLAGS_OFF], CTX_FLAG_SCHEDULED);
+        let [lp, ctx, schedule] = accounts;
+        let mut second = [lp, ctx, ctx_account(vec![0u8; SCHEDULE_LEN])];
+        assert_eq!(run_accounts(&mut second, &[0x18]), Err(ProgramError::AccountAlreadyInitialized));
+        let [lp, ctx, _] = second;
+
+        let mut accounts = [lp, ctx, schedule];
+        let entries = [(1_000, 1_100, 20_000), (1_050, 1_060, 30_000), (2_000, 2_000, BPS as u32)];
+        run_accounts(&mut accounts, &schedule_data(&entries)).unwrap();
+        assert_eq!(read_u32(&accounts[2].data, SCHEDULE_COUNT_OFF), 3);
+        let [lp, ctx, schedule] = accounts;
+
+        // Quotes take it right after the context; the widest entry in force
+        // wins, and max_spread still caps the total
+        let mut accounts = [ctx, schedule];
+        for (slot, schedule_bps) in [(1_000, base), (1_055, 2 * base), (1_101, 0)] {
+            set_slot(slot);
+            run_accounts(&mut accounts, &preview_data(100_000_000, 1_000)).unwrap();
+            let ret = RETURN_DATA.with(|r| r.borrow().clone());
+            assert_eq!(read_u64(&ret, QUOTE_SCHEDULE_OFF), schedule_bps);
+            assert_eq!(read_u64(&ret, QUOTE_SPREAD_OFF), (base + schedule_bps).min(200));
+        }
+        set_slot(1_000);
+        run_accounts(&mut accounts, &two_sided_data(100_000_000, 1_000)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_u64(&ret, TWO_SIDED_BID_OFF + QUOTE_SCHEDULE_OFF), base);
+        assert_eq!(read_u64(&ret, TWO_SIDED_ASK_OFF + QUOTE_SCHEDULE_OFF), base);
+        let [ctx, schedule] = accounts;
+
+        // No trade or quote can leave it out
+        let mismatch: ProgramError = MatcherError::ScheduleMismatch.into();
+        assert_eq!(run_preview(&ctx.data, 100_000_000, 1_000), Err(mismatch.clone()));
+        let slab = TestAccount::new(Pubkey::new_unique(), false, 0, vec![]);
+        let mut accounts = [lp, ctx, slab];
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000)), Err(mismatch.clone()));
+        let [lp, ctx, slab] = accounts;
+        let mut stranger = schedule.data.clone();
+        stranger[SCHEDULE_CTX_OFF..SCHEDULE_CTX_OFF + 32].copy_from_slice(Pubkey::new_unique().as_ref());
+        let mut accounts = [lp, ctx, slab, ctx_account(stranger)];
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000)), Err(mismatch.clone()));
+        accounts[3] = schedule;
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_000)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_u64(&ret, RET_LEN + QUOTE_SCHEDULE_OFF), base);
+        assert_eq!(read_i128(&ret, RET_EXEC_SIZE_OFF), 1_000);
+        let [lp, ctx, _, schedule] = accounts;
+
+        // Only the authority sets it, and only to widen
+        let mut accounts = [lp, ctx, schedule];
+        let invalid = Err(MatcherError::InvalidSchedule.into());
+        assert_eq!(run_accounts(&mut accounts, &schedule_data(&[(1_000, 1_100, 9_999)])), invalid);
+        assert_eq!(run_accounts(&mut accounts, &schedule_data(&[(1_100, 1_000, 20_000)])), invalid);
+        assert_eq!(run_accounts(&mut accounts, &schedule_data(&[(1, 1, 20_000); MAX_SCHEDULE_ENTRIES + 1])), invalid);
+        assert_eq!(run_accounts(&mut accounts, &schedule_data(&entries)[..30]), Err(ProgramError::InvalidInstructionData));
+        accounts[0].signer = false;
+        assert_eq!(run_accounts(&mut accounts, &schedule_data(&[])), Err(ProgramError::MissingRequiredSignature));
+        accounts[0].signer = true;
+        assert_eq!(read_u32(&accounts[2].data, SCHEDULE_COUNT_OFF), 3);
+        run_accounts(&mut accounts, &schedule_data(&[])).unwrap();
+        assert_eq!(read_u32(&accounts[2].data, SCHEDULE_COUNT_OFF), 0);
+        assert!(accounts[2].data[SCHEDULE_HEADER_LEN..].iter().all(|b| *b == 0));
+        let [lp, ctx, schedule] = accounts;
+
+        // Close takes the schedule with it, and won't go without it
+        let destination = TestAccount::new(Pubkey::new_unique(), false, 0, vec![]);
+        let mut accounts = [lp, ctx, destination];
+        assert_eq!(run_accounts(&mut accounts, &[0x05]), Err(mismatch));
+        let [lp, ctx, destination] = accounts;
+        let reclaimed = ctx.lamports + schedule.lamports;
+        let mut accounts = [lp, ctx, destination, schedule];
+        run_accounts(&mut accounts, &[0x05]).unwrap();
+        assert_eq!(accounts[2].lamports, reclaimed);
+        assert_eq!(accounts[3].lamports, 0);
+        assert!(accounts[3].data.iter().all(|b| *b == 0));
+    }
+
+    fn fee_tiers_data(tiers: &[(u128, u32)]) -> Vec<u8> {
+        let mut d = vec![0x22, tiers.len() as u8];
+        for (min_notional, fee_bps) in tiers {
+            d.extend_from_slice(&min_notional.to_le_bytes());
+            d.extend_from_slice(&fee_bps.to_le_bytes());
+        }
+        d
+    }
+
+    #[test]
+    fn test_fee_tiers_replace_the_base_fee_by_notional() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; SCHEDULE_LEN])];
+        run_accounts(&mut accounts, &[0x18]).unwrap();
+
+        // A schedule account without room for them can't take any
+        let tiers = [(50_000, 2), (1_000_000, 9)];
+        assert_eq!(run_accounts(&mut accounts, &fee_tiers_data(&tiers)), Err(ProgramError::AccountDataTooSmall));
+        let [lp, ctx, _] = accounts;
+        let mut schedule = vec![0u8; SCHEDULE_FEES_LEN];
+        write_u64(&mut schedule, SCHEDULE_MAGIC_OFF, SCHEDULE_MAGIC);
+        schedule[SCHEDULE_CTX_OFF..SCHEDULE_CTX_OFF + 32].copy_from_slice(ctx.key.as_ref());
+        let mut accounts = [lp, ctx, ctx_account(schedule)];
+
+        let invalid = Err(MatcherError::InvalidFeeTiers.into());
+        assert_eq!(run_accounts(&mut accounts, &fee_tiers_data(&[(0, 2)])), invalid);
+        assert_eq!(run_accounts(&mut accounts, &fee_tiers_data(&[(50_000, 2), (50_000, 9)])), invalid);
+        assert_eq!(run_accounts(&mut accounts, &fee_tiers_data(&[(50_000, BPS as u32 + 1)])), invalid);
+        assert_eq!(run_accounts(&mut accounts, &fee_tiers_data(&[(1, 1); MAX_FEE_TIERS + 1])), invalid);
+        assert_eq!(run_accounts(&mut accounts, &fee_tiers_data(&tiers)[..30]), Err(ProgramError::InvalidInstructionData));
+        run_accounts(&mut accounts, &fee_tiers_data(&tiers)).unwrap();
+        let tier = &accounts[2].data[FEE_TIERS_OFF + FEE_TIER_LEN..];
+        assert_eq!((read_u128(tier, FEE_TIER_NOTIONAL_OFF), read_u32(tier, FEE_TIER_FEE_OFF)), (1_000_000, 9));
+        let [lp, ctx, schedule] = accounts;
+
+        // Notional at the oracle picks the largest tier reached; below them
+        // all, base_fee_bps (5) still applies
+        let mut accounts = [ctx, schedule];
+        for (size, fee_bps) in [(100, 5), (500, 2), (9_999, 2), (10_000, 9)] {
+            run_accounts(&mut accounts, &preview_data(100_000_000, size)).unwrap();
+            let ret = RETURN_DATA.with(|r| r.borrow().clone());
+            assert_eq!(read_u64(&ret, QUOTE_FEE_OFF), fee_bps);
+        }
+        let [ctx, schedule] = accounts;
+
+        // Match charges it too, and from the first fill the tiers are fixed
+        let slab = TestAccount::new(Pubkey::new_unique(), false, 0, vec![]);
+        let mut accounts = [lp, ctx, slab, schedule];
+        run_accounts(&mut accounts, &match_data(100_000_000, 10_000)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_u64(&ret, RET_LEN + QUOTE_FEE_OFF), 9);
+        let [lp, ctx, _, schedule] = accounts;
+        let mut accounts = [lp, ctx, schedule];
+        assert_eq!(run_accounts(&mut accounts, &fee_tiers_data(&[])), Err(MatcherError::MarketLive.into()));
+    }
+
+    #[test]
+    fn test_rebate_pays_takers_who_shrink_inventory() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; SCHEDULE_REBATE_LEN])];
+        run_accounts(&mut accounts, &[0x18]).unwrap();
+
+        let rebate = |bps: u16| [&[0x23][..], &bps.to_le_bytes()].concat();
+        assert_eq!(run_accounts(&mut accounts, &rebate(BPS as u16 + 1)), Err(MatcherError::RebateTooHigh.into()));
+        assert_eq!(run_accounts(&mut accounts, &rebate(3)[..2]), Err(ProgramError::InvalidInstructionData));
+        let [lp, ctx, schedule] = accounts;
+        let short = ctx_account(schedule.data[..SCHEDULE_FEES_LEN].to_vec());
+        let mut accounts = [lp, ctx, short];
+        assert_eq!(run_accounts(&mut accounts, &rebate(3)), Err(ProgramError::AccountDataTooSmall));
+        let [lp, ctx, _] = accounts;
+
+        // Build up a book, then set the rebate on the live market
+        let slab = TestAccount::new(Pubkey::new_unique(), false, 0, vec![]);
+        let mut accounts = [lp, ctx, slab, schedule];
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_000)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_u64(&ret, RET_LEN + QUOTE_REBATE_OFF), 0);
+        let inventory = read_i128(&accounts[1].data, CTX_BASE + CTX_INVENTORY_OFF);
+        assert_ne!(inventory, 0);
+        let [lp, ctx, _, schedule] = accounts;
+        let mut accounts = [lp, ctx, schedule];
+        run_accounts(&mut accounts, &rebate(3)).unwrap();
+        assert_eq!(read_u16(&accounts[2].data, SCHEDULE_REBATE_OFF), 3);
+        let [lp, ctx, schedule] = accounts;
+
+        // Only the side that shrinks the book earns it, 3 bps nearer the oracle
+        let mut accounts = [ctx, schedule];
+        let mut quote = |size: i128| {
+            run_accounts(&mut accounts, &preview_data(100_000_000, size)).unwrap();
+            let ret = RETURN_DATA.with(|r| r.borrow().clone());
+            (read_u64(&ret, QUOTE_REBATE_OFF), read_u64(&ret, QUOTE_EXEC_PRICE_OFF))
+        };
+        let (growing, shrinking) = (quote(inventory.signum() * 10), quote(-inventory.signum() * 10));
+        assert_eq!(growing.0, 0);
+        assert_eq!(shrinking.0, 3);
+        let [ctx, schedule] = accounts;
+        let mut accounts = [lp, ctx, schedule];
+        run_accounts(&mut accounts, &rebate(0)).unwrap();
+        let [_, ctx, schedule] = accounts;
+        let mut accounts = [ctx, schedule];
+        run_accounts(&mut accounts, &preview_data(100_000_000, -inventory.signum() * 10)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_u64(&ret, QUOTE_REBATE_OFF), 0);
+        assert_eq!(shrinking.1.abs_diff(read_u64(&ret, QUOTE_EXEC_PRICE_OFF)), 30_000);
+    }
+
+    #[test]
+    fn test_fee_discount_follows_the_credibility_discount() {
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; SCHEDULE_REBATE_LEN])];
+        run_accounts(&mut accounts, &[0x18]).unwrap();
+
+        let discount = |bps: u16| [&[0x25][..], &bps.to_le_bytes()].concat();
+        assert_eq!(run_accounts(&mut accounts, &discount(BPS as u16 + 1)), Err(MatcherError::FeeDiscountTooHigh.into()));
+        assert_eq!(run_accounts(&mut accounts, &discount(1)[..2]), Err(ProgramError::InvalidInstructionData));
+        let [lp, ctx, schedule] = accounts;
+        let mut accounts = [lp, ctx, ctx_account(schedule.data[..SCHEDULE_FEES_LEN].to_vec())];
+        assert_eq!(run_accounts(&mut accounts, &discount(1)), Err(ProgramError::AccountDataTooSmall));
+        let [lp, ctx, _] = accounts;
+        let mut accounts = [lp, ctx, schedule];
+        run_accounts(&mut accounts, &discount(BPS as u16)).unwrap();
+        assert_eq!(read_u16(&accounts[2].data, SCHEDULE_FEE_DISCOUNT_OFF), BPS as u16);
+        // The rebate beside it is untouched
+        assert_eq!(read_u16(&accounts[2].data, SCHEDULE_REBATE_OFF), 0);
+
+        // The whole fee at the full discount (190 bps, max 200 less min 10),
+        // that share of it part way
+        let [lp, ctx, schedule] = accounts;
+        let mut accounts = [ctx, schedule];
+        run_accounts(&mut accounts, &preview_data(100_000_000, 10)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        let earned = read_u64(&ret, QUOTE_DISCOUNT_OFF);
+        assert!(earned > 0 && earned < 190);
+        assert_eq!(read_u64(&ret, QUOTE_FEE_OFF), 5 - 5 * earned / 190);
+
+        let [ctx, schedule] = accounts;
+        let mut accounts = [lp, ctx, schedule];
+        run_accounts(&mut accounts, &discount(0)).unwrap();
+        let [_, ctx, schedule] = accounts;
+        let mut accounts = [ctx, schedule];
+        run_accounts(&mut accounts, &preview_data(100_000_000, 10)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_u64(&ret, QUOTE_FEE_OFF), 5);
+    }
+
+    #[test]
+    fn test_min_notional_refuses_dust() {
+        set_slot(1);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; SCHEDULE_MIN_NOTIONAL_LEN])];
+        run_accounts(&mut accounts, &[0x18]).unwrap();
+
+        let min_notional = |min: u128| [&[0x2B][..], &min.to_le_bytes()].concat();
+        assert_eq!(run_accounts(&mut accounts, &min_notional(1)[..16]), Err(ProgramError::InvalidInstructionData));
+        let [lp, ctx, schedule] = accounts;
+        let mut accounts = [lp, ctx, ctx_account(schedule.data[..SCHEDULE_REBATE_LEN].to_vec())];
+        assert_eq!(run_accounts(&mut accounts, &min_notional(1)), Err(ProgramError::AccountDataTooSmall));
+        let [lp, ctx, _] = accounts;
+        let mut accounts = [lp, ctx, schedule];
+        run_accounts(&mut accounts, &min_notional(1_000)).unwrap();
+        assert_eq!(read_u128(&accounts[2].data, SCHEDULE_MIN_NOTIONAL_OFF), 1_000);
+
+        // At 100, 10 is a notional of 1000 and fills; 9 is dust
+        let [lp, ctx, schedule] = accounts;
+        let slab = slab_account(Pubkey::new_unique(), vec![]);
+        let mut accounts = [lp, ctx, slab, schedule];
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 9)), Err(MatcherError::BelowMinNotional.into()));
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, -9)), Err(MatcherError::BelowMinNotional.into()));
+        run_accounts(&mut accounts, &match_data(100_000_000, 10)).unwrap();
+        assert_eq!(read_i128(&accounts[1].data, CTX_BASE + CTX_INVENTORY_OFF), 10);
+
+        // Soft-reject mode answers dust with a zero fill and the reason
+        let [lp, ctx, slab, schedule] = accounts;
+        let mut pair = [lp, ctx];
+        run_accounts(&mut pair, &[0x10, 1]).unwrap();
+        let [lp, ctx] = pair;
+        let mut accounts = [lp, ctx, slab, schedule];
+        let state = accounts[1].data[CTX_BASE..].to_vec();
+        run_accounts(&mut accounts, &match_data(100_000_000, 9)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_i128(&ret, RET_EXEC_SIZE_OFF), 0);
+        assert_eq!(read_u64(&ret, RET_REJECT_REASON_OFF), MatcherError::BelowMinNotional as u64);
+        assert_eq!(accounts[1].data[CTX_BASE..], state[..]);
+
+        // 0 turns the minimum off
+        let [lp, ctx, slab, schedule] = accounts;
+        let mut accounts = [lp, ctx, schedule];
+        run_accounts(&mut accounts, &min_notional(0)).unwrap();
+        let [lp, ctx, schedule] = accounts;
+        let mut accounts = [lp, ctx, slab, schedule];
+        run_accounts(&mut accounts, &match_data(100_000_000, 1)).unwrap();
+        assert_eq!(read_i128(&accounts[1].data, CTX_BASE + CTX_INVENTORY_OFF), 11);
+    }
+
+    fn loyalty_tiers_data(tiers: &[(u128, u32)]) -> Vec<u8> {
+        let mut data = vec![0x2F, tiers.len() as u8];
+        for (min_volume, discount_bps) in tiers {
+            data.extend_from_slice(&min_volume.to_le_bytes());
+            data.extend_from_slice(&discount_bps.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_loyalty_tiers_discount_a_trader_by_volume() {
+        set_slot(1);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; SCHEDULE_LOYALTY_LEN])];
+        run_accounts(&mut accounts, &[0x18]).unwrap();
+
+        let invalid = Err(MatcherError::InvalidLoyaltyTiers.into());
+        assert_eq!(run_accounts(&mut accounts, &loyalty_tiers_data(&[(0, 1)])), invalid);
+        assert_eq!(run_accounts(&mut accounts, &loyalty_tiers_data(&[(5_000, 1), (1_000, 2)])), invalid);
+        assert_eq!(run_accounts(&mut accounts, &loyalty_tiers_data(&[(1_000, BPS as u32 + 1)])), invalid);
+        assert_eq!(run_accounts(&mut accounts, &loyalty_tiers_data(&[(1, 1); MAX_LOYALTY_TIERS + 1])), invalid);
+        run_accounts(&mut accounts, &loyalty_tiers_data(&[(1_000, 2), (5_000, 4)])).unwrap();
+        let tier = &accounts[2].data[LOYALTY_TIERS_OFF + LOYALTY_TIER_LEN..];
+        assert_eq!((read_u128(tier, LOYALTY_TIER_VOLUME_OFF), read_u32(tier, LOYALTY_TIER_DISCOUNT_OFF)), (5_000, 4));
+        let [lp, ctx, schedule] = accounts;
+
+        // A schedule without room for them takes none
+        let short = TestAccount { data: schedule.data[..SCHEDULE_MIN_NOTIONAL_LEN].to_vec(), ..schedule };
+        let mut accounts = [lp, ctx, short];
+        assert_eq!(run_accounts(&mut accounts, &loyalty_tiers_data(&[])), Err(ProgramError::AccountDataTooSmall));
+        let [lp, ctx, short] = accounts;
+        let schedule = TestAccount { data: schedule.data, ..short };
+
+        // The trader binds their own account, and signs for it
+        let trader = TestAccount::new(Pubkey::new_unique(), true, 0, vec![]);
+        let mut accounts = [trader, ctx, ctx_account(vec![0u8; TRADER_LEN])];
+        accounts[0].signer = false;
+        assert_eq!(run_accounts(&mut accounts, &[0x2E]), Err(ProgramError::MissingRequiredSignature));
+        accounts[0].signer = true;
+        run_accounts(&mut accounts, &[0x2E]).unwrap();
+        assert_eq!(run_accounts(&mut accounts, &[0x2E]), Err(ProgramError::AccountAlreadyInitialized));
+        assert_eq!(read_pubkey(&accounts[2].data, TRADER_KEY_OFF), accounts[0].key);
+        let [trader, ctx, trader_account] = accounts;
+
+        // 10 at 100 is a notional of 1000: the base fee (5) until the volume
+        // reaches a tier, then that tier's discount off it
+        let slab = slab_account(Pubkey::new_unique(), vec![]);
+        let mut accounts = [lp, ctx, slab, trader_account, trader, schedule];
+        for fee_bps in [5, 3, 3, 3, 3, 1] {
+            run_accounts(&mut accounts, &match_data(100_000_000, 10)).unwrap();
+            let ret = RETURN_DATA.with(|r| r.borrow().clone());
+            assert_eq!(read_u64(&ret, RET_LEN + QUOTE_FEE_OFF), fee_bps);
+        }
+        // Volume is figured at the exec price, so the spread counts too
+        assert!((6_000..6_200).contains(&read_u128(&accounts[3].data, TRADER_VOLUME_OFF)));
+        assert_eq!(read_u64(&accounts[3].data, TRADER_FILLS_OFF), 6);
+
+        // Someone else's volume is no use: the trader has to sign
+        accounts[4].signer = false;
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 10)), Err(ProgramError::MissingRequiredSignature));
+        accounts[4] = TestAccount::new(Pubkey::new_unique(), true, 0, vec![]);
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 10)), Err(MatcherError::TraderMismatch.into()));
+    }
+
+    #[test]
+    fn test_inventory_decay_relaxes_the_imbalance_penalty_between_fills() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; SCHEDULE_DECAY_LEN])];
+        run_accounts(&mut accounts, &[0x18]).unwrap();
+        let decay = |slots: u32| [&[0x31][..], &slots.to_le_bytes()].concat();
+        assert_eq!(run_accounts(&mut accounts, &decay(100)[..4]), Err(ProgramError::InvalidInstructionData));
+        run_accounts(&mut accounts, &decay(100)).unwrap();
+        assert_eq!(read_u32(&accounts[2].data, SCHEDULE_INVENTORY_HALFLIFE_OFF), 100);
+        let [lp, ctx, schedule] = accounts;
+
+        // The first fill starts the clock; a long book costs 20bps in the same
+        // slot, half that a halflife later, and the book keeps all of it
+        let mut accounts = [lp, ctx, slab_account(Pubkey::new_unique(), vec![]), schedule];
+        run_accounts(&mut accounts, &match_data(100_000_000, 10)).unwrap();
+        write_i128(&mut accounts[1].data, CTX_BASE + CTX_INVENTORY_OFF, 2_000_000_000_010);
+        let imbalance = |accounts: &mut [TestAccount]| {
+            run_accounts(accounts, &match_data(100_000_000, -10)).unwrap();
+            let ret = RETURN_DATA.with(|r| r.borrow().clone());
+            read_u64(&ret, RET_LEN + QUOTE_IMBALANCE_OFF)
+        };
+        assert_eq!(imbalance(&mut accounts), 20);
+        set_slot(1_100);
+        assert_eq!(imbalance(&mut accounts), 10);
+        assert_eq!(read_i128(&accounts[1].data, CTX_BASE + CTX_INVENTORY_OFF), 2_000_000_000_010 - 20);
+
+        // Fixed once the market is live, and only on a schedule with room
+        let [lp, ctx, _, schedule] = accounts;
+        let mut accounts = [lp, ctx, schedule];
+        assert_eq!(run_accounts(&mut accounts, &decay(0)), Err(MatcherError::MarketLive.into()));
+        let [lp, _, _] = accounts;
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let mut accounts = [lp, ctx_account(ctx), ctx_account(vec![0u8; SCHEDULE_LOYALTY_LEN])];
+        run_accounts(&mut accounts, &[0x18]).unwrap();
+        assert_eq!(run_accounts(&mut accounts, &decay(100)), Err(ProgramError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn test_inventory_band_surcharges_fills_that_go_deeper() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        write_u128(&mut ctx, CTX_BASE + CTX_MAX_INVENTORY_OFF, 4_000_000_000_000);
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; SCHEDULE_BAND_LEN])];
+        run_accounts(&mut accounts, &[0x18]).unwrap();
+        let band = |soft: u128, k: u32| [&[0x32][..], &soft.to_le_bytes(), &k.to_le_bytes()].concat();
+        assert_eq!(run_accounts(&mut accounts, &band(2_000_000_000_000, 100)[..20]), Err(ProgramError::InvalidInstructionData));
+        for bad in [band(4_000_000_000_000, 100), band(2_000_000_000_000, 10_001)] {
+            assert_eq!(run_accounts(&mut accounts, &bad), Err(MatcherError::InvalidInventoryBand.into()));
+        }
+        run_accounts(&mut accounts, &band(2_000_000_000_000, 100)).unwrap();
+        assert_eq!(read_u128(&accounts[2].data, SCHEDULE_SOFT_INVENTORY_OFF), 2_000_000_000_000);
+        assert_eq!(read_u32(&accounts[2].data, SCHEDULE_BAND_K_OFF), 100);
+        let [lp, ctx, schedule] = accounts;
+
+        // Halfway into the band, a fill that goes deeper pays a quarter of
+        // band_k_bps on top; one that comes back out pays none
+        let mut accounts = [lp, ctx, slab_account(Pubkey::new_unique(), vec![]), schedule];
+        run_accounts(&mut accounts, &match_data(100_000_000, 10)).unwrap();
+        let imbalance = |accounts: &mut [TestAccount], size: i128| {
+            write_i128(&mut accounts[1].data, CTX_BASE + CTX_INVENTORY_OFF, 3_000_000_000_000);
+            run_accounts(accounts, &match_data(100_000_000, size)).unwrap();
+            let ret = RETURN_DATA.with(|r| r.borrow().clone());
+            read_u64(&ret, RET_LEN + QUOTE_IMBALANCE_OFF)
+        };
+        assert_eq!((imbalance(&mut accounts, 10), imbalance(&mut accounts, -10)), (30 + 25, 30));
+        // The hedge target is whatever sits past the soft limit
+        let hedge = RETURN_DATA.with(|r| read_i128(&r.borrow(), MATCH_RET_HEDGE_OFF));
+        assert_eq!(hedge, 1_000_000_000_000 - 10);
+
+        // Fixed once the market is live, and only on a schedule with room
+        let [lp, ctx, _, schedule] = accounts;
+        let mut accounts = [lp, ctx, schedule];
+        assert_eq!(run_accounts(&mut accounts, &band(0, 0)), Err(MatcherError::MarketLive.into()));
+        let [lp, _, _] = accounts;
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let mut accounts = [lp, ctx_account(ctx), ctx_account(vec![0u8; SCHEDULE_DECAY_LEN])];
+        run_accounts(&mut accounts, &[0x18]).unwrap();
+        assert_eq!(run_accounts(&mut accounts, &band(0, 0)), Err(ProgramError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn test_inventory_caps_limit_each_side() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        write_u128(&mut ctx, CTX_BASE + CTX_MAX_INVENTORY_OFF, 1_000);
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; SCHEDULE_CAPS_LEN])];
+        run_accounts(&mut accounts, &[0x18]).unwrap();
+        let caps = |long: u128, short: u128| [&[0x33][..], &long.to_le_bytes(), &short.to_le_bytes()].concat();
+        assert_eq!(run_accounts(&mut accounts, &caps(0, 100)[..32]), Err(ProgramError::InvalidInstructionData));
+        run_accounts(&mut accounts, &caps(0, 100)).unwrap();
+        assert_eq!(read_u128(&accounts[2].data, SCHEDULE_MAX_LONG_OFF), 0);
+        assert_eq!(read_u128(&accounts[2].data, SCHEDULE_MAX_SHORT_OFF), 100);
+        let [lp, ctx, schedule] = accounts;
+
+        // The LP's long book keeps max_inventory; its short book, what takers
+        // buy, stops at 100
+        let mut accounts = [lp, ctx, slab_account(Pubkey::new_unique(), vec![]), schedule];
+        run_accounts(&mut accounts, &match_data(100_000_000, -900)).unwrap();
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_001)).unwrap();
+        assert_eq!(read_i128(&accounts[1].data, RET_EXEC_SIZE_OFF), 1_000);
+        assert_eq!(read_i128(&accounts[1].data, CTX_BASE + CTX_INVENTORY_OFF), 100);
+
+        // They change at once on a live market, and only on a schedule with room
+        let [lp, ctx, slab, schedule] = accounts;
+        let mut accounts = [lp, ctx, schedule];
+        run_accounts(&mut accounts, &caps(0, 0)).unwrap();
+        let [lp, ctx, schedule] = accounts;
+        let mut accounts = [lp, ctx, slab, schedule];
+        run_accounts(&mut accounts, &match_data(100_000_000, 800)).unwrap();
+        let [lp, _, _, _] = accounts;
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let mut accounts = [lp, ctx_account(ctx), ctx_account(vec![0u8; SCHEDULE_BAND_LEN])];
+        run_accounts(&mut accounts, &[0x18]).unwrap();
+        assert_eq!(run_accounts(&mut accounts, &caps(0, 100)), Err(ProgramError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn test_inventory_depth_cap_scales_with_liquidity() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        write_u128(&mut ctx, CTX_BASE + CTX_MAX_INVENTORY_OFF, 100_000);
+        write_u128(&mut ctx, CTX_BASE + CTX_LIQUIDITY_OFF, 200_000);
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; SCHEDULE_DEPTH_CAP_LEN])];
+        run_accounts(&mut accounts, &[0x18]).unwrap();
+        let depth_cap = |bps: u32| [&[0x36][..], &bps.to_le_bytes()].concat();
+        assert_eq!(run_accounts(&mut accounts, &depth_cap(5_000)[..4]), Err(ProgramError::InvalidInstructionData));
+        run_accounts(&mut accounts, &depth_cap(5_000)).unwrap();
+        assert_eq!(read_u32(&accounts[2].data, SCHEDULE_MAX_INVENTORY_BPS_OFF), 5_000);
+        let [lp, ctx, schedule] = accounts;
+
+        // Half of 0.2 of notional depth at 100 is 1_000, in place of 100_000
+        let mut accounts = [lp, ctx, slab_account(Pubkey::new_unique(), vec![]), schedule];
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_500)).unwrap();
+        assert_eq!(read_i128(&accounts[1].data, CTX_BASE + CTX_INVENTORY_OFF), 1_000);
+        // Doubling the depth doubles the limit
+        write_u128(&mut accounts[1].data, CTX_BASE + CTX_LIQUIDITY_OFF, 400_000);
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_500)).unwrap();
+        assert_eq!(read_i128(&accounts[1].data, CTX_BASE + CTX_INVENTORY_OFF), 2_000);
+        // And a cheaper asset takes more units for the same notional
+        run_accounts(&mut accounts, &match_data(80_000_000, 1_000)).unwrap();
+        assert_eq!(read_i128(&accounts[1].data, CTX_BASE + CTX_INVENTORY_OFF), 2_500);
+
+        // 0 goes back to max_inventory_abs; a short schedule has no room
+        let [lp, ctx, slab, schedule] = accounts;
+        let mut accounts = [lp, ctx, schedule];
+        run_accounts(&mut accounts, &depth_cap(0)).unwrap();
+        let [lp, ctx, schedule] = accounts;
+        let mut accounts = [lp, ctx, slab, schedule];
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_500)).unwrap();
+        assert_eq!(read_i128(&accounts[1].data, CTX_BASE + CTX_INVENTORY_OFF), 4_000);
+        let [lp, _, _, _] = accounts;
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let mut accounts = [lp, ctx_account(ctx), ctx_account(vec![0u8; SCHEDULE_REDUCE_ONLY_LEN])];
+        run_accounts(&mut accounts, &[0x18]).unwrap();
+        assert_eq!(run_accounts(&mut accounts, &depth_cap(5_000)), Err(ProgramError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn test_reduce_only_threshold_soft_rejects_early() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        write_u128(&mut ctx, CTX_BASE + CTX_MAX_INVENTORY_OFF, 1_000);
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; SCHEDULE_REDUCE_ONLY_LEN])];
+        run_accounts(&mut accounts, &[0x18]).unwrap();
+        assert_eq!(run_accounts(&mut accounts, &[0x34, 0x40]), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(
+            run_accounts(&mut accounts, &[&[0x34][..], &10_001u16.to_le_bytes()].concat()),
+            Err(MatcherError::ReduceOnlyThresholdTooHigh.into())
+        );
+        run_accounts(&mut accounts, &[&[0x34][..], &8_000u16.to_le_bytes()].concat()).unwrap();
+        assert_eq!(read_u16(&accounts[2].data, SCHEDULE_REDUCE_ONLY_BPS_OFF), 8_000);
+        let [lp, ctx, schedule] = accounts;
+
+        // Past 80% of the cap, growing the book gets a zero fill; shrinking it fills
+        let mut accounts = [lp, ctx, slab_account(Pubkey::new_unique(), vec![]), schedule];
+        run_accounts(&mut accounts, &match_data(100_000_000, 800)).unwrap();
+        run_accounts(&mut accounts, &match_data(100_000_000, 1)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_i128(&ret, RET_EXEC_SIZE_OFF), 0);
+        assert_eq!(read_u64(&ret, RET_REJECT_REASON_OFF), MatcherError::ReduceOnly as u64);
+        assert_eq!(read_i128(&accounts[1].data, CTX_BASE + CTX_INVENTORY_OFF), 800);
+        run_accounts(&mut accounts, &match_data(100_000_000, -1)).unwrap();
+        assert_eq!(read_i128(&accounts[1].data, CTX_BASE + CTX_INVENTORY_OFF), 799);
+
+        // The LP's own switch still fails hard outside soft-reject mode
+        accounts[1].data[CTX_BASE + CTX_FLAGS_OFF] |= CTX_FLAG_REDUCE_ONLY;
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1)), Err(MatcherError::ReduceOnly.into()));
+        let [lp, _, _, _] = accounts;
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let mut accounts = [lp, ctx_account(ctx), ctx_account(vec![0u8; SCHEDULE_CAPS_LEN])];
+        run_accounts(&mut accounts, &[0x18]).unwrap();
+        assert_eq!(run_accounts(&mut accounts, &[0x34, 0, 0]), Err(ProgramError::AccountDataTooSmall));
+    }
+
+    /// A fully verified PriceUpdateV2 account, owned by the Pyth receiver.
+    fn pyth_account(feed_id: [u8; 32], price: i64, exponent: i32, publish_time: i64) -> TestAccount {
+        let mut data = vec![0u8; PYTH_MIN_LEN];
+        data[..8].copy_from_slice(&PYTH_DISCRIMINATOR);
+        data[PYTH_VERIFICATION_OFF] = PYTH_VERIFIED_FULL;
+        data[PYTH_FEED_ID_OFF..PYTH_FEED_ID_OFF + 32].copy_from_slice(&feed_id);
+        write_i64(&mut data, PYTH_PRICE_OFF, price);
+        data[PYTH_EXPONENT_OFF..PYTH_EXPONENT_OFF + 4].copy_from_slice(&exponent.to_le_bytes());
+        write_i64(&mut data, PYTH_PUBLISH_TIME_OFF, publish_time);
+        let mut account = TestAccount::new(Pubkey::new_unique(), false, 0, data);
+        account.owner = PYTH_OWNERS[0];
+        account
+    }
+
+    #[test]
+    fn test_scale_e6() {
+        assert_eq!(scale_e6(15_000_000_000, -8), Some(150_000_000));
+        assert_eq!(scale_e6(15, 0), Some(15_000_000));
+        assert_eq!(scale_e6(1_234, -12), None);
+        assert_eq!(scale_e6(1, -40), None);
+        assert_eq!(scale_e6(-5, -2), None);
+        assert_eq!(scale_e6(i64::MAX, 10), None);
+        assert_eq!(conf_bps(15_000_000_000, 7_500_000), 5);
+        assert_eq!(conf_bps(15_000_000_000, 7_500_001), 6);
+        assert_eq!((conf_bps(15_000_000_000, 0), conf_bps(0, 5), conf_bps(-1, 5)), (0, 0, 0));
+        assert_eq!(conf_bps(1, u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn test_pyth_feed_prices_match_off_the_price_account() {
+        set_slot(1_000);
+        set_unix_time(1_700_000_000);
+        let feed_id = [7u8; 32];
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+
+        // Binding takes the feed id off a price account and a positive max age
+        let price = pyth_account(feed_id, 15_000_000_000, -8, 1_700_000_000);
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; FEED_LEN]), price];
+        assert_eq!(run_accounts(&mut accounts, &[0x1C, 0, 0, 0, 0]), Err(ProgramError::InvalidInstructionData));
+        accounts[3].owner = Pubkey::new_unique();
+        assert_eq!(run_accounts(&mut accounts, &[0x1C, 30, 0, 0, 0]), Err(MatcherError::InvalidFeedPrice.into()));
+        accounts[3].owner = PYTH_OWNERS[1];
+        run_accounts(&mut accounts, &[0x1C, 30, 0, 0, 0]).unwrap();
+        assert_eq!(read_u64(&accounts[2].data, FEED_MAGIC_OFF), FEED_MAGIC);
+        assert_eq!(read_u32(&accounts[2].data, FEED_MAX_AGE_OFF), 30);
+        assert_eq!(read_pubkey(&accounts[2].data, FEED_CTX_OFF), accounts[1].key);
+        assert_eq!(accounts[2].data[FEED_COUNT_OFF], 1);
+        assert_eq!(accounts[2].data[FEED_HEADER_LEN + FEED_ENTRY_ID_OFF..][..32], feed_id);
+        assert_eq!(accounts[1].data[CTX_BASE + CTX_FLAGS_OFF], CTX_FLAG_FEED);
+        assert_eq!(run_accounts(&mut accounts, &[0x1C, 30, 0, 0, 0]), Err(ProgramError::AccountAlreadyInitialized));
+        let [lp, ctx, feed, price] = accounts;
+
+        // Match prices off the feed, 150, not the call's 100, and echoes the
+        // call's price back
+        let slab = TestAccount::new(Pubkey::new_unique(), false, 0, vec![]);
+        let mut accounts = [lp, ctx, slab, feed, price];
+        set_unix_time(1_700_000_030);
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_000)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_u64(&ret, RET_ORACLE_ECHO_OFF), 100_000_000);
+        assert!(read_u64(&ret, RET_EXEC_PRICE_OFF) > 150_000_000);
+        assert_eq!(read_u64(&accounts[1].data, CTX_BASE + CTX_LAST_ORACLE_OFF), 150_000_000);
+        assert_eq!(read_u64(&ret, RET_LEN + QUOTE_ORACLE_OFF), 0);
+
+        // Its confidence interval widens the quote: 0.15 on 150 is 10bps
+        write_u64(&mut accounts[4].data, PYTH_CONF_OFF, 15_000_000);
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_000)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_u64(&ret, RET_LEN + QUOTE_ORACLE_OFF), 10);
+
+        // One second past the max age is stale
+        set_unix_time(1_700_000_031);
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000)), Err(MatcherError::FeedPriceStale.into()));
+        set_unix_time(1_700_000_000);
+        let mismatch: ProgramError = MatcherError::InvalidFeedPrice.into();
+        let [lp, ctx, slab, feed, price] = accounts;
+        for (account, tweak) in [
+            (pyth_account([8u8; 32], 15_000_000_000, -8, 1_700_000_000), None),
+            (pyth_account(feed_id, 0, -8, 1_700_000_000), None),
+            (pyth_account(feed_id, 15_000_000_000, -8, 1_700_000_000), Some(0u8)),
+        ] {
+            let mut account = account;
+            if let Some(level) = tweak {
+                account.data[PYTH_VERIFICATION_OFF] = level;
+            }
+            let mut accounts = [lp.clone(), ctx.clone(), slab.clone(), feed.clone(), account];
+            assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000)), Err(mismatch.clone()));
+        }
+
+        // The feed and price accounts can't be left out
+        let mut accounts = [lp.clone(), ctx.clone(), slab.clone(), feed.clone()];
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000)), Err(mismatch));
+        let mut accounts = [lp.clone(), ctx.clone(), slab, price];
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000)), Err(MatcherError::FeedMismatch.into()));
+
+        // Close takes the feed account with it
+        let destination = TestAccount::new(Pubkey::new_unique(), false, 0, vec![]);
+        let reclaimed = ctx.lamports + feed.lamports;
+        let mut accounts = [lp, ctx, destination, feed];
+        run_accounts(&mut accounts, &[0x05]).unwrap();
+        assert_eq!(accounts[2].lamports, reclaimed);
+        assert!(accounts[3].data.iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_pinned_feed_takes_only_its_price_account() {
+        set_slot(1_000);
+        set_unix_time(1_700_000_000);
+        let feed_id = [7u8; 32];
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+
+        // Pinned, the price account's key is bound rather than its feed id
+        let price = pyth_account(feed_id, 15_000_000_000, -8, 1_700_000_000);
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; FEED_LEN]), price];
+        assert_eq!(run_accounts(&mut accounts, &[0x1C, 30, 0, 0, 0, FEED_SOURCE_PYTH, 2]), Err(ProgramError::InvalidInstructionData));
+        run_accounts(&mut accounts, &[0x1C, 30, 0, 0, 0, FEED_SOURCE_PYTH, 1]).unwrap();
+        assert_eq!(read_pubkey(&accounts[2].data, FEED_HEADER_LEN + FEED_ENTRY_ID_OFF), accounts[3].key);
+        assert_eq!(accounts[2].data[FEED_HEADER_LEN + FEED_ENTRY_PINNED_OFF], 1);
+        let [lp, ctx, feed, price] = accounts;
+
+        let slab = TestAccount::new(Pubkey::new_unique(), false, 0, vec![]);
+        let mut accounts = [lp, ctx, slab, feed, price];
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_000)).unwrap();
+        assert_eq!(read_u64(&accounts[1].data, CTX_BASE + CTX_LAST_ORACLE_OFF), 150_000_000);
+
+        // Another verified update for the same feed is a look-alike
+        let price = core::mem::replace(&mut accounts[4], pyth_account(feed_id, 14_000_000_000, -8, 1_700_000_000));
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000)), Err(MatcherError::InvalidFeedPrice.into()));
+        let [lp, ctx, _, feed, _] = accounts;
+
+        // AddFeed pins the same way, and an unpinned feed is still bound by id
+        let other = pyth_account([8u8; 32], 15_100_000_000, -8, 1_700_000_000);
+        let mut accounts = [lp, ctx, feed, other];
+        assert_eq!(run_accounts(&mut accounts, &[0x1D, FEED_SOURCE_PYTH, 2]), Err(ProgramError::InvalidInstructionData));
+        run_accounts(&mut accounts, &[0x1D, FEED_SOURCE_PYTH, 0]).unwrap();
+        assert_eq!(accounts[2].data[FEED_HEADER_LEN + FEED_ENTRY_LEN + FEED_ENTRY_ID_OFF..][..32], [8u8; 32]);
+        assert_eq!(accounts[2].data[FEED_HEADER_LEN + FEED_ENTRY_LEN + FEED_ENTRY_PINNED_OFF], 0);
+        let [lp, ctx, feed, other] = accounts;
+        let mut accounts = [lp, ctx, TestAccount::new(Pubkey::new_unique(), false, 0, vec![]), feed, price, other];
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_000)).unwrap();
+        assert_eq!(read_u64(&accounts[1].data, CTX_BASE + CTX_LAST_ORACLE_OFF), 150_500_000);
+    }
+
+    /// A Switchboard pull feed holding `value` (scaled by 1e18).
+    fn switchboard_account(value: i128, updated_at: i64) -> TestAccount {
+        let mut data = vec![0u8; SWITCHBOARD_MIN_LEN];
+        data[..8].copy_from_slice(&SWITCHBOARD_DISCRIMINATOR);
+        write_i64(&mut data, SWITCHBOARD_UPDATED_OFF, updated_at);
+        write_i128(&mut data, SWITCHBOARD_VALUE_OFF, value);
+        let mut account = TestAccount::new(Pubkey::new_unique(), false, 0, data);
+        account.owner = SWITCHBOARD_OWNER;
+        account
+    }
+
+    #[test]
+    fn test_switchboard_feed_prices_match_off_the_aggregator() {
+        set_slot(1_000);
+        set_unix_time(1_700_000_000);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+
+        // The source byte picks the parser; the aggregator's key is the feed
+        let aggregator = switchboard_account(150_500_000_000_000_000_000, 1_700_000_000);
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; FEED_LEN]), aggregator];
+        assert_eq!(run_accounts(&mut accounts, &[0x1C, 30, 0, 0, 0]), Err(MatcherError::InvalidFeedPrice.into()));
+        assert_eq!(run_accounts(&mut accounts, &[0x1C, 30, 0, 0, 0, 3]), Err(ProgramError::InvalidInstructionData));
+        run_accounts(&mut accounts, &[0x1C, 30, 0, 0, 0, FEED_SOURCE_SWITCHBOARD]).unwrap();
+        assert_eq!(accounts[2].data[FEED_HEADER_LEN + FEED_ENTRY_SOURCE_OFF], FEED_SOURCE_SWITCHBOARD);
+        assert_eq!(read_pubkey(&accounts[2].data, FEED_HEADER_LEN + FEED_ENTRY_ID_OFF), accounts[3].key);
+        let [lp, ctx, feed, aggregator] = accounts;
+
+        let slab = TestAccount::new(Pubkey::new_unique(), false, 0, vec![]);
+        let mut accounts = [lp, ctx, slab, feed, aggregator];
+        set_unix_time(1_700_000_030);
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_000)).unwrap();
+        assert_eq!(read_u64(&accounts[1].data, CTX_BASE + CTX_LAST_ORACLE_OFF), 150_500_000);
+        set_unix_time(1_700_000_031);
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000)), Err(MatcherError::FeedPriceStale.into()));
+
+        // Another aggregator, or one with no positive price, is refused
+        set_unix_time(1_700_000_000);
+        let invalid = Err(MatcherError::InvalidFeedPrice.into());
+        accounts[4] = switchboard_account(150_500_000_000_000_000_000, 1_700_000_000);
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000)), invalid);
+        let key = read_pubkey(&accounts[3].data, FEED_HEADER_LEN + FEED_ENTRY_ID_OFF);
+        accounts[4] = switchboard_account(-1, 1_700_000_000);
+        accounts[4].key = key;
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000)), invalid);
+    }
+
+    /// A Chainlink OCR2 feed whose latest round, the second of a three-slot
+    /// live ring, answered `answer` (with `decimals`) at `timestamp`.
+    fn chainlink_account(answer: i128, decimals: u8, timestamp: u32) -> TestAccount {
+        let mut data = vec![0u8; CHAINLINK_HEADER_LEN + 3 * CHAINLINK_TRANSMISSION_LEN];
+        data[..8].copy_from_slice(&CHAINLINK_DISCRIMINATOR);
+        data[CHAINLINK_DECIMALS_OFF] = decimals;
+        write_u32(&mut data, CHAINLINK_ROUND_ID_OFF, 2);
+        write_u32(&mut data, CHAINLINK_LIVE_LENGTH_OFF, 3);
+        write_u32(&mut data, CHAINLINK_LIVE_CURSOR_OFF, 2);
+        let round = CHAINLINK_HEADER_LEN + CHAINLINK_TRANSMISSION_LEN;
+        write_u32(&mut data, round + CHAINLINK_TIMESTAMP_OFF, timestamp);
+        write_i128(&mut data, round + CHAINLINK_ANSWER_OFF, answer);
+        let mut account = TestAccount::new(Pubkey::new_unique(), false, 0, data);
+        account.owner = CHAINLINK_OWNER;
+        account
+    }
+
+    #[test]
+    fn test_chainlink_feed_prices_match_off_the_latest_round() {
+        set_slot(1_000);
+        set_unix_time(1_700_000_000);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+
+        // The feed account's key is the feed; an e8 answer is scaled to e6
+        let store = chainlink_account(15_050_000_000, 8, 1_700_000_000);
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; FEED_LEN]), store];
+        assert_eq!(run_accounts(&mut accounts, &[0x1C, 30, 0, 0, 0, FEED_SOURCE_SWITCHBOARD]), Err(MatcherError::InvalidFeedPrice.into()));
+        assert_eq!(run_accounts(&mut accounts, &[0x1C, 30, 0, 0, 0, 3]), Err(ProgramError::InvalidInstructionData));
+        run_accounts(&mut accounts, &[0x1C, 30, 0, 0, 0, FEED_SOURCE_CHAINLINK]).unwrap();
+        assert_eq!(accounts[2].data[FEED_HEADER_LEN + FEED_ENTRY_SOURCE_OFF], FEED_SOURCE_CHAINLINK);
+        assert_eq!(read_pubkey(&accounts[2].data, FEED_HEADER_LEN + FEED_ENTRY_ID_OFF), accounts[3].key);
+        let [lp, ctx, feed, store] = accounts;
+
+        let slab = TestAccount::new(Pubkey::new_unique(), false, 0, vec![]);
+        let mut accounts = [lp, ctx, slab, feed, store];
+        set_unix_time(1_700_000_030);
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_000)).unwrap();
+        assert_eq!(read_u64(&accounts[1].data, CTX_BASE + CTX_LAST_ORACLE_OFF), 150_500_000);
+
+        // The round's timestamp is its publish time
+        set_unix_time(1_700_000_031);
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000)), Err(MatcherError::FeedPriceStale.into()));
+
+        // A feed with no round yet, or a non-positive answer, is refused
+        set_unix_time(1_700_000_000);
+        let invalid = Err(MatcherError::InvalidFeedPrice.into());
+        let key = accounts[4].key;
+        write_u32(&mut accounts[4].data, CHAINLINK_ROUND_ID_OFF, 0);
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000)), invalid);
+        accounts[4] = chainlink_account(0, 8, 1_700_000_000);
+        accounts[4].key = key;
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000)), invalid);
+    }
+
+    #[test]
+    fn test_feeds_price_match_off_their_median() {
+        set_slot(1_000);
+        set_unix_time(1_700_000_000);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let a = pyth_account([1u8; 32], 15_000_000_000, -8, 1_700_000_000);
+        let b = pyth_account([2u8; 32], 15_100_000_000, -8, 1_700_000_000);
+        let c = switchboard_account(300_000_000_000_000_000_000, 1_700_000_000);
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; FEED_LEN]), a];
+        run_accounts(&mut accounts, &[0x1C, 30, 0, 0, 0]).unwrap();
+        let [lp, ctx, feed, a] = accounts;
+
+        // Each feed binds once, up to MAX_FEEDS of them
+        let mut accounts = [lp, ctx, feed, a.clone()];
+        assert_eq!(run_accounts(&mut accounts, &[0x1D, FEED_SOURCE_PYTH]), Err(MatcherError::InvalidFeedSet.into()));
+        accounts[3] = b.clone();
+        assert_eq!(run_accounts(&mut accounts, &[0x1D, 3]), Err(ProgramError::InvalidInstructionData));
+        run_accounts(&mut accounts, &[0x1D, FEED_SOURCE_PYTH]).unwrap();
+        accounts[3] = c.clone();
+        run_accounts(&mut accounts, &[0x1D, FEED_SOURCE_SWITCHBOARD]).unwrap();
+        assert_eq!(accounts[2].data[FEED_COUNT_OFF], 3);
+        accounts[3] = pyth_account([3u8; 32], 15_000_000_000, -8, 1_700_000_000);
+        assert_eq!(run_accounts(&mut accounts, &[0x1D, FEED_SOURCE_PYTH]), Err(MatcherError::InvalidFeedSet.into()));
+        let [lp, ctx, feed, _] = accounts;
+
+        // The one far off doesn't move the price, and every feed has to be
+        // there, in the order bound
+        let slab = TestAccount::new(Pubkey::new_unique(), false, 0, vec![]);
+        let mut accounts = [lp.clone(), ctx, slab.clone(), feed, a.clone(), b.clone(), c.clone()];
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_000)).unwrap();
+        assert_eq!(read_u64(&accounts[1].data, CTX_BASE + CTX_LAST_ORACLE_OFF), 151_000_000);
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_u64(&ret, RET_LEN + QUOTE_ORACLE_OFF), 0);
+        accounts.swap(4, 5);
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000)), Err(MatcherError::InvalidFeedPrice.into()));
+        accounts.swap(4, 5);
+        let [lp, ctx, slab, feed, ..] = accounts;
+        let mut short = [lp.clone(), ctx.clone(), slab.clone(), feed.clone(), a.clone(), b.clone()];
+        assert_eq!(run_accounts(&mut short, &match_data(100_000_000, 1_000)), Err(MatcherError::InvalidFeedPrice.into()));
+
+        // 150 to 300 is 9933bps of the median: past a 10% limit the trade is
+        // refused, or quoted that much wider
+        let mut accounts = [lp.clone(), ctx, feed];
+        assert_eq!(run_accounts(&mut accounts, &[0x1E, 0x11, 0x27, 0]), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(run_accounts(&mut accounts, &[0x1E, 0xE8, 0x03, 2]), Err(ProgramError::InvalidInstructionData));
+        run_accounts(&mut accounts, &[0x1E, 0xE8, 0x03, 0]).unwrap();
+        assert_eq!(read_u16(&accounts[2].data, FEED_MAX_DIVERGENCE_OFF), 1_000);
+        let [lp, ctx, feed] = accounts;
+        let mut accounts = [lp.clone(), ctx, slab.clone(), feed, a.clone(), b.clone(), c];
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000)), Err(MatcherError::FeedDivergence.into()));
+        let [lp, ctx, slab, feed, a, b, c] = accounts;
+        let mut accounts = [lp.clone(), ctx, feed];
+        run_accounts(&mut accounts, &[0x1E, 0xE8, 0x03, 1]).unwrap();
+        let [lp, ctx, feed] = accounts;
+        let mut accounts = [lp, ctx, slab, feed, a, b, c];
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_000)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_u64(&ret, RET_LEN + QUOTE_ORACLE_OFF), 9_933);
+
+        // Within the limit nothing is added
+        write_i128(&mut accounts[6].data, SWITCHBOARD_VALUE_OFF, 152_000_000_000_000_000_000);
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_000)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_u64(&ret, RET_LEN + QUOTE_ORACLE_OFF), 0);
+    }
+
+    fn fallback_data(fallback: u8, penalties: [u16; MAX_FEEDS]) -> Vec<u8> {
+        let mut d = vec![0x21, fallback];
+        penalties.iter().for_each(|p| d.extend_from_slice(&p.to_le_bytes()));
+        d
+    }
+
+    #[test]
+    fn test_feed_fallback_prices_off_the_first_good_feed() {
+        set_slot(1_000);
+        set_unix_time(1_700_000_000);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let primary = pyth_account([1u8; 32], 15_000_000_000, -8, 1_700_000_000);
+        let backup = pyth_account([2u8; 32], 15_100_000_000, -8, 1_700_000_020);
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; FEED_LEN]), primary];
+        run_accounts(&mut accounts, &[0x1C, 30, 0, 0, 0]).unwrap();
+        let [lp, ctx, feed, primary] = accounts;
+        let mut accounts = [lp, ctx, feed, backup];
+        run_accounts(&mut accounts, &[0x1D, FEED_SOURCE_PYTH]).unwrap();
+        let [lp, ctx, feed, backup] = accounts;
+
+        // The backup costs 25bps; penalties are capped at 100%
+        let mut accounts = [lp, ctx, feed];
+        assert_eq!(run_accounts(&mut accounts, &fallback_data(2, [0; MAX_FEEDS])), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(run_accounts(&mut accounts, &fallback_data(1, [0, 10_001, 0])), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(run_accounts(&mut accounts, &fallback_data(1, [0, 25, 0])[..7]), Err(ProgramError::InvalidInstructionData));
+        run_accounts(&mut accounts, &fallback_data(1, [0, 25, 0])).unwrap();
+        assert_eq!(accounts[2].data[FEED_FALLBACK_OFF], 1);
+        assert_eq!(read_u16(&accounts[2].data, FEED_HEADER_LEN + FEED_ENTRY_LEN + FEED_ENTRY_PENALTY_OFF), 25);
+        let [lp, ctx, feed] = accounts;
+
+        // Both fresh: the primary alone, at no penalty
+        let slab = TestAccount::new(Pubkey::new_unique(), false, 0, vec![]);
+        let mut accounts = [lp, ctx, slab, feed, primary, backup];
+        set_unix_time(1_700_000_020);
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_000)).unwrap();
+        assert_eq!(read_u64(&accounts[1].data, CTX_BASE + CTX_LAST_ORACLE_OFF), 150_000_000);
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_u64(&ret, RET_LEN + QUOTE_ORACLE_OFF), 0);
+
+        // The primary stale, or empty: the backup, 25bps wider
+        set_unix_time(1_700_000_031);
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_000)).unwrap();
+        assert_eq!(read_u64(&accounts[1].data, CTX_BASE + CTX_LAST_ORACLE_OFF), 151_000_000);
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_u64(&ret, RET_LEN + QUOTE_ORACLE_OFF), 25);
+        set_unix_time(1_700_000_020);
+        write_i64(&mut accounts[4].data, PYTH_PRICE_OFF, 0);
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_000)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_u64(&ret, RET_LEN + QUOTE_ORACLE_OFF), 25);
+
+        // The last feed has nothing to fall back to
+        set_unix_time(1_700_000_051);
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000)), Err(MatcherError::FeedPriceStale.into()));
+
+        // Another feed's account in the primary's place is refused, not
+        // skipped
+        set_unix_time(1_700_000_020);
+        accounts[4] = pyth_account([3u8; 32], 15_000_000_000, -8, 1_700_000_000);
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000)), Err(MatcherError::InvalidFeedPrice.into()));
+    }
+
+    #[test]
+    fn test_feed_slot_age_refuses_or_widens() {
+        set_slot(1_000);
+        set_unix_time(1_700_000_000);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let mut price = pyth_account([1u8; 32], 15_000_000_000, -8, 1_700_000_000);
+        write_u64(&mut price.data, PYTH_POSTED_SLOT_OFF, 990);
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; FEED_LEN]), price];
+        run_accounts(&mut accounts, &[0x1C, 30, 0, 0, 0]).unwrap();
+        let [lp, ctx, feed, price] = accounts;
+
+        // Off by default: only max_age_secs applies
+        let slab = TestAccount::new(Pubkey::new_unique(), false, 0, vec![]);
+        let mut accounts = [lp.clone(), ctx, slab.clone(), feed, price.clone()];
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_000)).unwrap();
+        let [lp, ctx, slab, feed, price] = accounts;
+
+        // Posted 10 slots ago: past a limit of 5 the trade is refused, or
+        // quoted at max_spread_bps
+        let mut accounts = [lp.clone(), ctx, feed];
+        assert_eq!(run_accounts(&mut accounts, &[0x20, 5, 0, 2]), Err(ProgramError::InvalidInstructionData));
+        run_accounts(&mut accounts, &[0x20, 5, 0, 0]).unwrap();
+        assert_eq!(read_u16(&accounts[2].data, FEED_MAX_AGE_SLOTS_OFF), 5);
+        let [lp, ctx, feed] = accounts;
+        let mut accounts = [lp.clone(), ctx, slab.clone(), feed, price.clone()];
+        assert_eq!(run_accounts(&mut accounts, &match_data(100_000_000, 1_000)), Err(MatcherError::FeedPriceStale.into()));
+        let [lp, ctx, slab, feed, price] = accounts;
+        let mut accounts = [lp.clone(), ctx, feed];
+        run_accounts(&mut accounts, &[0x20, 5, 0, 1]).unwrap();
+        let [lp, ctx, feed] = accounts;
+        let mut accounts = [lp, ctx, slab, feed, price];
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_000)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_u64(&ret, RET_LEN + QUOTE_ORACLE_OFF), BPS);
+        assert_eq!(read_u64(&ret, RET_LEN + QUOTE_SPREAD_OFF), 200);
+
+        // Within the limit nothing is added
+        set_slot(995);
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_000)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_u64(&ret, RET_LEN + QUOTE_ORACLE_OFF), 0);
+    }
+
+    fn publish_ladder_data(oracle_price_e6: u64, step: u128) -> Vec<u8> {
+        let mut d = vec![0x1B];
+        d.extend_from_slice(&oracle_price_e6.to_le_bytes());
+        d.extend_from_slice(&step.to_le_bytes());
+        d
+    }
+
+    #[test]
+    fn test_ladder_publishes_what_match_would_fill() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx), ctx_account(vec![0u8; LADDER_LEN])];
+        run_accounts(&mut accounts, &[0x1A]).unwrap();
+        assert_eq!(read_u64(&accounts[2].data, LADDER_MAGIC_OFF), LADDER_MAGIC);
+        assert_eq!(read_pubkey(&accounts[2].data, LADDER_CTX_OFF), accounts[1].key);
+        assert_eq!(run_accounts(&mut accounts, &[0x1A]), Err(ProgramError::AccountAlreadyInitialized));
+
+        // Every level is the quote for one trade of its size, smallest first
+        let level = |data: &[u8], off: usize, i: usize| {
+            let at = off + i * LEVEL_LEN;
+            (read_u128(data, at + LEVEL_SIZE_OFF), read_u64(data, at + LEVEL_PRICE_OFF), read_u64(data, at + LEVEL_SPREAD_OFF))
+        };
+        run_accounts(&mut accounts, &publish_ladder_data(100_000_000, 1_000_000)).unwrap();
+        let ladder = accounts[2].data.clone();
+        assert_eq!(read_u64(&ladder, LADDER_SLOT_OFF), 1_000);
+        assert_eq!(read_u64(&ladder, LADDER_ORACLE_OFF), 100_000_000);
+        assert_eq!(read_u128(&ladder, LADDER_STEP_OFF), 1_000_000);
+        assert_eq!(read_u32(&ladder, LADDER_BID_COUNT_OFF), MAX_LADDER_LEVELS as u32);
+        assert_eq!(read_u32(&ladder, LADDER_ASK_COUNT_OFF), MAX_LADDER_LEVELS as u32);
+        for i in 0..MAX_LADDER_LEVELS {
+            let size = 1_000_000 * (i as i128 + 1);
+            for (off, trade_size) in [(LADDER_BIDS_OFF, -size), (LADDER_ASKS_OFF, size)] {
+                let q = run_preview(&accounts[1].data, 100_000_000, trade_size).unwrap();
+                let expected = (size as u128, read_u64(&q, QUOTE_EXEC_PRICE_OFF), read_u64(&q, QUOTE_SPREAD_OFF));
+                assert_eq!(level(&ladder, off, i), expected);
+            }
+        }
+        assert!(level(&ladder, LADDER_BIDS_OFF, 0).1 < 100_000_000 && level(&ladder, LADDER_ASKS_OFF, 0).1 > 100_000_000);
+
+        // A side stops at its first clipped level
+        let step = 300_000_000_000;
+        run_accounts(&mut accounts, &publish_ladder_data(100_000_000, step)).unwrap();
+        let ladder = accounts[2].data.clone();
+        let clipped = (1..=MAX_LADDER_LEVELS)
+            .find(|i| {
+                let q = run_preview(&accounts[1].data, 100_000_000, step as i128 * *i as i128).unwrap();
+                read_i128(&q, QUOTE_FILL_SIZE_OFF) != step as i128 * *i as i128
+            })
+            .unwrap();
+        assert!(clipped < MAX_LADDER_LEVELS);
+        assert_eq!(read_u32(&ladder, LADDER_ASK_COUNT_OFF), clipped as u32);
+        assert!(level(&ladder, LADDER_ASKS_OFF, clipped - 1).0 < step * clipped as u128);
+        assert_eq!(level(&ladder, LADDER_ASKS_OFF, clipped), (0, 0, 0));
+
+        // A side the LP isn't quoting comes back empty with the reason
+        let [lp, ctx, ladder] = accounts;
+        let mut pair = [lp, ctx];
+        run_accounts(&mut pair, &[0x17, 1]).unwrap();
+        let [lp, ctx] = pair;
+        let mut accounts = [lp, ctx, ladder];
+        run_accounts(&mut accounts, &publish_ladder_data(100_000_000, 1_000_000)).unwrap();
+        assert_eq!(read_u32(&accounts[2].data, LADDER_ASK_COUNT_OFF), 0);
+        assert_eq!(read_u32(&accounts[2].data, LADDER_ASK_REASON_OFF), MatcherError::SideDisabled as u32);
+        assert_eq!(read_u32(&accounts[2].data, LADDER_BID_COUNT_OFF), MAX_LADDER_LEVELS as u32);
+        assert_eq!(read_u32(&accounts[2].data, LADDER_BID_REASON_OFF), 0);
+
+        // Only the authority publishes, a sane step, to its own ladder
+        assert_eq!(run_accounts(&mut accounts, &publish_ladder_data(100_000_000, 0)), Err(ProgramError::InvalidInstructionData));
+        let too_big = i128::MAX as u128 / MAX_LADDER_LEVELS as u128 + 1;
+        assert_eq!(run_accounts(&mut accounts, &publish_ladder_data(100_000_000, too_big)), Err(ProgramError::InvalidInstructionData));
+        accounts[0].signer = false;
+        assert_eq!(run_accounts(&mut accounts, &publish_ladder_data(100_000_000, 1)), Err(ProgramError::MissingRequiredSignature));
+        accounts[0].signer = true;
+        let [lp, ctx, ladder] = accounts;
+        let mut stranger = ladder.data.clone();
+        stranger[LADDER_CTX_OFF..LADDER_CTX_OFF + 32].copy_from_slice(Pubkey::new_unique().as_ref());
+        let mut accounts = [lp, ctx, ctx_account(stranger)];
+        assert_eq!(run_accounts(&mut accounts, &publish_ladder_data(100_000_000, 1)), Err(MatcherError::LadderMismatch.into()));
+
+        // Close reclaims it along with the context
+        let [lp, ctx, _] = accounts;
+        let reclaimed = ctx.lamports + ladder.lamports;
+        let mut accounts = [lp, ctx, TestAccount::new(Pubkey::new_unique(), false, 0, vec![]), ladder];
+        run_accounts(&mut accounts, &[0x05]).unwrap();
+        assert_eq!(accounts[2].lamports, reclaimed);
+        assert!(accounts[3].data.iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_soft_reject_returns_zero_fill_with_reason() {
+        set_slot(1);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        write_u128(&mut ctx, CTX_BASE + CTX_MAX_INVENTORY_OFF, 1_000);
+        // A book left past a lowered cap
+        write_i128(&mut ctx, CTX_BASE + CTX_INVENTORY_OFF, 1_500);
+
+        // Off by default: the inventory limit aborts
+        assert_eq!(
+            run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, -1)),
+            Err(MatcherError::InventoryLimit.into())
+        );
+
+        run_lp_ix(&mut ctx, &LP, true, &[0x10, 1]).unwrap();
+        assert_eq!(ctx[CTX_BASE + CTX_FLAGS_OFF], CTX_FLAG_SOFT_REJECT);
+        let state = ctx[CTX_BASE..].to_vec();
+        let reason = |ctx: &mut Vec<u8>, size: i128| {
+            run_lp_ix(ctx, &LP, true, &match_data(100_000_000, size)).unwrap();
+            let ret = RETURN_DATA.with(|r| r.borrow().clone());
+            assert_eq!(read_i128(&ret, RET_EXEC_SIZE_OFF), 0);
+            assert_eq!(read_u32(&ret, RET_FLAGS_OFF), FLAG_VALID);
+            assert_eq!(&ctx[..RET_LEN], &ret[..RET_LEN]);
+            read_u64(&ret, RET_REJECT_REASON_OFF)
+        };
+        assert_eq!(reason(&mut ctx, -1), MatcherError::InventoryLimit as u64);
+        assert_eq!(ctx[CTX_BASE..], state[..]);
+        write_i128(&mut ctx, CTX_BASE + CTX_INVENTORY_OFF, 0);
+
+        run_lp_ix(&mut ctx, &LP, true, &[0x0B, 1]).unwrap();
+        assert_eq!(reason(&mut ctx, 1), MatcherError::ReduceOnly as u64);
+        run_lp_ix(&mut ctx, &LP, true, &[0x0B, 0]).unwrap();
+
+        // A fill clears the reason; hard failures stay hard
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 10)).unwrap();
+        assert_eq!(read_u64(&ctx, RET_REJECT_REASON_OFF), 0);
+        assert_eq!(read_i128(&ctx, RET_EXEC_SIZE_OFF), 10);
+        assert_eq!(
+            run_lp_ix(&mut ctx, &LP, true, &match_data(0, 10)),
+            Err(MatcherError::ZeroOraclePrice.into())
+        );
+    }
+
+    #[test]
+    fn test_disabled_side_soft_rejects() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        write_i128(&mut ctx, CTX_BASE + CTX_INVENTORY_OFF, 500);
+
+        // Winding down a long book: bids only
+        run_lp_ix(&mut ctx, &LP, true, &[0x17, 1]).unwrap();
+        assert_eq!(ctx[CTX_BASE + CTX_FLAGS_OFF], CTX_FLAG_NO_ASKS);
+        let state = ctx[CTX_BASE..].to_vec();
+
+        // Soft-rejected without soft-reject mode, and nothing changes
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 100)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_i128(&ret, RET_EXEC_SIZE_OFF), 0);
+        assert_eq!(read_u64(&ret, RET_REJECT_REASON_OFF), MatcherError::SideDisabled as u64);
+        assert_eq!(ctx[CTX_BASE..], state[..]);
+        assert_eq!(run_preview(&ctx, 100_000_000, 100), Err(MatcherError::SideDisabled.into()));
+
+        // The other side still fills
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, -100)).unwrap();
+        assert_eq!(read_i128(&ctx, CTX_BASE + CTX_INVENTORY_OFF), 400);
+
+        let ret = run_two_sided(&ctx, 100_000_000, 100).unwrap();
+        assert_eq!(read_u64(&ret, TWO_SIDED_BID_REASON_OFF), 0);
+        assert_eq!(read_u64(&ret, TWO_SIDED_ASK_REASON_OFF), MatcherError::SideDisabled as u64);
+
+        // Both off quotes nothing; each flag clears on its own
+        run_lp_ix(&mut ctx, &LP, true, &[0x16, 1]).unwrap();
+        assert_eq!(ctx[CTX_BASE + CTX_FLAGS_OFF], CTX_FLAG_NO_BIDS | CTX_FLAG_NO_ASKS);
+        let ret = run_two_sided(&ctx, 100_000_000, 100).unwrap();
+        assert_eq!(read_u64(&ret, TWO_SIDED_BID_REASON_OFF), MatcherError::SideDisabled as u64);
+        run_lp_ix(&mut ctx, &LP, true, &[0x17, 0]).unwrap();
+        assert_eq!(ctx[CTX_BASE + CTX_FLAGS_OFF], CTX_FLAG_NO_BIDS);
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 100)).unwrap();
+        assert_eq!(read_i128(&ctx, CTX_BASE + CTX_INVENTORY_OFF), 500);
+
+        // Only the authority flips them
+        assert_eq!(run_lp_ix(&mut ctx, &LP, false, &[0x16, 0]), Err(ProgramError::MissingRequiredSignature));
+    }
+
+    #[test]
+    fn test_oversize_trade_partially_filled() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        // OI with no insurance → CRITICAL tier → 25% of max_fill
+        write_u128(&mut ctx, CTX_BASE + CTX_TOTAL_OI_OFF, 1_000);
+        write_u64(&mut ctx, CTX_BASE + CTX_SNAPSHOT_SLOT_OFF, 1_000);
+        let limit: i128 = 1_000_000_000_000 / 4;
+
+        for size in [limit + 1, -(limit * 3), i128::MIN] {
+            let clipped = limit * size.signum();
+            let ret = run_preview(&ctx, 100_000_000, size).unwrap();
+            assert_eq!(read_i128(&ret, QUOTE_FILL_SIZE_OFF), clipped);
+            assert_eq!(read_i128(&ret, QUOTE_NEW_INVENTORY_OFF), clipped);
+
+            let mut matched = ctx.clone();
+            run_lp_ix(&mut matched, &LP, true, &match_data(100_000_000, size)).unwrap();
+            assert_eq!(read_i128(&matched, RET_EXEC_SIZE_OFF), clipped);
+            assert_eq!(read_i128(&matched, CTX_BASE + CTX_INVENTORY_OFF), clipped);
+            // Priced as the clipped fill, not the request
+            assert_eq!(read_u64(&matched, RET_EXEC_PRICE_OFF), read_u64(&ret, QUOTE_EXEC_PRICE_OFF));
+            assert_eq!(
+                read_u64(&matched, RET_EXEC_PRICE_OFF),
+                read_u64(&run_preview(&ctx, 100_000_000, clipped).unwrap(), QUOTE_EXEC_PRICE_OFF)
+            );
+        }
+
+        // Within the limit the request is filled in full
+        let ret = run_preview(&ctx, 100_000_000, limit).unwrap();
+        assert_eq!(read_i128(&ret, QUOTE_FILL_SIZE_OFF), limit);
+
+        // Clipped again to what is left of the inventory cap
+        write_u128(&mut ctx, CTX_BASE + CTX_MAX_INVENTORY_OFF, limit as u128 - 1);
+        let ret = run_preview(&ctx, 100_000_000, limit * 2).unwrap();
+        assert_eq!(read_i128(&ret, QUOTE_FILL_SIZE_OFF), limit - 1);
+    }
+
+    fn two_sided_data(oracle_price_e6: u64, size: u128) -> Vec<u8> {
+        let mut d = vec![0x11];
+        d.extend_from_slice(&oracle_price_e6.to_le_bytes());
+        d.extend_from_slice(&size.to_le_bytes());
+        d
+    }
+
+    fn run_two_sided(ctx: &[u8], oracle_price_e6: u64, size: u128) -> Result<Vec<u8>, ProgramError> {
+        let mut accounts = [TestAccount::new(Pubkey::new_unique(), false, 0, ctx.to_vec())];
+        RETURN_DATA.with(|r| r.borrow_mut().clear());
+        run_accounts(&mut accounts, &two_sided_data(oracle_price_e6, size))?;
+        assert_eq!(accounts[0].data, ctx);
+        Ok(RETURN_DATA.with(|r| r.borrow().clone()))
+    }
+
+    #[test]
+    fn test_quote_two_sided() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        write_u128(&mut ctx, CTX_BASE + CTX_INSURANCE_OFF, 500);
+        write_u128(&mut ctx, CTX_BASE + CTX_TOTAL_OI_OFF, 1_000);
+        write_u64(&mut ctx, CTX_BASE + CTX_SNAPSHOT_SLOT_OFF, 1_000);
+        write_i128(&mut ctx, CTX_BASE + CTX_INVENTORY_OFF, 500);
+        write_u128(&mut ctx, CTX_BASE + CTX_MAX_INVENTORY_OFF, 10_000);
+
+        let ret = run_two_sided(&ctx, 100_000_000, 1_000).unwrap();
+        assert_eq!(ret.len(), TWO_SIDED_LEN);
+        assert_eq!(read_u64(&ret, TWO_SIDED_BID_REASON_OFF), 0);
+        assert_eq!(read_u64(&ret, TWO_SIDED_ASK_REASON_OFF), 0);
+        // Each side is exactly what a one-sided preview returns
+        assert_eq!(&ret[TWO_SIDED_BID_OFF..TWO_SIDED_ASK_OFF], &run_preview(&ctx, 100_000_000, -1_000).unwrap()[..]);
+        assert_eq!(&ret[TWO_SIDED_ASK_OFF..], &run_preview(&ctx, 100_000_000, 1_000).unwrap()[..]);
+        let bid = read_u64(&ret, TWO_SIDED_BID_OFF + QUOTE_EXEC_PRICE_OFF);
+        let ask = read_u64(&ret, TWO_SIDED_ASK_OFF + QUOTE_EXEC_PRICE_OFF);
+        assert!(bid < 100_000_000 && 100_000_000 < ask);
+
+        // Reduce-only on a long book: only the side that shrinks it is quoted
+        run_lp_ix(&mut ctx, &LP, true, &[0x0B, 1]).unwrap();
+        let ret = run_two_sided(&ctx, 100_000_000, 100).unwrap();
+        assert_eq!(read_u64(&ret, TWO_SIDED_BID_REASON_OFF), 0);
+        assert_eq!(read_u64(&ret, TWO_SIDED_ASK_REASON_OFF), MatcherError::ReduceOnly as u64);
+        assert!(ret[TWO_SIDED_ASK_OFF..].iter().all(|b| *b == 0));
+        assert_eq!(read_i128(&ret, TWO_SIDED_BID_OFF + QUOTE_NEW_INVENTORY_OFF), 400);
+
+        // Failures that hit both sides fail the call
+        assert_eq!(run_two_sided(&ctx, 0, 1), Err(MatcherError::ZeroOraclePrice.into()));
+        assert_eq!(run_two_sided(&ctx, 100_000_000, u128::MAX), Err(ProgramError::InvalidInstructionData));
+        run_lp_ix(&mut ctx, &LP, true, &[0x06, 1]).unwrap();
+        assert_eq!(run_two_sided(&ctx, 100_000_000, 1), Err(MatcherError::Paused.into()));
+    }
+
+    #[test]
+    fn test_quote_preview_rejects_like_match() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        // OI with no insurance → CRITICAL tier → 25% of max_fill
+        write_u128(&mut ctx, CTX_BASE + CTX_TOTAL_OI_OFF, 1_000);
+        write_u64(&mut ctx, CTX_BASE + CTX_SNAPSHOT_SLOT_OFF, 1_000);
+        assert_eq!(
+            run_preview(&ctx, 0, 1),
+            Err(MatcherError::ZeroOraclePrice.into())
+        );
+        run_lp_ix(&mut ctx, &LP, true, &[0x06, 1]).unwrap();
+        assert_eq!(
+            run_preview(&ctx, 100_000_000, 1),
+            Err(MatcherError::Paused.into())
+        );
+    }
+
+    #[test]
+    fn test_paused_rejects_match() {
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        run_lp_ix(&mut ctx, &LP, true, &[0x06, 1]).unwrap();
+        assert_eq!(ctx[CTX_BASE + CTX_FLAGS_OFF] & CTX_FLAG_PAUSED, CTX_FLAG_PAUSED);
+
+        let before = ctx.clone();
+        assert_eq!(
+            run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 1_000)),
+            Err(MatcherError::Paused.into())
+        );
+        assert_eq!(ctx, before);
+
+        run_lp_ix(&mut ctx, &LP, true, &[0x06, 0]).unwrap();
+        assert_eq!(ctx[CTX_BASE + CTX_FLAGS_OFF] & CTX_FLAG_PAUSED, 0);
+    }
+
+    #[test]
+    fn test_client_layout_matches_program() {
+        use provenance_client::layout;
+        let pairs = [
+            (MAGIC, layout::MAGIC),
+            (VERSION as u64, layout::VERSION as u64),
+            (VERSION_V5 as u64, layout::VERSION_V5 as u64),
+            (KIND_PLAIN as u64, layout::KIND_PLAIN as u64),
+            (KIND_CREDIBILITY as u64, layout::KIND_CREDIBILITY as u64),
+            (KIND_MULTI as u64, layout::KIND_MULTI as u64),
+            (KIND_STABLE as u64, layout::KIND_STABLE as u64),
+            (CALL_LEN as u64, layout::CALL_LEN as u64),
+            (CALL_REFERRER_OFF as u64, layout::CALL_REFERRER_OFF as u64),
+            (REFERRED_CALL_LEN as u64, layout::REFERRED_CALL_LEN as u64),
+            (PREVIEW_CALL_LEN as u64, layout::PREVIEW_CALL_LEN as u64),
+            (RET_ABI_VERSION_OFF as u64, layout::RET_ABI_VERSION_OFF as u64),
+            (RET_FLAGS_OFF as u64, layout::RET_FLAGS_OFF as u64),
+            (RET_EXEC_PRICE_OFF as u64, layout::RET_EXEC_PRICE_OFF as u64),
+            (RET_EXEC_SIZE_OFF as u64, layout::RET_EXEC_SIZE_OFF as u64),
+            (RET_REQ_ID_OFF as u64, layout::RET_REQ_ID_OFF as u64),
+            (RET_LP_ACCOUNT_ID_OFF as u64, layout::RET_LP_ACCOUNT_ID_OFF as u64),
+            (RET_ORACLE_ECHO_OFF as u64, layout::RET_ORACLE_ECHO_OFF as u64),
+            (RET_REJECT_REASON_OFF as u64, layout::RET_REJECT_REASON_OFF as u64),
+            (RET_LEN as u64, layout::RET_LEN as u64),
+            (MATCHER_ABI_VERSION as u64, layout::MATCHER_ABI_VERSION as u64),
+            (FLAG_VALID as u64, layout::FLAG_VALID as u64),
+            (MATCH_RET_FILL_SEQ_OFF as u64, layout::MATCH_RET_FILL_SEQ_OFF as u64),
+            (MATCH_RET_HEDGE_OFF as u64, layout::MATCH_RET_HEDGE_OFF as u64),
+            (MATCH_RET_LEN as u64, layout::MATCH_RET_LEN as u64),
+            (QUOTE_EXEC_PRICE_OFF as u64, layout::QUOTE_EXEC_PRICE_OFF as u64),
+            (QUOTE_COVERAGE_OFF as u64, layout::QUOTE_COVERAGE_OFF as u64),
+            (QUOTE_TIER_OFF as u64, layout::QUOTE_TIER_OFF as u64),
+            (QUOTE_FILL_PCT_OFF as u64, layout::QUOTE_FILL_PCT_OFF as u64),
+            (QUOTE_TIER_SPREAD_OFF as u64, layout::QUOTE_TIER_SPREAD_OFF as u64),
+            (QUOTE_IMBALANCE_OFF as u64, layout::QUOTE_IMBALANCE_OFF as u64),
+            (QUOTE_IMPACT_OFF as u64, layout::QUOTE_IMPACT_OFF as u64),
+            (QUOTE_STALE_OFF as u64, layout::QUOTE_STALE_OFF as u64),
+            (QUOTE_CRANK_OFF as u64, layout::QUOTE_CRANK_OFF as u64),
+            (QUOTE_LIQ_OFF as u64, layout::QUOTE_LIQ_OFF as u64),
+            (QUOTE_SPREAD_OFF as u64, layout::QUOTE_SPREAD_OFF as u64),
+            (QUOTE_SKEW_OFF as u64, layout::QUOTE_SKEW_OFF as u64),
+            (QUOTE_FUNDING_SKEW_OFF as u64, layout::QUOTE_FUNDING_SKEW_OFF as u64),
+            (QUOTE_FEE_OFF as u64, layout::QUOTE_FEE_OFF as u64),
+            (QUOTE_NEW_INVENTORY_OFF as u64, layout::QUOTE_NEW_INVENTORY_OFF as u64),
+            (QUOTE_FILL_SIZE_OFF as u64, layout::QUOTE_FILL_SIZE_OFF as u64),
+            (QUOTE_DISCOUNT_OFF as u64, layout::QUOTE_DISCOUNT_OFF as u64),
+            (QUOTE_FLOW_OFF as u64, layout::QUOTE_FLOW_OFF as u64),
+            (QUOTE_ANCHOR_OFF as u64, layout::QUOTE_ANCHOR_OFF as u64),
+            (QUOTE_SIGNAL_OFF as u64, layout::QUOTE_SIGNAL_OFF as u64),
+            (QUOTE_SCHEDULE_OFF as u64, layout::QUOTE_SCHEDULE_OFF as u64),
+            (QUOTE_ORACLE_OFF as u64, layout::QUOTE_ORACLE_OFF as u64),
+            (QUOTE_REBATE_OFF as u64, layout::QUOTE_REBATE_OFF as u64),
+            (QUOTE_LEN as u64, layout::QUOTE_LEN as u64),
+            (TWO_SIDED_BID_REASON_OFF as u64, layout::TWO_SIDED_BID_REASON_OFF as u64),
+            (TWO_SIDED_ASK_REASON_OFF as u64, layout::TWO_SIDED_ASK_REASON_OFF as u64),
+            (TWO_SIDED_BID_OFF as u64, layout::TWO_SIDED_BID_OFF as u64),
+            (TWO_SIDED_ASK_OFF as u64, layout::TWO_SIDED_ASK_OFF as u64),
+            (TWO_SIDED_LEN as u64, layout::TWO_SIDED_LEN as u64),
+            (CTX_MAGIC_OFF as u64, layout::CTX_MAGIC_OFF as u64),
+            (CTX_VERSION_OFF as u64, layout::CTX_VERSION_OFF as u64),
+            (CTX_KIND_OFF as u64, layout::CTX_KIND_OFF as u64),
+            (CTX_LP_PDA_OFF as u64, layout::CTX_LP_PDA_OFF as u64),
+            (CTX_BASE_FEE_OFF as u64, layout::CTX_BASE_FEE_OFF as u64),
+            (CTX_MIN_SPREAD_OFF as u64, layout::CTX_MIN_SPREAD_OFF as u64),
+            (CTX_MAX_SPREAD_OFF as u64, layout::CTX_MAX_SPREAD_OFF as u64),
+            (CTX_IMBALANCE_K_OFF as u64, layout::CTX_IMBALANCE_K_OFF as u64),
+            (CTX_LIQUIDITY_OFF as u64, layout::CTX_LIQUIDITY_OFF as u64),
+            (CTX_MAX_FILL_OFF as u64, layout::CTX_MAX_FILL_OFF as u64),
+            (CTX_INVENTORY_OFF as u64, layout::CTX_INVENTORY_OFF as u64),
+            (CTX_LAST_ORACLE_OFF as u64, layout::CTX_LAST_ORACLE_OFF as u64),
+            (CTX_LAST_EXEC_OFF as u64, layout::CTX_LAST_EXEC_OFF as u64),
+            (CTX_MAX_INVENTORY_OFF as u64, layout::CTX_MAX_INVENTORY_OFF as u64),
+            (CTX_INSURANCE_OFF as u64, layout::CTX_INSURANCE_OFF as u64),
+            (CTX_TOTAL_OI_OFF as u64, layout::CTX_TOTAL_OI_OFF as u64),
+            (CTX_MARKET_AGE_OFF as u64, layout::CTX_MARKET_AGE_OFF as u64),
+            (CTX_LAST_DEFICIT_OFF as u64, layout::CTX_LAST_DEFICIT_OFF as u64),
+            (CTX_SNAPSHOT_SLOT_OFF as u64, layout::CTX_SNAPSHOT_SLOT_OFF as u64),
+            (CTX_AGE_HALFLIFE_OFF as u64, layout::CTX_AGE_HALFLIFE_OFF as u64),
+            (CTX_INSURANCE_WEIGHT_OFF as u64, layout::CTX_INSURANCE_WEIGHT_OFF as u64),
+            (CTX_IMPACT_MODE_OFF as u64, layout::CTX_IMPACT_MODE_OFF as u64),
+            (CTX_COVERAGE_CURVE_OFF as u64, layout::CTX_COVERAGE_CURVE_OFF as u64),
+            (CTX_FLAGS_OFF as u64, layout::CTX_FLAGS_OFF as u64),
+            (CTX_BUMP_OFF as u64, layout::CTX_BUMP_OFF as u64),
+            (CREATION_SLOT_OFF as u64, layout::CREATION_SLOT_OFF as u64),
+            (CREATION_CREATOR_OFF as u64, layout::CREATION_CREATOR_OFF as u64),
+            (CREATION_DERIVED_OFF as u64, layout::CREATION_DERIVED_OFF as u64),
+            (CREATION_LEN as u64, layout::CREATION_LEN as u64),
+            (CTX_IMPACT_K_OFF as u64, layout::CTX_IMPACT_K_OFF as u64),
+            (CTX_SKEW_K_OFF as u64, layout::CTX_SKEW_K_OFF as u64),
+            (CTX_CURVE_POINTS_OFF as u64, layout::CTX_CURVE_POINTS_OFF as u64),
+            (CTX_LAST_CRANK_OFF as u64, layout::CTX_LAST_CRANK_OFF as u64),
+            (CTX_LAST_LIQS_OFF as u64, layout::CTX_LAST_LIQS_OFF as u64),
+            (CTX_LIQ_HEAT_OFF as u64, layout::CTX_LIQ_HEAT_OFF as u64),
+            (CTX_LIQ_HALFLIFE_OFF as u64, layout::CTX_LIQ_HALFLIFE_OFF as u64),
+            (CTX_PEG_BAND_OFF as u64, layout::CTX_PEG_BAND_OFF as u64),
+            (CTX_SPOT_WEIGHT_OFF as u64, layout::CTX_SPOT_WEIGHT_OFF as u64),
+            (CTX_ORACLE_DECIMALS_OFF as u64, layout::CTX_ORACLE_DECIMALS_OFF as u64),
+            (ORACLE_DECIMALS_E6 as u64, layout::ORACLE_DECIMALS_E6 as u64),
+            (MAX_ORACLE_DECIMALS as u64, layout::MAX_ORACLE_DECIMALS as u64),
+            (CTX_BASE as u64, layout::CTX_BASE as u64),
+            (CTX_LEN as u64, layout::CTX_LEN as u64),
+            (EXT_FUNDING_RATE_OFF as u64, layout::EXT_FUNDING_RATE_OFF as u64),
+            (EXT_FUNDING_HORIZON_OFF as u64, layout::EXT_FUNDING_HORIZON_OFF as u64),
+            (EXT_SLAB_OFF as u64, layout::EXT_SLAB_OFF as u64),
+            (EXT_PENDING_SLOT_OFF as u64, layout::EXT_PENDING_SLOT_OFF as u64),
+            (EXT_PENDING_PARAMS_OFF as u64, layout::EXT_PENDING_PARAMS_OFF as u64),
+            (PENDING_HEAD_LEN as u64, layout::PENDING_HEAD_LEN as u64),
+            (EXT_AUTHORITY_OFF as u64, layout::EXT_AUTHORITY_OFF as u64),
+            (EXT_PENDING_AUTHORITY_OFF as u64, layout::EXT_PENDING_AUTHORITY_OFF as u64),
+            (EXT_PERCOLATOR_OFF as u64, layout::EXT_PERCOLATOR_OFF as u64),
+            (EXT_ORACLE_JUMP_BPS_OFF as u64, layout::EXT_ORACLE_JUMP_BPS_OFF as u64),
+            (EXT_ORACLE_JUMP_WINDOW_OFF as u64, layout::EXT_ORACLE_JUMP_WINDOW_OFF as u64),
+            (EXT_LAST_ORACLE_SLOT_OFF as u64, layout::EXT_LAST_ORACLE_SLOT_OFF as u64),
+            (EXT_FILL_SEQ_OFF as u64, layout::EXT_FILL_SEQ_OFF as u64),
+            (EXT_CUM_NOTIONAL_OFF as u64, layout::EXT_CUM_NOTIONAL_OFF as u64),
+            (EXT_CUM_FEES_OFF as u64, layout::EXT_CUM_FEES_OFF as u64),
+            (EXT_GROSS_LONG_OFF as u64, layout::EXT_GROSS_LONG_OFF as u64),
+            (EXT_GROSS_SHORT_OFF as u64, layout::EXT_GROSS_SHORT_OFF as u64),
+            (EXT_FLOW_K_OFF as u64, layout::EXT_FLOW_K_OFF as u64),
+            (EXT_TWAP_WINDOW_OFF as u64, layout::EXT_TWAP_WINDOW_OFF as u64),
+            (EXT_PENDING_PARAMS_TAIL_OFF as u64, layout::EXT_PENDING_PARAMS_TAIL_OFF as u64),
+            (EXT_STATS_OFF as u64, layout::EXT_STATS_OFF as u64),
+            (EXT_SIGNAL_WEIGHTS_OFF as u64, layout::EXT_SIGNAL_WEIGHTS_OFF as u64),
+            (EXT_DEFICIT_HALFLIFE_OFF as u64, layout::EXT_DEFICIT_HALFLIFE_OFF as u64),
+            (EXT_RFQ_SIGNER_OFF as u64, layout::EXT_RFQ_SIGNER_OFF as u64),
+            (EXT_RFQ_PRICE_OFF as u64, layout::EXT_RFQ_PRICE_OFF as u64),
+            (EXT_RFQ_EXPIRY_OFF as u64, layout::EXT_RFQ_EXPIRY_OFF as u64),
+            (EXT_RFQ_NONCE_OFF as u64, layout::EXT_RFQ_NONCE_OFF as u64),
+            (EXT_RFQ_SIZE_OFF as u64, layout::EXT_RFQ_SIZE_OFF as u64),
+            (EXT_LIQ_AUCTION_SLOTS_OFF as u64, layout::EXT_LIQ_AUCTION_SLOTS_OFF as u64),
+            (EXT_LIQ_AUCTION_BPS_OFF as u64, layout::EXT_LIQ_AUCTION_BPS_OFF as u64),
+            (EXT_LAST_LIQ_SLOT_OFF as u64, layout::EXT_LAST_LIQ_SLOT_OFF as u64),
+            (EXT_MARKET_SLOTS_OFF as u64, layout::EXT_MARKET_SLOTS_OFF as u64),
+            (EXT_BASE as u64, layout::EXT_BASE as u64),
+            (EXT_LEN as u64, layout::EXT_LEN as u64),
+            (CTX_EXT_LEN as u64, layout::CTX_EXT_LEN as u64),
+            (MKT_SLAB_OFF as u64, layout::MKT_SLAB_OFF as u64),
+            (MKT_MAX_FILL_OFF as u64, layout::MKT_MAX_FILL_OFF as u64),
+            (MKT_MAX_INVENTORY_OFF as u64, layout::MKT_MAX_INVENTORY_OFF as u64),
+            (MKT_INVENTORY_OFF as u64, layout::MKT_INVENTORY_OFF as u64),
+            (MKT_INSURANCE_OFF as u64, layout::MKT_INSURANCE_OFF as u64),
+            (MKT_TOTAL_OI_OFF as u64, layout::MKT_TOTAL_OI_OFF as u64),
+            (MKT_GROSS_LONG_OFF as u64, layout::MKT_GROSS_LONG_OFF as u64),
+            (MKT_GROSS_SHORT_OFF as u64, layout::MKT_GROSS_SHORT_OFF as u64),
+            (MKT_SNAPSHOT_SLOT_OFF as u64, layout::MKT_SNAPSHOT_SLOT_OFF as u64),
+            (MKT_LAST_CRANK_OFF as u64, layout::MKT_LAST_CRANK_OFF as u64),
+            (MKT_LAST_LIQS_OFF as u64, layout::MKT_LAST_LIQS_OFF as u64),
+            (MKT_FUNDING_RATE_OFF as u64, layout::MKT_FUNDING_RATE_OFF as u64),
+            (MKT_MARKET_AGE_OFF as u64, layout::MKT_MARKET_AGE_OFF as u64),
+            (MKT_LAST_DEFICIT_OFF as u64, layout::MKT_LAST_DEFICIT_OFF as u64),
+            (MKT_LAST_LIQ_SLOT_OFF as u64, layout::MKT_LAST_LIQ_SLOT_OFF as u64),
+            (MKT_LAST_ORACLE_OFF as u64, layout::MKT_LAST_ORACLE_OFF as u64),
+            (MKT_LAST_ORACLE_SLOT_OFF as u64, layout::MKT_LAST_ORACLE_SLOT_OFF as u64),
+            (MKT_LAST_EXEC_OFF as u64, layout::MKT_LAST_EXEC_OFF as u64),
+            (MKT_LIQ_HEAT_OFF as u64, layout::MKT_LIQ_HEAT_OFF as u64),
+            (MARKETS_BASE as u64, layout::MARKETS_BASE as u64),
+            (MARKET_LEN as u64, layout::MARKET_LEN as u64),
+            (MAX_MARKETS as u64, layout::MAX_MARKETS as u64),
+            (CTX_SHARED_LEN as u64, layout::CTX_SHARED_LEN as u64),
+            (CTX_FLAG_PAUSED as u64, layout::CTX_FLAG_PAUSED as u64),
+            (CTX_FLAG_REDUCE_ONLY as u64, layout::CTX_FLAG_REDUCE_ONLY as u64),
+            (CTX_FLAG_RETURN_DATA_ONLY as u64, layout::CTX_FLAG_RETURN_DATA_ONLY as u64),
+            (CTX_FLAG_SOFT_REJECT as u64, layout::CTX_FLAG_SOFT_REJECT as u64),
+            (CTX_FLAG_NO_BIDS as u64, layout::CTX_FLAG_NO_BIDS as u64),
+            (CTX_FLAG_NO_ASKS as u64, layout::CTX_FLAG_NO_ASKS as u64),
+            (CTX_FLAG_SCHEDULED as u64, layout::CTX_FLAG_SCHEDULED as u64),
+            (CTX_FLAG_FEED as u64, layout::CTX_FLAG_FEED as u64),
+            (STATS_MAGIC, layout::STATS_MAGIC),
+            (STATS_VERSION as u64, layout::STATS_VERSION as u64),
+            (STATS_MAGIC_OFF as u64, layout::STATS_MAGIC_OFF as u64),
+            (STATS_VERSION_OFF as u64, layout::STATS_VERSION_OFF as u64),
+            (STATS_CAPACITY_OFF as u64, layout::STATS_CAPACITY_OFF as u64),
+            (STATS_CTX_OFF as u64, layout::STATS_CTX_OFF as u64),
+            (STATS_HEAD_OFF as u64, layout::STATS_HEAD_OFF as u64),
+            (STATS_COUNT_OFF as u64, layout::STATS_COUNT_OFF as u64),
+            (STATS_ORACLE_HEAD_OFF as u64, layout::STATS_ORACLE_HEAD_OFF as u64),
+            (STATS_ORACLE_COUNT_OFF as u64, layout::STATS_ORACLE_COUNT_OFF as u64),
+            (STATS_HEADER_LEN as u64, layout::STATS_HEADER_LEN as u64),
+            (STATS_CAPACITY as u64, layout::STATS_CAPACITY as u64),
+            (FILL_SEQ_OFF as u64, layout::FILL_SEQ_OFF as u64),
+            (FILL_SLOT_OFF as u64, layout::FILL_SLOT_OFF as u64),
+            (FILL_SIZE_OFF as u64, layout::FILL_SIZE_OFF as u64),
+            (FILL_PRICE_OFF as u64, layout::FILL_PRICE_OFF as u64),
+            (FILL_SPREAD_OFF as u64, layout::FILL_SPREAD_OFF as u64),
+            (FILL_LEN as u64, layout::FILL_LEN as u64),
+            (ORACLE_CAPACITY as u64, layout::ORACLE_CAPACITY as u64),
+            (ORACLE_SLOT_OFF as u64, layout::ORACLE_SLOT_OFF as u64),
+            (ORACLE_PRICE_OFF as u64, layout::ORACLE_PRICE_OFF as u64),
+            (ORACLE_SAMPLE_LEN as u64, layout::ORACLE_SAMPLE_LEN as u64),
+            (STATS_ORACLE_RING_OFF as u64, layout::STATS_ORACLE_RING_OFF as u64),
+            (STATS_LEN as u64, layout::STATS_LEN as u64),
+            (SIGNAL_MAGIC, layout::SIGNAL_MAGIC),
+            (SIGNAL_MAGIC_OFF as u64, layout::SIGNAL_MAGIC_OFF as u64),
+            (SIGNAL_VALUE_OFF as u64, layout::SIGNAL_VALUE_OFF as u64),
+            (SIGNAL_WEIGHT_OFF as u64, layout::SIGNAL_WEIGHT_OFF as u64),
+            (SIGNAL_STALE_AFTER_OFF as u64, layout::SIGNAL_STALE_AFTER_OFF as u64),
+            (SIGNAL_LEN as u64, layout::SIGNAL_LEN as u64),
+            (MAX_SIGNAL_ACCOUNTS as u64, layout::MAX_SIGNAL_ACCOUNTS as u64),
+            (SCHEDULE_MAGIC, layout::SCHEDULE_MAGIC),
+            (SCHEDULE_VERSION as u64, layout::SCHEDULE_VERSION as u64),
+            (SCHEDULE_MAGIC_OFF as u64, layout::SCHEDULE_MAGIC_OFF as u64),
+            (SCHEDULE_VERSION_OFF as u64, layout::SCHEDULE_VERSION_OFF as u64),
+            (SCHEDULE_COUNT_OFF as u64, layout::SCHEDULE_COUNT_OFF as u64),
+            (SCHEDULE_CTX_OFF as u64, layout::SCHEDULE_CTX_OFF as u64),
+            (SCHEDULE_HEADER_LEN as u64, layout::SCHEDULE_HEADER_LEN as u64),
+            (MAX_SCHEDULE_ENTRIES as u64, layout::MAX_SCHEDULE_ENTRIES as u64),
+            (SCHED_START_OFF as u64, layout::SCHED_START_OFF as u64),
+            (SCHED_END_OFF as u64, layout::SCHED_END_OFF as u64),
+            (SCHED_MULT_OFF as u64, layout::SCHED_MULT_OFF as u64),
+            (SCHED_ENTRY_LEN as u64, layout::SCHED_ENTRY_LEN as u64),
+            (SCHEDULE_LEN as u64, layout::SCHEDULE_LEN as u64),
+            (FEE_TIERS_OFF as u64, layout::FEE_TIERS_OFF as u64),
+            (MAX_FEE_TIERS as u64, layout::MAX_FEE_TIERS as u64),
+            (FEE_TIER_NOTIONAL_OFF as u64, layout::FEE_TIER_NOTIONAL_OFF as u64),
+            (FEE_TIER_FEE_OFF as u64, layout::FEE_TIER_FEE_OFF as u64),
+            (FEE_TIER_LEN as u64, layout::FEE_TIER_LEN as u64),
+            (SCHEDULE_FEES_LEN as u64, layout::SCHEDULE_FEES_LEN as u64),
+            (FEE_TIER_ARG_LEN as u64, layout::FEE_TIER_ARG_LEN as u64),
+            (SCHEDULE_REBATE_OFF as u64, layout::SCHEDULE_REBATE_OFF as u64),
+            (SCHEDULE_FEE_DISCOUNT_OFF as u64, layout::SCHEDULE_FEE_DISCOUNT_OFF as u64),
+            (SCHEDULE_REBATE_LEN as u64, layout::SCHEDULE_REBATE_LEN as u64),
+            (SCHEDULE_MIN_NOTIONAL_OFF as u64, layout::SCHEDULE_MIN_NOTIONAL_OFF as u64),
+            (SCHEDULE_MIN_NOTIONAL_LEN as u64, layout::SCHEDULE_MIN_NOTIONAL_LEN as u64),
+            (STATS_WITHDRAWN_OFF as u64, layout::STATS_WITHDRAWN_OFF as u64),
+            (STATS_WITHDRAWALS_OFF as u64, layout::STATS_WITHDRAWALS_OFF as u64),
+            (STATS_LAST_WITHDRAWAL_OFF as u64, layout::STATS_LAST_WITHDRAWAL_OFF as u64),
+            (STATS_LEDGER_LEN as u64, layout::STATS_LEDGER_LEN as u64),
+            (STATS_SKIM_BPS_OFF as u64, layout::STATS_SKIM_BPS_OFF as u64),
+            (STATS_SKIM_ACCRUED_OFF as u64, layout::STATS_SKIM_ACCRUED_OFF as u64),
+            (STATS_SKIM_CLAIMED_OFF as u64, layout::STATS_SKIM_CLAIMED_OFF as u64),
+            (STATS_SKIM_CLAIMS_OFF as u64, layout::STATS_SKIM_CLAIMS_OFF as u64),
+            (STATS_TREASURY_OFF as u64, layout::STATS_TREASURY_OFF as u64),
+            (STATS_PROTOCOL_FEE_BPS_OFF as u64, layout::STATS_PROTOCOL_FEE_BPS_OFF as u64),
+            (STATS_PROTOCOL_ACCRUED_OFF as u64, layout::STATS_PROTOCOL_ACCRUED_OFF as u64),
+            (STATS_PROTOCOL_SWEPT_OFF as u64, layout::STATS_PROTOCOL_SWEPT_OFF as u64),
+            (STATS_PROTOCOL_SWEEPS_OFF as u64, layout::STATS_PROTOCOL_SWEEPS_OFF as u64),
+            (STATS_PROTOCOL_LEN as u64, layout::STATS_PROTOCOL_LEN as u64),
+            (STATS_PNL_ENTRY_OFF as u64, layout::STATS_PNL_ENTRY_OFF as u64),
+            (STATS_PNL_MARK_OFF as u64, layout::STATS_PNL_MARK_OFF as u64),
+            (STATS_PNL_REALIZED_OFF as u64, layout::STATS_PNL_REALIZED_OFF as u64),
+            (STATS_PNL_UNREALIZED_OFF as u64, layout::STATS_PNL_UNREALIZED_OFF as u64),
+            (STATS_PNL_LEN as u64, layout::STATS_PNL_LEN as u64),
+            (STATS_SKIM_LEN as u64, layout::STATS_SKIM_LEN as u64),
+            (LOYALTY_TIERS_OFF as u64, layout::LOYALTY_TIERS_OFF as u64),
+            (MAX_LOYALTY_TIERS as u64, layout::MAX_LOYALTY_TIERS as u64),
+            (LOYALTY_TIER_VOLUME_OFF as u64, layout::LOYALTY_TIER_VOLUME_OFF as u64),
+            (LOYALTY_TIER_DISCOUNT_OFF as u64, layout::LOYALTY_TIER_DISCOUNT_OFF as u64),
+            (LOYALTY_TIER_LEN as u64, layout::LOYALTY_TIER_LEN as u64),
+            (SCHEDULE_LOYALTY_LEN as u64, layout::SCHEDULE_LOYALTY_LEN as u64),
+            (SCHEDULE_INVENTORY_HALFLIFE_OFF as u64, layout::SCHEDULE_INVENTORY_HALFLIFE_OFF as u64),
+            (SCHEDULE_DECAY_LEN as u64, layout::SCHEDULE_DECAY_LEN as u64),
+            (SCHEDULE_SOFT_INVENTORY_OFF as u64, layout::SCHEDULE_SOFT_INVENTORY_OFF as u64),
+            (SCHEDULE_BAND_K_OFF as u64, layout::SCHEDULE_BAND_K_OFF as u64),
+            (SCHEDULE_BAND_LEN as u64, layout::SCHEDULE_BAND_LEN as u64),
+            (SCHEDULE_MAX_LONG_OFF as u64, layout::SCHEDULE_MAX_LONG_OFF as u64),
+            (SCHEDULE_MAX_SHORT_OFF as u64, layout::SCHEDULE_MAX_SHORT_OFF as u64),
+            (SCHEDULE_CAPS_LEN as u64, layout::SCHEDULE_CAPS_LEN as u64),
+            (SCHEDULE_REDUCE_ONLY_BPS_OFF as u64, layout::SCHEDULE_REDUCE_ONLY_BPS_OFF as u64),
+            (SCHEDULE_REDUCE_ONLY_LEN as u64, layout::SCHEDULE_REDUCE_ONLY_LEN as u64),
+            (SCHEDULE_MAX_INVENTORY_BPS_OFF as u64, layout::SCHEDULE_MAX_INVENTORY_BPS_OFF as u64),
+            (SCHEDULE_DEPTH_CAP_LEN as u64, layout::SCHEDULE_DEPTH_CAP_LEN as u64),
+            (SCHEDULE_SIGNAL_COUNT_OFF as u64, layout::SCHEDULE_SIGNAL_COUNT_OFF as u64),
+            (SCHEDULE_SIGNALS_OFF as u64, layout::SCHEDULE_SIGNALS_OFF as u64),
+            (SIGNAL_ENTRY_KEY_OFF as u64, layout::SIGNAL_ENTRY_KEY_OFF as u64),
+            (SIGNAL_ENTRY_OWNER_OFF as u64, layout::SIGNAL_ENTRY_OWNER_OFF as u64),
+            (SIGNAL_ENTRY_LEN as u64, layout::SIGNAL_ENTRY_LEN as u64),
+            (SCHEDULE_SIGNALS_LEN as u64, layout::SCHEDULE_SIGNALS_LEN as u64),
+            (LOYALTY_TIER_ARG_LEN as u64, layout::LOYALTY_TIER_ARG_LEN as u64),
+            (TRADER_MAGIC, layout::TRADER_MAGIC),
+            (TRADER_VERSION as u64, layout::TRADER_VERSION as u64),
+            (TRADER_MAGIC_OFF as u64, layout::TRADER_MAGIC_OFF as u64),
+            (TRADER_VERSION_OFF as u64, layout::TRADER_VERSION_OFF as u64),
+            (TRADER_CTX_OFF as u64, layout::TRADER_CTX_OFF as u64),
+            (TRADER_KEY_OFF as u64, layout::TRADER_KEY_OFF as u64),
+            (TRADER_VOLUME_OFF as u64, layout::TRADER_VOLUME_OFF as u64),
+            (TRADER_FILLS_OFF as u64, layout::TRADER_FILLS_OFF as u64),
+            (TRADER_LAST_SLOT_OFF as u64, layout::TRADER_LAST_SLOT_OFF as u64),
+            (TRADER_LEN as u64, layout::TRADER_LEN as u64),
+            (SCHED_ARG_LEN as u64, layout::SCHED_ARG_LEN as u64),
+            (LADDER_MAGIC, layout::LADDER_MAGIC),
+            (LADDER_VERSION as u64, layout::LADDER_VERSION as u64),
+            (LADDER_MAGIC_OFF as u64, layout::LADDER_MAGIC_OFF as u64),
+            (LADDER_VERSION_OFF as u64, layout::LADDER_VERSION_OFF as u64),
+            (LADDER_MARKET_OFF as u64, layout::LADDER_MARKET_OFF as u64),
+            (LADDER_CTX_OFF as u64, layout::LADDER_CTX_OFF as u64),
+            (LADDER_SLOT_OFF as u64, layout::LADDER_SLOT_OFF as u64),
+            (LADDER_ORACLE_OFF as u64, layout::LADDER_ORACLE_OFF as u64),
+            (LADDER_STEP_OFF as u64, layout::LADDER_STEP_OFF as u64),
+            (LADDER_BID_COUNT_OFF as u64, layout::LADDER_BID_COUNT_OFF as u64),
+            (LADDER_ASK_COUNT_OFF as u64, layout::LADDER_ASK_COUNT_OFF as u64),
+            (LADDER_BID_REASON_OFF as u64, layout::LADDER_BID_REASON_OFF as u64),
+            (LADDER_ASK_REASON_OFF as u64, layout::LADDER_ASK_REASON_OFF as u64),
+            (LADDER_HEADER_LEN as u64, layout::LADDER_HEADER_LEN as u64),
+            (MAX_LADDER_LEVELS as u64, layout::MAX_LADDER_LEVELS as u64),
+            (LEVEL_SIZE_OFF as u64, layout::LEVEL_SIZE_OFF as u64),
+            (LEVEL_PRICE_OFF as u64, layout::LEVEL_PRICE_OFF as u64),
+            (LEVEL_SPREAD_OFF as u64, layout::LEVEL_SPREAD_OFF as u64),
+            (LEVEL_LEN as u64, layout::LEVEL_LEN as u64),
+            (LADDER_BIDS_OFF as u64, layout::LADDER_BIDS_OFF as u64),
+            (LADDER_ASKS_OFF as u64, layout::LADDER_ASKS_OFF as u64),
+            (LADDER_LEN as u64, layout::LADDER_LEN as u64),
+            (TIP_JAR_MAGIC, layout::TIP_JAR_MAGIC),
+            (TIP_JAR_VERSION as u64, layout::TIP_JAR_VERSION as u64),
+            (TIP_JAR_MAGIC_OFF as u64, layout::TIP_JAR_MAGIC_OFF as u64),
+            (TIP_JAR_VERSION_OFF as u64, layout::TIP_JAR_VERSION_OFF as u64),
+            (TIP_JAR_CTX_OFF as u64, layout::TIP_JAR_CTX_OFF as u64),
+            (TIP_JAR_PER_SLOT_OFF as u64, layout::TIP_JAR_PER_SLOT_OFF as u64),
+            (TIP_JAR_MAX_OFF as u64, layout::TIP_JAR_MAX_OFF as u64),
+            (TIP_JAR_PAID_OFF as u64, layout::TIP_JAR_PAID_OFF as u64),
+            (TIP_JAR_COUNT_OFF as u64, layout::TIP_JAR_COUNT_OFF as u64),
+            (TIP_JAR_LEN as u64, layout::TIP_JAR_LEN as u64),
+            (REFERRAL_MAGIC, layout::REFERRAL_MAGIC),
+            (REFERRAL_VERSION as u64, layout::REFERRAL_VERSION as u64),
+            (REFERRAL_MAGIC_OFF as u64, layout::REFERRAL_MAGIC_OFF as u64),
+            (REFERRAL_VERSION_OFF as u64, layout::REFERRAL_VERSION_OFF as u64),
+            (REFERRAL_CTX_OFF as u64, layout::REFERRAL_CTX_OFF as u64),
+            (REFERRAL_SHARE_OFF as u64, layout::REFERRAL_SHARE_OFF as u64),
+            (REFERRAL_COUNT_OFF as u64, layout::REFERRAL_COUNT_OFF as u64),
+            (REFERRAL_HEADER_LEN as u64, layout::REFERRAL_HEADER_LEN as u64),
+            (MAX_REFERRERS as u64, layout::MAX_REFERRERS as u64),
+            (REFERRER_KEY_OFF as u64, layout::REFERRER_KEY_OFF as u64),
+            (REFERRER_ACCRUED_OFF as u64, layout::REFERRER_ACCRUED_OFF as u64),
+            (REFERRER_FILLS_OFF as u64, layout::REFERRER_FILLS_OFF as u64),
+            (REFERRER_LEN as u64, layout::REFERRER_LEN as u64),
+            (REFERRAL_LEN as u64, layout::REFERRAL_LEN as u64),
+            (FEED_MAGIC, layout::FEED_MAGIC),
+            (FEED_VERSION as u64, layout::FEED_VERSION as u64),
+            (FEED_MAGIC_OFF as u64, layout::FEED_MAGIC_OFF as u64),
+            (FEED_VERSION_OFF as u64, layout::FEED_VERSION_OFF as u64),
+            (FEED_MAX_AGE_OFF as u64, layout::FEED_MAX_AGE_OFF as u64),
+            (FEED_CTX_OFF as u64, layout::FEED_CTX_OFF as u64),
+            (FEED_COUNT_OFF as u64, layout::FEED_COUNT_OFF as u64),
+            (FEED_WIDEN_OFF as u64, layout::FEED_WIDEN_OFF as u64),
+            (FEED_MAX_DIVERGENCE_OFF as u64, layout::FEED_MAX_DIVERGENCE_OFF as u64),
+            (FEED_MAX_AGE_SLOTS_OFF as u64, layout::FEED_MAX_AGE_SLOTS_OFF as u64),
+            (FEED_SLOT_WIDEN_OFF as u64, layout::FEED_SLOT_WIDEN_OFF as u64),
+            (FEED_FALLBACK_OFF as u64, layout::FEED_FALLBACK_OFF as u64),
+            (FEED_ENTRY_PENALTY_OFF as u64, layout::FEED_ENTRY_PENALTY_OFF as u64),
+            (FEED_HEADER_LEN as u64, layout::FEED_HEADER_LEN as u64),
+            (MAX_FEEDS as u64, layout::MAX_FEEDS as u64),
+            (FEED_ENTRY_ID_OFF as u64, layout::FEED_ENTRY_ID_OFF as u64),
+            (FEED_ENTRY_SOURCE_OFF as u64, layout::FEED_ENTRY_SOURCE_OFF as u64),
+            (FEED_ENTRY_PINNED_OFF as u64, layout::FEED_ENTRY_PINNED_OFF as u64),
+            (FEED_ENTRY_LEN as u64, layout::FEED_ENTRY_LEN as u64),
+            (FEED_LEN as u64, layout::FEED_LEN as u64),
+            (FEED_SOURCE_PYTH as u64, layout::FEED_SOURCE_PYTH as u64),
+            (FEED_SOURCE_SWITCHBOARD as u64, layout::FEED_SOURCE_SWITCHBOARD as u64),
+            (FEED_SOURCE_CHAINLINK as u64, layout::FEED_SOURCE_CHAINLINK as u64),
+            (SLAB_ADMIN_OFF as u64, layout::SLAB_ADMIN_OFF as u64),
+            (SLAB_HEADER_LEN as u64, layout::SLAB_HEADER_LEN as u64),
+            (SLAB_CONFIG_LEN as u64, layout::SLAB_CONFIG_LEN as u64),
+            (SLAB_ENGINE_OFF as u64, layout::SLAB_ENGINE_OFF as u64),
+            (SLAB_ENGINE_LEN as u64, layout::SLAB_ENGINE_LEN as u64),
+            (SLAB_MIN_LEN as u64, layout::SLAB_MIN_LEN as u64),
+            (ENGINE_INSURANCE_OFF as u64, layout::ENGINE_INSURANCE_OFF as u64),
+            (ENGINE_FUNDING_RATE_OFF as u64, layout::ENGINE_FUNDING_RATE_OFF as u64),
+            (ENGINE_LAST_CRANK_OFF as u64, layout::ENGINE_LAST_CRANK_OFF as u64),
+            (ENGINE_TOTAL_OI_OFF as u64, layout::ENGINE_TOTAL_OI_OFF as u64),
+            (ENGINE_LIFETIME_LIQS_OFF as u64, layout::ENGINE_LIFETIME_LIQS_OFF as u64),
+            (SLAB_ACCOUNTS_OFF as u64, layout::SLAB_ACCOUNTS_OFF as u64),
+            (SLAB_ACCOUNT_LEN as u64, layout::SLAB_ACCOUNT_LEN as u64),
+            (ACCT_KIND_OFF as u64, layout::ACCT_KIND_OFF as u64),
+            (ACCT_POSITION_OFF as u64, layout::ACCT_POSITION_OFF as u64),
+            (ACCT_MATCHER_PROGRAM_OFF as u64, layout::ACCT_MATCHER_PROGRAM_OFF as u64),
+            (ACCT_MATCHER_CONTEXT_OFF as u64, layout::ACCT_MATCHER_CONTEXT_OFF as u64),
+            (ACCT_KIND_LP as u64, layout::ACCT_KIND_LP as u64),
+            (RFQ_CTX_OFF as u64, layout::RFQ_CTX_OFF as u64),
+            (RFQ_PRICE_OFF as u64, layout::RFQ_PRICE_OFF as u64),
+            (RFQ_SIZE_OFF as u64, layout::RFQ_SIZE_OFF as u64),
+            (RFQ_EXPIRY_OFF as u64, layout::RFQ_EXPIRY_OFF as u64),
+            (RFQ_NONCE_OFF as u64, layout::RFQ_NONCE_OFF as u64),
+            (RFQ_MSG_LEN as u64, layout::RFQ_MSG_LEN as u64),
+            (ED25519_OFFSETS_OFF as u64, layout::ED25519_OFFSETS_OFF as u64),
+            (ED25519_SIG_OFF as u64, layout::ED25519_SIG_OFF as u64),
+            (ED25519_SIG_IX_OFF as u64, layout::ED25519_SIG_IX_OFF as u64),
+            (ED25519_KEY_OFF as u64, layout::ED25519_KEY_OFF as u64),
+            (ED25519_KEY_IX_OFF as u64, layout::ED25519_KEY_IX_OFF as u64),
+            (ED25519_MSG_OFF as u64, layout::ED25519_MSG_OFF as u64),
+            (ED25519_MSG_SIZE_OFF as u64, layout::ED25519_MSG_SIZE_OFF as u64),
+            (ED25519_MSG_IX_OFF as u64, layout::ED25519_MSG_IX_OFF as u64),
+            (ED25519_OFFSETS_LEN as u64, layout::ED25519_OFFSETS_LEN as u64),
+            (SETTLE_SET as u64, layout::SETTLE_SET as u64),
+            (SETTLE_ADJUST as u64, layout::SETTLE_ADJUST as u64),
+            (IMPACT_NONE as u64, layout::IMPACT_NONE as u64),
+            (IMPACT_LINEAR as u64, layout::IMPACT_LINEAR as u64),
+            (IMPACT_SQRT as u64, layout::IMPACT_SQRT as u64),
+            (CURVE_LINEAR as u64, layout::CURVE_LINEAR as u64),
+            (CURVE_SQRT as u64, layout::CURVE_SQRT as u64),
+            (CURVE_PIECEWISE as u64, layout::CURVE_PIECEWISE as u64),
+            (CURVE_POINTS as u64, layout::CURVE_POINTS as u64),
+            (SIGNAL_COVERAGE as u64, layout::SIGNAL_COVERAGE as u64),
+            (SIGNAL_AGE as u64, layout::SIGNAL_AGE as u64),
+            (SIGNAL_DEFICIT as u64, layout::SIGNAL_DEFICIT as u64),
+            (SIGNAL_LIQUIDATIONS as u64, layout::SIGNAL_LIQUIDATIONS as u64),
+            (SIGNALS as u64, layout::SIGNALS as u64),
+            (PARAMS_LEN as u64, layout::PARAMS_LEN as u64),
+            (PARAMS_MAX_LEN as u64, layout::PARAMS_MAX_LEN as u64),
+        ];
+        for (i, (ours, theirs)) in pairs.iter().enumerate() {
+            assert_eq!(ours, theirs, "layout constant #{}", i);
+        }
+        assert_eq!(layout::PARAMS_MAX_LEN, Params::default().pack().len());
+        assert_eq!(layout::CONTEXT_SEED, CONTEXT_SEED);
+    }
+
+    /// Run a client-built instruction, one TestAccount per meta.
+    fn run_client_ix(ix: &solana_program::instruction::Instruction, data: Vec<Vec<u8>>) -> (ProgramResult, Vec<TestAccount>) {
+        assert_eq!(ix.program_id, PROGRAM_ID);
+        let mut accounts: Vec<TestAccount> = ix
+            .accounts
+            .iter()
+            .zip(data)
+            .map(|(meta, data)| {
+                TestAccount::new(meta.pubkey, meta.is_signer, Rent::default().minimum_balance(data.len()), data)
+            })
+            .collect();
+        let result = run_accounts(&mut accounts, &ix.data);
+        (result, accounts)
+    }
+
+    #[test]
+    fn test_client_builders_drive_the_program() {
+        use provenance_client::instruction as ix;
+        use provenance_client::{MatcherParams, instruction::MatchCall};
+
+        set_slot(1_000);
+        let ctx_key = Pubkey::new_unique();
+        let params = MatcherParams {
+            base_fee_bps: 5,
+            min_spread_bps: 10,
+            max_spread_bps: 200,
+            imbalance_k_bps: 100,
+            liquidity_e6: 10_000_000_000_000,
+            max_fill: 1_000_000_000_000,
+            insurance_weight_bps: 50,
+            ..Default::default()
+        };
+        let init = ix::init(&PROGRAM_ID, &LP, &ctx_key, None, None, &params).unwrap();
+        let (result, accounts) = run_client_ix(&init, vec![vec![], vec![0u8; CTX_EXT_LEN]]);
+        result.unwrap();
+        let ctx = accounts[1].data.clone();
+        assert_eq!(Params::load(&ctx).pack(), params.pack());
+
+        let call = MatchCall { req_id: 9, oracle_price_e6: 100_000_000, trade_size: 1_000, ..Default::default() };
+        let fill = ix::match_trade(&PROGRAM_ID, &LP, &ctx_key, None, None, &call).unwrap();
+        let (result, accounts) = run_client_ix(&fill, vec![vec![], ctx]);
+        result.unwrap();
+        let ctx = accounts[1].data.clone();
+        assert_eq!(read_u64(&ctx, RET_REQ_ID_OFF), 9);
+        assert_eq!(read_i128(&ctx, CTX_BASE + CTX_INVENTORY_OFF), 1_000);
+
+        let preview = ix::quote_preview(&PROGRAM_ID, &ctx_key, None, 100_000_000, -1_000);
+        let (result, _) = run_client_ix(&preview, vec![ctx.clone()]);
+        result.unwrap();
+        assert_eq!(RETURN_DATA.with(|r| r.borrow().len()), QUOTE_LEN);
+
+        let pause = ix::set_paused(&PROGRAM_ID, &LP, &ctx_key, true);
+        let (result, accounts) = run_client_ix(&pause, vec![vec![], ctx]);
+        result.unwrap();
+        assert_eq!(accounts[1].data[CTX_BASE + CTX_FLAGS_OFF], CTX_FLAG_PAUSED);
+    }
+
+    #[test]
+    fn test_client_context_decoder_roundtrips() {
+        use provenance_client::instruction as ix;
+        use provenance_client::{MatcherContext, MatcherParams, instruction::MatchCall};
+
+        set_slot(1_000);
+        let ctx_key = Pubkey::new_unique();
+        let params = MatcherParams {
+            base_fee_bps: 5,
+            min_spread_bps: 10,
+            max_spread_bps: 200,
+            imbalance_k_bps: 100,
+            liquidity_e6: 10_000_000_000_000,
+            max_fill: 1_000_000_000_000,
+            coverage_curve: CURVE_PIECEWISE,
+            curve_points: [1_000, 4_000, 8_000, 10_000],
+            oracle_jump_bps: 500,
+            oracle_jump_window: 10,
+            flow_k_bps: 30,
+            signal_weights: [6_000, 0, 4_000, 0],
+            deficit_halflife: 500,
+            ..Default::default()
+        };
+        let init = ix::init_with_kind(&PROGRAM_ID, &LP, &ctx_key, None, None, KIND_MULTI, &params).unwrap();
+        let (result, accounts) = run_client_ix(&init, vec![vec![], vec![0u8; CTX_EXT_LEN]]);
+        result.unwrap();
+        let mut ctx = accounts[1].data.clone();
+        for (i, size) in [1_000i128, -400, 2_500].into_iter().enumerate() {
+            let call = MatchCall { req_id: i as u64, oracle_price_e6: 100_000_000, trade_size: size, ..Default::default() };
+            let fill = ix::match_trade(&PROGRAM_ID, &LP, &ctx_key, None, None, &call).unwrap();
+            let (result, accounts) = run_client_ix(&fill, vec![vec![], ctx]);
+            result.unwrap();
+            ctx = accounts[1].data.clone();
+        }
+        let pending = MatcherParams { max_spread_bps: 300, twap_window_slots: 50, signal_weights: [BPS as u16, 0, 0, 0], ..params };
+        let propose = ix::propose_params(&PROGRAM_ID, &LP, &ctx_key, &pending);
+        let (result, accounts) = run_client_ix(&propose, vec![vec![], ctx]);
+        result.unwrap();
+        let ctx = accounts[1].data.clone();
+
+        let decoded = MatcherContext::from_bytes(&ctx).unwrap();
+        assert_eq!(decoded.to_bytes(), ctx);
+        assert_eq!(decoded.version, VERSION);
+        assert_eq!(decoded.params.pack(), Params::load(&ctx).pack());
+        assert_eq!(decoded.inventory, read_i128(&ctx, CTX_BASE + CTX_INVENTORY_OFF));
+        assert_eq!(decoded.inventory, 3_100);
+        assert_eq!(decoded.last_return.req_id, 2);
+        assert_eq!(decoded.last_exec_price_e6, read_u64(&ctx, CTX_BASE + CTX_LAST_EXEC_OFF));
+        assert_eq!(decoded.authority(), LP);
+        let ext = decoded.extension.unwrap();
+        assert_eq!(ext.fill_seq, 3);
+        assert_eq!((ext.gross_long, ext.gross_short), (3_500, 400));
+        assert_eq!(ext.cum_notional, read_u128(&ctx, EXT_BASE + EXT_CUM_NOTIONAL_OFF));
+        assert_eq!(ext.pending_activation_slot, read_u64(&ctx, EXT_BASE + EXT_PENDING_SLOT_OFF));
+        assert_eq!(ext.pending_params.pack(), read_pending_params(&ctx));
+        assert_eq!(ext.pending_params, pending);
+
+        // Cancelling clears the proposal; the decoder sees the zeroed block
+        let cancel = ix::cancel_params(&PROGRAM_ID, &LP, &ctx_key);
+        let (result, accounts) = run_client_ix(&cancel, vec![vec![], ctx]);
+        result.unwrap();
+        let decoded = MatcherContext::from_bytes(&accounts[1].data).unwrap();
+        assert_eq!(decoded.extension.unwrap().pending_params, MatcherParams::default());
+        assert_eq!(decoded.to_bytes(), accounts[1].data);
+
+        // v5 accounts decode without an extension and round-trip at CTX_LEN
+        let mut v5 = vec![0u8; CTX_LEN];
+        run_init(&mut v5, &init_data(0)).unwrap();
+        run_lp_ix(&mut v5, &LP, true, &match_data(100_000_000, -700)).unwrap();
+        let decoded = MatcherContext::from_bytes(&v5).unwrap();
+        assert_eq!(decoded.version, VERSION_V5);
+        assert!(decoded.extension.is_none());
+        assert_eq!(decoded.params.pack(), Params::load(&v5).pack());
+        assert_eq!(decoded.to_bytes(), v5);
+    }
+
+    #[test]
+    fn test_error_codes_stable_and_decodable() {
+        // Codes are ABI: contiguous from 1 and never reshuffled
+        for (i, e) in MatcherError::ALL.iter().enumerate() {
+            assert_eq!(*e as u32, i as u32 + 1);
+            assert_eq!(MatcherError::from_code(*e as u32), Some(*e));
+            assert_eq!(ProgramError::from(*e), ProgramError::Custom(*e as u32));
+        }
+        assert_eq!(MatcherError::Paused as u32, 1);
+        assert_eq!(MatcherError::ReduceOnly as u32, 2);
+        assert_eq!(MatcherError::InventoryOverflow as u32, 3);
+        assert_eq!(MatcherError::from_code(0), None);
+        assert_eq!(MatcherError::from_code(MatcherError::ALL.len() as u32 + 1), None);
+    }
+
+    #[test]
+    fn test_inventory_overflow_rejected() {
+        set_slot(1);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        write_u128(&mut ctx, CTX_BASE + CTX_MAX_FILL_OFF, 0); // unlimited
+
+        for (inventory, trade) in [(i128::MAX, 1), (i128::MIN, -1), (i128::MAX - 5, 6), (i128::MIN + 5, -6)] {
+            write_i128(&mut ctx, CTX_BASE + CTX_INVENTORY_OFF, inventory);
+            let before = ctx.clone();
+            assert_eq!(
+                run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, trade)),
+                Err(MatcherError::InventoryOverflow.into())
+            );
+            assert_eq!(ctx, before);
+        }
+
+        // Right up to the boundary is fine, and trades back away from it too
+        write_i128(&mut ctx, CTX_BASE + CTX_INVENTORY_OFF, i128::MAX - 5);
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 5)).unwrap();
+        assert_eq!(read_i128(&ctx, CTX_BASE + CTX_INVENTORY_OFF), i128::MAX);
+        write_i128(&mut ctx, CTX_BASE + CTX_INVENTORY_OFF, i128::MIN);
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 1)).unwrap();
+        assert_eq!(read_i128(&ctx, CTX_BASE + CTX_INVENTORY_OFF), i128::MIN + 1);
+    }
+
+    #[test]
+    fn test_reduce_only_flag() {
+        set_slot(1);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        write_i128(&mut ctx, CTX_BASE + CTX_INVENTORY_OFF, 1_000);
+        assert_eq!(run_lp_ix(&mut ctx, &LP, false, &[0x0B, 1]), Err(ProgramError::MissingRequiredSignature));
+        run_lp_ix(&mut ctx, &LP, true, &[0x0B, 1]).unwrap();
+        assert_eq!(ctx[CTX_BASE + CTX_FLAGS_OFF], CTX_FLAG_REDUCE_ONLY);
+
+        // Growing |inventory| is refused, shrinking it is fine
+        assert_eq!(
+            run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 1)),
+            Err(MatcherError::ReduceOnly.into())
+        );
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, -400)).unwrap();
+        assert_eq!(read_i128(&ctx, CTX_BASE + CTX_INVENTORY_OFF), 600);
+        // Flipping through zero still shrinks |inventory|
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, -1_100)).unwrap();
+        assert_eq!(read_i128(&ctx, CTX_BASE + CTX_INVENTORY_OFF), -500);
+        assert_eq!(
+            run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, -1_000)),
+            Err(MatcherError::ReduceOnly.into())
+        );
+
+        run_lp_ix(&mut ctx, &LP, true, &[0x0B, 0]).unwrap();
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, -1_000)).unwrap();
+    }
+
+    #[test]
+    fn test_reduce_only_automatic_near_cap() {
+        set_slot(1);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        write_u128(&mut ctx, CTX_BASE + CTX_MAX_INVENTORY_OFF, 10_000);
+
+        write_i128(&mut ctx, CTX_BASE + CTX_INVENTORY_OFF, -9_499);
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, -1)).unwrap();
+        // Now at 95% of the cap: only reducing flow, the rest soft-rejected
+        // without soft-reject mode
+        let state = ctx[CTX_BASE..].to_vec();
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, -1)).unwrap();
+        let ret = RETURN_DATA.with(|r| r.borrow().clone());
+        assert_eq!(read_i128(&ret, RET_EXEC_SIZE_OFF), 0);
+        assert_eq!(read_u64(&ret, RET_REJECT_REASON_OFF), MatcherError::ReduceOnly as u64);
+        assert_eq!(ctx[CTX_BASE..], state[..]);
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 100)).unwrap();
+        assert_eq!(read_i128(&ctx, CTX_BASE + CTX_INVENTORY_OFF), -9_400);
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, -1)).unwrap();
+    }
+
+    fn settle_data(mode: u8, value: i128) -> Vec<u8> {
+        let mut d = vec![0x0C, mode];
+        d.extend_from_slice(&value.to_le_bytes());
+        d
+    }
+
+    #[test]
+    fn test_settle_inventory() {
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        write_i128(&mut ctx, CTX_BASE + CTX_INVENTORY_OFF, 5_000);
+
+        run_lp_ix(&mut ctx, &LP, true, &settle_data(SETTLE_ADJUST, -3_000)).unwrap();
+        assert_eq!(read_i128(&ctx, CTX_BASE + CTX_INVENTORY_OFF), 2_000);
+        run_lp_ix(&mut ctx, &LP, true, &settle_data(SETTLE_SET, -7)).unwrap();
+        assert_eq!(read_i128(&ctx, CTX_BASE + CTX_INVENTORY_OFF), -7);
+
+        let before = ctx.clone();
+        assert_eq!(
+            run_lp_ix(&mut ctx, &LP, false, &settle_data(SETTLE_SET, 0)),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            run_lp_ix(&mut ctx, &LP, true, &settle_data(SETTLE_ADJUST, i128::MIN)),
+            Err(ProgramError::ArithmeticOverflow)
+        );
+        assert_eq!(run_lp_ix(&mut ctx, &LP, true, &settle_data(2, 0)), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(run_lp_ix(&mut ctx, &LP, true, &[0x0C, 0]), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(ctx, before);
+    }
+
+    fn propose_authority_data(new_authority: &Pubkey) -> Vec<u8> {
+        let mut d = vec![0x0D];
+        d.extend_from_slice(new_authority.as_ref());
+        d
+    }
+
+    #[test]
+    fn test_authority_rotation() {
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        assert_eq!(current_authority(&ctx), LP);
+
+        let ops = Pubkey::new_unique();
+        run_lp_ix(&mut ctx, &LP, true, &propose_authority_data(&ops)).unwrap();
+        // Proposal alone changes nothing
+        assert_eq!(current_authority(&ctx), LP);
+        assert_eq!(run_lp_ix(&mut ctx, &Pubkey::new_unique(), true, &[0x0E]), Err(MatcherError::NotPendingAuthority.into()));
+        assert_eq!(run_lp_ix(&mut ctx, &ops, false, &[0x0E]), Err(ProgramError::MissingRequiredSignature));
+
+        run_lp_ix(&mut ctx, &ops, true, &[0x0E]).unwrap();
+        assert_eq!(current_authority(&ctx), ops);
+        assert_eq!(read_pubkey(&ctx, EXT_BASE + EXT_PENDING_AUTHORITY_OFF), Pubkey::default());
+
+        // The LP PDA no longer administers; the new key does. Matches still need the LP PDA.
+        assert_eq!(run_lp_ix(&mut ctx, &LP, true, &[0x06, 1]), Err(MatcherError::AuthorityMismatch.into()));
+        run_lp_ix(&mut ctx, &ops, true, &[0x06, 1]).unwrap();
+        run_lp_ix(&mut ctx, &ops, true, &[0x06, 0]).unwrap();
+        set_slot(1);
+        run_lp_ix(&mut ctx, &LP, true, &match_data(100_000_000, 1_000)).unwrap();
+        assert_eq!(
+            run_lp_ix(&mut ctx, &ops, true, &match_data(100_000_000, 1_000)),
+            Err(MatcherError::PdaMismatch.into())
+        );
+
+        // Proposing the zero key cancels a pending rotation
+        let next = Pubkey::new_unique();
+        run_lp_ix(&mut ctx, &ops, true, &propose_authority_data(&next)).unwrap();
+        run_lp_ix(&mut ctx, &ops, true, &propose_authority_data(&Pubkey::default())).unwrap();
+        assert_eq!(run_lp_ix(&mut ctx, &next, true, &[0x0E]), Err(MatcherError::NotPendingAuthority.into()));
+    }
+
+    #[test]
+    fn test_init_sets_authority() {
+        let ops = Pubkey::new_unique();
+        let mut accounts = [
+            TestAccount::new(LP, false, 0, vec![]),
+            ctx_account(vec![0u8; CTX_EXT_LEN]),
+            TestAccount::new(Pubkey::new_unique(), false, 0, vec![]),
+            TestAccount::new(ops, false, 0, vec![]),
+        ];
+        // Signed by the authority it records, or by the LP PDA, or not at all
+        assert_eq!(run_accounts(&mut accounts, &init_data(0)), Err(ProgramError::MissingRequiredSignature));
+        accounts[3].signer = true;
+        run_accounts(&mut accounts, &init_data(0)).unwrap();
+        assert_eq!(current_authority(&accounts[1].data), ops);
+
+        // v5 falls back to the LP PDA and can't rotate
+        let mut v5 = vec![0u8; CTX_LEN];
+        run_init(&mut v5, &init_data(0)).unwrap();
+        assert_eq!(current_authority(&v5), LP);
+        assert_eq!(
+            run_lp_ix(&mut v5, &LP, true, &propose_authority_data(&ops)),
+            Err(MatcherError::ExtensionRequired.into())
+        );
+    }
+
+    #[test]
+    fn test_spoofed_context_rejected_everywhere() {
+        set_slot(1);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let foreign = Pubkey::new_unique();
+
+        // (instruction data, index of the context account)
+        let cases: Vec<(Vec<u8>, usize)> = vec![
+            (match_data(100_000_000, 1_000), 1),
+            (init_data(0), 1),
+            (vec![0x03], 0),
+            (update_params_data(&params_data(0)), 1),
+            (vec![0x05], 1),
+            (vec![0x06, 1], 1),
+            (vec![0x07], 0),
+            (preview_data(100_000_000, 1_000), 0),
+            (propose_data(&params_data(0)), 1),
+            (vec![0x0A], 0),
+            (vec![0x0B, 1], 1),
+            (settle_data(SETTLE_SET, 0), 1),
+            (propose_authority_data(&Pubkey::new_unique()), 1),
+            (vec![0x0E], 1),
+        ];
+        for (data, ctx_idx) in cases {
+            let mut accounts: Vec<TestAccount> = (0..3)
+                .map(|_| TestAccount::new(Pubkey::new_unique(), true, 0, vec![0u8; SLAB_MIN_LEN]))
+                .collect();
+            accounts[ctx_idx] = TestAccount { owner: foreign, ..TestAccount::new(Pubkey::new_unique(), true, 0, ctx.clone()) };
+            if ctx_idx == 1 {
+                accounts[0] = TestAccount::new(LP, true, 0, vec![]);
+            }
+            assert_eq!(
+                run_accounts(&mut accounts, &data),
+                Err(ProgramError::IncorrectProgramId),
+                "tag {:#04x}",
+                data[0]
+            );
+            assert_eq!(accounts[ctx_idx].data, ctx, "tag {:#04x}", data[0]);
+        }
+    }
+
+    #[test]
+    fn test_set_paused_guarded() {
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        assert_eq!(run_lp_ix(&mut ctx, &LP, false, &[0x06, 1]), Err(ProgramError::MissingRequiredSignature));
+        assert_eq!(
+            run_lp_ix(&mut ctx, &Pubkey::new_unique(), true, &[0x06, 1]),
+            Err(MatcherError::AuthorityMismatch.into())
+        );
+        assert_eq!(run_lp_ix(&mut ctx, &LP, true, &[0x06, 2]), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(run_lp_ix(&mut ctx, &LP, true, &[0x06]), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(ctx[CTX_BASE + CTX_FLAGS_OFF], 0);
+    }
+
+    #[test]
+    fn test_migrate_v5_to_v6() {
+        set_slot(1);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        write_i128(&mut ctx, CTX_BASE + CTX_INVENTORY_OFF, 1_234);
+        write_u128(&mut ctx, CTX_BASE + CTX_INSURANCE_OFF, 99);
+        let params = Params::load(&ctx);
+
+        let rent = Rent::default().minimum_balance(CTX_EXT_LEN);
+        let mut accounts = [
+            TestAccount::new(Pubkey::new_unique(), false, rent, ctx.clone()),
+            TestAccount::new(Pubkey::new_unique(), true, 0, vec![]),
+            TestAccount::new(solana_program::system_program::id(), false, 0, vec![]),
+        ];
+        run_accounts(&mut accounts, &[0x07]).unwrap();
+
+        let migrated = &accounts[0].data;
+        assert_eq!(migrated.len(), CTX_EXT_LEN);
+        assert_eq!(read_u32(migrated, CTX_BASE + CTX_VERSION_OFF), VERSION);
+        assert!(is_extended(migrated));
+        // Every v5 byte except the version carries over
+        assert_eq!(migrated[..CTX_BASE + CTX_VERSION_OFF], ctx[..CTX_BASE + CTX_VERSION_OFF]);
+        assert_eq!(migrated[CTX_BASE + CTX_KIND_OFF..CTX_LEN], ctx[CTX_BASE + CTX_KIND_OFF..]);
+        assert!(migrated[EXT_BASE..].iter().all(|b| *b == 0));
+        assert_eq!(Params::load(migrated), params);
+        assert_eq!(read_i128(migrated, CTX_BASE + CTX_INVENTORY_OFF), 1_234);
+
+        // A second migration has nothing to do
+        assert_eq!(run_accounts(&mut accounts, &[0x07]), Err(MatcherError::NothingToMigrate.into()));
+    }
+
+    #[test]
+    fn test_migrate_unfunded_needs_payer_signature() {
+        set_slot(1);
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let mut accounts = [
+            TestAccount::new(Pubkey::new_unique(), false, 0, ctx.clone()),
+            TestAccount::new(Pubkey::new_unique(), false, 0, vec![]),
+            TestAccount::new(solana_program::system_program::id(), false, 0, vec![]),
+        ];
+        assert_eq!(run_accounts(&mut accounts, &[0x07]), Err(ProgramError::MissingRequiredSignature));
+        assert_eq!(accounts[0].data, ctx);
+    }
+
+    #[test]
+    fn test_migrate_uninitialized() {
+        set_slot(1);
+        let mut accounts = [
+            ctx_account(vec![0u8; CTX_LEN]),
+            TestAccount::new(Pubkey::new_unique(), true, 0, vec![]),
+            TestAccount::new(solana_program::system_program::id(), false, 0, vec![]),
+        ];
+        assert_eq!(run_accounts(&mut accounts, &[0x07]), Err(MatcherError::NotInitialized.into()));
+    }
+
+    #[test]
+    fn test_migrated_context_reads_as_before() {
+        use provenance_client::MatcherContext as Decoded;
+
+        set_slot(1_000);
+        let slab = Pubkey::new_unique();
+        let mut ctx = vec![0u8; CTX_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        write_u128(&mut ctx, CTX_BASE + CTX_INSURANCE_OFF, 500);
+        write_u128(&mut ctx, CTX_BASE + CTX_TOTAL_OI_OFF, 1_000);
+        write_u64(&mut ctx, CTX_BASE + CTX_SNAPSHOT_SLOT_OFF, 1_000);
+        write_i128(&mut ctx, CTX_BASE + CTX_INVENTORY_OFF, 2_000_000_000_000);
+        let quote = run_preview(&ctx, 100_000_000, 1_000).unwrap();
+        // Binding waits for the extension
+        let mut v5 = ctx.clone();
+        assert_eq!(run_bind(&mut v5, slab), Err(MatcherError::ExtensionRequired.into()));
+
+        let rent = Rent::default().minimum_balance(CTX_EXT_LEN);
+        let mut accounts = [
+            TestAccount::new(Pubkey::new_unique(), false, rent, ctx.clone()),
+            TestAccount::new(Pubkey::new_unique(), true, 0, vec![]),
+            TestAccount::new(solana_program::system_program::id(), false, 0, vec![]),
+        ];
+        run_accounts(&mut accounts, &[0x07]).unwrap();
+        let mut migrated = accounts[0].data.clone();
+
+        // Decoded, every v5 field reads the same; the extension comes up
+        // empty and there is no creation record
+        let (old, new) = (Decoded::from_bytes(&ctx).unwrap(), Decoded::from_bytes(&migrated).unwrap());
+        assert_eq!((old.version, new.version), (VERSION_V5, VERSION));
+        assert_eq!(new.extension, Some(Default::default()));
+        assert_eq!(new.creation, None);
+        assert_eq!(Decoded { version: VERSION_V5, extension: None, ..new }, old);
+
+        // It quotes exactly as before, the LP PDA still administers it, and it
+        // stays at its keypair address, unbound until BindSlab: until then it
+        // cranks only from the percolator program's slabs
+        assert_eq!(run_preview(&migrated, 100_000_000, 1_000).unwrap(), quote);
+        assert_eq!(current_authority(&migrated), LP);
+        assert!(!is_derived(&migrated));
+        assert_eq!(book_slab(&migrated, 0), Pubkey::default());
+        let fixture = SlabFixture { insurance: 1_000_000, total_oi: 1_000_000, ..Default::default() }.to_bytes();
+        assert_eq!(run_update(&mut migrated.clone(), slab, &fixture, 1_000), Err(MatcherError::SlabOwnerMismatch.into()));
+        run_bind(&mut migrated, slab).unwrap();
+        run_update(&mut migrated, slab, &fixture, 1_000).unwrap();
+        assert_eq!(read_i128(&migrated, CTX_BASE + CTX_INVENTORY_OFF), 2_000_000_000_000);
+    }
+
+    const PERCOLATOR: Pubkey = Pubkey::new_from_array([5u8; 32]);
+
+    fn slab_account(key: Pubkey, data: Vec<u8>) -> TestAccount {
+        TestAccount { owner: PERCOLATOR, ..TestAccount::new(key, false, 0, data) }
+    }
+
+    /// Run UpdateCredibility against `slab_key` with `slab_data`, clock at `slot`.
+    fn run_update(ctx: &mut Vec<u8>, slab_key: Pubkey, slab_data: &[u8], slot: u64) -> ProgramResult {
+        set_slot(slot);
+        let mut accounts = [
+            TestAccount::new(Pubkey::new_unique(), false, 0, ctx.clone()),
+            slab_account(slab_key, slab_data.to_vec()),
+            TestAccount::new(solana_program::sysvar::clock::id(), false, 0, slot.to_le_bytes().to_vec()),
+        ];
+        let result = run_accounts(&mut accounts, &[0x03]);
+        *ctx = accounts[0].data.clone();
+        result
+    }
+
+    fn init_bound(slab: Pubkey) -> Vec<u8> {
+        let mut accounts = [
+            TestAccount::new(LP, true, 0, vec![]),
+            ctx_account(vec![0u8; CTX_EXT_LEN]),
+            slab_account(slab, vec![]),
+        ];
+        run_accounts(&mut accounts, &init_data(0)).unwrap();
+        accounts[1].data.clone()
+    }
+
+    /// Run BindSlab on `ctx` with a PERCOLATOR-owned `slab`.
+    fn run_bind(ctx: &mut Vec<u8>, slab: Pubkey) -> ProgramResult {
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx.clone()), slab_account(slab, vec![])];
+        let result = run_accounts(&mut accounts, &[0x39]);
+        *ctx = accounts[1].data.clone();
+        result
+    }
+
+    #[test]
+    fn test_init_binds_slab() {
+        let slab = Pubkey::new_unique();
+        let ctx = init_bound(slab);
+        assert_eq!(read_pubkey(&ctx, EXT_BASE + EXT_SLAB_OFF), slab);
+        assert_eq!(read_pubkey(&ctx, EXT_BASE + EXT_PERCOLATOR_OFF), PERCOLATOR);
+
+        // Unbound when no slab is passed
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        assert!(ctx[EXT_BASE + EXT_SLAB_OFF..EXT_BASE + EXT_SLAB_OFF + 32].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_create_context_pins_the_lp_and_slab() {
+        use provenance_client::instruction as ix;
+        use provenance_client::MatcherParams;
+
+        set_slot(1_000);
+        let (slab, payer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (address, bump) = ix::context_address(&PROGRAM_ID, &LP, &slab);
+        // The system program is a no-op here, so the account comes in allocated
+        let len = CTX_EXT_LEN + CREATION_LEN;
+        let accounts = || vec![vec![], vec![0u8; len], vec![], vec![], vec![]];
+        let create = ix::create_context(&PROGRAM_ID, &LP, &slab, &payer, len as u32);
+        let (result, created) = run_client_ix(&create, accounts());
+        result.unwrap();
+        assert_eq!(MatcherContext::view(&created[1].data).bump, bump);
+        assert!(is_derived(&created[1].data));
+
+        // Only at the derived address, for a v6 account with room for the
+        // record that marks it, with the payer signing
+        let mut elsewhere = create.clone();
+        elsewhere.accounts[1].pubkey = Pubkey::new_unique();
+        assert_eq!(run_client_ix(&elsewhere, accounts()).0, Err(MatcherError::ContextAddressMismatch.into()));
+        let v5 = ix::create_context(&PROGRAM_ID, &LP, &slab, &payer, CTX_LEN as u32);
+        assert_eq!(run_client_ix(&v5, accounts()).0, Err(MatcherError::ExtensionRequired.into()));
+        let no_record = ix::create_context(&PROGRAM_ID, &LP, &slab, &payer, CTX_EXT_LEN as u32);
+        assert_eq!(run_client_ix(&no_record, accounts()).0, Err(MatcherError::ExtensionRequired.into()));
+        let mut unsigned = create.clone();
+        unsigned.accounts[3].is_signer = false;
+        assert_eq!(run_client_ix(&unsigned, accounts()).0, Err(ProgramError::MissingRequiredSignature));
+
+        // Init takes only the pair it was derived from
+        let params = MatcherParams { min_spread_bps: 10, max_spread_bps: 200, liquidity_e6: 1_000_000_000, max_fill: 1_000_000, ..Default::default() };
+        let init = |lp: &Pubkey, slab: Option<&Pubkey>| {
+            let init = ix::init(&PROGRAM_ID, lp, &address, slab, None, &params).unwrap();
+            let data = [vec![], created[1].data.clone(), vec![]];
+            run_client_ix(&init, data[..init.accounts.len()].to_vec())
+        };
+        assert_eq!(init(&LP, Some(&Pubkey::new_unique())).0, Err(MatcherError::ContextAddressMismatch.into()));
+        assert_eq!(init(&Pubkey::new_unique(), Some(&slab)).0, Err(MatcherError::ContextAddressMismatch.into()));
+        assert_eq!(init(&LP, None).0, Err(MatcherError::ContextAddressMismatch.into()));
+        let (result, accounts) = init(&LP, Some(&slab));
+        result.unwrap();
+        let ctx = accounts[1].data.clone();
+
+        // And every instruction after checks the context is still where the
+        // pair derives
+        let pause = ix::set_paused(&PROGRAM_ID, &LP, &address, true);
+        run_client_ix(&pause, vec![vec![], ctx.clone()]).0.unwrap();
+        let moved = ix::set_paused(&PROGRAM_ID, &LP, &Pubkey::new_unique(), true);
+        assert_eq!(run_client_ix(&moved, vec![vec![], ctx.clone()]).0, Err(MatcherError::ContextAddressMismatch.into()));
+        let mut other_bump = ctx.clone();
+        other_bump[CTX_BASE + CTX_BUMP_OFF] = bump.wrapping_sub(1);
+        assert_eq!(run_client_ix(&pause, vec![vec![], other_bump]).0, Err(MatcherError::ContextAddressMismatch.into()));
+        // The flag, not the bump, marks it: bump 0 is a valid bump, so a
+        // derived context holding it is still checked
+        let mut zero_bump = ctx;
+        zero_bump[CTX_BASE + CTX_BUMP_OFF] = 0;
+        assert_eq!(run_client_ix(&pause, vec![vec![], zero_bump]).0, Err(MatcherError::ContextAddressMismatch.into()));
+    }
+
+    #[test]
+    fn test_third_party_cannot_take_an_lps_pair() {
+        use provenance_client::instruction as ix;
+        use provenance_client::MatcherParams;
+
+        set_slot(1_000);
+        let (slab, mallory) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (address, _) = ix::context_address(&PROGRAM_ID, &LP, &slab);
+        let len = CTX_EXT_LEN + CREATION_LEN;
+        // Anyone may pay for the LP's address; that only allocates it
+        let create = ix::create_context(&PROGRAM_ID, &LP, &slab, &mallory, len as u32);
+        let (result, created) = run_client_ix(&create, vec![vec![], vec![0u8; len], vec![], vec![], vec![]]);
+        result.unwrap();
+
+        // Initialising it needs the LP PDA itself: neither an authority the
+        // caller signs for nor no signature at all will do
+        let params = MatcherParams { min_spread_bps: 10, max_spread_bps: 200, liquidity_e6: 1_000_000_000, max_fill: 1_000_000, ..Default::default() };
+        let init = |authority: Option<&Pubkey>, lp_signs: bool| {
+            let mut init = ix::init(&PROGRAM_ID, &LP, &address, Some(&slab), authority, &params).unwrap();
+            init.accounts[0].is_signer = lp_signs;
+            let data = [vec![], created[1].data.clone(), vec![], vec![]];
+            run_client_ix(&init, data[..init.accounts.len()].to_vec())
+        };
+        for (authority, lp_signs) in [(Some(&mallory), false), (None, false)] {
+            let (result, accounts) = init(authority, lp_signs);
+            assert_eq!(result, Err(ProgramError::MissingRequiredSignature));
+            assert_eq!(accounts[1].data, created[1].data);
         }
+
+        // The LP's own Init still goes through on the account the caller paid for
+        let (result, accounts) = init(None, true);
+        result.unwrap();
+        assert_eq!(current_authority(&accounts[1].data), LP);
     }
 
     #[test]
-    fn test_tier_critical_zero_insurance() {
-        let (tier, fill) = compute_tier(0);
-        assert_eq!(tier, "CRITICAL");
-        assert_eq!(fill, 25);
+    fn test_keypair_contexts_stay_legacy_at_any_size() {
+        // Only CreateContext's flag makes a context derived: a keypair
+        // account with room for the record still comes up at its own key
+        for len in [CTX_EXT_LEN, CTX_EXT_LEN + CREATION_LEN, CTX_SHARED_LEN + CREATION_LEN] {
+            let mut ctx = vec![0u8; len];
+            run_init(&mut ctx, &init_data(0)).unwrap();
+            assert!(!is_derived(&ctx));
+            assert_eq!(run_lp_ix(&mut ctx, &LP, true, &[0x06, 1]), Ok(()));
+        }
     }
 
     #[test]
-    fn test_tier_critical_5pct() {
-        let (tier, fill) = compute_tier(500); // 5%
-        assert_eq!(tier, "CRITICAL");
-        assert_eq!(fill, 25);
+    fn test_init_records_the_creation_slot_and_creator() {
+        use provenance_client::instruction as ix;
+        use provenance_client::{Creation, MatcherContext as Decoded, MatcherParams};
+
+        set_slot(4_321);
+        let (slab, payer, authority) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (ctx, _) = ix::context_address(&PROGRAM_ID, &LP, &slab);
+        let params = MatcherParams { min_spread_bps: 10, max_spread_bps: 200, liquidity_e6: 1_000_000_000, max_fill: 1_000_000, ..Default::default() };
+        let init = |len: usize, authority: Option<&Pubkey>| {
+            let create = ix::create_context(&PROGRAM_ID, &LP, &slab, &payer, len as u32);
+            let (result, created) = run_client_ix(&create, vec![vec![], vec![0u8; len], vec![], vec![], vec![]]);
+            result.unwrap();
+            let mut init = ix::init(&PROGRAM_ID, &LP, &ctx, Some(&slab), authority, &params).unwrap();
+            init.accounts[0].is_signer = true;
+            let data = [vec![], created[1].data.clone(), vec![], vec![]];
+            let (result, accounts) = run_client_ix(&init, data[..init.accounts.len()].to_vec());
+            result.unwrap();
+            Decoded::from_bytes(&accounts[1].data).unwrap().creation
+        };
+
+        // Right after the layout, single-market or shared. A derived context
+        // takes only the LP PDA's signature, so it is the creator even when
+        // Init names another authority
+        let record = |creator, derived| Some(Creation { slot: 4_321, creator, derived });
+        assert_eq!(init(CTX_EXT_LEN + CREATION_LEN, Some(&authority)), record(LP, true));
+        assert_eq!(init(CTX_SHARED_LEN + CREATION_LEN, Some(&authority)), record(LP, true));
+        assert_eq!(init(CTX_EXT_LEN + CREATION_LEN, None), record(LP, true));
+
+        // A keypair account with room gets one too, naming whichever key
+        // signed: the authority, or the LP PDA when the authority didn't
+        let keypair = |len: usize, authority_signs: bool| {
+            let mut init = ix::init(&PROGRAM_ID, &LP, &Pubkey::new_unique(), Some(&slab), Some(&authority), &params).unwrap();
+            init.accounts[0].is_signer = !authority_signs;
+            init.accounts[3].is_signer = authority_signs;
+            let (result, accounts) = run_client_ix(&init, vec![vec![], vec![0u8; len], vec![], vec![]]);
+            result.unwrap();
+            Decoded::from_bytes(&accounts[1].data).unwrap().creation
+        };
+        assert_eq!(keypair(CTX_EXT_LEN + CREATION_LEN, true), record(authority, false));
+        assert_eq!(keypair(CTX_SHARED_LEN + CREATION_LEN, false), record(LP, false));
+        // And an account without room for the record gets none
+        assert_eq!(keypair(CTX_EXT_LEN, true), None);
     }
 
     #[test]
-    fn test_tier_fragile_boundary() {
-        let (tier, fill) = compute_tier(1000); // exactly 10%
-        assert_eq!(tier, "FRAGILE");
-        assert_eq!(fill, 50);
+    fn test_init_v5_cannot_bind_slab() {
+        let mut accounts = [
+            TestAccount::new(LP, false, 0, vec![]),
+            ctx_account(vec![0u8; CTX_LEN]),
+            TestAccount::new(Pubkey::new_unique(), false, 0, vec![]),
+        ];
+        assert_eq!(run_accounts(&mut accounts, &init_data(0)), Err(MatcherError::ExtensionRequired.into()));
+        assert!(accounts[1].data.iter().all(|b| *b == 0));
     }
 
     #[test]
-    fn test_tier_fragile_20pct() {
-        let (tier, fill) = compute_tier(2000); // 20%
-        assert_eq!(tier, "FRAGILE");
-        assert_eq!(fill, 50);
+    fn test_update_rejects_foreign_slab() {
+        let slab = Pubkey::new_unique();
+        let mut ctx = init_bound(slab);
+        let slab_data = SlabFixture { insurance: 1_000_000, total_oi: 1_000_000, ..Default::default() }.to_bytes();
+
+        let before = ctx.clone();
+        assert_eq!(
+            run_update(&mut ctx, Pubkey::new_unique(), &slab_data, 100),
+            Err(MatcherError::SlabMismatch.into())
+        );
+        assert_eq!(ctx, before);
+
+        run_update(&mut ctx, slab, &slab_data, 100).unwrap();
+        assert_eq!(read_u128(&ctx, CTX_BASE + CTX_INSURANCE_OFF), 1_000_000);
+        assert_eq!(read_u64(&ctx, CTX_BASE + CTX_SNAPSHOT_SLOT_OFF), 100);
     }
 
     #[test]
-    fn test_tier_normal_boundary() {
-        let (tier, fill) = compute_tier(2500); // exactly 25%
-        assert_eq!(tier, "NORMAL");
-        assert_eq!(fill, 100);
+    fn test_update_snapshots_every_slab_field() {
+        let slab = Pubkey::new_unique();
+        let mut ctx = init_bound(slab);
+        let fixture = SlabFixture {
+            insurance: 250_000,
+            total_oi: 1_000_000,
+            last_crank_slot: 95,
+            lifetime_liqs: 3,
+            funding_rate_bps_per_slot: -2,
+            ..Default::default()
+        };
+        run_update(&mut ctx, slab, &fixture.to_bytes(), 100).unwrap();
+        assert_eq!(read_u128(&ctx, CTX_BASE + CTX_INSURANCE_OFF), 250_000);
+        assert_eq!(read_u128(&ctx, CTX_BASE + CTX_TOTAL_OI_OFF), 1_000_000);
+        assert_eq!(read_u64(&ctx, CTX_BASE + CTX_LAST_CRANK_OFF), 95);
+        assert_eq!(read_u64(&ctx, CTX_BASE + CTX_LAST_LIQS_OFF), 3);
+        assert_eq!(read_i64(&ctx, EXT_BASE + EXT_FUNDING_RATE_OFF), -2);
+
+        // A burned admin lets the market age; a live one holds it at zero
+        run_update(&mut ctx, slab, &fixture.to_bytes(), 150).unwrap();
+        assert_eq!(read_u64(&ctx, CTX_BASE + CTX_MARKET_AGE_OFF), 50);
+        let live = SlabFixture { admin: Pubkey::new_unique(), ..fixture };
+        run_update(&mut ctx, slab, &live.to_bytes(), 200).unwrap();
+        assert_eq!(read_u64(&ctx, CTX_BASE + CTX_MARKET_AGE_OFF), 0);
+
+        // One byte short of the engine is refused
+        let short = SlabFixture { len: SLAB_MIN_LEN - 1, ..fixture };
+        assert_eq!(run_update(&mut ctx, slab, &short.to_bytes(), 250), Err(ProgramError::AccountDataTooSmall));
+
+        // A smaller fund than last time paid out for a deficit; a refill doesn't clear it
+        assert_eq!(read_u64(&ctx, CTX_BASE + CTX_LAST_DEFICIT_OFF), 0);
+        let drawn = SlabFixture { insurance: 240_000, ..fixture };
+        run_update(&mut ctx, slab, &drawn.to_bytes(), 300).unwrap();
+        assert_eq!(read_u64(&ctx, CTX_BASE + CTX_LAST_DEFICIT_OFF), 300);
+        run_update(&mut ctx, slab, &fixture.to_bytes(), 350).unwrap();
+        assert_eq!(read_u64(&ctx, CTX_BASE + CTX_LAST_DEFICIT_OFF), 300);
+    }
+
+    fn auction_params(bps: u16, slots: u32) -> Vec<u8> {
+        let mut params = params_data(0);
+        params[124..126].copy_from_slice(&bps.to_le_bytes());
+        params[126..130].copy_from_slice(&slots.to_le_bytes());
+        params
     }
 
     #[test]
-    fn test_tier_normal_50pct() {
-        let (tier, fill) = compute_tier(5000); // 50%
-        assert_eq!(tier, "NORMAL");
-        assert_eq!(fill, 100);
+    fn test_liq_auction_premium_decays_from_last_liquidation() {
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        let mut init = vec![0x02, KIND_CREDIBILITY];
+        init.extend_from_slice(&auction_params(200, 40)); // 2% over 40 slots
+        run_init(&mut ctx, &init).unwrap();
+        let slab = Pubkey::new_unique();
+        run_bind(&mut ctx, slab).unwrap();
+        let fixture = SlabFixture { insurance: 1_000_000, total_oi: 1_000_000, ..Default::default() };
+
+        // The first snapshot's count predates the matcher and opens nothing
+        run_update(&mut ctx, slab, &SlabFixture { lifetime_liqs: 5, ..fixture }.to_bytes(), 100).unwrap();
+        assert_eq!(read_u64(&ctx, EXT_BASE + EXT_LAST_LIQ_SLOT_OFF), 0);
+        assert_eq!(read_u64(&run_preview(&ctx, 100_000_000, 1_000).unwrap(), QUOTE_LIQ_OFF), 0);
+
+        // New liquidations open it at full premium on both sides...
+        run_update(&mut ctx, slab, &SlabFixture { lifetime_liqs: 7, ..fixture }.to_bytes(), 110).unwrap();
+        assert_eq!(read_u64(&ctx, EXT_BASE + EXT_LAST_LIQ_SLOT_OFF), 110);
+        for size in [1_000, -1_000] {
+            assert_eq!(read_u64(&run_preview(&ctx, 100_000_000, size).unwrap(), QUOTE_LIQ_OFF), 200);
+        }
+
+        // ...which tightens linearly and is gone after liq_auction_slots
+        for (slot, premium) in [(120, 150), (130, 100), (149, 5), (150, 0)] {
+            set_slot(slot);
+            assert_eq!(read_u64(&run_preview(&ctx, 100_000_000, 1_000).unwrap(), QUOTE_LIQ_OFF), premium, "slot {slot}");
+        }
+
+        // A quiet snapshot leaves the auction's start where it was
+        run_update(&mut ctx, slab, &SlabFixture { lifetime_liqs: 7, ..fixture }.to_bytes(), 120).unwrap();
+        assert_eq!(read_u64(&ctx, EXT_BASE + EXT_LAST_LIQ_SLOT_OFF), 110);
     }
 
     #[test]
-    fn test_tier_strong_boundary() {
-        let (tier, fill) = compute_tier(10000); // exactly 100%
-        assert_eq!(tier, "STRONG");
-        assert_eq!(fill, 100);
+    fn test_liq_auction_validation() {
+        for (bps, slots) in [(200, 0), (0, 40)] {
+            let mut ctx = vec![0u8; CTX_EXT_LEN];
+            let mut init = vec![0x02, KIND_CREDIBILITY];
+            init.extend_from_slice(&auction_params(bps, slots));
+            assert_eq!(run_init(&mut ctx, &init), Err(MatcherError::InvalidLiqAuction.into()));
+        }
+        let mut v5 = vec![0u8; CTX_LEN];
+        let mut init = vec![0x02, KIND_CREDIBILITY];
+        init.extend_from_slice(&auction_params(200, 40));
+        assert_eq!(run_init(&mut v5, &init), Err(MatcherError::ExtensionRequired.into()));
     }
 
     #[test]
-    fn test_tier_strong_150pct() {
-        let (tier, fill) = compute_tier(15000); // 150%
-        assert_eq!(tier, "STRONG");
-        assert_eq!(fill, 100);
+    fn test_update_requires_clock_sysvar() {
+        set_slot(500);
+        let slab = Pubkey::new_unique();
+        let ctx = init_bound(slab);
+        // A forged "clock" claiming a far-future slot
+        let mut accounts = [
+            TestAccount::new(Pubkey::new_unique(), false, 0, ctx.clone()),
+            slab_account(slab, SlabFixture::default().to_bytes()),
+            TestAccount::new(Pubkey::new_unique(), false, 0, u64::MAX.to_le_bytes().to_vec()),
+        ];
+        assert_eq!(run_accounts(&mut accounts, &[0x03]), Err(MatcherError::InvalidClock.into()));
+        assert_eq!(accounts[0].data, ctx);
+
+        // The real sysvar's account bytes are ignored; the runtime slot is used
+        accounts[2] = TestAccount::new(solana_program::sysvar::clock::id(), false, 0, u64::MAX.to_le_bytes().to_vec());
+        run_accounts(&mut accounts, &[0x03]).unwrap();
+        assert_eq!(read_u64(&accounts[0].data, CTX_BASE + CTX_SNAPSHOT_SLOT_OFF), 500);
     }
 
     #[test]
-    fn test_tier_fortified_boundary() {
-        let (tier, fill) = compute_tier(20000); // exactly 200%
-        assert_eq!(tier, "FORTIFIED");
-        assert_eq!(fill, 150);
+    fn test_tip_jar_pays_keepers_for_stale_snapshots() {
+        set_slot(100);
+        let slab_key = Pubkey::new_unique();
+        let ctx = init_bound(slab_key);
+        let rent = Rent::default().minimum_balance(TIP_JAR_LEN);
+        let tip_data = |tag: u8, per_slot: u64, max: u64| [&[tag][..], &per_slot.to_le_bytes(), &max.to_le_bytes()].concat();
+        let mut accounts = [
+            TestAccount::new(LP, true, 0, vec![]),
+            ctx_account(ctx),
+            TestAccount::new(Pubkey::new_unique(), false, rent + 3_000, vec![0u8; TIP_JAR_LEN]),
+        ];
+        assert_eq!(run_accounts(&mut accounts, &tip_data(0x26, 10, 5_000)[..9]), Err(ProgramError::InvalidInstructionData));
+        run_accounts(&mut accounts, &tip_data(0x26, 10, 5_000)).unwrap();
+        assert_eq!(read_pubkey(&accounts[2].data, TIP_JAR_CTX_OFF), accounts[1].key);
+        assert_eq!(run_accounts(&mut accounts, &tip_data(0x26, 10, 5_000)), Err(ProgramError::AccountAlreadyInitialized));
+        let [lp, ctx, jar] = accounts;
+
+        let slab = slab_account(slab_key, SlabFixture::default().to_bytes());
+        let clock = TestAccount::new(solana_program::sysvar::clock::id(), false, 0, vec![]);
+        let keeper = TestAccount::new(Pubkey::new_unique(), true, 0, vec![]);
+        let mut accounts = [ctx, slab, clock, jar, keeper];
+        // The first snapshot has no staleness to pay for
+        set_slot(1_000);
+        run_accounts(&mut accounts, &[0x03]).unwrap();
+        assert_eq!(accounts[4].lamports, 0);
+
+        // 100 slots past the grace period: 10 lamports each
+        set_slot(1_000 + SNAPSHOT_GRACE_SLOTS + 100);
+        run_accounts(&mut accounts, &[0x03]).unwrap();
+        assert_eq!((accounts[4].lamports, accounts[3].lamports), (1_000, rent + 2_000));
+        assert_eq!((read_u64(&accounts[3].data, TIP_JAR_PAID_OFF), read_u64(&accounts[3].data, TIP_JAR_COUNT_OFF)), (1_000, 1));
+        // A refresh right after earns nothing; a long-stale one is capped, and
+        // then paid only what the escrow holds above rent
+        run_accounts(&mut accounts, &[0x03]).unwrap();
+        assert_eq!(accounts[4].lamports, 1_000);
+        set_slot(1_000_000);
+        run_accounts(&mut accounts, &[0x03]).unwrap();
+        assert_eq!((accounts[4].lamports, accounts[3].lamports), (3_000, rent));
+        assert_eq!((read_u64(&accounts[3].data, TIP_JAR_PAID_OFF), read_u64(&accounts[3].data, TIP_JAR_COUNT_OFF)), (3_000, 2));
+
+        // The keeper signs, follows the jar, and the jar is this context's
+        accounts[4].signer = false;
+        assert_eq!(run_accounts(&mut accounts, &[0x03]), Err(ProgramError::MissingRequiredSignature));
+        let [ctx, slab, clock, jar, keeper] = accounts;
+        let mut short = [ctx, slab, clock, jar];
+        assert_eq!(run_accounts(&mut short, &[0x03]), Err(ProgramError::NotEnoughAccountKeys));
+        let [ctx, slab, clock, jar] = short;
+        let other = init_bound(slab_key);
+        let mut foreign = [ctx_account(other), slab, clock, jar, TestAccount { signer: true, ..keeper }];
+        assert_eq!(run_accounts(&mut foreign, &[0x03]), Err(MatcherError::TipJarMismatch.into()));
+        let [_, _, _, jar, _] = foreign;
+
+        // The LP retunes the tip and later takes the jar back
+        let mut accounts = [lp, ctx, jar];
+        run_accounts(&mut accounts, &tip_data(0x27, 20, 0)).unwrap();
+        assert_eq!((read_u64(&accounts[2].data, TIP_JAR_PER_SLOT_OFF), read_u64(&accounts[2].data, TIP_JAR_MAX_OFF)), (20, 0));
+        let [lp, ctx, jar] = accounts;
+        let destination = TestAccount::new(Pubkey::new_unique(), false, 7, vec![]);
+        let mut accounts = [lp, ctx, jar, destination];
+        run_accounts(&mut accounts, &[0x28]).unwrap();
+        assert_eq!((accounts[2].lamports, accounts[3].lamports), (0, rent + 7));
+        assert!(accounts[2].data.iter().all(|&b| b == 0));
+        assert_eq!(run_accounts(&mut accounts, &[0x28]), Err(MatcherError::TipJarMismatch.into()));
     }
 
     #[test]
-    fn test_tier_fortified_300pct() {
-        let (tier, fill) = compute_tier(30000); // 300%
-        assert_eq!(tier, "FORTIFIED");
-        assert_eq!(fill, 150);
+    fn test_referral_account_credits_the_referrer_a_share_of_fees() {
+        set_slot(1);
+        let slab = Pubkey::new_unique();
+        let share = |tag: u8, bps: u16| [&[tag][..], &bps.to_le_bytes()].concat();
+        let mut accounts = [
+            TestAccount::new(LP, true, 0, vec![]),
+            ctx_account(init_bound(slab)),
+            ctx_account(vec![0u8; REFERRAL_LEN]),
+        ];
+        assert_eq!(run_accounts(&mut accounts, &share(0x29, BPS as u16 + 1)), Err(MatcherError::ReferralShareTooHigh.into()));
+        assert_eq!(run_accounts(&mut accounts, &share(0x29, 2_000)[..2]), Err(ProgramError::InvalidInstructionData));
+        run_accounts(&mut accounts, &share(0x29, 2_000)).unwrap();
+        assert_eq!(read_pubkey(&accounts[2].data, REFERRAL_CTX_OFF), accounts[1].key);
+        assert_eq!(run_accounts(&mut accounts, &share(0x29, 2_000)), Err(ProgramError::AccountAlreadyInitialized));
+        let [lp, ctx, referral] = accounts;
+
+        // The referrer rides after the call data; the referral account after the slab
+        let referrer = Pubkey::new_unique();
+        let referred = |trade_size: i128, referrer: &Pubkey| [match_data(100_000_000, trade_size), referrer.to_bytes().to_vec()].concat();
+        let mut accounts = [lp, ctx, slab_account(slab, vec![]), referral];
+        run_accounts(&mut accounts, &referred(1_000_000, &referrer)).unwrap();
+        let fees = read_u128(&accounts[1].data, EXT_BASE + EXT_CUM_FEES_OFF);
+        assert!(fees > 0);
+        let entry = REFERRAL_HEADER_LEN;
+        assert_eq!(read_u16(&accounts[3].data, REFERRAL_COUNT_OFF), 1);
+        assert_eq!(read_pubkey(&accounts[3].data, entry + REFERRER_KEY_OFF), referrer);
+        assert_eq!(read_u128(&accounts[3].data, entry + REFERRER_ACCRUED_OFF), fees * 2_000 / BPS as u128);
+        assert_eq!(read_u64(&accounts[3].data, entry + REFERRER_FILLS_OFF), 1);
+
+        // A second fill adds to the same entry; one naming no referrer, or
+        // the default key, credits no one
+        run_accounts(&mut accounts, &referred(-1_000_000, &referrer)).unwrap();
+        assert_eq!(read_u64(&accounts[3].data, entry + REFERRER_FILLS_OFF), 2);
+        run_accounts(&mut accounts, &match_data(100_000_000, 1_000_000)).unwrap();
+        run_accounts(&mut accounts, &referred(1_000_000, &Pubkey::default())).unwrap();
+        let fees = read_u128(&accounts[1].data, EXT_BASE + EXT_CUM_FEES_OFF);
+        assert_eq!(read_u16(&accounts[3].data, REFERRAL_COUNT_OFF), 1);
+        assert_eq!(read_u64(&accounts[3].data, entry + REFERRER_FILLS_OFF), 2);
+        assert!(read_u128(&accounts[3].data, entry + REFERRER_ACCRUED_OFF) < fees * 2_000 / BPS as u128);
+
+        // A full table turns new referrers away without failing the fill
+        for i in 1..MAX_REFERRERS {
+            run_accounts(&mut accounts, &referred(1_000, &Pubkey::new_from_array([i as u8; 32]))).unwrap();
+        }
+        assert_eq!(read_u16(&accounts[3].data, REFERRAL_COUNT_OFF) as usize, MAX_REFERRERS);
+        let table = accounts[3].data.clone();
+        run_accounts(&mut accounts, &referred(1_000, &Pubkey::new_unique())).unwrap();
+        assert_eq!(accounts[3].data, table);
+
+        // Another context's referral account is refused
+        let [lp, ctx, slab, referral] = accounts;
+        let mut foreign = [
+            TestAccount::new(LP, true, 0, vec![]),
+            ctx_account(init_bound(slab.key)),
+            slab,
+            referral,
+        ];
+        assert_eq!(run_accounts(&mut foreign, &referred(1_000, &referrer)), Err(MatcherError::ReferralMismatch.into()));
+        let [_, _, _, referral] = foreign;
+
+        // The LP retunes the share; credits already made stay
+        let mut accounts = [lp, ctx, referral];
+        assert_eq!(run_accounts(&mut accounts, &share(0x2A, BPS as u16 + 1)), Err(MatcherError::ReferralShareTooHigh.into()));
+        run_accounts(&mut accounts, &share(0x2A, 500)).unwrap();
+        assert_eq!(read_u16(&accounts[2].data, REFERRAL_SHARE_OFF), 500);
+        assert_eq!(read_u64(&accounts[2].data, entry + REFERRER_FILLS_OFF), 2);
     }
 
     #[test]
-    fn test_fill_cap_math() {
-        let base_max_fill: u128 = 1_000_000_000; // 1 SOL
-        // CRITICAL: 25% of base
-        assert_eq!((base_max_fill * FILL_PCT_CRITICAL) / 100, 250_000_000);
-        // FRAGILE: 50%
-        assert_eq!((base_max_fill * FILL_PCT_FRAGILE) / 100, 500_000_000);
-        // NORMAL/STRONG: 100%
-        assert_eq!((base_max_fill * FILL_PCT_NORMAL) / 100, 1_000_000_000);
-        // FORTIFIED: 150%
-        assert_eq!((base_max_fill * FILL_PCT_FORTIFIED) / 100, 1_500_000_000);
+    fn test_update_rejects_slab_with_wrong_owner() {
+        let slab = Pubkey::new_unique();
+        let mut ctx = init_bound(slab);
+        let mut accounts = [
+            TestAccount::new(Pubkey::new_unique(), false, 0, ctx.clone()),
+            // Right key, wrong owner: e.g. the slab was closed and recreated elsewhere
+            TestAccount::new(slab, false, 0, SlabFixture::default().to_bytes()),
+            TestAccount::new(solana_program::sysvar::clock::id(), false, 0, 100u64.to_le_bytes().to_vec()),
+        ];
+        assert_eq!(run_accounts(&mut accounts, &[0x03]), Err(MatcherError::SlabOwnerMismatch.into()));
+        assert_eq!(accounts[0].data, ctx);
+
+        run_update(&mut ctx, slab, &SlabFixture::default().to_bytes(), 100).unwrap();
     }
 
     #[test]
-    fn test_critical_gets_max_spread() {
-        // At CRITICAL tier, spread should be max_spread
-        let max_spread: u64 = 200;
-        let coverage_bps: u64 = 500; // 5% → CRITICAL
-        let spread = if coverage_bps < TIER_CRITICAL_BPS {
-            max_spread
-        } else {
-            0 // placeholder
+    fn test_update_cranks_unbound_contexts_from_percolator_slabs() {
+        let fixture = SlabFixture { insurance: 300_000_000, total_oi: 1_000_000_000, ..Default::default() }.to_bytes();
+        let mut v5 = vec![0u8; CTX_LEN];
+        run_init(&mut v5, &init_data(0)).unwrap();
+        let mut v6 = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut v6, &init_data(0)).unwrap();
+        let crank = |ctx: &[u8], owner: Pubkey| {
+            set_slot(100);
+            let mut accounts = [
+                TestAccount::new(Pubkey::new_unique(), false, 0, ctx.to_vec()),
+                TestAccount { owner, ..TestAccount::new(Pubkey::new_unique(), false, 0, fixture.clone()) },
+                TestAccount::new(solana_program::sysvar::clock::id(), false, 0, 100u64.to_le_bytes().to_vec()),
+            ];
+            let result = run_accounts(&mut accounts, &[0x03]);
+            (result, accounts[0].data.clone())
         };
-        assert_eq!(spread, 200);
+        for ctx in [v5, v6] {
+            // With no owner pinned, a look-alike slab under a program the
+            // attacker controls, or another deployment, is refused...
+            for owner in [Pubkey::new_unique(), PERCOLATOR] {
+                assert_eq!(crank(&ctx, owner), (Err(MatcherError::SlabOwnerMismatch.into()), ctx.clone()));
+            }
+            // ...and the percolator program's slabs are read
+            let (result, cranked) = crank(&ctx, PERCOLATOR_PROGRAM_ID);
+            result.unwrap();
+            assert_eq!(read_u128(&cranked, CTX_BASE + CTX_INSURANCE_OFF), 300_000_000);
+            assert_eq!(read_u64(&cranked, CTX_BASE + CTX_SNAPSHOT_SLOT_OFF), 100);
+        }
     }
 
     #[test]
-    fn test_fortified_gets_min_spread() {
-        // At FORTIFIED tier, spread should be min_spread
-        let min_spread: u64 = 5;
-        let coverage_bps: u64 = 25000; // 250% → FORTIFIED
-        let spread = if coverage_bps >= TIER_STRONG_BPS {
-            min_spread
-        } else {
-            0
+    fn test_bind_slab_pins_an_unbound_context() {
+        let slab = Pubkey::new_unique();
+        let fixture = SlabFixture { insurance: 1_000_000, total_oi: 1_000_000, ..Default::default() }.to_bytes();
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+
+        // The authority signs, and a system account is no slab
+        let mut accounts = [TestAccount::new(LP, false, 0, vec![]), ctx_account(ctx.clone()), slab_account(slab, vec![])];
+        assert_eq!(run_accounts(&mut accounts, &[0x39]), Err(ProgramError::MissingRequiredSignature));
+        let mut accounts = [
+            TestAccount::new(LP, true, 0, vec![]),
+            ctx_account(ctx.clone()),
+            TestAccount { owner: solana_program::system_program::id(), ..TestAccount::new(slab, false, 0, vec![]) },
+        ];
+        assert_eq!(run_accounts(&mut accounts, &[0x39]), Err(MatcherError::SlabOwnerMismatch.into()));
+
+        run_bind(&mut ctx, slab).unwrap();
+        assert_eq!(read_pubkey(&ctx, EXT_BASE + EXT_SLAB_OFF), slab);
+        assert_eq!(read_pubkey(&ctx, EXT_BASE + EXT_PERCOLATOR_OFF), PERCOLATOR);
+
+        // Now only the bound slab, under percolator, is read
+        assert_eq!(run_update(&mut ctx, Pubkey::new_unique(), &fixture, 100), Err(MatcherError::SlabMismatch.into()));
+        let mut spoofed = [
+            TestAccount::new(Pubkey::new_unique(), false, 0, ctx.clone()),
+            TestAccount { owner: Pubkey::new_unique(), ..TestAccount::new(slab, false, 0, fixture.clone()) },
+            TestAccount::new(solana_program::sysvar::clock::id(), false, 0, vec![]),
+        ];
+        assert_eq!(run_accounts(&mut spoofed, &[0x03]), Err(MatcherError::SlabOwnerMismatch.into()));
+        run_update(&mut ctx, slab, &fixture, 100).unwrap();
+        assert_eq!(read_u128(&ctx, CTX_BASE + CTX_INSURANCE_OFF), 1_000_000);
+
+        // The binding is for life, and only a plain v6 context takes one
+        assert_eq!(run_bind(&mut ctx, Pubkey::new_unique()), Err(MatcherError::SlabMismatch.into()));
+        let mut v5 = vec![0u8; CTX_LEN];
+        run_init(&mut v5, &init_data(0)).unwrap();
+        assert_eq!(run_bind(&mut v5, slab), Err(MatcherError::ExtensionRequired.into()));
+        let mut shared = init_shared(slab);
+        assert_eq!(run_bind(&mut shared, slab), Err(MatcherError::SharedContext.into()));
+    }
+
+    #[test]
+    fn test_match_checks_slab_when_passed() {
+        set_slot(1);
+        let slab = Pubkey::new_unique();
+        let ctx = init_bound(slab);
+        let run = |slab_key: Option<Pubkey>| {
+            let mut accounts = vec![
+                TestAccount::new(LP, true, 0, vec![]),
+                TestAccount::new(Pubkey::new_unique(), false, 0, ctx.clone()),
+            ];
+            if let Some(key) = slab_key {
+                accounts.push(slab_account(key, vec![]));
+            }
+            run_accounts(&mut accounts, &match_data(100_000_000, 1_000))
         };
-        assert_eq!(spread, 5);
+        assert_eq!(run(Some(Pubkey::new_unique())), Err(MatcherError::SlabMismatch.into()));
+        run(Some(slab)).unwrap();
+        run(None).unwrap();
+    }
+
+    // -------------------------------------------------------------------------
+    // RFQ
+    // -------------------------------------------------------------------------
+
+    const RFQ_SIGNER: Pubkey = Pubkey::new_from_array([5u8; 32]);
+
+    /// The instructions sysvar of a transaction made of `ixs`, running the last.
+    fn instructions_sysvar(ixs: &[solana_program::instruction::Instruction]) -> Vec<u8> {
+        use solana_instruction::{BorrowedAccountMeta, BorrowedInstruction};
+        let borrowed: Vec<BorrowedInstruction> = ixs
+            .iter()
+            .map(|ix| BorrowedInstruction {
+                program_id: &ix.program_id,
+                accounts: ix
+                    .accounts
+                    .iter()
+                    .map(|m| BorrowedAccountMeta { pubkey: &m.pubkey, is_signer: m.is_signer, is_writable: m.is_writable })
+                    .collect(),
+                data: &ix.data,
+            })
+            .collect();
+        let mut data = solana_instructions_sysvar::construct_instructions_data(&borrowed);
+        solana_instructions_sysvar::store_current_index_checked(&mut data, ixs.len() as u16 - 1).unwrap();
+        data
+    }
+
+    /// A v6 context quoting through RFQ_SIGNER.
+    fn rfq_context() -> Vec<u8> {
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        run_set_rfq_signer(ctx, &RFQ_SIGNER).unwrap()
+    }
+
+    fn run_set_rfq_signer(ctx: Vec<u8>, signer: &Pubkey) -> Result<Vec<u8>, ProgramError> {
+        let set = provenance_client::instruction::set_rfq_signer(&PROGRAM_ID, &LP, &Pubkey::new_unique(), signer);
+        let (result, accounts) = run_client_ix(&set, vec![vec![], ctx]);
+        result.map(|()| accounts[1].data.clone())
+    }
+
+    /// Run PostRfq for `quote` in a transaction where `before` precedes it.
+    fn run_post_rfq(
+        ctx: Vec<u8>,
+        quote: &provenance_client::instruction::RfqQuote,
+        before: Option<solana_program::instruction::Instruction>,
+    ) -> Result<Vec<u8>, ProgramError> {
+        let post = provenance_client::instruction::post_rfq(&PROGRAM_ID, quote);
+        let ixs: Vec<_> = before.into_iter().chain([post.clone()]).collect();
+        let (result, accounts) = run_client_ix(&post, vec![ctx, instructions_sysvar(&ixs)]);
+        result.map(|()| accounts[0].data.clone())
+    }
+
+    /// Post `quote` behind a verification of it by RFQ_SIGNER.
+    fn post_signed(ctx: Vec<u8>, quote: &provenance_client::instruction::RfqQuote) -> Result<Vec<u8>, ProgramError> {
+        let verify = provenance_client::instruction::rfq_signature(&RFQ_SIGNER, &[1; 64], quote);
+        run_post_rfq(ctx, quote, Some(verify))
+    }
+
+    fn rfq_quote(ctx: Pubkey, nonce: u64) -> provenance_client::instruction::RfqQuote {
+        provenance_client::instruction::RfqQuote { ctx, price_e6: 101_000_000, size: 1_500, expiry_slot: 1_010, nonce }
+    }
+
+    #[test]
+    fn test_set_rfq_signer_guarded() {
+        set_slot(1_000);
+        let mut ctx = vec![0u8; CTX_EXT_LEN];
+        run_init(&mut ctx, &init_data(0)).unwrap();
+        let set = provenance_client::instruction::set_rfq_signer(&PROGRAM_ID, &LP, &Pubkey::new_unique(), &RFQ_SIGNER);
+        let mut unsigned = [TestAccount::new(LP, false, 0, vec![]), ctx_account(ctx.clone())];
+        assert_eq!(run_accounts(&mut unsigned, &set.data), Err(ProgramError::MissingRequiredSignature));
+        let intruder = provenance_client::instruction::set_rfq_signer(&PROGRAM_ID, &Pubkey::new_unique(), &Pubkey::new_unique(), &RFQ_SIGNER);
+        assert_eq!(run_client_ix(&intruder, vec![vec![], ctx.clone()]).0, Err(MatcherError::AuthorityMismatch.into()));
+
+        let mut v5 = vec![0u8; CTX_LEN];
+        run_init(&mut v5, &init_data(0)).unwrap();
+        assert_eq!(run_set_rfq_signer(v5, &RFQ_SIGNER), Err(MatcherError::ExtensionRequired.into()));
+
+        // Changing the signer withdraws the posted quote but keeps its nonce
+        let ctx = run_set_rfq_signer(ctx, &RFQ_SIGNER).unwrap();
+        assert_eq!(read_pubkey(&ctx, EXT_BASE + EXT_RFQ_SIGNER_OFF), RFQ_SIGNER);
+        let ctx_key = Pubkey::new_unique();
+        let ctx = post_signed(ctx, &rfq_quote(ctx_key, 3)).unwrap();
+        assert!(posted_rfq(&ctx).is_some());
+        let ctx = run_set_rfq_signer(ctx, &Pubkey::default()).unwrap();
+        assert_eq!(posted_rfq(&ctx), None);
+        assert_eq!(read_u64(&ctx, EXT_BASE + EXT_RFQ_NONCE_OFF), 3);
+        assert_eq!(post_signed(ctx, &rfq_quote(ctx_key, 4)), Err(MatcherError::RfqSignerUnset.into()));
+    }
+
+    #[test]
+    fn test_post_rfq_needs_the_signers_verification() {
+        use provenance_client::instruction::rfq_signature;
+
+        set_slot(1_000);
+        let ctx_key = Pubkey::new_unique();
+        let quote = rfq_quote(ctx_key, 1);
+        let unverified = Err(MatcherError::RfqUnverified.into());
+        assert_eq!(run_post_rfq(rfq_context(), &quote, None), unverified);
+
+        let other_key = rfq_signature(&Pubkey::new_unique(), &[1; 64], &quote);
+        assert_eq!(run_post_rfq(rfq_context(), &quote, Some(other_key)), unverified);
+        let other_quote = rfq_signature(&RFQ_SIGNER, &[1; 64], &provenance_client::instruction::RfqQuote { price_e6: 1, ..quote });
+        assert_eq!(run_post_rfq(rfq_context(), &quote, Some(other_quote)), unverified);
+        let mut elsewhere = rfq_signature(&RFQ_SIGNER, &[1; 64], &quote);
+        elsewhere.data[ED25519_OFFSETS_OFF + ED25519_MSG_IX_OFF] = 0;
+        assert_eq!(run_post_rfq(rfq_context(), &quote, Some(elsewhere)), unverified);
+        let mut not_ed25519 = rfq_signature(&RFQ_SIGNER, &[1; 64], &quote);
+        not_ed25519.program_id = Pubkey::new_unique();
+        assert_eq!(run_post_rfq(rfq_context(), &quote, Some(not_ed25519)), unverified);
+
+        let ctx = post_signed(rfq_context(), &quote).unwrap();
+        let posted = posted_rfq(&ctx).unwrap();
+        assert_eq!((posted.price_e6, posted.size_left, posted.expiry_slot), (101_000_000, 1_500, 1_010));
+        assert_eq!(read_u64(&ctx, EXT_BASE + EXT_RFQ_NONCE_OFF), 1);
+        let decoded = provenance_client::MatcherContext::from_bytes(&ctx).unwrap();
+        let ext = decoded.extension.unwrap();
+        assert_eq!((ext.rfq_signer, ext.rfq_price_e6, ext.rfq_size_left), (RFQ_SIGNER, 101_000_000, 1_500));
+        assert_eq!((ext.rfq_expiry_slot, ext.rfq_nonce), (1_010, 1));
+        assert_eq!(decoded.to_bytes(), ctx);
+    }
+
+    #[test]
+    fn test_post_rfq_rejects_stale_and_foreign_quotes() {
+        use provenance_client::instruction::RfqQuote;
+
+        set_slot(1_000);
+        let ctx_key = Pubkey::new_unique();
+        let quote = rfq_quote(ctx_key, 5);
+        let invalid = Err(MatcherError::InvalidRfq.into());
+        let foreign = RfqQuote { ctx: Pubkey::new_unique(), ..quote };
+        let verify = provenance_client::instruction::rfq_signature(&RFQ_SIGNER, &[1; 64], &foreign);
+        let mut post = provenance_client::instruction::post_rfq(&PROGRAM_ID, &foreign);
+        post.accounts[0].pubkey = ctx_key;
+        let sysvar = instructions_sysvar(&[verify, post.clone()]);
+        assert_eq!(run_client_ix(&post, vec![rfq_context(), sysvar]).0, Err(MatcherError::InvalidRfq.into()));
+        assert_eq!(post_signed(rfq_context(), &RfqQuote { price_e6: 0, ..quote }), invalid);
+        assert_eq!(post_signed(rfq_context(), &RfqQuote { size: 0, ..quote }), invalid);
+        assert_eq!(post_signed(rfq_context(), &RfqQuote { expiry_slot: 999, ..quote }), Err(MatcherError::RfqExpired.into()));
+
+        let ctx = post_signed(rfq_context(), &quote).unwrap();
+        assert_eq!(post_signed(ctx.clone(), &quote), Err(MatcherError::RfqReplayed.into()));
+        assert_eq!(post_signed(ctx.clone(), &RfqQuote { nonce: 4, ..quote }), Err(MatcherError::RfqReplayed.into()));
+
+        // A newer quote replaces the posted one
+        let ctx = post_signed(ctx, &RfqQuote { size: -200, nonce: 6, ..quote }).unwrap();
+        assert_eq!(posted_rfq(&ctx).unwrap().size_left, -200);
+
+        let mut paused = rfq_context();
+        paused[CTX_BASE + CTX_FLAGS_OFF] |= CTX_FLAG_PAUSED;
+        assert_eq!(post_signed(paused, &quote), Err(MatcherError::Paused.into()));
+    }
+
+    #[test]
+    fn test_match_fills_against_posted_rfq() {
+        set_slot(1_000);
+        let ctx_key = Pubkey::new_unique();
+        let mut ctx = post_signed(rfq_context(), &rfq_quote(ctx_key, 1)).unwrap();
+        let trade = |ctx: &mut Vec<u8>, size: i128| {
+            run_lp_ix(ctx, &LP, true, &match_data(100_000_000, size)).unwrap();
+            (read_u64(ctx, RET_EXEC_PRICE_OFF), read_i128(ctx, RET_EXEC_SIZE_OFF))
+        };
+
+        assert_eq!(trade(&mut ctx, 1_000), (101_000_000, 1_000));
+        assert_eq!(posted_rfq(&ctx).unwrap().size_left, 500);
+        // The other side still prices off the kind
+        let (price, size) = trade(&mut ctx, -300);
+        assert_ne!(price, 101_000_000);
+        assert_eq!(size, -300);
+        assert_eq!(posted_rfq(&ctx).unwrap().size_left, 500);
+        // Clipped to what is left, then withdrawn
+        assert_eq!(trade(&mut ctx, 2_000), (101_000_000, 500));
+        assert_eq!(posted_rfq(&ctx), None);
+        assert_ne!(trade(&mut ctx, 1_000).0, 101_000_000);
+        assert_eq!(read_i128(&ctx, CTX_BASE + CTX_INVENTORY_OFF), 2_200);
+
+        // Past its expiry the quote no longer fills
+        let mut ctx = post_signed(ctx, &rfq_quote(ctx_key, 2)).unwrap();
+        set_slot(1_011);
+        assert_ne!(trade(&mut ctx, 1_000).0, 101_000_000);
+        assert_eq!(posted_rfq(&ctx).unwrap().size_left, 1_500);
+    }
+
+    // -------------------------------------------------------------------------
+    // Shared contexts
+    // -------------------------------------------------------------------------
+
+    /// A shared context initialized with `slab` as its first market.
+    fn init_shared(slab: Pubkey) -> Vec<u8> {
+        let mut accounts = [
+            TestAccount::new(LP, true, 0, vec![]),
+            ctx_account(vec![0u8; CTX_SHARED_LEN]),
+            slab_account(slab, vec![]),
+        ];
+        run_accounts(&mut accounts, &init_data(0)).unwrap();
+        accounts[1].data.clone()
+    }
+
+    /// Run SetMarket for a percolator-owned `slab`, signed by the LP.
+    fn run_set_market(ctx: &mut Vec<u8>, slab: Pubkey, max_fill: u128, max_inventory: u128) -> ProgramResult {
+        let ix = provenance_client::instruction::set_market(&PROGRAM_ID, &LP, &Pubkey::new_unique(), &slab, max_fill, max_inventory);
+        let mut accounts = [TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx.clone()), slab_account(slab, vec![])];
+        let result = run_accounts(&mut accounts, &ix.data);
+        *ctx = accounts[1].data.clone();
+        result
+    }
+
+    /// Run Match on `slab`'s book; returns the filled size.
+    fn run_market_match(ctx: &mut Vec<u8>, slab: Option<Pubkey>, size: i128) -> Result<i128, ProgramError> {
+        let mut accounts = vec![TestAccount::new(LP, true, 0, vec![]), ctx_account(ctx.clone())];
+        accounts.extend(slab.map(|slab| slab_account(slab, vec![])));
+        let result = run_accounts(&mut accounts, &match_data(100_000_000, size));
+        *ctx = accounts[1].data.clone();
+        result.map(|()| read_i128(ctx, RET_EXEC_SIZE_OFF))
+    }
+
+    fn market_off(index: usize, off: usize) -> usize {
+        MARKETS_BASE + index * MARKET_LEN + off
+    }
+
+    #[test]
+    fn test_init_shared_lists_slab_as_first_market() {
+        let slab = Pubkey::new_unique();
+        let ctx = init_shared(slab);
+        assert_eq!(market_slots(&ctx), MAX_MARKETS);
+        assert_eq!(read_pubkey(&ctx, market_off(0, MKT_SLAB_OFF)), slab);
+        assert_eq!(read_pubkey(&ctx, EXT_BASE + EXT_SLAB_OFF), Pubkey::default());
+        assert_eq!(read_pubkey(&ctx, EXT_BASE + EXT_PERCOLATOR_OFF), PERCOLATOR);
+
+        // A plain v6 account is still single-market
+        assert!(!is_shared(&init_bound(slab)));
+    }
+
+    #[test]
+    fn test_set_market_adds_updates_and_fills_the_table() {
+        set_slot(1_000);
+        let first = Pubkey::new_unique();
+        let mut ctx = init_shared(first);
+        let second = Pubkey::new_unique();
+        run_set_market(&mut ctx, second, 500, 0).unwrap();
+        assert_eq!(read_pubkey(&ctx, market_off(1, MKT_SLAB_OFF)), second);
+        assert_eq!(read_u128(&ctx, market_off(1, MKT_MAX_FILL_OFF)), 500);
+
+        // Listed again, only the caps change
+        run_set_market(&mut ctx, second, 0, 9_000).unwrap();
+        assert_eq!(read_u128(&ctx, market_off(1, MKT_MAX_FILL_OFF)), 0);
+        assert_eq!(read_u128(&ctx, market_off(1, MKT_MAX_INVENTORY_OFF)), 9_000);
+        assert_eq!(read_pubkey(&ctx, market_off(2, MKT_SLAB_OFF)), Pubkey::default());
+
+        for _ in 2..MAX_MARKETS {
+            run_set_market(&mut ctx, Pubkey::new_unique(), 0, 0).unwrap();
+        }
+        let before = ctx.clone();
+        assert_eq!(run_set_market(&mut ctx, Pubkey::new_unique(), 0, 0), Err(MatcherError::MarketTableFull.into()));
+        assert_eq!(ctx, before);
+
+        // A slab under another program is refused like at Init
+        let mut ctx = init_shared(first);
+        let foreign = Pubkey::new_unique();
+        let ix = provenance_client::instruction::set_market(&PROGRAM_ID, &LP, &Pubkey::new_unique(), &foreign, 0, 0);
+        let (result, _) = run_client_ix(&ix, vec![vec![], ctx.clone(), vec![]]);
+        assert_eq!(result, Err(MatcherError::SlabOwnerMismatch.into()));
+
+        // Authority-gated, and only on a context with a table
+        let mut accounts = [TestAccount::new(LP, false, 0, vec![]), ctx_account(ctx.clone()), slab_account(foreign, vec![])];
+        assert_eq!(run_accounts(&mut accounts, &ix.data), Err(ProgramError::MissingRequiredSignature));
+        let mut single = init_bound(first);
+        assert_eq!(run_set_market(&mut single, foreign, 0, 0), Err(MatcherError::MarketTableFull.into()));
+        assert_eq!(run_set_market(&mut ctx, foreign, 0, 0), Ok(()));
+    }
+
+    #[test]
+    fn test_shared_match_keeps_books_apart() {
+        set_slot(1_000);
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut ctx = init_shared(first);
+        run_set_market(&mut ctx, second, 400, 0).unwrap();
+
+        assert_eq!(run_market_match(&mut ctx, Some(first), 1_000), Ok(1_000));
+        // The second market's own max_fill clips it
+        assert_eq!(run_market_match(&mut ctx, Some(second), -1_000), Ok(-400));
+        assert_eq!(read_i128(&ctx, market_off(0, MKT_INVENTORY_OFF)), 1_000);
+        assert_eq!(read_i128(&ctx, market_off(1, MKT_INVENTORY_OFF)), -400);
+        assert_eq!(read_i128(&ctx, CTX_BASE + CTX_INVENTORY_OFF), 0);
+        assert_eq!(read_u64(&ctx, market_off(1, MKT_LAST_ORACLE_OFF)), 100_000_000);
+        assert_eq!(read_u128(&ctx, market_off(0, MKT_GROSS_LONG_OFF)), 1_000);
+        assert_eq!(read_u128(&ctx, market_off(1, MKT_GROSS_SHORT_OFF)), 400);
+
+        // Without a listed slab there is no book to trade on
+        let before = ctx.clone();
+        let unknown = Err(MatcherError::UnknownMarket.into());
+        assert_eq!(run_market_match(&mut ctx, None, 1_000), unknown);
+        assert_eq!(run_market_match(&mut ctx, Some(Pubkey::new_unique()), 1_000), unknown);
+        assert_eq!(ctx, before);
+    }
+
+    #[test]
+    fn test_shared_update_snapshots_one_market() {
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut ctx = init_shared(first);
+        run_set_market(&mut ctx, second, 0, 0).unwrap();
+        let slab_data = SlabFixture { insurance: 1_000_000, total_oi: 2_000_000, funding_rate_bps_per_slot: -3, ..Default::default() }.to_bytes();
+
+        run_update(&mut ctx, second, &slab_data, 100).unwrap();
+        assert_eq!(read_u128(&ctx, market_off(1, MKT_INSURANCE_OFF)), 1_000_000);
+        assert_eq!(read_u128(&ctx, market_off(1, MKT_TOTAL_OI_OFF)), 2_000_000);
+        assert_eq!(read_u64(&ctx, market_off(1, MKT_SNAPSHOT_SLOT_OFF)), 100);
+        assert_eq!(read_i64(&ctx, market_off(1, MKT_FUNDING_RATE_OFF)), -3);
+        assert_eq!(read_u128(&ctx, market_off(0, MKT_INSURANCE_OFF)), 0);
+        assert_eq!(read_u128(&ctx, CTX_BASE + CTX_INSURANCE_OFF), 0);
+
+        let before = ctx.clone();
+        assert_eq!(run_update(&mut ctx, Pubkey::new_unique(), &slab_data, 101), Err(MatcherError::UnknownMarket.into()));
+        assert_eq!(ctx, before);
+    }
+
+    #[test]
+    fn test_shared_preview_and_settle_take_a_market_index() {
+        use provenance_client::instruction as ix;
+
+        set_slot(1_000);
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut ctx = init_shared(first);
+        run_set_market(&mut ctx, second, 0, 0).unwrap();
+        run_market_match(&mut ctx, Some(second), 1_000).unwrap();
+
+        let preview = |ctx: &Vec<u8>, index: u16| {
+            RETURN_DATA.with(|r| r.borrow_mut().clear());
+            let preview = ix::on_market(ix::quote_preview(&PROGRAM_ID, &Pubkey::new_unique(), None, 100_000_000, 10), index);
+            run_client_ix(&preview, vec![ctx.clone()]).0.map(|()| RETURN_DATA.with(|r| read_i128(&r.borrow(), QUOTE_NEW_INVENTORY_OFF)))
+        };
+        assert_eq!(preview(&ctx, 0), Ok(10));
+        assert_eq!(preview(&ctx, 1), Ok(1_010));
+        assert_eq!(preview(&ctx, 2), Err(MatcherError::UnknownMarket.into()));
+        assert_eq!(preview(&init_bound(first), 1), Err(MatcherError::UnknownMarket.into()));
+
+        let settle = ix::on_market(ix::settle_inventory(&PROGRAM_ID, &LP, &Pubkey::new_unique(), SETTLE_ADJUST, -600), 1);
+        let (result, accounts) = run_client_ix(&settle, vec![vec![], ctx]);
+        result.unwrap();
+        assert_eq!(read_i128(&accounts[1].data, market_off(1, MKT_INVENTORY_OFF)), 400);
+        assert_eq!(read_i128(&accounts[1].data, market_off(0, MKT_INVENTORY_OFF)), 0);
+    }
+
+    #[test]
+    fn test_transfer_inventory_between_books_on_one_slab() {
+        use provenance_client::instruction as ix;
+
+        set_slot(1_000);
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut shared = init_shared(first);
+        run_set_market(&mut shared, second, 0, 500).unwrap();
+        let mut bound = init_bound(second);
+        write_i128(&mut bound, CTX_BASE + CTX_INVENTORY_OFF, 300);
+
+        let (from, to) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let transfer = |bound: &mut Vec<u8>, shared: &mut Vec<u8>, amount: i128, to_market: u16| {
+            let ix = ix::on_market(ix::on_market(ix::transfer_inventory(&PROGRAM_ID, &LP, &from, &LP, &to, amount), 0), to_market);
+            let (result, accounts) = run_client_ix(&ix, vec![vec![], bound.clone(), vec![], shared.clone()]);
+            (*bound, *shared) = (accounts[1].data.clone(), accounts[3].data.clone());
+            result
+        };
+        transfer(&mut bound, &mut shared, 200, 1).unwrap();
+        assert_eq!(read_i128(&bound, CTX_BASE + CTX_INVENTORY_OFF), 100);
+        assert_eq!(read_i128(&shared, market_off(1, MKT_INVENTORY_OFF)), 200);
+        assert_eq!(read_i128(&shared, market_off(0, MKT_INVENTORY_OFF)), 0);
+        // A negative amount moves it back
+        transfer(&mut bound, &mut shared, -50, 1).unwrap();
+        assert_eq!(read_i128(&bound, CTX_BASE + CTX_INVENTORY_OFF), 150);
+
+        // Nothing moves past the destination's cap, or to another slab's book
+        let before = (bound.clone(), shared.clone());
+        assert_eq!(transfer(&mut bound, &mut shared, 400, 1), Err(MatcherError::InventoryLimit.into()));
+        assert_eq!(transfer(&mut bound, &mut shared, 10, 0), Err(MatcherError::SlabMismatch.into()));
+        assert_eq!(transfer(&mut bound, &mut shared, 10, 2), Err(MatcherError::UnknownMarket.into()));
+        assert_eq!((bound.clone(), shared.clone()), before);
+
+        // Both authorities sign, and a context can't trade with itself
+        let mut ix = ix::on_market(ix::on_market(ix::transfer_inventory(&PROGRAM_ID, &LP, &from, &Pubkey::new_unique(), &to, 10), 0), 1);
+        ix.accounts[2].is_signer = false;
+        let (result, _) = run_client_ix(&ix, vec![vec![], bound.clone(), vec![], shared.clone()]);
+        assert_eq!(result, Err(ProgramError::MissingRequiredSignature));
+        let ix = ix::transfer_inventory(&PROGRAM_ID, &LP, &from, &LP, &from, 10);
+        let (result, _) = run_client_ix(&ix, vec![vec![], bound.clone(), vec![], bound]);
+        assert_eq!(result, Err(MatcherError::DuplicateAccount.into()));
+    }
+
+    #[test]
+    fn test_sync_inventory_reads_the_lp_position() {
+        use provenance_client::test_support::SlabAccount;
+
+        let (ctx_key, slab) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let lp = SlabAccount {
+            index: 3,
+            kind: ACCT_KIND_LP,
+            position_size: -700,
+            matcher_program: PROGRAM_ID,
+            matcher_context: ctx_key,
+        };
+        let fixture = SlabFixture { lp: Some(lp), len: SLAB_ACCOUNTS_OFF + 4 * SLAB_ACCOUNT_LEN, ..Default::default() };
+        let sync = |ctx: &mut Vec<u8>, slab: Pubkey, fixture: SlabFixture, lp_idx: u16| {
+            let ix = provenance_client::instruction::sync_inventory(&PROGRAM_ID, &ctx_key, &slab, lp_idx);
+            let mut accounts = [TestAccount::new(ctx_key, false, 0, ctx.clone()), slab_account(slab, fixture.to_bytes())];
+            let result = run_accounts(&mut accounts, &ix.data);
+            *ctx = accounts[0].data.clone();
+            result
+        };
+
+        // The LP is short what takers bought, so the book is long the opposite
+        let mut ctx = init_bound(slab);
+        write_i128(&mut ctx, CTX_BASE + CTX_INVENTORY_OFF, 650);
+        sync(&mut ctx, slab, fixture, 3).unwrap();
+        assert_eq!(read_i128(&ctx, CTX_BASE + CTX_INVENTORY_OFF), 700);
+
+        // Only an LP routed through this context, at an index the slab has
+        let before = ctx.clone();
+        let user = SlabFixture { lp: Some(SlabAccount { kind: 0, ..lp }), ..fixture };
+        assert_eq!(sync(&mut ctx, slab, user, 3), Err(MatcherError::LpAccountMismatch.into()));
+        let other = SlabFixture { lp: Some(SlabAccount { matcher_context: Pubkey::new_unique(), ..lp }), ..fixture };
+        assert_eq!(sync(&mut ctx, slab, other, 3), Err(MatcherError::LpAccountMismatch.into()));
+        assert_eq!(sync(&mut ctx, slab, fixture, 2), Err(MatcherError::LpAccountMismatch.into()));
+        assert_eq!(sync(&mut ctx, slab, fixture, 4), Err(ProgramError::AccountDataTooSmall));
+        assert_eq!(sync(&mut ctx, Pubkey::new_unique(), fixture, 3), Err(MatcherError::SlabMismatch.into()));
+        assert_eq!(ctx, before);
+
+        // An unbound context reads only the percolator program's slabs
+        let mut unbound = vec![0u8; CTX_LEN];
+        run_init(&mut unbound, &init_data(0)).unwrap();
+        assert_eq!(sync(&mut unbound, slab, fixture, 3), Err(MatcherError::SlabOwnerMismatch.into()));
+        let mut accounts = [
+            TestAccount::new(ctx_key, false, 0, unbound.clone()),
+            TestAccount { owner: PERCOLATOR_PROGRAM_ID, ..TestAccount::new(slab, false, 0, fixture.to_bytes()) },
+        ];
+        run_accounts(&mut accounts, &provenance_client::instruction::sync_inventory(&PROGRAM_ID, &ctx_key, &slab, 3).data).unwrap();
+        assert_eq!(read_i128(&accounts[0].data, CTX_BASE + CTX_INVENTORY_OFF), 700);
+
+        // A shared context syncs the slab's own market
+        let mut shared = init_shared(Pubkey::new_unique());
+        run_set_market(&mut shared, slab, 0, 500).unwrap();
+        sync(&mut shared, slab, SlabFixture { lp: Some(SlabAccount { position_size: 250, ..lp }), ..fixture }, 3).unwrap();
+        assert_eq!(read_i128(&shared, market_off(1, MKT_INVENTORY_OFF)), -250);
+        assert_eq!(read_i128(&shared, market_off(0, MKT_INVENTORY_OFF)), 0);
+    }
+
+    #[test]
+    fn test_shared_context_refuses_stats_and_rfq() {
+        let ctx = init_shared(Pubkey::new_unique());
+        assert_eq!(run_set_rfq_signer(ctx.clone(), &RFQ_SIGNER), Err(MatcherError::SharedContext.into()));
+        let init_stats = provenance_client::instruction::init_stats(&PROGRAM_ID, &LP, &Pubkey::new_unique(), &Pubkey::new_unique());
+        let (result, _) = run_client_ix(&init_stats, vec![vec![], ctx, vec![0u8; STATS_LEN]]);
+        assert_eq!(result, Err(MatcherError::SharedContext.into()));
+    }
+
+    #[test]
+    fn test_impact_saturates() {
+        assert_eq!(impact_bps(IMPACT_LINEAR, u32::MAX as u64, u128::MAX, 1), u64::MAX);
+        assert_eq!(impact_bps(IMPACT_SQRT, u32::MAX as u64, u128::MAX, 1), u64::MAX);
     }
 }
@@ -0,0 +1,1806 @@
+//! Machine-readable interface of the credibility matcher, and the TypeScript
+//! bindings generated from it.
+//!
+//! The tables below are written against `provenance-client`'s layout
+//! constants, and errors come straight from the program's `MatcherError`. The
+//! tests run the client's builders and codecs through the tables, so the IDL
+//! can't describe bytes the program doesn't use. `cargo run -p provenance-idl`
+//! rewrites the checked-in `idl/credibility_matcher.json`,
+//! `ui/src/generated/matcher.ts` and the quote's golden vectors in
+//! `idl/quote_vectors.json` ([`vectors`]); a test fails while any is stale.
+
+mod typescript;
+pub mod vectors;
+
+use credibility_matcher::error::MatcherError;
+use provenance_client::layout::*;
+use provenance_core::TIER_NAMES;
+use serde_json::{json, Value};
+
+pub use typescript::typescript;
+pub use vectors::render_vectors;
+
+/// Field types. Integers are little-endian.
+#[derive(Clone, Copy, Debug)]
+pub enum Ty {
+    U8,
+    U16,
+    U32,
+    U64,
+    I64,
+    U128,
+    I128,
+    /// One byte, non-zero is true.
+    Bool,
+    Pubkey,
+    /// Opaque bytes.
+    Bytes(usize),
+    Array(&'static Ty, usize),
+    /// One of [`TYPES`], by name.
+    Defined(&'static str),
+}
+
+impl Ty {
+    pub fn size(&self) -> usize {
+        match self {
+            Ty::U8 | Ty::Bool => 1,
+            Ty::U16 => 2,
+            Ty::U32 => 4,
+            Ty::U64 | Ty::I64 => 8,
+            Ty::U128 | Ty::I128 => 16,
+            Ty::Pubkey => 32,
+            Ty::Bytes(len) => *len,
+            Ty::Array(ty, len) => ty.size() * len,
+            Ty::Defined(name) => type_def(name).size,
+        }
+    }
+
+    fn to_json(self) -> Value {
+        match self {
+            Ty::U8 => "u8".into(),
+            Ty::U16 => "u16".into(),
+            Ty::U32 => "u32".into(),
+            Ty::U64 => "u64".into(),
+            Ty::I64 => "i64".into(),
+            Ty::U128 => "u128".into(),
+            Ty::I128 => "i128".into(),
+            Ty::Bool => "bool".into(),
+            Ty::Pubkey => "pubkey".into(),
+            Ty::Bytes(len) => json!({ "bytes": len }),
+            Ty::Array(ty, len) => json!({ "array": [ty.to_json(), len] }),
+            Ty::Defined(name) => json!({ "defined": name }),
+        }
+    }
+}
+
+/// A field at a byte offset from the start of its struct.
+#[derive(Clone, Copy, Debug)]
+pub struct Field {
+    pub name: &'static str,
+    pub ty: Ty,
+    pub offset: usize,
+    /// Present only when the data is long enough to hold it.
+    pub optional: bool,
+}
+
+const fn field(name: &'static str, ty: Ty, offset: usize) -> Field {
+    Field { name, ty, offset, optional: false }
+}
+
+const fn optional(name: &'static str, ty: Ty, offset: usize) -> Field {
+    Field { name, ty, offset, optional: true }
+}
+
+/// A fixed-layout struct; unlisted bytes are padding or reserved.
+#[derive(Debug)]
+pub struct TypeDef {
+    pub name: &'static str,
+    pub docs: &'static str,
+    pub size: usize,
+    pub fields: &'static [Field],
+}
+
+/// A program-owned account: a [`TypeDef`] identified by a magic number.
+#[derive(Debug)]
+pub struct AccountDef {
+    pub ty: &'static str,
+    pub magic_offset: usize,
+    pub magic: u64,
+    /// Smallest valid account, when older versions are shorter.
+    pub min_size: usize,
+}
+
+/// One account of an instruction, in order.
+#[derive(Debug)]
+pub struct AccountMetaDef {
+    pub name: &'static str,
+    pub writable: bool,
+    pub signer: bool,
+    /// Optional accounts are positional: one may only be passed after every
+    /// optional account before it.
+    pub optional: bool,
+    /// Fixed address, for sysvars and programs.
+    pub address: Option<&'static str>,
+}
+
+const fn meta(name: &'static str, writable: bool, signer: bool) -> AccountMetaDef {
+    AccountMetaDef { name, writable, signer, optional: false, address: None }
+}
+
+const fn optional_meta(name: &'static str, writable: bool) -> AccountMetaDef {
+    AccountMetaDef { name, writable, signer: false, optional: true, address: None }
+}
+
+const fn optional_signer(name: &'static str) -> AccountMetaDef {
+    AccountMetaDef { name, writable: false, signer: true, optional: true, address: None }
+}
+
+const fn fixed_meta(name: &'static str, address: &'static str) -> AccountMetaDef {
+    AccountMetaDef { name, writable: false, signer: false, optional: false, address: Some(address) }
+}
+
+#[derive(Debug)]
+pub struct InstructionDef {
+    pub name: &'static str,
+    pub docs: &'static str,
+    /// Leading bytes of the data: the tag, then any fixed selector.
+    pub discriminator: &'static [u8],
+    pub accounts: &'static [AccountMetaDef],
+    /// Offsets are into the instruction data. Only the last arg can be
+    /// optional; leaving it out shortens the data.
+    pub args: &'static [Field],
+    /// Full data length; the program zero-pads up to it where it is longer
+    /// than the args.
+    pub data_len: usize,
+    /// Return data, as a [`TYPES`] name.
+    pub returns: Option<&'static str>,
+}
+
+/// A named value, typed for the bindings.
+#[derive(Debug)]
+pub struct Constant {
+    pub name: &'static str,
+    pub ty: Ty,
+    pub value: u64,
+}
+
+const CLOCK_SYSVAR: &str = "SysvarC1ock11111111111111111111111111111111";
+const SYSTEM_PROGRAM: &str = "11111111111111111111111111111111";
+const INSTRUCTIONS_SYSVAR: &str = "Sysvar1nstructions1111111111111111111111111";
+
+pub const TYPES: &[TypeDef] = &[
+    TypeDef {
+        name: "MatcherParams",
+        docs: "Parameter block of Init, UpdateParams and ProposeParams. The first PARAMS_LEN bytes are required; missing trailing fields read as zero.",
+        size: PARAMS_MAX_LEN,
+        fields: &[
+            field("base_fee_bps", Ty::U32, 0),
+            field("min_spread_bps", Ty::U32, 4),
+            field("max_spread_bps", Ty::U32, 8),
+            field("imbalance_k_bps", Ty::U32, 12),
+            field("liquidity_e6", Ty::U128, 16),
+            field("max_fill", Ty::U128, 32),
+            field("max_inventory", Ty::U128, 48),
+            field("age_halflife", Ty::U32, 64),
+            field("insurance_weight_bps", Ty::U32, 68),
+            field("impact_mode", Ty::U8, 72),
+            field("impact_k_bps", Ty::U32, 73),
+            field("skew_k_bps", Ty::U32, 77),
+            field("coverage_curve", Ty::U8, 81),
+            field("curve_points", Ty::Array(&Ty::U16, CURVE_POINTS), 82),
+            field("liq_halflife", Ty::U32, 90),
+            field("funding_horizon", Ty::U32, 94),
+            field("oracle_jump_bps", Ty::U16, 98),
+            field("oracle_jump_window", Ty::U32, 100),
+            field("flow_k_bps", Ty::U32, 104),
+            field("twap_window_slots", Ty::U32, 108),
+            field("signal_weights", Ty::Array(&Ty::U16, SIGNALS), 112),
+            field("deficit_halflife", Ty::U32, 120),
+            field("liq_auction_bps", Ty::U16, 124),
+            field("liq_auction_slots", Ty::U32, 126),
+            field("peg_band_e6", Ty::U32, 130),
+            field("spot_weight_bps", Ty::U16, 134),
+        ],
+    },
+    TypeDef {
+        name: "MatcherReturn",
+        docs: "Result of a Match: the first RET_LEN bytes of the context account and of Match's return data.",
+        size: RET_LEN,
+        fields: &[
+            field("abi_version", Ty::U32, RET_ABI_VERSION_OFF),
+            field("flags", Ty::U32, RET_FLAGS_OFF),
+            field("exec_price_e6", Ty::U64, RET_EXEC_PRICE_OFF),
+            field("exec_size", Ty::I128, RET_EXEC_SIZE_OFF),
+            field("req_id", Ty::U64, RET_REQ_ID_OFF),
+            field("lp_account_id", Ty::U64, RET_LP_ACCOUNT_ID_OFF),
+            field("oracle_price_e6", Ty::U64, RET_ORACLE_ECHO_OFF),
+            field("reject_reason", Ty::U64, RET_REJECT_REASON_OFF),
+        ],
+    },
+    TypeDef {
+        name: "QuoteBreakdown",
+        docs: "How a quote was priced: QuotePreview's return data, also embedded in Match and QuoteTwoSided returns.",
+        size: QUOTE_LEN,
+        fields: &[
+            field("exec_price_e6", Ty::U64, QUOTE_EXEC_PRICE_OFF),
+            field("coverage_bps", Ty::U64, QUOTE_COVERAGE_OFF),
+            field("tier", Ty::U64, QUOTE_TIER_OFF),
+            field("fill_pct", Ty::U64, QUOTE_FILL_PCT_OFF),
+            field("tier_spread_bps", Ty::U64, QUOTE_TIER_SPREAD_OFF),
+            field("imbalance_bps", Ty::U64, QUOTE_IMBALANCE_OFF),
+            field("impact_bps", Ty::U64, QUOTE_IMPACT_OFF),
+            field("stale_bps", Ty::U64, QUOTE_STALE_OFF),
+            field("crank_bps", Ty::U64, QUOTE_CRANK_OFF),
+            field("liq_bps", Ty::U64, QUOTE_LIQ_OFF),
+            field("spread_bps", Ty::U64, QUOTE_SPREAD_OFF),
+            field("skew_bps", Ty::I64, QUOTE_SKEW_OFF),
+            field("funding_skew_bps", Ty::I64, QUOTE_FUNDING_SKEW_OFF),
+            field("fee_bps", Ty::U64, QUOTE_FEE_OFF),
+            field("new_inventory", Ty::I128, QUOTE_NEW_INVENTORY_OFF),
+            field("fill_size", Ty::I128, QUOTE_FILL_SIZE_OFF),
+            field("discount_bps", Ty::U64, QUOTE_DISCOUNT_OFF),
+            field("flow_bps", Ty::U64, QUOTE_FLOW_OFF),
+            field("anchor_price_e6", Ty::U64, QUOTE_ANCHOR_OFF),
+            field("signal_bps", Ty::U64, QUOTE_SIGNAL_OFF),
+            field("schedule_bps", Ty::U64, QUOTE_SCHEDULE_OFF),
+            field("oracle_bps", Ty::U64, QUOTE_ORACLE_OFF),
+            field("rebate_bps", Ty::U64, QUOTE_REBATE_OFF),
+        ],
+    },
+    TypeDef {
+        name: "MatchReturn",
+        docs: "Match's return data. hedge_size is what an off-chain hedger should trade, positive to buy, to bring the book's inventory back within the schedule's soft_inventory (all of it without a band); the LP is short a positive inventory, so it buys. 0 on a soft reject.",
+        size: MATCH_RET_LEN,
+        fields: &[
+            field("result", Ty::Defined("MatcherReturn"), 0),
+            field("quote", Ty::Defined("QuoteBreakdown"), MATCH_RET_BREAKDOWN_OFF),
+            field("fill_seq", Ty::U64, MATCH_RET_FILL_SEQ_OFF),
+            field("hedge_size", Ty::I128, MATCH_RET_HEDGE_OFF),
+        ],
+    },
+    TypeDef {
+        name: "TwoSidedQuote",
+        docs: "QuoteTwoSided's return data. A side with a non-zero reject reason (a MatcherError code) has a zeroed quote.",
+        size: TWO_SIDED_LEN,
+        fields: &[
+            field("bid_reject_reason", Ty::U64, TWO_SIDED_BID_REASON_OFF),
+            field("ask_reject_reason", Ty::U64, TWO_SIDED_ASK_REASON_OFF),
+            field("bid", Ty::Defined("QuoteBreakdown"), TWO_SIDED_BID_OFF),
+            field("ask", Ty::Defined("QuoteBreakdown"), TWO_SIDED_ASK_OFF),
+        ],
+    },
+    TypeDef {
+        name: "ContextCore",
+        docs: "The v5 context body at CTX_BASE.",
+        size: CTX_LEN - CTX_BASE,
+        fields: &[
+            field("magic", Ty::U64, CTX_MAGIC_OFF),
+            field("version", Ty::U32, CTX_VERSION_OFF),
+            field("kind", Ty::U8, CTX_KIND_OFF),
+            field("oracle_decimals", Ty::U8, CTX_ORACLE_DECIMALS_OFF),
+            field("spot_weight_bps", Ty::U16, CTX_SPOT_WEIGHT_OFF),
+            field("lp_pda", Ty::Pubkey, CTX_LP_PDA_OFF),
+            field("base_fee_bps", Ty::U32, CTX_BASE_FEE_OFF),
+            field("min_spread_bps", Ty::U32, CTX_MIN_SPREAD_OFF),
+            field("max_spread_bps", Ty::U32, CTX_MAX_SPREAD_OFF),
+            field("imbalance_k_bps", Ty::U32, CTX_IMBALANCE_K_OFF),
+            field("liquidity_e6", Ty::U128, CTX_LIQUIDITY_OFF),
+            field("max_fill", Ty::U128, CTX_MAX_FILL_OFF),
+            field("inventory", Ty::I128, CTX_INVENTORY_OFF),
+            field("last_oracle_price_e6", Ty::U64, CTX_LAST_ORACLE_OFF),
+            field("last_exec_price_e6", Ty::U64, CTX_LAST_EXEC_OFF),
+            field("max_inventory", Ty::U128, CTX_MAX_INVENTORY_OFF),
+            field("insurance_snapshot", Ty::U128, CTX_INSURANCE_OFF),
+            field("total_oi_snapshot", Ty::U128, CTX_TOTAL_OI_OFF),
+            field("market_age", Ty::U64, CTX_MARKET_AGE_OFF),
+            field("last_deficit", Ty::U64, CTX_LAST_DEFICIT_OFF),
+            field("snapshot_slot", Ty::U64, CTX_SNAPSHOT_SLOT_OFF),
+            field("age_halflife", Ty::U32, CTX_AGE_HALFLIFE_OFF),
+            field("insurance_weight_bps", Ty::U32, CTX_INSURANCE_WEIGHT_OFF),
+            field("impact_mode", Ty::U8, CTX_IMPACT_MODE_OFF),
+            field("coverage_curve", Ty::U8, CTX_COVERAGE_CURVE_OFF),
+            field("flags", Ty::U8, CTX_FLAGS_OFF),
+            field("impact_k_bps", Ty::U32, CTX_IMPACT_K_OFF),
+            field("skew_k_bps", Ty::U32, CTX_SKEW_K_OFF),
+            field("curve_points", Ty::Array(&Ty::U16, CURVE_POINTS), CTX_CURVE_POINTS_OFF),
+            field("peg_band_e6", Ty::U32, CTX_PEG_BAND_OFF),
+            field("last_crank_slot", Ty::U64, CTX_LAST_CRANK_OFF),
+            field("last_liquidations", Ty::U64, CTX_LAST_LIQS_OFF),
+            field("liq_heat", Ty::U32, CTX_LIQ_HEAT_OFF),
+            field("liq_halflife", Ty::U32, CTX_LIQ_HALFLIFE_OFF),
+        ],
+    },
+    TypeDef {
+        name: "ContextExtension",
+        docs: "The v6 extension at EXT_BASE. A queued proposal is split: its first PENDING_HEAD_LEN bytes are pending_params_head, the rest pending_params_tail.",
+        size: EXT_LEN,
+        fields: &[
+            field("funding_rate_bps_per_slot", Ty::I64, EXT_FUNDING_RATE_OFF),
+            field("funding_horizon", Ty::U32, EXT_FUNDING_HORIZON_OFF),
+            field("liq_auction_slots", Ty::U32, EXT_LIQ_AUCTION_SLOTS_OFF),
+            field("slab", Ty::Pubkey, EXT_SLAB_OFF),
+            field("pending_activation_slot", Ty::U64, EXT_PENDING_SLOT_OFF),
+            field("pending_params_head", Ty::Bytes(PENDING_HEAD_LEN), EXT_PENDING_PARAMS_OFF),
+            field("authority", Ty::Pubkey, EXT_AUTHORITY_OFF),
+            field("pending_authority", Ty::Pubkey, EXT_PENDING_AUTHORITY_OFF),
+            field("percolator_program", Ty::Pubkey, EXT_PERCOLATOR_OFF),
+            field("oracle_jump_bps", Ty::U16, EXT_ORACLE_JUMP_BPS_OFF),
+            field("liq_auction_bps", Ty::U16, EXT_LIQ_AUCTION_BPS_OFF),
+            field("oracle_jump_window", Ty::U32, EXT_ORACLE_JUMP_WINDOW_OFF),
+            field("last_oracle_slot", Ty::U64, EXT_LAST_ORACLE_SLOT_OFF),
+            field("fill_seq", Ty::U64, EXT_FILL_SEQ_OFF),
+            field("cum_notional", Ty::U128, EXT_CUM_NOTIONAL_OFF),
+            field("cum_fees", Ty::U128, EXT_CUM_FEES_OFF),
+            field("gross_long", Ty::U128, EXT_GROSS_LONG_OFF),
+            field("gross_short", Ty::U128, EXT_GROSS_SHORT_OFF),
+            field("flow_k_bps", Ty::U32, EXT_FLOW_K_OFF),
+            field("twap_window_slots", Ty::U32, EXT_TWAP_WINDOW_OFF),
+            field("pending_params_tail", Ty::Bytes(PARAMS_MAX_LEN - PENDING_HEAD_LEN), EXT_PENDING_PARAMS_TAIL_OFF),
+            field("stats", Ty::Pubkey, EXT_STATS_OFF),
+            field("signal_weights", Ty::Array(&Ty::U16, SIGNALS), EXT_SIGNAL_WEIGHTS_OFF),
+            field("deficit_halflife", Ty::U32, EXT_DEFICIT_HALFLIFE_OFF),
+            field("market_slots", Ty::U32, EXT_MARKET_SLOTS_OFF),
+            field("rfq_signer", Ty::Pubkey, EXT_RFQ_SIGNER_OFF),
+            field("rfq_price_e6", Ty::U64, EXT_RFQ_PRICE_OFF),
+            field("rfq_expiry_slot", Ty::U64, EXT_RFQ_EXPIRY_OFF),
+            field("rfq_nonce", Ty::U64, EXT_RFQ_NONCE_OFF),
+            field("rfq_size_left", Ty::I128, EXT_RFQ_SIZE_OFF),
+            field("last_liq_slot", Ty::U64, EXT_LAST_LIQ_SLOT_OFF),
+        ],
+    },
+    TypeDef {
+        name: "MarketBook",
+        docs: "One market of a shared context: its slab (the default key on a free entry), caps that override the context's when non-zero, and the inventory and snapshots a single-market context keeps in its core and extension.",
+        size: MARKET_LEN,
+        fields: &[
+            field("slab", Ty::Pubkey, MKT_SLAB_OFF),
+            field("max_fill", Ty::U128, MKT_MAX_FILL_OFF),
+            field("max_inventory", Ty::U128, MKT_MAX_INVENTORY_OFF),
+            field("inventory", Ty::I128, MKT_INVENTORY_OFF),
+            field("insurance_snapshot", Ty::U128, MKT_INSURANCE_OFF),
+            field("total_oi_snapshot", Ty::U128, MKT_TOTAL_OI_OFF),
+            field("gross_long", Ty::U128, MKT_GROSS_LONG_OFF),
+            field("gross_short", Ty::U128, MKT_GROSS_SHORT_OFF),
+            field("snapshot_slot", Ty::U64, MKT_SNAPSHOT_SLOT_OFF),
+            field("last_crank_slot", Ty::U64, MKT_LAST_CRANK_OFF),
+            field("last_liquidations", Ty::U64, MKT_LAST_LIQS_OFF),
+            field("funding_rate_bps_per_slot", Ty::I64, MKT_FUNDING_RATE_OFF),
+            field("market_age", Ty::U64, MKT_MARKET_AGE_OFF),
+            field("last_deficit", Ty::U64, MKT_LAST_DEFICIT_OFF),
+            field("last_liq_slot", Ty::U64, MKT_LAST_LIQ_SLOT_OFF),
+            field("last_oracle_price_e6", Ty::U64, MKT_LAST_ORACLE_OFF),
+            field("last_oracle_slot", Ty::U64, MKT_LAST_ORACLE_SLOT_OFF),
+            field("last_exec_price_e6", Ty::U64, MKT_LAST_EXEC_OFF),
+            field("liq_heat", Ty::U32, MKT_LIQ_HEAT_OFF),
+        ],
+    },
+    TypeDef {
+        name: "Creation",
+        docs: "The record of a v6 context: the slot and the key that signed Init (the authority it set, or the LP PDA), and derived when CreateContext made the account, at the address the bump derives. It follows the layout, at CTX_EXT_LEN or CTX_SHARED_LEN, in any account created CREATION_LEN bytes longer, derived or not; others have none.",
+        size: CREATION_LEN,
+        fields: &[
+            field("slot", Ty::U64, CREATION_SLOT_OFF),
+            field("creator", Ty::Pubkey, CREATION_CREATOR_OFF),
+            field("derived", Ty::Bool, CREATION_DERIVED_OFF),
+        ],
+    },
+    TypeDef {
+        name: "MatcherContext",
+        docs: "A context account: CTX_LEN bytes at v5, CTX_EXT_LEN at v6, CTX_SHARED_LEN for a shared context with a market table (extension.market_slots non-zero). authority and slab are the default key when unset; the LP PDA administers a context without an authority. A Creation record may follow the layout.",
+        size: CTX_SHARED_LEN,
+        fields: &[
+            field("last_return", Ty::Defined("MatcherReturn"), 0),
+            field("core", Ty::Defined("ContextCore"), CTX_BASE),
+            optional("extension", Ty::Defined("ContextExtension"), EXT_BASE),
+            optional("markets", Ty::Array(&Ty::Defined("MarketBook"), MAX_MARKETS), MARKETS_BASE),
+        ],
+    },
+    TypeDef {
+        name: "RfqQuote",
+        docs: "A quote signed off-chain by the context's RFQ signer; these bytes are the signed message. size is signed like a trade: positive when takers may buy.",
+        size: RFQ_MSG_LEN,
+        fields: &[
+            field("ctx", Ty::Pubkey, RFQ_CTX_OFF),
+            field("price_e6", Ty::U64, RFQ_PRICE_OFF),
+            field("size", Ty::I128, RFQ_SIZE_OFF),
+            field("expiry_slot", Ty::U64, RFQ_EXPIRY_OFF),
+            field("nonce", Ty::U64, RFQ_NONCE_OFF),
+        ],
+    },
+    TypeDef {
+        name: "StatsHeader",
+        docs: "Stats account header. head and oracle_head are the next ring slots to write; count and oracle_count saturate at the ring capacity.",
+        size: STATS_HEADER_LEN,
+        fields: &[
+            field("magic", Ty::U64, STATS_MAGIC_OFF),
+            field("version", Ty::U32, STATS_VERSION_OFF),
+            field("capacity", Ty::U32, STATS_CAPACITY_OFF),
+            field("ctx", Ty::Pubkey, STATS_CTX_OFF),
+            field("head", Ty::U32, STATS_HEAD_OFF),
+            field("count", Ty::U32, STATS_COUNT_OFF),
+            field("oracle_head", Ty::U32, STATS_ORACLE_HEAD_OFF),
+            field("oracle_count", Ty::U32, STATS_ORACLE_COUNT_OFF),
+        ],
+    },
+    TypeDef {
+        name: "FillRecord",
+        docs: "One fill in the stats ring.",
+        size: FILL_LEN,
+        fields: &[
+            field("fill_seq", Ty::U64, FILL_SEQ_OFF),
+            field("slot", Ty::U64, FILL_SLOT_OFF),
+            field("exec_size", Ty::I128, FILL_SIZE_OFF),
+            field("exec_price_e6", Ty::U64, FILL_PRICE_OFF),
+            field("spread_bps", Ty::U64, FILL_SPREAD_OFF),
+        ],
+    },
+    TypeDef {
+        name: "OracleSample",
+        docs: "One oracle observation in the stats ring, as TWAP anchoring reads it.",
+        size: ORACLE_SAMPLE_LEN,
+        fields: &[field("slot", Ty::U64, ORACLE_SLOT_OFF), field("oracle_price_e6", Ty::U64, ORACLE_PRICE_OFF)],
+    },
+    TypeDef {
+        name: "Stats",
+        docs: "A stats account bound to one context by InitStats: STATS_LEN bytes, STATS_LEDGER_LEN with a fee ledger of the withdrawals RecordWithdrawal recorded against the context's cum_fees, STATS_SKIM_LEN with the insurance skim: skim_bps of each fill's spread accrued for the insurance fund and what ClaimSkim paid in, STATS_PROTOCOL_LEN with the protocol fee: the treasury's protocol_fee_bps of each fill's fee, kept out of what RecordWithdrawal allows the LP, and what SweepProtocolFee recorded it taking, or STATS_PNL_LEN with the book marked to the oracle on each fill: the average entry price, the mark, and the realized and unrealized PnL in quote units.",
+        size: STATS_PNL_LEN,
+        fields: &[
+            field("header", Ty::Defined("StatsHeader"), 0),
+            field("fills", Ty::Array(&Ty::Defined("FillRecord"), STATS_CAPACITY), STATS_HEADER_LEN),
+            field("oracle_samples", Ty::Array(&Ty::Defined("OracleSample"), ORACLE_CAPACITY), STATS_ORACLE_RING_OFF),
+            optional("withdrawn_fees", Ty::U128, STATS_WITHDRAWN_OFF),
+            optional("withdrawals", Ty::U64, STATS_WITHDRAWALS_OFF),
+            optional("last_withdrawal_slot", Ty::U64, STATS_LAST_WITHDRAWAL_OFF),
+            optional("skim_bps", Ty::U16, STATS_SKIM_BPS_OFF),
+            optional("skim_accrued", Ty::U128, STATS_SKIM_ACCRUED_OFF),
+            optional("skim_claimed", Ty::U128, STATS_SKIM_CLAIMED_OFF),
+            optional("skim_claims", Ty::U64, STATS_SKIM_CLAIMS_OFF),
+            optional("treasury", Ty::Pubkey, STATS_TREASURY_OFF),
+            optional("protocol_fee_bps", Ty::U16, STATS_PROTOCOL_FEE_BPS_OFF),
+            optional("protocol_accrued", Ty::U128, STATS_PROTOCOL_ACCRUED_OFF),
+            optional("protocol_swept", Ty::U128, STATS_PROTOCOL_SWEPT_OFF),
+            optional("protocol_sweeps", Ty::U64, STATS_PROTOCOL_SWEEPS_OFF),
+            optional("entry_price_e6", Ty::U64, STATS_PNL_ENTRY_OFF),
+            optional("mark_price_e6", Ty::U64, STATS_PNL_MARK_OFF),
+            optional("realized_pnl", Ty::I128, STATS_PNL_REALIZED_OFF),
+            optional("unrealized_pnl", Ty::I128, STATS_PNL_UNREALIZED_OFF),
+        ],
+    },
+    TypeDef {
+        name: "Signal",
+        docs: "A signal account, owned by any program: once SetSignals registers it, adds value_bps * weight_bps / 10000 to the spread of every trade, through stale_after_slot.",
+        size: SIGNAL_LEN,
+        fields: &[
+            field("magic", Ty::U64, SIGNAL_MAGIC_OFF),
+            field("value_bps", Ty::U32, SIGNAL_VALUE_OFF),
+            field("weight_bps", Ty::U16, SIGNAL_WEIGHT_OFF),
+            field("stale_after_slot", Ty::U64, SIGNAL_STALE_AFTER_OFF),
+        ],
+    },
+    TypeDef {
+        name: "ScheduleEntry",
+        docs: "One window of a spread schedule: from start_slot through end_slot, the spread is multiplied by multiplier_bps / 10000.",
+        size: SCHED_ENTRY_LEN,
+        fields: &[
+            field("start_slot", Ty::U64, SCHED_START_OFF),
+            field("end_slot", Ty::U64, SCHED_END_OFF),
+            field("multiplier_bps", Ty::U32, SCHED_MULT_OFF),
+        ],
+    },
+    TypeDef {
+        name: "ScheduleEntryArgs",
+        docs: "A ScheduleEntry as SetSchedule takes it, unpadded.",
+        size: SCHED_ARG_LEN,
+        fields: &[field("start_slot", Ty::U64, 0), field("end_slot", Ty::U64, 8), field("multiplier_bps", Ty::U32, 16)],
+    },
+    TypeDef {
+        name: "FeeTier",
+        docs: "One fee tier: a trade whose notional at the oracle is at least min_notional pays fee_bps in place of base_fee_bps. Unused tiers are zero.",
+        size: FEE_TIER_LEN,
+        fields: &[field("min_notional", Ty::U128, FEE_TIER_NOTIONAL_OFF), field("fee_bps", Ty::U32, FEE_TIER_FEE_OFF)],
+    },
+    TypeDef {
+        name: "LoyaltyTier",
+        docs: "One loyalty tier: a trader whose trader account has traded at least min_volume with the context gets discount_bps off the fee. Unused tiers are zero.",
+        size: LOYALTY_TIER_LEN,
+        fields: &[field("min_volume", Ty::U128, LOYALTY_TIER_VOLUME_OFF), field("discount_bps", Ty::U32, LOYALTY_TIER_DISCOUNT_OFF)],
+    },
+    TypeDef {
+        name: "LoyaltyTierArgs",
+        docs: "A LoyaltyTier as SetLoyaltyTiers takes it, unpadded.",
+        size: LOYALTY_TIER_ARG_LEN,
+        fields: &[field("min_volume", Ty::U128, 0), field("discount_bps", Ty::U32, 16)],
+    },
+    TypeDef {
+        name: "FeeTierArgs",
+        docs: "A FeeTier as SetFeeTiers takes it, unpadded.",
+        size: FEE_TIER_ARG_LEN,
+        fields: &[field("min_notional", Ty::U128, 0), field("fee_bps", Ty::U32, 16)],
+    },
+    TypeDef {
+        name: "SignalEntry",
+        docs: "A signal account SetSignals registered, and the program that owned it then.",
+        size: SIGNAL_ENTRY_LEN,
+        fields: &[field("signal", Ty::Pubkey, SIGNAL_ENTRY_KEY_OFF), field("owner", Ty::Pubkey, SIGNAL_ENTRY_OWNER_OFF)],
+    },
+    TypeDef {
+        name: "Schedule",
+        docs: "A spread schedule bound to one context by InitSchedule: SCHEDULE_LEN bytes, SCHEDULE_FEES_LEN with fee tiers, SCHEDULE_REBATE_LEN with a rebate and fee discount, SCHEDULE_MIN_NOTIONAL_LEN with a minimum notional, SCHEDULE_LOYALTY_LEN with loyalty tiers, SCHEDULE_DECAY_LEN with an inventory halflife, SCHEDULE_BAND_LEN with an inventory band, SCHEDULE_CAPS_LEN with inventory caps, SCHEDULE_REDUCE_ONLY_LEN with a reduce-only threshold, SCHEDULE_DEPTH_CAP_LEN with a depth-relative cap, SCHEDULE_SIGNALS_LEN with a signal registry. The first count entries are live; where several cover a slot, the widest wins. Of the fee tiers a trade reaches, the largest sets its fee. Fills that shrink |inventory| get rebate_bps off their spread and fee; fee_discount_bps of the fee is waived at the full credibility discount. Trades whose notional at the oracle is below min_notional_e6 are refused. Of the loyalty tiers a trader account's volume reaches, the largest takes its discount_bps off the fee. The imbalance and skew penalties price the inventory as halving every inventory_halflife slots since the last fill. Past soft_inventory, fills that take |inventory| deeper pay up to band_k_bps more, growing with the square of the depth into the band. max_long_inventory and max_short_inventory cap the LP's long book (negative inventory) and short book (positive) in place of max_inventory, which a zero cap leaves. Past reduce_only_bps of the cap (AUTO_REDUCE_ONLY_BPS at 0), trades that grow |inventory| are soft-rejected. A non-zero max_inventory_bps sets max_inventory to that share of liquidity_notional_e6, in base units at the oracle. Every trade and quote passes the first signal_count signals, in order.",
+        size: SCHEDULE_SIGNALS_LEN,
+        fields: &[
+            field("magic", Ty::U64, SCHEDULE_MAGIC_OFF),
+            field("version", Ty::U32, SCHEDULE_VERSION_OFF),
+            field("count", Ty::U32, SCHEDULE_COUNT_OFF),
+            field("ctx", Ty::Pubkey, SCHEDULE_CTX_OFF),
+            field("entries", Ty::Array(&Ty::Defined("ScheduleEntry"), MAX_SCHEDULE_ENTRIES), SCHEDULE_HEADER_LEN),
+            optional("fee_tiers", Ty::Array(&Ty::Defined("FeeTier"), MAX_FEE_TIERS), FEE_TIERS_OFF),
+            optional("rebate_bps", Ty::U16, SCHEDULE_REBATE_OFF),
+            optional("fee_discount_bps", Ty::U16, SCHEDULE_FEE_DISCOUNT_OFF),
+            optional("min_notional_e6", Ty::U128, SCHEDULE_MIN_NOTIONAL_OFF),
+            optional("loyalty_tiers", Ty::Array(&Ty::Defined("LoyaltyTier"), MAX_LOYALTY_TIERS), LOYALTY_TIERS_OFF),
+            optional("inventory_halflife", Ty::U32, SCHEDULE_INVENTORY_HALFLIFE_OFF),
+            optional("soft_inventory", Ty::U128, SCHEDULE_SOFT_INVENTORY_OFF),
+            optional("band_k_bps", Ty::U32, SCHEDULE_BAND_K_OFF),
+            optional("max_long_inventory", Ty::U128, SCHEDULE_MAX_LONG_OFF),
+            optional("max_short_inventory", Ty::U128, SCHEDULE_MAX_SHORT_OFF),
+            optional("reduce_only_bps", Ty::U16, SCHEDULE_REDUCE_ONLY_BPS_OFF),
+            optional("max_inventory_bps", Ty::U32, SCHEDULE_MAX_INVENTORY_BPS_OFF),
+            optional("signal_count", Ty::U8, SCHEDULE_SIGNAL_COUNT_OFF),
+            optional("signals", Ty::Array(&Ty::Defined("SignalEntry"), MAX_SIGNAL_ACCOUNTS), SCHEDULE_SIGNALS_OFF),
+        ],
+    },
+    TypeDef {
+        name: "LadderLevel",
+        docs: "One ladder level: a single trade of size (after clipping) fills at exec_price_e6.",
+        size: LEVEL_LEN,
+        fields: &[
+            field("size", Ty::U128, LEVEL_SIZE_OFF),
+            field("exec_price_e6", Ty::U64, LEVEL_PRICE_OFF),
+            field("spread_bps", Ty::U64, LEVEL_SPREAD_OFF),
+        ],
+    },
+    TypeDef {
+        name: "Ladder",
+        docs: "A ladder bound to one context by InitLadder, as PublishLadder last priced it. The first bid_count bids and ask_count asks are live; a side cut short by the inventory carries the MatcherError code as its reason.",
+        size: LADDER_LEN,
+        fields: &[
+            field("magic", Ty::U64, LADDER_MAGIC_OFF),
+            field("version", Ty::U32, LADDER_VERSION_OFF),
+            field("market", Ty::U16, LADDER_MARKET_OFF),
+            field("ctx", Ty::Pubkey, LADDER_CTX_OFF),
+            field("slot", Ty::U64, LADDER_SLOT_OFF),
+            field("oracle_price_e6", Ty::U64, LADDER_ORACLE_OFF),
+            field("step_size", Ty::U128, LADDER_STEP_OFF),
+            field("bid_count", Ty::U32, LADDER_BID_COUNT_OFF),
+            field("ask_count", Ty::U32, LADDER_ASK_COUNT_OFF),
+            field("bid_reason", Ty::U32, LADDER_BID_REASON_OFF),
+            field("ask_reason", Ty::U32, LADDER_ASK_REASON_OFF),
+            field("bids", Ty::Array(&Ty::Defined("LadderLevel"), MAX_LADDER_LEVELS), LADDER_BIDS_OFF),
+            field("asks", Ty::Array(&Ty::Defined("LadderLevel"), MAX_LADDER_LEVELS), LADDER_ASKS_OFF),
+        ],
+    },
+    TypeDef {
+        name: "TipJar",
+        docs: "A tip jar bound to one context by InitTipJar. UpdateCredibility, passed it and a signing keeper, pays the keeper lamports_per_slot per slot the snapshot was stale past SNAPSHOT_GRACE_SLOTS, up to max_tip, out of the jar's lamports above rent exemption.",
+        size: TIP_JAR_LEN,
+        fields: &[
+            field("magic", Ty::U64, TIP_JAR_MAGIC_OFF),
+            field("version", Ty::U32, TIP_JAR_VERSION_OFF),
+            field("ctx", Ty::Pubkey, TIP_JAR_CTX_OFF),
+            field("lamports_per_slot", Ty::U64, TIP_JAR_PER_SLOT_OFF),
+            field("max_tip", Ty::U64, TIP_JAR_MAX_OFF),
+            field("paid", Ty::U64, TIP_JAR_PAID_OFF),
+            field("tips", Ty::U64, TIP_JAR_COUNT_OFF),
+        ],
+    },
+    TypeDef {
+        name: "Referrer",
+        docs: "What a referral account has credited one referrer: its share of the fees on the fills it referred (in the notional units of cum_fees) and how many there were.",
+        size: REFERRER_LEN,
+        fields: &[
+            field("referrer", Ty::Pubkey, REFERRER_KEY_OFF),
+            field("accrued_fees", Ty::U128, REFERRER_ACCRUED_OFF),
+            field("fills", Ty::U64, REFERRER_FILLS_OFF),
+        ],
+    },
+    TypeDef {
+        name: "Referrals",
+        docs: "A referral account bound to one context by InitReferral. A Match passed it and naming a referrer credits that referrer with share_bps of the fill's fee, in the first count entries; once all MAX_REFERRERS are taken, new referrers earn nothing.",
+        size: REFERRAL_LEN,
+        fields: &[
+            field("magic", Ty::U64, REFERRAL_MAGIC_OFF),
+            field("version", Ty::U32, REFERRAL_VERSION_OFF),
+            field("ctx", Ty::Pubkey, REFERRAL_CTX_OFF),
+            field("share_bps", Ty::U16, REFERRAL_SHARE_OFF),
+            field("count", Ty::U16, REFERRAL_COUNT_OFF),
+            field("referrers", Ty::Array(&Ty::Defined("Referrer"), MAX_REFERRERS), REFERRAL_HEADER_LEN),
+        ],
+    },
+    TypeDef {
+        name: "Trader",
+        docs: "A trader account bound to one context and one trader by InitTrader. Every Match passed it, with the trader signing, adds the fill's notional (at the exec price, in the units of cum_notional) to volume, which the schedule's loyalty tiers discount the fee by.",
+        size: TRADER_LEN,
+        fields: &[
+            field("magic", Ty::U64, TRADER_MAGIC_OFF),
+            field("version", Ty::U32, TRADER_VERSION_OFF),
+            field("ctx", Ty::Pubkey, TRADER_CTX_OFF),
+            field("trader", Ty::Pubkey, TRADER_KEY_OFF),
+            field("volume", Ty::U128, TRADER_VOLUME_OFF),
+            field("fills", Ty::U64, TRADER_FILLS_OFF),
+            field("last_slot", Ty::U64, TRADER_LAST_SLOT_OFF),
+        ],
+    },
+    TypeDef {
+        name: "FeedEntry",
+        docs: "One oracle feed: feed_id (a Pyth feed id, or a pinned Pyth price account's, Switchboard aggregator's or Chainlink feed's key) and the program publishing it (FEED_SOURCE_*).",
+        size: FEED_ENTRY_LEN,
+        fields: &[
+            field("feed_id", Ty::Bytes(32), FEED_ENTRY_ID_OFF),
+            field("source", Ty::U8, FEED_ENTRY_SOURCE_OFF),
+            field("pinned", Ty::Bool, FEED_ENTRY_PINNED_OFF),
+            field("penalty_bps", Ty::U16, FEED_ENTRY_PENALTY_OFF),
+        ],
+    },
+    TypeDef {
+        name: "Feed",
+        docs: "Oracle feeds bound to one context by InitFeed and AddFeed: Match prices off the median of the first count entries' prices, each published at most max_age_secs ago, and widens by the widest Pyth confidence interval. Feeds more than max_divergence_bps apart (0 = any) are refused, or with widen set quoted with the divergence added to the spread. A price posted more than max_age_slots slots ago (0 = any) is refused, or with slot_widen set quoted at max_spread_bps. With fallback set Match prices instead off the first entry with a fresh positive price, plus its penalty_bps.",
+        size: FEED_LEN,
+        fields: &[
+            field("magic", Ty::U64, FEED_MAGIC_OFF),
+            field("version", Ty::U32, FEED_VERSION_OFF),
+            field("max_age_secs", Ty::U32, FEED_MAX_AGE_OFF),
+            field("ctx", Ty::Pubkey, FEED_CTX_OFF),
+            field("count", Ty::U8, FEED_COUNT_OFF),
+            field("widen", Ty::Bool, FEED_WIDEN_OFF),
+            field("max_divergence_bps", Ty::U16, FEED_MAX_DIVERGENCE_OFF),
+            field("max_age_slots", Ty::U16, FEED_MAX_AGE_SLOTS_OFF),
+            field("slot_widen", Ty::Bool, FEED_SLOT_WIDEN_OFF),
+            field("fallback", Ty::Bool, FEED_FALLBACK_OFF),
+            field("entries", Ty::Array(&Ty::Defined("FeedEntry"), MAX_FEEDS), FEED_HEADER_LEN),
+        ],
+    },
+];
+
+pub const ACCOUNTS: &[AccountDef] = &[
+    AccountDef { ty: "MatcherContext", magic_offset: CTX_BASE + CTX_MAGIC_OFF, magic: MAGIC, min_size: CTX_LEN },
+    AccountDef { ty: "Stats", magic_offset: STATS_MAGIC_OFF, magic: STATS_MAGIC, min_size: STATS_LEN },
+    AccountDef { ty: "Signal", magic_offset: SIGNAL_MAGIC_OFF, magic: SIGNAL_MAGIC, min_size: SIGNAL_LEN },
+    AccountDef { ty: "Schedule", magic_offset: SCHEDULE_MAGIC_OFF, magic: SCHEDULE_MAGIC, min_size: SCHEDULE_LEN },
+    AccountDef { ty: "Ladder", magic_offset: LADDER_MAGIC_OFF, magic: LADDER_MAGIC, min_size: LADDER_LEN },
+    AccountDef { ty: "Feed", magic_offset: FEED_MAGIC_OFF, magic: FEED_MAGIC, min_size: FEED_LEN },
+    AccountDef { ty: "TipJar", magic_offset: TIP_JAR_MAGIC_OFF, magic: TIP_JAR_MAGIC, min_size: TIP_JAR_LEN },
+    AccountDef { ty: "Referrals", magic_offset: REFERRAL_MAGIC_OFF, magic: REFERRAL_MAGIC, min_size: REFERRAL_LEN },
+    AccountDef { ty: "Trader", magic_offset: TRADER_MAGIC_OFF, magic: TRADER_MAGIC, min_size: TRADER_LEN },
+];
+
+const AUTHORITY_ACCOUNTS: &[AccountMetaDef] = &[meta("authority", false, true), meta("ctx", true, false)];
+const QUOTE_ACCOUNTS: &[AccountMetaDef] = &[meta("ctx", false, false), optional_meta("schedule", false), optional_meta("stats", false)];
+
+pub const INSTRUCTIONS: &[InstructionDef] = &[
+    InstructionDef {
+        name: "Match",
+        docs: "Price and fill one trade; called by percolator with the LP PDA as signer. A scheduled context needs its schedule after the slab, and one with a feed its feed account and a price account per feed after that, priced off (their median) instead of oracle_price_e6 (which is still echoed). Once InitStats binds a stats account, every Match must pass it (StatsRequired otherwise), so the protocol fee, skim and PnL can't be skipped. The signal accounts the schedule registers follow the slab, schedule, feed and stats, all of them and in order. A referrer named after the call data is credited its share of the fee in the referral account, passed right after the slab. A trader account and its trader, who signs, may follow the slab and referral account for the schedule's loyalty discount; the fill's notional is added to its volume.",
+        discriminator: &[TAG_MATCH],
+        accounts: &[
+            meta("lp_pda", false, true),
+            meta("ctx", true, false),
+            optional_meta("slab", false),
+            optional_meta("referral", true),
+            optional_meta("trader_account", true),
+            optional_signer("trader"),
+            optional_meta("schedule", false),
+            optional_meta("feed", false),
+            optional_meta("price", false),
+            optional_meta("stats", true),
+        ],
+        args: &[
+            field("req_id", Ty::U64, CALL_REQ_ID_OFF),
+            field("lp_idx", Ty::U16, CALL_LP_IDX_OFF),
+            field("lp_account_id", Ty::U64, CALL_LP_ACCOUNT_ID_OFF),
+            field("oracle_price_e6", Ty::U64, CALL_ORACLE_PRICE_OFF),
+            field("trade_size", Ty::I128, CALL_TRADE_SIZE_OFF),
+            optional("referrer", Ty::Pubkey, CALL_REFERRER_OFF),
+        ],
+        data_len: REFERRED_CALL_LEN,
+        returns: Some("MatchReturn"),
+    },
+    InstructionDef {
+        name: "Init",
+        docs: "Initialize a context, signed by the authority when one is named and by the LP PDA otherwise (always the LP PDA on a CreateContext account). Binding a slab or a separate authority needs a CTX_EXT_LEN account.",
+        discriminator: &[TAG_INIT, KIND_CREDIBILITY],
+        accounts: &[meta("lp_pda", false, false), meta("ctx", true, false), optional_meta("slab", false), optional_signer("authority")],
+        args: &[field("params", Ty::Defined("MatcherParams"), 2)],
+        data_len: 2 + PARAMS_MAX_LEN,
+        returns: None,
+    },
+    InstructionDef {
+        name: "InitPlain",
+        docs: "Init for the plain kind: the same context, priced on inventory imbalance alone.",
+        discriminator: &[TAG_INIT, KIND_PLAIN],
+        accounts: &[meta("lp_pda", false, false), meta("ctx", true, false), optional_meta("slab", false), optional_signer("authority")],
+        args: &[field("params", Ty::Defined("MatcherParams"), 2)],
+        data_len: 2 + PARAMS_MAX_LEN,
+        returns: None,
+    },
+    InstructionDef {
+        name: "InitMulti",
+        docs: "Init for the multi kind: priced by signal_weights' blend of coverage, age, deficit and liquidations. Needs a CTX_EXT_LEN account.",
+        discriminator: &[TAG_INIT, KIND_MULTI],
+        accounts: &[meta("lp_pda", false, false), meta("ctx", true, false), optional_meta("slab", false), optional_signer("authority")],
+        args: &[field("params", Ty::Defined("MatcherParams"), 2)],
+        data_len: 2 + PARAMS_MAX_LEN,
+        returns: None,
+    },
+    InstructionDef {
+        name: "InitStable",
+        docs: "Init for the stable kind: priced on a band of peg_band_e6 around 1.0, widened by the depeg and thin coverage, with no inventory terms.",
+        discriminator: &[TAG_INIT, KIND_STABLE],
+        accounts: &[meta("lp_pda", false, false), meta("ctx", true, false), optional_meta("slab", false), optional_signer("authority")],
+        args: &[field("params", Ty::Defined("MatcherParams"), 2)],
+        data_len: 2 + PARAMS_MAX_LEN,
+        returns: None,
+    },
+    InstructionDef {
+        name: "UpdateCredibility",
+        docs: "Refresh the insurance and open-interest snapshot from the slab, which must be the context's bound slab (or a listed market) under the pinned percolator program; an unbound context takes any slab PERCOLATOR_PROGRAM_ID owns. Permissionless. With the context's tip jar and a signing keeper after the clock, the keeper is paid for the staleness the refresh cleared.",
+        discriminator: &[TAG_UPDATE_CREDIBILITY],
+        accounts: &[
+            meta("ctx", true, false),
+            meta("slab", false, false),
+            fixed_meta("clock", CLOCK_SYSVAR),
+            optional_meta("tip_jar", true),
+            AccountMetaDef { signer: true, ..optional_meta("keeper", true) },
+        ],
+        args: &[],
+        data_len: 1,
+        returns: None,
+    },
+    InstructionDef {
+        name: "UpdateParams",
+        docs: "Replace the parameters. Before the first fill only; after it, use ProposeParams.",
+        discriminator: &[TAG_UPDATE_PARAMS],
+        accounts: AUTHORITY_ACCOUNTS,
+        args: &[field("params", Ty::Defined("MatcherParams"), 1)],
+        data_len: 1 + PARAMS_MAX_LEN,
+        returns: None,
+    },
+    InstructionDef {
+        name: "Close",
+        docs: "Close the context, and its schedule, feed, stats and a ladder account if bound, sending the rent to destination. A scheduled context needs its schedule, one with a feed its feed account.",
+        discriminator: &[TAG_CLOSE],
+        accounts: &[
+            meta("authority", false, true),
+            meta("ctx", true, false),
+            meta("destination", true, false),
+            optional_meta("schedule", true),
+            optional_meta("feed", true),
+            optional_meta("stats", true),
+            optional_meta("ladder", true),
+        ],
+        args: &[],
+        data_len: 1,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetPaused",
+        docs: "Pause or resume matching.",
+        discriminator: &[TAG_SET_PAUSED],
+        accounts: AUTHORITY_ACCOUNTS,
+        args: &[field("paused", Ty::Bool, 1)],
+        data_len: 2,
+        returns: None,
+    },
+    InstructionDef {
+        name: "Migrate",
+        docs: "Grow a v5 context to v6. Permissionless; payer funds the extra rent.",
+        discriminator: &[TAG_MIGRATE],
+        accounts: &[meta("ctx", true, false), meta("payer", true, true), fixed_meta("system_program", SYSTEM_PROGRAM)],
+        args: &[],
+        data_len: 1,
+        returns: None,
+    },
+    InstructionDef {
+        name: "QuotePreview",
+        docs: "Price a trade without filling it, on a shared context for the market at that table index. A scheduled context needs its schedule, and the signal accounts it registers after the stats, in order. Read-only: simulate it and decode the return data.",
+        discriminator: &[TAG_QUOTE_PREVIEW],
+        accounts: QUOTE_ACCOUNTS,
+        args: &[
+            field("oracle_price_e6", Ty::U64, 1),
+            field("trade_size", Ty::I128, 9),
+            optional("market", Ty::U16, PREVIEW_CALL_LEN),
+        ],
+        data_len: PREVIEW_CALL_LEN + 2,
+        returns: Some("QuoteBreakdown"),
+    },
+    InstructionDef {
+        name: "ProposeParams",
+        docs: "Queue parameters behind the timelock; without params, cancel the pending proposal.",
+        discriminator: &[TAG_PROPOSE_PARAMS],
+        accounts: AUTHORITY_ACCOUNTS,
+        args: &[optional("params", Ty::Defined("MatcherParams"), 1)],
+        data_len: 1 + PARAMS_MAX_LEN,
+        returns: None,
+    },
+    InstructionDef {
+        name: "CommitParams",
+        docs: "Apply a proposal whose timelock has passed. Permissionless.",
+        discriminator: &[TAG_COMMIT_PARAMS],
+        accounts: &[meta("ctx", true, false)],
+        args: &[],
+        data_len: 1,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetReduceOnly",
+        docs: "Only accept trades that shrink |inventory|.",
+        discriminator: &[TAG_SET_REDUCE_ONLY],
+        accounts: AUTHORITY_ACCOUNTS,
+        args: &[field("on", Ty::Bool, 1)],
+        data_len: 2,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SettleInventory",
+        docs: "Set (SETTLE_SET) or adjust (SETTLE_ADJUST) the recorded inventory; on a shared context, of the market at that table index.",
+        discriminator: &[TAG_SETTLE_INVENTORY],
+        accounts: AUTHORITY_ACCOUNTS,
+        args: &[field("mode", Ty::U8, 1), field("value", Ty::I128, 2), optional("market", Ty::U16, 18)],
+        data_len: 20,
+        returns: None,
+    },
+    InstructionDef {
+        name: "ProposeAuthority",
+        docs: "Start an authority rotation; the default key cancels a pending one.",
+        discriminator: &[TAG_PROPOSE_AUTHORITY],
+        accounts: AUTHORITY_ACCOUNTS,
+        args: &[field("new_authority", Ty::Pubkey, 1)],
+        data_len: 33,
+        returns: None,
+    },
+    InstructionDef {
+        name: "AcceptAuthority",
+        docs: "Complete a rotation, signed by the proposed authority.",
+        discriminator: &[TAG_ACCEPT_AUTHORITY],
+        accounts: &[meta("new_authority", false, true), meta("ctx", true, false)],
+        args: &[],
+        data_len: 1,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetReturnDataOnly",
+        docs: "Report Match results only through return data, leaving the context's MatcherReturn untouched.",
+        discriminator: &[TAG_SET_RETURN_DATA_ONLY],
+        accounts: AUTHORITY_ACCOUNTS,
+        args: &[field("on", Ty::Bool, 1)],
+        data_len: 2,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetSoftReject",
+        docs: "Answer refused matches with a zero fill and a reject reason instead of failing.",
+        discriminator: &[TAG_SET_SOFT_REJECT],
+        accounts: AUTHORITY_ACCOUNTS,
+        args: &[field("on", Ty::Bool, 1)],
+        data_len: 2,
+        returns: None,
+    },
+    InstructionDef {
+        name: "QuoteTwoSided",
+        docs: "Price a bid and an ask for the same size, on a shared context for the market at that table index. A scheduled context needs its schedule, and the signal accounts it registers after the stats, in order. Read-only: simulate it and decode the return data.",
+        discriminator: &[TAG_QUOTE_TWO_SIDED],
+        accounts: QUOTE_ACCOUNTS,
+        args: &[field("oracle_price_e6", Ty::U64, 1), field("size", Ty::U128, 9), optional("market", Ty::U16, PREVIEW_CALL_LEN)],
+        data_len: PREVIEW_CALL_LEN + 2,
+        returns: Some("TwoSidedQuote"),
+    },
+    InstructionDef {
+        name: "InitStats",
+        docs: "Bind a stats account (STATS_LEN bytes, program-owned, rent-exempt) to the context. With protocol_fee_bps (at most 10000) and a treasury, on a STATS_PROTOCOL_LEN account, every Match that passes it also accrues that share of the fill's fee to the treasury, fixed for the account's life.",
+        discriminator: &[TAG_INIT_STATS],
+        accounts: &[meta("authority", false, true), meta("ctx", true, false), meta("stats", true, false), optional_meta("treasury", false)],
+        args: &[optional("protocol_fee_bps", Ty::U16, 1)],
+        data_len: 3,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetRfqSigner",
+        docs: "Set the key whose quotes PostRfq accepts (the default key turns RFQ off) and withdraw any posted quote. v6 only.",
+        discriminator: &[TAG_SET_RFQ_SIGNER],
+        accounts: AUTHORITY_ACCOUNTS,
+        args: &[field("signer", Ty::Pubkey, 1)],
+        data_len: 33,
+        returns: None,
+    },
+    InstructionDef {
+        name: "PostRfq",
+        docs: "Post a signed quote for Match to fill on its side until its size or expiry runs out. Permissionless; must directly follow an ed25519 program instruction verifying the quote by the RFQ signer, with the key, signature and message in its own data.",
+        discriminator: &[TAG_POST_RFQ],
+        accounts: &[meta("ctx", true, false), fixed_meta("instructions", INSTRUCTIONS_SYSVAR)],
+        args: &[field("quote", Ty::Defined("RfqQuote"), 1)],
+        data_len: 1 + RFQ_MSG_LEN,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetMarket",
+        docs: "List a slab in a shared context's market table, or change its caps (zero keeps the context's). The slab must be owned by the pinned percolator program.",
+        discriminator: &[TAG_SET_MARKET],
+        accounts: &[meta("authority", false, true), meta("ctx", true, false), meta("slab", false, false)],
+        args: &[field("max_fill", Ty::U128, 1), field("max_inventory", Ty::U128, 17)],
+        data_len: 33,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetNoBids",
+        docs: "Stop buying from takers: a Match that sells to the LP comes back as a zero fill with reason SideDisabled, soft-reject mode or not.",
+        discriminator: &[TAG_SET_NO_BIDS],
+        accounts: AUTHORITY_ACCOUNTS,
+        args: &[field("on", Ty::Bool, 1)],
+        data_len: 2,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetNoAsks",
+        docs: "Stop selling to takers: a Match that buys from the LP comes back as a zero fill with reason SideDisabled, soft-reject mode or not.",
+        discriminator: &[TAG_SET_NO_ASKS],
+        accounts: AUTHORITY_ACCOUNTS,
+        args: &[field("on", Ty::Bool, 1)],
+        data_len: 2,
+        returns: None,
+    },
+    InstructionDef {
+        name: "InitSchedule",
+        docs: "Bind a schedule account (SCHEDULE_LEN bytes, program-owned, rent-exempt) to the context. A context takes one; it goes with Close.",
+        discriminator: &[TAG_INIT_SCHEDULE],
+        accounts: &[meta("authority", false, true), meta("ctx", true, false), meta("schedule", true, false)],
+        args: &[],
+        data_len: 1,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetSchedule",
+        docs: "Replace the schedule's entries with the first count of entries. Multipliers are at least 10000, so it can only widen the spread.",
+        discriminator: &[TAG_SET_SCHEDULE],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("schedule", true, false)],
+        args: &[
+            field("count", Ty::U8, 1),
+            field("entries", Ty::Array(&Ty::Defined("ScheduleEntryArgs"), MAX_SCHEDULE_ENTRIES), 2),
+        ],
+        data_len: 2 + MAX_SCHEDULE_ENTRIES * SCHED_ARG_LEN,
+        returns: None,
+    },
+    InstructionDef {
+        name: "InitLadder",
+        docs: "Bind a ladder account (LADDER_LEN bytes, program-owned, rent-exempt) to the context for PublishLadder to write.",
+        discriminator: &[TAG_INIT_LADDER],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("ladder", true, false)],
+        args: &[],
+        data_len: 1,
+        returns: None,
+    },
+    InstructionDef {
+        name: "PublishLadder",
+        docs: "Price MAX_LADDER_LEVELS trades of step_size, 2 * step_size, ... each way, as Match would fill them now, into the ladder, on a shared context for the market at that table index. A side stops at its first clipped level. A scheduled context needs its schedule after the ladder, and the signal accounts it registers after the stats, in order.",
+        discriminator: &[TAG_PUBLISH_LADDER],
+        accounts: &[
+            meta("authority", false, true),
+            meta("ctx", false, false),
+            meta("ladder", true, false),
+            optional_meta("schedule", false),
+            optional_meta("stats", false),
+        ],
+        args: &[field("oracle_price_e6", Ty::U64, 1), field("step_size", Ty::U128, 9), optional("market", Ty::U16, PREVIEW_CALL_LEN)],
+        data_len: PREVIEW_CALL_LEN + 2,
+        returns: None,
+    },
+    InstructionDef {
+        name: "InitFeed",
+        docs: "Bind a feed account (FEED_LEN bytes, program-owned, rent-exempt) to the context with the feed of price: a fully verified PriceUpdateV2 owned by the Pyth receiver or push oracle, with source FEED_SOURCE_SWITCHBOARD a Switchboard on-demand pull feed, or with FEED_SOURCE_CHAINLINK a Chainlink OCR2 feed. Pinned, the feed is bound to the price account's key rather than its Pyth feed id. Match then prices off that feed. A context takes one; it goes with Close.",
+        discriminator: &[TAG_INIT_FEED],
+        accounts: &[
+            meta("authority", false, true),
+            meta("ctx", true, false),
+            meta("feed", true, false),
+            meta("price", false, false),
+        ],
+        args: &[field("max_age_secs", Ty::U32, 1), field("source", Ty::U8, 5), optional("pinned", Ty::Bool, 6)],
+        data_len: 7,
+        returns: None,
+    },
+    InstructionDef {
+        name: "AddFeed",
+        docs: "Bind the feed of price, from source, to the context's feed account as well, up to MAX_FEEDS feeds, each once. Match then needs a price account per feed, in the order they were bound, and prices off their median.",
+        discriminator: &[TAG_ADD_FEED],
+        accounts: &[
+            meta("authority", false, true),
+            meta("ctx", false, false),
+            meta("feed", true, false),
+            meta("price", false, false),
+        ],
+        args: &[field("source", Ty::U8, 1), optional("pinned", Ty::Bool, 2)],
+        data_len: 3,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetFeedDivergence",
+        docs: "How far apart (highest minus lowest, in bps of the median) the context's feeds may be before Match refuses the trade, or with widen adds the divergence to the spread. 0 turns the check off; at most 10000.",
+        discriminator: &[TAG_SET_FEED_DIVERGENCE],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("feed", true, false)],
+        args: &[field("max_divergence_bps", Ty::U16, 1), field("widen", Ty::Bool, 3)],
+        data_len: 4,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetOracleDecimals",
+        docs: "The decimals of the oracle price Match and the quotes are called with, ORACLE_DECIMALS_E6 through MAX_ORACLE_DECIMALS (0 also means e6); the program divides it down to e6, rounding down. Only before the first fill.",
+        discriminator: &[TAG_SET_ORACLE_DECIMALS],
+        accounts: AUTHORITY_ACCOUNTS,
+        args: &[field("decimals", Ty::U8, 1)],
+        data_len: 2,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetFeedSlotAge",
+        docs: "How many slots before the current one the context's feed prices may have been posted (a Pyth update's posted slot, a Switchboard result's or Chainlink round's slot) before Match refuses the trade, or with widen quotes it at max_spread_bps. 0 turns the check off.",
+        discriminator: &[TAG_SET_FEED_SLOT_AGE],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("feed", true, false)],
+        args: &[field("max_age_slots", Ty::U16, 1), field("widen", Ty::Bool, 3)],
+        data_len: 4,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetFeedFallback",
+        docs: "With fallback, Match prices off the first of the context's feeds, in the order they were bound, whose price is fresh and positive, and adds that feed's penalty_bps (at most 10000 each) to the spread, instead of taking their median. A price account that isn't its feed's still fails the Match.",
+        discriminator: &[TAG_SET_FEED_FALLBACK],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("feed", true, false)],
+        args: &[field("fallback", Ty::Bool, 1), field("penalties_bps", Ty::Array(&Ty::U16, MAX_FEEDS), 2)],
+        data_len: 2 + 2 * MAX_FEEDS,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetFeeTiers",
+        docs: "Replace the fee tiers of a schedule account created with SCHEDULE_FEES_LEN bytes with the first count of tiers, min_notional positive and rising, fee_bps at most 10000; count 0 clears them. Refused once the market has traded.",
+        discriminator: &[TAG_SET_FEE_TIERS],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("schedule", true, false)],
+        args: &[
+            field("count", Ty::U8, 1),
+            field("tiers", Ty::Array(&Ty::Defined("FeeTierArgs"), MAX_FEE_TIERS), 2),
+        ],
+        data_len: 2 + MAX_FEE_TIERS * FEE_TIER_ARG_LEN,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetRebate",
+        docs: "Set the rebate (at most 10000) a fill that shrinks |inventory| gets off its spread and fee, never past the oracle, on a schedule account created with SCHEDULE_REBATE_LEN bytes. Takes effect at once; 0 turns it off.",
+        discriminator: &[TAG_SET_REBATE],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("schedule", true, false)],
+        args: &[field("rebate_bps", Ty::U16, 1)],
+        data_len: 3,
+        returns: None,
+    },
+    InstructionDef {
+        name: "RecordWithdrawal",
+        docs: "Record that the LP withdrew amount of the fees the context accrued (cum_fees) in the fee ledger of the bound stats account, created with STATS_LEDGER_LEN bytes. The ledger never exceeds cum_fees.",
+        discriminator: &[TAG_RECORD_WITHDRAWAL],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("stats", true, false)],
+        args: &[field("amount", Ty::U128, 1)],
+        data_len: 17,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetFeeDiscount",
+        docs: "Set the share of the fee (at most 10000) a quote waives at the full credibility discount, that share of it part way, on a schedule account created with SCHEDULE_REBATE_LEN bytes. The plain kind keeps its whole fee. Takes effect at once; 0 turns it off.",
+        discriminator: &[TAG_SET_FEE_DISCOUNT],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("schedule", true, false)],
+        args: &[field("fee_discount_bps", Ty::U16, 1)],
+        data_len: 3,
+        returns: None,
+    },
+    InstructionDef {
+        name: "InitTipJar",
+        docs: "Bind a tip jar (TIP_JAR_LEN bytes, program-owned, rent-exempt; its lamports above that are the escrow) to the context, paying keepers lamports_per_slot per stale slot up to max_tip.",
+        discriminator: &[TAG_INIT_TIP_JAR],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("tip_jar", true, false)],
+        args: &[field("lamports_per_slot", Ty::U64, 1), field("max_tip", Ty::U64, 9)],
+        data_len: 17,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetTip",
+        docs: "Change a bound tip jar's tip. Takes effect at once; a zero max_tip stops the tips.",
+        discriminator: &[TAG_SET_TIP],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("tip_jar", true, false)],
+        args: &[field("lamports_per_slot", Ty::U64, 1), field("max_tip", Ty::U64, 9)],
+        data_len: 17,
+        returns: None,
+    },
+    InstructionDef {
+        name: "CloseTipJar",
+        docs: "Zero a bound tip jar and sweep its lamports, escrow and rent, to destination.",
+        discriminator: &[TAG_CLOSE_TIP_JAR],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("tip_jar", true, false), meta("destination", true, false)],
+        args: &[],
+        data_len: 1,
+        returns: None,
+    },
+    InstructionDef {
+        name: "InitReferral",
+        docs: "Bind a referral account (REFERRAL_LEN bytes, program-owned, rent-exempt) to the context, crediting referrers named in Match data with share_bps of each fill's fee.",
+        discriminator: &[TAG_INIT_REFERRAL],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("referral", true, false)],
+        args: &[field("share_bps", Ty::U16, 1)],
+        data_len: 3,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetReferralShare",
+        docs: "Change a bound referral account's share_bps, for fills from then on.",
+        discriminator: &[TAG_SET_REFERRAL_SHARE],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("referral", true, false)],
+        args: &[field("share_bps", Ty::U16, 1)],
+        data_len: 3,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetMinNotional",
+        docs: "Set the notional at the oracle below which a trade is refused as dust (BelowMinNotional, soft-rejected in soft-reject mode), on a schedule account created with SCHEDULE_MIN_NOTIONAL_LEN bytes. Takes effect at once; 0 turns it off.",
+        discriminator: &[TAG_SET_MIN_NOTIONAL],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("schedule", true, false)],
+        args: &[field("min_notional_e6", Ty::U128, 1)],
+        data_len: 17,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetSkim",
+        docs: "Set the share of each fill's spread (at most 10000), on its notional, that Match accrues for the insurance fund in the bound stats account, created with STATS_SKIM_LEN bytes. 0 stops accruing; what accrued stays claimable.",
+        discriminator: &[TAG_SET_SKIM],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("stats", true, false)],
+        args: &[field("skim_bps", Ty::U16, 1)],
+        data_len: 3,
+        returns: None,
+    },
+    InstructionDef {
+        name: "ClaimSkim",
+        docs: "Pay amount of the skim accrued in the bound stats account into the insurance fund of the slab the context was bound to, through percolator's TopUpInsurance with the authority as the user: from its source token account into the slab's vault. Never more than accrued and not yet claimed.",
+        discriminator: &[TAG_CLAIM_SKIM],
+        accounts: &[
+            meta("authority", false, true),
+            meta("ctx", false, false),
+            meta("stats", true, false),
+            meta("slab", true, false),
+            meta("source", true, false),
+            meta("vault", true, false),
+            meta("token_program", false, false),
+            meta("percolator", false, false),
+        ],
+        args: &[field("amount", Ty::U64, 1)],
+        data_len: 9,
+        returns: None,
+    },
+    InstructionDef {
+        name: "InitTrader",
+        docs: "Bind a trader account (TRADER_LEN bytes, rent-exempt, owned by the program) to the context and the signing trader, for Match to record the trader's volume in and the schedule's loyalty tiers to discount the fee by.",
+        discriminator: &[TAG_INIT_TRADER],
+        accounts: &[meta("trader", false, true), meta("ctx", false, false), meta("trader_account", true, false)],
+        args: &[],
+        data_len: 1,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetLoyaltyTiers",
+        docs: "Replace the loyalty tiers of a schedule account created with SCHEDULE_LOYALTY_LEN bytes with the first count of tiers, min_volume positive and rising, discount_bps at most 10000; count 0 clears them. Takes effect at once.",
+        discriminator: &[TAG_SET_LOYALTY_TIERS],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("schedule", true, false)],
+        args: &[
+            field("count", Ty::U8, 1),
+            field("tiers", Ty::Array(&Ty::Defined("LoyaltyTierArgs"), MAX_LOYALTY_TIERS), 2),
+        ],
+        data_len: 2 + MAX_LOYALTY_TIERS * LOYALTY_TIER_ARG_LEN,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SweepProtocolFee",
+        docs: "Record that the treasury took amount of the protocol fee accrued in a stats account of the context; the fees stay with percolator. Signed by the treasury InitStats named. Never more than accrued and not yet swept.",
+        discriminator: &[TAG_SWEEP_PROTOCOL_FEE],
+        accounts: &[meta("treasury", false, true), meta("ctx", false, false), meta("stats", true, false)],
+        args: &[field("amount", Ty::U128, 1)],
+        data_len: 17,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetInventoryDecay",
+        docs: "Set the slots over which the inventory the imbalance and skew penalties see halves while no fill comes in, on a schedule account created with SCHEDULE_DECAY_LEN bytes; 0 turns it off. The book keeps the full inventory. v6 contexts only decay; before the first fill only.",
+        discriminator: &[TAG_SET_INVENTORY_DECAY],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("schedule", true, false)],
+        args: &[field("halflife_slots", Ty::U32, 1)],
+        data_len: 5,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetInventoryBand",
+        docs: "Set the soft inventory limit past which a fill that takes |inventory| deeper pays up to band_k_bps more, in the square of how far toward max_inventory it gets, on a schedule account created with SCHEDULE_BAND_LEN bytes; a soft_inventory of 0 turns it off. max_inventory stays the hard limit and the soft one must sit below it; band_k_bps at most 10000. Before the first fill only.",
+        discriminator: &[TAG_SET_INVENTORY_BAND],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("schedule", true, false)],
+        args: &[field("soft_inventory", Ty::U128, 1), field("band_k_bps", Ty::U32, 17)],
+        data_len: 21,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetInventoryCaps",
+        docs: "Cap a long and a short book separately, on a schedule account created with SCHEDULE_CAPS_LEN bytes; a zero cap leaves that side at max_inventory (the market's, on a shared context). Auto reduce-only follows the cap of the side the book is on. Takes effect at once.",
+        discriminator: &[TAG_SET_INVENTORY_CAPS],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("schedule", true, false)],
+        args: &[field("max_long_inventory", Ty::U128, 1), field("max_short_inventory", Ty::U128, 17)],
+        data_len: 33,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetReduceOnlyThreshold",
+        docs: "Set the share of the cap, in bps, past which the book goes reduce-only on its own, on a schedule account created with SCHEDULE_REDUCE_ONLY_LEN bytes; 0 restores AUTO_REDUCE_ONLY_BPS. Past it, trades that grow |inventory| get a zero fill with ReduceOnly as the reason, soft-reject mode or not. Takes effect at once.",
+        discriminator: &[TAG_SET_REDUCE_ONLY_THRESHOLD],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("schedule", true, false)],
+        args: &[field("reduce_only_bps", Ty::U16, 1)],
+        data_len: 3,
+        returns: None,
+    },
+    InstructionDef {
+        name: "TransferInventory",
+        docs: "Move amount of inventory from the source book to the destination's, without trading; a negative amount moves it the other way. The market indices pick the books on shared contexts. Both authorities sign, both books must quote the same slab (SlabMismatch), and neither may end up past its max_inventory (InventoryLimit).",
+        discriminator: &[TAG_TRANSFER_INVENTORY],
+        accounts: &[
+            meta("from_authority", false, true),
+            meta("from_ctx", true, false),
+            meta("to_authority", false, true),
+            meta("to_ctx", true, false),
+        ],
+        args: &[field("amount", Ty::I128, 1), optional("from_market", Ty::U16, 17), optional("to_market", Ty::U16, 19)],
+        data_len: 21,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetInventoryDepthCap",
+        docs: "Set max_inventory as max_inventory_bps of liquidity_notional_e6, converted to base units at each trade's oracle, on a schedule account created with SCHEDULE_DEPTH_CAP_LEN bytes; 0 goes back to max_inventory as set. A market's own max_inventory still wins. Takes effect at once.",
+        discriminator: &[TAG_SET_INVENTORY_DEPTH_CAP],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("schedule", true, false)],
+        args: &[field("max_inventory_bps", Ty::U32, 1)],
+        data_len: 5,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SyncInventory",
+        docs: "Set inventory_base to minus the position_size of the LP at lp_idx in the bound slab's account table (any slab PERCOLATOR_PROGRAM_ID owns on an unbound context), which must be matched through this program and context. A shared context syncs the slab's market entry. Permissionless; logs the drift.",
+        discriminator: &[TAG_SYNC_INVENTORY],
+        accounts: &[meta("ctx", true, false), meta("slab", false, false)],
+        args: &[field("lp_idx", Ty::U16, 1)],
+        data_len: 3,
+        returns: None,
+    },
+    InstructionDef {
+        name: "CreateContext",
+        docs: "Create the context for lp_pda on slab at the program address derived from [\"context\", lp_pda, slab], with len bytes (at least CTX_EXT_LEN + CREATION_LEN) and rent from the payer, and mark it derived in its creation record. Init must then name the same pair, and every instruction checks a derived context is still at its address; a keypair context stays the legacy path at any size.",
+        discriminator: &[TAG_CREATE_CONTEXT],
+        accounts: &[meta("lp_pda", false, false), meta("ctx", true, false), meta("slab", false, false), meta("payer", true, true), fixed_meta("system_program", SYSTEM_PROGRAM)],
+        args: &[field("len", Ty::U32, 1)],
+        data_len: 5,
+        returns: None,
+    },
+    InstructionDef {
+        name: "BindSlab",
+        docs: "Bind an unbound v6 context (Init gave it no slab, or it was migrated from v5) to the slab and pin the slab's owner as the percolator program. Refused on bound, shared and CreateContext contexts.",
+        discriminator: &[TAG_BIND_SLAB],
+        accounts: &[meta("authority", false, true), meta("ctx", true, false), meta("slab", false, false)],
+        args: &[],
+        data_len: 1,
+        returns: None,
+    },
+    InstructionDef {
+        name: "SetSignals",
+        docs: "Register the signal accounts after the schedule, up to MAX_SIGNAL_ACCOUNTS, and their owners, on a schedule account created with SCHEDULE_SIGNALS_LEN bytes. Every Match, quote and ladder must then pass exactly those, in order, after the stats account. None clears the list.",
+        discriminator: &[TAG_SET_SIGNALS],
+        accounts: &[meta("authority", false, true), meta("ctx", false, false), meta("schedule", true, false)],
+        args: &[],
+        data_len: 1,
+        returns: None,
+    },
+];
+
+pub const CONSTANTS: &[Constant] = &[
+    Constant { name: "MAGIC", ty: Ty::U64, value: MAGIC },
+    Constant { name: "VERSION", ty: Ty::U32, value: VERSION as u64 },
+    Constant { name: "VERSION_V5", ty: Ty::U32, value: VERSION_V5 as u64 },
+    Constant { name: "KIND_PLAIN", ty: Ty::U8, value: KIND_PLAIN as u64 },
+    Constant { name: "KIND_CREDIBILITY", ty: Ty::U8, value: KIND_CREDIBILITY as u64 },
+    Constant { name: "KIND_MULTI", ty: Ty::U8, value: KIND_MULTI as u64 },
+    Constant { name: "KIND_STABLE", ty: Ty::U8, value: KIND_STABLE as u64 },
+    Constant { name: "MATCHER_ABI_VERSION", ty: Ty::U32, value: MATCHER_ABI_VERSION as u64 },
+    Constant { name: "FLAG_VALID", ty: Ty::U32, value: FLAG_VALID as u64 },
+    Constant { name: "CTX_BASE", ty: Ty::U32, value: CTX_BASE as u64 },
+    Constant { name: "CTX_LEN", ty: Ty::U32, value: CTX_LEN as u64 },
+    Constant { name: "EXT_BASE", ty: Ty::U32, value: EXT_BASE as u64 },
+    Constant { name: "CTX_EXT_LEN", ty: Ty::U32, value: CTX_EXT_LEN as u64 },
+    Constant { name: "MARKETS_BASE", ty: Ty::U32, value: MARKETS_BASE as u64 },
+    Constant { name: "MARKET_LEN", ty: Ty::U32, value: MARKET_LEN as u64 },
+    Constant { name: "MAX_MARKETS", ty: Ty::U32, value: MAX_MARKETS as u64 },
+    Constant { name: "CTX_SHARED_LEN", ty: Ty::U32, value: CTX_SHARED_LEN as u64 },
+    Constant { name: "CREATION_LEN", ty: Ty::U32, value: CREATION_LEN as u64 },
+    Constant { name: "CTX_FLAG_PAUSED", ty: Ty::U8, value: CTX_FLAG_PAUSED as u64 },
+    Constant { name: "CTX_FLAG_REDUCE_ONLY", ty: Ty::U8, value: CTX_FLAG_REDUCE_ONLY as u64 },
+    Constant { name: "CTX_FLAG_RETURN_DATA_ONLY", ty: Ty::U8, value: CTX_FLAG_RETURN_DATA_ONLY as u64 },
+    Constant { name: "CTX_FLAG_SOFT_REJECT", ty: Ty::U8, value: CTX_FLAG_SOFT_REJECT as u64 },
+    Constant { name: "CTX_FLAG_NO_BIDS", ty: Ty::U8, value: CTX_FLAG_NO_BIDS as u64 },
+    Constant { name: "CTX_FLAG_NO_ASKS", ty: Ty::U8, value: CTX_FLAG_NO_ASKS as u64 },
+    Constant { name: "CTX_FLAG_SCHEDULED", ty: Ty::U8, value: CTX_FLAG_SCHEDULED as u64 },
+    Constant { name: "CTX_FLAG_FEED", ty: Ty::U8, value: CTX_FLAG_FEED as u64 },
+    Constant { name: "PARAMS_LEN", ty: Ty::U32, value: PARAMS_LEN as u64 },
+    Constant { name: "PARAMS_MAX_LEN", ty: Ty::U32, value: PARAMS_MAX_LEN as u64 },
+    Constant { name: "PENDING_HEAD_LEN", ty: Ty::U32, value: PENDING_HEAD_LEN as u64 },
+    Constant { name: "STATS_MAGIC", ty: Ty::U64, value: STATS_MAGIC },
+    Constant { name: "STATS_VERSION", ty: Ty::U32, value: STATS_VERSION as u64 },
+    Constant { name: "STATS_CAPACITY", ty: Ty::U32, value: STATS_CAPACITY as u64 },
+    Constant { name: "ORACLE_CAPACITY", ty: Ty::U32, value: ORACLE_CAPACITY as u64 },
+    Constant { name: "STATS_LEN", ty: Ty::U32, value: STATS_LEN as u64 },
+    Constant { name: "STATS_LEDGER_LEN", ty: Ty::U32, value: STATS_LEDGER_LEN as u64 },
+    Constant { name: "STATS_SKIM_LEN", ty: Ty::U32, value: STATS_SKIM_LEN as u64 },
+    Constant { name: "STATS_PROTOCOL_LEN", ty: Ty::U32, value: STATS_PROTOCOL_LEN as u64 },
+    Constant { name: "STATS_PNL_LEN", ty: Ty::U32, value: STATS_PNL_LEN as u64 },
+    Constant { name: "SIGNAL_MAGIC", ty: Ty::U64, value: SIGNAL_MAGIC },
+    Constant { name: "SIGNAL_LEN", ty: Ty::U32, value: SIGNAL_LEN as u64 },
+    Constant { name: "MAX_SIGNAL_ACCOUNTS", ty: Ty::U32, value: MAX_SIGNAL_ACCOUNTS as u64 },
+    Constant { name: "SCHEDULE_MAGIC", ty: Ty::U64, value: SCHEDULE_MAGIC },
+    Constant { name: "SCHEDULE_LEN", ty: Ty::U32, value: SCHEDULE_LEN as u64 },
+    Constant { name: "MAX_SCHEDULE_ENTRIES", ty: Ty::U32, value: MAX_SCHEDULE_ENTRIES as u64 },
+    Constant { name: "SCHEDULE_FEES_LEN", ty: Ty::U32, value: SCHEDULE_FEES_LEN as u64 },
+    Constant { name: "MAX_FEE_TIERS", ty: Ty::U32, value: MAX_FEE_TIERS as u64 },
+    Constant { name: "SCHEDULE_REBATE_LEN", ty: Ty::U32, value: SCHEDULE_REBATE_LEN as u64 },
+    Constant { name: "SCHEDULE_MIN_NOTIONAL_LEN", ty: Ty::U32, value: SCHEDULE_MIN_NOTIONAL_LEN as u64 },
+    Constant { name: "SCHEDULE_LOYALTY_LEN", ty: Ty::U32, value: SCHEDULE_LOYALTY_LEN as u64 },
+    Constant { name: "SCHEDULE_DECAY_LEN", ty: Ty::U32, value: SCHEDULE_DECAY_LEN as u64 },
+    Constant { name: "SCHEDULE_BAND_LEN", ty: Ty::U32, value: SCHEDULE_BAND_LEN as u64 },
+    Constant { name: "SCHEDULE_CAPS_LEN", ty: Ty::U32, value: SCHEDULE_CAPS_LEN as u64 },
+    Constant { name: "SCHEDULE_REDUCE_ONLY_LEN", ty: Ty::U32, value: SCHEDULE_REDUCE_ONLY_LEN as u64 },
+    Constant { name: "SCHEDULE_DEPTH_CAP_LEN", ty: Ty::U32, value: SCHEDULE_DEPTH_CAP_LEN as u64 },
+    Constant { name: "SCHEDULE_SIGNALS_LEN", ty: Ty::U32, value: SCHEDULE_SIGNALS_LEN as u64 },
+    Constant { name: "MAX_LOYALTY_TIERS", ty: Ty::U32, value: MAX_LOYALTY_TIERS as u64 },
+    Constant { name: "LADDER_MAGIC", ty: Ty::U64, value: LADDER_MAGIC },
+    Constant { name: "LADDER_LEN", ty: Ty::U32, value: LADDER_LEN as u64 },
+    Constant { name: "TIP_JAR_MAGIC", ty: Ty::U64, value: TIP_JAR_MAGIC },
+    Constant { name: "TIP_JAR_LEN", ty: Ty::U32, value: TIP_JAR_LEN as u64 },
+    Constant { name: "REFERRAL_MAGIC", ty: Ty::U64, value: REFERRAL_MAGIC },
+    Constant { name: "REFERRAL_LEN", ty: Ty::U32, value: REFERRAL_LEN as u64 },
+    Constant { name: "MAX_REFERRERS", ty: Ty::U32, value: MAX_REFERRERS as u64 },
+    Constant { name: "TRADER_MAGIC", ty: Ty::U64, value: TRADER_MAGIC },
+    Constant { name: "TRADER_LEN", ty: Ty::U32, value: TRADER_LEN as u64 },
+    Constant { name: "MAX_LADDER_LEVELS", ty: Ty::U32, value: MAX_LADDER_LEVELS as u64 },
+    Constant { name: "FEED_MAGIC", ty: Ty::U64, value: FEED_MAGIC },
+    Constant { name: "FEED_LEN", ty: Ty::U32, value: FEED_LEN as u64 },
+    Constant { name: "MAX_FEEDS", ty: Ty::U32, value: MAX_FEEDS as u64 },
+    Constant { name: "FEED_SOURCE_PYTH", ty: Ty::U8, value: FEED_SOURCE_PYTH as u64 },
+    Constant { name: "FEED_SOURCE_SWITCHBOARD", ty: Ty::U8, value: FEED_SOURCE_SWITCHBOARD as u64 },
+    Constant { name: "FEED_SOURCE_CHAINLINK", ty: Ty::U8, value: FEED_SOURCE_CHAINLINK as u64 },
+    Constant { name: "ORACLE_DECIMALS_E6", ty: Ty::U8, value: ORACLE_DECIMALS_E6 as u64 },
+    Constant { name: "MAX_ORACLE_DECIMALS", ty: Ty::U8, value: MAX_ORACLE_DECIMALS as u64 },
+    Constant { name: "SETTLE_SET", ty: Ty::U8, value: SETTLE_SET as u64 },
+    Constant { name: "SETTLE_ADJUST", ty: Ty::U8, value: SETTLE_ADJUST as u64 },
+    Constant { name: "IMPACT_NONE", ty: Ty::U8, value: IMPACT_NONE as u64 },
+    Constant { name: "IMPACT_LINEAR", ty: Ty::U8, value: IMPACT_LINEAR as u64 },
+    Constant { name: "IMPACT_SQRT", ty: Ty::U8, value: IMPACT_SQRT as u64 },
+    Constant { name: "CURVE_LINEAR", ty: Ty::U8, value: CURVE_LINEAR as u64 },
+    Constant { name: "CURVE_SQRT", ty: Ty::U8, value: CURVE_SQRT as u64 },
+    Constant { name: "CURVE_PIECEWISE", ty: Ty::U8, value: CURVE_PIECEWISE as u64 },
+    Constant { name: "CURVE_POINTS", ty: Ty::U32, value: CURVE_POINTS as u64 },
+    Constant { name: "SIGNAL_COVERAGE", ty: Ty::U8, value: SIGNAL_COVERAGE as u64 },
+    Constant { name: "SIGNAL_AGE", ty: Ty::U8, value: SIGNAL_AGE as u64 },
+    Constant { name: "SIGNAL_DEFICIT", ty: Ty::U8, value: SIGNAL_DEFICIT as u64 },
+    Constant { name: "SIGNAL_LIQUIDATIONS", ty: Ty::U8, value: SIGNAL_LIQUIDATIONS as u64 },
+    Constant { name: "SIGNALS", ty: Ty::U32, value: SIGNALS as u64 },
+    Constant { name: "RFQ_MSG_LEN", ty: Ty::U32, value: RFQ_MSG_LEN as u64 },
+];
+
+pub fn type_def(name: &str) -> &'static TypeDef {
+    TYPES.iter().find(|t| t.name == name).unwrap_or_else(|| panic!("unknown type {name}"))
+}
+
+fn field_json(f: &Field) -> Value {
+    let mut v = json!({ "name": f.name, "type": f.ty.to_json(), "offset": f.offset });
+    if f.optional {
+        v["optional"] = true.into();
+    }
+    v
+}
+
+/// The IDL as JSON.
+pub fn idl() -> Value {
+    let instructions: Vec<Value> = INSTRUCTIONS
+        .iter()
+        .map(|ix| {
+            let accounts: Vec<Value> = ix
+                .accounts
+                .iter()
+                .map(|a| {
+                    let mut v = json!({ "name": a.name, "writable": a.writable, "signer": a.signer });
+                    if a.optional {
+                        v["optional"] = true.into();
+                    }
+                    if let Some(address) = a.address {
+                        v["address"] = address.into();
+                    }
+                    v
+                })
+                .collect();
+            json!({
+                "name": ix.name,
+                "docs": ix.docs,
+                "discriminator": ix.discriminator,
+                "accounts": accounts,
+                "args": ix.args.iter().map(field_json).collect::<Vec<_>>(),
+                "data_len": ix.data_len,
+                "returns": ix.returns,
+            })
+        })
+        .collect();
+    let types: Vec<Value> = TYPES
+        .iter()
+        .map(|t| {
+            json!({
+                "name": t.name,
+                "docs": t.docs,
+                "size": t.size,
+                "fields": t.fields.iter().map(field_json).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    let accounts: Vec<Value> = ACCOUNTS
+        .iter()
+        .map(|a| json!({ "name": a.ty, "magic_offset": a.magic_offset, "magic": a.magic, "min_size": a.min_size }))
+        .collect();
+    let constants: Vec<Value> = CONSTANTS
+        .iter()
+        .map(|c| json!({ "name": c.name, "type": c.ty.to_json(), "value": c.value }))
+        .collect();
+    let errors: Vec<Value> = MatcherError::ALL
+        .iter()
+        .map(|e| json!({ "code": *e as u32, "name": format!("{e:?}"), "msg": e.message() }))
+        .collect();
+    json!({
+        "name": "credibility_matcher",
+        "endian": "little",
+        "instructions": instructions,
+        "accounts": accounts,
+        "types": types,
+        "constants": constants,
+        "tiers": TIER_NAMES,
+        "errors": errors,
+    })
+}
+
+/// Where the generated files live, relative to this crate.
+pub const JSON_PATH: &str = "credibility_matcher.json";
+pub const TS_PATH: &str = "../../ui/src/generated/matcher.ts";
+pub const VECTORS_PATH: &str = "quote_vectors.json";
+
+/// The IDL as it is written to [`JSON_PATH`].
+pub fn render_json() -> String {
+    let mut out = serde_json::to_string_pretty(&idl()).expect("IDL serializes");
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use provenance_client::{
+        instruction as ix, ContextExtension, FeeTier, LoyaltyTier, MarketBook, MatcherContext, MatcherParams, MatcherReturn, ScheduleEntry,
+    };
+    use solana_program::{instruction::Instruction, pubkey::Pubkey};
+
+    /// Every field as a u128 bit pattern (arrays: the first element).
+    fn read(data: &[u8], ty: Ty, off: usize) -> u128 {
+        let mut buf = [0u8; 16];
+        let n = match ty {
+            Ty::Array(inner, _) => inner.size(),
+            ty => ty.size().min(16),
+        };
+        buf[..n].copy_from_slice(&data[off..off + n]);
+        u128::from_le_bytes(buf)
+    }
+
+    #[test]
+    fn test_types_fit_and_do_not_overlap() {
+        for t in TYPES {
+            let mut fields: Vec<&Field> = t.fields.iter().collect();
+            fields.sort_by_key(|f| f.offset);
+            for pair in fields.windows(2) {
+                assert!(pair[0].offset + pair[0].ty.size() <= pair[1].offset, "{}.{} overlaps", t.name, pair[0].name);
+            }
+            let last = fields.last().unwrap();
+            assert!(last.offset + last.ty.size() <= t.size, "{}.{} overruns", t.name, last.name);
+        }
+        for a in ACCOUNTS {
+            let t = type_def(a.ty);
+            assert!(a.min_size <= t.size && a.magic_offset + 8 <= a.min_size);
+        }
+    }
+
+    #[test]
+    fn test_params_match_client_codec() {
+        // Distinct bytes in every field, so a wrong offset reads something else
+        let params = MatcherParams {
+            base_fee_bps: 1,
+            min_spread_bps: 2,
+            max_spread_bps: 3,
+            imbalance_k_bps: 4,
+            liquidity_e6: 5,
+            max_fill: 6,
+            max_inventory: 7,
+            age_halflife: 8,
+            insurance_weight_bps: 9,
+            impact_mode: 10,
+            impact_k_bps: 11,
+            skew_k_bps: 12,
+            coverage_curve: 13,
+            curve_points: [14, 0, 0, 0],
+            liq_halflife: 15,
+            funding_horizon: 16,
+            oracle_jump_bps: 17,
+            oracle_jump_window: 18,
+            flow_k_bps: 19,
+            twap_window_slots: 20,
+            signal_weights: [21, 0, 0, 0],
+            deficit_halflife: 22,
+            liq_auction_bps: 23,
+            liq_auction_slots: 24,
+            peg_band_e6: 25,
+            spot_weight_bps: 26,
+        };
+        let data = params.pack();
+        let t = type_def("MatcherParams");
+        assert_eq!(t.size, data.len());
+        for (i, f) in t.fields.iter().enumerate() {
+            assert_eq!(read(&data, f.ty, f.offset), i as u128 + 1, "MatcherParams.{}", f.name);
+        }
+    }
+
+    #[test]
+    fn test_context_matches_client_codec() {
+        let params = MatcherParams { base_fee_bps: 3, liquidity_e6: 4, curve_points: [5, 6, 7, 8], flow_k_bps: 9, ..Default::default() };
+        let mut markets = [MarketBook::default(); MAX_MARKETS];
+        markets[1] = MarketBook {
+            slab: Pubkey::new_from_array([1; 32]),
+            max_fill: 2,
+            max_inventory: 3,
+            inventory: 4,
+            insurance_snapshot: 5,
+            total_oi_snapshot: 6,
+            gross_long: 7,
+            gross_short: 8,
+            snapshot_slot: 9,
+            last_crank_slot: 10,
+            last_liquidations: 11,
+            funding_rate_bps_per_slot: 12,
+            market_age: 13,
+            last_deficit: 14,
+            last_liq_slot: 15,
+            last_oracle_price_e6: 16,
+            last_oracle_slot: 17,
+            last_exec_price_e6: 18,
+            liq_heat: 19,
+        };
+        let ctx = MatcherContext {
+            last_return: MatcherReturn { exec_price_e6: 10, reject_reason: 11, ..Default::default() },
+            version: VERSION,
+            kind: KIND_CREDIBILITY,
+            params,
+            inventory: -12,
+            snapshot_slot: 13,
+            flags: 14,
+            liq_heat: 15,
+            extension: Some(ContextExtension {
+                funding_rate_bps_per_slot: -16,
+                slab: Pubkey::new_from_array([17; 32]),
+                gross_short: 18,
+                stats: Pubkey::new_from_array([19; 32]),
+                rfq_signer: Pubkey::new_from_array([20; 32]),
+                rfq_nonce: 21,
+                rfq_size_left: -22,
+                last_liq_slot: 23,
+                ..Default::default()
+            }),
+            markets: Some(markets),
+            ..Default::default()
+        };
+        let data = ctx.to_bytes();
+        let at = |path: &[&str]| -> (Ty, usize) {
+            let mut t = type_def("MatcherContext");
+            let mut off = 0;
+            for (depth, name) in path.iter().enumerate() {
+                let f = t.fields.iter().find(|f| f.name == *name).unwrap_or_else(|| panic!("no field {name}"));
+                off += f.offset;
+                if depth + 1 < path.len() {
+                    let Ty::Defined(inner) = f.ty else { panic!("{name} is not a struct") };
+                    t = type_def(inner);
+                } else {
+                    return (f.ty, off);
+                }
+            }
+            unreachable!()
+        };
+        let value = |path: &[&str]| {
+            let (ty, off) = at(path);
+            read(&data, ty, off)
+        };
+        assert_eq!(data.len(), type_def("MatcherContext").size);
+        assert_eq!(value(&["last_return", "exec_price_e6"]), 10);
+        assert_eq!(value(&["last_return", "reject_reason"]), 11);
+        assert_eq!(value(&["core", "magic"]), MAGIC as u128);
+        assert_eq!(value(&["core", "version"]), VERSION as u128);
+        assert_eq!(value(&["core", "base_fee_bps"]), 3);
+        assert_eq!(value(&["core", "liquidity_e6"]), 4);
+        assert_eq!(value(&["core", "curve_points"]), 5);
+        assert_eq!(value(&["core", "inventory"]) as i128, -12);
+        assert_eq!(value(&["core", "snapshot_slot"]), 13);
+        assert_eq!(value(&["core", "flags"]), 14);
+        assert_eq!(value(&["core", "liq_heat"]), 15);
+        assert_eq!(value(&["extension", "funding_rate_bps_per_slot"]) as u64 as i64, -16);
+        assert_eq!(value(&["extension", "flow_k_bps"]), 9);
+        assert_eq!(value(&["extension", "gross_short"]), 18);
+        let (_, slab) = at(&["extension", "slab"]);
+        assert_eq!(data[slab..slab + 32], [17; 32]);
+        let (_, stats) = at(&["extension", "stats"]);
+        assert_eq!(data[stats..stats + 32], [19; 32]);
+        let (_, signer) = at(&["extension", "rfq_signer"]);
+        assert_eq!(data[signer..signer + 32], [20; 32]);
+        assert_eq!(value(&["extension", "rfq_nonce"]), 21);
+        assert_eq!(value(&["extension", "rfq_size_left"]) as i128, -22);
+        assert_eq!(value(&["extension", "last_liq_slot"]), 23);
+        assert_eq!(value(&["extension", "market_slots"]), MAX_MARKETS as u128);
+        // Field i of the second market holds i + 1 (the slab's first byte)
+        let (_, markets_off) = at(&["markets"]);
+        let book = markets_off + type_def("MarketBook").size;
+        for (i, f) in type_def("MarketBook").fields.iter().enumerate() {
+            assert_eq!(read(&data, f.ty, book + f.offset) as u8, i as u8 + 1, "MarketBook.{}", f.name);
+        }
+    }
+
+    #[test]
+    fn test_instructions_match_client_builders() {
+        let program = Pubkey::new_unique();
+        let [a, b, c, d, e, f] = [(); 6].map(|_| Pubkey::new_unique());
+        let params = MatcherParams::default();
+        let quote = ix::RfqQuote { ctx: a, price_e6: 1, size: -2, expiry_slot: 3, nonce: 4 };
+        let entry = ScheduleEntry { start_slot: 5, end_slot: 6, multiplier_bps: 7 };
+        let built: Vec<(&str, Instruction)> = vec![
+            (
+                "Match",
+                ix::with_trader(
+                    ix::with_referral(
+                        ix::with_schedule(
+                            ix::with_feed(ix::match_trade(&program, &a, &b, Some(&c), Some(&d), &Default::default()).unwrap(), &f, &[a])
+                                .unwrap(),
+                            &e,
+                        )
+                        .unwrap(),
+                        &b,
+                        &f,
+                    )
+                    .unwrap(),
+                    &c,
+                    &d,
+                )
+                .unwrap(),
+            ),
+            ("Init", ix::init(&program, &a, &b, Some(&c), Some(&d), &params).unwrap()),
+            ("InitPlain", ix::init_with_kind(&program, &a, &b, Some(&c), Some(&d), KIND_PLAIN, &params).unwrap()),
+            ("InitMulti", ix::init_with_kind(&program, &a, &b, Some(&c), Some(&d), KIND_MULTI, &params).unwrap()),
+            ("InitStable", ix::init_with_kind(&program, &a, &b, Some(&c), Some(&d), KIND_STABLE, &params).unwrap()),
+            ("UpdateCredibility", ix::with_tip_jar(ix::update_credibility(&program, &a, &b), &c, &d).unwrap()),
+            ("UpdateParams", ix::update_params(&program, &a, &b, &params)),
+            (
+                "Close",
+                ix::with_ladder(ix::with_schedule(ix::with_feed(ix::close(&program, &a, &b, &c, Some(&d)), &f, &[]).unwrap(), &e).unwrap(), &a)
+                    .unwrap(),
+            ),
+            ("SetPaused", ix::set_paused(&program, &a, &b, true)),
+            ("Migrate", ix::migrate(&program, &a, &b)),
+            ("QuotePreview", ix::with_schedule(ix::on_market(ix::quote_preview(&program, &a, Some(&b), 1, -1), 1), &e).unwrap()),
+            ("ProposeParams", ix::propose_params(&program, &a, &b, &params)),
+            ("CommitParams", ix::commit_params(&program, &a)),
+            ("SetReduceOnly", ix::set_reduce_only(&program, &a, &b, true)),
+            ("SettleInventory", ix::on_market(ix::settle_inventory(&program, &a, &b, SETTLE_ADJUST, -1), 1)),
+            ("ProposeAuthority", ix::propose_authority(&program, &a, &b, &c)),
+            ("AcceptAuthority", ix::accept_authority(&program, &a, &b)),
+            ("SetReturnDataOnly", ix::set_return_data_only(&program, &a, &b, true)),
+            ("SetSoftReject", ix::set_soft_reject(&program, &a, &b, true)),
+            ("QuoteTwoSided", ix::with_schedule(ix::on_market(ix::quote_two_sided(&program, &a, Some(&b), 1, 1), 1), &e).unwrap()),
+            ("InitStats", ix::init_stats_with_protocol_fee(&program, &a, &b, &c, &d, 2_000)),
+            ("SetRfqSigner", ix::set_rfq_signer(&program, &a, &b, &c)),
+            ("PostRfq", ix::post_rfq(&program, &quote)),
+            ("SetMarket", ix::set_market(&program, &a, &b, &c, 1, 2)),
+            ("SetNoBids", ix::set_no_bids(&program, &a, &b, true)),
+            ("SetNoAsks", ix::set_no_asks(&program, &a, &b, true)),
+            ("InitSchedule", ix::init_schedule(&program, &a, &b, &c)),
+            ("SetSchedule", ix::set_schedule(&program, &a, &b, &c, &[entry; MAX_SCHEDULE_ENTRIES]).unwrap()),
+            ("InitLadder", ix::init_ladder(&program, &a, &b, &c)),
+            (
+                "PublishLadder",
+                ix::with_schedule(ix::on_market(ix::publish_ladder(&program, &a, &b, &c, Some(&d), 1, 1), 1), &e).unwrap(),
+            ),
+            ("InitFeed", ix::pin_feed(ix::init_feed(&program, &a, &b, &c, &d, 30, FEED_SOURCE_SWITCHBOARD)).unwrap()),
+            ("AddFeed", ix::pin_feed(ix::add_feed(&program, &a, &b, &c, &d, FEED_SOURCE_PYTH)).unwrap()),
+            ("SetFeedDivergence", ix::set_feed_divergence(&program, &a, &b, &c, 150, true)),
+            ("SetOracleDecimals", ix::set_oracle_decimals(&program, &a, &b, 9)),
+            ("SetFeedSlotAge", ix::set_feed_slot_age(&program, &a, &b, &c, 300, true)),
+            ("SetFeedFallback", ix::set_feed_fallback(&program, &a, &b, &c, true, [0, 25, 50])),
+            ("SetFeeTiers", ix::set_fee_tiers(&program, &a, &b, &c, &[FeeTier::default(); MAX_FEE_TIERS]).unwrap()),
+            ("SetRebate", ix::set_rebate(&program, &a, &b, &c, 5)),
+            ("RecordWithdrawal", ix::record_withdrawal(&program, &a, &b, &c, 1)),
+            ("SetFeeDiscount", ix::set_fee_discount(&program, &a, &b, &c, 5)),
+            ("InitTipJar", ix::init_tip_jar(&program, &a, &b, &c, 10, 5_000)),
+            ("SetTip", ix::set_tip(&program, &a, &b, &c, 10, 5_000)),
+            ("CloseTipJar", ix::close_tip_jar(&program, &a, &b, &c, &d)),
+            ("InitReferral", ix::init_referral(&program, &a, &b, &c, 2_000)),
+            ("SetReferralShare", ix::set_referral_share(&program, &a, &b, &c, 2_000)),
+            ("SetMinNotional", ix::set_min_notional(&program, &a, &b, &c, 1_000)),
+            ("SetSkim", ix::set_skim(&program, &a, &b, &c, 2_500)),
+            ("ClaimSkim", ix::claim_skim(&program, &ix::SkimClaim { authority: a, ctx: b, stats: c, amount: 1, ..Default::default() })),
+            ("InitTrader", ix::init_trader(&program, &a, &b, &c)),
+            ("SetLoyaltyTiers", ix::set_loyalty_tiers(&program, &a, &b, &c, &[LoyaltyTier::default(); MAX_LOYALTY_TIERS]).unwrap()),
+            ("SweepProtocolFee", ix::sweep_protocol_fee(&program, &a, &b, &c, 1)),
+            ("SetInventoryDecay", ix::set_inventory_decay(&program, &a, &b, &c, 300)),
+            ("SetInventoryBand", ix::set_inventory_band(&program, &a, &b, &c, 2_000_000, 100)),
+            ("SetInventoryCaps", ix::set_inventory_caps(&program, &a, &b, &c, 0, 500_000)),
+            ("SetReduceOnlyThreshold", ix::set_reduce_only_threshold(&program, &a, &b, &c, 8_000)),
+            ("TransferInventory", ix::on_market(ix::on_market(ix::transfer_inventory(&program, &a, &b, &c, &d, -500), 0), 1)),
+            ("SetInventoryDepthCap", ix::set_inventory_depth_cap(&program, &a, &b, &c, 30_000)),
+            ("SyncInventory", ix::sync_inventory(&program, &a, &b, 3)),
+            ("CreateContext", ix::create_context(&program, &a, &b, &c, 880)),
+            ("BindSlab", ix::bind_slab(&program, &a, &b, &c)),
+            ("SetSignals", ix::set_signals(&program, &a, &b, &c, &[])),
+        ];
+        assert_eq!(built.len(), INSTRUCTIONS.len());
+        for ((name, built), def) in built.iter().zip(INSTRUCTIONS) {
+            assert_eq!(*name, def.name);
+            assert_eq!(built.data.len(), def.data_len, "{name} data length");
+            assert!(built.data.starts_with(def.discriminator), "{name} discriminator");
+            assert_eq!(built.accounts.len(), def.accounts.len(), "{name} accounts");
+            for (meta, def) in built.accounts.iter().zip(def.accounts) {
+                assert_eq!((meta.is_writable, meta.is_signer), (def.writable, def.signer), "{name}.{}", def.name);
+                if let Some(address) = def.address {
+                    assert_eq!(meta.pubkey.to_string(), address, "{name}.{}", def.name);
+                }
+            }
+            if let Some(last) = def.args.last() {
+                assert!(last.offset + last.ty.size() <= def.data_len, "{name} args overrun");
+            }
+        }
+        let post = INSTRUCTIONS.iter().find(|def| def.name == "PostRfq").unwrap();
+        let fields = type_def("RfqQuote").fields;
+        let quote_at = |name: &str| post.args[0].offset + fields.iter().find(|f| f.name == name).unwrap().offset;
+        let data = &built.iter().find(|(name, _)| *name == "PostRfq").unwrap().1.data;
+        assert_eq!(data[quote_at("ctx")..quote_at("ctx") + 32], a.to_bytes());
+        assert_eq!(read(data, Ty::I128, quote_at("size")) as i128, -2);
+        assert_eq!(read(data, Ty::U64, quote_at("nonce")), 4);
+        let set = INSTRUCTIONS.iter().find(|def| def.name == "SetSchedule").unwrap();
+        let fields = type_def("ScheduleEntryArgs").fields;
+        let last = set.args[1].offset + (MAX_SCHEDULE_ENTRIES - 1) * SCHED_ARG_LEN;
+        let data = &built.iter().find(|(name, _)| *name == "SetSchedule").unwrap().1.data;
+        assert_eq!(read(data, Ty::U64, last + fields[1].offset), 6);
+        assert_eq!(read(data, Ty::U32, last + fields[2].offset), 7);
+        // Cancelling a proposal drops the optional block
+        let propose = INSTRUCTIONS.iter().find(|def| def.name == "ProposeParams").unwrap();
+        assert_eq!(ix::cancel_params(&program, &a, &b).data.len(), propose.args[0].offset);
+        // So does quoting a single-market context
+        let preview = INSTRUCTIONS.iter().find(|def| def.name == "QuotePreview").unwrap();
+        assert_eq!(ix::quote_preview(&program, &a, None, 1, 1).data.len(), preview.args[2].offset);
+    }
+
+    #[test]
+    fn test_generated_files_are_current() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+        let json = std::fs::read_to_string(dir.join(crate::JSON_PATH)).unwrap();
+        let ts = std::fs::read_to_string(dir.join(crate::TS_PATH)).unwrap();
+        let vectors = std::fs::read_to_string(dir.join(crate::VECTORS_PATH)).unwrap();
+        let hint = "stale; run `cargo run -p provenance-idl`";
+        assert!(json == render_json(), "{} {hint}", crate::JSON_PATH);
+        assert!(ts == typescript(), "{} {hint}", crate::TS_PATH);
+        assert!(vectors == render_vectors(), "{} {hint}", crate::VECTORS_PATH);
+    }
+}
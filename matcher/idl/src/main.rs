@@ -0,0 +1,31 @@
+//! Write the IDL, the TypeScript bindings and the quote vectors, or with
+//! `--check`, fail when the checked-in copies are stale.
+
+use std::{fs, path::Path, process};
+
+use provenance_idl::{render_json, render_vectors, typescript, JSON_PATH, TS_PATH, VECTORS_PATH};
+
+fn main() {
+    let check = std::env::args().skip(1).any(|arg| arg == "--check");
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut stale = false;
+    for (path, contents) in [(JSON_PATH, render_json()), (TS_PATH, typescript()), (VECTORS_PATH, render_vectors())] {
+        let path = dir.join(path);
+        if fs::read_to_string(&path).ok().as_deref() == Some(contents.as_str()) {
+            continue;
+        }
+        if check {
+            eprintln!("{} is stale", path.display());
+            stale = true;
+        } else if let Err(err) = fs::create_dir_all(path.parent().unwrap()).and_then(|()| fs::write(&path, contents)) {
+            eprintln!("error: {}: {err}", path.display());
+            process::exit(1);
+        } else {
+            println!("wrote {}", path.display());
+        }
+    }
+    if stale {
+        eprintln!("run `cargo run -p provenance-idl` to regenerate");
+        process::exit(1);
+    }
+}
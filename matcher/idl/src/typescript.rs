@@ -0,0 +1,320 @@
+//! Render the IDL tables as a TypeScript module for `@solana/web3.js`:
+//! constants, error lookup, a decoder per type, an encoder per instruction
+//! argument type, and one instruction builder per instruction.
+
+use std::fmt::Write;
+
+use credibility_matcher::error::MatcherError;
+use provenance_core::TIER_NAMES;
+
+use crate::{Field, InstructionDef, Ty, TypeDef, CONSTANTS, INSTRUCTIONS, TYPES};
+
+const HEADER: &str = r#"// Generated by provenance-idl from the matcher's Rust definitions. Do not edit:
+// run `cargo run -p provenance-idl` in matcher/ after changing the program.
+
+import { PublicKey, TransactionInstruction } from "@solana/web3.js";
+
+function view(data: Uint8Array): DataView {
+  return new DataView(data.buffer, data.byteOffset, data.byteLength);
+}
+
+function readU128(dv: DataView, off: number): bigint {
+  return dv.getBigUint64(off, true) | (dv.getBigUint64(off + 8, true) << 64n);
+}
+
+function readI128(dv: DataView, off: number): bigint {
+  return BigInt.asIntN(128, readU128(dv, off));
+}
+
+function writeU128(dv: DataView, off: number, value: bigint): void {
+  const bits = BigInt.asUintN(128, value);
+  dv.setBigUint64(off, bits & 0xffff_ffff_ffff_ffffn, true);
+  dv.setBigUint64(off + 8, bits >> 64n, true);
+}
+
+interface Meta {
+  pubkey: PublicKey;
+  isSigner: boolean;
+  isWritable: boolean;
+}
+
+/** Optional accounts are positional: one can only follow every optional account before it. */
+function accountMetas(entries: [string, PublicKey | undefined, boolean, boolean, boolean][]): Meta[] {
+  const keys: Meta[] = [];
+  let gap: string | undefined;
+  for (const [name, pubkey, isSigner, isWritable, optional] of entries) {
+    if (pubkey === undefined) {
+      if (!optional) throw new Error(`missing account ${name}`);
+      gap ??= name;
+      continue;
+    }
+    if (gap !== undefined) throw new Error(`account ${name} needs ${gap} before it`);
+    keys.push({ pubkey, isSigner, isWritable });
+  }
+  return keys;
+}
+"#;
+
+/// The bindings module, as written to [`crate::TS_PATH`].
+pub fn typescript() -> String {
+    let mut out = String::from(HEADER);
+
+    out.push_str("\n// Constants\n\n");
+    for c in CONSTANTS {
+        let value = match c.ty {
+            Ty::U64 => format!("0x{:x}n", c.value),
+            _ => c.value.to_string(),
+        };
+        writeln!(out, "export const {} = {value};", c.name).unwrap();
+    }
+    for ix in INSTRUCTIONS {
+        writeln!(out, "export const TAG_{} = 0x{:02x};", screaming(ix.name), ix.discriminator[0]).unwrap();
+    }
+    let tiers: Vec<String> = TIER_NAMES.iter().map(|t| format!("\"{t}\"")).collect();
+    writeln!(out, "\n/** Coverage tier names, indexed by QuoteBreakdown.tier. */").unwrap();
+    writeln!(out, "export const TIER_NAMES = [{}] as const;", tiers.join(", ")).unwrap();
+
+    out.push_str("\n// Errors\n\n");
+    out.push_str("export interface MatcherErrorInfo {\n  code: number;\n  name: string;\n  message: string;\n}\n\n");
+    out.push_str("/** `custom program error` codes; never renumbered. */\n");
+    out.push_str("export const MATCHER_ERRORS: MatcherErrorInfo[] = [\n");
+    for e in MatcherError::ALL {
+        writeln!(out, "  {{ code: {}, name: \"{e:?}\", message: {:?} }},", *e as u32, e.message()).unwrap();
+    }
+    out.push_str("];\n\n");
+    out.push_str("export function matcherError(code: number): MatcherErrorInfo | undefined {\n");
+    out.push_str("  return MATCHER_ERRORS.find((e) => e.code === code);\n}\n");
+
+    out.push_str("\n// Types\n");
+    let encoded: Vec<&str> = INSTRUCTIONS
+        .iter()
+        .flat_map(|ix| ix.args)
+        .filter_map(|f| match f.ty {
+            Ty::Defined(name) => Some(name),
+            _ => None,
+        })
+        .collect();
+    for t in TYPES {
+        render_type(&mut out, t, encoded.contains(&t.name));
+    }
+
+    out.push_str("\n// Instructions\n");
+    for ix in INSTRUCTIONS {
+        render_instruction(&mut out, ix);
+    }
+    out
+}
+
+fn render_type(out: &mut String, t: &TypeDef, encoder: bool) {
+    writeln!(out, "\n/** {} */", t.docs).unwrap();
+    writeln!(out, "export interface {} {{", t.name).unwrap();
+    for f in t.fields {
+        let opt = if f.optional { "?" } else { "" };
+        writeln!(out, "  {}{opt}: {};", camel(f.name), ts_type(&f.ty)).unwrap();
+    }
+    out.push_str("}\n\n");
+    writeln!(out, "export const {}_SIZE = {};\n", screaming(t.name), t.size).unwrap();
+
+    writeln!(out, "export function decode{}(data: Uint8Array, offset = 0): {} {{", t.name, t.name).unwrap();
+    if needs_view(t.fields) {
+        out.push_str("  const dv = view(data);\n");
+    }
+    out.push_str("  return {\n");
+    for f in t.fields {
+        let at = format!("offset + {}", f.offset);
+        let read = read_expr(&f.ty, &at);
+        if f.optional {
+            let end = f.offset + f.ty.size();
+            writeln!(out, "    {}: data.length >= offset + {end} ? {read} : undefined,", camel(f.name)).unwrap();
+        } else {
+            writeln!(out, "    {}: {read},", camel(f.name)).unwrap();
+        }
+    }
+    out.push_str("  };\n}\n");
+
+    if encoder {
+        writeln!(out, "\nexport function encode{}(value: {}, data: Uint8Array, offset = 0): void {{", t.name, t.name).unwrap();
+        if needs_view(t.fields) {
+            out.push_str("  const dv = view(data);\n");
+        }
+        for f in t.fields {
+            let line = write_stmt(&f.ty, &format!("offset + {}", f.offset), &format!("value.{}", camel(f.name)));
+            writeln!(out, "  {line}").unwrap();
+        }
+        out.push_str("}\n");
+    }
+}
+
+fn render_instruction(out: &mut String, ix: &InstructionDef) {
+    let name = lower_first(ix.name);
+    let accounts: Vec<_> = ix.accounts.iter().filter(|a| a.address.is_none()).collect();
+    writeln!(out, "\nexport interface {}Accounts {{", ix.name).unwrap();
+    for a in &accounts {
+        let opt = if a.optional { "?" } else { "" };
+        writeln!(out, "  {}{opt}: PublicKey;", camel(a.name)).unwrap();
+    }
+    out.push_str("}\n");
+    if !ix.args.is_empty() {
+        writeln!(out, "\nexport interface {}Args {{", ix.name).unwrap();
+        for f in ix.args {
+            let opt = if f.optional { "?" } else { "" };
+            writeln!(out, "  {}{opt}: {};", camel(f.name), ts_type(&f.ty)).unwrap();
+        }
+        out.push_str("}\n");
+    }
+
+    let returns = match ix.returns {
+        Some(t) => format!(" Return data: decode{t}."),
+        None => String::new(),
+    };
+    writeln!(out, "\n/** {} (0x{:02x}): {}{returns} */", ix.name, ix.discriminator[0], ix.docs).unwrap();
+    let args = if ix.args.is_empty() { String::new() } else { format!(", args: {}Args", ix.name) };
+    writeln!(
+        out,
+        "export function {name}Instruction(programId: PublicKey, accounts: {}Accounts{args}): TransactionInstruction {{",
+        ix.name
+    )
+    .unwrap();
+
+    let len = match ix.args.last() {
+        Some(last) if last.optional => {
+            format!("args.{} === undefined ? {} : {}", camel(last.name), last.offset, ix.data_len)
+        }
+        _ => ix.data_len.to_string(),
+    };
+    writeln!(out, "  const data = new Uint8Array({len});").unwrap();
+    let discriminator: Vec<String> = ix.discriminator.iter().map(|b| format!("0x{b:02x}")).collect();
+    writeln!(out, "  data.set([{}]);", discriminator.join(", ")).unwrap();
+    if needs_view(ix.args) {
+        out.push_str("  const dv = view(data);\n");
+    }
+    for f in ix.args {
+        let value = format!("args.{}", camel(f.name));
+        let stmt = write_stmt(&f.ty, &f.offset.to_string(), &value);
+        if f.optional {
+            writeln!(out, "  if ({value} !== undefined) {stmt}").unwrap();
+        } else {
+            writeln!(out, "  {stmt}").unwrap();
+        }
+    }
+
+    out.push_str("  const keys = accountMetas([\n");
+    for a in ix.accounts {
+        let key = match a.address {
+            Some(address) => format!("new PublicKey(\"{address}\")"),
+            None => format!("accounts.{}", camel(a.name)),
+        };
+        writeln!(out, "    [\"{}\", {key}, {}, {}, {}],", a.name, a.signer, a.writable, a.optional).unwrap();
+    }
+    out.push_str("  ]);\n");
+    out.push_str("  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });\n}\n");
+}
+
+/// Whether reading or writing `fields` goes through a DataView.
+fn needs_view(fields: &[Field]) -> bool {
+    fn scalar(ty: &Ty) -> bool {
+        match ty {
+            Ty::Pubkey | Ty::Bytes(_) | Ty::Defined(_) => false,
+            Ty::Array(inner, _) => scalar(inner),
+            _ => true,
+        }
+    }
+    fields.iter().any(|f| scalar(&f.ty))
+}
+
+fn ts_type(ty: &Ty) -> String {
+    match ty {
+        Ty::U8 | Ty::U16 | Ty::U32 => "number".into(),
+        Ty::U64 | Ty::I64 | Ty::U128 | Ty::I128 => "bigint".into(),
+        Ty::Bool => "boolean".into(),
+        Ty::Pubkey => "PublicKey".into(),
+        Ty::Bytes(_) => "Uint8Array".into(),
+        Ty::Array(inner, _) => format!("{}[]", ts_type(inner)),
+        Ty::Defined(name) => name.to_string(),
+    }
+}
+
+fn read_expr(ty: &Ty, at: &str) -> String {
+    match ty {
+        Ty::U8 => format!("dv.getUint8({at})"),
+        Ty::U16 => format!("dv.getUint16({at}, true)"),
+        Ty::U32 => format!("dv.getUint32({at}, true)"),
+        Ty::U64 => format!("dv.getBigUint64({at}, true)"),
+        Ty::I64 => format!("dv.getBigInt64({at}, true)"),
+        Ty::U128 => format!("readU128(dv, {at})"),
+        Ty::I128 => format!("readI128(dv, {at})"),
+        Ty::Bool => format!("dv.getUint8({at}) !== 0"),
+        Ty::Pubkey => format!("new PublicKey(data.slice({at}, {at} + 32))"),
+        Ty::Bytes(len) => format!("data.slice({at}, {at} + {len})"),
+        Ty::Array(inner, len) => {
+            let item = read_expr(inner, &format!("{at} + i * {}", inner.size()));
+            format!("Array.from({{ length: {len} }}, (_, i) => {item})")
+        }
+        Ty::Defined(name) => format!("decode{name}(data, {at})"),
+    }
+}
+
+fn write_stmt(ty: &Ty, at: &str, value: &str) -> String {
+    match ty {
+        Ty::U8 => format!("dv.setUint8({at}, {value});"),
+        Ty::U16 => format!("dv.setUint16({at}, {value}, true);"),
+        Ty::U32 => format!("dv.setUint32({at}, {value}, true);"),
+        Ty::U64 => format!("dv.setBigUint64({at}, {value}, true);"),
+        Ty::I64 => format!("dv.setBigInt64({at}, {value}, true);"),
+        Ty::U128 | Ty::I128 => format!("writeU128(dv, {at}, {value});"),
+        Ty::Bool => format!("dv.setUint8({at}, {value} ? 1 : 0);"),
+        Ty::Pubkey => format!("data.set({value}.toBytes(), {at});"),
+        Ty::Bytes(_) => format!("data.set({value}, {at});"),
+        Ty::Array(inner, _) => {
+            let item = write_stmt(inner, &format!("{at} + i * {}", inner.size()), "item");
+            format!("{value}.forEach((item, i) => {{ {item} }});")
+        }
+        Ty::Defined(name) => format!("encode{name}({value}, data, {at});"),
+    }
+}
+
+/// `base_fee_bps` -> `baseFeeBps`
+fn camel(snake: &str) -> String {
+    let mut out = String::new();
+    let mut upper = false;
+    for c in snake.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            out.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// `QuoteBreakdown` -> `QUOTE_BREAKDOWN`
+fn screaming(pascal: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in pascal.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            out.push('_');
+        }
+        out.extend(c.to_uppercase());
+    }
+    out
+}
+
+fn lower_first(s: &str) -> String {
+    let mut chars = s.chars();
+    chars.next().map(|c| c.to_lowercase().chain(chars).collect()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(camel("oracle_price_e6"), "oraclePriceE6");
+        assert_eq!(screaming("UpdateCredibility"), "UPDATE_CREDIBILITY");
+        assert_eq!(lower_first("QuotePreview"), "quotePreview");
+    }
+}
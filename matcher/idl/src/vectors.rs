@@ -0,0 +1,401 @@
+//! Golden vectors for the quote math: canonical (params, state, oracle, size)
+//! inputs with everything `provenance_core::quote` decided for them, written
+//! to [`crate::VECTORS_PATH`] for every other implementation to check itself
+//! against.
+//!
+//! Integers wider than 32 bits are decimal strings, so JavaScript can read
+//! them as `BigInt` without losing precision; narrower ones are numbers. A
+//! rejected trade carries `"error"` (the `QuoteError` variant in snake case)
+//! instead of `"expect"`.
+
+use provenance_core::{
+    quote, Params, Quote, QuoteError, State, CURVE_PIECEWISE, CURVE_SQRT, IMPACT_LINEAR, IMPACT_SQRT,
+};
+use serde_json::{json, Value};
+
+/// How a field is written to and read from JSON.
+trait Num: Sized {
+    fn to_json(&self) -> Value;
+    fn from_json(v: &Value) -> Option<Self>;
+}
+
+macro_rules! narrow {
+    ($($ty:ty),*) => {$(
+        impl Num for $ty {
+            fn to_json(&self) -> Value {
+                json!(self)
+            }
+            fn from_json(v: &Value) -> Option<Self> {
+                v.as_u64()?.try_into().ok()
+            }
+        }
+    )*};
+}
+
+macro_rules! wide {
+    ($($ty:ty),*) => {$(
+        impl Num for $ty {
+            fn to_json(&self) -> Value {
+                self.to_string().into()
+            }
+            fn from_json(v: &Value) -> Option<Self> {
+                v.as_str()?.parse().ok()
+            }
+        }
+    )*};
+}
+
+narrow!(u8, u16, u32);
+wide!(u64, i64, u128, i128);
+
+impl Num for bool {
+    fn to_json(&self) -> Value {
+        json!(self)
+    }
+    fn from_json(v: &Value) -> Option<Self> {
+        v.as_bool()
+    }
+}
+
+impl<const N: usize> Num for [u16; N] {
+    fn to_json(&self) -> Value {
+        self.iter().map(Num::to_json).collect()
+    }
+    fn from_json(v: &Value) -> Option<Self> {
+        let items = v.as_array()?;
+        items.iter().map(u16::from_json).collect::<Option<Vec<_>>>()?.try_into().ok()
+    }
+}
+
+impl Num for Option<u64> {
+    fn to_json(&self) -> Value {
+        self.as_ref().map_or(Value::Null, Num::to_json)
+    }
+    fn from_json(v: &Value) -> Option<Self> {
+        if v.is_null() { Some(None) } else { u64::from_json(v).map(Some) }
+    }
+}
+
+/// `to_json` and `from_json` for a struct, from one list of its fields.
+macro_rules! codec {
+    ($ty:ident, $to:ident, $from:ident { $($field:ident),* $(,)? }) => {
+        fn $to(v: &$ty) -> Value {
+            Value::Object([$((stringify!($field).to_string(), v.$field.to_json())),*].into_iter().collect())
+        }
+
+        #[doc = concat!("Read a [`", stringify!($ty), "`] as written to the vectors file.")]
+        pub fn $from(v: &Value) -> Result<$ty, String> {
+            Ok($ty {
+                $($field: Num::from_json(&v[stringify!($field)])
+                    .ok_or(concat!(stringify!($ty), ".", stringify!($field), " missing or out of range"))?),*
+            })
+        }
+    };
+}
+
+codec!(Params, params_json, params_from_json {
+    base_fee_bps, min_spread_bps, max_spread_bps, imbalance_k_bps, liquidity_e6, max_fill, max_inventory,
+    age_halflife, insurance_weight_bps, impact_mode, impact_k_bps, skew_k_bps, coverage_curve, curve_points, liq_halflife,
+    funding_horizon, oracle_jump_bps, oracle_jump_window, flow_k_bps, twap_window_slots, signal_weights,
+    deficit_halflife, liq_auction_bps, liq_auction_slots, peg_band_e6, spot_weight_bps,
+});
+
+codec!(State, state_json, state_from_json {
+    current_slot, inventory, insurance_snapshot, total_oi_snapshot, snapshot_slot, last_crank_slot, liq_heat,
+    funding_rate_bps_per_slot, last_oracle_price_e6, last_oracle_slot, gross_long, gross_short, reduce_only,
+    twap_price_e6, market_age, last_deficit_slot, last_liq_slot, signal_bps, spread_mult_bps, oracle_bps, rebate_bps,
+    fee_discount_bps, inventory_halflife, soft_inventory, band_k_bps, max_long_inventory, max_short_inventory,
+    reduce_only_bps,
+});
+
+codec!(Quote, quote_json, quote_from_json {
+    discount_bps, coverage_bps, tier, fill_pct, tier_spread_bps, imbalance_bps, impact_bps, stale_bps,
+    crank_bps, liq_bps, flow_bps, signal_bps, schedule_bps, oracle_bps, spread_bps, skew_bps, funding_skew_bps, fee_bps, rebate_bps,
+    anchor_price_e6, exec_price_e6, fill_size, new_inventory,
+});
+
+pub fn error_name(err: &QuoteError) -> &'static str {
+    match err {
+        QuoteError::ZeroOraclePrice => "zero_oracle_price",
+        QuoteError::OracleJump { .. } => "oracle_jump",
+        QuoteError::StatsRequired => "stats_required",
+        QuoteError::InventoryOverflow { .. } => "inventory_overflow",
+        QuoteError::ReduceOnly { .. } => "reduce_only",
+        QuoteError::InventoryLimit => "inventory_limit",
+    }
+}
+
+/// One canonical input.
+pub struct Case {
+    pub name: &'static str,
+    pub params: Params,
+    pub state: State,
+    pub oracle_price_e6: u64,
+    pub trade_size: i128,
+}
+
+const ORACLE: u64 = 100_000_000;
+const SLOT: u64 = 1_000_000;
+
+fn params() -> Params {
+    Params {
+        base_fee_bps: 5,
+        min_spread_bps: 10,
+        max_spread_bps: 200,
+        imbalance_k_bps: 100,
+        liquidity_e6: 10_000_000_000_000,
+        max_fill: 1_000_000_000_000,
+        insurance_weight_bps: 50,
+        ..Default::default()
+    }
+}
+
+/// Fresh snapshot and crank at `insurance` per 100 of open interest.
+fn state(insurance: u128) -> State {
+    State {
+        current_slot: SLOT,
+        insurance_snapshot: insurance * 10_000_000_000,
+        total_oi_snapshot: 1_000_000_000_000,
+        snapshot_slot: SLOT - 100,
+        last_crank_slot: SLOT - 50,
+        ..Default::default()
+    }
+}
+
+fn case(name: &'static str, params: Params, state: State, trade_size: i128) -> Case {
+    Case { name, params, state, oracle_price_e6: ORACLE, trade_size }
+}
+
+/// The vectors: every tier and curve, each spread and skew term on its own,
+/// the clamps, the fill clip and every rejection.
+pub fn cases() -> Vec<Case> {
+    let p = params();
+    let normal = state(50);
+    vec![
+        case("critical_buy", p, state(5), 1_000_000),
+        case("critical_clips_fill", p, state(5), 1_000_000_000_000),
+        case("fragile_sell", p, state(15), -1_000_000),
+        case("normal_linear", p, normal, 1_000_000),
+        case("normal_sqrt", Params { coverage_curve: CURVE_SQRT, ..p }, normal, 1_000_000),
+        case(
+            "normal_piecewise",
+            Params { coverage_curve: CURVE_PIECEWISE, curve_points: [1_000, 3_000, 6_000, 8_500], ..p },
+            normal,
+            1_000_000,
+        ),
+        case("normal_weight_floors_at_min_spread", Params { insurance_weight_bps: 1_000, ..p }, state(99), 1_000_000),
+        case("strong_sell", p, state(150), -1_000_000),
+        case("fortified_buy", p, state(300), 1_000_000),
+        case("fortified_clips_at_bonus", p, state(300), 2_000_000_000_000),
+        case("no_open_interest", p, State { total_oi_snapshot: 0, insurance_snapshot: 0, ..normal }, 1_000_000),
+        case("insurance_without_open_interest", p, State { total_oi_snapshot: 0, ..normal }, 1_000_000),
+        case("imbalance_short_book", p, State { inventory: 5_000_000_000_000, ..normal }, 1_000_000),
+        case(
+            "skew_short_book_sell",
+            Params { skew_k_bps: 50, ..p },
+            State { inventory: 4_000_000_000_000, ..normal },
+            -1_000_000,
+        ),
+        case(
+            "skew_long_book_buy",
+            Params { skew_k_bps: 50, ..p },
+            State { inventory: -4_000_000_000_000, ..normal },
+            1_000_000,
+        ),
+        case(
+            "skew_capped_by_spread",
+            Params { skew_k_bps: 10_000, ..p },
+            State { inventory: 9_000_000_000_000, ..state(300) },
+            1_000_000,
+        ),
+        case("funding_skew_longs_pay", Params { funding_horizon: 10, ..p }, State { funding_rate_bps_per_slot: 2, ..normal }, 1_000_000),
+        case("funding_skew_shorts_pay", Params { funding_horizon: 10, ..p }, State { funding_rate_bps_per_slot: -2, ..normal }, -1_000_000),
+        case("impact_linear", Params { impact_mode: IMPACT_LINEAR, impact_k_bps: 30, ..p }, normal, 500_000_000_000),
+        case("impact_sqrt", Params { impact_mode: IMPACT_SQRT, impact_k_bps: 30, ..p }, normal, 500_000_000_000),
+        case("stale_snapshot", p, State { snapshot_slot: SLOT - 9_000, ..normal }, 1_000_000),
+        case("crank_lag", p, State { last_crank_slot: SLOT - 2_150, ..normal }, 1_000_000),
+        case(
+            "liquidation_heat_decays",
+            Params { liq_halflife: 100, ..p },
+            State { liq_heat: 3_000, snapshot_slot: SLOT - 150, ..normal },
+            1_000_000,
+        ),
+        case(
+            "liquidation_auction_decays",
+            Params { liq_auction_bps: 200, liq_auction_slots: 40, ..p },
+            State { last_liq_slot: SLOT - 10, ..normal },
+            1_000_000,
+        ),
+        case(
+            "one_way_flow_continued",
+            Params { flow_k_bps: 40, ..p },
+            State { gross_long: 9_000_000_000_000, gross_short: 1_000_000_000_000, ..normal },
+            1_000_000,
+        ),
+        case(
+            "one_way_flow_reversed",
+            Params { flow_k_bps: 40, ..p },
+            State { gross_long: 9_000_000_000_000, gross_short: 1_000_000_000_000, ..normal },
+            -1_000_000,
+        ),
+        case(
+            "twap_anchor",
+            Params { twap_window_slots: 50, ..p },
+            State { twap_price_e6: Some(99_000_000), ..normal },
+            1_000_000,
+        ),
+        case(
+            "twap_blended_with_spot",
+            Params { twap_window_slots: 50, spot_weight_bps: 3_000, ..p },
+            State { twap_price_e6: Some(99_000_000), ..normal },
+            1_000_000,
+        ),
+        case("external_signals", p, State { signal_bps: 30, ..normal }, 1_000_000),
+        case("spread_schedule", p, State { spread_mult_bps: 12_000, ..normal }, 1_000_000),
+        case("oracle_divergence", p, State { oracle_bps: 20, ..normal }, 1_000_000),
+        case("inventory_rebate", p, State { inventory: 5_000_000, rebate_bps: 3, ..normal }, -1_000_000),
+        case("coverage_fee_discount", p, State { fee_discount_bps: 10_000, ..state(300) }, 1_000_000),
+        case(
+            "inventory_decay",
+            p,
+            State { inventory: 5_000_000_000_000, last_oracle_slot: SLOT - 400, inventory_halflife: 200, ..normal },
+            1_000_000,
+        ),
+        case(
+            "inventory_band",
+            Params { max_inventory: 100_000_000, ..p },
+            State { inventory: 80_000_000, soft_inventory: 50_000_000, band_k_bps: 100, ..normal },
+            1_000_000,
+        ),
+        case("spread_capped_at_max", p, State { snapshot_slot: SLOT - 100_000, ..state(5) }, 1_000_000),
+        case("zero_max_spread_floors_at_one", Params { min_spread_bps: 0, max_spread_bps: 0, ..p }, normal, 1_000_000),
+        case("zero_size", p, normal, 0),
+        Case { oracle_price_e6: 0, ..case("zero_oracle_price", p, normal, 1_000_000) },
+        case(
+            "oracle_jump",
+            Params { oracle_jump_bps: 100, oracle_jump_window: 10, ..p },
+            State { last_oracle_price_e6: 97_000_000, last_oracle_slot: SLOT - 5, ..normal },
+            1_000_000,
+        ),
+        case("stats_required", Params { twap_window_slots: 50, ..p }, normal, 1_000_000),
+        case("reduce_only_flag", p, State { inventory: 1_000_000, reduce_only: true, ..normal }, 1_000_000),
+        case(
+            "reduce_only_near_cap",
+            Params { max_inventory: 100_000_000, ..p },
+            State { inventory: -96_000_000, ..normal },
+            -1_000_000,
+        ),
+        case(
+            "reduce_only_early_threshold",
+            Params { max_inventory: 100_000_000, ..p },
+            State { inventory: 80_000_000, reduce_only_bps: 8_000, ..normal },
+            1_000_000,
+        ),
+        case("inventory_headroom", Params { max_inventory: 100_000_000, ..p }, State { inventory: 90_000_000, ..normal }, 20_000_000),
+        case(
+            "inventory_headroom_short_side",
+            Params { max_inventory: 100_000_000, ..p },
+            State { inventory: 40_000_000, max_short_inventory: 50_000_000, ..normal },
+            20_000_000,
+        ),
+        case("inventory_limit", Params { max_inventory: 100_000_000, ..p }, State { inventory: 120_000_000, ..normal }, -1_000_000),
+        case("inventory_overflow", p, State { inventory: i128::MAX, ..normal }, 1),
+    ]
+}
+
+/// A case as written to the file, with the result.
+pub fn vector(case: &Case) -> Value {
+    let mut v = json!({
+        "name": case.name,
+        "params": params_json(&case.params),
+        "state": state_json(&case.state),
+        "oracle_price_e6": case.oracle_price_e6.to_json(),
+        "trade_size": case.trade_size.to_json(),
+    });
+    match quote(&case.params, &case.state, case.oracle_price_e6, case.trade_size) {
+        Ok(q) => v["expect"] = quote_json(&q),
+        Err(err) => v["error"] = error_name(&err).into(),
+    }
+    v
+}
+
+/// The vectors file, as written to [`crate::VECTORS_PATH`].
+pub fn render_vectors() -> String {
+    let doc = json!({
+        "description": "Golden vectors for the credibility matcher's quote: generated by provenance-idl from provenance-core. Integers wider than 32 bits are decimal strings.",
+        "vectors": cases().iter().map(vector).collect::<Vec<_>>(),
+    });
+    let mut out = serde_json::to_string_pretty(&doc).expect("vectors serialize");
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Price every checked-in vector from its written inputs alone.
+    #[test]
+    fn test_checked_in_vectors_reprice() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+        let text = std::fs::read_to_string(dir.join(crate::VECTORS_PATH)).unwrap();
+        let doc: Value = serde_json::from_str(&text).unwrap();
+        let vectors = doc["vectors"].as_array().unwrap();
+        assert_eq!(vectors.len(), cases().len());
+
+        let (mut fills, mut rejects) = (0, 0);
+        for v in vectors {
+            let name = v["name"].as_str().unwrap();
+            let params = params_from_json(&v["params"]).unwrap();
+            let state = state_from_json(&v["state"]).unwrap();
+            let oracle = u64::from_json(&v["oracle_price_e6"]).unwrap();
+            let size = i128::from_json(&v["trade_size"]).unwrap();
+            match quote(&params, &state, oracle, size) {
+                Ok(q) => {
+                    assert_eq!(Ok(q), quote_from_json(&v["expect"]), "{name}");
+                    fills += 1;
+                }
+                Err(err) => {
+                    assert_eq!(v["error"], error_name(&err), "{name}");
+                    rejects += 1;
+                }
+            }
+        }
+        assert_eq!((fills, rejects), (40, 8));
+    }
+
+    #[test]
+    fn test_vectors_cover_what_they_say() {
+        let names: Vec<&str> = cases().iter().map(|c| c.name).collect();
+        let mut unique = names.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), names.len(), "duplicate vector names");
+
+        for c in cases() {
+            let result = quote(&c.params, &c.state, c.oracle_price_e6, c.trade_size);
+            // Names that promise an error, or a term, deliver it
+            match (c.name, result) {
+                ("zero_oracle_price", r) => assert_eq!(r, Err(QuoteError::ZeroOraclePrice)),
+                ("stats_required", r) => assert_eq!(r, Err(QuoteError::StatsRequired)),
+                ("inventory_limit", r) => assert_eq!(r, Err(QuoteError::InventoryLimit)),
+                (name, Err(err)) => assert!(name.starts_with(error_name(&err)), "{name}: {err:?}"),
+                (name, Ok(q)) => {
+                    let term = match name {
+                        "stale_snapshot" => q.stale_bps,
+                        "crank_lag" => q.crank_bps,
+                        "liquidation_heat_decays" | "liquidation_auction_decays" => q.liq_bps,
+                        "one_way_flow_continued" => q.flow_bps,
+                        "impact_linear" | "impact_sqrt" => q.impact_bps,
+                        "imbalance_short_book" | "inventory_decay" | "inventory_band" => q.imbalance_bps,
+                        "inventory_rebate" => q.rebate_bps,
+                        "coverage_fee_discount" => c.params.base_fee_bps as u64 - q.fee_bps,
+                        "inventory_headroom" | "inventory_headroom_short_side" => (c.trade_size - q.fill_size).unsigned_abs() as u64,
+                        _ => continue,
+                    };
+                    assert!(term > 0, "{name} leaves its term at zero");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! A stand-in for percolator in the end-to-end tests: a program that owns
+//! the LP PDA and calls Match the way percolator's trade-cpi does, signing for
+//! the PDA with `invoke_signed`, so the whole signature flow runs under the
+//! real runtime without the percolator binary.
+//!
+//! Instruction data is a packed [`MatchCall`]; accounts are
+//! `[matcher program, lp_pda, ctx (writable), slab?, stats? (writable)]`.
+//! After the CPI it holds the matcher to what percolator relies on (see
+//! [`check_return`]) and fails the transaction otherwise. The matcher's return
+//! data is left in place for the transaction to report.
+
+use provenance_client::{instruction, layout::*, MatcherReturn};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program::{get_return_data, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+pub use provenance_client::instruction::MatchCall;
+
+/// Seed prefix of the LP PDA; the context key completes it.
+pub const LP_SEED: &[u8] = b"lp";
+
+/// The LP PDA `caller` signs for on behalf of `ctx`.
+pub fn lp_pda(caller: &Pubkey, ctx: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LP_SEED, ctx.as_ref()], caller)
+}
+
+/// What percolator checks of a matcher's answer before it books the fill: the
+/// ABI version and valid flag, the echoes of the request, and a fill on the
+/// requested side no larger than asked for, at a nonzero price. A soft reject
+/// fills nothing and passes.
+pub fn check_return(call: &MatchCall, ret: &MatcherReturn) -> ProgramResult {
+    let valid = ret.abi_version == MATCHER_ABI_VERSION && ret.flags & FLAG_VALID != 0;
+    let echoed = ret.req_id == call.req_id
+        && ret.lp_account_id == call.lp_account_id
+        && ret.oracle_price_e6 == call.oracle_price_e6;
+    let sized = ret.exec_size == 0
+        || (ret.exec_size.signum() == call.trade_size.signum()
+            && ret.exec_size.unsigned_abs() <= call.trade_size.unsigned_abs()
+            && ret.exec_price_e6 != 0);
+    if !(valid && echoed && sized) {
+        msg!("matcher answered req {} with {:?}", call.req_id, ret);
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let [matcher, lp, ctx, optional @ ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let call = MatchCall::unpack(data)?;
+
+    let (pda, bump) = lp_pda(program_id, ctx.key);
+    if *lp.key != pda {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let ix = instruction::match_trade(
+        matcher.key,
+        lp.key,
+        ctx.key,
+        optional.first().map(|a| a.key),
+        optional.get(1).map(|a| a.key),
+        &call,
+    )?;
+    invoke_signed(&ix, accounts, &[&[LP_SEED, ctx.key.as_ref(), &[bump]]])?;
+
+    // Percolator trusts only what the matcher returned for this request
+    let (from, data) = get_return_data().ok_or(ProgramError::InvalidAccountData)?;
+    if from != *matcher.key {
+        msg!("return data from {}, not the matcher", from);
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let ret = MatcherReturn::from_bytes(&data)?;
+    check_return(&call, &ret)?;
+
+    // Unless the context opted out, the same answer sits at its front
+    let ctx_data = ctx.try_borrow_data()?;
+    if ctx_data[CTX_BASE + CTX_FLAGS_OFF] & CTX_FLAG_RETURN_DATA_ONLY == 0 && MatcherReturn::from_bytes(&ctx_data)? != ret {
+        msg!("context prefix disagrees with the return data for req {}", call.req_id);
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(call: &MatchCall, exec_size: i128) -> MatcherReturn {
+        MatcherReturn {
+            abi_version: MATCHER_ABI_VERSION,
+            flags: FLAG_VALID,
+            exec_price_e6: call.oracle_price_e6 + 1,
+            exec_size,
+            req_id: call.req_id,
+            lp_account_id: call.lp_account_id,
+            oracle_price_e6: call.oracle_price_e6,
+            reject_reason: 0,
+        }
+    }
+
+    #[test]
+    fn test_check_return() {
+        let call = MatchCall { req_id: 9, lp_account_id: 3, oracle_price_e6: 100, trade_size: -50, ..Default::default() };
+        check_return(&call, &fill(&call, -50)).unwrap();
+        check_return(&call, &fill(&call, -20)).unwrap();
+        // A soft reject
+        check_return(&call, &MatcherReturn { reject_reason: 2, ..fill(&call, 0) }).unwrap();
+
+        let bad = [
+            MatcherReturn { abi_version: 0, ..fill(&call, -50) },
+            MatcherReturn { flags: 0, ..fill(&call, -50) },
+            MatcherReturn { req_id: 10, ..fill(&call, -50) },
+            MatcherReturn { lp_account_id: 4, ..fill(&call, -50) },
+            MatcherReturn { oracle_price_e6: 101, ..fill(&call, -50) },
+            MatcherReturn { exec_price_e6: 0, ..fill(&call, -50) },
+            fill(&call, -51),
+            fill(&call, 50),
+        ];
+        for ret in bad {
+            assert_eq!(check_return(&call, &ret), Err(ProgramError::InvalidAccountData), "{ret:?}");
+        }
+    }
+}
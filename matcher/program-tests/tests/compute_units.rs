@@ -0,0 +1,275 @@
+//! Compute units per instruction, against checked-in baselines.
+//!
+//! Every scenario runs on each `cargo test`, so the harness itself stays
+//! working, but units are only metered for the BPF build. Point the run at
+//! `cargo build-sbf` output to measure:
+//!
+//! ```text
+//! SBF_OUT_DIR=$PWD/matcher/target/deploy cargo test --manifest-path matcher/program-tests/Cargo.toml --test compute_units -- --nocapture
+//! ```
+//!
+//! A scenario more than `TOLERANCE_PCT` above its baseline in
+//! `cu_baselines.csv` fails, as does one with no row there, so a new scenario
+//! can't go in unmeasured. `CU_BASELINES=record` rewrites the file from the
+//! run instead; do that, and commit it, when adding a scenario or when a
+//! change is meant to cost more (or has made things cheaper).
+
+use provenance_client::{
+    instruction::{self, MatchCall},
+    layout::*,
+    test_support::SlabFixture,
+    MatcherContext, MatcherParams,
+};
+use solana_program::{clock::Clock, instruction::Instruction, pubkey::Pubkey, rent::Rent};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+const BASELINES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/cu_baselines.csv");
+const TOLERANCE_PCT: u64 = 2;
+
+const SLOT: u64 = 1_000;
+const ORACLE: u64 = 100_000_000;
+
+struct Bench {
+    context: ProgramTestContext,
+    matcher: Pubkey,
+    slab: Pubkey,
+    /// Stands in for the LP PDA, and is the default authority
+    lp: Keypair,
+    req_id: u64,
+    units: Vec<(&'static str, u64)>,
+}
+
+fn params() -> MatcherParams {
+    MatcherParams {
+        base_fee_bps: 5,
+        min_spread_bps: 10,
+        max_spread_bps: 200,
+        imbalance_k_bps: 100,
+        liquidity_e6: 10_000_000_000_000,
+        max_fill: 1_000_000_000_000,
+        age_halflife: 2_160_000,
+        insurance_weight_bps: 50,
+        ..Default::default()
+    }
+}
+
+/// Every optional term on, with the square-root shapes: the most arithmetic
+/// a quote can do.
+fn all_terms() -> MatcherParams {
+    MatcherParams {
+        impact_mode: IMPACT_SQRT,
+        impact_k_bps: 30,
+        skew_k_bps: 50,
+        coverage_curve: CURVE_SQRT,
+        liq_halflife: 150,
+        funding_horizon: 1_000,
+        oracle_jump_bps: 500,
+        oracle_jump_window: 10,
+        flow_k_bps: 20,
+        ..params()
+    }
+}
+
+/// A slab with an adminless market at 30% coverage, last cranked at `SLOT`.
+fn slab_data() -> Vec<u8> {
+    SlabFixture { insurance: 300_000_000_000, total_oi: 1_000_000_000_000, last_crank_slot: SLOT, ..Default::default() }
+        .to_bytes()
+}
+
+/// Units are only metered for the BPF build, which `ProgramTest` loads
+/// whenever one of these is set.
+fn metered() -> bool {
+    std::env::var_os("SBF_OUT_DIR").is_some() || std::env::var_os("BPF_OUT_DIR").is_some()
+}
+
+impl Bench {
+    async fn start() -> Self {
+        let matcher = Pubkey::new_unique();
+        let slab = Pubkey::new_unique();
+        let mut program_test = ProgramTest::default();
+        program_test.add_program("credibility_matcher", matcher, processor!(credibility_matcher::process_instruction));
+        let data = slab_data();
+        program_test.add_account(
+            slab,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: Pubkey::new_unique(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+        let context = program_test.start_with_context().await;
+        context.set_sysvar(&Clock { slot: SLOT, ..Clock::default() });
+        Bench { context, matcher, slab, lp: Keypair::new(), req_id: 0, units: Vec::new() }
+    }
+
+    /// Put a rent-exempt account owned by the matcher at `key`.
+    fn put(&mut self, key: &Pubkey, data: Vec<u8>) {
+        let account = Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: self.matcher,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.context.set_account(key, &AccountSharedData::from(account));
+    }
+
+    async fn read_ctx(&mut self, ctx: &Pubkey) -> MatcherContext {
+        let account = self.context.banks_client.get_account(*ctx).await.unwrap().unwrap();
+        MatcherContext::from_bytes(&account.data).unwrap()
+    }
+
+    async fn write_ctx(&mut self, ctx: &Pubkey, update: impl FnOnce(&mut MatcherContext)) {
+        let mut decoded = self.read_ctx(ctx).await;
+        update(&mut decoded);
+        self.put(ctx, decoded.to_bytes());
+    }
+
+    fn set_slot(&self, slot: u64) {
+        self.context.set_sysvar(&Clock { slot, ..Clock::default() });
+    }
+
+    /// Send `ix` alone, signed by the payer and by the LP when it takes the
+    /// LP's signature, and return the units it consumed.
+    async fn send(&mut self, ix: Instruction) -> u64 {
+        let blockhash = self.context.banks_client.get_latest_blockhash().await.unwrap();
+        let mut signers = vec![&self.context.payer];
+        if ix.accounts.iter().any(|meta| meta.is_signer && meta.pubkey == self.lp.pubkey()) {
+            signers.push(&self.lp);
+        }
+        let tx = Transaction::new_signed_with_payer(&[ix], Some(&self.context.payer.pubkey()), &signers, blockhash);
+        let result = self.context.banks_client.process_transaction_with_metadata(tx).await.unwrap();
+        if let Err(err) = result.result {
+            panic!("{err}: {:#?}", result.metadata.map(|m| m.log_messages));
+        }
+        result.metadata.unwrap().compute_units_consumed
+    }
+
+    async fn measure(&mut self, name: &'static str, ix: Instruction) {
+        let units = self.send(ix).await;
+        self.units.push((name, units));
+    }
+
+    /// A Match for `trade_size`, each with a fresh request id so repeats are
+    /// distinct transactions.
+    fn match_ix(&mut self, ctx: &Pubkey, stats: Option<&Pubkey>, trade_size: i128) -> Instruction {
+        self.req_id += 1;
+        let call = MatchCall { req_id: self.req_id, oracle_price_e6: ORACLE, trade_size, ..Default::default() };
+        let slab = stats.map(|_| &self.slab);
+        instruction::match_trade(&self.matcher, &self.lp.pubkey(), ctx, slab, stats, &call).unwrap()
+    }
+}
+
+fn read_baselines() -> Vec<(String, u64)> {
+    let text = std::fs::read_to_string(BASELINES).unwrap_or_default();
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let (name, units) = line.split_once(',')?;
+            Some((name.to_string(), units.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+fn write_baselines(units: &[(&str, u64)]) {
+    let mut text = String::from("scenario,units\n");
+    for (name, units) in units {
+        text.push_str(&format!("{name},{units}\n"));
+    }
+    std::fs::write(BASELINES, text).unwrap();
+}
+
+#[tokio::test]
+async fn test_compute_units_within_baseline() {
+    let mut b = Bench::start().await;
+    let lp = b.lp.pubkey();
+    let (v5, v6, stats) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+    // Setup instructions
+    b.put(&v5, vec![0u8; CTX_LEN]);
+    b.measure("init_v5", instruction::init(&b.matcher, &lp, &v5, None, None, &params()).unwrap()).await;
+    b.put(&v6, vec![0u8; CTX_EXT_LEN]);
+    b.measure("init_v6_slab", instruction::init(&b.matcher, &lp, &v6, Some(&b.slab), None, &params()).unwrap()).await;
+    b.measure("update_params", instruction::update_params(&b.matcher, &lp, &v6, &all_terms())).await;
+    b.put(&stats, vec![0u8; STATS_LEN]);
+    b.measure("init_stats", instruction::init_stats(&b.matcher, &lp, &v6, &stats)).await;
+    b.measure("update_credibility", instruction::update_credibility(&b.matcher, &v6, &b.slab)).await;
+
+    // Match, from the plainest context to the busiest
+    let ix = b.match_ix(&v5, None, 1_000_000);
+    b.measure("match_v5", ix).await;
+    b.write_ctx(&v6, |c| c.params = params()).await;
+    // v6 is bound to the stats account, which every Match then has to pass
+    let ix = b.match_ix(&v6, Some(&stats), 1_000_000);
+    b.measure("match_v6", ix).await;
+    let ix = b.match_ix(&v6, Some(&stats), 1_000_000_000_000);
+    b.measure("match_v6_clipped", ix).await;
+    b.write_ctx(&v6, |c| {
+        c.params = all_terms();
+        c.inventory = -2_000_000_000;
+        c.liq_heat = 50_000;
+        let ext = c.extension.as_mut().unwrap();
+        ext.funding_rate_bps_per_slot = 1;
+        ext.gross_long = 5_000_000_000;
+    })
+    .await;
+    let ix = b.match_ix(&v6, Some(&stats), 1_000_000);
+    b.measure("match_v6_all_terms", ix).await;
+
+    // TWAP over a full oracle ring, one recorded print per slot
+    b.write_ctx(&v6, |c| c.params = MatcherParams { twap_window_slots: 1_000, ..all_terms() }).await;
+    for slot in SLOT + 1..=SLOT + ORACLE_CAPACITY as u64 {
+        b.set_slot(slot);
+        let ix = b.match_ix(&v6, Some(&stats), 1_000);
+        b.send(ix).await;
+    }
+    b.set_slot(SLOT + ORACLE_CAPACITY as u64 + 1);
+    let ix = b.match_ix(&v6, Some(&stats), 1_000_000);
+    b.measure("match_v6_twap_full_ring", ix).await;
+
+    // A soft reject answers instead of failing
+    b.write_ctx(&v6, |c| c.flags |= CTX_FLAG_REDUCE_ONLY | CTX_FLAG_SOFT_REJECT).await;
+    let inventory = b.read_ctx(&v6).await.inventory;
+    let ix = b.match_ix(&v6, Some(&stats), if inventory < 0 { -1_000_000 } else { 1_000_000 });
+    b.measure("match_soft_reject", ix).await;
+
+    let preview = instruction::quote_preview(&b.matcher, &v6, Some(&stats), ORACLE, 1_000_000);
+    b.measure("quote_preview", preview).await;
+    let two_sided = instruction::quote_two_sided(&b.matcher, &v6, Some(&stats), ORACLE, 1_000_000);
+    b.measure("quote_two_sided", two_sided).await;
+
+    if !metered() {
+        println!("native build: units aren't metered; set SBF_OUT_DIR to compare against {BASELINES}");
+        return;
+    }
+    if std::env::var("CU_BASELINES").as_deref() == Ok("record") {
+        write_baselines(&b.units);
+        println!("recorded {} baselines to {BASELINES}", b.units.len());
+        return;
+    }
+
+    let baselines = read_baselines();
+    let (mut over, mut missing) = (Vec::new(), Vec::new());
+    println!("{:<26} {:>8} {:>9} {:>7}", "scenario", "units", "baseline", "change");
+    for &(name, units) in &b.units {
+        let Some(&(_, baseline)) = baselines.iter().find(|(n, _)| n == name) else {
+            println!("{name:<26} {units:>8} {:>9} {:>7}", "-", "new");
+            missing.push(name);
+            continue;
+        };
+        let change = (units as f64 - baseline as f64) * 100.0 / baseline.max(1) as f64;
+        println!("{name:<26} {units:>8} {baseline:>9} {change:>+6.1}%");
+        if units * 100 > baseline * (100 + TOLERANCE_PCT) {
+            over.push(name);
+        }
+    }
+    assert!(missing.is_empty(), "no baseline for {missing:?}; record them with CU_BASELINES=record and commit {BASELINES}");
+    assert!(over.is_empty(), "over baseline by more than {TOLERANCE_PCT}%: {over:?}");
+}
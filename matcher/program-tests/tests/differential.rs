@@ -0,0 +1,400 @@
+//! Match under the runtime against `provenance-core` on the same inputs.
+//!
+//! Each case is a random context (any kind, v5 or v6, any parameters,
+//! book, snapshot and flags), a clock and a trade. The context is written
+//! straight into the bank and Match is sent for it; the pure model (the kind's
+//! `quote`) is asked to quote the same inputs. The two must agree bit for bit: the MatcherReturn, every term of
+//! the breakdown and the state Match writes back on a fill, the zero fill of
+//! a soft reject, or the error code of a hard one.
+//!
+//! Run natively by default. With the program built by `cargo build-sbf`,
+//! `SBF_OUT_DIR=matcher/target/deploy` runs the same cases against the BPF
+//! binary instead.
+
+use std::cell::{Cell, RefCell};
+
+use credibility_matcher::error::MatcherError;
+use proptest::prelude::*;
+use proptest::test_runner::{Config, TestCaseError, TestRunner};
+use provenance_client::{
+    instruction::{self, MatchCall},
+    layout::*,
+    ContextExtension, MatcherContext, MatcherParams, MatcherReturn,
+};
+use provenance_core::{hedge_size, Params, Quote, QuoteError, State};
+use solana_compute_budget_interface::ComputeBudgetInstruction;
+use solana_program::{clock::Clock, pubkey::Pubkey, rent::Rent};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+#[derive(Clone, Debug)]
+struct Case {
+    kind: u8,
+    params: Params,
+    state: State,
+    extended: bool,
+    flags: u8,
+    fill_seq: u64,
+    oracle_price_e6: u64,
+    trade_size: i128,
+}
+
+struct Harness {
+    context: ProgramTestContext,
+    matcher: Pubkey,
+    ctx: Pubkey,
+    lp: Keypair,
+}
+
+/// bps values around the interesting edges: off, small, typical, and junk.
+fn bps() -> impl Strategy<Value = u32> {
+    prop_oneof![Just(0), 1u32..=100, 100u32..=20_000, any::<u32>()]
+}
+
+fn amount() -> impl Strategy<Value = u128> {
+    prop_oneof![Just(0), 1u128..=1_000_000_000_000_000, any::<u128>()]
+}
+
+fn params() -> impl Strategy<Value = Params> {
+    (
+        (bps(), bps(), bps(), bps(), amount(), amount(), amount()),
+        (bps(), 0u8..=3, bps(), bps(), 0u8..=3, any::<[u16; CURVE_POINTS]>()),
+        (
+            0u32..=100_000,
+            0u32..=1_000_000,
+            prop_oneof![3 => Just(0), 1 => 1u16..=5_000],
+            0u32..=1_000,
+            bps(),
+            // Without the stats account a TWAP window can only refuse
+            prop_oneof![7 => Just(0), 1 => 1u32..=100],
+        ),
+        (
+            0u32..=10_000_000,
+            any::<[u16; SIGNALS]>(),
+            0u32..=1_000_000,
+            prop_oneof![3 => Just((0, 0)), 1 => (1u16..=5_000, 1u32..=1_000)],
+            prop_oneof![Just(0), 0u32..=20_000, any::<u32>()],
+            prop_oneof![Just(0), 0u16..=10_000, any::<u16>()],
+        ),
+    )
+        .prop_map(|(a, b, c, d)| Params {
+            base_fee_bps: a.0,
+            min_spread_bps: a.1,
+            max_spread_bps: a.2,
+            imbalance_k_bps: a.3,
+            liquidity_e6: a.4,
+            max_fill: a.5,
+            max_inventory: a.6,
+            age_halflife: d.0,
+            insurance_weight_bps: b.0,
+            impact_mode: b.1,
+            impact_k_bps: b.2,
+            skew_k_bps: b.3,
+            coverage_curve: b.4,
+            curve_points: b.5,
+            liq_halflife: c.0,
+            funding_horizon: c.1,
+            oracle_jump_bps: c.2,
+            oracle_jump_window: c.3,
+            flow_k_bps: c.4,
+            twap_window_slots: c.5,
+            signal_weights: d.1,
+            deficit_halflife: d.2,
+            liq_auction_bps: d.3.0,
+            liq_auction_slots: d.3.1,
+            peg_band_e6: d.4,
+            spot_weight_bps: d.5,
+        })
+}
+
+/// Slots stay near the clock so staleness, crank lag and the circuit breaker
+/// all come into play.
+fn state() -> impl Strategy<Value = State> {
+    (
+        (1_000u64..=10_000_000, 0u64..=1_000, 0u64..=1_000, 0u64..=1_000),
+        (
+            prop_oneof![Just(0i128), -1_000_000_000_000i128..=1_000_000_000_000, any::<i128>()],
+            amount(),
+            amount(),
+            any::<u32>(),
+            prop_oneof![Just(0i64), -100i64..=100, any::<i64>()],
+        ),
+        (prop_oneof![Just(0u64), 1u64..=1_000_000_000], amount(), amount()),
+        (0u64..=100_000_000, prop_oneof![Just(0u64), 0u64..=1_000], prop_oneof![Just(0u64), 0u64..=1_000]),
+    )
+        .prop_map(|(slots, book, flow, history)| State {
+            current_slot: slots.0,
+            snapshot_slot: slots.0 - slots.1,
+            last_crank_slot: slots.0 - slots.2,
+            last_oracle_slot: slots.0 - slots.3,
+            inventory: book.0,
+            insurance_snapshot: book.1,
+            total_oi_snapshot: book.2,
+            liq_heat: book.3 as u64,
+            funding_rate_bps_per_slot: book.4,
+            last_oracle_price_e6: flow.0,
+            gross_long: flow.1,
+            gross_short: flow.2,
+            reduce_only: false,
+            // Match is sent without the stats account
+            twap_price_e6: None,
+            market_age: history.0,
+            last_deficit_slot: if history.1 == 0 { 0 } else { slots.0 - history.1 },
+            last_liq_slot: if history.2 == 0 { 0 } else { slots.0 - history.2 },
+            // Nor any signal accounts, a schedule or oracle feeds
+            signal_bps: 0,
+            spread_mult_bps: 0,
+            oracle_bps: 0,
+            rebate_bps: 0,
+            fee_discount_bps: 0,
+            inventory_halflife: 0,
+            soft_inventory: 0,
+            band_k_bps: 0,
+            max_long_inventory: 0,
+            max_short_inventory: 0,
+            reduce_only_bps: 0,
+        })
+}
+
+fn case() -> impl Strategy<Value = Case> {
+    (
+        prop_oneof![Just(KIND_CREDIBILITY), Just(KIND_PLAIN), Just(KIND_MULTI), Just(KIND_STABLE)],
+        params(),
+        state(),
+        any::<bool>(),
+        any::<(bool, bool, bool)>(),
+        prop_oneof![Just(0u64), any::<u64>()],
+        prop_oneof![Just(0u64), 1u64..=1_000_000_000, any::<u64>()],
+        prop_oneof![Just(0i128), -1_000_000_000_000i128..=1_000_000_000_000, any::<i128>()],
+    )
+        .prop_map(|(kind, mut params, mut state, extended, (reduce_only, soft_reject, return_data_only), fill_seq, oracle_price_e6, trade_size)| {
+            if !extended {
+                // A v5 context has nowhere to keep these; the program prices as if they were zero
+                params = Params { funding_horizon: 0, oracle_jump_bps: 0, oracle_jump_window: 0, flow_k_bps: 0, twap_window_slots: 0, signal_weights: [0; SIGNALS], deficit_halflife: 0, liq_auction_bps: 0, liq_auction_slots: 0, spot_weight_bps: 0, ..params };
+                state = State { funding_rate_bps_per_slot: 0, last_oracle_slot: 0, gross_long: 0, gross_short: 0, last_liq_slot: 0, ..state };
+            }
+            state.reduce_only = reduce_only;
+            let flags = (reduce_only as u8 * CTX_FLAG_REDUCE_ONLY)
+                | (soft_reject as u8 * CTX_FLAG_SOFT_REJECT)
+                | (return_data_only as u8 * CTX_FLAG_RETURN_DATA_ONLY);
+            Case { kind, params, state, extended, flags, fill_seq, oracle_price_e6, trade_size }
+        })
+}
+
+/// The context account the case describes, bound to `lp`.
+fn context_for(case: &Case, lp: &Pubkey) -> MatcherContext {
+    let (p, s) = (&case.params, &case.state);
+    MatcherContext {
+        version: if case.extended { VERSION } else { VERSION_V5 },
+        kind: case.kind,
+        lp_pda: *lp,
+        params: MatcherParams {
+            base_fee_bps: p.base_fee_bps,
+            min_spread_bps: p.min_spread_bps,
+            max_spread_bps: p.max_spread_bps,
+            imbalance_k_bps: p.imbalance_k_bps,
+            liquidity_e6: p.liquidity_e6,
+            max_fill: p.max_fill,
+            max_inventory: p.max_inventory,
+            age_halflife: p.age_halflife,
+            insurance_weight_bps: p.insurance_weight_bps,
+            impact_mode: p.impact_mode,
+            impact_k_bps: p.impact_k_bps,
+            skew_k_bps: p.skew_k_bps,
+            coverage_curve: p.coverage_curve,
+            curve_points: p.curve_points,
+            liq_halflife: p.liq_halflife,
+            funding_horizon: p.funding_horizon,
+            oracle_jump_bps: p.oracle_jump_bps,
+            oracle_jump_window: p.oracle_jump_window,
+            flow_k_bps: p.flow_k_bps,
+            twap_window_slots: p.twap_window_slots,
+            signal_weights: p.signal_weights,
+            deficit_halflife: p.deficit_halflife,
+            liq_auction_bps: p.liq_auction_bps,
+            liq_auction_slots: p.liq_auction_slots,
+            peg_band_e6: p.peg_band_e6,
+            spot_weight_bps: p.spot_weight_bps,
+        },
+        inventory: s.inventory,
+        last_oracle_price_e6: s.last_oracle_price_e6,
+        insurance_snapshot: s.insurance_snapshot,
+        total_oi_snapshot: s.total_oi_snapshot,
+        snapshot_slot: s.snapshot_slot,
+        flags: case.flags,
+        last_crank_slot: s.last_crank_slot,
+        liq_heat: s.liq_heat as u32,
+        market_age: s.market_age,
+        last_deficit: s.last_deficit_slot,
+        extension: case.extended.then(|| ContextExtension {
+            funding_rate_bps_per_slot: s.funding_rate_bps_per_slot,
+            last_oracle_slot: s.last_oracle_slot,
+            fill_seq: case.fill_seq,
+            gross_long: s.gross_long,
+            gross_short: s.gross_short,
+            last_liq_slot: s.last_liq_slot,
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+/// Decode a quote breakdown back into the model's type.
+fn breakdown(data: &[u8]) -> Quote {
+    let u64_at = |off: usize| u64::from_le_bytes(data[off..off + 8].try_into().unwrap());
+    let i64_at = |off: usize| i64::from_le_bytes(data[off..off + 8].try_into().unwrap());
+    let i128_at = |off: usize| i128::from_le_bytes(data[off..off + 16].try_into().unwrap());
+    Quote {
+        discount_bps: u64_at(QUOTE_DISCOUNT_OFF),
+        coverage_bps: u64_at(QUOTE_COVERAGE_OFF),
+        tier: u64_at(QUOTE_TIER_OFF) as u8,
+        fill_pct: u64_at(QUOTE_FILL_PCT_OFF) as u128,
+        tier_spread_bps: u64_at(QUOTE_TIER_SPREAD_OFF),
+        imbalance_bps: u64_at(QUOTE_IMBALANCE_OFF),
+        impact_bps: u64_at(QUOTE_IMPACT_OFF),
+        stale_bps: u64_at(QUOTE_STALE_OFF),
+        crank_bps: u64_at(QUOTE_CRANK_OFF),
+        liq_bps: u64_at(QUOTE_LIQ_OFF),
+        flow_bps: u64_at(QUOTE_FLOW_OFF),
+        signal_bps: u64_at(QUOTE_SIGNAL_OFF),
+        schedule_bps: u64_at(QUOTE_SCHEDULE_OFF),
+        oracle_bps: u64_at(QUOTE_ORACLE_OFF),
+        spread_bps: u64_at(QUOTE_SPREAD_OFF),
+        skew_bps: i64_at(QUOTE_SKEW_OFF),
+        funding_skew_bps: i64_at(QUOTE_FUNDING_SKEW_OFF),
+        fee_bps: u64_at(QUOTE_FEE_OFF),
+        rebate_bps: u64_at(QUOTE_REBATE_OFF),
+        anchor_price_e6: u64_at(QUOTE_ANCHOR_OFF),
+        exec_price_e6: u64_at(QUOTE_EXEC_PRICE_OFF),
+        fill_size: i128_at(QUOTE_FILL_SIZE_OFF),
+        new_inventory: i128_at(QUOTE_NEW_INVENTORY_OFF),
+    }
+}
+
+async fn start() -> Harness {
+    let matcher = Pubkey::new_unique();
+    let mut program_test = ProgramTest::default();
+    program_test.add_program("credibility_matcher", matcher, processor!(credibility_matcher::process_instruction));
+    Harness { context: program_test.start_with_context().await, matcher, ctx: Pubkey::new_unique(), lp: Keypair::new() }
+}
+
+/// Run one case through both paths and compare.
+async fn check(h: &mut Harness, case: &Case, req_id: u64) -> Result<(), TestCaseError> {
+    let before = context_for(case, &h.lp.pubkey());
+    let data = before.to_bytes();
+    let account = Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: h.matcher,
+        executable: false,
+        rent_epoch: 0,
+    };
+    h.context.set_account(&h.ctx, &AccountSharedData::from(account));
+    h.context.set_sysvar(&Clock { slot: case.state.current_slot, ..Clock::default() });
+
+    let call = MatchCall { req_id, lp_idx: 0, lp_account_id: 7, oracle_price_e6: case.oracle_price_e6, trade_size: case.trade_size };
+    let ixs = [
+        ComputeBudgetInstruction::set_compute_unit_limit(1_400_000),
+        instruction::match_trade(&h.matcher, &h.lp.pubkey(), &h.ctx, None, None, &call).unwrap(),
+    ];
+    let blockhash = h.context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(&ixs, Some(&h.context.payer.pubkey()), &[&h.context.payer, &h.lp], blockhash);
+    let result = h.context.banks_client.process_transaction_with_metadata(tx).await.unwrap();
+    let after = h.context.banks_client.get_account(h.ctx).await.unwrap().unwrap();
+    let after = MatcherContext::from_bytes(&after.data).unwrap();
+
+    let ret = |exec_price_e6: u64, exec_size: i128, reject_reason: u64| MatcherReturn {
+        abi_version: MATCHER_ABI_VERSION,
+        flags: FLAG_VALID,
+        exec_price_e6,
+        exec_size,
+        req_id,
+        lp_account_id: call.lp_account_id,
+        oracle_price_e6: case.oracle_price_e6,
+        reject_reason,
+    };
+    let last_return = |ret: MatcherReturn| if case.flags & CTX_FLAG_RETURN_DATA_ONLY != 0 { before.last_return } else { ret };
+
+    let quote = match case.kind {
+        KIND_PLAIN => provenance_core::plain::quote,
+        KIND_MULTI => provenance_core::multi::quote,
+        KIND_STABLE => provenance_core::stable::quote,
+        _ => provenance_core::quote,
+    };
+    match quote(&case.params, &case.state, case.oracle_price_e6, case.trade_size) {
+        Ok(q) => {
+            prop_assert_eq!(&result.result, &Ok(()));
+            let data = result.metadata.unwrap().return_data.expect("Match sets return data").data;
+            prop_assert_eq!(data.len(), MATCH_RET_LEN);
+            let expected = ret(q.exec_price_e6, q.fill_size, 0);
+            prop_assert_eq!(MatcherReturn::from_bytes(&data).unwrap(), expected);
+            prop_assert_eq!(breakdown(&data[MATCH_RET_BREAKDOWN_OFF..MATCH_RET_FILL_SEQ_OFF]), q);
+            let fill_seq = if case.extended { case.fill_seq.wrapping_add(1) } else { 0 };
+            prop_assert_eq!(u64::from_le_bytes(data[MATCH_RET_FILL_SEQ_OFF..MATCH_RET_HEDGE_OFF].try_into().unwrap()), fill_seq);
+            let hedge = hedge_size(q.new_inventory, case.state.soft_inventory);
+            prop_assert_eq!(i128::from_le_bytes(data[MATCH_RET_HEDGE_OFF..].try_into().unwrap()), hedge);
+
+            prop_assert_eq!(after.last_return, last_return(expected));
+            prop_assert_eq!(after.inventory, q.new_inventory);
+            prop_assert_eq!((after.last_oracle_price_e6, after.last_exec_price_e6), (case.oracle_price_e6, q.exec_price_e6));
+            if let Some(ext) = after.extension {
+                prop_assert_eq!((ext.fill_seq, ext.last_oracle_slot), (fill_seq, case.state.current_slot));
+                let (mut long, mut short) = (case.state.gross_long, case.state.gross_short);
+                if q.fill_size > 0 {
+                    long = long.saturating_add(q.fill_size.unsigned_abs());
+                } else {
+                    short = short.saturating_add(q.fill_size.unsigned_abs());
+                }
+                prop_assert_eq!((ext.gross_long, ext.gross_short), (long, short));
+            }
+        }
+        Err(err) => {
+            let code = MatcherError::from(err);
+            let soft = matches!(
+                err,
+                QuoteError::ReduceOnly { .. } | QuoteError::InventoryLimit | QuoteError::InventoryOverflow { .. }
+            );
+            // Reduce-only near the cap always answers soft; the LP's own switch
+            // follows soft-reject mode
+            let near_cap = matches!(err, QuoteError::ReduceOnly { .. }) && case.flags & CTX_FLAG_REDUCE_ONLY == 0;
+            if soft && (near_cap || case.flags & CTX_FLAG_SOFT_REJECT != 0) {
+                prop_assert_eq!(&result.result, &Ok(()));
+                let data = result.metadata.unwrap().return_data.expect("a soft reject sets return data").data;
+                let expected = ret(case.oracle_price_e6, 0, code as u64);
+                prop_assert_eq!(MatcherReturn::from_bytes(&data).unwrap(), expected);
+                prop_assert!(data[RET_LEN..].iter().all(|&b| b == 0), "soft reject carries no quote");
+                prop_assert_eq!(after, MatcherContext { last_return: last_return(expected), ..before });
+            } else {
+                prop_assert_eq!(
+                    &result.result,
+                    &Err(TransactionError::InstructionError(1, InstructionError::Custom(code as u32)))
+                );
+                prop_assert_eq!(after, before);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_match_agrees_with_core() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let harness = RefCell::new(rt.block_on(start()));
+    let req_id = Cell::new(0);
+    // Each case is a transaction, so fewer than the core's property tests
+    let mut runner = TestRunner::new(Config { cases: 256, failure_persistence: None, ..Config::default() });
+    let result = runner.run(&case(), |case| {
+        // A fresh request id keeps every transaction's signature distinct
+        req_id.set(req_id.get() + 1);
+        let mut harness = harness.borrow_mut();
+        rt.block_on(check(&mut harness, &case, req_id.get()))
+    });
+    if let Err(err) = result {
+        panic!("{err}");
+    }
+}
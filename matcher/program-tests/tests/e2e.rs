@@ -0,0 +1,282 @@
+//! The matcher under the real runtime: a v6 context created and initialised
+//! against a slab, a credibility crank reading that slab, and fills taken
+//! through a CPI signed for the LP PDA, the way percolator calls in.
+
+use credibility_matcher::error::MatcherError;
+use provenance_client::{
+    instruction, layout::*, test_support::SlabFixture, MatcherContext, MatcherParams, MatcherReturn,
+};
+use provenance_program_tests::{lp_pda, MatchCall};
+use solana_program::{instruction::AccountMeta, pubkey::Pubkey, rent::Rent};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    instruction::{Instruction, InstructionError},
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use solana_system_interface::instruction as system_instruction;
+
+const SLOT: u64 = 1_000;
+const ORACLE: u64 = 100_000_000;
+
+struct Market {
+    matcher: Pubkey,
+    caller: Pubkey,
+    percolator: Pubkey,
+    slab: Pubkey,
+    ctx: Keypair,
+}
+
+fn params() -> MatcherParams {
+    MatcherParams {
+        base_fee_bps: 5,
+        min_spread_bps: 10,
+        max_spread_bps: 200,
+        imbalance_k_bps: 100,
+        liquidity_e6: 10_000_000_000_000,
+        max_fill: 1_000_000_000_000,
+        age_halflife: 2_160_000,
+        insurance_weight_bps: 50,
+        ..Default::default()
+    }
+}
+
+/// A slab with an adminless market at 30% coverage, last cranked at `SLOT`.
+fn slab_data() -> Vec<u8> {
+    SlabFixture { insurance: 300_000_000_000, total_oi: 1_000_000_000_000, last_crank_slot: SLOT, ..Default::default() }
+        .to_bytes()
+}
+
+/// Boot the matcher and the calling program with a seeded slab, at `SLOT`.
+async fn start() -> (ProgramTestContext, Market) {
+    let market = Market {
+        matcher: Pubkey::new_unique(),
+        caller: Pubkey::new_unique(),
+        percolator: Pubkey::new_unique(),
+        slab: Pubkey::new_unique(),
+        ctx: Keypair::new(),
+    };
+    let mut program_test = ProgramTest::default();
+    program_test.prefer_bpf(false);
+    program_test.add_program("credibility_matcher", market.matcher, processor!(credibility_matcher::process_instruction));
+    program_test.add_program("lp_caller", market.caller, processor!(provenance_program_tests::process_instruction));
+    let data = slab_data();
+    program_test.add_account(
+        market.slab,
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: market.percolator,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+    context.warp_to_slot(SLOT).unwrap();
+    (context, market)
+}
+
+async fn send(context: &mut ProgramTestContext, ixs: &[Instruction], signers: &[&Keypair]) -> Result<(), TransactionError> {
+    let mut all = vec![&context.payer];
+    all.extend_from_slice(signers);
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(ixs, Some(&context.payer.pubkey()), &all, blockhash);
+    context.banks_client.process_transaction(tx).await.map_err(|e| e.unwrap())
+}
+
+async fn read_ctx(context: &mut ProgramTestContext, market: &Market) -> MatcherContext {
+    let account = context.banks_client.get_account(market.ctx.pubkey()).await.unwrap().unwrap();
+    assert_eq!(account.owner, market.matcher);
+    MatcherContext::from_bytes(&account.data).unwrap()
+}
+
+/// Create the v6 context and Init it bound to the slab, in one transaction.
+/// The LP PDA can only sign through the caller, so the payer takes the
+/// authority and signs Init for it.
+async fn init(context: &mut ProgramTestContext, market: &Market) {
+    let ctx = market.ctx.pubkey();
+    let (lp, _) = lp_pda(&market.caller, &ctx);
+    let create = system_instruction::create_account(
+        &context.payer.pubkey(),
+        &ctx,
+        Rent::default().minimum_balance(CTX_EXT_LEN),
+        CTX_EXT_LEN as u64,
+        &market.matcher,
+    );
+    let authority = context.payer.pubkey();
+    let init = instruction::init(&market.matcher, &lp, &ctx, Some(&market.slab), Some(&authority), &params()).unwrap();
+    send(context, &[create, init], &[&market.ctx]).await.unwrap();
+}
+
+/// Set context flag bits directly, as the authority could.
+async fn set_flags(context: &mut ProgramTestContext, market: &Market, flags: u8) {
+    let mut account = context.banks_client.get_account(market.ctx.pubkey()).await.unwrap().unwrap();
+    account.data[CTX_BASE + CTX_FLAGS_OFF] |= flags;
+    context.set_account(&market.ctx.pubkey(), &AccountSharedData::from(account));
+}
+
+fn crank(market: &Market) -> Instruction {
+    instruction::update_credibility(&market.matcher, &market.ctx.pubkey(), &market.slab)
+}
+
+/// Match through the caller, which signs for the LP PDA.
+fn cpi_match(market: &Market, call: &MatchCall) -> Instruction {
+    let ctx = market.ctx.pubkey();
+    let (lp, _) = lp_pda(&market.caller, &ctx);
+    Instruction::new_with_bytes(
+        market.caller,
+        &call.pack(),
+        vec![
+            AccountMeta::new_readonly(market.matcher, false),
+            AccountMeta::new_readonly(lp, false),
+            AccountMeta::new(ctx, false),
+            AccountMeta::new_readonly(market.slab, false),
+        ],
+    )
+}
+
+fn custom(err: MatcherError) -> InstructionError {
+    InstructionError::Custom(err as u32)
+}
+
+#[tokio::test]
+async fn test_init_binds_context() {
+    let (mut context, market) = start().await;
+    init(&mut context, &market).await;
+
+    let ctx = read_ctx(&mut context, &market).await;
+    let ext = ctx.extension.expect("v6 context");
+    assert_eq!(ctx.lp_pda, lp_pda(&market.caller, &market.ctx.pubkey()).0);
+    assert_eq!(ctx.params, params());
+    assert_eq!((ext.slab, ext.percolator_program), (market.slab, market.percolator));
+    assert_eq!((ctx.inventory, ctx.snapshot_slot, ext.fill_seq), (0, 0, 0));
+
+    // A second Init is refused
+    let authority = context.payer.pubkey();
+    let again =
+        instruction::init(&market.matcher, &ctx.lp_pda, &market.ctx.pubkey(), Some(&market.slab), Some(&authority), &params())
+            .unwrap();
+    assert_eq!(
+        send(&mut context, &[again], &[]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized))
+    );
+}
+
+#[tokio::test]
+async fn test_crank_snapshots_slab() {
+    let (mut context, market) = start().await;
+    init(&mut context, &market).await;
+    send(&mut context, &[crank(&market)], &[]).await.unwrap();
+
+    let ctx = read_ctx(&mut context, &market).await;
+    assert_eq!(ctx.insurance_snapshot, 300_000_000_000);
+    assert_eq!(ctx.total_oi_snapshot, 1_000_000_000_000);
+    assert_eq!((ctx.snapshot_slot, ctx.last_crank_slot), (SLOT, SLOT));
+
+    // Only the bound slab is read
+    let other = instruction::update_credibility(&market.matcher, &market.ctx.pubkey(), &market.percolator);
+    assert_eq!(
+        send(&mut context, &[other], &[]).await,
+        Err(TransactionError::InstructionError(0, custom(MatcherError::SlabMismatch)))
+    );
+}
+
+#[tokio::test]
+async fn test_cpi_match_writes_return_and_state() {
+    let (mut context, market) = start().await;
+    init(&mut context, &market).await;
+    send(&mut context, &[crank(&market)], &[]).await.unwrap();
+
+    let call = MatchCall { req_id: 42, lp_idx: 0, lp_account_id: 7, oracle_price_e6: ORACLE, trade_size: 1_000_000 };
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[cpi_match(&market, &call)],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        blockhash,
+    );
+    let result = context.banks_client.process_transaction_with_metadata(tx).await.unwrap();
+    result.result.unwrap();
+    let return_data = result.metadata.unwrap().return_data.expect("Match sets return data");
+    assert_eq!(return_data.program_id, market.matcher);
+    let data = return_data.data;
+    assert_eq!(data.len(), MATCH_RET_LEN);
+
+    let ret = MatcherReturn::from_bytes(&data).unwrap();
+    assert_eq!((ret.req_id, ret.lp_account_id, ret.oracle_price_e6), (42, 7, ORACLE));
+    assert_eq!((ret.exec_size, ret.reject_reason), (1_000_000, 0));
+    // The taker buys above the oracle
+    assert!(ret.exec_price_e6 > ORACLE, "exec {}", ret.exec_price_e6);
+    let quote = &data[MATCH_RET_BREAKDOWN_OFF..MATCH_RET_FILL_SEQ_OFF];
+    let read_u64 = |off: usize| u64::from_le_bytes(quote[off..off + 8].try_into().unwrap());
+    assert_eq!(read_u64(QUOTE_COVERAGE_OFF), 3_000);
+    assert_eq!(read_u64(QUOTE_TIER_OFF), 2); // NORMAL
+    assert_eq!(read_u64(QUOTE_EXEC_PRICE_OFF), ret.exec_price_e6);
+    let new_inventory = i128::from_le_bytes(quote[QUOTE_NEW_INVENTORY_OFF..QUOTE_NEW_INVENTORY_OFF + 16].try_into().unwrap());
+    assert_eq!(u64::from_le_bytes(data[MATCH_RET_FILL_SEQ_OFF..MATCH_RET_HEDGE_OFF].try_into().unwrap()), 1);
+    // With no schedule there is no band, so the hedge is the whole inventory:
+    // the LP is short what the taker bought, and buys it back
+    assert_eq!(i128::from_le_bytes(data[MATCH_RET_HEDGE_OFF..].try_into().unwrap()), new_inventory);
+
+    let ctx = read_ctx(&mut context, &market).await;
+    let ext = ctx.extension.unwrap();
+    assert_eq!(ctx.last_return, ret);
+    assert_eq!(ctx.inventory, new_inventory);
+    assert_eq!((ctx.last_oracle_price_e6, ctx.last_exec_price_e6), (ORACLE, ret.exec_price_e6));
+    assert_eq!((ext.fill_seq, ext.last_oracle_slot, ext.gross_long), (1, SLOT, 1_000_000));
+
+    // The next fill is numbered after it
+    let call = MatchCall { req_id: 43, trade_size: -500_000, ..call };
+    send(&mut context, &[cpi_match(&market, &call)], &[]).await.unwrap();
+    let ext = read_ctx(&mut context, &market).await.extension.unwrap();
+    assert_eq!((ext.fill_seq, ext.gross_short), (2, 500_000));
+}
+
+#[tokio::test]
+async fn test_match_needs_lp_pda_signature() {
+    let (mut context, market) = start().await;
+    init(&mut context, &market).await;
+    let ctx = market.ctx.pubkey();
+    let (lp, _) = lp_pda(&market.caller, &ctx);
+    let call = MatchCall { req_id: 1, oracle_price_e6: ORACLE, trade_size: 1_000_000, ..Default::default() };
+
+    // Outside a CPI nobody can sign for the PDA
+    let mut direct = instruction::match_trade(&market.matcher, &lp, &ctx, None, None, &call).unwrap();
+    direct.accounts[0].is_signer = false;
+    assert_eq!(
+        send(&mut context, &[direct], &[]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature))
+    );
+
+    // A key that signs but isn't the bound PDA is refused
+    let impostor = Keypair::new();
+    let forged = instruction::match_trade(&market.matcher, &impostor.pubkey(), &ctx, None, None, &call).unwrap();
+    assert_eq!(
+        send(&mut context, &[forged], &[&impostor]).await,
+        Err(TransactionError::InstructionError(0, custom(MatcherError::PdaMismatch)))
+    );
+    assert_eq!(read_ctx(&mut context, &market).await.inventory, 0);
+}
+
+#[tokio::test]
+async fn test_cpi_soft_reject_passes_caller_checks() {
+    let (mut context, market) = start().await;
+    init(&mut context, &market).await;
+    send(&mut context, &[crank(&market)], &[]).await.unwrap();
+    set_flags(&mut context, &market, CTX_FLAG_REDUCE_ONLY | CTX_FLAG_SOFT_REJECT).await;
+
+    // Flat, so any fill would grow the position: answered with a zero fill
+    let call = MatchCall { req_id: 1, lp_account_id: 7, oracle_price_e6: ORACLE, trade_size: 1_000_000, ..Default::default() };
+    send(&mut context, &[cpi_match(&market, &call)], &[]).await.unwrap();
+    let ctx = read_ctx(&mut context, &market).await;
+    assert_eq!((ctx.last_return.req_id, ctx.last_return.exec_size), (1, 0));
+    assert_eq!(ctx.last_return.reject_reason, MatcherError::ReduceOnly as u64);
+    assert_eq!(ctx.inventory, 0);
+
+    // With the prefix left alone, the caller goes by the return data only
+    set_flags(&mut context, &market, CTX_FLAG_RETURN_DATA_ONLY).await;
+    let call = MatchCall { req_id: 2, ..call };
+    send(&mut context, &[cpi_match(&market, &call)], &[]).await.unwrap();
+    assert_eq!(read_ctx(&mut context, &market).await.last_return.req_id, 1);
+}
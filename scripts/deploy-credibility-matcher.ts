@@ -53,7 +53,7 @@ const CREDIBILITY_MATCHER_ID = new PublicKey(
   process.env.CREDIBILITY_MATCHER_ID || marketInfo.credibilityMatcherId || PublicKey.default.toBase58()
 );
 
-const MATCHER_CTX_SIZE = 320;
+const MATCHER_CTX_SIZE = 832; // v6: room for the slab binding
 
 // Credibility matcher parameters
 const KIND_CREDIBILITY = 2;
@@ -214,6 +214,8 @@ async function main() {
       keys: [
         { pubkey: lpPda, isSigner: false, isWritable: false },
         { pubkey: matcherCtxKp.publicKey, isSigner: false, isWritable: true },
+        { pubkey: SLAB, isSigner: false, isWritable: false },
+        { pubkey: payer.publicKey, isSigner: false, isWritable: false }, // matcher authority
       ],
       data: initData,
     },
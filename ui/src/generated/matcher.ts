@@ -0,0 +1,2760 @@
+// Generated by provenance-idl from the matcher's Rust definitions. Do not edit:
+// run `cargo run -p provenance-idl` in matcher/ after changing the program.
+
+import { PublicKey, TransactionInstruction } from "@solana/web3.js";
+
+function view(data: Uint8Array): DataView {
+  return new DataView(data.buffer, data.byteOffset, data.byteLength);
+}
+
+function readU128(dv: DataView, off: number): bigint {
+  return dv.getBigUint64(off, true) | (dv.getBigUint64(off + 8, true) << 64n);
+}
+
+function readI128(dv: DataView, off: number): bigint {
+  return BigInt.asIntN(128, readU128(dv, off));
+}
+
+function writeU128(dv: DataView, off: number, value: bigint): void {
+  const bits = BigInt.asUintN(128, value);
+  dv.setBigUint64(off, bits & 0xffff_ffff_ffff_ffffn, true);
+  dv.setBigUint64(off + 8, bits >> 64n, true);
+}
+
+interface Meta {
+  pubkey: PublicKey;
+  isSigner: boolean;
+  isWritable: boolean;
+}
+
+/** Optional accounts are positional: one can only follow every optional account before it. */
+function accountMetas(entries: [string, PublicKey | undefined, boolean, boolean, boolean][]): Meta[] {
+  const keys: Meta[] = [];
+  let gap: string | undefined;
+  for (const [name, pubkey, isSigner, isWritable, optional] of entries) {
+    if (pubkey === undefined) {
+      if (!optional) throw new Error(`missing account ${name}`);
+      gap ??= name;
+      continue;
+    }
+    if (gap !== undefined) throw new Error(`account ${name} needs ${gap} before it`);
+    keys.push({ pubkey, isSigner, isWritable });
+  }
+  return keys;
+}
+
+// Constants
+
+export const MAGIC = 0x504552434d415443n;
+export const VERSION = 6;
+export const VERSION_V5 = 5;
+export const KIND_PLAIN = 1;
+export const KIND_CREDIBILITY = 2;
+export const KIND_MULTI = 3;
+export const KIND_STABLE = 4;
+export const MATCHER_ABI_VERSION = 1;
+export const FLAG_VALID = 1;
+export const CTX_BASE = 64;
+export const CTX_LEN = 320;
+export const EXT_BASE = 320;
+export const CTX_EXT_LEN = 832;
+export const MARKETS_BASE = 832;
+export const MARKET_LEN = 256;
+export const MAX_MARKETS = 8;
+export const CTX_SHARED_LEN = 2880;
+export const CREATION_LEN = 48;
+export const CTX_FLAG_PAUSED = 1;
+export const CTX_FLAG_REDUCE_ONLY = 2;
+export const CTX_FLAG_RETURN_DATA_ONLY = 4;
+export const CTX_FLAG_SOFT_REJECT = 8;
+export const CTX_FLAG_NO_BIDS = 16;
+export const CTX_FLAG_NO_ASKS = 32;
+export const CTX_FLAG_SCHEDULED = 64;
+export const CTX_FLAG_FEED = 128;
+export const PARAMS_LEN = 72;
+export const PARAMS_MAX_LEN = 136;
+export const PENDING_HEAD_LEN = 104;
+export const STATS_MAGIC = 0x5045524353544154n;
+export const STATS_VERSION = 1;
+export const STATS_CAPACITY = 64;
+export const ORACLE_CAPACITY = 32;
+export const STATS_LEN = 3648;
+export const STATS_LEDGER_LEN = 3680;
+export const STATS_SKIM_LEN = 3728;
+export const STATS_PROTOCOL_LEN = 3808;
+export const STATS_PNL_LEN = 3856;
+export const SIGNAL_MAGIC = 0x504552435349474en;
+export const SIGNAL_LEN = 32;
+export const MAX_SIGNAL_ACCOUNTS = 4;
+export const SCHEDULE_MAGIC = 0x5045524353434844n;
+export const SCHEDULE_LEN = 240;
+export const MAX_SCHEDULE_ENTRIES = 8;
+export const SCHEDULE_FEES_LEN = 336;
+export const MAX_FEE_TIERS = 4;
+export const SCHEDULE_REBATE_LEN = 344;
+export const SCHEDULE_MIN_NOTIONAL_LEN = 360;
+export const SCHEDULE_LOYALTY_LEN = 456;
+export const SCHEDULE_DECAY_LEN = 464;
+export const SCHEDULE_BAND_LEN = 488;
+export const SCHEDULE_CAPS_LEN = 520;
+export const SCHEDULE_REDUCE_ONLY_LEN = 528;
+export const SCHEDULE_DEPTH_CAP_LEN = 536;
+export const SCHEDULE_SIGNALS_LEN = 800;
+export const MAX_LOYALTY_TIERS = 4;
+export const LADDER_MAGIC = 0x504552434c414452n;
+export const LADDER_LEN = 608;
+export const TIP_JAR_MAGIC = 0x5045524354495053n;
+export const TIP_JAR_LEN = 80;
+export const REFERRAL_MAGIC = 0x5045524352454653n;
+export const REFERRAL_LEN = 952;
+export const MAX_REFERRERS = 16;
+export const TRADER_MAGIC = 0x5045524354524452n;
+export const TRADER_LEN = 112;
+export const MAX_LADDER_LEVELS = 8;
+export const FEED_MAGIC = 0x5045524346454544n;
+export const FEED_LEN = 176;
+export const MAX_FEEDS = 3;
+export const FEED_SOURCE_PYTH = 0;
+export const FEED_SOURCE_SWITCHBOARD = 1;
+export const FEED_SOURCE_CHAINLINK = 2;
+export const ORACLE_DECIMALS_E6 = 6;
+export const MAX_ORACLE_DECIMALS = 18;
+export const SETTLE_SET = 0;
+export const SETTLE_ADJUST = 1;
+export const IMPACT_NONE = 0;
+export const IMPACT_LINEAR = 1;
+export const IMPACT_SQRT = 2;
+export const CURVE_LINEAR = 0;
+export const CURVE_SQRT = 1;
+export const CURVE_PIECEWISE = 2;
+export const CURVE_POINTS = 4;
+export const SIGNAL_COVERAGE = 0;
+export const SIGNAL_AGE = 1;
+export const SIGNAL_DEFICIT = 2;
+export const SIGNAL_LIQUIDATIONS = 3;
+export const SIGNALS = 4;
+export const RFQ_MSG_LEN = 72;
+export const TAG_MATCH = 0x00;
+export const TAG_INIT = 0x02;
+export const TAG_INIT_PLAIN = 0x02;
+export const TAG_INIT_MULTI = 0x02;
+export const TAG_INIT_STABLE = 0x02;
+export const TAG_UPDATE_CREDIBILITY = 0x03;
+export const TAG_UPDATE_PARAMS = 0x04;
+export const TAG_CLOSE = 0x05;
+export const TAG_SET_PAUSED = 0x06;
+export const TAG_MIGRATE = 0x07;
+export const TAG_QUOTE_PREVIEW = 0x08;
+export const TAG_PROPOSE_PARAMS = 0x09;
+export const TAG_COMMIT_PARAMS = 0x0a;
+export const TAG_SET_REDUCE_ONLY = 0x0b;
+export const TAG_SETTLE_INVENTORY = 0x0c;
+export const TAG_PROPOSE_AUTHORITY = 0x0d;
+export const TAG_ACCEPT_AUTHORITY = 0x0e;
+export const TAG_SET_RETURN_DATA_ONLY = 0x0f;
+export const TAG_SET_SOFT_REJECT = 0x10;
+export const TAG_QUOTE_TWO_SIDED = 0x11;
+export const TAG_INIT_STATS = 0x12;
+export const TAG_SET_RFQ_SIGNER = 0x13;
+export const TAG_POST_RFQ = 0x14;
+export const TAG_SET_MARKET = 0x15;
+export const TAG_SET_NO_BIDS = 0x16;
+export const TAG_SET_NO_ASKS = 0x17;
+export const TAG_INIT_SCHEDULE = 0x18;
+export const TAG_SET_SCHEDULE = 0x19;
+export const TAG_INIT_LADDER = 0x1a;
+export const TAG_PUBLISH_LADDER = 0x1b;
+export const TAG_INIT_FEED = 0x1c;
+export const TAG_ADD_FEED = 0x1d;
+export const TAG_SET_FEED_DIVERGENCE = 0x1e;
+export const TAG_SET_ORACLE_DECIMALS = 0x1f;
+export const TAG_SET_FEED_SLOT_AGE = 0x20;
+export const TAG_SET_FEED_FALLBACK = 0x21;
+export const TAG_SET_FEE_TIERS = 0x22;
+export const TAG_SET_REBATE = 0x23;
+export const TAG_RECORD_WITHDRAWAL = 0x24;
+export const TAG_SET_FEE_DISCOUNT = 0x25;
+export const TAG_INIT_TIP_JAR = 0x26;
+export const TAG_SET_TIP = 0x27;
+export const TAG_CLOSE_TIP_JAR = 0x28;
+export const TAG_INIT_REFERRAL = 0x29;
+export const TAG_SET_REFERRAL_SHARE = 0x2a;
+export const TAG_SET_MIN_NOTIONAL = 0x2b;
+export const TAG_SET_SKIM = 0x2c;
+export const TAG_CLAIM_SKIM = 0x2d;
+export const TAG_INIT_TRADER = 0x2e;
+export const TAG_SET_LOYALTY_TIERS = 0x2f;
+export const TAG_SWEEP_PROTOCOL_FEE = 0x30;
+export const TAG_SET_INVENTORY_DECAY = 0x31;
+export const TAG_SET_INVENTORY_BAND = 0x32;
+export const TAG_SET_INVENTORY_CAPS = 0x33;
+export const TAG_SET_REDUCE_ONLY_THRESHOLD = 0x34;
+export const TAG_TRANSFER_INVENTORY = 0x35;
+export const TAG_SET_INVENTORY_DEPTH_CAP = 0x36;
+export const TAG_SYNC_INVENTORY = 0x37;
+export const TAG_CREATE_CONTEXT = 0x38;
+export const TAG_BIND_SLAB = 0x39;
+export const TAG_SET_SIGNALS = 0x3a;
+
+/** Coverage tier names, indexed by QuoteBreakdown.tier. */
+export const TIER_NAMES = ["CRITICAL", "FRAGILE", "NORMAL", "STRONG", "FORTIFIED"] as const;
+
+// Errors
+
+export interface MatcherErrorInfo {
+  code: number;
+  name: string;
+  message: string;
+}
+
+/** `custom program error` codes; never renumbered. */
+export const MATCHER_ERRORS: MatcherErrorInfo[] = [
+  { code: 1, name: "Paused", message: "matcher is paused" },
+  { code: 2, name: "ReduceOnly", message: "reduce-only: trade would grow inventory" },
+  { code: 3, name: "InventoryOverflow", message: "inventory overflow" },
+  { code: 4, name: "MaxFillExceeded", message: "trade exceeds the tier fill limit" },
+  { code: 5, name: "InventoryLimit", message: "trade would exceed the inventory limit" },
+  { code: 6, name: "ZeroOraclePrice", message: "zero oracle price" },
+  { code: 7, name: "NotInitialized", message: "context not initialized" },
+  { code: 8, name: "PdaMismatch", message: "LP PDA mismatch" },
+  { code: 9, name: "AuthorityMismatch", message: "authority mismatch" },
+  { code: 10, name: "WrongKind", message: "unsupported matcher kind" },
+  { code: 11, name: "ExtensionRequired", message: "needs a v6 (extended) context" },
+  { code: 12, name: "MarketLive", message: "market is live; use ProposeParams/CommitParams" },
+  { code: 13, name: "SlabMismatch", message: "slab mismatch" },
+  { code: 14, name: "SlabOwnerMismatch", message: "slab owned by the wrong program" },
+  { code: 15, name: "InvalidClock", message: "expected the Clock sysvar" },
+  { code: 16, name: "NotPendingAuthority", message: "not the pending authority" },
+  { code: 17, name: "NothingToMigrate", message: "nothing to migrate" },
+  { code: 18, name: "NoPendingProposal", message: "no pending parameter proposal" },
+  { code: 19, name: "TimelockActive", message: "parameter proposal still timelocked" },
+  { code: 20, name: "UnknownImpactMode", message: "unknown impact_mode" },
+  { code: 21, name: "UnknownCoverageCurve", message: "unknown coverage_curve" },
+  { code: 22, name: "InvalidCurvePoints", message: "curve points must be non-decreasing and <= 10000" },
+  { code: 23, name: "SpreadOrder", message: "min_spread_bps above max_spread_bps" },
+  { code: 24, name: "SpreadTooWide", message: "max_spread_bps above 10000" },
+  { code: 25, name: "FeeTooHigh", message: "base_fee_bps above 10000" },
+  { code: 26, name: "InsuranceWeightTooHigh", message: "insurance_weight_bps above 10000" },
+  { code: 27, name: "LiquidityRequired", message: "liquidity_notional_e6 required by a depth-scaled term" },
+  { code: 28, name: "DuplicateAccount", message: "account passed more than once" },
+  { code: 29, name: "OracleJump", message: "oracle moved too far since the last fill" },
+  { code: 30, name: "InvalidOracleBreaker", message: "oracle breaker needs both a threshold and a window" },
+  { code: 31, name: "StatsMismatch", message: "stats account not bound to this context" },
+  { code: 32, name: "StatsRequired", message: "the bound stats account is required" },
+  { code: 33, name: "InvalidSignalWeights", message: "signal weights must sum to 10000, each weighted signal with a halflife" },
+  { code: 34, name: "RfqSignerUnset", message: "no RFQ signer set" },
+  { code: 35, name: "RfqUnverified", message: "RFQ quote not verified for the RFQ signer" },
+  { code: 36, name: "RfqExpired", message: "RFQ quote expired" },
+  { code: 37, name: "RfqReplayed", message: "RFQ nonce already used" },
+  { code: 38, name: "InvalidRfq", message: "RFQ quote for another context or empty" },
+  { code: 39, name: "InvalidLiqAuction", message: "liquidation auction needs both a premium and a length" },
+  { code: 40, name: "UnknownMarket", message: "market not in this context's market table" },
+  { code: 41, name: "MarketTableFull", message: "no free market slot" },
+  { code: 42, name: "SharedContext", message: "not available on a shared context" },
+  { code: 43, name: "InvalidSignal", message: "invalid signal account" },
+  { code: 44, name: "SideDisabled", message: "the LP isn't quoting this side" },
+  { code: 45, name: "ScheduleMismatch", message: "schedule account not bound to this context" },
+  { code: 46, name: "InvalidSchedule", message: "invalid spread schedule" },
+  { code: 47, name: "LadderMismatch", message: "ladder account not bound to this context" },
+  { code: 48, name: "FeedMismatch", message: "feed account not bound to this context" },
+  { code: 49, name: "InvalidFeedPrice", message: "invalid oracle price account" },
+  { code: 50, name: "FeedPriceStale", message: "oracle price too old" },
+  { code: 51, name: "FeedDivergence", message: "oracle feeds disagree" },
+  { code: 52, name: "InvalidFeedSet", message: "feed account full, or feed already bound" },
+  { code: 53, name: "SpotWeightTooHigh", message: "spot_weight_bps above 10000" },
+  { code: 54, name: "InvalidFeeTiers", message: "fee tiers must rise in notional, each at most 10000 bps" },
+  { code: 55, name: "RebateTooHigh", message: "rebate_bps above 10000" },
+  { code: 56, name: "WithdrawalExceedsFees", message: "withdrawal exceeds the fees accrued" },
+  { code: 57, name: "FeeDiscountTooHigh", message: "fee_discount_bps above 10000" },
+  { code: 58, name: "TipJarMismatch", message: "tip jar not bound to this context" },
+  { code: 59, name: "ReferralShareTooHigh", message: "referral share_bps above 10000" },
+  { code: 60, name: "ReferralMismatch", message: "referral account not bound to this context" },
+  { code: 61, name: "BelowMinNotional", message: "trade notional below the minimum" },
+  { code: 62, name: "SkimTooHigh", message: "skim_bps above 10000" },
+  { code: 63, name: "SkimExceedsAccrued", message: "claim exceeds the skim accrued" },
+  { code: 64, name: "InvalidLoyaltyTiers", message: "loyalty tiers must rise in volume, each at most 10000 bps" },
+  { code: 65, name: "TraderMismatch", message: "trader account not bound to this context and trader" },
+  { code: 66, name: "ProtocolFeeTooHigh", message: "protocol_fee_bps above 10000" },
+  { code: 67, name: "TreasuryMismatch", message: "signer is not the stats account's treasury" },
+  { code: 68, name: "SweepExceedsAccrued", message: "sweep exceeds the protocol fee accrued" },
+  { code: 69, name: "InvalidInventoryBand", message: "soft inventory must sit below max_inventory, band_k_bps at most 10000" },
+  { code: 70, name: "ReduceOnlyThresholdTooHigh", message: "reduce_only_bps above 10000" },
+  { code: 71, name: "LpAccountMismatch", message: "slab account is not an LP matched through this context" },
+  { code: 72, name: "ContextAddressMismatch", message: "context is not at its LP PDA and slab's address" },
+];
+
+export function matcherError(code: number): MatcherErrorInfo | undefined {
+  return MATCHER_ERRORS.find((e) => e.code === code);
+}
+
+// Types
+
+/** Parameter block of Init, UpdateParams and ProposeParams. The first PARAMS_LEN bytes are required; missing trailing fields read as zero. */
+export interface MatcherParams {
+  baseFeeBps: number;
+  minSpreadBps: number;
+  maxSpreadBps: number;
+  imbalanceKBps: number;
+  liquidityE6: bigint;
+  maxFill: bigint;
+  maxInventory: bigint;
+  ageHalflife: number;
+  insuranceWeightBps: number;
+  impactMode: number;
+  impactKBps: number;
+  skewKBps: number;
+  coverageCurve: number;
+  curvePoints: number[];
+  liqHalflife: number;
+  fundingHorizon: number;
+  oracleJumpBps: number;
+  oracleJumpWindow: number;
+  flowKBps: number;
+  twapWindowSlots: number;
+  signalWeights: number[];
+  deficitHalflife: number;
+  liqAuctionBps: number;
+  liqAuctionSlots: number;
+  pegBandE6: number;
+  spotWeightBps: number;
+}
+
+export const MATCHER_PARAMS_SIZE = 136;
+
+export function decodeMatcherParams(data: Uint8Array, offset = 0): MatcherParams {
+  const dv = view(data);
+  return {
+    baseFeeBps: dv.getUint32(offset + 0, true),
+    minSpreadBps: dv.getUint32(offset + 4, true),
+    maxSpreadBps: dv.getUint32(offset + 8, true),
+    imbalanceKBps: dv.getUint32(offset + 12, true),
+    liquidityE6: readU128(dv, offset + 16),
+    maxFill: readU128(dv, offset + 32),
+    maxInventory: readU128(dv, offset + 48),
+    ageHalflife: dv.getUint32(offset + 64, true),
+    insuranceWeightBps: dv.getUint32(offset + 68, true),
+    impactMode: dv.getUint8(offset + 72),
+    impactKBps: dv.getUint32(offset + 73, true),
+    skewKBps: dv.getUint32(offset + 77, true),
+    coverageCurve: dv.getUint8(offset + 81),
+    curvePoints: Array.from({ length: 4 }, (_, i) => dv.getUint16(offset + 82 + i * 2, true)),
+    liqHalflife: dv.getUint32(offset + 90, true),
+    fundingHorizon: dv.getUint32(offset + 94, true),
+    oracleJumpBps: dv.getUint16(offset + 98, true),
+    oracleJumpWindow: dv.getUint32(offset + 100, true),
+    flowKBps: dv.getUint32(offset + 104, true),
+    twapWindowSlots: dv.getUint32(offset + 108, true),
+    signalWeights: Array.from({ length: 4 }, (_, i) => dv.getUint16(offset + 112 + i * 2, true)),
+    deficitHalflife: dv.getUint32(offset + 120, true),
+    liqAuctionBps: dv.getUint16(offset + 124, true),
+    liqAuctionSlots: dv.getUint32(offset + 126, true),
+    pegBandE6: dv.getUint32(offset + 130, true),
+    spotWeightBps: dv.getUint16(offset + 134, true),
+  };
+}
+
+export function encodeMatcherParams(value: MatcherParams, data: Uint8Array, offset = 0): void {
+  const dv = view(data);
+  dv.setUint32(offset + 0, value.baseFeeBps, true);
+  dv.setUint32(offset + 4, value.minSpreadBps, true);
+  dv.setUint32(offset + 8, value.maxSpreadBps, true);
+  dv.setUint32(offset + 12, value.imbalanceKBps, true);
+  writeU128(dv, offset + 16, value.liquidityE6);
+  writeU128(dv, offset + 32, value.maxFill);
+  writeU128(dv, offset + 48, value.maxInventory);
+  dv.setUint32(offset + 64, value.ageHalflife, true);
+  dv.setUint32(offset + 68, value.insuranceWeightBps, true);
+  dv.setUint8(offset + 72, value.impactMode);
+  dv.setUint32(offset + 73, value.impactKBps, true);
+  dv.setUint32(offset + 77, value.skewKBps, true);
+  dv.setUint8(offset + 81, value.coverageCurve);
+  value.curvePoints.forEach((item, i) => { dv.setUint16(offset + 82 + i * 2, item, true); });
+  dv.setUint32(offset + 90, value.liqHalflife, true);
+  dv.setUint32(offset + 94, value.fundingHorizon, true);
+  dv.setUint16(offset + 98, value.oracleJumpBps, true);
+  dv.setUint32(offset + 100, value.oracleJumpWindow, true);
+  dv.setUint32(offset + 104, value.flowKBps, true);
+  dv.setUint32(offset + 108, value.twapWindowSlots, true);
+  value.signalWeights.forEach((item, i) => { dv.setUint16(offset + 112 + i * 2, item, true); });
+  dv.setUint32(offset + 120, value.deficitHalflife, true);
+  dv.setUint16(offset + 124, value.liqAuctionBps, true);
+  dv.setUint32(offset + 126, value.liqAuctionSlots, true);
+  dv.setUint32(offset + 130, value.pegBandE6, true);
+  dv.setUint16(offset + 134, value.spotWeightBps, true);
+}
+
+/** Result of a Match: the first RET_LEN bytes of the context account and of Match's return data. */
+export interface MatcherReturn {
+  abiVersion: number;
+  flags: number;
+  execPriceE6: bigint;
+  execSize: bigint;
+  reqId: bigint;
+  lpAccountId: bigint;
+  oraclePriceE6: bigint;
+  rejectReason: bigint;
+}
+
+export const MATCHER_RETURN_SIZE = 64;
+
+export function decodeMatcherReturn(data: Uint8Array, offset = 0): MatcherReturn {
+  const dv = view(data);
+  return {
+    abiVersion: dv.getUint32(offset + 0, true),
+    flags: dv.getUint32(offset + 4, true),
+    execPriceE6: dv.getBigUint64(offset + 8, true),
+    execSize: readI128(dv, offset + 16),
+    reqId: dv.getBigUint64(offset + 32, true),
+    lpAccountId: dv.getBigUint64(offset + 40, true),
+    oraclePriceE6: dv.getBigUint64(offset + 48, true),
+    rejectReason: dv.getBigUint64(offset + 56, true),
+  };
+}
+
+/** How a quote was priced: QuotePreview's return data, also embedded in Match and QuoteTwoSided returns. */
+export interface QuoteBreakdown {
+  execPriceE6: bigint;
+  coverageBps: bigint;
+  tier: bigint;
+  fillPct: bigint;
+  tierSpreadBps: bigint;
+  imbalanceBps: bigint;
+  impactBps: bigint;
+  staleBps: bigint;
+  crankBps: bigint;
+  liqBps: bigint;
+  spreadBps: bigint;
+  skewBps: bigint;
+  fundingSkewBps: bigint;
+  feeBps: bigint;
+  newInventory: bigint;
+  fillSize: bigint;
+  discountBps: bigint;
+  flowBps: bigint;
+  anchorPriceE6: bigint;
+  signalBps: bigint;
+  scheduleBps: bigint;
+  oracleBps: bigint;
+  rebateBps: bigint;
+}
+
+export const QUOTE_BREAKDOWN_SIZE = 200;
+
+export function decodeQuoteBreakdown(data: Uint8Array, offset = 0): QuoteBreakdown {
+  const dv = view(data);
+  return {
+    execPriceE6: dv.getBigUint64(offset + 0, true),
+    coverageBps: dv.getBigUint64(offset + 8, true),
+    tier: dv.getBigUint64(offset + 16, true),
+    fillPct: dv.getBigUint64(offset + 24, true),
+    tierSpreadBps: dv.getBigUint64(offset + 32, true),
+    imbalanceBps: dv.getBigUint64(offset + 40, true),
+    impactBps: dv.getBigUint64(offset + 48, true),
+    staleBps: dv.getBigUint64(offset + 56, true),
+    crankBps: dv.getBigUint64(offset + 64, true),
+    liqBps: dv.getBigUint64(offset + 72, true),
+    spreadBps: dv.getBigUint64(offset + 80, true),
+    skewBps: dv.getBigInt64(offset + 88, true),
+    fundingSkewBps: dv.getBigInt64(offset + 96, true),
+    feeBps: dv.getBigUint64(offset + 104, true),
+    newInventory: readI128(dv, offset + 112),
+    fillSize: readI128(dv, offset + 128),
+    discountBps: dv.getBigUint64(offset + 144, true),
+    flowBps: dv.getBigUint64(offset + 152, true),
+    anchorPriceE6: dv.getBigUint64(offset + 160, true),
+    signalBps: dv.getBigUint64(offset + 168, true),
+    scheduleBps: dv.getBigUint64(offset + 176, true),
+    oracleBps: dv.getBigUint64(offset + 184, true),
+    rebateBps: dv.getBigUint64(offset + 192, true),
+  };
+}
+
+/** Match's return data. hedge_size is what an off-chain hedger should trade, positive to buy, to bring the book's inventory back within the schedule's soft_inventory (all of it without a band); the LP is short a positive inventory, so it buys. 0 on a soft reject. */
+export interface MatchReturn {
+  result: MatcherReturn;
+  quote: QuoteBreakdown;
+  fillSeq: bigint;
+  hedgeSize: bigint;
+}
+
+export const MATCH_RETURN_SIZE = 288;
+
+export function decodeMatchReturn(data: Uint8Array, offset = 0): MatchReturn {
+  const dv = view(data);
+  return {
+    result: decodeMatcherReturn(data, offset + 0),
+    quote: decodeQuoteBreakdown(data, offset + 64),
+    fillSeq: dv.getBigUint64(offset + 264, true),
+    hedgeSize: readI128(dv, offset + 272),
+  };
+}
+
+/** QuoteTwoSided's return data. A side with a non-zero reject reason (a MatcherError code) has a zeroed quote. */
+export interface TwoSidedQuote {
+  bidRejectReason: bigint;
+  askRejectReason: bigint;
+  bid: QuoteBreakdown;
+  ask: QuoteBreakdown;
+}
+
+export const TWO_SIDED_QUOTE_SIZE = 416;
+
+export function decodeTwoSidedQuote(data: Uint8Array, offset = 0): TwoSidedQuote {
+  const dv = view(data);
+  return {
+    bidRejectReason: dv.getBigUint64(offset + 0, true),
+    askRejectReason: dv.getBigUint64(offset + 8, true),
+    bid: decodeQuoteBreakdown(data, offset + 16),
+    ask: decodeQuoteBreakdown(data, offset + 216),
+  };
+}
+
+/** The v5 context body at CTX_BASE. */
+export interface ContextCore {
+  magic: bigint;
+  version: number;
+  kind: number;
+  oracleDecimals: number;
+  spotWeightBps: number;
+  lpPda: PublicKey;
+  baseFeeBps: number;
+  minSpreadBps: number;
+  maxSpreadBps: number;
+  imbalanceKBps: number;
+  liquidityE6: bigint;
+  maxFill: bigint;
+  inventory: bigint;
+  lastOraclePriceE6: bigint;
+  lastExecPriceE6: bigint;
+  maxInventory: bigint;
+  insuranceSnapshot: bigint;
+  totalOiSnapshot: bigint;
+  marketAge: bigint;
+  lastDeficit: bigint;
+  snapshotSlot: bigint;
+  ageHalflife: number;
+  insuranceWeightBps: number;
+  impactMode: number;
+  coverageCurve: number;
+  flags: number;
+  impactKBps: number;
+  skewKBps: number;
+  curvePoints: number[];
+  pegBandE6: number;
+  lastCrankSlot: bigint;
+  lastLiquidations: bigint;
+  liqHeat: number;
+  liqHalflife: number;
+}
+
+export const CONTEXT_CORE_SIZE = 256;
+
+export function decodeContextCore(data: Uint8Array, offset = 0): ContextCore {
+  const dv = view(data);
+  return {
+    magic: dv.getBigUint64(offset + 0, true),
+    version: dv.getUint32(offset + 8, true),
+    kind: dv.getUint8(offset + 12),
+    oracleDecimals: dv.getUint8(offset + 13),
+    spotWeightBps: dv.getUint16(offset + 14, true),
+    lpPda: new PublicKey(data.slice(offset + 16, offset + 16 + 32)),
+    baseFeeBps: dv.getUint32(offset + 48, true),
+    minSpreadBps: dv.getUint32(offset + 52, true),
+    maxSpreadBps: dv.getUint32(offset + 56, true),
+    imbalanceKBps: dv.getUint32(offset + 60, true),
+    liquidityE6: readU128(dv, offset + 64),
+    maxFill: readU128(dv, offset + 80),
+    inventory: readI128(dv, offset + 96),
+    lastOraclePriceE6: dv.getBigUint64(offset + 112, true),
+    lastExecPriceE6: dv.getBigUint64(offset + 120, true),
+    maxInventory: readU128(dv, offset + 128),
+    insuranceSnapshot: readU128(dv, offset + 144),
+    totalOiSnapshot: readU128(dv, offset + 160),
+    marketAge: dv.getBigUint64(offset + 176, true),
+    lastDeficit: dv.getBigUint64(offset + 184, true),
+    snapshotSlot: dv.getBigUint64(offset + 192, true),
+    ageHalflife: dv.getUint32(offset + 200, true),
+    insuranceWeightBps: dv.getUint32(offset + 204, true),
+    impactMode: dv.getUint8(offset + 208),
+    coverageCurve: dv.getUint8(offset + 209),
+    flags: dv.getUint8(offset + 210),
+    impactKBps: dv.getUint32(offset + 212, true),
+    skewKBps: dv.getUint32(offset + 216, true),
+    curvePoints: Array.from({ length: 4 }, (_, i) => dv.getUint16(offset + 220 + i * 2, true)),
+    pegBandE6: dv.getUint32(offset + 228, true),
+    lastCrankSlot: dv.getBigUint64(offset + 232, true),
+    lastLiquidations: dv.getBigUint64(offset + 240, true),
+    liqHeat: dv.getUint32(offset + 248, true),
+    liqHalflife: dv.getUint32(offset + 252, true),
+  };
+}
+
+/** The v6 extension at EXT_BASE. A queued proposal is split: its first PENDING_HEAD_LEN bytes are pending_params_head, the rest pending_params_tail. */
+export interface ContextExtension {
+  fundingRateBpsPerSlot: bigint;
+  fundingHorizon: number;
+  liqAuctionSlots: number;
+  slab: PublicKey;
+  pendingActivationSlot: bigint;
+  pendingParamsHead: Uint8Array;
+  authority: PublicKey;
+  pendingAuthority: PublicKey;
+  percolatorProgram: PublicKey;
+  oracleJumpBps: number;
+  liqAuctionBps: number;
+  oracleJumpWindow: number;
+  lastOracleSlot: bigint;
+  fillSeq: bigint;
+  cumNotional: bigint;
+  cumFees: bigint;
+  grossLong: bigint;
+  grossShort: bigint;
+  flowKBps: number;
+  twapWindowSlots: number;
+  pendingParamsTail: Uint8Array;
+  stats: PublicKey;
+  signalWeights: number[];
+  deficitHalflife: number;
+  marketSlots: number;
+  rfqSigner: PublicKey;
+  rfqPriceE6: bigint;
+  rfqExpirySlot: bigint;
+  rfqNonce: bigint;
+  rfqSizeLeft: bigint;
+  lastLiqSlot: bigint;
+}
+
+export const CONTEXT_EXTENSION_SIZE = 512;
+
+export function decodeContextExtension(data: Uint8Array, offset = 0): ContextExtension {
+  const dv = view(data);
+  return {
+    fundingRateBpsPerSlot: dv.getBigInt64(offset + 0, true),
+    fundingHorizon: dv.getUint32(offset + 8, true),
+    liqAuctionSlots: dv.getUint32(offset + 12, true),
+    slab: new PublicKey(data.slice(offset + 16, offset + 16 + 32)),
+    pendingActivationSlot: dv.getBigUint64(offset + 48, true),
+    pendingParamsHead: data.slice(offset + 56, offset + 56 + 104),
+    authority: new PublicKey(data.slice(offset + 160, offset + 160 + 32)),
+    pendingAuthority: new PublicKey(data.slice(offset + 192, offset + 192 + 32)),
+    percolatorProgram: new PublicKey(data.slice(offset + 224, offset + 224 + 32)),
+    oracleJumpBps: dv.getUint16(offset + 256, true),
+    liqAuctionBps: dv.getUint16(offset + 258, true),
+    oracleJumpWindow: dv.getUint32(offset + 260, true),
+    lastOracleSlot: dv.getBigUint64(offset + 264, true),
+    fillSeq: dv.getBigUint64(offset + 272, true),
+    cumNotional: readU128(dv, offset + 280),
+    cumFees: readU128(dv, offset + 296),
+    grossLong: readU128(dv, offset + 312),
+    grossShort: readU128(dv, offset + 328),
+    flowKBps: dv.getUint32(offset + 344, true),
+    twapWindowSlots: dv.getUint32(offset + 348, true),
+    pendingParamsTail: data.slice(offset + 352, offset + 352 + 32),
+    stats: new PublicKey(data.slice(offset + 416, offset + 416 + 32)),
+    signalWeights: Array.from({ length: 4 }, (_, i) => dv.getUint16(offset + 448 + i * 2, true)),
+    deficitHalflife: dv.getUint32(offset + 456, true),
+    marketSlots: dv.getUint32(offset + 460, true),
+    rfqSigner: new PublicKey(data.slice(offset + 384, offset + 384 + 32)),
+    rfqPriceE6: dv.getBigUint64(offset + 464, true),
+    rfqExpirySlot: dv.getBigUint64(offset + 472, true),
+    rfqNonce: dv.getBigUint64(offset + 480, true),
+    rfqSizeLeft: readI128(dv, offset + 488),
+    lastLiqSlot: dv.getBigUint64(offset + 504, true),
+  };
+}
+
+/** One market of a shared context: its slab (the default key on a free entry), caps that override the context's when non-zero, and the inventory and snapshots a single-market context keeps in its core and extension. */
+export interface MarketBook {
+  slab: PublicKey;
+  maxFill: bigint;
+  maxInventory: bigint;
+  inventory: bigint;
+  insuranceSnapshot: bigint;
+  totalOiSnapshot: bigint;
+  grossLong: bigint;
+  grossShort: bigint;
+  snapshotSlot: bigint;
+  lastCrankSlot: bigint;
+  lastLiquidations: bigint;
+  fundingRateBpsPerSlot: bigint;
+  marketAge: bigint;
+  lastDeficit: bigint;
+  lastLiqSlot: bigint;
+  lastOraclePriceE6: bigint;
+  lastOracleSlot: bigint;
+  lastExecPriceE6: bigint;
+  liqHeat: number;
+}
+
+export const MARKET_BOOK_SIZE = 256;
+
+export function decodeMarketBook(data: Uint8Array, offset = 0): MarketBook {
+  const dv = view(data);
+  return {
+    slab: new PublicKey(data.slice(offset + 0, offset + 0 + 32)),
+    maxFill: readU128(dv, offset + 32),
+    maxInventory: readU128(dv, offset + 48),
+    inventory: readI128(dv, offset + 64),
+    insuranceSnapshot: readU128(dv, offset + 80),
+    totalOiSnapshot: readU128(dv, offset + 96),
+    grossLong: readU128(dv, offset + 112),
+    grossShort: readU128(dv, offset + 128),
+    snapshotSlot: dv.getBigUint64(offset + 144, true),
+    lastCrankSlot: dv.getBigUint64(offset + 152, true),
+    lastLiquidations: dv.getBigUint64(offset + 160, true),
+    fundingRateBpsPerSlot: dv.getBigInt64(offset + 168, true),
+    marketAge: dv.getBigUint64(offset + 176, true),
+    lastDeficit: dv.getBigUint64(offset + 184, true),
+    lastLiqSlot: dv.getBigUint64(offset + 192, true),
+    lastOraclePriceE6: dv.getBigUint64(offset + 200, true),
+    lastOracleSlot: dv.getBigUint64(offset + 208, true),
+    lastExecPriceE6: dv.getBigUint64(offset + 216, true),
+    liqHeat: dv.getUint32(offset + 224, true),
+  };
+}
+
+/** The record of a v6 context: the slot and the key that signed Init (the authority it set, or the LP PDA), and derived when CreateContext made the account, at the address the bump derives. It follows the layout, at CTX_EXT_LEN or CTX_SHARED_LEN, in any account created CREATION_LEN bytes longer, derived or not; others have none. */
+export interface Creation {
+  slot: bigint;
+  creator: PublicKey;
+  derived: boolean;
+}
+
+export const CREATION_SIZE = 48;
+
+export function decodeCreation(data: Uint8Array, offset = 0): Creation {
+  const dv = view(data);
+  return {
+    slot: dv.getBigUint64(offset + 0, true),
+    creator: new PublicKey(data.slice(offset + 8, offset + 8 + 32)),
+    derived: dv.getUint8(offset + 40) !== 0,
+  };
+}
+
+/** A context account: CTX_LEN bytes at v5, CTX_EXT_LEN at v6, CTX_SHARED_LEN for a shared context with a market table (extension.market_slots non-zero). authority and slab are the default key when unset; the LP PDA administers a context without an authority. A Creation record may follow the layout. */
+export interface MatcherContext {
+  lastReturn: MatcherReturn;
+  core: ContextCore;
+  extension?: ContextExtension;
+  markets?: MarketBook[];
+}
+
+export const MATCHER_CONTEXT_SIZE = 2880;
+
+export function decodeMatcherContext(data: Uint8Array, offset = 0): MatcherContext {
+  return {
+    lastReturn: decodeMatcherReturn(data, offset + 0),
+    core: decodeContextCore(data, offset + 64),
+    extension: data.length >= offset + 832 ? decodeContextExtension(data, offset + 320) : undefined,
+    markets: data.length >= offset + 2880 ? Array.from({ length: 8 }, (_, i) => decodeMarketBook(data, offset + 832 + i * 256)) : undefined,
+  };
+}
+
+/** A quote signed off-chain by the context's RFQ signer; these bytes are the signed message. size is signed like a trade: positive when takers may buy. */
+export interface RfqQuote {
+  ctx: PublicKey;
+  priceE6: bigint;
+  size: bigint;
+  expirySlot: bigint;
+  nonce: bigint;
+}
+
+export const RFQ_QUOTE_SIZE = 72;
+
+export function decodeRfqQuote(data: Uint8Array, offset = 0): RfqQuote {
+  const dv = view(data);
+  return {
+    ctx: new PublicKey(data.slice(offset + 0, offset + 0 + 32)),
+    priceE6: dv.getBigUint64(offset + 32, true),
+    size: readI128(dv, offset + 40),
+    expirySlot: dv.getBigUint64(offset + 56, true),
+    nonce: dv.getBigUint64(offset + 64, true),
+  };
+}
+
+export function encodeRfqQuote(value: RfqQuote, data: Uint8Array, offset = 0): void {
+  const dv = view(data);
+  data.set(value.ctx.toBytes(), offset + 0);
+  dv.setBigUint64(offset + 32, value.priceE6, true);
+  writeU128(dv, offset + 40, value.size);
+  dv.setBigUint64(offset + 56, value.expirySlot, true);
+  dv.setBigUint64(offset + 64, value.nonce, true);
+}
+
+/** Stats account header. head and oracle_head are the next ring slots to write; count and oracle_count saturate at the ring capacity. */
+export interface StatsHeader {
+  magic: bigint;
+  version: number;
+  capacity: number;
+  ctx: PublicKey;
+  head: number;
+  count: number;
+  oracleHead: number;
+  oracleCount: number;
+}
+
+export const STATS_HEADER_SIZE = 64;
+
+export function decodeStatsHeader(data: Uint8Array, offset = 0): StatsHeader {
+  const dv = view(data);
+  return {
+    magic: dv.getBigUint64(offset + 0, true),
+    version: dv.getUint32(offset + 8, true),
+    capacity: dv.getUint32(offset + 12, true),
+    ctx: new PublicKey(data.slice(offset + 16, offset + 16 + 32)),
+    head: dv.getUint32(offset + 48, true),
+    count: dv.getUint32(offset + 52, true),
+    oracleHead: dv.getUint32(offset + 56, true),
+    oracleCount: dv.getUint32(offset + 60, true),
+  };
+}
+
+/** One fill in the stats ring. */
+export interface FillRecord {
+  fillSeq: bigint;
+  slot: bigint;
+  execSize: bigint;
+  execPriceE6: bigint;
+  spreadBps: bigint;
+}
+
+export const FILL_RECORD_SIZE = 48;
+
+export function decodeFillRecord(data: Uint8Array, offset = 0): FillRecord {
+  const dv = view(data);
+  return {
+    fillSeq: dv.getBigUint64(offset + 0, true),
+    slot: dv.getBigUint64(offset + 8, true),
+    execSize: readI128(dv, offset + 16),
+    execPriceE6: dv.getBigUint64(offset + 32, true),
+    spreadBps: dv.getBigUint64(offset + 40, true),
+  };
+}
+
+/** One oracle observation in the stats ring, as TWAP anchoring reads it. */
+export interface OracleSample {
+  slot: bigint;
+  oraclePriceE6: bigint;
+}
+
+export const ORACLE_SAMPLE_SIZE = 16;
+
+export function decodeOracleSample(data: Uint8Array, offset = 0): OracleSample {
+  const dv = view(data);
+  return {
+    slot: dv.getBigUint64(offset + 0, true),
+    oraclePriceE6: dv.getBigUint64(offset + 8, true),
+  };
+}
+
+/** A stats account bound to one context by InitStats: STATS_LEN bytes, STATS_LEDGER_LEN with a fee ledger of the withdrawals RecordWithdrawal recorded against the context's cum_fees, STATS_SKIM_LEN with the insurance skim: skim_bps of each fill's spread accrued for the insurance fund and what ClaimSkim paid in, STATS_PROTOCOL_LEN with the protocol fee: the treasury's protocol_fee_bps of each fill's fee, kept out of what RecordWithdrawal allows the LP, and what SweepProtocolFee recorded it taking, or STATS_PNL_LEN with the book marked to the oracle on each fill: the average entry price, the mark, and the realized and unrealized PnL in quote units. */
+export interface Stats {
+  header: StatsHeader;
+  fills: FillRecord[];
+  oracleSamples: OracleSample[];
+  withdrawnFees?: bigint;
+  withdrawals?: bigint;
+  lastWithdrawalSlot?: bigint;
+  skimBps?: number;
+  skimAccrued?: bigint;
+  skimClaimed?: bigint;
+  skimClaims?: bigint;
+  treasury?: PublicKey;
+  protocolFeeBps?: number;
+  protocolAccrued?: bigint;
+  protocolSwept?: bigint;
+  protocolSweeps?: bigint;
+  entryPriceE6?: bigint;
+  markPriceE6?: bigint;
+  realizedPnl?: bigint;
+  unrealizedPnl?: bigint;
+}
+
+export const STATS_SIZE = 3856;
+
+export function decodeStats(data: Uint8Array, offset = 0): Stats {
+  const dv = view(data);
+  return {
+    header: decodeStatsHeader(data, offset + 0),
+    fills: Array.from({ length: 64 }, (_, i) => decodeFillRecord(data, offset + 64 + i * 48)),
+    oracleSamples: Array.from({ length: 32 }, (_, i) => decodeOracleSample(data, offset + 3136 + i * 16)),
+    withdrawnFees: data.length >= offset + 3664 ? readU128(dv, offset + 3648) : undefined,
+    withdrawals: data.length >= offset + 3672 ? dv.getBigUint64(offset + 3664, true) : undefined,
+    lastWithdrawalSlot: data.length >= offset + 3680 ? dv.getBigUint64(offset + 3672, true) : undefined,
+    skimBps: data.length >= offset + 3682 ? dv.getUint16(offset + 3680, true) : undefined,
+    skimAccrued: data.length >= offset + 3704 ? readU128(dv, offset + 3688) : undefined,
+    skimClaimed: data.length >= offset + 3720 ? readU128(dv, offset + 3704) : undefined,
+    skimClaims: data.length >= offset + 3728 ? dv.getBigUint64(offset + 3720, true) : undefined,
+    treasury: data.length >= offset + 3760 ? new PublicKey(data.slice(offset + 3728, offset + 3728 + 32)) : undefined,
+    protocolFeeBps: data.length >= offset + 3762 ? dv.getUint16(offset + 3760, true) : undefined,
+    protocolAccrued: data.length >= offset + 3784 ? readU128(dv, offset + 3768) : undefined,
+    protocolSwept: data.length >= offset + 3800 ? readU128(dv, offset + 3784) : undefined,
+    protocolSweeps: data.length >= offset + 3808 ? dv.getBigUint64(offset + 3800, true) : undefined,
+    entryPriceE6: data.length >= offset + 3816 ? dv.getBigUint64(offset + 3808, true) : undefined,
+    markPriceE6: data.length >= offset + 3824 ? dv.getBigUint64(offset + 3816, true) : undefined,
+    realizedPnl: data.length >= offset + 3840 ? readI128(dv, offset + 3824) : undefined,
+    unrealizedPnl: data.length >= offset + 3856 ? readI128(dv, offset + 3840) : undefined,
+  };
+}
+
+/** A signal account, owned by any program: once SetSignals registers it, adds value_bps * weight_bps / 10000 to the spread of every trade, through stale_after_slot. */
+export interface Signal {
+  magic: bigint;
+  valueBps: number;
+  weightBps: number;
+  staleAfterSlot: bigint;
+}
+
+export const SIGNAL_SIZE = 32;
+
+export function decodeSignal(data: Uint8Array, offset = 0): Signal {
+  const dv = view(data);
+  return {
+    magic: dv.getBigUint64(offset + 0, true),
+    valueBps: dv.getUint32(offset + 8, true),
+    weightBps: dv.getUint16(offset + 12, true),
+    staleAfterSlot: dv.getBigUint64(offset + 16, true),
+  };
+}
+
+/** One window of a spread schedule: from start_slot through end_slot, the spread is multiplied by multiplier_bps / 10000. */
+export interface ScheduleEntry {
+  startSlot: bigint;
+  endSlot: bigint;
+  multiplierBps: number;
+}
+
+export const SCHEDULE_ENTRY_SIZE = 24;
+
+export function decodeScheduleEntry(data: Uint8Array, offset = 0): ScheduleEntry {
+  const dv = view(data);
+  return {
+    startSlot: dv.getBigUint64(offset + 0, true),
+    endSlot: dv.getBigUint64(offset + 8, true),
+    multiplierBps: dv.getUint32(offset + 16, true),
+  };
+}
+
+/** A ScheduleEntry as SetSchedule takes it, unpadded. */
+export interface ScheduleEntryArgs {
+  startSlot: bigint;
+  endSlot: bigint;
+  multiplierBps: number;
+}
+
+export const SCHEDULE_ENTRY_ARGS_SIZE = 20;
+
+export function decodeScheduleEntryArgs(data: Uint8Array, offset = 0): ScheduleEntryArgs {
+  const dv = view(data);
+  return {
+    startSlot: dv.getBigUint64(offset + 0, true),
+    endSlot: dv.getBigUint64(offset + 8, true),
+    multiplierBps: dv.getUint32(offset + 16, true),
+  };
+}
+
+/** One fee tier: a trade whose notional at the oracle is at least min_notional pays fee_bps in place of base_fee_bps. Unused tiers are zero. */
+export interface FeeTier {
+  minNotional: bigint;
+  feeBps: number;
+}
+
+export const FEE_TIER_SIZE = 24;
+
+export function decodeFeeTier(data: Uint8Array, offset = 0): FeeTier {
+  const dv = view(data);
+  return {
+    minNotional: readU128(dv, offset + 0),
+    feeBps: dv.getUint32(offset + 16, true),
+  };
+}
+
+/** One loyalty tier: a trader whose trader account has traded at least min_volume with the context gets discount_bps off the fee. Unused tiers are zero. */
+export interface LoyaltyTier {
+  minVolume: bigint;
+  discountBps: number;
+}
+
+export const LOYALTY_TIER_SIZE = 24;
+
+export function decodeLoyaltyTier(data: Uint8Array, offset = 0): LoyaltyTier {
+  const dv = view(data);
+  return {
+    minVolume: readU128(dv, offset + 0),
+    discountBps: dv.getUint32(offset + 16, true),
+  };
+}
+
+/** A LoyaltyTier as SetLoyaltyTiers takes it, unpadded. */
+export interface LoyaltyTierArgs {
+  minVolume: bigint;
+  discountBps: number;
+}
+
+export const LOYALTY_TIER_ARGS_SIZE = 20;
+
+export function decodeLoyaltyTierArgs(data: Uint8Array, offset = 0): LoyaltyTierArgs {
+  const dv = view(data);
+  return {
+    minVolume: readU128(dv, offset + 0),
+    discountBps: dv.getUint32(offset + 16, true),
+  };
+}
+
+/** A FeeTier as SetFeeTiers takes it, unpadded. */
+export interface FeeTierArgs {
+  minNotional: bigint;
+  feeBps: number;
+}
+
+export const FEE_TIER_ARGS_SIZE = 20;
+
+export function decodeFeeTierArgs(data: Uint8Array, offset = 0): FeeTierArgs {
+  const dv = view(data);
+  return {
+    minNotional: readU128(dv, offset + 0),
+    feeBps: dv.getUint32(offset + 16, true),
+  };
+}
+
+/** A signal account SetSignals registered, and the program that owned it then. */
+export interface SignalEntry {
+  signal: PublicKey;
+  owner: PublicKey;
+}
+
+export const SIGNAL_ENTRY_SIZE = 64;
+
+export function decodeSignalEntry(data: Uint8Array, offset = 0): SignalEntry {
+  return {
+    signal: new PublicKey(data.slice(offset + 0, offset + 0 + 32)),
+    owner: new PublicKey(data.slice(offset + 32, offset + 32 + 32)),
+  };
+}
+
+/** A spread schedule bound to one context by InitSchedule: SCHEDULE_LEN bytes, SCHEDULE_FEES_LEN with fee tiers, SCHEDULE_REBATE_LEN with a rebate and fee discount, SCHEDULE_MIN_NOTIONAL_LEN with a minimum notional, SCHEDULE_LOYALTY_LEN with loyalty tiers, SCHEDULE_DECAY_LEN with an inventory halflife, SCHEDULE_BAND_LEN with an inventory band, SCHEDULE_CAPS_LEN with inventory caps, SCHEDULE_REDUCE_ONLY_LEN with a reduce-only threshold, SCHEDULE_DEPTH_CAP_LEN with a depth-relative cap, SCHEDULE_SIGNALS_LEN with a signal registry. The first count entries are live; where several cover a slot, the widest wins. Of the fee tiers a trade reaches, the largest sets its fee. Fills that shrink |inventory| get rebate_bps off their spread and fee; fee_discount_bps of the fee is waived at the full credibility discount. Trades whose notional at the oracle is below min_notional_e6 are refused. Of the loyalty tiers a trader account's volume reaches, the largest takes its discount_bps off the fee. The imbalance and skew penalties price the inventory as halving every inventory_halflife slots since the last fill. Past soft_inventory, fills that take |inventory| deeper pay up to band_k_bps more, growing with the square of the depth into the band. max_long_inventory and max_short_inventory cap the LP's long book (negative inventory) and short book (positive) in place of max_inventory, which a zero cap leaves. Past reduce_only_bps of the cap (AUTO_REDUCE_ONLY_BPS at 0), trades that grow |inventory| are soft-rejected. A non-zero max_inventory_bps sets max_inventory to that share of liquidity_notional_e6, in base units at the oracle. Every trade and quote passes the first signal_count signals, in order. */
+export interface Schedule {
+  magic: bigint;
+  version: number;
+  count: number;
+  ctx: PublicKey;
+  entries: ScheduleEntry[];
+  feeTiers?: FeeTier[];
+  rebateBps?: number;
+  feeDiscountBps?: number;
+  minNotionalE6?: bigint;
+  loyaltyTiers?: LoyaltyTier[];
+  inventoryHalflife?: number;
+  softInventory?: bigint;
+  bandKBps?: number;
+  maxLongInventory?: bigint;
+  maxShortInventory?: bigint;
+  reduceOnlyBps?: number;
+  maxInventoryBps?: number;
+  signalCount?: number;
+  signals?: SignalEntry[];
+}
+
+export const SCHEDULE_SIZE = 800;
+
+export function decodeSchedule(data: Uint8Array, offset = 0): Schedule {
+  const dv = view(data);
+  return {
+    magic: dv.getBigUint64(offset + 0, true),
+    version: dv.getUint32(offset + 8, true),
+    count: dv.getUint32(offset + 12, true),
+    ctx: new PublicKey(data.slice(offset + 16, offset + 16 + 32)),
+    entries: Array.from({ length: 8 }, (_, i) => decodeScheduleEntry(data, offset + 48 + i * 24)),
+    feeTiers: data.length >= offset + 336 ? Array.from({ length: 4 }, (_, i) => decodeFeeTier(data, offset + 240 + i * 24)) : undefined,
+    rebateBps: data.length >= offset + 338 ? dv.getUint16(offset + 336, true) : undefined,
+    feeDiscountBps: data.length >= offset + 340 ? dv.getUint16(offset + 338, true) : undefined,
+    minNotionalE6: data.length >= offset + 360 ? readU128(dv, offset + 344) : undefined,
+    loyaltyTiers: data.length >= offset + 456 ? Array.from({ length: 4 }, (_, i) => decodeLoyaltyTier(data, offset + 360 + i * 24)) : undefined,
+    inventoryHalflife: data.length >= offset + 460 ? dv.getUint32(offset + 456, true) : undefined,
+    softInventory: data.length >= offset + 480 ? readU128(dv, offset + 464) : undefined,
+    bandKBps: data.length >= offset + 484 ? dv.getUint32(offset + 480, true) : undefined,
+    maxLongInventory: data.length >= offset + 504 ? readU128(dv, offset + 488) : undefined,
+    maxShortInventory: data.length >= offset + 520 ? readU128(dv, offset + 504) : undefined,
+    reduceOnlyBps: data.length >= offset + 522 ? dv.getUint16(offset + 520, true) : undefined,
+    maxInventoryBps: data.length >= offset + 532 ? dv.getUint32(offset + 528, true) : undefined,
+    signalCount: data.length >= offset + 537 ? dv.getUint8(offset + 536) : undefined,
+    signals: data.length >= offset + 800 ? Array.from({ length: 4 }, (_, i) => decodeSignalEntry(data, offset + 544 + i * 64)) : undefined,
+  };
+}
+
+/** One ladder level: a single trade of size (after clipping) fills at exec_price_e6. */
+export interface LadderLevel {
+  size: bigint;
+  execPriceE6: bigint;
+  spreadBps: bigint;
+}
+
+export const LADDER_LEVEL_SIZE = 32;
+
+export function decodeLadderLevel(data: Uint8Array, offset = 0): LadderLevel {
+  const dv = view(data);
+  return {
+    size: readU128(dv, offset + 0),
+    execPriceE6: dv.getBigUint64(offset + 16, true),
+    spreadBps: dv.getBigUint64(offset + 24, true),
+  };
+}
+
+/** A ladder bound to one context by InitLadder, as PublishLadder last priced it. The first bid_count bids and ask_count asks are live; a side cut short by the inventory carries the MatcherError code as its reason. */
+export interface Ladder {
+  magic: bigint;
+  version: number;
+  market: number;
+  ctx: PublicKey;
+  slot: bigint;
+  oraclePriceE6: bigint;
+  stepSize: bigint;
+  bidCount: number;
+  askCount: number;
+  bidReason: number;
+  askReason: number;
+  bids: LadderLevel[];
+  asks: LadderLevel[];
+}
+
+export const LADDER_SIZE = 608;
+
+export function decodeLadder(data: Uint8Array, offset = 0): Ladder {
+  const dv = view(data);
+  return {
+    magic: dv.getBigUint64(offset + 0, true),
+    version: dv.getUint32(offset + 8, true),
+    market: dv.getUint16(offset + 12, true),
+    ctx: new PublicKey(data.slice(offset + 16, offset + 16 + 32)),
+    slot: dv.getBigUint64(offset + 48, true),
+    oraclePriceE6: dv.getBigUint64(offset + 56, true),
+    stepSize: readU128(dv, offset + 64),
+    bidCount: dv.getUint32(offset + 80, true),
+    askCount: dv.getUint32(offset + 84, true),
+    bidReason: dv.getUint32(offset + 88, true),
+    askReason: dv.getUint32(offset + 92, true),
+    bids: Array.from({ length: 8 }, (_, i) => decodeLadderLevel(data, offset + 96 + i * 32)),
+    asks: Array.from({ length: 8 }, (_, i) => decodeLadderLevel(data, offset + 352 + i * 32)),
+  };
+}
+
+/** A tip jar bound to one context by InitTipJar. UpdateCredibility, passed it and a signing keeper, pays the keeper lamports_per_slot per slot the snapshot was stale past SNAPSHOT_GRACE_SLOTS, up to max_tip, out of the jar's lamports above rent exemption. */
+export interface TipJar {
+  magic: bigint;
+  version: number;
+  ctx: PublicKey;
+  lamportsPerSlot: bigint;
+  maxTip: bigint;
+  paid: bigint;
+  tips: bigint;
+}
+
+export const TIP_JAR_SIZE = 80;
+
+export function decodeTipJar(data: Uint8Array, offset = 0): TipJar {
+  const dv = view(data);
+  return {
+    magic: dv.getBigUint64(offset + 0, true),
+    version: dv.getUint32(offset + 8, true),
+    ctx: new PublicKey(data.slice(offset + 16, offset + 16 + 32)),
+    lamportsPerSlot: dv.getBigUint64(offset + 48, true),
+    maxTip: dv.getBigUint64(offset + 56, true),
+    paid: dv.getBigUint64(offset + 64, true),
+    tips: dv.getBigUint64(offset + 72, true),
+  };
+}
+
+/** What a referral account has credited one referrer: its share of the fees on the fills it referred (in the notional units of cum_fees) and how many there were. */
+export interface Referrer {
+  referrer: PublicKey;
+  accruedFees: bigint;
+  fills: bigint;
+}
+
+export const REFERRER_SIZE = 56;
+
+export function decodeReferrer(data: Uint8Array, offset = 0): Referrer {
+  const dv = view(data);
+  return {
+    referrer: new PublicKey(data.slice(offset + 0, offset + 0 + 32)),
+    accruedFees: readU128(dv, offset + 32),
+    fills: dv.getBigUint64(offset + 48, true),
+  };
+}
+
+/** A referral account bound to one context by InitReferral. A Match passed it and naming a referrer credits that referrer with share_bps of the fill's fee, in the first count entries; once all MAX_REFERRERS are taken, new referrers earn nothing. */
+export interface Referrals {
+  magic: bigint;
+  version: number;
+  ctx: PublicKey;
+  shareBps: number;
+  count: number;
+  referrers: Referrer[];
+}
+
+export const REFERRALS_SIZE = 952;
+
+export function decodeReferrals(data: Uint8Array, offset = 0): Referrals {
+  const dv = view(data);
+  return {
+    magic: dv.getBigUint64(offset + 0, true),
+    version: dv.getUint32(offset + 8, true),
+    ctx: new PublicKey(data.slice(offset + 16, offset + 16 + 32)),
+    shareBps: dv.getUint16(offset + 48, true),
+    count: dv.getUint16(offset + 50, true),
+    referrers: Array.from({ length: 16 }, (_, i) => decodeReferrer(data, offset + 56 + i * 56)),
+  };
+}
+
+/** A trader account bound to one context and one trader by InitTrader. Every Match passed it, with the trader signing, adds the fill's notional (at the exec price, in the units of cum_notional) to volume, which the schedule's loyalty tiers discount the fee by. */
+export interface Trader {
+  magic: bigint;
+  version: number;
+  ctx: PublicKey;
+  trader: PublicKey;
+  volume: bigint;
+  fills: bigint;
+  lastSlot: bigint;
+}
+
+export const TRADER_SIZE = 112;
+
+export function decodeTrader(data: Uint8Array, offset = 0): Trader {
+  const dv = view(data);
+  return {
+    magic: dv.getBigUint64(offset + 0, true),
+    version: dv.getUint32(offset + 8, true),
+    ctx: new PublicKey(data.slice(offset + 16, offset + 16 + 32)),
+    trader: new PublicKey(data.slice(offset + 48, offset + 48 + 32)),
+    volume: readU128(dv, offset + 80),
+    fills: dv.getBigUint64(offset + 96, true),
+    lastSlot: dv.getBigUint64(offset + 104, true),
+  };
+}
+
+/** One oracle feed: feed_id (a Pyth feed id, or a pinned Pyth price account's, Switchboard aggregator's or Chainlink feed's key) and the program publishing it (FEED_SOURCE_*). */
+export interface FeedEntry {
+  feedId: Uint8Array;
+  source: number;
+  pinned: boolean;
+  penaltyBps: number;
+}
+
+export const FEED_ENTRY_SIZE = 40;
+
+export function decodeFeedEntry(data: Uint8Array, offset = 0): FeedEntry {
+  const dv = view(data);
+  return {
+    feedId: data.slice(offset + 0, offset + 0 + 32),
+    source: dv.getUint8(offset + 32),
+    pinned: dv.getUint8(offset + 33) !== 0,
+    penaltyBps: dv.getUint16(offset + 34, true),
+  };
+}
+
+/** Oracle feeds bound to one context by InitFeed and AddFeed: Match prices off the median of the first count entries' prices, each published at most max_age_secs ago, and widens by the widest Pyth confidence interval. Feeds more than max_divergence_bps apart (0 = any) are refused, or with widen set quoted with the divergence added to the spread. A price posted more than max_age_slots slots ago (0 = any) is refused, or with slot_widen set quoted at max_spread_bps. With fallback set Match prices instead off the first entry with a fresh positive price, plus its penalty_bps. */
+export interface Feed {
+  magic: bigint;
+  version: number;
+  maxAgeSecs: number;
+  ctx: PublicKey;
+  count: number;
+  widen: boolean;
+  maxDivergenceBps: number;
+  maxAgeSlots: number;
+  slotWiden: boolean;
+  fallback: boolean;
+  entries: FeedEntry[];
+}
+
+export const FEED_SIZE = 176;
+
+export function decodeFeed(data: Uint8Array, offset = 0): Feed {
+  const dv = view(data);
+  return {
+    magic: dv.getBigUint64(offset + 0, true),
+    version: dv.getUint32(offset + 8, true),
+    maxAgeSecs: dv.getUint32(offset + 12, true),
+    ctx: new PublicKey(data.slice(offset + 16, offset + 16 + 32)),
+    count: dv.getUint8(offset + 48),
+    widen: dv.getUint8(offset + 49) !== 0,
+    maxDivergenceBps: dv.getUint16(offset + 50, true),
+    maxAgeSlots: dv.getUint16(offset + 52, true),
+    slotWiden: dv.getUint8(offset + 54) !== 0,
+    fallback: dv.getUint8(offset + 55) !== 0,
+    entries: Array.from({ length: 3 }, (_, i) => decodeFeedEntry(data, offset + 56 + i * 40)),
+  };
+}
+
+// Instructions
+
+export interface MatchAccounts {
+  lpPda: PublicKey;
+  ctx: PublicKey;
+  slab?: PublicKey;
+  referral?: PublicKey;
+  traderAccount?: PublicKey;
+  trader?: PublicKey;
+  schedule?: PublicKey;
+  feed?: PublicKey;
+  price?: PublicKey;
+  stats?: PublicKey;
+}
+
+export interface MatchArgs {
+  reqId: bigint;
+  lpIdx: number;
+  lpAccountId: bigint;
+  oraclePriceE6: bigint;
+  tradeSize: bigint;
+  referrer?: PublicKey;
+}
+
+/** Match (0x00): Price and fill one trade; called by percolator with the LP PDA as signer. A scheduled context needs its schedule after the slab, and one with a feed its feed account and a price account per feed after that, priced off (their median) instead of oracle_price_e6 (which is still echoed). Once InitStats binds a stats account, every Match must pass it (StatsRequired otherwise), so the protocol fee, skim and PnL can't be skipped. The signal accounts the schedule registers follow the slab, schedule, feed and stats, all of them and in order. A referrer named after the call data is credited its share of the fee in the referral account, passed right after the slab. A trader account and its trader, who signs, may follow the slab and referral account for the schedule's loyalty discount; the fill's notional is added to its volume. Return data: decodeMatchReturn. */
+export function matchInstruction(programId: PublicKey, accounts: MatchAccounts, args: MatchArgs): TransactionInstruction {
+  const data = new Uint8Array(args.referrer === undefined ? 67 : 99);
+  data.set([0x00]);
+  const dv = view(data);
+  dv.setBigUint64(1, args.reqId, true);
+  dv.setUint16(9, args.lpIdx, true);
+  dv.setBigUint64(11, args.lpAccountId, true);
+  dv.setBigUint64(19, args.oraclePriceE6, true);
+  writeU128(dv, 27, args.tradeSize);
+  if (args.referrer !== undefined) data.set(args.referrer.toBytes(), 67);
+  const keys = accountMetas([
+    ["lp_pda", accounts.lpPda, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+    ["slab", accounts.slab, false, false, true],
+    ["referral", accounts.referral, false, true, true],
+    ["trader_account", accounts.traderAccount, false, true, true],
+    ["trader", accounts.trader, true, false, true],
+    ["schedule", accounts.schedule, false, false, true],
+    ["feed", accounts.feed, false, false, true],
+    ["price", accounts.price, false, false, true],
+    ["stats", accounts.stats, false, true, true],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface InitAccounts {
+  lpPda: PublicKey;
+  ctx: PublicKey;
+  slab?: PublicKey;
+  authority?: PublicKey;
+}
+
+export interface InitArgs {
+  params: MatcherParams;
+}
+
+/** Init (0x02): Initialize a context, signed by the authority when one is named and by the LP PDA otherwise (always the LP PDA on a CreateContext account). Binding a slab or a separate authority needs a CTX_EXT_LEN account. */
+export function initInstruction(programId: PublicKey, accounts: InitAccounts, args: InitArgs): TransactionInstruction {
+  const data = new Uint8Array(138);
+  data.set([0x02, 0x02]);
+  encodeMatcherParams(args.params, data, 2);
+  const keys = accountMetas([
+    ["lp_pda", accounts.lpPda, false, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+    ["slab", accounts.slab, false, false, true],
+    ["authority", accounts.authority, true, false, true],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface InitPlainAccounts {
+  lpPda: PublicKey;
+  ctx: PublicKey;
+  slab?: PublicKey;
+  authority?: PublicKey;
+}
+
+export interface InitPlainArgs {
+  params: MatcherParams;
+}
+
+/** InitPlain (0x02): Init for the plain kind: the same context, priced on inventory imbalance alone. */
+export function initPlainInstruction(programId: PublicKey, accounts: InitPlainAccounts, args: InitPlainArgs): TransactionInstruction {
+  const data = new Uint8Array(138);
+  data.set([0x02, 0x01]);
+  encodeMatcherParams(args.params, data, 2);
+  const keys = accountMetas([
+    ["lp_pda", accounts.lpPda, false, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+    ["slab", accounts.slab, false, false, true],
+    ["authority", accounts.authority, true, false, true],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface InitMultiAccounts {
+  lpPda: PublicKey;
+  ctx: PublicKey;
+  slab?: PublicKey;
+  authority?: PublicKey;
+}
+
+export interface InitMultiArgs {
+  params: MatcherParams;
+}
+
+/** InitMulti (0x02): Init for the multi kind: priced by signal_weights' blend of coverage, age, deficit and liquidations. Needs a CTX_EXT_LEN account. */
+export function initMultiInstruction(programId: PublicKey, accounts: InitMultiAccounts, args: InitMultiArgs): TransactionInstruction {
+  const data = new Uint8Array(138);
+  data.set([0x02, 0x03]);
+  encodeMatcherParams(args.params, data, 2);
+  const keys = accountMetas([
+    ["lp_pda", accounts.lpPda, false, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+    ["slab", accounts.slab, false, false, true],
+    ["authority", accounts.authority, true, false, true],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface InitStableAccounts {
+  lpPda: PublicKey;
+  ctx: PublicKey;
+  slab?: PublicKey;
+  authority?: PublicKey;
+}
+
+export interface InitStableArgs {
+  params: MatcherParams;
+}
+
+/** InitStable (0x02): Init for the stable kind: priced on a band of peg_band_e6 around 1.0, widened by the depeg and thin coverage, with no inventory terms. */
+export function initStableInstruction(programId: PublicKey, accounts: InitStableAccounts, args: InitStableArgs): TransactionInstruction {
+  const data = new Uint8Array(138);
+  data.set([0x02, 0x04]);
+  encodeMatcherParams(args.params, data, 2);
+  const keys = accountMetas([
+    ["lp_pda", accounts.lpPda, false, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+    ["slab", accounts.slab, false, false, true],
+    ["authority", accounts.authority, true, false, true],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface UpdateCredibilityAccounts {
+  ctx: PublicKey;
+  slab: PublicKey;
+  tipJar?: PublicKey;
+  keeper?: PublicKey;
+}
+
+/** UpdateCredibility (0x03): Refresh the insurance and open-interest snapshot from the slab, which must be the context's bound slab (or a listed market) under the pinned percolator program; an unbound context takes any slab PERCOLATOR_PROGRAM_ID owns. Permissionless. With the context's tip jar and a signing keeper after the clock, the keeper is paid for the staleness the refresh cleared. */
+export function updateCredibilityInstruction(programId: PublicKey, accounts: UpdateCredibilityAccounts): TransactionInstruction {
+  const data = new Uint8Array(1);
+  data.set([0x03]);
+  const keys = accountMetas([
+    ["ctx", accounts.ctx, false, true, false],
+    ["slab", accounts.slab, false, false, false],
+    ["clock", new PublicKey("SysvarC1ock11111111111111111111111111111111"), false, false, false],
+    ["tip_jar", accounts.tipJar, false, true, true],
+    ["keeper", accounts.keeper, true, true, true],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface UpdateParamsAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+}
+
+export interface UpdateParamsArgs {
+  params: MatcherParams;
+}
+
+/** UpdateParams (0x04): Replace the parameters. Before the first fill only; after it, use ProposeParams. */
+export function updateParamsInstruction(programId: PublicKey, accounts: UpdateParamsAccounts, args: UpdateParamsArgs): TransactionInstruction {
+  const data = new Uint8Array(137);
+  data.set([0x04]);
+  encodeMatcherParams(args.params, data, 1);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface CloseAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  destination: PublicKey;
+  schedule?: PublicKey;
+  feed?: PublicKey;
+  stats?: PublicKey;
+  ladder?: PublicKey;
+}
+
+/** Close (0x05): Close the context, and its schedule, feed, stats and a ladder account if bound, sending the rent to destination. A scheduled context needs its schedule, one with a feed its feed account. */
+export function closeInstruction(programId: PublicKey, accounts: CloseAccounts): TransactionInstruction {
+  const data = new Uint8Array(1);
+  data.set([0x05]);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+    ["destination", accounts.destination, false, true, false],
+    ["schedule", accounts.schedule, false, true, true],
+    ["feed", accounts.feed, false, true, true],
+    ["stats", accounts.stats, false, true, true],
+    ["ladder", accounts.ladder, false, true, true],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetPausedAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+}
+
+export interface SetPausedArgs {
+  paused: boolean;
+}
+
+/** SetPaused (0x06): Pause or resume matching. */
+export function setPausedInstruction(programId: PublicKey, accounts: SetPausedAccounts, args: SetPausedArgs): TransactionInstruction {
+  const data = new Uint8Array(2);
+  data.set([0x06]);
+  const dv = view(data);
+  dv.setUint8(1, args.paused ? 1 : 0);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface MigrateAccounts {
+  ctx: PublicKey;
+  payer: PublicKey;
+}
+
+/** Migrate (0x07): Grow a v5 context to v6. Permissionless; payer funds the extra rent. */
+export function migrateInstruction(programId: PublicKey, accounts: MigrateAccounts): TransactionInstruction {
+  const data = new Uint8Array(1);
+  data.set([0x07]);
+  const keys = accountMetas([
+    ["ctx", accounts.ctx, false, true, false],
+    ["payer", accounts.payer, true, true, false],
+    ["system_program", new PublicKey("11111111111111111111111111111111"), false, false, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface QuotePreviewAccounts {
+  ctx: PublicKey;
+  schedule?: PublicKey;
+  stats?: PublicKey;
+}
+
+export interface QuotePreviewArgs {
+  oraclePriceE6: bigint;
+  tradeSize: bigint;
+  market?: number;
+}
+
+/** QuotePreview (0x08): Price a trade without filling it, on a shared context for the market at that table index. A scheduled context needs its schedule, and the signal accounts it registers after the stats, in order. Read-only: simulate it and decode the return data. Return data: decodeQuoteBreakdown. */
+export function quotePreviewInstruction(programId: PublicKey, accounts: QuotePreviewAccounts, args: QuotePreviewArgs): TransactionInstruction {
+  const data = new Uint8Array(args.market === undefined ? 25 : 27);
+  data.set([0x08]);
+  const dv = view(data);
+  dv.setBigUint64(1, args.oraclePriceE6, true);
+  writeU128(dv, 9, args.tradeSize);
+  if (args.market !== undefined) dv.setUint16(25, args.market, true);
+  const keys = accountMetas([
+    ["ctx", accounts.ctx, false, false, false],
+    ["schedule", accounts.schedule, false, false, true],
+    ["stats", accounts.stats, false, false, true],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface ProposeParamsAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+}
+
+export interface ProposeParamsArgs {
+  params?: MatcherParams;
+}
+
+/** ProposeParams (0x09): Queue parameters behind the timelock; without params, cancel the pending proposal. */
+export function proposeParamsInstruction(programId: PublicKey, accounts: ProposeParamsAccounts, args: ProposeParamsArgs): TransactionInstruction {
+  const data = new Uint8Array(args.params === undefined ? 1 : 137);
+  data.set([0x09]);
+  if (args.params !== undefined) encodeMatcherParams(args.params, data, 1);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface CommitParamsAccounts {
+  ctx: PublicKey;
+}
+
+/** CommitParams (0x0a): Apply a proposal whose timelock has passed. Permissionless. */
+export function commitParamsInstruction(programId: PublicKey, accounts: CommitParamsAccounts): TransactionInstruction {
+  const data = new Uint8Array(1);
+  data.set([0x0a]);
+  const keys = accountMetas([
+    ["ctx", accounts.ctx, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetReduceOnlyAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+}
+
+export interface SetReduceOnlyArgs {
+  on: boolean;
+}
+
+/** SetReduceOnly (0x0b): Only accept trades that shrink |inventory|. */
+export function setReduceOnlyInstruction(programId: PublicKey, accounts: SetReduceOnlyAccounts, args: SetReduceOnlyArgs): TransactionInstruction {
+  const data = new Uint8Array(2);
+  data.set([0x0b]);
+  const dv = view(data);
+  dv.setUint8(1, args.on ? 1 : 0);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SettleInventoryAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+}
+
+export interface SettleInventoryArgs {
+  mode: number;
+  value: bigint;
+  market?: number;
+}
+
+/** SettleInventory (0x0c): Set (SETTLE_SET) or adjust (SETTLE_ADJUST) the recorded inventory; on a shared context, of the market at that table index. */
+export function settleInventoryInstruction(programId: PublicKey, accounts: SettleInventoryAccounts, args: SettleInventoryArgs): TransactionInstruction {
+  const data = new Uint8Array(args.market === undefined ? 18 : 20);
+  data.set([0x0c]);
+  const dv = view(data);
+  dv.setUint8(1, args.mode);
+  writeU128(dv, 2, args.value);
+  if (args.market !== undefined) dv.setUint16(18, args.market, true);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface ProposeAuthorityAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+}
+
+export interface ProposeAuthorityArgs {
+  newAuthority: PublicKey;
+}
+
+/** ProposeAuthority (0x0d): Start an authority rotation; the default key cancels a pending one. */
+export function proposeAuthorityInstruction(programId: PublicKey, accounts: ProposeAuthorityAccounts, args: ProposeAuthorityArgs): TransactionInstruction {
+  const data = new Uint8Array(33);
+  data.set([0x0d]);
+  data.set(args.newAuthority.toBytes(), 1);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface AcceptAuthorityAccounts {
+  newAuthority: PublicKey;
+  ctx: PublicKey;
+}
+
+/** AcceptAuthority (0x0e): Complete a rotation, signed by the proposed authority. */
+export function acceptAuthorityInstruction(programId: PublicKey, accounts: AcceptAuthorityAccounts): TransactionInstruction {
+  const data = new Uint8Array(1);
+  data.set([0x0e]);
+  const keys = accountMetas([
+    ["new_authority", accounts.newAuthority, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetReturnDataOnlyAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+}
+
+export interface SetReturnDataOnlyArgs {
+  on: boolean;
+}
+
+/** SetReturnDataOnly (0x0f): Report Match results only through return data, leaving the context's MatcherReturn untouched. */
+export function setReturnDataOnlyInstruction(programId: PublicKey, accounts: SetReturnDataOnlyAccounts, args: SetReturnDataOnlyArgs): TransactionInstruction {
+  const data = new Uint8Array(2);
+  data.set([0x0f]);
+  const dv = view(data);
+  dv.setUint8(1, args.on ? 1 : 0);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetSoftRejectAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+}
+
+export interface SetSoftRejectArgs {
+  on: boolean;
+}
+
+/** SetSoftReject (0x10): Answer refused matches with a zero fill and a reject reason instead of failing. */
+export function setSoftRejectInstruction(programId: PublicKey, accounts: SetSoftRejectAccounts, args: SetSoftRejectArgs): TransactionInstruction {
+  const data = new Uint8Array(2);
+  data.set([0x10]);
+  const dv = view(data);
+  dv.setUint8(1, args.on ? 1 : 0);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface QuoteTwoSidedAccounts {
+  ctx: PublicKey;
+  schedule?: PublicKey;
+  stats?: PublicKey;
+}
+
+export interface QuoteTwoSidedArgs {
+  oraclePriceE6: bigint;
+  size: bigint;
+  market?: number;
+}
+
+/** QuoteTwoSided (0x11): Price a bid and an ask for the same size, on a shared context for the market at that table index. A scheduled context needs its schedule, and the signal accounts it registers after the stats, in order. Read-only: simulate it and decode the return data. Return data: decodeTwoSidedQuote. */
+export function quoteTwoSidedInstruction(programId: PublicKey, accounts: QuoteTwoSidedAccounts, args: QuoteTwoSidedArgs): TransactionInstruction {
+  const data = new Uint8Array(args.market === undefined ? 25 : 27);
+  data.set([0x11]);
+  const dv = view(data);
+  dv.setBigUint64(1, args.oraclePriceE6, true);
+  writeU128(dv, 9, args.size);
+  if (args.market !== undefined) dv.setUint16(25, args.market, true);
+  const keys = accountMetas([
+    ["ctx", accounts.ctx, false, false, false],
+    ["schedule", accounts.schedule, false, false, true],
+    ["stats", accounts.stats, false, false, true],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface InitStatsAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  stats: PublicKey;
+  treasury?: PublicKey;
+}
+
+export interface InitStatsArgs {
+  protocolFeeBps?: number;
+}
+
+/** InitStats (0x12): Bind a stats account (STATS_LEN bytes, program-owned, rent-exempt) to the context. With protocol_fee_bps (at most 10000) and a treasury, on a STATS_PROTOCOL_LEN account, every Match that passes it also accrues that share of the fill's fee to the treasury, fixed for the account's life. */
+export function initStatsInstruction(programId: PublicKey, accounts: InitStatsAccounts, args: InitStatsArgs): TransactionInstruction {
+  const data = new Uint8Array(args.protocolFeeBps === undefined ? 1 : 3);
+  data.set([0x12]);
+  const dv = view(data);
+  if (args.protocolFeeBps !== undefined) dv.setUint16(1, args.protocolFeeBps, true);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+    ["stats", accounts.stats, false, true, false],
+    ["treasury", accounts.treasury, false, false, true],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetRfqSignerAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+}
+
+export interface SetRfqSignerArgs {
+  signer: PublicKey;
+}
+
+/** SetRfqSigner (0x13): Set the key whose quotes PostRfq accepts (the default key turns RFQ off) and withdraw any posted quote. v6 only. */
+export function setRfqSignerInstruction(programId: PublicKey, accounts: SetRfqSignerAccounts, args: SetRfqSignerArgs): TransactionInstruction {
+  const data = new Uint8Array(33);
+  data.set([0x13]);
+  data.set(args.signer.toBytes(), 1);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface PostRfqAccounts {
+  ctx: PublicKey;
+}
+
+export interface PostRfqArgs {
+  quote: RfqQuote;
+}
+
+/** PostRfq (0x14): Post a signed quote for Match to fill on its side until its size or expiry runs out. Permissionless; must directly follow an ed25519 program instruction verifying the quote by the RFQ signer, with the key, signature and message in its own data. */
+export function postRfqInstruction(programId: PublicKey, accounts: PostRfqAccounts, args: PostRfqArgs): TransactionInstruction {
+  const data = new Uint8Array(73);
+  data.set([0x14]);
+  encodeRfqQuote(args.quote, data, 1);
+  const keys = accountMetas([
+    ["ctx", accounts.ctx, false, true, false],
+    ["instructions", new PublicKey("Sysvar1nstructions1111111111111111111111111"), false, false, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetMarketAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  slab: PublicKey;
+}
+
+export interface SetMarketArgs {
+  maxFill: bigint;
+  maxInventory: bigint;
+}
+
+/** SetMarket (0x15): List a slab in a shared context's market table, or change its caps (zero keeps the context's). The slab must be owned by the pinned percolator program. */
+export function setMarketInstruction(programId: PublicKey, accounts: SetMarketAccounts, args: SetMarketArgs): TransactionInstruction {
+  const data = new Uint8Array(33);
+  data.set([0x15]);
+  const dv = view(data);
+  writeU128(dv, 1, args.maxFill);
+  writeU128(dv, 17, args.maxInventory);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+    ["slab", accounts.slab, false, false, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetNoBidsAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+}
+
+export interface SetNoBidsArgs {
+  on: boolean;
+}
+
+/** SetNoBids (0x16): Stop buying from takers: a Match that sells to the LP comes back as a zero fill with reason SideDisabled, soft-reject mode or not. */
+export function setNoBidsInstruction(programId: PublicKey, accounts: SetNoBidsAccounts, args: SetNoBidsArgs): TransactionInstruction {
+  const data = new Uint8Array(2);
+  data.set([0x16]);
+  const dv = view(data);
+  dv.setUint8(1, args.on ? 1 : 0);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetNoAsksAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+}
+
+export interface SetNoAsksArgs {
+  on: boolean;
+}
+
+/** SetNoAsks (0x17): Stop selling to takers: a Match that buys from the LP comes back as a zero fill with reason SideDisabled, soft-reject mode or not. */
+export function setNoAsksInstruction(programId: PublicKey, accounts: SetNoAsksAccounts, args: SetNoAsksArgs): TransactionInstruction {
+  const data = new Uint8Array(2);
+  data.set([0x17]);
+  const dv = view(data);
+  dv.setUint8(1, args.on ? 1 : 0);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface InitScheduleAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  schedule: PublicKey;
+}
+
+/** InitSchedule (0x18): Bind a schedule account (SCHEDULE_LEN bytes, program-owned, rent-exempt) to the context. A context takes one; it goes with Close. */
+export function initScheduleInstruction(programId: PublicKey, accounts: InitScheduleAccounts): TransactionInstruction {
+  const data = new Uint8Array(1);
+  data.set([0x18]);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+    ["schedule", accounts.schedule, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetScheduleAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  schedule: PublicKey;
+}
+
+export interface SetScheduleArgs {
+  count: number;
+  entries: ScheduleEntryArgs[];
+}
+
+/** SetSchedule (0x19): Replace the schedule's entries with the first count of entries. Multipliers are at least 10000, so it can only widen the spread. */
+export function setScheduleInstruction(programId: PublicKey, accounts: SetScheduleAccounts, args: SetScheduleArgs): TransactionInstruction {
+  const data = new Uint8Array(162);
+  data.set([0x19]);
+  const dv = view(data);
+  dv.setUint8(1, args.count);
+  args.entries.forEach((item, i) => { encodeScheduleEntryArgs(item, data, 2 + i * 20); });
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["schedule", accounts.schedule, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface InitLadderAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  ladder: PublicKey;
+}
+
+/** InitLadder (0x1a): Bind a ladder account (LADDER_LEN bytes, program-owned, rent-exempt) to the context for PublishLadder to write. */
+export function initLadderInstruction(programId: PublicKey, accounts: InitLadderAccounts): TransactionInstruction {
+  const data = new Uint8Array(1);
+  data.set([0x1a]);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["ladder", accounts.ladder, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface PublishLadderAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  ladder: PublicKey;
+  schedule?: PublicKey;
+  stats?: PublicKey;
+}
+
+export interface PublishLadderArgs {
+  oraclePriceE6: bigint;
+  stepSize: bigint;
+  market?: number;
+}
+
+/** PublishLadder (0x1b): Price MAX_LADDER_LEVELS trades of step_size, 2 * step_size, ... each way, as Match would fill them now, into the ladder, on a shared context for the market at that table index. A side stops at its first clipped level. A scheduled context needs its schedule after the ladder, and the signal accounts it registers after the stats, in order. */
+export function publishLadderInstruction(programId: PublicKey, accounts: PublishLadderAccounts, args: PublishLadderArgs): TransactionInstruction {
+  const data = new Uint8Array(args.market === undefined ? 25 : 27);
+  data.set([0x1b]);
+  const dv = view(data);
+  dv.setBigUint64(1, args.oraclePriceE6, true);
+  writeU128(dv, 9, args.stepSize);
+  if (args.market !== undefined) dv.setUint16(25, args.market, true);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["ladder", accounts.ladder, false, true, false],
+    ["schedule", accounts.schedule, false, false, true],
+    ["stats", accounts.stats, false, false, true],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface InitFeedAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  feed: PublicKey;
+  price: PublicKey;
+}
+
+export interface InitFeedArgs {
+  maxAgeSecs: number;
+  source: number;
+  pinned?: boolean;
+}
+
+/** InitFeed (0x1c): Bind a feed account (FEED_LEN bytes, program-owned, rent-exempt) to the context with the feed of price: a fully verified PriceUpdateV2 owned by the Pyth receiver or push oracle, with source FEED_SOURCE_SWITCHBOARD a Switchboard on-demand pull feed, or with FEED_SOURCE_CHAINLINK a Chainlink OCR2 feed. Pinned, the feed is bound to the price account's key rather than its Pyth feed id. Match then prices off that feed. A context takes one; it goes with Close. */
+export function initFeedInstruction(programId: PublicKey, accounts: InitFeedAccounts, args: InitFeedArgs): TransactionInstruction {
+  const data = new Uint8Array(args.pinned === undefined ? 6 : 7);
+  data.set([0x1c]);
+  const dv = view(data);
+  dv.setUint32(1, args.maxAgeSecs, true);
+  dv.setUint8(5, args.source);
+  if (args.pinned !== undefined) dv.setUint8(6, args.pinned ? 1 : 0);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+    ["feed", accounts.feed, false, true, false],
+    ["price", accounts.price, false, false, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface AddFeedAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  feed: PublicKey;
+  price: PublicKey;
+}
+
+export interface AddFeedArgs {
+  source: number;
+  pinned?: boolean;
+}
+
+/** AddFeed (0x1d): Bind the feed of price, from source, to the context's feed account as well, up to MAX_FEEDS feeds, each once. Match then needs a price account per feed, in the order they were bound, and prices off their median. */
+export function addFeedInstruction(programId: PublicKey, accounts: AddFeedAccounts, args: AddFeedArgs): TransactionInstruction {
+  const data = new Uint8Array(args.pinned === undefined ? 2 : 3);
+  data.set([0x1d]);
+  const dv = view(data);
+  dv.setUint8(1, args.source);
+  if (args.pinned !== undefined) dv.setUint8(2, args.pinned ? 1 : 0);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["feed", accounts.feed, false, true, false],
+    ["price", accounts.price, false, false, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetFeedDivergenceAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  feed: PublicKey;
+}
+
+export interface SetFeedDivergenceArgs {
+  maxDivergenceBps: number;
+  widen: boolean;
+}
+
+/** SetFeedDivergence (0x1e): How far apart (highest minus lowest, in bps of the median) the context's feeds may be before Match refuses the trade, or with widen adds the divergence to the spread. 0 turns the check off; at most 10000. */
+export function setFeedDivergenceInstruction(programId: PublicKey, accounts: SetFeedDivergenceAccounts, args: SetFeedDivergenceArgs): TransactionInstruction {
+  const data = new Uint8Array(4);
+  data.set([0x1e]);
+  const dv = view(data);
+  dv.setUint16(1, args.maxDivergenceBps, true);
+  dv.setUint8(3, args.widen ? 1 : 0);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["feed", accounts.feed, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetOracleDecimalsAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+}
+
+export interface SetOracleDecimalsArgs {
+  decimals: number;
+}
+
+/** SetOracleDecimals (0x1f): The decimals of the oracle price Match and the quotes are called with, ORACLE_DECIMALS_E6 through MAX_ORACLE_DECIMALS (0 also means e6); the program divides it down to e6, rounding down. Only before the first fill. */
+export function setOracleDecimalsInstruction(programId: PublicKey, accounts: SetOracleDecimalsAccounts, args: SetOracleDecimalsArgs): TransactionInstruction {
+  const data = new Uint8Array(2);
+  data.set([0x1f]);
+  const dv = view(data);
+  dv.setUint8(1, args.decimals);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetFeedSlotAgeAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  feed: PublicKey;
+}
+
+export interface SetFeedSlotAgeArgs {
+  maxAgeSlots: number;
+  widen: boolean;
+}
+
+/** SetFeedSlotAge (0x20): How many slots before the current one the context's feed prices may have been posted (a Pyth update's posted slot, a Switchboard result's or Chainlink round's slot) before Match refuses the trade, or with widen quotes it at max_spread_bps. 0 turns the check off. */
+export function setFeedSlotAgeInstruction(programId: PublicKey, accounts: SetFeedSlotAgeAccounts, args: SetFeedSlotAgeArgs): TransactionInstruction {
+  const data = new Uint8Array(4);
+  data.set([0x20]);
+  const dv = view(data);
+  dv.setUint16(1, args.maxAgeSlots, true);
+  dv.setUint8(3, args.widen ? 1 : 0);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["feed", accounts.feed, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetFeedFallbackAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  feed: PublicKey;
+}
+
+export interface SetFeedFallbackArgs {
+  fallback: boolean;
+  penaltiesBps: number[];
+}
+
+/** SetFeedFallback (0x21): With fallback, Match prices off the first of the context's feeds, in the order they were bound, whose price is fresh and positive, and adds that feed's penalty_bps (at most 10000 each) to the spread, instead of taking their median. A price account that isn't its feed's still fails the Match. */
+export function setFeedFallbackInstruction(programId: PublicKey, accounts: SetFeedFallbackAccounts, args: SetFeedFallbackArgs): TransactionInstruction {
+  const data = new Uint8Array(8);
+  data.set([0x21]);
+  const dv = view(data);
+  dv.setUint8(1, args.fallback ? 1 : 0);
+  args.penaltiesBps.forEach((item, i) => { dv.setUint16(2 + i * 2, item, true); });
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["feed", accounts.feed, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetFeeTiersAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  schedule: PublicKey;
+}
+
+export interface SetFeeTiersArgs {
+  count: number;
+  tiers: FeeTierArgs[];
+}
+
+/** SetFeeTiers (0x22): Replace the fee tiers of a schedule account created with SCHEDULE_FEES_LEN bytes with the first count of tiers, min_notional positive and rising, fee_bps at most 10000; count 0 clears them. Refused once the market has traded. */
+export function setFeeTiersInstruction(programId: PublicKey, accounts: SetFeeTiersAccounts, args: SetFeeTiersArgs): TransactionInstruction {
+  const data = new Uint8Array(82);
+  data.set([0x22]);
+  const dv = view(data);
+  dv.setUint8(1, args.count);
+  args.tiers.forEach((item, i) => { encodeFeeTierArgs(item, data, 2 + i * 20); });
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["schedule", accounts.schedule, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetRebateAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  schedule: PublicKey;
+}
+
+export interface SetRebateArgs {
+  rebateBps: number;
+}
+
+/** SetRebate (0x23): Set the rebate (at most 10000) a fill that shrinks |inventory| gets off its spread and fee, never past the oracle, on a schedule account created with SCHEDULE_REBATE_LEN bytes. Takes effect at once; 0 turns it off. */
+export function setRebateInstruction(programId: PublicKey, accounts: SetRebateAccounts, args: SetRebateArgs): TransactionInstruction {
+  const data = new Uint8Array(3);
+  data.set([0x23]);
+  const dv = view(data);
+  dv.setUint16(1, args.rebateBps, true);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["schedule", accounts.schedule, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface RecordWithdrawalAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  stats: PublicKey;
+}
+
+export interface RecordWithdrawalArgs {
+  amount: bigint;
+}
+
+/** RecordWithdrawal (0x24): Record that the LP withdrew amount of the fees the context accrued (cum_fees) in the fee ledger of the bound stats account, created with STATS_LEDGER_LEN bytes. The ledger never exceeds cum_fees. */
+export function recordWithdrawalInstruction(programId: PublicKey, accounts: RecordWithdrawalAccounts, args: RecordWithdrawalArgs): TransactionInstruction {
+  const data = new Uint8Array(17);
+  data.set([0x24]);
+  const dv = view(data);
+  writeU128(dv, 1, args.amount);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["stats", accounts.stats, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetFeeDiscountAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  schedule: PublicKey;
+}
+
+export interface SetFeeDiscountArgs {
+  feeDiscountBps: number;
+}
+
+/** SetFeeDiscount (0x25): Set the share of the fee (at most 10000) a quote waives at the full credibility discount, that share of it part way, on a schedule account created with SCHEDULE_REBATE_LEN bytes. The plain kind keeps its whole fee. Takes effect at once; 0 turns it off. */
+export function setFeeDiscountInstruction(programId: PublicKey, accounts: SetFeeDiscountAccounts, args: SetFeeDiscountArgs): TransactionInstruction {
+  const data = new Uint8Array(3);
+  data.set([0x25]);
+  const dv = view(data);
+  dv.setUint16(1, args.feeDiscountBps, true);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["schedule", accounts.schedule, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface InitTipJarAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  tipJar: PublicKey;
+}
+
+export interface InitTipJarArgs {
+  lamportsPerSlot: bigint;
+  maxTip: bigint;
+}
+
+/** InitTipJar (0x26): Bind a tip jar (TIP_JAR_LEN bytes, program-owned, rent-exempt; its lamports above that are the escrow) to the context, paying keepers lamports_per_slot per stale slot up to max_tip. */
+export function initTipJarInstruction(programId: PublicKey, accounts: InitTipJarAccounts, args: InitTipJarArgs): TransactionInstruction {
+  const data = new Uint8Array(17);
+  data.set([0x26]);
+  const dv = view(data);
+  dv.setBigUint64(1, args.lamportsPerSlot, true);
+  dv.setBigUint64(9, args.maxTip, true);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["tip_jar", accounts.tipJar, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetTipAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  tipJar: PublicKey;
+}
+
+export interface SetTipArgs {
+  lamportsPerSlot: bigint;
+  maxTip: bigint;
+}
+
+/** SetTip (0x27): Change a bound tip jar's tip. Takes effect at once; a zero max_tip stops the tips. */
+export function setTipInstruction(programId: PublicKey, accounts: SetTipAccounts, args: SetTipArgs): TransactionInstruction {
+  const data = new Uint8Array(17);
+  data.set([0x27]);
+  const dv = view(data);
+  dv.setBigUint64(1, args.lamportsPerSlot, true);
+  dv.setBigUint64(9, args.maxTip, true);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["tip_jar", accounts.tipJar, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface CloseTipJarAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  tipJar: PublicKey;
+  destination: PublicKey;
+}
+
+/** CloseTipJar (0x28): Zero a bound tip jar and sweep its lamports, escrow and rent, to destination. */
+export function closeTipJarInstruction(programId: PublicKey, accounts: CloseTipJarAccounts): TransactionInstruction {
+  const data = new Uint8Array(1);
+  data.set([0x28]);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["tip_jar", accounts.tipJar, false, true, false],
+    ["destination", accounts.destination, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface InitReferralAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  referral: PublicKey;
+}
+
+export interface InitReferralArgs {
+  shareBps: number;
+}
+
+/** InitReferral (0x29): Bind a referral account (REFERRAL_LEN bytes, program-owned, rent-exempt) to the context, crediting referrers named in Match data with share_bps of each fill's fee. */
+export function initReferralInstruction(programId: PublicKey, accounts: InitReferralAccounts, args: InitReferralArgs): TransactionInstruction {
+  const data = new Uint8Array(3);
+  data.set([0x29]);
+  const dv = view(data);
+  dv.setUint16(1, args.shareBps, true);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["referral", accounts.referral, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetReferralShareAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  referral: PublicKey;
+}
+
+export interface SetReferralShareArgs {
+  shareBps: number;
+}
+
+/** SetReferralShare (0x2a): Change a bound referral account's share_bps, for fills from then on. */
+export function setReferralShareInstruction(programId: PublicKey, accounts: SetReferralShareAccounts, args: SetReferralShareArgs): TransactionInstruction {
+  const data = new Uint8Array(3);
+  data.set([0x2a]);
+  const dv = view(data);
+  dv.setUint16(1, args.shareBps, true);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["referral", accounts.referral, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetMinNotionalAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  schedule: PublicKey;
+}
+
+export interface SetMinNotionalArgs {
+  minNotionalE6: bigint;
+}
+
+/** SetMinNotional (0x2b): Set the notional at the oracle below which a trade is refused as dust (BelowMinNotional, soft-rejected in soft-reject mode), on a schedule account created with SCHEDULE_MIN_NOTIONAL_LEN bytes. Takes effect at once; 0 turns it off. */
+export function setMinNotionalInstruction(programId: PublicKey, accounts: SetMinNotionalAccounts, args: SetMinNotionalArgs): TransactionInstruction {
+  const data = new Uint8Array(17);
+  data.set([0x2b]);
+  const dv = view(data);
+  writeU128(dv, 1, args.minNotionalE6);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["schedule", accounts.schedule, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetSkimAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  stats: PublicKey;
+}
+
+export interface SetSkimArgs {
+  skimBps: number;
+}
+
+/** SetSkim (0x2c): Set the share of each fill's spread (at most 10000), on its notional, that Match accrues for the insurance fund in the bound stats account, created with STATS_SKIM_LEN bytes. 0 stops accruing; what accrued stays claimable. */
+export function setSkimInstruction(programId: PublicKey, accounts: SetSkimAccounts, args: SetSkimArgs): TransactionInstruction {
+  const data = new Uint8Array(3);
+  data.set([0x2c]);
+  const dv = view(data);
+  dv.setUint16(1, args.skimBps, true);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["stats", accounts.stats, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface ClaimSkimAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  stats: PublicKey;
+  slab: PublicKey;
+  source: PublicKey;
+  vault: PublicKey;
+  tokenProgram: PublicKey;
+  percolator: PublicKey;
+}
+
+export interface ClaimSkimArgs {
+  amount: bigint;
+}
+
+/** ClaimSkim (0x2d): Pay amount of the skim accrued in the bound stats account into the insurance fund of the slab the context was bound to, through percolator's TopUpInsurance with the authority as the user: from its source token account into the slab's vault. Never more than accrued and not yet claimed. */
+export function claimSkimInstruction(programId: PublicKey, accounts: ClaimSkimAccounts, args: ClaimSkimArgs): TransactionInstruction {
+  const data = new Uint8Array(9);
+  data.set([0x2d]);
+  const dv = view(data);
+  dv.setBigUint64(1, args.amount, true);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["stats", accounts.stats, false, true, false],
+    ["slab", accounts.slab, false, true, false],
+    ["source", accounts.source, false, true, false],
+    ["vault", accounts.vault, false, true, false],
+    ["token_program", accounts.tokenProgram, false, false, false],
+    ["percolator", accounts.percolator, false, false, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface InitTraderAccounts {
+  trader: PublicKey;
+  ctx: PublicKey;
+  traderAccount: PublicKey;
+}
+
+/** InitTrader (0x2e): Bind a trader account (TRADER_LEN bytes, rent-exempt, owned by the program) to the context and the signing trader, for Match to record the trader's volume in and the schedule's loyalty tiers to discount the fee by. */
+export function initTraderInstruction(programId: PublicKey, accounts: InitTraderAccounts): TransactionInstruction {
+  const data = new Uint8Array(1);
+  data.set([0x2e]);
+  const keys = accountMetas([
+    ["trader", accounts.trader, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["trader_account", accounts.traderAccount, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetLoyaltyTiersAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  schedule: PublicKey;
+}
+
+export interface SetLoyaltyTiersArgs {
+  count: number;
+  tiers: LoyaltyTierArgs[];
+}
+
+/** SetLoyaltyTiers (0x2f): Replace the loyalty tiers of a schedule account created with SCHEDULE_LOYALTY_LEN bytes with the first count of tiers, min_volume positive and rising, discount_bps at most 10000; count 0 clears them. Takes effect at once. */
+export function setLoyaltyTiersInstruction(programId: PublicKey, accounts: SetLoyaltyTiersAccounts, args: SetLoyaltyTiersArgs): TransactionInstruction {
+  const data = new Uint8Array(82);
+  data.set([0x2f]);
+  const dv = view(data);
+  dv.setUint8(1, args.count);
+  args.tiers.forEach((item, i) => { encodeLoyaltyTierArgs(item, data, 2 + i * 20); });
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["schedule", accounts.schedule, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SweepProtocolFeeAccounts {
+  treasury: PublicKey;
+  ctx: PublicKey;
+  stats: PublicKey;
+}
+
+export interface SweepProtocolFeeArgs {
+  amount: bigint;
+}
+
+/** SweepProtocolFee (0x30): Record that the treasury took amount of the protocol fee accrued in a stats account of the context; the fees stay with percolator. Signed by the treasury InitStats named. Never more than accrued and not yet swept. */
+export function sweepProtocolFeeInstruction(programId: PublicKey, accounts: SweepProtocolFeeAccounts, args: SweepProtocolFeeArgs): TransactionInstruction {
+  const data = new Uint8Array(17);
+  data.set([0x30]);
+  const dv = view(data);
+  writeU128(dv, 1, args.amount);
+  const keys = accountMetas([
+    ["treasury", accounts.treasury, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["stats", accounts.stats, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetInventoryDecayAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  schedule: PublicKey;
+}
+
+export interface SetInventoryDecayArgs {
+  halflifeSlots: number;
+}
+
+/** SetInventoryDecay (0x31): Set the slots over which the inventory the imbalance and skew penalties see halves while no fill comes in, on a schedule account created with SCHEDULE_DECAY_LEN bytes; 0 turns it off. The book keeps the full inventory. v6 contexts only decay; before the first fill only. */
+export function setInventoryDecayInstruction(programId: PublicKey, accounts: SetInventoryDecayAccounts, args: SetInventoryDecayArgs): TransactionInstruction {
+  const data = new Uint8Array(5);
+  data.set([0x31]);
+  const dv = view(data);
+  dv.setUint32(1, args.halflifeSlots, true);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["schedule", accounts.schedule, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetInventoryBandAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  schedule: PublicKey;
+}
+
+export interface SetInventoryBandArgs {
+  softInventory: bigint;
+  bandKBps: number;
+}
+
+/** SetInventoryBand (0x32): Set the soft inventory limit past which a fill that takes |inventory| deeper pays up to band_k_bps more, in the square of how far toward max_inventory it gets, on a schedule account created with SCHEDULE_BAND_LEN bytes; a soft_inventory of 0 turns it off. max_inventory stays the hard limit and the soft one must sit below it; band_k_bps at most 10000. Before the first fill only. */
+export function setInventoryBandInstruction(programId: PublicKey, accounts: SetInventoryBandAccounts, args: SetInventoryBandArgs): TransactionInstruction {
+  const data = new Uint8Array(21);
+  data.set([0x32]);
+  const dv = view(data);
+  writeU128(dv, 1, args.softInventory);
+  dv.setUint32(17, args.bandKBps, true);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["schedule", accounts.schedule, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetInventoryCapsAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  schedule: PublicKey;
+}
+
+export interface SetInventoryCapsArgs {
+  maxLongInventory: bigint;
+  maxShortInventory: bigint;
+}
+
+/** SetInventoryCaps (0x33): Cap a long and a short book separately, on a schedule account created with SCHEDULE_CAPS_LEN bytes; a zero cap leaves that side at max_inventory (the market's, on a shared context). Auto reduce-only follows the cap of the side the book is on. Takes effect at once. */
+export function setInventoryCapsInstruction(programId: PublicKey, accounts: SetInventoryCapsAccounts, args: SetInventoryCapsArgs): TransactionInstruction {
+  const data = new Uint8Array(33);
+  data.set([0x33]);
+  const dv = view(data);
+  writeU128(dv, 1, args.maxLongInventory);
+  writeU128(dv, 17, args.maxShortInventory);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["schedule", accounts.schedule, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetReduceOnlyThresholdAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  schedule: PublicKey;
+}
+
+export interface SetReduceOnlyThresholdArgs {
+  reduceOnlyBps: number;
+}
+
+/** SetReduceOnlyThreshold (0x34): Set the share of the cap, in bps, past which the book goes reduce-only on its own, on a schedule account created with SCHEDULE_REDUCE_ONLY_LEN bytes; 0 restores AUTO_REDUCE_ONLY_BPS. Past it, trades that grow |inventory| get a zero fill with ReduceOnly as the reason, soft-reject mode or not. Takes effect at once. */
+export function setReduceOnlyThresholdInstruction(programId: PublicKey, accounts: SetReduceOnlyThresholdAccounts, args: SetReduceOnlyThresholdArgs): TransactionInstruction {
+  const data = new Uint8Array(3);
+  data.set([0x34]);
+  const dv = view(data);
+  dv.setUint16(1, args.reduceOnlyBps, true);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["schedule", accounts.schedule, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface TransferInventoryAccounts {
+  fromAuthority: PublicKey;
+  fromCtx: PublicKey;
+  toAuthority: PublicKey;
+  toCtx: PublicKey;
+}
+
+export interface TransferInventoryArgs {
+  amount: bigint;
+  fromMarket?: number;
+  toMarket?: number;
+}
+
+/** TransferInventory (0x35): Move amount of inventory from the source book to the destination's, without trading; a negative amount moves it the other way. The market indices pick the books on shared contexts. Both authorities sign, both books must quote the same slab (SlabMismatch), and neither may end up past its max_inventory (InventoryLimit). */
+export function transferInventoryInstruction(programId: PublicKey, accounts: TransferInventoryAccounts, args: TransferInventoryArgs): TransactionInstruction {
+  const data = new Uint8Array(args.toMarket === undefined ? 19 : 21);
+  data.set([0x35]);
+  const dv = view(data);
+  writeU128(dv, 1, args.amount);
+  if (args.fromMarket !== undefined) dv.setUint16(17, args.fromMarket, true);
+  if (args.toMarket !== undefined) dv.setUint16(19, args.toMarket, true);
+  const keys = accountMetas([
+    ["from_authority", accounts.fromAuthority, true, false, false],
+    ["from_ctx", accounts.fromCtx, false, true, false],
+    ["to_authority", accounts.toAuthority, true, false, false],
+    ["to_ctx", accounts.toCtx, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetInventoryDepthCapAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  schedule: PublicKey;
+}
+
+export interface SetInventoryDepthCapArgs {
+  maxInventoryBps: number;
+}
+
+/** SetInventoryDepthCap (0x36): Set max_inventory as max_inventory_bps of liquidity_notional_e6, converted to base units at each trade's oracle, on a schedule account created with SCHEDULE_DEPTH_CAP_LEN bytes; 0 goes back to max_inventory as set. A market's own max_inventory still wins. Takes effect at once. */
+export function setInventoryDepthCapInstruction(programId: PublicKey, accounts: SetInventoryDepthCapAccounts, args: SetInventoryDepthCapArgs): TransactionInstruction {
+  const data = new Uint8Array(5);
+  data.set([0x36]);
+  const dv = view(data);
+  dv.setUint32(1, args.maxInventoryBps, true);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["schedule", accounts.schedule, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SyncInventoryAccounts {
+  ctx: PublicKey;
+  slab: PublicKey;
+}
+
+export interface SyncInventoryArgs {
+  lpIdx: number;
+}
+
+/** SyncInventory (0x37): Set inventory_base to minus the position_size of the LP at lp_idx in the bound slab's account table (any slab PERCOLATOR_PROGRAM_ID owns on an unbound context), which must be matched through this program and context. A shared context syncs the slab's market entry. Permissionless; logs the drift. */
+export function syncInventoryInstruction(programId: PublicKey, accounts: SyncInventoryAccounts, args: SyncInventoryArgs): TransactionInstruction {
+  const data = new Uint8Array(3);
+  data.set([0x37]);
+  const dv = view(data);
+  dv.setUint16(1, args.lpIdx, true);
+  const keys = accountMetas([
+    ["ctx", accounts.ctx, false, true, false],
+    ["slab", accounts.slab, false, false, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface CreateContextAccounts {
+  lpPda: PublicKey;
+  ctx: PublicKey;
+  slab: PublicKey;
+  payer: PublicKey;
+}
+
+export interface CreateContextArgs {
+  len: number;
+}
+
+/** CreateContext (0x38): Create the context for lp_pda on slab at the program address derived from ["context", lp_pda, slab], with len bytes (at least CTX_EXT_LEN + CREATION_LEN) and rent from the payer, and mark it derived in its creation record. Init must then name the same pair, and every instruction checks a derived context is still at its address; a keypair context stays the legacy path at any size. */
+export function createContextInstruction(programId: PublicKey, accounts: CreateContextAccounts, args: CreateContextArgs): TransactionInstruction {
+  const data = new Uint8Array(5);
+  data.set([0x38]);
+  const dv = view(data);
+  dv.setUint32(1, args.len, true);
+  const keys = accountMetas([
+    ["lp_pda", accounts.lpPda, false, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+    ["slab", accounts.slab, false, false, false],
+    ["payer", accounts.payer, true, true, false],
+    ["system_program", new PublicKey("11111111111111111111111111111111"), false, false, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface BindSlabAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  slab: PublicKey;
+}
+
+/** BindSlab (0x39): Bind an unbound v6 context (Init gave it no slab, or it was migrated from v5) to the slab and pin the slab's owner as the percolator program. Refused on bound, shared and CreateContext contexts. */
+export function bindSlabInstruction(programId: PublicKey, accounts: BindSlabAccounts): TransactionInstruction {
+  const data = new Uint8Array(1);
+  data.set([0x39]);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, true, false],
+    ["slab", accounts.slab, false, false, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}
+
+export interface SetSignalsAccounts {
+  authority: PublicKey;
+  ctx: PublicKey;
+  schedule: PublicKey;
+}
+
+/** SetSignals (0x3a): Register the signal accounts after the schedule, up to MAX_SIGNAL_ACCOUNTS, and their owners, on a schedule account created with SCHEDULE_SIGNALS_LEN bytes. Every Match, quote and ladder must then pass exactly those, in order, after the stats account. None clears the list. */
+export function setSignalsInstruction(programId: PublicKey, accounts: SetSignalsAccounts): TransactionInstruction {
+  const data = new Uint8Array(1);
+  data.set([0x3a]);
+  const keys = accountMetas([
+    ["authority", accounts.authority, true, false, false],
+    ["ctx", accounts.ctx, false, false, false],
+    ["schedule", accounts.schedule, false, true, false],
+  ]);
+  return new TransactionInstruction({ programId, keys, data: Buffer.from(data) });
+}